@@ -0,0 +1,239 @@
+//! Generate the decode tables of the F1 games from their data files
+//!
+//! Rosters and calendars change with every game, and mapping their identifiers by hand means
+//! editing hundreds of match arms. Instead, the identifiers are listed in data files in the `spec`
+//! directory, with one `identifier,Variant` pair per line. For each file, this build script
+//! generates a function that decodes an identifier into its variant, and a test that checks every
+//! row of the file. Tables whose variants are looked up by name, like the drivers, also get a
+//! static list of their variants with their names.
+//!
+//! The data files are grouped by game, e.g. `spec/nineteen` for F1 2019, since the same identifier
+//! can refer to different drivers or teams in different games. The rosters of patches that update
+//! a game, like the F2 season of F1 2019, are added to the files of that game. The generated
+//! functions are written to a directory per game as well, from where the decoder of the game
+//! includes them.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Table that is generated from a data file
+struct Table {
+    /// Directory of the game in `spec`
+    game: &'static str,
+
+    /// Name of the data file in the directory of the game, without its extension
+    name: &'static str,
+
+    /// Name of the generated function
+    function: &'static str,
+
+    /// Enum that the identifiers are decoded into
+    enumeration: &'static str,
+
+    /// Type of the identifier in the packets
+    value: &'static str,
+
+    /// Name of the value in the error message
+    description: &'static str,
+
+    /// Name of the generated list of variants by their name, if the table has one
+    names: Option<&'static str>,
+}
+
+impl Table {
+    /// Returns the path of the data file, relative to the root of the crate.
+    fn file(&self) -> String {
+        format!("spec/{}/{}.csv", self.game, self.name)
+    }
+}
+
+const TABLES: [Table; 4] = [
+    Table {
+        game: "nineteen",
+        name: "drivers",
+        function: "decode_driver",
+        enumeration: "Driver",
+        value: "u8",
+        description: "driver",
+        names: Some("DRIVER_NAMES"),
+    },
+    Table {
+        game: "nineteen",
+        name: "nationalities",
+        function: "decode_nationality",
+        enumeration: "Nationality",
+        value: "u8",
+        description: "nationality",
+        names: None,
+    },
+    Table {
+        game: "nineteen",
+        name: "teams",
+        function: "decode_team",
+        enumeration: "Team",
+        value: "u8",
+        description: "team",
+        names: None,
+    },
+    Table {
+        game: "nineteen",
+        name: "tracks",
+        function: "decode_track",
+        enumeration: "Track",
+        value: "i8",
+        description: "track",
+        names: None,
+    },
+];
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+
+    for table in TABLES.iter() {
+        let file = table.file();
+        println!("cargo:rerun-if-changed={}", file);
+
+        let data = fs::read_to_string(&file)
+            .unwrap_or_else(|error| panic!("Failed to read {}: {}", file, error));
+        let rows = parse(table, &data);
+        let code = generate(table, &rows);
+
+        let directory = Path::new(&out_dir).join(table.game);
+        fs::create_dir_all(&directory)
+            .unwrap_or_else(|error| panic!("Failed to create {}: {}", directory.display(), error));
+
+        let path = directory.join(format!("{}.rs", table.function));
+        fs::write(&path, code)
+            .unwrap_or_else(|error| panic!("Failed to write {}: {}", path.display(), error));
+    }
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+/// Parse the rows of a data file, skipping empty lines and comments.
+fn parse(table: &Table, data: &str) -> Vec<(String, String)> {
+    let mut rows: Vec<(String, String)> = Vec::new();
+
+    for (index, line) in data.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (value, variant) = match line.split_once(',') {
+            Some((value, variant)) => (value.trim(), variant.trim()),
+            None => panic!(
+                "{}:{}: expected `identifier,Variant`",
+                table.file(),
+                index + 1
+            ),
+        };
+
+        if rows.iter().any(|(existing, _)| existing == value) {
+            panic!(
+                "{}:{}: duplicate identifier {}",
+                table.file(),
+                index + 1,
+                value
+            );
+        }
+
+        rows.push((value.to_string(), variant.to_string()));
+    }
+
+    rows
+}
+
+/// Generate the decode function, the list of names, and the test for a table.
+fn generate(table: &Table, rows: &[(String, String)]) -> String {
+    let mut code = String::new();
+
+    writeln!(code, "// Generated by build.rs from {}.", table.file()).unwrap();
+    writeln!(code).unwrap();
+    writeln!(
+        code,
+        "/// Decode the identifier of a {} into its variant",
+        table.description
+    )
+    .unwrap();
+    writeln!(
+        code,
+        "fn {}(value: {}) -> Result<{}, DecodeError> {{",
+        table.function, table.value, table.enumeration
+    )
+    .unwrap();
+    writeln!(code, "    match value {{").unwrap();
+
+    for (value, variant) in rows {
+        writeln!(
+            code,
+            "        {} => Ok({}::{}),",
+            value, table.enumeration, variant
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        code,
+        "        _ => Err(DecodeError::InvalidData(String::from(\"Failed to decode {}.\"))),",
+        table.description
+    )
+    .unwrap();
+    writeln!(code, "    }}").unwrap();
+    writeln!(code, "}}").unwrap();
+    writeln!(code).unwrap();
+
+    if let Some(names) = table.names {
+        writeln!(
+            code,
+            "/// Variants of the {} table with their names",
+            table.description
+        )
+        .unwrap();
+        writeln!(
+            code,
+            "static {}: [(&str, {}); {}] = [",
+            names,
+            table.enumeration,
+            rows.len()
+        )
+        .unwrap();
+
+        for (_, variant) in rows {
+            // Names are compared without case and punctuation, which only works for letters.
+            if !variant.chars().all(|letter| letter.is_ascii_alphanumeric()) {
+                panic!("{}: variant {} is not alphanumeric", table.file(), variant);
+            }
+
+            writeln!(
+                code,
+                "    (\"{}\", {}::{}),",
+                variant, table.enumeration, variant
+            )
+            .unwrap();
+        }
+
+        writeln!(code, "];").unwrap();
+        writeln!(code).unwrap();
+    }
+
+    writeln!(code, "#[cfg(test)]").unwrap();
+    writeln!(code, "#[test]").unwrap();
+    writeln!(code, "fn {}_table() {{", table.function).unwrap();
+
+    for (value, variant) in rows {
+        writeln!(
+            code,
+            "    assert_eq!(Ok({}::{}), {}({}));",
+            table.enumeration, variant, table.function, value
+        )
+        .unwrap();
+    }
+
+    writeln!(code, "}}").unwrap();
+
+    code
+}
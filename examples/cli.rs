@@ -1,52 +1,403 @@
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-use clap::{crate_version, App, Arg};
+use bytes::Bytes;
+use clap::{crate_version, App, AppSettings, Arg, ArgMatches, SubCommand};
+use tokio::net::UdpSocket;
+use tokio::time::interval;
 use tokio_stream::StreamExt;
 
+use f1_api::anonymize::{Anonymizer, Names};
+use f1_api::capture::{CaptureReader, CaptureWriter, Datagram};
+use f1_api::export::{FlatFileWriter, Format};
+use f1_api::packet::Packet;
 use f1_api::packet::Packet::{Event, Lap, Motion, Participants, Session, Setup, Status, Telemetry};
+use f1_api::tracker::SessionTracker;
 use f1_api::F1;
 
+const PACKET_TYPES: [&str; 8] = [
+    "event",
+    "lap",
+    "motion",
+    "participants",
+    "session",
+    "setup",
+    "status",
+    "telemetry",
+];
+
 #[tokio::main]
 async fn main() {
+    let address = Arg::with_name("address")
+        .short("a")
+        .long("address")
+        .value_name("IP ADDRESS")
+        .help("IP address to bind the local socket to")
+        .default_value("0.0.0.0")
+        .takes_value(true)
+        .global(true);
+
+    let port = Arg::with_name("port")
+        .short("p")
+        .long("port")
+        .value_name("PORT")
+        .help("Port to bind the local socket to")
+        .default_value("20777")
+        .takes_value(true)
+        .global(true);
+
     let matches = App::new("F1 API")
         .version(crate_version!())
+        .setting(AppSettings::ArgsNegateSubcommands)
+        .arg(address)
+        .arg(port)
         .arg(
-            Arg::with_name("address")
-                .short("a")
-                .long("address")
-                .value_name("IP ADDRESS")
-                .help("IP address to bind the local socket to")
-                .default_value("0.0.0.0")
+            Arg::with_name("only")
+                .long("only")
+                .value_name("PACKETS")
+                .help("Only print the given packet types")
+                .possible_values(&PACKET_TYPES)
+                .use_delimiter(true)
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("port")
-                .short("p")
-                .long("port")
-                .value_name("PORT")
-                .help("Port to bind the local socket to")
-                .default_value("20777")
+            Arg::with_name("car")
+                .long("car")
+                .value_name("INDEX")
+                .help("Only print the data of the car with the given index, implies --verbose")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .help("Print the full contents of each packet"),
+        )
+        .arg(
+            Arg::with_name("json-lines")
+                .long("json-lines")
+                .help("Print one JSON object per packet, requires the json feature"),
+        )
+        .subcommand(
+            SubCommand::with_name("record")
+                .about("Records the raw packets sent by the game to a capture")
+                .arg(
+                    Arg::with_name("capture")
+                        .value_name("CAPTURE")
+                        .help("File to write the capture to")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("timing")
+                .about("Prints a live classification of the current session"),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Decodes a capture and writes one flat file per packet type")
+                .arg(
+                    Arg::with_name("capture")
+                        .value_name("CAPTURE")
+                        .help("Capture to export")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Format of the exported files")
+                        .possible_values(&["csv", "json", "parquet"])
+                        .default_value("csv")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("DIRECTORY")
+                        .help("Directory to write the exported files to")
+                        .default_value("export")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("anonymize")
+                .about("Replaces the names of human players in a capture")
+                .arg(
+                    Arg::with_name("capture")
+                        .value_name("CAPTURE")
+                        .help("Capture to anonymize")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .value_name("OUTPUT")
+                        .help("File to write the anonymized capture to")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("strip")
+                        .long("strip")
+                        .help("Remove the names instead of replacing them with pseudonyms"),
+                )
+                .arg(
+                    Arg::with_name("session-uids")
+                        .long("session-uids")
+                        .help("Replace the unique ids of the sessions as well"),
+                ),
+        )
         .get_matches();
 
+    match matches.subcommand() {
+        ("record", Some(arguments)) => record(&matches, arguments).await,
+        ("timing", Some(_)) => timing(&matches).await,
+        ("export", Some(arguments)) => export(arguments),
+        ("anonymize", Some(arguments)) => anonymize(arguments),
+        _ if matches.is_present("json-lines") => json_lines(&matches).await,
+        _ => listen(&matches).await,
+    }
+}
+
+fn socket_address(matches: &ArgMatches) -> SocketAddr {
     let ip_address = matches.value_of("address").unwrap();
     let port: u16 = matches.value_of("port").unwrap().parse().unwrap();
 
-    let socket = SocketAddr::new(IpAddr::from_str(ip_address).unwrap(), port);
-    let mut stream = F1::stream(socket).unwrap();
+    SocketAddr::new(IpAddr::from_str(ip_address).unwrap(), port)
+}
+
+async fn listen(matches: &ArgMatches<'_>) {
+    let mut stream = F1::stream(socket_address(matches)).unwrap();
+
+    let only: Option<Vec<&str>> = matches.values_of("only").map(|values| values.collect());
+    let car: Option<usize> = matches
+        .value_of("car")
+        .map(|index| index.parse().expect("Car index must be a number"));
+    let verbose = matches.is_present("verbose") || car.is_some();
 
     while let Some(packet) = stream.next().await {
-        match packet {
-            Event(_) => println!("Received Event packet"),
-            Lap(_) => println!("Received Lap packet"),
-            Motion(_) => println!("Received Motion packet"),
-            Participants(_) => println!("Received Participants packet"),
-            Session(_) => println!("Received Session packet"),
-            Setup(_) => println!("Received Setup packet"),
-            Status(_) => println!("Received Status packet"),
-            Telemetry(_) => println!("Received Telemetry packet"),
+        let name = packet_name(&packet);
+
+        if only.as_ref().is_some_and(|only| !only.contains(&name)) {
+            continue;
+        }
+
+        if verbose {
+            print_packet(&packet, car);
+        } else {
+            println!("Received {} packet", name);
         }
     }
 }
+
+fn packet_name(packet: &Packet) -> &'static str {
+    match packet {
+        Event(_) => "event",
+        Lap(_) => "lap",
+        Motion(_) => "motion",
+        Participants(_) => "participants",
+        Session(_) => "session",
+        Setup(_) => "setup",
+        Status(_) => "status",
+        Telemetry(_) => "telemetry",
+    }
+}
+
+fn print_packet(packet: &Packet, car: Option<usize>) {
+    let car = match car {
+        Some(car) => car,
+        None => {
+            println!("{:#?}", packet);
+            return;
+        }
+    };
+
+    // Packets without per-car data are always printed in full.
+    let (header, data) = match packet {
+        Lap(packet) => (packet.header(), car_data(packet.laps(), car)),
+        Motion(packet) => (packet.header(), car_data(packet.cars(), car)),
+        Participants(packet) => (packet.header(), car_data(packet.participants(), car)),
+        Setup(packet) => (packet.header(), car_data(packet.setups(), car)),
+        Status(packet) => (packet.header(), car_data(packet.statuses(), car)),
+        Telemetry(packet) => (packet.header(), car_data(packet.telemetry(), car)),
+        Event(_) | Session(_) => {
+            println!("{:#?}", packet);
+            return;
+        }
+    };
+
+    println!("{} packet for car #{}", packet_name(packet), car);
+    println!("{:#?}", header);
+    println!("{}", data);
+}
+
+fn car_data<T: Debug>(data: &[T], car: usize) -> String {
+    match data.get(car) {
+        Some(data) => format!("{:#?}", data),
+        None => String::from("No data for this car"),
+    }
+}
+
+async fn record(matches: &ArgMatches<'_>, arguments: &ArgMatches<'_>) {
+    let socket = UdpSocket::bind(socket_address(matches)).await.unwrap();
+    let file = File::create(arguments.value_of("capture").unwrap()).unwrap();
+    let mut writer = CaptureWriter::new(BufWriter::new(file)).unwrap();
+
+    let start = Instant::now();
+    let mut buffer = vec![0u8; 2048];
+
+    loop {
+        let (size, _) = socket.recv_from(&mut buffer).await.unwrap();
+        let payload = Bytes::copy_from_slice(&buffer[..size]);
+
+        writer
+            .write(&Datagram::new(start.elapsed(), payload))
+            .unwrap();
+        writer.flush().unwrap();
+    }
+}
+
+#[cfg(feature = "json")]
+async fn json_lines(matches: &ArgMatches<'_>) {
+    let socket = UdpSocket::bind(socket_address(matches)).await.unwrap();
+    let mut writer = f1_api::jsonl::JsonLinesWriter::new(std::io::stdout());
+    let mut buffer = vec![0u8; 2048];
+
+    loop {
+        let (size, _) = socket.recv_from(&mut buffer).await.unwrap();
+
+        if let Err(error) = writer.write_datagram(&buffer[..size]) {
+            eprintln!("Failed to decode packet: {}", error);
+        }
+    }
+}
+
+#[cfg(not(feature = "json"))]
+async fn json_lines(_matches: &ArgMatches<'_>) {
+    eprintln!("Printing JSON lines requires the json feature");
+}
+
+async fn timing(matches: &ArgMatches<'_>) {
+    let mut stream = F1::stream(socket_address(matches)).unwrap();
+    let mut tracker = SessionTracker::default();
+    let mut redraw = interval(Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            packet = stream.next() => match packet {
+                Some(packet) => tracker.update(&packet),
+                None => break,
+            },
+            _ = redraw.tick() => print_classification(&tracker),
+        }
+    }
+}
+
+fn print_classification(tracker: &SessionTracker) {
+    // Clear the terminal and move the cursor to the top left corner.
+    print!("\x1B[2J\x1B[H");
+
+    if let Some(session) = tracker.session() {
+        println!(
+            "{:?} at {:?}, {}s left",
+            session.session_type(),
+            session.track(),
+            session.time_left().as_secs()
+        );
+    }
+
+    println!(
+        "{:>3}  {:<20} {:>10} {:>10} {:>9} {:<14} {:>4}",
+        "Pos", "Driver", "Last", "Best", "Gap", "Tyre", "Pits"
+    );
+
+    for entry in tracker.classification() {
+        let name = entry
+            .name()
+            .clone()
+            .unwrap_or_else(|| format!("Car #{}", entry.vehicle_index()));
+        let gap = match entry.gap() {
+            Some(gap) => format!("+{:.3}", gap.as_secs_f32()),
+            None => String::new(),
+        };
+        let tyre = match entry.tyre_compound() {
+            Some(compound) => format!("{:?}", compound),
+            None => String::new(),
+        };
+
+        println!(
+            "{:>3}  {:<20} {:>10} {:>10} {:>9} {:<14} {:>4}",
+            entry.position(),
+            name,
+            format_lap_time(*entry.last_lap_time()),
+            format_lap_time(*entry.best_lap_time()),
+            gap,
+            tyre,
+            entry.pit_stops()
+        );
+    }
+}
+
+fn format_lap_time(time: Duration) -> String {
+    if time.is_zero() {
+        return String::from("-");
+    }
+
+    format!(
+        "{}:{:02}.{:03}",
+        time.as_secs() / 60,
+        time.as_secs() % 60,
+        time.subsec_millis()
+    )
+}
+
+fn export(arguments: &ArgMatches) {
+    let file = File::open(arguments.value_of("capture").unwrap()).unwrap();
+    let reader = CaptureReader::new(BufReader::new(file)).unwrap();
+
+    let format = Format::from_str(arguments.value_of("format").unwrap()).unwrap();
+    let mut writer = FlatFileWriter::new(arguments.value_of("output").unwrap(), format)
+        .unwrap_or_else(|error| panic!("{}", error));
+
+    let mut packets = 0;
+
+    for datagram in reader {
+        match datagram.unwrap().decode() {
+            Ok(Some(packet)) => {
+                writer.write(&packet).unwrap();
+                packets += 1;
+            }
+            Ok(None) => {}
+            Err(error) => eprintln!("Failed to decode packet: {}", error),
+        }
+    }
+
+    writer.finish().unwrap();
+    println!("Exported {} packets", packets);
+}
+
+fn anonymize(arguments: &ArgMatches) {
+    let input = File::open(arguments.value_of("capture").unwrap()).unwrap();
+    let reader = CaptureReader::new(BufReader::new(input)).unwrap();
+
+    let output = File::create(arguments.value_of("output").unwrap()).unwrap();
+    let writer = CaptureWriter::new(BufWriter::new(output)).unwrap();
+
+    let names = match arguments.is_present("strip") {
+        true => Names::Strip,
+        false => Names::Pseudonymize,
+    };
+
+    let mut anonymizer = Anonymizer::new(names);
+
+    if arguments.is_present("session-uids") {
+        anonymizer = anonymizer.replace_session_uids();
+    }
+
+    let datagrams = anonymizer.anonymize_capture(reader, writer).unwrap();
+    println!("Anonymized {} datagrams", datagrams);
+}
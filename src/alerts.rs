@@ -0,0 +1,400 @@
+//! Alerts when the values of a car cross a threshold
+//!
+//! Pit walls watch a small number of values, like the temperature of the brakes or the wear of the
+//! tyres, and need to know when one of them becomes critical. The `AlertMonitor` checks the values
+//! of every car against thresholds that are registered by the application. It reports an `Onset`
+//! when a value crosses its threshold, and a `Clear` when it returns back, so that applications can
+//! show and hide warnings without comparing the values themselves.
+//!
+//! With the `net` feature, `PacketStreamExt::alerts` turns a stream of packets into a stream of
+//! alerts.
+
+use std::collections::HashSet;
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::status::CarStatus;
+use crate::packet::telemetry::Telemetry;
+use crate::packet::Packet;
+use crate::types::{CornerProperty, VehicleIndex};
+
+/// Value of a car that can be monitored
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum Metric {
+    /// Hottest brake of the car in degrees celsius
+    BrakeTemperature,
+
+    /// Hottest tyre surface of the car in degrees celsius
+    TyreSurfaceTemperature,
+
+    /// Hottest tyre carcass of the car in degrees celsius
+    TyreInnerTemperature,
+
+    /// Temperature of the engine in degrees celsius
+    EngineTemperature,
+
+    /// Most worn tyre of the car in percent
+    TyreWear,
+
+    /// Most damaged tyre of the car in percent
+    TyreDamage,
+
+    /// Most damaged wing of the car in percent
+    WingDamage,
+
+    /// Damage of the engine in percent
+    EngineDamage,
+
+    /// Damage of the gear box in percent
+    GearBoxDamage,
+
+    /// Fuel in the tank in laps, relative to the end of the race
+    ///
+    /// A negative value means that the car does not have enough fuel to finish the race.
+    FuelDelta,
+}
+
+impl Metric {
+    fn read_telemetry(self, telemetry: &Telemetry) -> Option<f32> {
+        match self {
+            Metric::BrakeTemperature => Some(highest(telemetry.brake_temperature())),
+            Metric::TyreSurfaceTemperature => Some(highest(telemetry.tyre_surface_temperature())),
+            Metric::TyreInnerTemperature => Some(highest(telemetry.tyre_inner_temperature())),
+            Metric::EngineTemperature => Some(telemetry.engine_temperature() as f32),
+            _ => None,
+        }
+    }
+
+    fn read_status(self, status: &CarStatus) -> Option<f32> {
+        match self {
+            Metric::TyreWear => Some(highest(status.tyre_wear())),
+            Metric::TyreDamage => Some(highest(status.tyre_damage())),
+            Metric::WingDamage => Some(
+                status
+                    .front_left_wing_damage()
+                    .max(status.front_right_wing_damage())
+                    .max(status.rear_wing_damage()) as f32,
+            ),
+            Metric::EngineDamage => Some(status.engine_damage() as f32),
+            Metric::GearBoxDamage => Some(status.gear_box_damage() as f32),
+            Metric::FuelDelta => Some(status.fuel_remaining_laps()),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the highest value of the four corners of a car.
+fn highest<T: Copy + Into<f32>>(corners: &CornerProperty<T>) -> f32 {
+    [
+        corners.front_left().into(),
+        corners.front_right().into(),
+        corners.rear_left().into(),
+        corners.rear_right().into(),
+    ]
+    .iter()
+    .fold(f32::MIN, |max, value| max.max(*value))
+}
+
+/// Direction in which a value crosses a threshold
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum Comparison {
+    /// The alert is raised when the value rises above the threshold.
+    Above,
+
+    /// The alert is raised when the value falls below the threshold.
+    Below,
+}
+
+/// Threshold for a metric
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd)]
+pub struct Threshold {
+    /// Returns the metric that is monitored.
+    #[getset(get_copy = "pub")]
+    metric: Metric,
+
+    /// Returns the direction in which the value must cross the threshold.
+    #[getset(get_copy = "pub")]
+    comparison: Comparison,
+
+    /// Returns the value of the threshold.
+    #[getset(get_copy = "pub")]
+    value: f32,
+}
+
+impl Threshold {
+    /// Create a threshold that is crossed when the metric rises above the value.
+    pub fn above(metric: Metric, value: f32) -> Self {
+        Threshold::new(metric, Comparison::Above, value)
+    }
+
+    /// Create a threshold that is crossed when the metric falls below the value.
+    pub fn below(metric: Metric, value: f32) -> Self {
+        Threshold::new(metric, Comparison::Below, value)
+    }
+
+    fn is_crossed(&self, value: f32) -> bool {
+        match self.comparison {
+            Comparison::Above => value > self.value,
+            Comparison::Below => value < self.value,
+        }
+    }
+}
+
+/// Whether an alert starts or ends
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum AlertState {
+    /// The value has crossed the threshold.
+    Onset,
+
+    /// The value has returned from the threshold.
+    Clear,
+}
+
+/// Alert for a car whose value crossed a threshold
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, PartialOrd)]
+pub struct Alert {
+    /// Returns the threshold that was crossed.
+    #[getset(get_copy = "pub")]
+    threshold: Threshold,
+
+    /// Returns whether the alert starts or ends.
+    #[getset(get_copy = "pub")]
+    state: AlertState,
+
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the value of the metric.
+    #[getset(get_copy = "pub")]
+    value: f32,
+
+    /// Returns the session time at which the threshold was crossed.
+    #[getset(get = "pub")]
+    session_time: Duration,
+}
+
+/// Monitor that checks the values of all cars against thresholds
+///
+/// Alerts that are active when a new session starts are dropped without a `Clear`.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::alerts::{AlertMonitor, AlertState, Metric, Threshold};
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut monitor = AlertMonitor::default()
+///     .threshold(Threshold::above(Metric::BrakeTemperature, 1000.0))
+///     .threshold(Threshold::above(Metric::TyreWear, 60.0))
+///     .threshold(Threshold::below(Metric::FuelDelta, 0.0));
+///
+/// for packet in packets() {
+///     for alert in monitor.update(&packet) {
+///         if alert.state() == AlertState::Onset {
+///             println!("Car #{}: {:?}", alert.vehicle_index(), alert.threshold().metric());
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AlertMonitor {
+    thresholds: Vec<Threshold>,
+    session_uid: Option<u64>,
+    active: HashSet<(usize, VehicleIndex)>,
+}
+
+impl AlertMonitor {
+    /// Add a threshold to the monitor.
+    pub fn threshold(mut self, threshold: Threshold) -> Self {
+        self.thresholds.push(threshold);
+        self
+    }
+
+    /// Returns the thresholds of the monitor.
+    pub fn thresholds(&self) -> &[Threshold] {
+        &self.thresholds
+    }
+
+    /// Returns whether an alert for the given threshold and car is active.
+    pub fn is_active(&self, threshold: usize, vehicle_index: VehicleIndex) -> bool {
+        self.active.contains(&(threshold, vehicle_index))
+    }
+
+    /// Update the monitor with a new packet, and return the alerts that started or ended.
+    pub fn update(&mut self, packet: &Packet) -> Vec<Alert> {
+        let header = packet.header();
+
+        if self.session_uid != Some(header.session_uid()) {
+            self.session_uid = Some(header.session_uid());
+            self.active.clear();
+        }
+
+        let values: Vec<Vec<Option<f32>>> = match packet {
+            Packet::Telemetry(packet) => self
+                .thresholds
+                .iter()
+                .map(|threshold| {
+                    packet
+                        .telemetry()
+                        .iter()
+                        .map(|telemetry| threshold.metric.read_telemetry(telemetry))
+                        .collect()
+                })
+                .collect(),
+            Packet::Status(packet) => self
+                .thresholds
+                .iter()
+                .map(|threshold| {
+                    packet
+                        .statuses()
+                        .iter()
+                        .map(|status| threshold.metric.read_status(status))
+                        .collect()
+                })
+                .collect(),
+            _ => return Vec::new(),
+        };
+
+        let mut alerts = Vec::new();
+
+        for (index, (threshold, values)) in self.thresholds.iter().zip(values).enumerate() {
+            for (vehicle_index, value) in values.into_iter().enumerate() {
+                let value = match value {
+                    Some(value) => value,
+                    None => continue,
+                };
+
+                let key = (index, vehicle_index as VehicleIndex);
+                let state = match (threshold.is_crossed(value), self.active.contains(&key)) {
+                    (true, false) => AlertState::Onset,
+                    (false, true) => AlertState::Clear,
+                    _ => continue,
+                };
+
+                match state {
+                    AlertState::Onset => self.active.insert(key),
+                    AlertState::Clear => self.active.remove(&key),
+                };
+
+                alerts.push(Alert::new(
+                    *threshold,
+                    state,
+                    vehicle_index as VehicleIndex,
+                    value,
+                    *header.session_time(),
+                ));
+            }
+        }
+
+        alerts
+    }
+}
+
+/// Stream adapter that yields the alerts of a monitor
+///
+/// The adapter is created by `PacketStreamExt::alerts`.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct Alerts<S> {
+    stream: S,
+    monitor: AlertMonitor,
+    pending: std::vec::IntoIter<Alert>,
+}
+
+#[cfg(feature = "net")]
+impl<S> Alerts<S> {
+    pub(crate) fn new(stream: S, monitor: AlertMonitor) -> Self {
+        Alerts {
+            stream,
+            monitor,
+            pending: Vec::new().into_iter(),
+        }
+    }
+
+    /// Returns the monitor of the adapter.
+    pub fn monitor(&self) -> &AlertMonitor {
+        &self.monitor
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for Alerts<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = Alert;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(alert) = this.pending.next() {
+                return Poll::Ready(Some(alert));
+            }
+
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    this.pending = this.monitor.update(&packet).into_iter();
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::alerts::{AlertMonitor, AlertState, Metric, Threshold};
+    use crate::fixtures::{header, telemetry, telemetry_packet, with_corners};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+    use crate::types::CornerProperty;
+
+    fn brake_temperature(brake_temperature: u16) -> Packet {
+        let telemetry = with_corners(
+            telemetry(200, 1.0, 0.0, 0.0, Gear::Seventh, 11000),
+            CornerProperty::new(brake_temperature, 400, 400, 400),
+            CornerProperty::default(),
+            CornerProperty::default(),
+        );
+
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::default(), 0),
+            vec![telemetry],
+        )
+    }
+
+    #[test]
+    fn update_reports_onset_and_clear() {
+        let mut monitor =
+            AlertMonitor::default().threshold(Threshold::above(Metric::BrakeTemperature, 1000.0));
+
+        assert!(monitor.update(&brake_temperature(900)).is_empty());
+
+        let alerts = monitor.update(&brake_temperature(1050));
+        assert_eq!(1, alerts.len());
+        assert_eq!(AlertState::Onset, alerts[0].state());
+        assert_eq!(1050.0, alerts[0].value());
+        assert!(monitor.is_active(0, 0));
+
+        assert!(monitor.update(&brake_temperature(1100)).is_empty());
+
+        let alerts = monitor.update(&brake_temperature(950));
+        assert_eq!(AlertState::Clear, alerts[0].state());
+        assert!(!monitor.is_active(0, 0));
+    }
+}
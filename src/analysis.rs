@@ -0,0 +1,30 @@
+//! Analysis of the driving of cars in a session
+//!
+//! The packets sent by the F1 games describe the state of the cars at a point in time. The modules
+//! in this namespace combine successive packets to derive information that is not published by the
+//! games directly, and that is commonly used by tools that coach drivers or analyse their laps.
+
+pub mod braking;
+pub mod comparison;
+pub mod corners;
+pub mod damage;
+pub mod delta;
+pub mod dirty_air;
+pub mod ghost;
+pub mod incidents;
+pub mod inputs;
+pub mod kinematics;
+pub mod off_track;
+pub mod penalties;
+pub mod pit_window;
+pub mod positions;
+pub mod qualifying;
+pub mod safety_car;
+pub mod speed_trap;
+pub mod spotter;
+pub mod stints;
+pub mod trace;
+pub mod track_evolution;
+pub mod track_map;
+pub mod undercut;
+pub mod weather;
@@ -0,0 +1,407 @@
+//! Braking points and braking performance
+//!
+//! How late and how hard a driver brakes for a corner is one of the biggest differences between a
+//! fast and a slow lap. The `BrakingAnalyzer` detects the braking zones of each car, and attributes
+//! them to the corner that the car is approaching. For each zone, it records where the car started
+//! braking, its peak deceleration, and a trace of the brake input over the lap distance.
+//!
+//! For the player's car, the game also publishes the slip of each wheel, which is used to detect
+//! lockups. A wheel that locks up under braking has a slip ratio close to -1.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::analysis::corners::Corner;
+use crate::packet::lap::LapPacket;
+use crate::packet::motion::MotionPacket;
+use crate::packet::telemetry::TelemetryPacket;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Brake input above which a car is considered to be braking
+const BRAKE_THRESHOLD: f32 = 0.05;
+
+/// Wheel slip below which a wheel is considered to be locked
+const LOCKUP_THRESHOLD: f32 = -0.2;
+
+/// Speed in kilometers per hour below which lockups are ignored
+const LOCKUP_MINIMUM_SPEED: u16 = 30;
+
+/// Sample of the brake input in a braking zone
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct BrakeSample {
+    /// Returns the lap distance of the sample in meters.
+    #[getset(get_copy = "pub")]
+    distance: f32,
+
+    /// Returns the brake input.
+    #[getset(get_copy = "pub")]
+    brake: f32,
+
+    /// Returns the speed of the car in kilometers per hour.
+    #[getset(get_copy = "pub")]
+    speed: u16,
+}
+
+/// Braking zone of a car on a lap
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Clone, PartialOrd, Default)]
+#[allow(clippy::too_many_arguments)]
+pub struct BrakingZone {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the lap on which the car braked.
+    #[getset(get_copy = "pub")]
+    lap_number: u8,
+
+    /// Returns the number of the corner the car braked for, if it is known.
+    #[getset(get_copy = "pub")]
+    corner: Option<u8>,
+
+    /// Returns the lap distance at which the car started braking in meters.
+    #[getset(get_copy = "pub")]
+    start_distance: f32,
+
+    /// Returns the speed at which the car started braking in kilometers per hour.
+    #[getset(get_copy = "pub")]
+    start_speed: u16,
+
+    /// Returns the peak deceleration in meters per second squared.
+    #[getset(get_copy = "pub")]
+    peak_deceleration: f32,
+
+    /// Returns the brake input over the lap distance.
+    #[getset(get = "pub")]
+    trace: Vec<BrakeSample>,
+
+    /// Returns the lap distances at which a wheel locked up.
+    ///
+    /// Lockups can only be detected for the player's car.
+    #[getset(get = "pub")]
+    lockups: Vec<f32>,
+}
+
+impl BrakingZone {
+    /// Compare the braking zone to a reference, e.g. the same corner on the best lap.
+    pub fn compare(&self, reference: &BrakingZone) -> BrakingComparison {
+        BrakingComparison::new(
+            self.corner,
+            self.start_distance - reference.start_distance,
+            self.start_speed as i32 - reference.start_speed as i32,
+            self.peak_deceleration - reference.peak_deceleration,
+            self.lockups.len() as i32 - reference.lockups.len() as i32,
+        )
+    }
+}
+
+/// Comparison of a braking zone with a reference
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct BrakingComparison {
+    /// Returns the number of the corner, if it is known.
+    #[getset(get_copy = "pub")]
+    corner: Option<u8>,
+
+    /// Returns how many meters later the car started braking than the reference.
+    #[getset(get_copy = "pub")]
+    start_distance: f32,
+
+    /// Returns how much faster the car was when it started braking than the reference.
+    #[getset(get_copy = "pub")]
+    start_speed: i32,
+
+    /// Returns how much harder the car decelerated than the reference.
+    #[getset(get_copy = "pub")]
+    peak_deceleration: f32,
+
+    /// Returns how many more lockups the car had than the reference.
+    #[getset(get_copy = "pub")]
+    lockups: i32,
+}
+
+/// Compare the braking zones of a lap with those of a reference lap.
+///
+/// The braking zones are matched by their corner. Zones that cannot be attributed to a corner, or
+/// for which the reference lap has no zone, are skipped.
+pub fn compare_laps(lap: &[BrakingZone], reference: &[BrakingZone]) -> Vec<BrakingComparison> {
+    lap.iter()
+        .filter(|zone| zone.corner().is_some())
+        .filter_map(|zone| {
+            reference
+                .iter()
+                .find(|other| other.corner() == zone.corner())
+                .map(|other| zone.compare(other))
+        })
+        .collect()
+}
+
+/// Braking state of a single car
+#[derive(Debug, PartialEq, Clone, Default)]
+struct CarBraking {
+    lap: Option<(u8, f32)>,
+    last: Option<(Duration, f32)>,
+    zone: Option<BrakingZone>,
+    locked: bool,
+}
+
+/// Analyzer that detects the braking zones of all cars
+///
+/// The analyzer needs to know the corners of the track to attribute braking zones to corners. They
+/// can be detected with the `CornerAnalyzer`, and updated with `BrakingAnalyzer::set_corners`.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::braking::BrakingAnalyzer;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut analyzer = BrakingAnalyzer::default();
+///
+/// for packet in packets() {
+///     for zone in analyzer.update(&packet) {
+///         println!("Braked at {}m", zone.start_distance());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BrakingAnalyzer {
+    corners: Vec<Corner>,
+    session_uid: Option<u64>,
+    wheel_slip: Option<f32>,
+    cars: Vec<CarBraking>,
+}
+
+impl BrakingAnalyzer {
+    /// Create an analyzer for a track with the given corners.
+    pub fn new(corners: Vec<Corner>) -> Self {
+        BrakingAnalyzer {
+            corners,
+            ..Default::default()
+        }
+    }
+
+    /// Replace the corners of the track.
+    pub fn set_corners(&mut self, corners: Vec<Corner>) {
+        self.corners = corners;
+    }
+
+    /// Update the analyzer with a new packet, and return the braking zones that have ended.
+    pub fn update(&mut self, packet: &Packet) -> Vec<BrakingZone> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.wheel_slip = None;
+            self.cars.clear();
+        }
+
+        match packet {
+            Packet::Lap(packet) => {
+                self.update_laps(packet);
+                Vec::new()
+            }
+            Packet::Motion(packet) => {
+                self.update_motion(packet);
+                Vec::new()
+            }
+            Packet::Telemetry(packet) => self.update_telemetry(packet),
+            _ => Vec::new(),
+        }
+    }
+
+    fn update_laps(&mut self, packet: &LapPacket) {
+        self.cars.resize(packet.laps().len(), CarBraking::default());
+
+        for (car, lap) in self.cars.iter_mut().zip(packet.laps()) {
+            car.lap = Some((lap.current_lap_number(), lap.lap_distance()));
+        }
+    }
+
+    fn update_motion(&mut self, packet: &MotionPacket) {
+        let slip = packet.wheel_slip();
+
+        self.wheel_slip = Some(
+            slip.front_left()
+                .min(slip.front_right())
+                .min(slip.rear_left())
+                .min(slip.rear_right()),
+        );
+    }
+
+    fn update_telemetry(&mut self, packet: &TelemetryPacket) -> Vec<BrakingZone> {
+        let header = packet.header();
+        let time = *header.session_time();
+        let player = header.player_car_index() as usize;
+
+        let mut zones = Vec::new();
+
+        for (index, (car, telemetry)) in self.cars.iter_mut().zip(packet.telemetry()).enumerate() {
+            let (lap_number, distance) = match car.lap {
+                Some(lap) => lap,
+                None => continue,
+            };
+
+            let speed = telemetry.speed() as f32 / 3.6;
+            let deceleration = match car.last {
+                Some((last_time, last_speed)) if time > last_time => {
+                    (last_speed - speed) / (time - last_time).as_secs_f32()
+                }
+                _ => 0.0,
+            };
+            car.last = Some((time, speed));
+
+            if telemetry.brake() > BRAKE_THRESHOLD {
+                let zone = car.zone.get_or_insert_with(|| {
+                    BrakingZone::new(
+                        index as VehicleIndex,
+                        lap_number,
+                        None,
+                        distance,
+                        telemetry.speed(),
+                        0.0,
+                        Vec::new(),
+                        Vec::new(),
+                    )
+                });
+
+                zone.peak_deceleration = zone.peak_deceleration.max(deceleration);
+                zone.trace.push(BrakeSample::new(
+                    distance,
+                    telemetry.brake(),
+                    telemetry.speed(),
+                ));
+
+                let locked = index == player
+                    && telemetry.speed() > LOCKUP_MINIMUM_SPEED
+                    && self.wheel_slip.is_some_and(|slip| slip < LOCKUP_THRESHOLD);
+
+                if locked && !car.locked {
+                    zone.lockups.push(distance);
+                }
+
+                car.locked = locked;
+            } else if let Some(mut zone) = car.zone.take() {
+                zone.corner = self
+                    .corners
+                    .iter()
+                    .find(|corner| corner.exit() >= zone.start_distance)
+                    .map(|corner| corner.number());
+
+                car.locked = false;
+                zones.push(zone);
+            }
+        }
+
+        zones
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::analysis::braking::{compare_laps, BrakingAnalyzer, BrakingZone};
+    use crate::analysis::corners::Corner;
+    use crate::fixtures::{header, lap, lap_packet, motion_packet, telemetry, telemetry_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+    use crate::types::CornerProperty;
+
+    fn wheel_slip(slip: f32) -> Packet {
+        motion_packet(
+            header(PacketType::Motion, 1, Duration::default(), 0),
+            Vec::new(),
+            CornerProperty::new(0.0, slip, 0.0, 0.0),
+        )
+    }
+
+    fn braking(millis: u64, speed: u16, brake: f32) -> Packet {
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+            vec![telemetry(speed, 0.0, 0.0, brake, Gear::Fourth, 10000)],
+        )
+    }
+
+    fn drive(
+        analyzer: &mut BrakingAnalyzer,
+        samples: &[(f32, u64, u16, f32, f32)],
+    ) -> Vec<BrakingZone> {
+        let mut zones = Vec::new();
+
+        for (distance, millis, speed, brake, slip) in samples {
+            analyzer.update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                vec![lap(3, *distance)],
+            ));
+            analyzer.update(&wheel_slip(*slip));
+            zones.append(&mut analyzer.update(&braking(*millis, *speed, *brake)));
+        }
+
+        zones
+    }
+
+    #[test]
+    fn update_detects_braking_zone() {
+        let mut analyzer = BrakingAnalyzer::new(vec![Corner::new(1, 300.0, 320.0, 350.0)]);
+
+        let zones = drive(
+            &mut analyzer,
+            &[
+                (100.0, 0, 300, 0.0, 0.0),
+                (180.0, 1000, 288, 1.0, 0.0),
+                (250.0, 2000, 180, 1.0, -0.5),
+                (280.0, 3000, 120, 0.5, 0.0),
+                (300.0, 4000, 110, 0.0, 0.0),
+            ],
+        );
+
+        assert_eq!(1, zones.len());
+
+        let zone = &zones[0];
+        assert_eq!(Some(1), zone.corner());
+        assert_eq!(3, zone.lap_number());
+        assert_eq!(180.0, zone.start_distance());
+        assert_eq!(288, zone.start_speed());
+        assert_approx_eq!(30.0, zone.peak_deceleration(), 0.01);
+        assert_eq!(3, zone.trace().len());
+        assert_eq!(vec![250.0], *zone.lockups());
+    }
+
+    #[test]
+    fn compare_laps_by_corner() {
+        let lap = vec![BrakingZone::new(
+            0,
+            2,
+            Some(1),
+            190.0,
+            290,
+            40.0,
+            Vec::new(),
+            Vec::new(),
+        )];
+        let reference = vec![BrakingZone::new(
+            0,
+            1,
+            Some(1),
+            180.0,
+            288,
+            38.0,
+            Vec::new(),
+            vec![185.0],
+        )];
+
+        let comparison = compare_laps(&lap, &reference);
+
+        assert_eq!(1, comparison.len());
+        assert_eq!(10.0, comparison[0].start_distance());
+        assert_eq!(2, comparison[0].start_speed());
+        assert_approx_eq!(2.0, comparison[0].peak_deceleration());
+        assert_eq!(-1, comparison[0].lockups());
+    }
+}
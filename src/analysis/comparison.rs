@@ -0,0 +1,255 @@
+//! Comparison of two laps
+//!
+//! Comparing a lap with a reference, for example the driver's best lap or the lap of a faster
+//! driver, shows where on the track time is gained or lost, and which inputs make the difference.
+//! Both laps are compared at the same lap distances, which requires them to be recorded as
+//! distance-indexed `LapTrace`s.
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::analysis::trace::LapTrace;
+use crate::types::VehicleIndex;
+
+/// Difference between two laps at a lap distance
+///
+/// All differences are calculated as the value of the lap minus the value of the reference.
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct ComparisonPoint {
+    /// Returns the lap distance in meters.
+    #[getset(get_copy = "pub")]
+    distance: f32,
+
+    /// Returns the time difference in seconds, which is positive when the lap is slower.
+    #[getset(get_copy = "pub")]
+    time_delta: f32,
+
+    /// Returns the difference in speed in kilometers per hour.
+    #[getset(get_copy = "pub")]
+    speed_delta: f32,
+
+    /// Returns the difference in throttle input.
+    #[getset(get_copy = "pub")]
+    throttle_delta: f32,
+
+    /// Returns the difference in brake input.
+    #[getset(get_copy = "pub")]
+    brake_delta: f32,
+
+    /// Returns the difference in steering input.
+    #[getset(get_copy = "pub")]
+    steering_delta: f32,
+}
+
+/// Comparison of a lap with a reference lap
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::comparison::LapComparison;
+/// # use f1_api::analysis::trace::LapTrace;
+/// # let lap = LapTrace::default();
+/// # let reference = LapTrace::default();
+///
+/// let comparison = LapComparison::between(&lap, &reference);
+///
+/// if let Some(point) = comparison.points().last() {
+///     println!("{:+.3}s at {}m", point.time_delta(), point.distance());
+/// }
+/// ```
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Clone, PartialOrd, Default)]
+pub struct LapComparison {
+    /// Returns the index of the car that drove the lap.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the number of the lap.
+    #[getset(get_copy = "pub")]
+    lap_number: u8,
+
+    /// Returns the index of the car that drove the reference lap.
+    #[getset(get_copy = "pub")]
+    reference_vehicle_index: VehicleIndex,
+
+    /// Returns the number of the reference lap.
+    #[getset(get_copy = "pub")]
+    reference_lap_number: u8,
+
+    /// Returns the differences between the laps, ordered by lap distance.
+    #[getset(get = "pub")]
+    points: Vec<ComparisonPoint>,
+}
+
+impl LapComparison {
+    /// Compare a lap with a reference lap.
+    ///
+    /// The laps are compared at the lap distances of the reference. The two traces can have been
+    /// recorded with different steps, and can be from different drivers or sessions. Distances that
+    /// only one of the laps covers are skipped.
+    pub fn between(lap: &LapTrace, reference: &LapTrace) -> Self {
+        let points = reference
+            .samples()
+            .iter()
+            .filter_map(|expected| {
+                let actual = lap.at(expected.distance())?;
+
+                Some(ComparisonPoint::new(
+                    expected.distance(),
+                    actual.time().as_secs_f32() - expected.time().as_secs_f32(),
+                    actual.speed() - expected.speed(),
+                    actual.throttle() - expected.throttle(),
+                    actual.brake() - expected.brake(),
+                    actual.steering() - expected.steering(),
+                ))
+            })
+            .collect();
+
+        LapComparison::new(
+            lap.vehicle_index(),
+            lap.lap_number(),
+            reference.vehicle_index(),
+            reference.lap_number(),
+            points,
+        )
+    }
+
+    /// Returns the time difference at the given lap distance, interpolated between the closest
+    /// points.
+    pub fn time_delta_at(&self, distance: f32) -> Option<f32> {
+        let index = self
+            .points
+            .iter()
+            .position(|point| point.distance() >= distance)?;
+        let after = self.points[index];
+
+        if index == 0 || after.distance() <= distance {
+            return Some(after.time_delta());
+        }
+
+        let before = self.points[index - 1];
+        let ratio = (distance - before.distance()) / (after.distance() - before.distance());
+
+        Some(before.time_delta() + (after.time_delta() - before.time_delta()) * ratio)
+    }
+
+    /// Returns the section of the lap in which the most time was lost against the reference.
+    ///
+    /// The section is returned as the lap distances at its start and end, and the time that was
+    /// lost in it. `None` is returned if no time was lost anywhere.
+    pub fn biggest_loss(&self) -> Option<(f32, f32, f32)> {
+        let mut best: Option<(f32, f32, f32)> = None;
+        let mut start = match self.points.first() {
+            Some(point) => *point,
+            None => return None,
+        };
+
+        for point in &self.points {
+            // Start a new section whenever the delta reaches a new minimum, since any loss has to
+            // be measured from the lowest point before it.
+            if point.time_delta() < start.time_delta() {
+                start = *point;
+            }
+
+            let loss = point.time_delta() - start.time_delta();
+
+            if loss > 0.0 && best.is_none_or(|(_, _, best)| loss > best) {
+                best = Some((start.distance(), point.distance(), loss));
+            }
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::analysis::comparison::LapComparison;
+    use crate::analysis::trace::{LapTrace, TraceSample};
+    use crate::packet::telemetry::Gear;
+    use crate::types::Property3D;
+
+    fn trace(lap_number: u8, step: f32, samples: &[(u64, f32)]) -> LapTrace {
+        let samples = samples
+            .iter()
+            .enumerate()
+            .map(|(index, (millis, speed))| {
+                TraceSample::new(
+                    index as f32 * step,
+                    Duration::from_millis(*millis),
+                    Property3D::default(),
+                    *speed,
+                    1.0,
+                    0.0,
+                    0.0,
+                    Gear::Sixth,
+                    10000.0,
+                )
+            })
+            .collect();
+
+        LapTrace::new(0, lap_number, Duration::default(), step, samples)
+    }
+
+    #[test]
+    fn between_laps_with_same_step() {
+        let lap = trace(
+            2,
+            10.0,
+            &[(0, 200.0), (200, 190.0), (500, 180.0), (700, 200.0)],
+        );
+        let reference = trace(
+            1,
+            10.0,
+            &[(0, 200.0), (200, 200.0), (400, 200.0), (600, 200.0)],
+        );
+
+        let comparison = LapComparison::between(&lap, &reference);
+
+        assert_eq!(2, comparison.lap_number());
+        assert_eq!(1, comparison.reference_lap_number());
+        assert_eq!(4, comparison.points().len());
+
+        let point = comparison.points()[2];
+        assert_eq!(20.0, point.distance());
+        assert_approx_eq!(0.1, point.time_delta());
+        assert_approx_eq!(-20.0, point.speed_delta());
+
+        assert_approx_eq!(0.05, comparison.time_delta_at(15.0).unwrap());
+    }
+
+    #[test]
+    fn between_laps_with_different_steps() {
+        let lap = trace(2, 5.0, &[(0, 200.0), (100, 200.0), (200, 200.0)]);
+        let reference = trace(1, 10.0, &[(0, 200.0), (300, 200.0), (600, 200.0)]);
+
+        let comparison = LapComparison::between(&lap, &reference);
+
+        assert_eq!(2, comparison.points().len());
+        assert_approx_eq!(-0.1, comparison.points()[1].time_delta());
+    }
+
+    #[test]
+    fn biggest_loss_after_gain() {
+        let lap = trace(
+            2,
+            10.0,
+            &[(0, 0.0), (100, 0.0), (400, 0.0), (600, 0.0), (700, 0.0)],
+        );
+        let reference = trace(
+            1,
+            10.0,
+            &[(0, 0.0), (200, 0.0), (300, 0.0), (400, 0.0), (500, 0.0)],
+        );
+
+        let comparison = LapComparison::between(&lap, &reference);
+        let (start, end, loss) = comparison.biggest_loss().unwrap();
+
+        assert_eq!(10.0, start);
+        assert_eq!(30.0, end);
+        assert_approx_eq!(0.3, loss);
+    }
+}
@@ -0,0 +1,375 @@
+//! Corners of a track and how cars drive through them
+//!
+//! The corners of a track are found in its learned `TrackMap`, as the sections in which cars turn
+//! with a significant lateral G force or steering input. The `CornerAnalyzer` then follows each car
+//! through the corners, and reports the speed with which it entered the corner, the minimum speed
+//! at the apex, and the throttle with which it left the corner.
+
+use derive_new::new;
+use getset::CopyGetters;
+
+use crate::analysis::track_map::TrackMap;
+use crate::packet::lap::LapPacket;
+use crate::packet::telemetry::TelemetryPacket;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Lateral G force above which a section of the track is considered a corner
+const LATERAL_G_THRESHOLD: f32 = 1.0;
+
+/// Steering input above which a section of the track is considered a corner
+const STEERING_THRESHOLD: f32 = 0.1;
+
+/// Maximum distance in meters between two sections that are merged into a single corner
+const MERGE_DISTANCE: f32 = 30.0;
+
+/// Minimum length of a corner in meters
+const MINIMUM_LENGTH: f32 = 15.0;
+
+/// Share of the track that must be mapped before corners are detected
+pub(crate) const MINIMUM_COVERAGE: f32 = 0.9;
+
+/// Corner of a track
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct Corner {
+    /// Returns the number of the corner, starting at 1.
+    #[getset(get_copy = "pub")]
+    number: u8,
+
+    /// Returns the lap distance at which the corner starts in meters.
+    #[getset(get_copy = "pub")]
+    entry: f32,
+
+    /// Returns the lap distance with the highest lateral G force in meters.
+    #[getset(get_copy = "pub")]
+    apex: f32,
+
+    /// Returns the lap distance at which the corner ends in meters.
+    #[getset(get_copy = "pub")]
+    exit: f32,
+}
+
+impl Corner {
+    /// Returns whether the given lap distance is inside the corner.
+    pub fn contains(&self, distance: f32) -> bool {
+        self.entry <= distance && distance <= self.exit
+    }
+}
+
+/// Detect the corners in a track map.
+///
+/// Corners that cross the start/finish line are split in two.
+pub fn detect_corners(map: &TrackMap) -> Vec<Corner> {
+    let mut sections: Vec<(f32, f32, f32, f32)> = Vec::new();
+
+    for point in map.points() {
+        let turning = point.lateral_g().abs() > LATERAL_G_THRESHOLD
+            || point.steering().abs() > STEERING_THRESHOLD;
+
+        if !turning {
+            continue;
+        }
+
+        let start = point.distance();
+        let end = start + map.resolution();
+        let lateral_g = point.lateral_g().abs();
+
+        match sections.last_mut() {
+            Some(section) if start - section.1 <= MERGE_DISTANCE => {
+                section.1 = end;
+
+                if lateral_g > section.3 {
+                    section.2 = start + map.resolution() / 2.0;
+                    section.3 = lateral_g;
+                }
+            }
+            _ => sections.push((start, end, start + map.resolution() / 2.0, lateral_g)),
+        }
+    }
+
+    sections
+        .into_iter()
+        .filter(|(start, end, _, _)| end - start >= MINIMUM_LENGTH)
+        .enumerate()
+        .map(|(index, (start, end, apex, _))| Corner::new(index as u8 + 1, start, apex, end))
+        .collect()
+}
+
+/// Statistics of a car driving through a corner
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct CornerStatistics {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the lap in which the car drove through the corner.
+    #[getset(get_copy = "pub")]
+    lap_number: u8,
+
+    /// Returns the number of the corner.
+    #[getset(get_copy = "pub")]
+    corner: u8,
+
+    /// Returns the speed with which the car entered the corner in kilometers per hour.
+    #[getset(get_copy = "pub")]
+    entry_speed: u16,
+
+    /// Returns the minimum speed of the car in the corner in kilometers per hour.
+    #[getset(get_copy = "pub")]
+    apex_speed: u16,
+
+    /// Returns the throttle input with which the car left the corner.
+    #[getset(get_copy = "pub")]
+    exit_throttle: f32,
+}
+
+/// Progress of a car through a corner
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct Progress {
+    corner: usize,
+    lap_number: u8,
+    entry_speed: u16,
+    apex_speed: u16,
+
+    /// Cars that are first seen in the middle of a corner are followed until they leave it, but
+    /// their statistics are not reported.
+    complete: bool,
+}
+
+/// Analyzer that reports how cars drive through the corners of a track
+///
+/// The analyzer learns a map of the track, and detects its corners once most of the track has been
+/// mapped. Alternatively, the corners can be provided up front with `CornerAnalyzer::with_corners`.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::corners::CornerAnalyzer;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut analyzer = CornerAnalyzer::default();
+///
+/// for packet in packets() {
+///     for statistics in analyzer.update(&packet) {
+///         println!("{:?}", statistics);
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CornerAnalyzer {
+    track_map: TrackMap,
+    corners: Vec<Corner>,
+    fixed: bool,
+    session_uid: Option<u64>,
+    laps: Vec<(u8, f32)>,
+    observed: Vec<bool>,
+    progress: Vec<Option<Progress>>,
+}
+
+impl CornerAnalyzer {
+    /// Create an analyzer for a track with known corners.
+    pub fn with_corners(corners: Vec<Corner>) -> Self {
+        CornerAnalyzer {
+            corners,
+            fixed: true,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the learned map of the track.
+    pub fn track_map(&self) -> &TrackMap {
+        &self.track_map
+    }
+
+    /// Returns the corners of the track.
+    ///
+    /// The list is empty until enough of the track has been mapped to detect its corners.
+    pub fn corners(&self) -> &[Corner] {
+        &self.corners
+    }
+
+    /// Update the analyzer with a new packet, and return the corners that cars have completed.
+    pub fn update(&mut self, packet: &Packet) -> Vec<CornerStatistics> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.laps.clear();
+            self.observed.clear();
+            self.progress.clear();
+
+            if !self.fixed {
+                self.corners.clear();
+            }
+        }
+
+        self.track_map.update(packet);
+
+        match packet {
+            Packet::Lap(packet) => {
+                self.update_laps(packet);
+                Vec::new()
+            }
+            Packet::Telemetry(packet) => self.update_telemetry(packet),
+            _ => Vec::new(),
+        }
+    }
+
+    fn update_laps(&mut self, packet: &LapPacket) {
+        self.laps = packet
+            .laps()
+            .iter()
+            .map(|lap| (lap.current_lap_number(), lap.lap_distance()))
+            .collect();
+
+        if self.corners.is_empty() && self.track_map.coverage() >= MINIMUM_COVERAGE {
+            self.corners = detect_corners(&self.track_map);
+        }
+    }
+
+    fn update_telemetry(&mut self, packet: &TelemetryPacket) -> Vec<CornerStatistics> {
+        let mut statistics = Vec::new();
+
+        if self.corners.is_empty() {
+            return statistics;
+        }
+
+        self.observed.resize(self.laps.len(), false);
+        self.progress.resize(self.laps.len(), None);
+
+        for (index, telemetry) in packet.telemetry().iter().enumerate() {
+            let (lap_number, distance) = match self.laps.get(index) {
+                Some(lap) => *lap,
+                None => continue,
+            };
+
+            let corner = self
+                .corners
+                .iter()
+                .position(|corner| corner.contains(distance));
+            let progress = &mut self.progress[index];
+            let observed = std::mem::replace(&mut self.observed[index], true);
+
+            if let Some(current) = progress {
+                if Some(current.corner) == corner && current.lap_number == lap_number {
+                    current.apex_speed = current.apex_speed.min(telemetry.speed());
+                    continue;
+                }
+
+                if current.complete {
+                    statistics.push(CornerStatistics::new(
+                        index as VehicleIndex,
+                        current.lap_number,
+                        self.corners[current.corner].number(),
+                        current.entry_speed,
+                        current.apex_speed,
+                        telemetry.throttle(),
+                    ));
+                }
+            }
+
+            *progress = corner.map(|corner| Progress {
+                corner,
+                lap_number,
+                entry_speed: telemetry.speed(),
+                apex_speed: telemetry.speed(),
+                complete: observed,
+            });
+        }
+
+        statistics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::corners::{detect_corners, Corner, CornerAnalyzer, CornerStatistics};
+    use crate::analysis::track_map::TrackMap;
+    use crate::fixtures::{header, lap, lap_packet, telemetry, telemetry_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+    use crate::types::Property3D;
+
+    fn inputs(speed: u16, throttle: f32) -> Packet {
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::default(), 0),
+            vec![telemetry(speed, throttle, 0.0, 0.0, Gear::Third, 10000)],
+        )
+    }
+
+    #[test]
+    fn detect_corners_in_map() {
+        let mut map = TrackMap::new(10.0);
+
+        for distance in (0..500).step_by(10) {
+            let distance = distance as f32;
+            let lateral_g = match distance as u32 {
+                100..=150 => 2.0,
+                170..=190 => 3.0,
+                300..=300 => 2.0,
+                400..=450 => -1.5,
+                _ => 0.0,
+            };
+
+            map.add_motion(distance, &Property3D::default(), lateral_g);
+        }
+
+        let corners = detect_corners(&map);
+
+        assert_eq!(2, corners.len());
+        assert_eq!(Corner::new(1, 100.0, 175.0, 200.0), corners[0]);
+        assert_eq!(Corner::new(2, 400.0, 405.0, 460.0), corners[1]);
+    }
+
+    #[test]
+    fn update_reports_corner() {
+        let mut analyzer = CornerAnalyzer::with_corners(vec![Corner::new(1, 100.0, 150.0, 200.0)]);
+
+        let samples = [
+            (50.0, 280, 1.0),
+            (110.0, 250, 0.0),
+            (150.0, 120, 0.2),
+            (190.0, 160, 0.8),
+        ];
+
+        for (distance, speed, throttle) in samples.iter() {
+            analyzer.update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                vec![lap(2, *distance)],
+            ));
+            assert!(analyzer.update(&inputs(*speed, *throttle)).is_empty());
+        }
+
+        analyzer.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(2, 210.0)],
+        ));
+        let statistics = analyzer.update(&inputs(200, 1.0));
+
+        assert_eq!(
+            vec![CornerStatistics::new(0, 2, 1, 250, 120, 1.0)],
+            statistics
+        );
+    }
+
+    #[test]
+    fn update_ignores_partial_corner() {
+        let mut analyzer = CornerAnalyzer::with_corners(vec![Corner::new(1, 100.0, 150.0, 200.0)]);
+
+        analyzer.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(2, 150.0)],
+        ));
+        analyzer.update(&inputs(120, 0.2));
+        analyzer.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(2, 210.0)],
+        ));
+
+        assert!(analyzer.update(&inputs(200, 1.0)).is_empty());
+    }
+}
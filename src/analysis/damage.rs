@@ -0,0 +1,224 @@
+//! Changes of the damage of the cars
+//!
+//! The status packet reports the damage of the wings, the engine, and the gear box of every car.
+//! The values only ever increase during a session, until the car pits for a new front wing. The
+//! `DamageTracker` compares consecutive status packets and reports each increase, so that
+//! broadcasters can show that a car has damaged its front wing without diffing the packets
+//! themselves.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::status::CarStatus;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Part of a car that can be damaged
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum DamageComponent {
+    FrontLeftWing,
+    FrontRightWing,
+    RearWing,
+    Engine,
+    GearBox,
+}
+
+impl DamageComponent {
+    /// Returns all components in the order in which they are compared.
+    pub fn all() -> [DamageComponent; 5] {
+        [
+            DamageComponent::FrontLeftWing,
+            DamageComponent::FrontRightWing,
+            DamageComponent::RearWing,
+            DamageComponent::Engine,
+            DamageComponent::GearBox,
+        ]
+    }
+
+    /// Returns the damage of the component in percent.
+    pub fn read(self, status: &CarStatus) -> u8 {
+        match self {
+            DamageComponent::FrontLeftWing => status.front_left_wing_damage(),
+            DamageComponent::FrontRightWing => status.front_right_wing_damage(),
+            DamageComponent::RearWing => status.rear_wing_damage(),
+            DamageComponent::Engine => status.engine_damage(),
+            DamageComponent::GearBox => status.gear_box_damage(),
+        }
+    }
+}
+
+/// Increase of the damage of a component
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct DamageChange {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the component that was damaged.
+    #[getset(get_copy = "pub")]
+    component: DamageComponent,
+
+    /// Returns the damage before the change in percent.
+    #[getset(get_copy = "pub")]
+    from: u8,
+
+    /// Returns the damage after the change in percent.
+    #[getset(get_copy = "pub")]
+    to: u8,
+
+    /// Returns the session time at which the change was reported.
+    #[getset(get = "pub")]
+    session_time: Duration,
+}
+
+/// Tracker for the damage of all cars in a session
+///
+/// The first status packet of a session sets the initial damage without reporting a change.
+/// Repairs, e.g. a new front wing in the pit lane, update the damage silently.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::damage::DamageTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = DamageTracker::default();
+///
+/// for packet in packets() {
+///     for change in tracker.update(&packet) {
+///         println!("{:?} damage for car #{}", change.component(), change.vehicle_index());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DamageTracker {
+    session_uid: Option<u64>,
+    damage: Vec<[u8; 5]>,
+}
+
+impl DamageTracker {
+    /// Returns the current damage of a component of the given car in percent.
+    pub fn damage(&self, vehicle_index: VehicleIndex, component: DamageComponent) -> Option<u8> {
+        let position = DamageComponent::all()
+            .iter()
+            .position(|candidate| *candidate == component)?;
+
+        self.damage
+            .get(vehicle_index as usize)
+            .map(|damage| damage[position])
+    }
+
+    /// Update the tracker with a new packet, and return the increases of the damage.
+    pub fn update(&mut self, packet: &Packet) -> Vec<DamageChange> {
+        let header = packet.header();
+
+        if self.session_uid != Some(header.session_uid()) {
+            self.session_uid = Some(header.session_uid());
+            self.damage.clear();
+        }
+
+        let statuses = match packet {
+            Packet::Status(packet) => packet.statuses(),
+            _ => return Vec::new(),
+        };
+
+        let damage: Vec<[u8; 5]> = statuses
+            .iter()
+            .map(|status| DamageComponent::all().map(|component| component.read(status)))
+            .collect();
+
+        let mut changes = Vec::new();
+
+        for (index, (previous, current)) in self.damage.iter().zip(&damage).enumerate() {
+            for (component, (from, to)) in DamageComponent::all()
+                .iter()
+                .zip(previous.iter().zip(current.iter()))
+            {
+                if to > from {
+                    changes.push(DamageChange::new(
+                        index as VehicleIndex,
+                        *component,
+                        *from,
+                        *to,
+                        *header.session_time(),
+                    ));
+                }
+            }
+        }
+
+        self.damage = damage;
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::damage::{DamageComponent, DamageTracker};
+    use crate::fixtures::header;
+    use crate::packet::header::PacketType;
+    use crate::packet::status::{CarStatus, CarStatusPacket};
+    use crate::packet::Packet;
+
+    fn status_packet(seconds: u64, front_left_wing: u8, engine: u8) -> Packet {
+        let status = CarStatus::new(
+            Default::default(),
+            false,
+            Default::default(),
+            0,
+            false,
+            0.0,
+            0.0,
+            0.0,
+            0,
+            0,
+            0,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            front_left_wing,
+            0,
+            0,
+            engine,
+            0,
+            Default::default(),
+            0.0,
+            Default::default(),
+            0.0,
+            0.0,
+            0.0,
+        );
+
+        Packet::Status(CarStatusPacket::new(
+            header(PacketType::Status, 1, Duration::from_secs(seconds), 0),
+            vec![status].into(),
+        ))
+    }
+
+    #[test]
+    fn update_reports_increases() {
+        let mut tracker = DamageTracker::default();
+
+        assert!(tracker.update(&status_packet(1, 10, 0)).is_empty());
+        assert!(tracker.update(&status_packet(2, 10, 0)).is_empty());
+
+        let changes = tracker.update(&status_packet(3, 45, 5));
+
+        assert_eq!(2, changes.len());
+        assert_eq!(DamageComponent::FrontLeftWing, changes[0].component());
+        assert_eq!(10, changes[0].from());
+        assert_eq!(45, changes[0].to());
+        assert_eq!(Duration::from_secs(3), *changes[0].session_time());
+        assert_eq!(DamageComponent::Engine, changes[1].component());
+
+        assert!(tracker.update(&status_packet(4, 0, 5)).is_empty());
+        assert_eq!(Some(0), tracker.damage(0, DamageComponent::FrontLeftWing));
+    }
+}
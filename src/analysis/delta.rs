@@ -0,0 +1,341 @@
+//! Live time delta to the personal best lap
+//!
+//! A delta bar shows a driver whether the current lap is faster or slower than their best lap. The
+//! games only publish sector times, which would make the delta jump three times per lap. Instead,
+//! the `DeltaTracker` records the time at which each car reached every point of its best lap, and
+//! compares the current lap against it at the same lap distance.
+//!
+//! Lap distances are published in the lap packets, which are usually sent less often than motion
+//! packets. Between two lap packets, the lap distance and lap time of each car are extrapolated
+//! from its speed, so that the delta is updated smoothly at the rate of the motion packets.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::CopyGetters;
+
+use crate::packet::lap::{LapPacket, PitStatus};
+use crate::packet::motion::MotionPacket;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Time delta of a car to its personal best lap
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct LiveDelta {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the number of the current lap.
+    #[getset(get_copy = "pub")]
+    lap_number: u8,
+
+    /// Returns the lap distance of the car in meters.
+    #[getset(get_copy = "pub")]
+    distance: f32,
+
+    /// Returns the time delta in seconds, which is positive when the car is slower than its best lap.
+    #[getset(get_copy = "pub")]
+    delta: f32,
+}
+
+/// Lap time and lap distance at the last lap packet
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct Anchor {
+    session_time: Duration,
+    lap_time: Duration,
+    distance: f32,
+}
+
+/// Progress of a single car through its current and best lap
+#[derive(Debug, PartialEq, Clone, Default)]
+struct CarDelta {
+    lap_number: u8,
+    valid: bool,
+    anchor: Option<Anchor>,
+    current: Vec<(f32, f32)>,
+    best: Option<(Duration, Vec<(f32, f32)>)>,
+}
+
+impl CarDelta {
+    /// Returns the time at which the best lap reached the given distance, in seconds.
+    fn reference_time(&self, distance: f32) -> Option<f32> {
+        let samples = &self.best.as_ref()?.1;
+        let index = samples.partition_point(|(d, _)| *d < distance);
+
+        if index == 0 || index == samples.len() {
+            return None;
+        }
+
+        let (d0, t0) = samples[index - 1];
+        let (d1, t1) = samples[index];
+
+        if d1 <= d0 {
+            return Some(t1);
+        }
+
+        Some(t0 + (t1 - t0) * (distance - d0) / (d1 - d0))
+    }
+}
+
+/// Tracker for the time delta of each car to its personal best lap in the session
+///
+/// The tracker records every valid lap of each car, and uses the fastest one as the reference for
+/// the following laps. Deltas are reported for every motion packet, once a car has completed a
+/// valid lap. Laps that are started or finished in the pit lane are not used as a reference.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::delta::DeltaTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = DeltaTracker::default();
+///
+/// for packet in packets() {
+///     for delta in tracker.update(&packet) {
+///         println!("Car {}: {:+.3}s", delta.vehicle_index(), delta.delta());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DeltaTracker {
+    session_uid: Option<u64>,
+    track_length: Option<f32>,
+    cars: Vec<CarDelta>,
+}
+
+impl DeltaTracker {
+    /// Returns the time of the personal best lap of a car, if it has completed a valid lap.
+    pub fn best_lap_time(&self, vehicle_index: VehicleIndex) -> Option<Duration> {
+        self.cars
+            .get(vehicle_index as usize)
+            .and_then(|car| car.best.as_ref())
+            .map(|(lap_time, _)| *lap_time)
+    }
+
+    /// Update the tracker with a new packet, and return the deltas of all cars with a reference lap.
+    pub fn update(&mut self, packet: &Packet) -> Vec<LiveDelta> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.track_length = None;
+            self.cars.clear();
+        }
+
+        match packet {
+            Packet::Session(packet) => {
+                self.track_length = Some(packet.track_length() as f32);
+                Vec::new()
+            }
+            Packet::Lap(packet) => {
+                self.update_laps(packet);
+                Vec::new()
+            }
+            Packet::Motion(packet) => self.update_motion(packet),
+            _ => Vec::new(),
+        }
+    }
+
+    fn update_laps(&mut self, packet: &LapPacket) {
+        let session_time = *packet.header().session_time();
+        let track_length = self.track_length;
+
+        self.cars.resize(packet.laps().len(), CarDelta::default());
+
+        for (car, lap) in self.cars.iter_mut().zip(packet.laps()) {
+            let lap_number = lap.current_lap_number();
+
+            if lap_number != car.lap_number {
+                let lap_time = *lap.last_lap_time();
+                let completed = lap_number == car.lap_number + 1
+                    && car.valid
+                    && !car.current.is_empty()
+                    && !lap_time.is_zero();
+
+                if completed && car.best.as_ref().is_none_or(|(best, _)| lap_time < *best) {
+                    let mut samples = std::mem::take(&mut car.current);
+
+                    // The lap ends at the finish line, which is only known from the session packet
+                    if let Some(track_length) = track_length {
+                        samples.push((track_length, lap_time.as_secs_f32()));
+                    }
+
+                    car.best = Some((lap_time, samples));
+                }
+
+                car.lap_number = lap_number;
+                car.valid = true;
+                car.current.clear();
+            }
+
+            if lap.pit_status() != PitStatus::None || !lap.is_valid_lap() {
+                car.valid = false;
+            }
+
+            if lap.lap_distance() < 0.0 {
+                car.anchor = None;
+                continue;
+            }
+
+            let time = lap.current_lap_time().as_secs_f32();
+
+            if car
+                .current
+                .last()
+                .is_none_or(|(distance, _)| lap.lap_distance() > *distance)
+            {
+                car.current.push((lap.lap_distance(), time));
+            }
+
+            car.anchor = Some(Anchor {
+                session_time,
+                lap_time: *lap.current_lap_time(),
+                distance: lap.lap_distance(),
+            });
+        }
+    }
+
+    fn update_motion(&mut self, packet: &MotionPacket) -> Vec<LiveDelta> {
+        let session_time = *packet.header().session_time();
+        let mut deltas = Vec::new();
+
+        for (index, (car, motion)) in self.cars.iter_mut().zip(packet.cars()).enumerate() {
+            let velocity = motion.velocity();
+            let speed = (velocity.x().powi(2) + velocity.y().powi(2) + velocity.z().powi(2)).sqrt();
+
+            let anchor = match car.anchor {
+                Some(anchor) => anchor,
+                None => continue,
+            };
+
+            let elapsed = session_time
+                .checked_sub(anchor.session_time)
+                .unwrap_or_default()
+                .as_secs_f32();
+            let distance = anchor.distance + speed * elapsed;
+            let lap_time = anchor.lap_time.as_secs_f32() + elapsed;
+
+            if let Some(reference) = car.reference_time(distance) {
+                deltas.push(LiveDelta::new(
+                    index as VehicleIndex,
+                    car.lap_number,
+                    distance,
+                    lap_time - reference,
+                ));
+            }
+        }
+
+        deltas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::analysis::delta::DeltaTracker;
+    use crate::fixtures::{header, lap_packet, motion, motion_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+    use crate::packet::Packet;
+    use crate::types::{CornerProperty, Property3D};
+
+    fn timed_lap(lap_number: u8, distance: f32, lap_time: u64, last: u64) -> Lap {
+        Lap::new(
+            Duration::from_millis(last),
+            Duration::from_millis(lap_time),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            distance,
+            distance,
+            0.0,
+            1,
+            lap_number,
+            PitStatus::None,
+            Sector::First,
+            true,
+            0,
+            1,
+            DriverStatus::FlyingLap,
+            ResultStatus::Active,
+        )
+    }
+
+    fn speed(millis: u64, speed: f32) -> Packet {
+        let velocity = Property3D::new(speed, 0.0, 0.0);
+
+        motion_packet(
+            header(PacketType::Motion, 1, Duration::from_millis(millis), 0),
+            vec![motion(
+                Property3D::default(),
+                velocity,
+                Property3D::default(),
+            )],
+            CornerProperty::default(),
+        )
+    }
+
+    #[test]
+    fn update_interpolates_delta_between_lap_packets() {
+        let mut tracker = DeltaTracker::default();
+
+        // First lap at 50 m/s
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![timed_lap(1, 0.0, 0, 0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(10_000), 0),
+            vec![timed_lap(1, 500.0, 10_000, 0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(20_000), 0),
+            vec![timed_lap(1, 1000.0, 20_000, 0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(20_100), 0),
+            vec![timed_lap(2, 5.0, 100, 20_000)],
+        ));
+
+        assert_eq!(Some(Duration::from_secs(20)), tracker.best_lap_time(0));
+        assert_approx_eq!(0.0, tracker.update(&speed(20_100, 50.0))[0].delta());
+
+        // Second lap at 40 m/s
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(30_000), 0),
+            vec![timed_lap(2, 400.0, 10_000, 20_000)],
+        ));
+        let deltas = tracker.update(&speed(32_500, 40.0));
+
+        assert_eq!(1, deltas.len());
+        assert_eq!(2, deltas[0].lap_number());
+        assert_approx_eq!(500.0, deltas[0].distance());
+        assert_approx_eq!(2.5, deltas[0].delta());
+    }
+
+    #[test]
+    fn update_without_last_lap_time() {
+        let mut tracker = DeltaTracker::default();
+
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![timed_lap(1, 0.0, 0, 0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(10_000), 0),
+            vec![timed_lap(1, 500.0, 10_000, 0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(20_000), 0),
+            vec![timed_lap(2, 5.0, 100, 0)],
+        ));
+
+        assert_eq!(None, tracker.best_lap_time(0));
+    }
+}
@@ -0,0 +1,285 @@
+//! Time that cars spend in the dirty air of the car ahead
+//!
+//! A car that follows closely behind another one loses downforce in its turbulent wake, which
+//! overheats and wears its tyres. How long a car has been stuck behind others is therefore an
+//! important input for tyre and strategy models. The `DirtyAirTracker` finds the car directly ahead
+//! of each car on track, including lapped cars, and measures the gap to it. The time that a car
+//! spends within a configurable gap is accumulated per lap.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::lap::{Lap, LapPacket, PitStatus, ResultStatus};
+use crate::packet::Packet;
+use crate::tracker::SessionTracker;
+use crate::types::VehicleIndex;
+
+/// Default gap to the car ahead below which a car is in dirty air
+const DEFAULT_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// Time that a car spent in dirty air during a lap
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct LapExposure {
+    /// Returns the number of the lap.
+    #[getset(get_copy = "pub")]
+    lap: u8,
+
+    /// Returns the time the car spent within the threshold of the car ahead.
+    #[getset(get = "pub")]
+    exposed: Duration,
+
+    /// Returns the time the car was observed on track during the lap.
+    #[getset(get = "pub")]
+    observed: Duration,
+}
+
+impl LapExposure {
+    /// Returns the share of the observed time that the car spent in dirty air, between 0 and 1.
+    pub fn share(&self) -> f32 {
+        if self.observed.is_zero() {
+            return 0.0;
+        }
+
+        self.exposed.as_secs_f32() / self.observed.as_secs_f32()
+    }
+}
+
+/// Tracker for the exposure of the cars to dirty air
+///
+/// The gap to the car ahead is measured whenever a lap packet is received, as the time since the
+/// car ahead passed the same point on the track. Cars in the pit lane are ignored, both as the car
+/// that follows and as the car ahead.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use f1_api::analysis::dirty_air::DirtyAirTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = DirtyAirTracker::new(Duration::from_millis(1500));
+///
+/// for packet in packets() {
+///     tracker.update(&packet);
+/// }
+///
+/// for lap in tracker.exposure(0) {
+///     println!("Lap {}: {:.0}% in dirty air", lap.lap(), lap.share() * 100.0);
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct DirtyAirTracker {
+    threshold: Duration,
+    tracker: SessionTracker,
+    session_time: Option<Duration>,
+    exposure: Vec<Vec<LapExposure>>,
+}
+
+impl DirtyAirTracker {
+    /// Create a tracker that counts gaps below the given threshold as dirty air.
+    pub fn new(threshold: Duration) -> Self {
+        DirtyAirTracker {
+            threshold,
+            tracker: SessionTracker::default(),
+            session_time: None,
+            exposure: Vec::new(),
+        }
+    }
+
+    /// Returns the gap to the car ahead below which a car is in dirty air.
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Returns the model of the session that is used to measure the gaps.
+    pub fn tracker(&self) -> &SessionTracker {
+        &self.tracker
+    }
+
+    /// Returns the exposure of the given car to dirty air in each lap.
+    pub fn exposure(&self, vehicle_index: VehicleIndex) -> &[LapExposure] {
+        self.exposure
+            .get(vehicle_index as usize)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns the current gap of the given car to the car directly ahead on track.
+    pub fn gap_ahead(&self, vehicle_index: VehicleIndex) -> Option<Duration> {
+        let laps = self.tracker.laps().as_ref()?;
+        self.gap(laps, vehicle_index as usize)
+    }
+
+    /// Update the tracker with a new packet.
+    pub fn update(&mut self, packet: &Packet) {
+        if self.tracker.session_uid() != Some(packet.header().session_uid()) {
+            self.session_time = None;
+            self.exposure.clear();
+        }
+
+        self.tracker.update(packet);
+
+        let laps = match packet {
+            Packet::Lap(packet) => packet,
+            _ => return,
+        };
+
+        let now = *laps.header().session_time();
+        let elapsed = self
+            .session_time
+            .and_then(|session_time| now.checked_sub(session_time))
+            .unwrap_or_default();
+        self.session_time = Some(now);
+
+        if self.exposure.len() < laps.laps().len() {
+            self.exposure.resize(laps.laps().len(), Vec::new());
+        }
+
+        for (index, lap) in laps.laps().iter().enumerate() {
+            if !on_track(lap) {
+                continue;
+            }
+
+            let exposed = self
+                .gap(laps, index)
+                .is_some_and(|gap| gap < self.threshold);
+            let exposure = &mut self.exposure[index];
+
+            if exposure.last().map(|exposure| exposure.lap) != Some(lap.current_lap_number()) {
+                exposure.push(LapExposure::new(
+                    lap.current_lap_number(),
+                    Duration::default(),
+                    Duration::default(),
+                ));
+            }
+
+            let current = exposure.last_mut().expect("exposure was just pushed");
+            current.observed += elapsed;
+
+            if exposed {
+                current.exposed += elapsed;
+            }
+        }
+    }
+
+    /// Returns the time since the car directly ahead on track passed the position of the car.
+    fn gap(&self, laps: &LapPacket, index: usize) -> Option<Duration> {
+        let car = laps.laps().get(index)?;
+        let track_length = self
+            .tracker
+            .session()
+            .as_ref()
+            .map(|session| session.track_length() as f32);
+
+        let (ahead, distance) = laps
+            .laps()
+            .iter()
+            .enumerate()
+            .filter(|(other, lap)| *other != index && on_track(lap))
+            .map(|(other, lap)| {
+                let distance = lap.lap_distance() - car.lap_distance();
+
+                match track_length {
+                    Some(length) => (other, distance.rem_euclid(length)),
+                    None => (other, distance),
+                }
+            })
+            .filter(|(_, distance)| *distance > 0.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        let passed_at = self
+            .tracker
+            .car(ahead as VehicleIndex)?
+            .time_at_distance(laps.laps()[ahead].total_distance() - distance)?;
+
+        laps.header().session_time().checked_sub(passed_at)
+    }
+}
+
+impl Default for DirtyAirTracker {
+    fn default() -> Self {
+        DirtyAirTracker::new(DEFAULT_THRESHOLD)
+    }
+}
+
+/// Returns whether the car is racing on track.
+fn on_track(lap: &Lap) -> bool {
+    lap.position() > 0
+        && lap.pit_status() == PitStatus::None
+        && lap.result_status() == ResultStatus::Active
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::dirty_air::DirtyAirTracker;
+    use crate::fixtures::header;
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, LapPacket, PitStatus, ResultStatus, Sector};
+    use crate::packet::Packet;
+
+    fn lap(distance: f32, position: u8) -> Lap {
+        Lap::new(
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            distance,
+            distance,
+            0.0,
+            position,
+            1,
+            PitStatus::None,
+            Sector::First,
+            true,
+            0,
+            position,
+            DriverStatus::OnTrack,
+            ResultStatus::Active,
+        )
+    }
+
+    /// Drive two cars at 50 m/s for four seconds, with the second car 40 meters behind the first.
+    fn drive(tracker: &mut DirtyAirTracker) {
+        for step in 0..=8 {
+            let seconds = step as f32 * 0.5;
+            let distance = 100.0 + 50.0 * seconds;
+
+            tracker.update(&Packet::Lap(LapPacket::new(
+                header(PacketType::Lap, 1, Duration::from_secs_f32(seconds), 0),
+                vec![lap(distance, 1), lap(distance - 40.0, 2)].into(),
+            )));
+        }
+    }
+
+    #[test]
+    fn update_accumulates_time_in_dirty_air() {
+        let mut tracker = DirtyAirTracker::default();
+        drive(&mut tracker);
+
+        let follower = tracker.exposure(1);
+        assert_eq!(1, follower.len());
+        assert_eq!(Duration::from_secs(4), *follower[0].exposed());
+        assert_eq!(1.0, follower[0].share());
+
+        let leader = tracker.exposure(0);
+        assert_eq!(Duration::from_secs(4), *leader[0].observed());
+        assert_eq!(Duration::default(), *leader[0].exposed());
+
+        let gap = tracker.gap_ahead(1).unwrap();
+        assert!((gap.as_secs_f32() - 0.8).abs() < 0.01);
+    }
+
+    #[test]
+    fn update_respects_threshold() {
+        let mut tracker = DirtyAirTracker::new(Duration::from_millis(500));
+        drive(&mut tracker);
+
+        assert_eq!(Duration::default(), *tracker.exposure(1)[0].exposed());
+    }
+}
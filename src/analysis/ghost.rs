@@ -0,0 +1,305 @@
+//! Racing against a lap from another session
+//!
+//! A ghost lap is a reference lap that is replayed alongside a live lap, like the ghost car in the
+//! time trial mode of the game. The reference can come from any session, for example the best lap
+//! from yesterday's practice, which makes it possible to race against a past self across sessions
+//! and days.
+//!
+//! `reference_lap` loads the fastest lap of a car from a capture. The `Ghost` then follows a car
+//! through its laps, and reports the live time delta and the inputs of both laps at the current lap
+//! distance. When the car completes a lap, the full lap is compared with the reference as well.
+
+#[cfg(feature = "decode")]
+use std::io::{Error, Read};
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::analysis::comparison::LapComparison;
+use crate::analysis::trace::{LapTrace, LapTraceRecorder, TraceSample};
+#[cfg(feature = "decode")]
+use crate::capture::CaptureReader;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Load the fastest lap of a car from a capture.
+///
+/// If no vehicle index is given, the laps of the player's car are used. `Ok(None)` is returned if
+/// the car did not complete a lap in the capture.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use f1_api::analysis::ghost::{reference_lap, Ghost};
+/// use f1_api::capture::CaptureReader;
+///
+/// let capture = CaptureReader::new(File::open("practice.f1cap")?)?;
+///
+/// if let Some(reference) = reference_lap(capture, None)? {
+///     let ghost = Ghost::new(reference);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "decode")]
+pub fn reference_lap<R: Read>(
+    capture: CaptureReader<R>,
+    vehicle_index: Option<VehicleIndex>,
+) -> Result<Option<LapTrace>, Error> {
+    let mut recorder = LapTraceRecorder::default();
+    let mut fastest: Option<LapTrace> = None;
+
+    for datagram in capture {
+        let packet = match datagram?.decode()? {
+            Some(packet) => packet,
+            None => continue,
+        };
+
+        let car = vehicle_index.unwrap_or_else(|| packet.header().player_car_index());
+
+        for trace in recorder.update(&packet) {
+            if trace.vehicle_index() != car || trace.lap_time().is_zero() {
+                continue;
+            }
+
+            if fastest
+                .as_ref()
+                .is_none_or(|fastest| trace.lap_time() < fastest.lap_time())
+            {
+                fastest = Some(trace);
+            }
+        }
+    }
+
+    Ok(fastest)
+}
+
+/// Live comparison of a car with the ghost at the current lap distance
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, PartialOrd, Default)]
+#[allow(clippy::too_many_arguments)]
+pub struct GhostPoint {
+    /// Returns the number of the current lap.
+    #[getset(get_copy = "pub")]
+    lap_number: u8,
+
+    /// Returns the lap distance of the car in meters.
+    #[getset(get_copy = "pub")]
+    distance: f32,
+
+    /// Returns the time difference in seconds, which is positive when the car is behind the ghost.
+    #[getset(get_copy = "pub")]
+    time_delta: f32,
+
+    /// Returns the speed of the car in kilometers per hour.
+    #[getset(get_copy = "pub")]
+    speed: f32,
+
+    /// Returns the ratio of the throttle input of the car.
+    #[getset(get_copy = "pub")]
+    throttle: f32,
+
+    /// Returns the ratio of the brake input of the car.
+    #[getset(get_copy = "pub")]
+    brake: f32,
+
+    /// Returns the ratio of the steering input of the car.
+    #[getset(get_copy = "pub")]
+    steering: f32,
+
+    /// Returns the sample of the ghost at the same lap distance.
+    #[getset(get = "pub")]
+    ghost: TraceSample,
+}
+
+/// Ghost that a car races against
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::ghost::Ghost;
+/// # use f1_api::analysis::trace::LapTrace;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+/// # let reference = LapTrace::default();
+///
+/// let mut ghost = Ghost::new(reference);
+///
+/// for packet in packets() {
+///     if let Some(point) = ghost.update(&packet) {
+///         println!("{:+.3}s at {:.0}m", point.time_delta(), point.distance());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ghost {
+    reference: LapTrace,
+    vehicle_index: Option<VehicleIndex>,
+    recorder: LapTraceRecorder,
+    lap: Option<(u8, f32, f32)>,
+    last_comparison: Option<LapComparison>,
+}
+
+impl Ghost {
+    /// Create a ghost for the given reference lap that follows the player's car.
+    pub fn new(reference: LapTrace) -> Self {
+        // Live laps are sampled at the same distances as the reference to compare them.
+        let recorder = match reference.step() {
+            step if step > 0.0 => LapTraceRecorder::new(step),
+            _ => LapTraceRecorder::default(),
+        };
+
+        Ghost {
+            reference,
+            vehicle_index: None,
+            recorder,
+            lap: None,
+            last_comparison: None,
+        }
+    }
+
+    /// Follow the given car instead of the player's car.
+    pub fn follow(mut self, vehicle_index: VehicleIndex) -> Self {
+        self.vehicle_index = Some(vehicle_index);
+        self
+    }
+
+    /// Returns the reference lap.
+    pub fn reference(&self) -> &LapTrace {
+        &self.reference
+    }
+
+    /// Returns the comparison of the last lap that the car completed with the reference.
+    pub fn last_comparison(&self) -> Option<&LapComparison> {
+        self.last_comparison.as_ref()
+    }
+
+    /// Update the ghost with a new packet, and return the live comparison for telemetry packets.
+    pub fn update(&mut self, packet: &Packet) -> Option<GhostPoint> {
+        let vehicle_index = self
+            .vehicle_index
+            .unwrap_or_else(|| packet.header().player_car_index());
+
+        for trace in self.recorder.update(packet) {
+            if trace.vehicle_index() == vehicle_index {
+                self.last_comparison = Some(LapComparison::between(&trace, &self.reference));
+            }
+        }
+
+        match packet {
+            Packet::Lap(packet) => {
+                self.lap = packet.car(vehicle_index).map(|lap| {
+                    (
+                        lap.current_lap_number(),
+                        lap.lap_distance(),
+                        lap.current_lap_time().as_secs_f32(),
+                    )
+                });
+
+                None
+            }
+            Packet::Telemetry(packet) => {
+                let (lap_number, distance, time) = self.lap?;
+                let telemetry = packet.car(vehicle_index)?;
+                let ghost = self.reference.at(distance)?;
+
+                Some(GhostPoint::new(
+                    lap_number,
+                    distance,
+                    time - ghost.time().as_secs_f32(),
+                    telemetry.speed() as f32,
+                    telemetry.throttle(),
+                    telemetry.brake(),
+                    telemetry.steering(),
+                    ghost,
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::ghost::Ghost;
+    use crate::analysis::trace::{LapTrace, TraceSample};
+    use crate::fixtures::{header, lap_packet, telemetry, telemetry_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+    use crate::types::Property3D;
+
+    fn reference() -> LapTrace {
+        let samples = (0..=20)
+            .map(|index| {
+                TraceSample::new(
+                    index as f32 * 10.0,
+                    Duration::from_millis(index * 200),
+                    Property3D::default(),
+                    180.0,
+                    1.0,
+                    0.0,
+                    0.0,
+                    Gear::Fifth,
+                    10000.0,
+                )
+            })
+            .collect();
+
+        LapTrace::new(0, 3, Duration::from_secs(4), 10.0, samples)
+    }
+
+    fn timed_lap(distance: f32, seconds: f32) -> Lap {
+        Lap::new(
+            Duration::default(),
+            Duration::from_secs_f32(seconds),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            distance,
+            distance,
+            0.0,
+            1,
+            2,
+            PitStatus::None,
+            Sector::First,
+            true,
+            0,
+            1,
+            DriverStatus::FlyingLap,
+            ResultStatus::Active,
+        )
+    }
+
+    fn braking(brake: f32) -> Packet {
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::default(), 0),
+            vec![telemetry(150, 0.0, 0.0, brake, Gear::Fourth, 9000)],
+        )
+    }
+
+    #[test]
+    fn update_compares_live_lap_with_ghost() {
+        let mut ghost = Ghost::new(reference());
+
+        assert_eq!(None, ghost.update(&braking(1.0)));
+        assert_eq!(
+            None,
+            ghost.update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                vec![timed_lap(100.0, 2.5)]
+            ))
+        );
+
+        let point = ghost.update(&braking(1.0)).unwrap();
+
+        assert_eq!(2, point.lap_number());
+        assert_eq!(100.0, point.distance());
+        assert!((point.time_delta() - 0.5).abs() < 0.001);
+        assert_eq!(1.0, point.brake());
+        assert_eq!(0.0, point.ghost().brake());
+        assert_eq!(180.0, point.ghost().speed());
+    }
+}
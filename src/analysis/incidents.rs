@@ -0,0 +1,293 @@
+//! Detection of contacts and crashes
+//!
+//! The F1 games do not report when two cars touch or a car hits a wall. Such incidents leave a
+//! clear trace in the telemetry, though: the car experiences a spike in its lateral or longitudinal
+//! G force, and loses speed or picks up damage at the same moment. The `IncidentDetector` watches
+//! for these patterns in the motion, telemetry, and status packets of every car, and reports an
+//! `IncidentDetected` event for stewarding tools and highlight reels.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::status::CarStatus;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// G force in the horizontal plane that is higher than anything a car reaches while driving
+const SPIKE_THRESHOLD: f32 = 7.0;
+
+/// G force of a spike that indicates a heavy impact
+const HEAVY_IMPACT: f32 = 15.0;
+
+/// Loss of speed in km/h that indicates that the car has hit something
+const SPEED_LOSS_THRESHOLD: u16 = 50;
+
+/// Increase of the damage in percent that indicates a heavy impact
+const HEAVY_DAMAGE: u8 = 30;
+
+/// Time in which the spike, the loss of speed, and the damage must occur together
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Minimum time between two incidents of the same car
+const COOLDOWN: Duration = Duration::from_secs(3);
+
+/// Severity of an incident
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum Severity {
+    /// The car lost speed without picking up damage.
+    Light,
+
+    /// The car picked up damage.
+    Moderate,
+
+    /// The impact was very hard, or the car picked up a lot of damage.
+    Heavy,
+}
+
+/// Incident of a car that has been detected
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct IncidentDetected {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    car: VehicleIndex,
+
+    /// Returns the lap in which the incident occurred.
+    #[getset(get_copy = "pub")]
+    lap: u8,
+
+    /// Returns the session time of the incident.
+    #[getset(get = "pub")]
+    session_time: Duration,
+
+    /// Returns the severity of the incident.
+    #[getset(get_copy = "pub")]
+    severity: Severity,
+}
+
+/// Recent history of a single car
+#[derive(Debug, PartialEq, Clone, Default)]
+struct CarState {
+    lap: u8,
+    spike: Option<(Duration, f32)>,
+    speeds: VecDeque<(Duration, u16)>,
+    damage: Option<u8>,
+    damage_increase: Option<(Duration, u8)>,
+    last_incident: Option<Duration>,
+}
+
+impl CarState {
+    fn check(&mut self, car: VehicleIndex, now: Duration) -> Option<IncidentDetected> {
+        let recent = |time: Duration| now.saturating_sub(time) <= WINDOW;
+
+        let (_, g_force) = self.spike.filter(|(time, _)| recent(*time))?;
+
+        if self
+            .last_incident
+            .is_some_and(|time| now.saturating_sub(time) < COOLDOWN)
+        {
+            return None;
+        }
+
+        let damage = self
+            .damage_increase
+            .filter(|(time, _)| recent(*time))
+            .map(|(_, increase)| increase);
+
+        let speed_loss = match (
+            self.speeds.iter().map(|(_, speed)| *speed).max(),
+            self.speeds.back(),
+        ) {
+            (Some(max), Some((_, current))) => max.saturating_sub(*current),
+            _ => 0,
+        };
+
+        let severity = match damage {
+            Some(increase) if increase >= HEAVY_DAMAGE => Severity::Heavy,
+            _ if g_force >= HEAVY_IMPACT => Severity::Heavy,
+            Some(_) => Severity::Moderate,
+            None if speed_loss >= SPEED_LOSS_THRESHOLD => Severity::Light,
+            None => return None,
+        };
+
+        self.last_incident = Some(now);
+        self.spike = None;
+
+        Some(IncidentDetected::new(car, self.lap, now, severity))
+    }
+}
+
+/// Detector for contacts and crashes
+///
+/// An incident is detected when a car experiences a spike in its G force, and within a second
+/// either loses a lot of speed or picks up damage. Each car reports at most one incident within
+/// three seconds, so that a single crash is not reported multiple times.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::incidents::IncidentDetector;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut detector = IncidentDetector::default();
+///
+/// for packet in packets() {
+///     for incident in detector.update(&packet) {
+///         println!("{:?} incident of car #{} on lap {}", incident.severity(), incident.car(), incident.lap());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct IncidentDetector {
+    session_uid: Option<u64>,
+    cars: Vec<CarState>,
+}
+
+impl IncidentDetector {
+    /// Update the detector with a new packet, and return the incidents that were detected.
+    pub fn update(&mut self, packet: &Packet) -> Vec<IncidentDetected> {
+        let header = packet.header();
+        let now = *header.session_time();
+
+        if self.session_uid != Some(header.session_uid()) {
+            self.session_uid = Some(header.session_uid());
+            self.cars.clear();
+        }
+
+        match packet {
+            Packet::Lap(packet) => {
+                self.ensure_cars(packet.laps().len());
+
+                for (car, lap) in self.cars.iter_mut().zip(packet.laps()) {
+                    car.lap = lap.current_lap_number();
+                }
+
+                return Vec::new();
+            }
+            Packet::Motion(packet) => {
+                self.ensure_cars(packet.cars().len());
+
+                for (car, motion) in self.cars.iter_mut().zip(packet.cars()) {
+                    let g_force = motion.g_force();
+                    let g_force = g_force.x().hypot(g_force.y());
+
+                    if g_force >= SPIKE_THRESHOLD {
+                        car.spike = Some((now, g_force));
+                    }
+                }
+            }
+            Packet::Telemetry(packet) => {
+                self.ensure_cars(packet.telemetry().len());
+
+                for (car, telemetry) in self.cars.iter_mut().zip(packet.telemetry()) {
+                    car.speeds.push_back((now, telemetry.speed()));
+
+                    while car
+                        .speeds
+                        .front()
+                        .is_some_and(|(time, _)| now.saturating_sub(*time) > WINDOW)
+                    {
+                        car.speeds.pop_front();
+                    }
+                }
+            }
+            Packet::Status(packet) => {
+                self.ensure_cars(packet.statuses().len());
+
+                for (car, status) in self.cars.iter_mut().zip(packet.statuses()) {
+                    let damage = total_damage(status);
+
+                    if let Some(previous) = car.damage.filter(|previous| damage > *previous) {
+                        car.damage_increase = Some((now, damage - previous));
+                    }
+
+                    car.damage = Some(damage);
+                }
+            }
+            _ => return Vec::new(),
+        }
+
+        self.cars
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, car)| car.check(index as VehicleIndex, now))
+            .collect()
+    }
+
+    fn ensure_cars(&mut self, count: usize) {
+        if self.cars.len() < count {
+            self.cars.resize(count, CarState::default());
+        }
+    }
+}
+
+/// Returns the highest damage of the wings, the engine, and the gear box.
+fn total_damage(status: &CarStatus) -> u8 {
+    status
+        .front_left_wing_damage()
+        .max(status.front_right_wing_damage())
+        .max(status.rear_wing_damage())
+        .max(status.engine_damage())
+        .max(status.gear_box_damage())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::incidents::{IncidentDetector, Severity};
+    use crate::fixtures::{header, motion, motion_packet, telemetry, telemetry_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+    use crate::types::{CornerProperty, Property3D};
+
+    fn impact(millis: u64, g_force: f32) -> Packet {
+        let g_force = Property3D::new(0.0, g_force, 0.0);
+
+        motion_packet(
+            header(PacketType::Motion, 1, Duration::from_millis(millis), 0),
+            vec![motion(
+                Property3D::default(),
+                Property3D::default(),
+                g_force,
+            )],
+            CornerProperty::default(),
+        )
+    }
+
+    fn speed(millis: u64, speed: u16) -> Packet {
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+            vec![telemetry(speed, 1.0, 0.0, 0.0, Gear::Seventh, 11000)],
+        )
+    }
+
+    #[test]
+    fn update_detects_spike_with_speed_loss() {
+        let mut detector = IncidentDetector::default();
+
+        assert!(detector.update(&speed(0, 280)).is_empty());
+        assert!(detector.update(&impact(100, -9.0)).is_empty());
+
+        let incidents = detector.update(&speed(200, 150));
+        assert_eq!(1, incidents.len());
+        assert_eq!(Severity::Light, incidents[0].severity());
+        assert_eq!(Duration::from_millis(200), *incidents[0].session_time());
+
+        assert!(detector.update(&impact(300, -9.0)).is_empty());
+    }
+
+    #[test]
+    fn update_ignores_braking() {
+        let mut detector = IncidentDetector::default();
+
+        detector.update(&speed(0, 300));
+        detector.update(&impact(100, -5.0));
+
+        assert!(detector.update(&speed(200, 200)).is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+//! Usage of the gears and pedals per lap
+//!
+//! A summary of the driver's inputs is one of the first things an engineer looks at after a lap.
+//! How much of the lap was spent at full throttle, how much time was lost coasting, and whether the
+//! driver overlapped throttle and brake tell a lot about the driving style and the setup of the car.
+//!
+//! The `InputAggregator` sums up the time each car spends in each gear and with each combination of
+//! pedal inputs, and reports the totals at the end of each lap.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::lap::LapPacket;
+use crate::packet::telemetry::{Gear, Telemetry, TelemetryPacket};
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Throttle input above which the car is considered to be at full throttle
+const FULL_THROTTLE: f32 = 0.98;
+
+/// Pedal input above which a pedal is considered to be pressed
+const PEDAL_THRESHOLD: f32 = 0.05;
+
+/// Summary of the inputs of a car on a lap
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Clone, PartialOrd, Default)]
+pub struct InputStatistics {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the number of the lap.
+    #[getset(get_copy = "pub")]
+    lap_number: u8,
+
+    /// Returns the time for which inputs were recorded.
+    #[getset(get = "pub")]
+    duration: Duration,
+
+    /// Returns the time spent in each gear.
+    #[getset(get = "pub")]
+    time_in_gear: BTreeMap<Gear, Duration>,
+
+    /// Returns the time spent at full throttle.
+    #[getset(get = "pub")]
+    full_throttle: Duration,
+
+    /// Returns the time spent without pressing either throttle or brake.
+    #[getset(get = "pub")]
+    coasting: Duration,
+
+    /// Returns the time spent pressing throttle and brake at the same time.
+    #[getset(get = "pub")]
+    overlap: Duration,
+}
+
+impl InputStatistics {
+    /// Returns the share of the lap spent at full throttle, between 0 and 1.
+    pub fn full_throttle_ratio(&self) -> f32 {
+        ratio(self.full_throttle, self.duration)
+    }
+
+    /// Returns the share of the lap spent coasting, between 0 and 1.
+    pub fn coasting_ratio(&self) -> f32 {
+        ratio(self.coasting, self.duration)
+    }
+
+    /// Returns the share of the lap spent pressing throttle and brake at the same time, between 0
+    /// and 1.
+    pub fn overlap_ratio(&self) -> f32 {
+        ratio(self.overlap, self.duration)
+    }
+
+    fn add(&mut self, telemetry: &Telemetry, elapsed: Duration) {
+        let throttle = telemetry.throttle() > PEDAL_THRESHOLD;
+        let brake = telemetry.brake() > PEDAL_THRESHOLD;
+
+        self.duration += elapsed;
+        *self.time_in_gear.entry(telemetry.gear()).or_default() += elapsed;
+
+        if telemetry.throttle() >= FULL_THROTTLE {
+            self.full_throttle += elapsed;
+        }
+
+        if !throttle && !brake {
+            self.coasting += elapsed;
+        }
+
+        if throttle && brake {
+            self.overlap += elapsed;
+        }
+    }
+}
+
+fn ratio(part: Duration, total: Duration) -> f32 {
+    if total.is_zero() {
+        return 0.0;
+    }
+
+    part.as_secs_f32() / total.as_secs_f32()
+}
+
+/// Inputs of a single car on its current lap
+#[derive(Debug, PartialEq, Clone, Default)]
+struct CarInputs {
+    statistics: Option<InputStatistics>,
+    last: Option<(Duration, Telemetry)>,
+}
+
+/// Aggregator for the inputs of all cars
+///
+/// The time between two telemetry packets is attributed to the inputs of the earlier packet. The
+/// statistics for a lap are reported when the car starts its next lap.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::inputs::InputAggregator;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut aggregator = InputAggregator::default();
+///
+/// for packet in packets() {
+///     for statistics in aggregator.update(&packet) {
+///         println!("{:.0}% full throttle", statistics.full_throttle_ratio() * 100.0);
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct InputAggregator {
+    session_uid: Option<u64>,
+    cars: Vec<CarInputs>,
+}
+
+impl InputAggregator {
+    /// Update the aggregator with a new packet, and return the statistics of completed laps.
+    pub fn update(&mut self, packet: &Packet) -> Vec<InputStatistics> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.cars.clear();
+        }
+
+        match packet {
+            Packet::Lap(packet) => self.update_laps(packet),
+            Packet::Telemetry(packet) => {
+                self.update_telemetry(packet);
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn update_laps(&mut self, packet: &LapPacket) -> Vec<InputStatistics> {
+        self.cars.resize(packet.laps().len(), CarInputs::default());
+
+        let mut completed = Vec::new();
+
+        for (index, (car, lap)) in self.cars.iter_mut().zip(packet.laps()).enumerate() {
+            let lap_number = lap.current_lap_number();
+
+            match &car.statistics {
+                Some(statistics) if statistics.lap_number() == lap_number => {}
+                _ => {
+                    let statistics = InputStatistics {
+                        vehicle_index: index as VehicleIndex,
+                        lap_number,
+                        ..Default::default()
+                    };
+
+                    if let Some(previous) = car.statistics.replace(statistics) {
+                        if lap_number > previous.lap_number() && !previous.duration.is_zero() {
+                            completed.push(previous);
+                        }
+                    }
+                }
+            }
+        }
+
+        completed
+    }
+
+    fn update_telemetry(&mut self, packet: &TelemetryPacket) {
+        let time = *packet.header().session_time();
+
+        for (car, telemetry) in self.cars.iter_mut().zip(packet.telemetry()) {
+            if let (Some(statistics), Some((last_time, last))) = (&mut car.statistics, &car.last) {
+                if let Some(elapsed) = time.checked_sub(*last_time) {
+                    statistics.add(last, elapsed);
+                }
+            }
+
+            car.last = Some((time, *telemetry));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::analysis::inputs::InputAggregator;
+    use crate::fixtures::{header, lap, lap_packet, telemetry, telemetry_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+
+    fn pedals(millis: u64, throttle: f32, brake: f32, gear: Gear) -> Packet {
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+            vec![telemetry(200, throttle, 0.0, brake, gear, 10000)],
+        )
+    }
+
+    #[test]
+    fn update_summarizes_lap() {
+        let mut aggregator = InputAggregator::default();
+
+        aggregator.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 0.0)],
+        ));
+        aggregator.update(&pedals(0, 1.0, 0.0, Gear::Seventh));
+        aggregator.update(&pedals(6000, 0.0, 0.0, Gear::Seventh));
+        aggregator.update(&pedals(7000, 0.3, 0.5, Gear::Third));
+        aggregator.update(&pedals(10000, 1.0, 0.0, Gear::Fourth));
+
+        let statistics = aggregator.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(2, 0.0)],
+        ));
+        assert_eq!(1, statistics.len());
+
+        let statistics = &statistics[0];
+        assert_eq!(1, statistics.lap_number());
+        assert_eq!(Duration::from_secs(10), *statistics.duration());
+        assert_eq!(
+            Duration::from_secs(7),
+            statistics.time_in_gear()[&Gear::Seventh]
+        );
+        assert_eq!(
+            Duration::from_secs(3),
+            statistics.time_in_gear()[&Gear::Third]
+        );
+        assert_approx_eq!(0.6, statistics.full_throttle_ratio());
+        assert_approx_eq!(0.1, statistics.coasting_ratio());
+        assert_approx_eq!(0.3, statistics.overlap_ratio());
+    }
+
+    #[test]
+    fn update_without_telemetry() {
+        let mut aggregator = InputAggregator::default();
+
+        aggregator.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 0.0)],
+        ));
+
+        assert!(aggregator
+            .update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                vec![lap(2, 0.0)]
+            ))
+            .is_empty());
+    }
+}
@@ -0,0 +1,331 @@
+//! Kinematics derived from motion data
+//!
+//! The motion packet publishes the position and velocity of every car, but the full set of forces
+//! only for the player's car. The `KinematicsEstimator` derives the heading, acceleration, and
+//! curvature of the path of every car from the change in velocity between two motion packets.
+//! Finite differences amplify the noise in the data, which is why the acceleration is smoothed with
+//! an exponential moving average.
+
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::motion::MotionPacket;
+use crate::packet::Packet;
+use crate::types::Property3D;
+
+/// Weight of a new sample in the moving average of the acceleration
+const DEFAULT_SMOOTHING: f32 = 0.3;
+
+/// Minimum speed in meters per second below which the curvature is not calculated
+const MINIMUM_SPEED: f32 = 1.0;
+
+/// Kinematics of a car at a point in time
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct Kinematics {
+    /// Returns the speed of the car in meters per second.
+    #[getset(get_copy = "pub")]
+    speed: f32,
+
+    /// Returns the heading of the car in radians.
+    ///
+    /// The heading is the direction of travel on the horizontal plane, measured from the Z axis
+    /// towards the X axis of the game's coordinate system.
+    #[getset(get_copy = "pub")]
+    heading: f32,
+
+    /// Returns the acceleration along the direction of travel in meters per second squared.
+    ///
+    /// The acceleration is negative when the car is slowing down.
+    #[getset(get_copy = "pub")]
+    longitudinal_acceleration: f32,
+
+    /// Returns the acceleration perpendicular to the direction of travel in meters per second
+    /// squared.
+    ///
+    /// The acceleration is positive when the car is turning towards the X axis.
+    #[getset(get_copy = "pub")]
+    lateral_acceleration: f32,
+
+    /// Returns the curvature of the path of the car in 1/meters.
+    ///
+    /// The curvature is the inverse of the radius of the turn, with the same sign as the lateral
+    /// acceleration. It is zero when the car is (almost) stationary.
+    #[getset(get_copy = "pub")]
+    curvature: f32,
+}
+
+/// Kinematics of all cars at a point in time
+#[derive(new, Debug, Getters, PartialEq, Clone, PartialOrd)]
+pub struct KinematicsFrame {
+    /// Returns the session time of the motion packet the kinematics were derived from.
+    #[getset(get = "pub")]
+    session_time: Duration,
+
+    /// Returns the kinematics of all cars, ordered by vehicle index.
+    #[getset(get = "pub")]
+    cars: Vec<Kinematics>,
+}
+
+/// State of a single car between two motion packets
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct CarState {
+    velocity: Property3D<f32>,
+    longitudinal: f32,
+    lateral: f32,
+}
+
+/// Estimator for the kinematics of all cars
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::kinematics::KinematicsEstimator;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut estimator = KinematicsEstimator::default();
+///
+/// for packet in packets() {
+///     if let Some(frame) = estimator.update(&packet) {
+///         println!("{:?}", frame.cars()[0].lateral_acceleration());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct KinematicsEstimator {
+    smoothing: f32,
+    session_uid: Option<u64>,
+    session_time: Option<Duration>,
+    cars: Vec<CarState>,
+}
+
+impl KinematicsEstimator {
+    /// Create a new estimator with the given weight of new samples in the moving average.
+    ///
+    /// The weight must be between 0 and 1. A weight of 1 disables smoothing, while smaller weights
+    /// smooth the acceleration more, but also delay its response to changes.
+    pub fn new(smoothing: f32) -> Self {
+        KinematicsEstimator {
+            smoothing: smoothing.clamp(f32::EPSILON, 1.0),
+            session_uid: None,
+            session_time: None,
+            cars: Vec::new(),
+        }
+    }
+
+    /// Update the estimator with a new packet, and return the kinematics if it was a motion packet.
+    pub fn update(&mut self, packet: &Packet) -> Option<KinematicsFrame> {
+        match packet {
+            Packet::Motion(packet) => Some(self.update_motion(packet)),
+            _ => None,
+        }
+    }
+
+    fn update_motion(&mut self, packet: &MotionPacket) -> KinematicsFrame {
+        let header = packet.header();
+        let session_time = *header.session_time();
+
+        let elapsed = match self.session_time {
+            Some(previous) if self.session_uid == Some(header.session_uid()) => session_time
+                .checked_sub(previous)
+                .filter(|elapsed| !elapsed.is_zero()),
+            _ => None,
+        };
+
+        // Without a previous sample from the same session, there is no basis for the acceleration.
+        if elapsed.is_none() {
+            self.cars.clear();
+        }
+
+        self.session_uid = Some(header.session_uid());
+        self.session_time = Some(session_time);
+        self.cars.resize(packet.cars().len(), CarState::default());
+
+        let smoothing = self.smoothing;
+        let cars = packet
+            .cars()
+            .iter()
+            .zip(self.cars.iter_mut())
+            .map(|(motion, state)| {
+                let velocity = *motion.velocity();
+                let speed = horizontal_length(&velocity);
+                let heading = velocity.x().atan2(velocity.z());
+
+                if let Some(elapsed) = elapsed {
+                    let seconds = elapsed.as_secs_f32();
+                    let acceleration_x = (velocity.x() - state.velocity.x()) / seconds;
+                    let acceleration_z = (velocity.z() - state.velocity.z()) / seconds;
+
+                    let (longitudinal, lateral) = if speed > f32::EPSILON {
+                        let (direction_x, direction_z) =
+                            (velocity.x() / speed, velocity.z() / speed);
+
+                        (
+                            acceleration_x * direction_x + acceleration_z * direction_z,
+                            acceleration_x * direction_z - acceleration_z * direction_x,
+                        )
+                    } else {
+                        (0.0, 0.0)
+                    };
+
+                    state.longitudinal += (longitudinal - state.longitudinal) * smoothing;
+                    state.lateral += (lateral - state.lateral) * smoothing;
+                }
+
+                state.velocity = velocity;
+
+                let curvature = if speed > MINIMUM_SPEED {
+                    state.lateral / speed.powi(2)
+                } else {
+                    0.0
+                };
+
+                Kinematics::new(speed, heading, state.longitudinal, state.lateral, curvature)
+            })
+            .collect();
+
+        KinematicsFrame::new(session_time, cars)
+    }
+}
+
+impl Default for KinematicsEstimator {
+    fn default() -> Self {
+        KinematicsEstimator::new(DEFAULT_SMOOTHING)
+    }
+}
+
+fn horizontal_length(vector: &Property3D<f32>) -> f32 {
+    (vector.x().powi(2) + vector.z().powi(2)).sqrt()
+}
+
+/// Stream adapter that derives the kinematics of all cars from motion packets
+///
+/// The adapter is created by `PacketStreamExt::kinematics`.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct DeriveKinematics<S> {
+    stream: S,
+    estimator: KinematicsEstimator,
+}
+
+#[cfg(feature = "net")]
+impl<S> DeriveKinematics<S> {
+    pub(crate) fn new(stream: S) -> Self {
+        DeriveKinematics {
+            stream,
+            estimator: KinematicsEstimator::default(),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for DeriveKinematics<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = KinematicsFrame;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    if let Some(frame) = this.estimator.update(&packet) {
+                        return Poll::Ready(Some(frame));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::analysis::kinematics::KinematicsEstimator;
+    use crate::fixtures::{header, motion, motion_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::Packet;
+    use crate::types::{CornerProperty, Property3D};
+
+    fn velocity(millis: u64, velocity: Property3D<f32>) -> Packet {
+        motion_packet(
+            header(PacketType::Motion, 1, Duration::from_millis(millis), 0),
+            vec![motion(
+                Property3D::default(),
+                velocity,
+                Property3D::default(),
+            )],
+            CornerProperty::default(),
+        )
+    }
+
+    #[test]
+    fn update_with_braking() {
+        let mut estimator = KinematicsEstimator::new(1.0);
+
+        estimator.update(&velocity(0, Property3D::new(0.0, 0.0, 50.0)));
+        let frame = estimator
+            .update(&velocity(100, Property3D::new(0.0, 0.0, 49.0)))
+            .unwrap();
+
+        let kinematics = frame.cars()[0];
+        assert_approx_eq!(49.0, kinematics.speed());
+        assert_approx_eq!(0.0, kinematics.heading());
+        assert_approx_eq!(-10.0, kinematics.longitudinal_acceleration(), 0.01);
+        assert_approx_eq!(0.0, kinematics.lateral_acceleration(), 0.01);
+    }
+
+    #[test]
+    fn update_with_turn() {
+        let mut estimator = KinematicsEstimator::new(1.0);
+
+        estimator.update(&velocity(0, Property3D::new(-1.0, 0.0, 20.0)));
+        let frame = estimator
+            .update(&velocity(100, Property3D::new(1.0, 0.0, 20.0)))
+            .unwrap();
+
+        let kinematics = frame.cars()[0];
+        assert_approx_eq!(20.0, kinematics.lateral_acceleration(), 0.1);
+        assert_approx_eq!(20.0 / 400.0, kinematics.curvature(), 0.001);
+    }
+
+    #[test]
+    fn update_with_smoothing() {
+        let mut estimator = KinematicsEstimator::new(0.5);
+
+        estimator.update(&velocity(0, Property3D::new(0.0, 0.0, 50.0)));
+        let frame = estimator
+            .update(&velocity(100, Property3D::new(0.0, 0.0, 49.0)))
+            .unwrap();
+
+        assert_approx_eq!(-5.0, frame.cars()[0].longitudinal_acceleration(), 0.01);
+    }
+
+    #[test]
+    fn update_heading() {
+        let mut estimator = KinematicsEstimator::default();
+
+        let frame = estimator
+            .update(&velocity(0, Property3D::new(10.0, 0.0, 0.0)))
+            .unwrap();
+
+        assert_approx_eq!(FRAC_PI_2, frame.cars()[0].heading());
+        assert_approx_eq!(0.0, frame.cars()[0].longitudinal_acceleration());
+    }
+}
@@ -0,0 +1,362 @@
+//! Excursions of cars beyond the limits of the track
+//!
+//! The telemetry packet reports the surface under each wheel of a car, which tells when a car runs
+//! onto the grass or into a gravel trap. But cars can also leave the track on tarmac run-off areas,
+//! which the game only reveals by invalidating the current lap. The `OffTrackDetector` combines
+//! both signals into excursions, and counts them for each car and lap. Using the learned map of the
+//! track, each excursion is attributed to the corner in which it occurred.
+
+use derive_new::new;
+use getset::CopyGetters;
+
+use crate::analysis::corners::{detect_corners, Corner, MINIMUM_COVERAGE};
+use crate::analysis::track_map::TrackMap;
+use crate::packet::lap::LapPacket;
+use crate::packet::telemetry::{Surface, TelemetryPacket};
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Number of wheels that must be off the track for the car to be off the track
+const MINIMUM_WHEELS: usize = 2;
+
+/// Maximum distance in meters between an excursion and the corner it is attributed to
+const CORNER_DISTANCE: f32 = 100.0;
+
+/// Excursion of a car beyond the limits of the track
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd)]
+pub struct OffTrackExcursion {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the lap in which the excursion started.
+    #[getset(get_copy = "pub")]
+    lap: u8,
+
+    /// Returns the lap distance at which the excursion started in meters.
+    #[getset(get_copy = "pub")]
+    distance: f32,
+
+    /// Returns the number of the corner in which the excursion occurred, if it is known.
+    #[getset(get_copy = "pub")]
+    corner: Option<u8>,
+
+    /// Returns the surface that the car ran onto, or `None` if it stayed on tarmac.
+    #[getset(get_copy = "pub")]
+    surface: Option<Surface>,
+
+    /// Returns whether the excursion invalidated the lap.
+    #[getset(get_copy = "pub")]
+    invalidated_lap: bool,
+}
+
+/// Current lap of a car, and the excursion it is on
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct CarState {
+    lap_number: u8,
+    distance: f32,
+    lap_valid: bool,
+    excursion: Option<OffTrackExcursion>,
+}
+
+/// Detector for off-track excursions
+///
+/// A car is off the track when at least two of its wheels are on grass, gravel, sand, mud, or
+/// water, or when the game invalidates its lap. The excursion ends when the car is back on the
+/// track, and is then returned by `update`. Like the `CornerAnalyzer`, the detector learns the map
+/// of the track to find its corners, unless they are provided with `with_corners`.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::off_track::OffTrackDetector;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut detector = OffTrackDetector::default();
+///
+/// for packet in packets() {
+///     for excursion in detector.update(&packet) {
+///         println!("Car #{} went off in corner {:?}", excursion.vehicle_index(), excursion.corner());
+///     }
+/// }
+///
+/// println!("Car #0 went off {} times on lap 3", detector.count(0, 3));
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct OffTrackDetector {
+    track_map: TrackMap,
+    corners: Vec<Corner>,
+    fixed: bool,
+    session_uid: Option<u64>,
+    cars: Vec<CarState>,
+    excursions: Vec<OffTrackExcursion>,
+}
+
+impl OffTrackDetector {
+    /// Create a detector for a track with known corners.
+    pub fn with_corners(corners: Vec<Corner>) -> Self {
+        OffTrackDetector {
+            corners,
+            fixed: true,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the corners of the track.
+    pub fn corners(&self) -> &[Corner] {
+        &self.corners
+    }
+
+    /// Returns the excursions that have ended in the current session.
+    pub fn excursions(&self) -> &[OffTrackExcursion] {
+        &self.excursions
+    }
+
+    /// Returns the number of excursions of a car in the given lap.
+    pub fn count(&self, vehicle_index: VehicleIndex, lap: u8) -> usize {
+        self.excursions
+            .iter()
+            .filter(|excursion| excursion.vehicle_index == vehicle_index && excursion.lap == lap)
+            .count()
+    }
+
+    /// Update the detector with a new packet, and return the excursions that have ended.
+    pub fn update(&mut self, packet: &Packet) -> Vec<OffTrackExcursion> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.cars.clear();
+            self.excursions.clear();
+
+            if !self.fixed {
+                self.corners.clear();
+            }
+        }
+
+        self.track_map.update(packet);
+
+        match packet {
+            Packet::Lap(packet) => {
+                self.update_laps(packet);
+                Vec::new()
+            }
+            Packet::Telemetry(packet) => self.update_telemetry(packet),
+            _ => Vec::new(),
+        }
+    }
+
+    fn update_laps(&mut self, packet: &LapPacket) {
+        if self.corners.is_empty() && self.track_map.coverage() >= MINIMUM_COVERAGE {
+            self.corners = detect_corners(&self.track_map);
+        }
+
+        if self.cars.len() < packet.laps().len() {
+            self.cars.resize(packet.laps().len(), CarState::default());
+        }
+
+        for (index, lap) in packet.laps().iter().enumerate() {
+            let car = &mut self.cars[index];
+            let invalidated =
+                car.lap_number == lap.current_lap_number() && car.lap_valid && !lap.is_valid_lap();
+
+            car.lap_number = lap.current_lap_number();
+            car.distance = lap.lap_distance();
+            car.lap_valid = lap.is_valid_lap();
+
+            if invalidated {
+                start(car, &self.corners, index).invalidated_lap = true;
+            }
+        }
+    }
+
+    fn update_telemetry(&mut self, packet: &TelemetryPacket) -> Vec<OffTrackExcursion> {
+        let mut ended = Vec::new();
+
+        for (index, telemetry) in packet.telemetry().iter().enumerate() {
+            let car = match self.cars.get_mut(index) {
+                Some(car) if car.lap_number > 0 => car,
+                _ => continue,
+            };
+
+            let surfaces = telemetry.surface_type();
+            let off_track: Vec<Surface> = [
+                surfaces.front_left(),
+                surfaces.front_right(),
+                surfaces.rear_left(),
+                surfaces.rear_right(),
+            ]
+            .iter()
+            .copied()
+            .filter(|surface| is_off_track(*surface))
+            .collect();
+
+            if off_track.len() >= MINIMUM_WHEELS {
+                let excursion = start(car, &self.corners, index);
+                excursion.surface = excursion.surface.or(off_track.first().copied());
+            } else if let Some(excursion) = car.excursion.take() {
+                ended.push(excursion);
+            }
+        }
+
+        self.excursions.extend(ended.iter().copied());
+        ended
+    }
+}
+
+/// Start an excursion at the current position of the car, unless it is already on one.
+fn start<'a>(car: &'a mut CarState, corners: &[Corner], index: usize) -> &'a mut OffTrackExcursion {
+    let (lap, distance) = (car.lap_number, car.distance);
+
+    let corner = corners
+        .iter()
+        .map(|corner| {
+            let gap = if corner.contains(distance) {
+                0.0
+            } else {
+                (corner.entry() - distance)
+                    .abs()
+                    .min((distance - corner.exit()).abs())
+            };
+
+            (gap, corner.number())
+        })
+        .filter(|(gap, _)| *gap <= CORNER_DISTANCE)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, number)| number);
+
+    car.excursion.get_or_insert(OffTrackExcursion::new(
+        index as VehicleIndex,
+        lap,
+        distance,
+        corner,
+        None,
+        false,
+    ))
+}
+
+/// Returns whether the surface is beyond the limits of the track.
+fn is_off_track(surface: Surface) -> bool {
+    matches!(
+        surface,
+        Surface::Grass | Surface::Gravel | Surface::Mud | Surface::Sand | Surface::Water
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::corners::Corner;
+    use crate::analysis::off_track::OffTrackDetector;
+    use crate::fixtures::{header, lap_packet, telemetry, telemetry_packet, with_corners};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+    use crate::packet::telemetry::{Gear, Surface};
+    use crate::packet::Packet;
+    use crate::types::CornerProperty;
+
+    fn checked_lap(lap_number: u8, distance: f32, is_valid_lap: bool) -> Lap {
+        Lap::new(
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            distance,
+            distance,
+            0.0,
+            1,
+            lap_number,
+            PitStatus::None,
+            Sector::First,
+            is_valid_lap,
+            0,
+            1,
+            DriverStatus::FlyingLap,
+            ResultStatus::Active,
+        )
+    }
+
+    fn surfaces(left: Surface, right: Surface) -> Packet {
+        let telemetry = with_corners(
+            telemetry(150, 0.5, 0.0, 0.0, Gear::Fourth, 9000),
+            CornerProperty::default(),
+            CornerProperty::default(),
+            CornerProperty::new(left, right, left, right),
+        );
+
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::default(), 0),
+            vec![telemetry],
+        )
+    }
+
+    fn corners() -> Vec<Corner> {
+        vec![
+            Corner::new(1, 100.0, 150.0, 200.0),
+            Corner::new(2, 600.0, 650.0, 700.0),
+        ]
+    }
+
+    #[test]
+    fn update_detects_excursion_onto_gravel() {
+        let mut detector = OffTrackDetector::with_corners(corners());
+
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![checked_lap(2, 220.0, true)],
+        ));
+        assert!(detector
+            .update(&surfaces(Surface::Gravel, Surface::Gravel))
+            .is_empty());
+
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![checked_lap(2, 240.0, false)],
+        ));
+        assert!(detector
+            .update(&surfaces(Surface::Grass, Surface::Gravel))
+            .is_empty());
+
+        let excursions = detector.update(&surfaces(Surface::Tarmac, Surface::Tarmac));
+
+        assert_eq!(1, excursions.len());
+        assert_eq!(2, excursions[0].lap());
+        assert_eq!(Some(1), excursions[0].corner());
+        assert_eq!(Some(Surface::Gravel), excursions[0].surface());
+        assert!(excursions[0].invalidated_lap());
+        assert_eq!(1, detector.count(0, 2));
+    }
+
+    #[test]
+    fn update_detects_track_limits_on_tarmac() {
+        let mut detector = OffTrackDetector::with_corners(corners());
+
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![checked_lap(3, 400.0, true)],
+        ));
+        detector.update(&surfaces(Surface::RumbleStrip, Surface::Tarmac));
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![checked_lap(3, 410.0, false)],
+        ));
+
+        let excursions = detector.update(&surfaces(Surface::Tarmac, Surface::Tarmac));
+
+        assert_eq!(1, excursions.len());
+        assert_eq!(None, excursions[0].corner());
+        assert_eq!(None, excursions[0].surface());
+
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![checked_lap(4, 10.0, true)],
+        ));
+        detector.update(&surfaces(Surface::Tarmac, Surface::Tarmac));
+
+        assert_eq!(0, detector.count(0, 4));
+        assert_eq!(1, detector.count(0, 3));
+    }
+}
@@ -0,0 +1,269 @@
+//! Penalties and warnings of the drivers
+//!
+//! F1 2019 does not publish an event when a penalty is handed out. The lap packet only reports the
+//! time penalties that a car has accumulated and that still need to be served. The
+//! `PenaltyTracker` watches this value: an increase is a new penalty, and a decrease means that
+//! the car served (part of) its penalties during a pit stop. Warnings for exceeding track limits are
+//! not published either, but the game invalidates the lap of the driver at the same time, which is
+//! counted as a warning instead.
+//!
+//! When the chequered flag is shown, the tracker takes a snapshot of the penalties that are still
+//! outstanding, since these are added to the race time of the drivers.
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::event::Event;
+use crate::packet::lap::LapPacket;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Time penalty that was handed out to a driver
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct Penalty {
+    /// Returns the lap in which the penalty was handed out.
+    #[getset(get_copy = "pub")]
+    lap: u8,
+
+    /// Returns the length of the penalty in seconds.
+    #[getset(get_copy = "pub")]
+    seconds: u8,
+}
+
+/// Penalties and warnings of a single driver
+#[derive(Debug, Getters, CopyGetters, PartialEq, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+pub struct DriverPenalties {
+    /// Returns the index of the driver's car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the penalties that were handed out to the driver.
+    #[getset(get = "pub")]
+    penalties: Vec<Penalty>,
+
+    /// Returns the penalties that the driver has served in seconds.
+    #[getset(get_copy = "pub")]
+    served: u32,
+
+    /// Returns the penalties that the driver still needs to serve in seconds.
+    #[getset(get_copy = "pub")]
+    outstanding: u8,
+
+    /// Returns the number of laps that were invalidated for exceeding track limits.
+    #[getset(get_copy = "pub")]
+    warnings: u32,
+
+    lap_number: u8,
+    lap_valid: bool,
+}
+
+impl DriverPenalties {
+    /// Returns the penalties that were handed out to the driver in seconds.
+    pub fn total(&self) -> u32 {
+        self.penalties
+            .iter()
+            .map(|penalty| penalty.seconds as u32)
+            .sum()
+    }
+}
+
+/// Tracker for the penalties and warnings of all drivers in a session
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::penalties::PenaltyTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = PenaltyTracker::default();
+///
+/// for packet in packets() {
+///     tracker.update(&packet);
+/// }
+///
+/// for driver in tracker.outstanding_at_flag() {
+///     println!("Car #{} gets {}s added", driver.vehicle_index(), driver.outstanding());
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PenaltyTracker {
+    session_uid: Option<u64>,
+    drivers: Vec<DriverPenalties>,
+    at_flag: Option<Vec<DriverPenalties>>,
+}
+
+impl PenaltyTracker {
+    /// Returns the penalties and warnings of each driver, indexed by the vehicle index.
+    pub fn drivers(&self) -> &[DriverPenalties] {
+        &self.drivers
+    }
+
+    /// Returns the penalties and warnings of the given driver.
+    pub fn driver(&self, vehicle_index: VehicleIndex) -> Option<&DriverPenalties> {
+        self.drivers.get(vehicle_index as usize)
+    }
+
+    /// Returns the drivers that had penalties outstanding when the chequered flag was shown.
+    ///
+    /// The list is empty until the chequered flag has been shown.
+    pub fn outstanding_at_flag(&self) -> Vec<&DriverPenalties> {
+        self.at_flag
+            .iter()
+            .flatten()
+            .filter(|driver| driver.outstanding > 0)
+            .collect()
+    }
+
+    /// Update the tracker with a new packet.
+    pub fn update(&mut self, packet: &Packet) {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.drivers.clear();
+            self.at_flag = None;
+        }
+
+        match packet {
+            Packet::Lap(packet) => self.update_laps(packet),
+            Packet::Event(packet) if *packet.event() == Event::ChequeredFlag => {
+                self.at_flag = Some(self.drivers.clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn update_laps(&mut self, packet: &LapPacket) {
+        while self.drivers.len() < packet.laps().len() {
+            self.drivers.push(DriverPenalties {
+                vehicle_index: self.drivers.len() as VehicleIndex,
+                ..Default::default()
+            });
+        }
+
+        for (driver, lap) in self.drivers.iter_mut().zip(packet.laps()) {
+            if lap.position() == 0 {
+                continue;
+            }
+
+            let penalties = lap.penalties();
+
+            if penalties > driver.outstanding {
+                driver.penalties.push(Penalty::new(
+                    lap.current_lap_number(),
+                    penalties - driver.outstanding,
+                ));
+            } else if penalties < driver.outstanding {
+                driver.served += (driver.outstanding - penalties) as u32;
+            }
+
+            if driver.lap_number == lap.current_lap_number()
+                && driver.lap_valid
+                && !lap.is_valid_lap()
+            {
+                driver.warnings += 1;
+            }
+
+            driver.outstanding = penalties;
+            driver.lap_number = lap.current_lap_number();
+            driver.lap_valid = lap.is_valid_lap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::penalties::{Penalty, PenaltyTracker};
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::event::{Event, EventPacket};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+    use crate::packet::Packet;
+
+    fn penalised_lap(lap_number: u8, is_valid_lap: bool, penalties: u8) -> Lap {
+        Lap::new(
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            0.0,
+            0.0,
+            0.0,
+            1,
+            lap_number,
+            PitStatus::None,
+            Sector::First,
+            is_valid_lap,
+            penalties,
+            1,
+            DriverStatus::OnTrack,
+            ResultStatus::Active,
+        )
+    }
+
+    #[test]
+    fn update_tracks_penalties_and_warnings() {
+        let mut tracker = PenaltyTracker::default();
+
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![penalised_lap(1, true, 0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![penalised_lap(1, false, 0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![penalised_lap(2, true, 5)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![penalised_lap(3, true, 0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![penalised_lap(4, false, 3)],
+        ));
+
+        let driver = tracker.driver(0).unwrap();
+
+        assert_eq!(
+            &vec![Penalty::new(2, 5), Penalty::new(4, 3)],
+            driver.penalties()
+        );
+        assert_eq!(8, driver.total());
+        assert_eq!(5, driver.served());
+        assert_eq!(3, driver.outstanding());
+        assert_eq!(1, driver.warnings());
+    }
+
+    #[test]
+    fn outstanding_at_flag_is_a_snapshot() {
+        let mut tracker = PenaltyTracker::default();
+
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![penalised_lap(5, true, 5)],
+        ));
+        assert!(tracker.outstanding_at_flag().is_empty());
+
+        tracker.update(&Packet::Event(EventPacket::new(
+            header(PacketType::Event, 1, Duration::default(), 0),
+            Event::ChequeredFlag,
+        )));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![penalised_lap(6, true, 10)],
+        ));
+
+        let outstanding = tracker.outstanding_at_flag();
+
+        assert_eq!(1, outstanding.len());
+        assert_eq!(5, outstanding[0].outstanding());
+    }
+}
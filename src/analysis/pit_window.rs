@@ -0,0 +1,237 @@
+//! Pit windows of the cars in a race
+//!
+//! Before a car pits, strategists want to know where it would rejoin the race: which car it would
+//! come out behind, and which car it would stay ahead of. The answer depends on the gaps between
+//! the cars and on the time that is lost by driving through the pit lane, which differs from track
+//! to track. `measure_pit_loss` estimates the loss from the pit stops that have already been made
+//! in the session, and `pit_windows` projects each car's gap to the leader after a stop onto the
+//! current order of the race.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::tracker::{LapRecord, SessionTracker};
+use crate::types::VehicleIndex;
+
+/// Position in which a car would rejoin the race after a pit stop
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct PitWindow {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the number of laps that the current tyres have been driven.
+    #[getset(get_copy = "pub")]
+    tyre_age: u8,
+
+    /// Returns the position in which the car would rejoin the race.
+    #[getset(get_copy = "pub")]
+    rejoin_position: u8,
+
+    /// Returns the car that the car would rejoin behind, if any.
+    #[getset(get_copy = "pub")]
+    ahead: Option<VehicleIndex>,
+
+    /// Returns the gap to the car ahead after rejoining.
+    #[getset(get = "pub")]
+    gap_ahead: Option<Duration>,
+
+    /// Returns the car that the car would rejoin ahead of, if any.
+    #[getset(get_copy = "pub")]
+    behind: Option<VehicleIndex>,
+
+    /// Returns the gap to the car behind after rejoining.
+    #[getset(get = "pub")]
+    gap_behind: Option<Duration>,
+}
+
+/// Estimate the time that a pit stop costs at the track of the session.
+///
+/// The loss of a pit stop is the time of the in-lap and the out-lap, compared to two laps at the
+/// median pace of the car. The estimate is the average loss of all pit stops in the session, or
+/// `None` if no car has completed a pit stop yet.
+pub fn measure_pit_loss(tracker: &SessionTracker) -> Option<Duration> {
+    let losses: Vec<Duration> = tracker
+        .cars()
+        .iter()
+        .flat_map(|car| pit_losses(car.laps()))
+        .collect();
+
+    if losses.is_empty() {
+        return None;
+    }
+
+    Some(losses.iter().sum::<Duration>() / losses.len() as u32)
+}
+
+/// Calculate where each car in the race would rejoin after a pit stop.
+///
+/// The gaps are taken from the classification of the tracker. Cars without a known gap to the
+/// leader are left out.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::pit_window::{measure_pit_loss, pit_windows};
+/// use f1_api::tracker::SessionTracker;
+/// # use std::time::Duration;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = SessionTracker::default();
+///
+/// for packet in packets() {
+///     tracker.update(&packet);
+///
+///     let pit_loss = measure_pit_loss(&tracker).unwrap_or(Duration::from_secs(22));
+///
+///     for window in pit_windows(&tracker, pit_loss) {
+///         println!("Car #{} would rejoin P{}", window.vehicle_index(), window.rejoin_position());
+///     }
+/// }
+/// ```
+pub fn pit_windows(tracker: &SessionTracker, pit_loss: Duration) -> Vec<PitWindow> {
+    let cars: Vec<(VehicleIndex, Duration, u8)> = tracker
+        .classification()
+        .iter()
+        .filter_map(|entry| {
+            let gap = match entry.position() {
+                1 => Duration::default(),
+                _ => (*entry.gap())?,
+            };
+
+            let tyre_age = tracker
+                .laps()
+                .as_ref()
+                .and_then(|laps| laps.car(entry.vehicle_index()))
+                .zip(
+                    tracker
+                        .car(entry.vehicle_index())
+                        .and_then(|car| car.current_stint()),
+                )
+                .map(|(lap, stint)| lap.current_lap_number().saturating_sub(stint.start_lap()))
+                .unwrap_or(0);
+
+            Some((entry.vehicle_index(), gap, tyre_age))
+        })
+        .collect();
+
+    windows(&cars, pit_loss)
+}
+
+/// Project the gaps of the cars after a pit stop onto the order of the race.
+///
+/// The cars are given as their index, their gap to the leader, and the age of their tyres.
+fn windows(cars: &[(VehicleIndex, Duration, u8)], pit_loss: Duration) -> Vec<PitWindow> {
+    let mut order: Vec<(VehicleIndex, Duration)> = cars
+        .iter()
+        .map(|(vehicle_index, gap, _)| (*vehicle_index, *gap))
+        .collect();
+    order.sort_by_key(|(_, gap)| *gap);
+
+    cars.iter()
+        .map(|(vehicle_index, gap, tyre_age)| {
+            let projected = *gap + pit_loss;
+            let others = order.iter().filter(|(index, _)| index != vehicle_index);
+
+            let ahead = others.clone().rfind(|(_, gap)| *gap <= projected);
+            let behind = others.clone().find(|(_, gap)| *gap > projected);
+            let rejoin_position = others.filter(|(_, gap)| *gap <= projected).count() + 1;
+
+            PitWindow::new(
+                *vehicle_index,
+                *tyre_age,
+                rejoin_position as u8,
+                ahead.map(|(index, _)| *index),
+                ahead.map(|(_, gap)| projected - *gap),
+                behind.map(|(index, _)| *index),
+                behind.map(|(_, gap)| *gap - projected),
+            )
+        })
+        .collect()
+}
+
+/// Returns the time lost by each pit stop in the given laps of a car.
+fn pit_losses(laps: &[LapRecord]) -> Vec<Duration> {
+    let mut pace: Vec<Duration> = laps
+        .iter()
+        .enumerate()
+        // The first lap starts from the grid, and the lap after a pit stop from the pit lane.
+        .filter(|(index, lap)| *index > 0 && !lap.pit_stop() && !laps[index - 1].pit_stop())
+        .map(|(_, lap)| *lap.lap_time())
+        .collect();
+
+    if pace.is_empty() {
+        return Vec::new();
+    }
+
+    pace.sort();
+    let median = pace[pace.len() / 2];
+
+    laps.windows(2)
+        .filter(|pair| pair[0].pit_stop())
+        .filter_map(|pair| (*pair[0].lap_time() + *pair[1].lap_time()).checked_sub(median * 2))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::pit_window::{pit_losses, windows};
+    use crate::tracker::LapRecord;
+
+    fn lap(lap_number: u8, seconds: u64, pit_stop: bool) -> LapRecord {
+        LapRecord::new(
+            lap_number,
+            Duration::from_secs(seconds),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            true,
+            1,
+            None,
+            pit_stop,
+            Duration::default(),
+        )
+    }
+
+    #[test]
+    fn pit_losses_compare_to_median_pace() {
+        let laps = vec![
+            lap(1, 100, false),
+            lap(2, 90, false),
+            lap(3, 91, false),
+            lap(4, 105, true),
+            lap(5, 100, false),
+            lap(6, 90, false),
+        ];
+
+        assert_eq!(vec![Duration::from_secs(25)], pit_losses(&laps));
+    }
+
+    #[test]
+    fn windows_project_gaps() {
+        let cars = vec![
+            (0, Duration::from_secs(0), 10),
+            (1, Duration::from_secs(5), 12),
+            (2, Duration::from_secs(20), 3),
+            (3, Duration::from_secs(30), 8),
+        ];
+
+        let windows = windows(&cars, Duration::from_secs(20));
+
+        let leader = windows[0];
+        assert_eq!(3, leader.rejoin_position());
+        assert_eq!(Some(2), leader.ahead());
+        assert_eq!(Some(3), leader.behind());
+        assert_eq!(Some(Duration::from_secs(10)), *leader.gap_behind());
+        assert_eq!(10, leader.tyre_age());
+
+        let last = windows[3];
+        assert_eq!(4, last.rejoin_position());
+        assert_eq!(None, last.behind());
+    }
+}
@@ -0,0 +1,236 @@
+//! Positions of the cars lap by lap
+//!
+//! The classic chart of a race plots the position of every driver at the end of each lap, which
+//! shows at a glance who gained or lost places, and where. The `PositionChart` records the position
+//! of each car when it starts the race and whenever it completes a lap, together with the laps in
+//! which it made a pit stop. The series of a car ends when it retires or is disqualified.
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::lap::{PitStatus, ResultStatus};
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Position of a car at the end of a lap
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct PositionSample {
+    /// Returns the number of the lap, or `0` for the starting grid.
+    #[getset(get_copy = "pub")]
+    lap: u8,
+
+    /// Returns the position of the car at the end of the lap.
+    #[getset(get_copy = "pub")]
+    position: u8,
+
+    /// Returns whether the car made a pit stop during the lap.
+    #[getset(get_copy = "pub")]
+    pit_stop: bool,
+}
+
+/// Positions of a single car throughout the race
+#[derive(Debug, Getters, CopyGetters, PartialEq, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+pub struct PositionSeries {
+    /// Returns the position of the car at the start and at the end of each completed lap.
+    #[getset(get = "pub")]
+    samples: Vec<PositionSample>,
+
+    /// Returns the lap in which the car retired or was disqualified, if it did.
+    #[getset(get_copy = "pub")]
+    retired_on: Option<u8>,
+
+    lap_number: u8,
+    pit_stop: bool,
+    pit_status: PitStatus,
+}
+
+impl PositionSeries {
+    /// Returns the laps in which the car made a pit stop.
+    pub fn pit_stops(&self) -> Vec<u8> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.pit_stop())
+            .map(|sample| sample.lap())
+            .collect()
+    }
+}
+
+/// Chart of the positions of all cars by lap
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::positions::PositionChart;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut chart = PositionChart::default();
+///
+/// for packet in packets() {
+///     chart.update(&packet);
+/// }
+///
+/// for (vehicle_index, series) in chart.series().iter().enumerate() {
+///     let positions: Vec<u8> = series.samples().iter().map(|sample| sample.position()).collect();
+///     println!("Car #{}: {:?}", vehicle_index, positions);
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PositionChart {
+    session_uid: Option<u64>,
+    series: Vec<PositionSeries>,
+}
+
+impl PositionChart {
+    /// Returns the series of each car, indexed by the vehicle index.
+    pub fn series(&self) -> &[PositionSeries] {
+        &self.series
+    }
+
+    /// Returns the series of the given car.
+    pub fn car(&self, vehicle_index: VehicleIndex) -> Option<&PositionSeries> {
+        self.series.get(vehicle_index as usize)
+    }
+
+    /// Update the chart with a new packet.
+    pub fn update(&mut self, packet: &Packet) {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.series.clear();
+        }
+
+        let laps = match packet {
+            Packet::Lap(packet) => packet.laps(),
+            _ => return,
+        };
+
+        if self.series.len() < laps.len() {
+            self.series.resize(laps.len(), PositionSeries::default());
+        }
+
+        for (series, lap) in self.series.iter_mut().zip(laps) {
+            if series.retired_on.is_some() || lap.position() == 0 {
+                continue;
+            }
+
+            if series.samples.is_empty() {
+                series
+                    .samples
+                    .push(PositionSample::new(0, lap.grid_position(), false));
+                series.lap_number = lap.current_lap_number();
+            }
+
+            if series.pit_status == PitStatus::None && lap.pit_status() != PitStatus::None {
+                series.pit_stop = true;
+            }
+            series.pit_status = lap.pit_status();
+
+            if lap.current_lap_number() > series.lap_number {
+                series.samples.push(PositionSample::new(
+                    series.lap_number,
+                    lap.position(),
+                    series.pit_stop,
+                ));
+                series.lap_number = lap.current_lap_number();
+                series.pit_stop = false;
+            }
+
+            if matches!(
+                lap.result_status(),
+                ResultStatus::Retired | ResultStatus::Disqualified
+            ) {
+                series.retired_on = Some(series.lap_number);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::positions::PositionChart;
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+
+    fn lap(lap_number: u8, position: u8, pit_status: PitStatus, status: ResultStatus) -> Lap {
+        Lap::new(
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            0.0,
+            0.0,
+            0.0,
+            position,
+            lap_number,
+            pit_status,
+            Sector::First,
+            true,
+            0,
+            2,
+            DriverStatus::OnTrack,
+            status,
+        )
+    }
+
+    #[test]
+    fn update_records_positions_by_lap() {
+        let mut chart = PositionChart::default();
+
+        chart.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 2, PitStatus::None, ResultStatus::Active)],
+        ));
+        chart.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(2, 1, PitStatus::None, ResultStatus::Active)],
+        ));
+        chart.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(2, 3, PitStatus::Pitting, ResultStatus::Active)],
+        ));
+        chart.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(3, 4, PitStatus::None, ResultStatus::Active)],
+        ));
+
+        let series = chart.car(0).unwrap();
+        let positions: Vec<(u8, u8)> = series
+            .samples()
+            .iter()
+            .map(|sample| (sample.lap(), sample.position()))
+            .collect();
+
+        assert_eq!(vec![(0, 2), (1, 1), (2, 4)], positions);
+        assert_eq!(vec![2], series.pit_stops());
+        assert_eq!(None, series.retired_on());
+    }
+
+    #[test]
+    fn update_truncates_series_on_retirement() {
+        let mut chart = PositionChart::default();
+
+        chart.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 2, PitStatus::None, ResultStatus::Active)],
+        ));
+        chart.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(2, 5, PitStatus::None, ResultStatus::Retired)],
+        ));
+        chart.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(3, 20, PitStatus::None, ResultStatus::Retired)],
+        ));
+
+        let series = chart.car(0).unwrap();
+
+        assert_eq!(2, series.samples().len());
+        assert_eq!(Some(2), series.retired_on());
+    }
+}
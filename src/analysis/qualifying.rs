@@ -0,0 +1,401 @@
+//! Classification of qualifying sessions
+//!
+//! F1 2019 does not send a final classification for qualifying. The order of the grid has to be
+//! derived from the laps that the drivers completed. The `QualifyingClassifier` records the best
+//! valid lap of every car in each segment of a qualifying, and ranks the cars in the same way as
+//! the knockout format: the slowest cars are eliminated after Q1 and Q2 and ranked by their time in
+//! that segment, while the remaining cars are ranked by their time in Q3. Cars that did not set a
+//! time within 107% of the fastest lap in Q1 are flagged.
+//!
+//! Short and one-shot qualifying have a single segment, in which all cars are ranked by their best
+//! lap. F1 2019 uses a new session for each segment of a knockout qualifying. The classifier
+//! therefore tracks the segments by their session type instead of the session UID, and a new
+//! classifier should be used for each qualifying.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::session::Session;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Number of cars that advance from Q1 to Q2, and from Q2 to Q3
+const DEFAULT_ADVANCING: [usize; 2] = [15, 10];
+
+/// Cars must set a time within 107% of the fastest time in Q1
+const MAXIMUM_DEFICIT: f64 = 1.07;
+
+/// Entry in the classification of a qualifying
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct QualifyingEntry {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the position of the car.
+    #[getset(get_copy = "pub")]
+    position: usize,
+
+    /// Returns the best valid lap of the car in each segment that it took part in.
+    #[getset(get = "pub")]
+    lap_times: Vec<Option<Duration>>,
+
+    /// Returns the segment in which the car was eliminated, or `None` if it reached the last one.
+    #[getset(get_copy = "pub")]
+    eliminated_in: Option<Session>,
+
+    /// Returns whether the car set a time within 107% of the fastest time in the first segment.
+    #[getset(get_copy = "pub")]
+    within_107_percent: bool,
+}
+
+impl QualifyingEntry {
+    /// Returns the lap time that decided the position of the car.
+    pub fn lap_time(&self) -> Option<Duration> {
+        self.lap_times.last().copied().flatten()
+    }
+}
+
+/// Progress of a car through the current lap
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct CarState {
+    lap_number: u8,
+    lap_valid: bool,
+    best_lap_time: Option<Duration>,
+}
+
+/// Segment of a qualifying, e.g. Q1
+#[derive(Debug, PartialEq, Clone)]
+struct Segment {
+    session: Session,
+    cars: BTreeMap<VehicleIndex, CarState>,
+}
+
+impl Segment {
+    fn best_lap_time(&self, vehicle_index: VehicleIndex) -> Option<Duration> {
+        self.cars
+            .get(&vehicle_index)
+            .and_then(|car| car.best_lap_time)
+    }
+}
+
+/// Classifier for qualifying sessions
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::qualifying::QualifyingClassifier;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut classifier = QualifyingClassifier::default();
+///
+/// for packet in packets() {
+///     classifier.update(&packet);
+/// }
+///
+/// for entry in classifier.classification() {
+///     println!("P{} car #{} {:?}", entry.position(), entry.vehicle_index(), entry.lap_time());
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct QualifyingClassifier {
+    advancing: Vec<usize>,
+    session: Session,
+    segments: Vec<Segment>,
+}
+
+impl QualifyingClassifier {
+    /// Create a classifier where the given number of cars advance from each segment to the next.
+    pub fn new(advancing: Vec<usize>) -> Self {
+        QualifyingClassifier {
+            advancing,
+            session: Session::Unknown,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Update the classifier with a new packet.
+    pub fn update(&mut self, packet: &Packet) {
+        match packet {
+            Packet::Session(session) => self.session = session.session_type(),
+            Packet::Lap(packet) => {
+                let session = self.session;
+
+                if self.segments.last().map(|segment| segment.session) != Some(session) {
+                    self.segments.push(Segment {
+                        session,
+                        cars: BTreeMap::new(),
+                    });
+                }
+
+                let segment = self.segments.last_mut().expect("segment was just pushed");
+
+                for (index, lap) in packet.laps().iter().enumerate() {
+                    if lap.position() == 0 {
+                        continue;
+                    }
+
+                    let car = segment.cars.entry(index as VehicleIndex).or_default();
+
+                    if lap.current_lap_number() > car.lap_number && car.lap_number > 0 {
+                        let lap_time = *lap.last_lap_time();
+
+                        if car.lap_valid && !lap_time.is_zero() {
+                            car.best_lap_time = Some(
+                                car.best_lap_time
+                                    .map_or(lap_time, |best| best.min(lap_time)),
+                            );
+                        }
+
+                        car.lap_valid = lap.is_valid_lap();
+                    } else if car.lap_number == 0 {
+                        car.lap_valid = lap.is_valid_lap();
+                    } else {
+                        car.lap_valid &= lap.is_valid_lap();
+                    }
+
+                    car.lap_number = lap.current_lap_number();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the classification of the qualifying, ordered by position.
+    pub fn classification(&self) -> Vec<QualifyingEntry> {
+        let first = match self.segments.first() {
+            Some(segment) => segment,
+            None => return Vec::new(),
+        };
+
+        let cutoff = first
+            .cars
+            .values()
+            .filter_map(|car| car.best_lap_time)
+            .min()
+            .map(|fastest| fastest.mul_f64(MAXIMUM_DEFICIT));
+
+        let mut candidates: Vec<VehicleIndex> = first.cars.keys().copied().collect();
+        let mut groups: Vec<(Option<Session>, Vec<VehicleIndex>)> = Vec::new();
+
+        for (index, segment) in self.segments.iter().enumerate() {
+            candidates.sort_by_key(|vehicle_index| {
+                (
+                    segment.best_lap_time(*vehicle_index).is_none(),
+                    segment.best_lap_time(*vehicle_index),
+                    *vehicle_index,
+                )
+            });
+
+            if index + 1 == self.segments.len() {
+                groups.push((None, candidates.clone()));
+                break;
+            }
+
+            let advancing = self
+                .advancing
+                .get(index)
+                .copied()
+                .unwrap_or(candidates.len())
+                .min(candidates.len());
+
+            groups.push((Some(segment.session), candidates.split_off(advancing)));
+        }
+
+        groups
+            .iter()
+            .rev()
+            .flat_map(|(eliminated_in, cars)| {
+                cars.iter()
+                    .map(move |vehicle_index| (*eliminated_in, *vehicle_index))
+            })
+            .enumerate()
+            .map(|(position, (eliminated_in, vehicle_index))| {
+                let lap_times: Vec<Option<Duration>> = self
+                    .segments
+                    .iter()
+                    .take_while(|segment| Some(segment.session) != eliminated_in)
+                    .chain(
+                        self.segments
+                            .iter()
+                            .find(|segment| Some(segment.session) == eliminated_in),
+                    )
+                    .map(|segment| segment.best_lap_time(vehicle_index))
+                    .collect();
+
+                let within_107_percent = match (first.best_lap_time(vehicle_index), cutoff) {
+                    (Some(lap_time), Some(cutoff)) => lap_time <= cutoff,
+                    _ => false,
+                };
+
+                QualifyingEntry::new(
+                    vehicle_index,
+                    position + 1,
+                    lap_times,
+                    eliminated_in,
+                    within_107_percent,
+                )
+            })
+            .collect()
+    }
+}
+
+impl Default for QualifyingClassifier {
+    fn default() -> Self {
+        QualifyingClassifier::new(DEFAULT_ADVANCING.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::qualifying::QualifyingClassifier;
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+    use crate::packet::session::{Session, SessionPacket};
+    use crate::packet::Packet;
+
+    fn session_packet(session_type: Session) -> Packet {
+        Packet::Session(SessionPacket::new(
+            header(PacketType::Session, 1, Duration::default(), 0),
+            Default::default(),
+            30,
+            20,
+            0,
+            5000,
+            session_type,
+            Default::default(),
+            Default::default(),
+            Duration::default(),
+            Duration::from_secs(1080),
+            80,
+            false,
+            false,
+            0,
+            false,
+            Vec::new(),
+            Default::default(),
+            false,
+            None,
+        ))
+    }
+
+    fn lap(lap_number: u8, last_lap_time: f32, is_valid_lap: bool) -> Lap {
+        Lap::new(
+            Duration::from_secs_f32(last_lap_time),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            0.0,
+            0.0,
+            0.0,
+            1,
+            lap_number,
+            PitStatus::None,
+            Sector::First,
+            is_valid_lap,
+            0,
+            0,
+            DriverStatus::FlyingLap,
+            ResultStatus::Active,
+        )
+    }
+
+    /// Drive a lap with each car, and complete it with the given times.
+    fn segment(classifier: &mut QualifyingClassifier, session: Session, times: &[(f32, bool)]) {
+        classifier.update(&session_packet(session));
+
+        let start = times.iter().map(|(_, valid)| lap(1, 0.0, *valid)).collect();
+        let end = times.iter().map(|(time, _)| lap(2, *time, true)).collect();
+
+        classifier.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            start,
+        ));
+        classifier.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            end,
+        ));
+    }
+
+    #[test]
+    fn classification_of_knockout_qualifying() {
+        let mut classifier = QualifyingClassifier::new(vec![3, 2]);
+
+        segment(
+            &mut classifier,
+            Session::Q1,
+            &[
+                (80.0, true),
+                (81.0, true),
+                (82.0, true),
+                (83.0, true),
+                (90.0, true),
+            ],
+        );
+        segment(
+            &mut classifier,
+            Session::Q2,
+            &[
+                (80.5, true),
+                (79.0, false),
+                (79.5, true),
+                (0.0, true),
+                (0.0, true),
+            ],
+        );
+        segment(
+            &mut classifier,
+            Session::Q3,
+            &[
+                (79.9, true),
+                (0.0, true),
+                (79.0, true),
+                (0.0, true),
+                (0.0, true),
+            ],
+        );
+
+        let classification = classifier.classification();
+        let order: Vec<u8> = classification
+            .iter()
+            .map(|entry| entry.vehicle_index())
+            .collect();
+
+        assert_eq!(vec![2, 0, 1, 3, 4], order);
+
+        assert_eq!(None, classification[0].eliminated_in());
+        assert_eq!(Some(Session::Q2), classification[2].eliminated_in());
+        assert_eq!(None, classification[2].lap_time());
+        assert_eq!(Some(Session::Q1), classification[4].eliminated_in());
+        assert_eq!(1, classification[4].lap_times().len());
+        assert_eq!(3, classification[0].lap_times().len());
+
+        assert!(classification[3].within_107_percent());
+        assert!(!classification[4].within_107_percent());
+    }
+
+    #[test]
+    fn classification_of_short_qualifying() {
+        let mut classifier = QualifyingClassifier::default();
+
+        segment(
+            &mut classifier,
+            Session::ShortQualifying,
+            &[(81.0, true), (80.0, true)],
+        );
+
+        let classification = classifier.classification();
+
+        assert_eq!(1, classification[0].vehicle_index());
+        assert_eq!(Some(Duration::from_secs(80)), classification[0].lap_time());
+        assert_eq!(2, classification[1].position());
+    }
+}
@@ -0,0 +1,224 @@
+//! Periods in which the race is neutralised
+//!
+//! When an incident needs to be cleared, race control neutralises the race with a full or a virtual
+//! safety car. The session packet reports which kind of safety car is currently deployed. The
+//! `SafetyCarTracker` turns the changes of this status into periods with a start and end time, and
+//! the laps of the leader that they affected. Post-race reports and strategy tools use these
+//! periods, for example to find cheap pit stops.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::session::SafetyCar;
+use crate::packet::Packet;
+
+/// Period in which the race was neutralised
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct NeutralisationPeriod {
+    /// Returns the kind of safety car, which is either `Full` or `Virtual`.
+    #[getset(get_copy = "pub")]
+    safety_car: SafetyCar,
+
+    /// Returns the session time at which the safety car was deployed.
+    #[getset(get = "pub")]
+    start: Duration,
+
+    /// Returns the session time at which the safety car came in, or `None` if it is still out.
+    #[getset(get = "pub")]
+    end: Option<Duration>,
+
+    /// Returns the lap of the leader when the safety car was deployed.
+    #[getset(get_copy = "pub")]
+    start_lap: u8,
+
+    /// Returns the lap of the leader when the safety car came in, or `None` if it is still out.
+    #[getset(get_copy = "pub")]
+    end_lap: Option<u8>,
+}
+
+impl NeutralisationPeriod {
+    /// Returns the duration of the period, or `None` if it has not ended yet.
+    pub fn duration(&self) -> Option<Duration> {
+        self.end.map(|end| end.saturating_sub(self.start))
+    }
+
+    /// Returns the number of laps of the leader that were affected by the period.
+    ///
+    /// Laps are counted from the lap in which the safety car was deployed to the lap in which it
+    /// came in, including both.
+    pub fn laps(&self) -> Option<u8> {
+        self.end_lap
+            .map(|end_lap| end_lap.saturating_sub(self.start_lap) + 1)
+    }
+}
+
+/// Tracker for full and virtual safety car periods
+///
+/// The tracker reports a period when it starts and again when it ends. A switch from a virtual to
+/// a full safety car ends the first period and starts a new one.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::safety_car::SafetyCarTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = SafetyCarTracker::default();
+///
+/// for packet in packets() {
+///     for period in tracker.update(&packet) {
+///         match period.duration() {
+///             Some(duration) => println!("{:?} in after {:?}", period.safety_car(), duration),
+///             None => println!("{:?} deployed", period.safety_car()),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SafetyCarTracker {
+    session_uid: Option<u64>,
+    leader_lap: u8,
+    periods: Vec<NeutralisationPeriod>,
+}
+
+impl SafetyCarTracker {
+    /// Returns all periods of the session, including a period that is still ongoing.
+    pub fn periods(&self) -> &[NeutralisationPeriod] {
+        &self.periods
+    }
+
+    /// Returns the total time for which the race was neutralised, excluding an ongoing period.
+    pub fn total_duration(&self) -> Duration {
+        self.periods
+            .iter()
+            .filter_map(|period| period.duration())
+            .sum()
+    }
+
+    /// Update the tracker with a new packet, and return the periods that started or ended.
+    pub fn update(&mut self, packet: &Packet) -> Vec<NeutralisationPeriod> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.leader_lap = 0;
+            self.periods.clear();
+        }
+
+        match packet {
+            Packet::Lap(packet) => {
+                if let Some(leader) = packet.laps().iter().find(|lap| lap.position() == 1) {
+                    self.leader_lap = leader.current_lap_number();
+                }
+
+                Vec::new()
+            }
+            Packet::Session(session) => {
+                self.update_status(session.safety_car(), *packet.header().session_time())
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn update_status(
+        &mut self,
+        safety_car: SafetyCar,
+        time: Duration,
+    ) -> Vec<NeutralisationPeriod> {
+        let current = self
+            .periods
+            .last()
+            .filter(|period| period.end().is_none())
+            .map(|period| period.safety_car());
+
+        if current == Some(safety_car) || (current.is_none() && safety_car == SafetyCar::None) {
+            return Vec::new();
+        }
+
+        let mut changes = Vec::new();
+
+        if current.is_some() {
+            let leader_lap = self.leader_lap;
+            let period = self.periods.last_mut().unwrap();
+
+            period.end = Some(time);
+            period.end_lap = Some(leader_lap);
+            changes.push(*period);
+        }
+
+        if safety_car != SafetyCar::None {
+            let period = NeutralisationPeriod::new(safety_car, time, None, self.leader_lap, None);
+
+            self.periods.push(period);
+            changes.push(period);
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::safety_car::SafetyCarTracker;
+    use crate::fixtures::header;
+    use crate::packet::header::PacketType;
+    use crate::packet::session::{Formula, SafetyCar, Session, SessionPacket, Track, Weather};
+    use crate::packet::Packet;
+
+    fn session_packet(seconds: u64, safety_car: SafetyCar) -> Packet {
+        Packet::Session(SessionPacket::new(
+            header(PacketType::Session, 1, Duration::from_secs(seconds), 0),
+            Weather::Clear,
+            30,
+            20,
+            50,
+            5000,
+            Session::Race,
+            Track::Monza,
+            Formula::ModernF1,
+            Duration::default(),
+            Duration::default(),
+            80,
+            false,
+            false,
+            0,
+            false,
+            Vec::new(),
+            safety_car,
+            false,
+            None,
+        ))
+    }
+
+    #[test]
+    fn update_with_virtual_and_full_safety_car() {
+        let mut tracker = SafetyCarTracker::default();
+
+        assert!(tracker
+            .update(&session_packet(10, SafetyCar::None))
+            .is_empty());
+
+        let changes = tracker.update(&session_packet(20, SafetyCar::Virtual));
+        assert_eq!(1, changes.len());
+        assert_eq!(None, changes[0].duration());
+
+        assert!(tracker
+            .update(&session_packet(25, SafetyCar::Virtual))
+            .is_empty());
+
+        let changes = tracker.update(&session_packet(50, SafetyCar::Full));
+        assert_eq!(2, changes.len());
+        assert_eq!(Some(Duration::from_secs(30)), changes[0].duration());
+        assert_eq!(SafetyCar::Full, changes[1].safety_car());
+
+        tracker.update(&session_packet(200, SafetyCar::None));
+
+        assert_eq!(2, tracker.periods().len());
+        assert_eq!(Duration::from_secs(180), tracker.total_duration());
+    }
+}
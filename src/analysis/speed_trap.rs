@@ -0,0 +1,276 @@
+//! Speed traps derived from telemetry
+//!
+//! Newer F1 games publish an event when a car sets the fastest speed through the speed trap. The
+//! 2018 and 2019 games do not, but the same information can be derived from the speed of each car
+//! in the telemetry packets and its lap distance in the lap packets.
+//!
+//! The speed trap can cover a zone of the track, for example the end of the longest straight, or
+//! the whole lap to measure the top speed of each car.
+
+use std::cmp::Ordering;
+
+use derive_new::new;
+use getset::CopyGetters;
+
+use crate::packet::lap::{LapPacket, PitStatus};
+use crate::packet::telemetry::TelemetryPacket;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Speed of a car through the speed trap
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct SpeedTrapEntry {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the number of the lap.
+    #[getset(get_copy = "pub")]
+    lap_number: u8,
+
+    /// Returns the highest speed of the car in the speed trap in kilometers per hour.
+    #[getset(get_copy = "pub")]
+    speed: u16,
+
+    /// Returns the lap distance at which the highest speed was reached in meters.
+    #[getset(get_copy = "pub")]
+    distance: f32,
+}
+
+/// Speed trap state of a single car
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct CarTrap {
+    lap_number: u8,
+    distance: Option<f32>,
+    current: Option<SpeedTrapEntry>,
+    best: Option<SpeedTrapEntry>,
+}
+
+/// Speed trap that measures the highest speed of each car in a zone of the track
+///
+/// A car's speed is reported when it leaves the zone, or at the end of the lap if the speed trap
+/// covers the whole lap. Cars in the pit lane are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::speed_trap::SpeedTrap;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut speed_trap = SpeedTrap::zone(4200.0, 4400.0);
+///
+/// for packet in packets() {
+///     for entry in speed_trap.update(&packet) {
+///         println!("Car {}: {} km/h", entry.vehicle_index(), entry.speed());
+///     }
+/// }
+///
+/// if let Some(fastest) = speed_trap.leaderboard().first() {
+///     println!("Fastest: car {}", fastest.vehicle_index());
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SpeedTrap {
+    zone: Option<(f32, f32)>,
+    session_uid: Option<u64>,
+    cars: Vec<CarTrap>,
+}
+
+impl SpeedTrap {
+    /// Create a speed trap that measures the top speed of each car over the whole lap.
+    pub fn top_speed() -> Self {
+        SpeedTrap::default()
+    }
+
+    /// Create a speed trap between the given lap distances in meters.
+    pub fn zone(start: f32, end: f32) -> Self {
+        SpeedTrap {
+            zone: Some((start, end)),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the best speed of each car, ordered from fastest to slowest.
+    ///
+    /// If two cars reached the same speed, the car that reached it on an earlier lap is ranked ahead.
+    pub fn leaderboard(&self) -> Vec<SpeedTrapEntry> {
+        let mut entries: Vec<SpeedTrapEntry> =
+            self.cars.iter().filter_map(|car| car.best).collect();
+
+        entries.sort_by(|a, b| match b.speed().cmp(&a.speed()) {
+            Ordering::Equal => a.lap_number().cmp(&b.lap_number()),
+            ordering => ordering,
+        });
+
+        entries
+    }
+
+    /// Returns the best speed of a car.
+    pub fn best(&self, vehicle_index: VehicleIndex) -> Option<SpeedTrapEntry> {
+        self.cars
+            .get(vehicle_index as usize)
+            .and_then(|car| car.best)
+    }
+
+    /// Update the speed trap with a new packet, and return the speeds of the cars that have passed
+    /// through it.
+    pub fn update(&mut self, packet: &Packet) -> Vec<SpeedTrapEntry> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.cars.clear();
+        }
+
+        match packet {
+            Packet::Lap(packet) => self.update_laps(packet),
+            Packet::Telemetry(packet) => {
+                self.update_telemetry(packet);
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn contains(&self, distance: f32) -> bool {
+        match self.zone {
+            Some((start, end)) => start <= distance && distance < end,
+            None => distance >= 0.0,
+        }
+    }
+
+    fn update_laps(&mut self, packet: &LapPacket) -> Vec<SpeedTrapEntry> {
+        self.cars.resize(packet.laps().len(), CarTrap::default());
+
+        let mut entries = Vec::new();
+
+        for index in 0..self.cars.len() {
+            let lap = &packet.laps()[index];
+            let distance = lap.lap_distance();
+            let in_zone = lap.pit_status() == PitStatus::None && self.contains(distance);
+            let car = &mut self.cars[index];
+
+            if car.lap_number != lap.current_lap_number() || !in_zone {
+                if let Some(entry) = car.current.take() {
+                    if car.best.is_none_or(|best| entry.speed() > best.speed()) {
+                        car.best = Some(entry);
+                    }
+
+                    entries.push(entry);
+                }
+            }
+
+            car.lap_number = lap.current_lap_number();
+            car.distance = if in_zone { Some(distance) } else { None };
+        }
+
+        entries
+    }
+
+    fn update_telemetry(&mut self, packet: &TelemetryPacket) {
+        for (index, (car, telemetry)) in self.cars.iter_mut().zip(packet.telemetry()).enumerate() {
+            let distance = match car.distance {
+                Some(distance) => distance,
+                None => continue,
+            };
+
+            if car
+                .current
+                .is_none_or(|current| telemetry.speed() > current.speed())
+            {
+                car.current = Some(SpeedTrapEntry::new(
+                    index as VehicleIndex,
+                    car.lap_number,
+                    telemetry.speed(),
+                    distance,
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::speed_trap::SpeedTrap;
+    use crate::fixtures::{header, lap, lap_packet, telemetry, telemetry_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+
+    fn speeds(speeds: &[u16]) -> Packet {
+        let cars = speeds
+            .iter()
+            .map(|speed| telemetry(*speed, 1.0, 0.0, 0.0, Gear::Eighth, 12000))
+            .collect();
+
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::default(), 0),
+            cars,
+        )
+    }
+
+    #[test]
+    fn update_reports_speed_when_leaving_zone() {
+        let mut speed_trap = SpeedTrap::zone(100.0, 200.0);
+
+        speed_trap.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 50.0), lap(1, 120.0)],
+        ));
+        speed_trap.update(&speeds(&[330, 310]));
+        speed_trap.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 150.0), lap(1, 180.0)],
+        ));
+        speed_trap.update(&speeds(&[300, 315]));
+
+        let entries = speed_trap.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 190.0), lap(1, 210.0)],
+        ));
+        assert_eq!(1, entries.len());
+        assert_eq!(1, entries[0].vehicle_index());
+        assert_eq!(315, entries[0].speed());
+        assert_eq!(180.0, entries[0].distance());
+
+        speed_trap.update(&speeds(&[320, 340]));
+        let entries = speed_trap.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 250.0), lap(1, 260.0)],
+        ));
+        assert_eq!(1, entries.len());
+        assert_eq!(320, entries[0].speed());
+
+        let leaderboard = speed_trap.leaderboard();
+        assert_eq!(2, leaderboard.len());
+        assert_eq!(0, leaderboard[0].vehicle_index());
+        assert_eq!(1, leaderboard[1].vehicle_index());
+    }
+
+    #[test]
+    fn top_speed_per_lap() {
+        let mut speed_trap = SpeedTrap::top_speed();
+
+        speed_trap.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 100.0)],
+        ));
+        speed_trap.update(&speeds(&[300]));
+        speed_trap.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 4000.0)],
+        ));
+        speed_trap.update(&speeds(&[250]));
+
+        let entries = speed_trap.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(2, 10.0)],
+        ));
+        assert_eq!(1, entries.len());
+        assert_eq!(300, entries[0].speed());
+        assert_eq!(Some(300), speed_trap.best(0).map(|entry| entry.speed()));
+    }
+}
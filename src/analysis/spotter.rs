@@ -0,0 +1,361 @@
+//! Spotter for cars alongside the player
+//!
+//! In wheel-to-wheel racing, a spotter tells the driver when another car is alongside, and when it
+//! is clear again. Drivers on a simulator cannot look over their shoulder, so audio spotters are a
+//! common tool in other sims. The `Spotter` derives these cues from the motion packet, which
+//! contains the position of every car and the direction in which the player's car is pointing.
+//!
+//! A car is reported on the left or the right when it overlaps with the player's car, or is closely
+//! behind it, and is within reach to the side. Cars that are close in space but far apart on the
+//! track, for example on a bridge or a parallel straight, are ignored by comparing their lap
+//! distances on the map of the track.
+
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use derive_new::new;
+use getset::CopyGetters;
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::analysis::track_map::TrackMap;
+use crate::packet::motion::MotionPacket;
+use crate::packet::Packet;
+use crate::types::Property3D;
+
+/// Length of a car in meters, within which a car is alongside
+const CAR_LENGTH: f32 = 5.6;
+
+/// Distance behind the player's car in meters, within which a car is closely behind
+const BEHIND: f32 = 3.0;
+
+/// Lateral distance in meters, within which a car is next to the player's car
+const LATERAL_RANGE: f32 = 4.5;
+
+/// Difference of the lap distances in meters, above which cars are on different parts of the track
+const MAX_LAP_DISTANCE: f32 = 25.0;
+
+/// Scale of the normalized direction vectors in the motion packet
+const DIRECTION_SCALE: f32 = 32767.0;
+
+/// Side of the player's car on which other cars are
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+pub enum SpotterCue {
+    /// No car is alongside.
+    #[default]
+    Clear,
+
+    /// A car is alongside on the left.
+    Left,
+
+    /// A car is alongside on the right.
+    Right,
+
+    /// Cars are alongside on both sides.
+    ThreeWide,
+}
+
+/// Change of the spotter cue
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, Eq, Hash)]
+pub struct SpotterEvent {
+    /// Returns the session time of the motion packet in which the cue has changed.
+    #[getset(get_copy = "pub")]
+    session_time: Duration,
+
+    /// Returns the new cue.
+    #[getset(get_copy = "pub")]
+    cue: SpotterCue,
+}
+
+/// Spotter for cars alongside the player's car
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::spotter::{Spotter, SpotterCue};
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut spotter = Spotter::default();
+///
+/// for packet in packets() {
+///     if let Some(event) = spotter.update(&packet) {
+///         match event.cue() {
+///             SpotterCue::Clear => println!("Clear"),
+///             SpotterCue::Left => println!("Car left"),
+///             SpotterCue::Right => println!("Car right"),
+///             SpotterCue::ThreeWide => println!("Three wide"),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Spotter {
+    map: TrackMap,
+    session_uid: Option<u64>,
+    lap_distances: Vec<f32>,
+    cue: SpotterCue,
+}
+
+impl Spotter {
+    /// Returns the current cue.
+    pub fn cue(&self) -> SpotterCue {
+        self.cue
+    }
+
+    /// Returns the map of the track that is used to compare lap distances.
+    pub fn map(&self) -> &TrackMap {
+        &self.map
+    }
+
+    /// Update the spotter with a new packet, and return an event if the cue has changed.
+    ///
+    /// The cue is only updated by motion packets.
+    pub fn update(&mut self, packet: &Packet) -> Option<SpotterEvent> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.lap_distances.clear();
+            self.cue = SpotterCue::Clear;
+        }
+
+        self.map.update(packet);
+
+        let packet = match packet {
+            Packet::Lap(packet) => {
+                self.lap_distances = packet.laps().iter().map(|lap| lap.lap_distance()).collect();
+                return None;
+            }
+            Packet::Motion(packet) => packet,
+            _ => return None,
+        };
+
+        let cue = self.spot(packet);
+        if cue == self.cue {
+            return None;
+        }
+
+        self.cue = cue;
+        Some(SpotterEvent::new(*packet.header().session_time(), cue))
+    }
+
+    fn spot(&self, packet: &MotionPacket) -> SpotterCue {
+        let player_index = packet.header().player_car_index() as usize;
+        let player = match packet.player() {
+            Some(player) => player,
+            None => return SpotterCue::Clear,
+        };
+
+        let forward = direction(player.forward_direction());
+        let right = direction(player.right_direction());
+
+        let mut left_side = false;
+        let mut right_side = false;
+
+        for (index, car) in packet.cars().iter().enumerate() {
+            if index == player_index || !self.on_same_part_of_track(player_index, index) {
+                continue;
+            }
+
+            let offset = [
+                car.position().x() - player.position().x(),
+                car.position().y() - player.position().y(),
+                car.position().z() - player.position().z(),
+            ];
+            let longitudinal = dot(offset, forward);
+            let lateral = dot(offset, right);
+
+            let overlaps = (-(CAR_LENGTH + BEHIND)..=CAR_LENGTH).contains(&longitudinal);
+            if !overlaps || lateral.abs() > LATERAL_RANGE {
+                continue;
+            }
+
+            if lateral < 0.0 {
+                left_side = true;
+            } else {
+                right_side = true;
+            }
+        }
+
+        match (left_side, right_side) {
+            (true, true) => SpotterCue::ThreeWide,
+            (true, false) => SpotterCue::Left,
+            (false, true) => SpotterCue::Right,
+            (false, false) => SpotterCue::Clear,
+        }
+    }
+
+    /// Returns whether the cars are close on the track, or whether their lap distances are unknown.
+    fn on_same_part_of_track(&self, player: usize, other: usize) -> bool {
+        let (player, other) = match (
+            self.lap_distances.get(player),
+            self.lap_distances.get(other),
+        ) {
+            (Some(player), Some(other)) => (*player, *other),
+            _ => return true,
+        };
+
+        let mut difference = (other - player).abs();
+
+        // Cars on either side of the finish line are close, although their lap distances are not.
+        let length = self.map.length();
+        if length > difference {
+            difference = difference.min(length - difference);
+        }
+
+        difference <= MAX_LAP_DISTANCE
+    }
+}
+
+impl Default for Spotter {
+    fn default() -> Self {
+        Spotter {
+            map: TrackMap::default(),
+            session_uid: None,
+            lap_distances: Vec::new(),
+            cue: SpotterCue::Clear,
+        }
+    }
+}
+
+/// Stream adapter that yields the changes of the spotter cue
+///
+/// The adapter is created by `PacketStreamExt::spotter`. The packets themselves are dropped.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct Spotting<S> {
+    stream: S,
+    spotter: Spotter,
+}
+
+#[cfg(feature = "net")]
+impl<S> Spotting<S> {
+    pub(crate) fn new(stream: S) -> Self {
+        Spotting {
+            stream,
+            spotter: Spotter::default(),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for Spotting<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = SpotterEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    if let Some(event) = this.spotter.update(&packet) {
+                        return Poll::Ready(Some(event));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+fn direction(direction: &Property3D<i16>) -> [f32; 3] {
+    [
+        f32::from(direction.x()) / DIRECTION_SCALE,
+        f32::from(direction.y()) / DIRECTION_SCALE,
+        f32::from(direction.z()) / DIRECTION_SCALE,
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::spotter::{Spotter, SpotterCue, SpotterEvent};
+    use crate::fixtures::{header, lap, lap_packet, motion_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::motion::Motion;
+    use crate::packet::Packet;
+    use crate::types::{CornerProperty, Property3D};
+
+    /// Place the cars on a straight along the Z axis, with the player's car at the origin.
+    fn motion(millis: u64, positions: &[(f32, f32)]) -> Packet {
+        let cars = positions
+            .iter()
+            .map(|(x, z)| {
+                Motion::new(
+                    Property3D::new(*x, 0.0, *z),
+                    Property3D::default(),
+                    Property3D::new(0, 0, 32767),
+                    Property3D::new(32767, 0, 0),
+                    Property3D::default(),
+                    0.0,
+                    0.0,
+                    0.0,
+                )
+            })
+            .collect::<Vec<Motion>>();
+
+        motion_packet(
+            header(PacketType::Motion, 1, Duration::from_millis(millis), 0),
+            cars,
+            CornerProperty::default(),
+        )
+    }
+
+    fn laps(distances: &[f32]) -> Packet {
+        let laps = distances.iter().map(|distance| lap(1, *distance)).collect();
+
+        lap_packet(header(PacketType::Lap, 1, Duration::default(), 0), laps)
+    }
+
+    #[test]
+    fn update_reports_cars_alongside() {
+        let mut spotter = Spotter::default();
+
+        assert_eq!(None, spotter.update(&motion(0, &[(0.0, 0.0), (3.0, 20.0)])));
+        assert_eq!(
+            Some(SpotterEvent::new(
+                Duration::from_millis(10),
+                SpotterCue::Right
+            )),
+            spotter.update(&motion(10, &[(0.0, 0.0), (3.0, -6.0)]))
+        );
+        assert_eq!(None, spotter.update(&motion(20, &[(0.0, 0.0), (3.0, 1.0)])));
+        assert_eq!(
+            SpotterCue::ThreeWide,
+            spotter
+                .update(&motion(30, &[(0.0, 0.0), (3.0, 1.0), (-2.5, 2.0)]))
+                .unwrap()
+                .cue()
+        );
+        assert_eq!(
+            SpotterCue::Clear,
+            spotter
+                .update(&motion(40, &[(0.0, 0.0), (3.0, 12.0), (-2.5, -15.0)]))
+                .unwrap()
+                .cue()
+        );
+    }
+
+    #[test]
+    fn update_ignores_cars_on_other_parts_of_the_track() {
+        let mut spotter = Spotter::default();
+
+        spotter.update(&laps(&[100.0, 2400.0]));
+
+        assert_eq!(None, spotter.update(&motion(0, &[(0.0, 0.0), (3.0, 1.0)])));
+        assert_eq!(SpotterCue::Clear, spotter.cue());
+    }
+}
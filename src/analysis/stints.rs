@@ -0,0 +1,278 @@
+//! Pace of the drivers in each stint
+//!
+//! After a race, the pace of the drivers is best compared stint by stint: how fast was each driver
+//! on a set of tyres, and how quickly did the tyres degrade? The `stint_report` summarizes every
+//! stint of every driver in the session. In-laps and out-laps are left out of the average pace, and
+//! laps in which a driver was stuck in traffic are flagged, so that a slow stint can be told apart
+//! from a stint behind a slower car.
+
+use std::time::Duration;
+
+#[cfg(feature = "json")]
+use std::io::{Error, ErrorKind};
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::analysis::dirty_air::{DirtyAirTracker, LapExposure};
+use crate::analysis::undercut::Degradation;
+use crate::packet::status::VisualTyreCompound;
+use crate::tracker::{LapRecord, SessionTracker, Stint};
+use crate::types::VehicleIndex;
+
+/// Share of a lap spent in dirty air above which the lap is affected by traffic
+const TRAFFIC_SHARE: f32 = 0.5;
+
+/// Summary of a single stint
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Clone, PartialOrd)]
+pub struct StintSummary {
+    /// Returns the tyre compound of the stint.
+    #[getset(get_copy = "pub")]
+    compound: VisualTyreCompound,
+
+    /// Returns the lap on which the stint started.
+    #[getset(get_copy = "pub")]
+    start_lap: u8,
+
+    /// Returns the last lap of the stint, or `None` if the stint was not completed.
+    #[getset(get_copy = "pub")]
+    end_lap: Option<u8>,
+
+    /// Returns the number of laps that were completed in the stint.
+    #[getset(get_copy = "pub")]
+    laps: usize,
+
+    /// Returns the average lap time of the stint, excluding in-laps and out-laps.
+    #[getset(get = "pub")]
+    average_pace: Option<Duration>,
+
+    /// Returns the change of the lap time per lap in seconds.
+    #[getset(get_copy = "pub")]
+    degradation: Option<f32>,
+
+    /// Returns the laps in which the driver spent most of the time in dirty air.
+    #[getset(get = "pub")]
+    traffic_laps: Vec<u8>,
+}
+
+/// Stints of a single driver
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Clone, PartialOrd)]
+pub struct DriverStints {
+    /// Returns the index of the driver's car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the name of the driver, if the participants are known.
+    #[getset(get = "pub")]
+    name: Option<String>,
+
+    /// Returns the stints of the driver.
+    #[getset(get = "pub")]
+    stints: Vec<StintSummary>,
+}
+
+/// Report of the stints of all drivers in a session
+#[derive(new, Debug, Getters, PartialEq, Clone, PartialOrd, Default)]
+pub struct StintReport {
+    /// Returns the stints of each driver that completed at least one lap.
+    #[getset(get = "pub")]
+    drivers: Vec<DriverStints>,
+}
+
+impl StintReport {
+    /// Serialize the report as pretty-printed JSON.
+    ///
+    /// Lap times are written as seconds, and tyre compounds by their name.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        use serde_json::json;
+
+        let report = json!({
+            "drivers": self.drivers.iter().map(|driver| json!({
+                "vehicle_index": driver.vehicle_index,
+                "name": driver.name,
+                "stints": driver.stints.iter().map(|stint| json!({
+                    "compound": format!("{:?}", stint.compound),
+                    "start_lap": stint.start_lap,
+                    "end_lap": stint.end_lap,
+                    "laps": stint.laps,
+                    "average_pace": stint.average_pace.map(|pace| pace.as_secs_f64()),
+                    "degradation": stint.degradation,
+                    "traffic_laps": stint.traffic_laps,
+                })).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+        });
+
+        serde_json::to_string_pretty(&report)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+    }
+}
+
+/// Summarize the stints of every driver in the session.
+///
+/// Traffic is measured by the `DirtyAirTracker`. Without one, no laps are flagged.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::dirty_air::DirtyAirTracker;
+/// use f1_api::analysis::stints::stint_report;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut dirty_air = DirtyAirTracker::default();
+///
+/// for packet in packets() {
+///     dirty_air.update(&packet);
+/// }
+///
+/// let report = stint_report(dirty_air.tracker(), Some(&dirty_air));
+///
+/// for driver in report.drivers() {
+///     for stint in driver.stints() {
+///         println!("Car #{} {:?}: {:?}", driver.vehicle_index(), stint.compound(), stint.average_pace());
+///     }
+/// }
+/// ```
+pub fn stint_report(tracker: &SessionTracker, dirty_air: Option<&DirtyAirTracker>) -> StintReport {
+    let drivers = tracker
+        .cars()
+        .iter()
+        .enumerate()
+        .filter(|(_, car)| !car.laps().is_empty())
+        .map(|(index, car)| {
+            let vehicle_index = index as VehicleIndex;
+            let exposure = dirty_air
+                .map(|dirty_air| dirty_air.exposure(vehicle_index))
+                .unwrap_or_default();
+
+            DriverStints::new(
+                vehicle_index,
+                tracker.name(vehicle_index).map(String::from),
+                car.stints()
+                    .iter()
+                    .map(|stint| summarize(car.laps(), stint, exposure))
+                    .collect(),
+            )
+        })
+        .collect();
+
+    StintReport::new(drivers)
+}
+
+/// Summarize a stint from the laps of a car and its exposure to dirty air.
+fn summarize(laps: &[LapRecord], stint: &Stint, exposure: &[LapExposure]) -> StintSummary {
+    let end_lap = stint.end_lap().unwrap_or(u8::MAX);
+    let stint_laps: Vec<&LapRecord> = laps
+        .iter()
+        .filter(|lap| lap.lap_number() >= stint.start_lap() && lap.lap_number() <= end_lap)
+        .collect();
+
+    // The first lap of a stint is the start of the race or an out-lap.
+    let pace: Vec<Duration> = stint_laps
+        .iter()
+        .filter(|lap| lap.lap_number() > stint.start_lap() && !lap.pit_stop())
+        .map(|lap| *lap.lap_time())
+        .collect();
+
+    let average_pace = match pace.len() {
+        0 => None,
+        count => Some(pace.iter().sum::<Duration>() / count as u32),
+    };
+
+    let traffic_laps = stint_laps
+        .iter()
+        .map(|lap| lap.lap_number())
+        .filter(|lap_number| {
+            exposure
+                .iter()
+                .any(|exposure| exposure.lap() == *lap_number && exposure.share() >= TRAFFIC_SHARE)
+        })
+        .collect();
+
+    StintSummary::new(
+        stint.compound(),
+        stint.start_lap(),
+        stint.end_lap(),
+        stint_laps.len(),
+        average_pace,
+        Degradation::of_stint(laps, stint).map(|degradation| degradation.per_lap()),
+        traffic_laps,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::dirty_air::LapExposure;
+    use crate::analysis::stints::summarize;
+    use crate::packet::status::VisualTyreCompound;
+    use crate::tracker::{LapRecord, Stint};
+
+    fn lap(lap_number: u8, seconds: f32, pit_stop: bool) -> LapRecord {
+        LapRecord::new(
+            lap_number,
+            Duration::from_secs_f32(seconds),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            true,
+            1,
+            None,
+            pit_stop,
+            Duration::default(),
+        )
+    }
+
+    #[test]
+    fn summarize_excludes_in_and_out_laps() {
+        let laps = vec![
+            lap(1, 95.0, false),
+            lap(2, 90.0, false),
+            lap(3, 91.0, false),
+            lap(4, 110.0, true),
+            lap(5, 105.0, false),
+            lap(6, 89.0, false),
+        ];
+        let exposure = vec![
+            LapExposure::new(3, Duration::from_secs(60), Duration::from_secs(91)),
+            LapExposure::new(2, Duration::from_secs(10), Duration::from_secs(90)),
+        ];
+
+        let stint = Stint::new(VisualTyreCompound::F1Soft, 1, Some(4));
+        let summary = summarize(&laps, &stint, &exposure);
+
+        assert_eq!(4, summary.laps());
+        assert_eq!(Some(Duration::from_secs_f32(90.5)), *summary.average_pace());
+        assert!((summary.degradation().unwrap() - 1.0).abs() < 0.001);
+        assert_eq!(&vec![3], summary.traffic_laps());
+
+        let stint = Stint::new(VisualTyreCompound::F1Medium, 5, None);
+        let summary = summarize(&laps, &stint, &[]);
+
+        assert_eq!(2, summary.laps());
+        assert_eq!(Some(Duration::from_secs(89)), *summary.average_pace());
+        assert_eq!(None, summary.degradation());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn report_to_json() {
+        use crate::analysis::stints::{DriverStints, StintReport};
+
+        let laps = vec![lap(1, 95.0, false), lap(2, 90.0, false)];
+        let stint = Stint::new(VisualTyreCompound::F1Hard, 1, None);
+        let report = StintReport::new(vec![DriverStints::new(
+            3,
+            Some(String::from("Driver")),
+            vec![summarize(&laps, &stint, &[])],
+        )]);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+
+        assert_eq!(3, json["drivers"][0]["vehicle_index"]);
+        assert_eq!("F1Hard", json["drivers"][0]["stints"][0]["compound"]);
+        assert_eq!(90.0, json["drivers"][0]["stints"][0]["average_pace"]);
+    }
+}
@@ -0,0 +1,414 @@
+//! Traces of laps indexed by distance
+//!
+//! Telemetry is published at a fixed rate in time, but laps are best compared by distance. At the
+//! same point on the track, two laps can be many seconds apart, while the car is at the same corner.
+//!
+//! The `LapTraceRecorder` records the telemetry and position of each car during a lap, and resamples
+//! the lap onto fixed steps of the lap distance once it has been completed. The resulting
+//! `LapTrace` is the basis for overlay charts and lap comparisons.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::lap::LapPacket;
+use crate::packet::motion::MotionPacket;
+use crate::packet::telemetry::{Gear, TelemetryPacket};
+use crate::packet::Packet;
+use crate::types::{Property3D, VehicleIndex};
+
+/// Default distance between two samples of a trace in meters
+const DEFAULT_STEP: f32 = 5.0;
+
+/// Sample of a lap at a lap distance
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, PartialOrd, Default)]
+#[allow(clippy::too_many_arguments)]
+pub struct TraceSample {
+    /// Returns the lap distance of the sample in meters.
+    #[getset(get_copy = "pub")]
+    distance: f32,
+
+    /// Returns the time since the start of the lap.
+    #[getset(get = "pub")]
+    time: Duration,
+
+    /// Returns the position of the car in 3D space.
+    #[getset(get = "pub")]
+    position: Property3D<f32>,
+
+    /// Returns the speed of the car in kilometers per hour.
+    #[getset(get_copy = "pub")]
+    speed: f32,
+
+    /// Returns the ratio of the throttle input.
+    #[getset(get_copy = "pub")]
+    throttle: f32,
+
+    /// Returns the ratio of the brake input.
+    #[getset(get_copy = "pub")]
+    brake: f32,
+
+    /// Returns the ratio of the steering input.
+    #[getset(get_copy = "pub")]
+    steering: f32,
+
+    /// Returns the gear of the car.
+    #[getset(get_copy = "pub")]
+    gear: Gear,
+
+    /// Returns the engine RPM.
+    #[getset(get_copy = "pub")]
+    engine_rpm: f32,
+}
+
+impl TraceSample {
+    /// Interpolate between two samples at the given lap distance.
+    fn interpolate(&self, other: &TraceSample, distance: f32) -> TraceSample {
+        let span = other.distance - self.distance;
+        let ratio = if span > 0.0 {
+            ((distance - self.distance) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let lerp = |from: f32, to: f32| from + (to - from) * ratio;
+
+        TraceSample::new(
+            distance,
+            self.time + other.time.saturating_sub(self.time).mul_f32(ratio),
+            Property3D::new(
+                lerp(self.position.x(), other.position.x()),
+                lerp(self.position.y(), other.position.y()),
+                lerp(self.position.z(), other.position.z()),
+            ),
+            lerp(self.speed, other.speed),
+            lerp(self.throttle, other.throttle),
+            lerp(self.brake, other.brake),
+            lerp(self.steering, other.steering),
+            if ratio < 0.5 { self.gear } else { other.gear },
+            lerp(self.engine_rpm, other.engine_rpm),
+        )
+    }
+}
+
+/// Lap of a car, sampled at fixed steps of the lap distance
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Clone, PartialOrd, Default)]
+pub struct LapTrace {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the number of the lap.
+    #[getset(get_copy = "pub")]
+    lap_number: u8,
+
+    /// Returns the time of the lap as reported by the game.
+    #[getset(get = "pub")]
+    lap_time: Duration,
+
+    /// Returns the distance between two samples in meters.
+    #[getset(get_copy = "pub")]
+    step: f32,
+
+    /// Returns the samples of the lap, starting at a lap distance of 0.
+    #[getset(get = "pub")]
+    samples: Vec<TraceSample>,
+}
+
+impl LapTrace {
+    /// Returns the sample at the given lap distance, interpolated between the closest samples.
+    pub fn at(&self, distance: f32) -> Option<TraceSample> {
+        if distance < 0.0 || self.step <= 0.0 {
+            return None;
+        }
+
+        let index = (distance / self.step) as usize;
+        let before = self.samples.get(index)?;
+
+        match self.samples.get(index + 1) {
+            Some(after) => Some(before.interpolate(after, distance)),
+            None if distance <= before.distance => Some(*before),
+            None => None,
+        }
+    }
+
+    /// Resample raw samples onto fixed steps of the lap distance.
+    fn resample(
+        vehicle_index: VehicleIndex,
+        lap_number: u8,
+        lap_time: Duration,
+        step: f32,
+        raw: &[TraceSample],
+    ) -> Self {
+        let mut samples = Vec::new();
+        let mut index = 0;
+        let mut distance = 0.0;
+
+        if let Some(last) = raw.last() {
+            while distance <= last.distance() {
+                while index + 1 < raw.len() && raw[index + 1].distance() < distance {
+                    index += 1;
+                }
+
+                let before = &raw[index];
+                let after = raw.get(index + 1).unwrap_or(before);
+
+                samples.push(before.interpolate(after, distance));
+                distance = samples.len() as f32 * step;
+            }
+        }
+
+        LapTrace::new(vehicle_index, lap_number, lap_time, step, samples)
+    }
+}
+
+/// Raw samples of a car on its current lap
+#[derive(Debug, PartialEq, Clone, Default)]
+struct CarTrace {
+    lap_number: u8,
+    distance: f32,
+    time: Duration,
+    position: Property3D<f32>,
+    raw: Vec<TraceSample>,
+}
+
+/// Recorder for the laps of all cars
+///
+/// The recorder combines the lap distance from the lap packet, the position from the motion packet,
+/// and the inputs from the telemetry packet into raw samples, one per telemetry packet. When a car
+/// starts a new lap, its previous lap is resampled and returned. Laps that were joined after the
+/// start, e.g. when the recorder was started in the middle of a session, are dropped.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::trace::LapTraceRecorder;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut recorder = LapTraceRecorder::new(1.0);
+///
+/// for packet in packets() {
+///     for trace in recorder.update(&packet) {
+///         println!("Lap {} with {} samples", trace.lap_number(), trace.samples().len());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct LapTraceRecorder {
+    step: f32,
+    session_uid: Option<u64>,
+    cars: Vec<CarTrace>,
+}
+
+impl LapTraceRecorder {
+    /// Create a new recorder that samples laps every `step` meters.
+    pub fn new(step: f32) -> Self {
+        LapTraceRecorder {
+            step: step.max(f32::EPSILON),
+            session_uid: None,
+            cars: Vec::new(),
+        }
+    }
+
+    /// Update the recorder with a new packet, and return the traces of completed laps.
+    pub fn update(&mut self, packet: &Packet) -> Vec<LapTrace> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.cars.clear();
+        }
+
+        match packet {
+            Packet::Lap(packet) => return self.update_laps(packet),
+            Packet::Motion(packet) => self.update_motion(packet),
+            Packet::Telemetry(packet) => self.update_telemetry(packet),
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    fn update_laps(&mut self, packet: &LapPacket) -> Vec<LapTrace> {
+        let step = self.step;
+        let mut traces = Vec::new();
+
+        if self.cars.len() < packet.laps().len() {
+            self.cars.resize(packet.laps().len(), CarTrace::default());
+        }
+
+        for (index, (car, lap)) in self.cars.iter_mut().zip(packet.laps()).enumerate() {
+            let lap_number = lap.current_lap_number();
+
+            if lap_number != car.lap_number {
+                let complete = car
+                    .raw
+                    .first()
+                    .is_some_and(|sample| sample.distance() <= step);
+
+                if lap_number == car.lap_number + 1 && complete {
+                    traces.push(LapTrace::resample(
+                        index as VehicleIndex,
+                        car.lap_number,
+                        *lap.last_lap_time(),
+                        step,
+                        &car.raw,
+                    ));
+                }
+
+                car.lap_number = lap_number;
+                car.raw.clear();
+            }
+
+            car.distance = lap.lap_distance();
+            car.time = *lap.current_lap_time();
+        }
+
+        traces
+    }
+
+    fn update_motion(&mut self, packet: &MotionPacket) {
+        for (car, motion) in self.cars.iter_mut().zip(packet.cars()) {
+            car.position = *motion.position();
+        }
+    }
+
+    fn update_telemetry(&mut self, packet: &TelemetryPacket) {
+        for (car, telemetry) in self.cars.iter_mut().zip(packet.telemetry()) {
+            // Before the car crosses the line for the first time, the lap distance is negative.
+            if car.distance < 0.0 || car.lap_number == 0 {
+                continue;
+            }
+
+            if car
+                .raw
+                .last()
+                .is_some_and(|last| car.distance < last.distance())
+            {
+                continue;
+            }
+
+            car.raw.push(TraceSample::new(
+                car.distance,
+                car.time,
+                car.position,
+                telemetry.speed() as f32,
+                telemetry.throttle(),
+                telemetry.brake(),
+                telemetry.steering(),
+                telemetry.gear(),
+                telemetry.engine_rpm() as f32,
+            ));
+        }
+    }
+}
+
+impl Default for LapTraceRecorder {
+    fn default() -> Self {
+        LapTraceRecorder::new(DEFAULT_STEP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::analysis::trace::LapTraceRecorder;
+    use crate::fixtures::{header, lap_packet, telemetry, telemetry_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+
+    fn timed_lap(lap_number: u8, distance: f32, millis: u64) -> Lap {
+        Lap::new(
+            Duration::from_secs(80),
+            Duration::from_millis(millis),
+            Duration::default(),
+            Duration::default(),
+            Duration::default(),
+            distance,
+            distance,
+            0.0,
+            1,
+            lap_number,
+            PitStatus::None,
+            Sector::First,
+            true,
+            0,
+            1,
+            DriverStatus::FlyingLap,
+            ResultStatus::Active,
+        )
+    }
+
+    fn at_speed(speed: u16) -> Packet {
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::default(), 0),
+            vec![telemetry(speed, 1.0, 0.0, 0.0, Gear::Fifth, 10000)],
+        )
+    }
+
+    fn drive(recorder: &mut LapTraceRecorder, lap_number: u8, samples: &[(f32, u64, u16)]) {
+        for (distance, millis, speed) in samples {
+            assert!(recorder
+                .update(&lap_packet(
+                    header(PacketType::Lap, 1, Duration::default(), 0),
+                    vec![timed_lap(lap_number, *distance, *millis)]
+                ))
+                .is_empty());
+            recorder.update(&at_speed(*speed));
+        }
+    }
+
+    #[test]
+    fn update_resamples_lap() {
+        let mut recorder = LapTraceRecorder::new(10.0);
+
+        drive(
+            &mut recorder,
+            1,
+            &[(0.0, 0, 100), (15.0, 1000, 200), (25.0, 1500, 300)],
+        );
+
+        let traces = recorder.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![timed_lap(2, 1.0, 0)],
+        ));
+        assert_eq!(1, traces.len());
+
+        let trace = &traces[0];
+        assert_eq!(1, trace.lap_number());
+        assert_eq!(Duration::from_secs(80), *trace.lap_time());
+
+        let distances: Vec<f32> = trace.samples().iter().map(|s| s.distance()).collect();
+        assert_eq!(vec![0.0, 10.0, 20.0], distances);
+
+        let sample = trace.samples()[1];
+        assert_approx_eq!(166.66, sample.speed(), 0.01);
+        assert_eq!(666, sample.time().as_millis());
+
+        let sample = trace.at(15.0).unwrap();
+        assert_approx_eq!(208.33, sample.speed(), 0.01);
+        assert!(trace.at(20.0).is_some());
+        assert_eq!(None, trace.at(22.5));
+    }
+
+    #[test]
+    fn update_drops_partial_lap() {
+        let mut recorder = LapTraceRecorder::new(10.0);
+
+        drive(&mut recorder, 1, &[(500.0, 0, 100), (600.0, 1000, 200)]);
+
+        assert!(recorder
+            .update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                vec![timed_lap(2, 1.0, 0)]
+            ))
+            .is_empty());
+    }
+}
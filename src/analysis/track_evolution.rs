@@ -0,0 +1,333 @@
+//! Evolution of the grip of the track during a session
+//!
+//! As more rubber is laid down on the racing line, a track gets faster throughout a session. Lap
+//! times improve for everyone, which makes it hard to tell whether a driver found time or whether
+//! the track did. The `TrackEvolution` divides the session into windows of time, and compares the
+//! sector times of the whole field from window to window. Each driver contributes their best time
+//! in each sector per window, and the median across all drivers makes the indicator robust against
+//! single drivers finding time.
+//!
+//! Cars get faster as they burn fuel as well. When the fuel load of a car is known from the status
+//! packets, its sector times are corrected to an empty tank before they are compared.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::lap::Lap;
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+/// Default length of a window
+const DEFAULT_WINDOW: Duration = Duration::from_secs(300);
+
+/// Time that a kilogram of fuel costs per lap in seconds
+const FUEL_EFFECT: f32 = 0.03;
+
+/// Typical sector times of the field in a window of the session
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct EvolutionSample {
+    /// Returns the session time at which the window starts.
+    #[getset(get = "pub")]
+    session_time: Duration,
+
+    /// Returns the median of the drivers' best time in each sector.
+    #[getset(get = "pub")]
+    sector_times: [Option<Duration>; 3],
+
+    /// Returns the number of drivers that completed a valid lap in the window.
+    #[getset(get_copy = "pub")]
+    drivers: usize,
+}
+
+impl EvolutionSample {
+    /// Returns the sum of the sector times, if all three are known.
+    pub fn lap_time(&self) -> Option<Duration> {
+        self.sector_times.iter().copied().sum()
+    }
+}
+
+/// Lap that has been completed by a car
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct CompletedLap {
+    session_time: Duration,
+    vehicle_index: VehicleIndex,
+    sector_times: [Duration; 3],
+}
+
+/// Progress of a car through the current lap
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct CarState {
+    lap: Option<Lap>,
+    lap_valid: bool,
+    fuel: Option<f32>,
+}
+
+/// Indicator for the evolution of the track during a session
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::track_evolution::TrackEvolution;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut evolution = TrackEvolution::default();
+///
+/// for packet in packets() {
+///     evolution.update(&packet);
+/// }
+///
+/// if let Some(improvement) = evolution.improvement() {
+///     println!("The track has become {:.2}% faster", improvement);
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct TrackEvolution {
+    window: Duration,
+    session_uid: Option<u64>,
+    cars: Vec<CarState>,
+    laps: Vec<CompletedLap>,
+}
+
+impl TrackEvolution {
+    /// Create an indicator that compares the sector times in windows of the given length.
+    pub fn new(window: Duration) -> Self {
+        TrackEvolution {
+            window: window.max(Duration::from_secs(1)),
+            session_uid: None,
+            cars: Vec::new(),
+            laps: Vec::new(),
+        }
+    }
+
+    /// Returns the typical sector times of the field in each window that contains valid laps.
+    pub fn samples(&self) -> Vec<EvolutionSample> {
+        let mut windows: Vec<u64> = self
+            .laps
+            .iter()
+            .map(|lap| self.window_of(lap.session_time))
+            .collect();
+        windows.sort_unstable();
+        windows.dedup();
+
+        windows
+            .into_iter()
+            .map(|window| self.sample(window))
+            .collect()
+    }
+
+    /// Returns how much faster the track is in the latest window compared to the first, in percent.
+    pub fn improvement(&self) -> Option<f32> {
+        let samples = self.samples();
+        let first = samples.first()?.lap_time()?.as_secs_f32();
+        let last = samples.last()?.lap_time()?.as_secs_f32();
+
+        Some((first - last) / first * 100.0)
+    }
+
+    /// Update the indicator with a new packet.
+    pub fn update(&mut self, packet: &Packet) {
+        let header = packet.header();
+
+        if self.session_uid != Some(header.session_uid()) {
+            self.session_uid = Some(header.session_uid());
+            self.cars.clear();
+            self.laps.clear();
+        }
+
+        match packet {
+            Packet::Status(packet) => {
+                self.ensure_cars(packet.statuses().len());
+
+                for (car, status) in self.cars.iter_mut().zip(packet.statuses()) {
+                    car.fuel = Some(status.fuel_remaining());
+                }
+            }
+            Packet::Lap(packet) => {
+                self.ensure_cars(packet.laps().len());
+
+                for (index, (car, lap)) in self.cars.iter_mut().zip(packet.laps()).enumerate() {
+                    let previous = match car.lap.replace(*lap) {
+                        Some(previous) => previous,
+                        None => {
+                            car.lap_valid = lap.is_valid_lap();
+                            continue;
+                        }
+                    };
+
+                    if lap.current_lap_number() <= previous.current_lap_number() {
+                        car.lap_valid &= lap.is_valid_lap();
+                        continue;
+                    }
+
+                    let lap_time = *lap.last_lap_time();
+                    let sector1 = *previous.sector1_time();
+                    let sector2 = *previous.sector2_time();
+
+                    if car.lap_valid && previous.is_valid_lap() && !lap_time.is_zero() {
+                        if let Some(sector3) = lap_time.checked_sub(sector1 + sector2) {
+                            self.laps.push(CompletedLap {
+                                session_time: *header.session_time(),
+                                vehicle_index: index as VehicleIndex,
+                                sector_times: correct_for_fuel(
+                                    [sector1, sector2, sector3],
+                                    car.fuel,
+                                ),
+                            });
+                        }
+                    }
+
+                    car.lap_valid = lap.is_valid_lap();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ensure_cars(&mut self, count: usize) {
+        if self.cars.len() < count {
+            self.cars.resize(count, CarState::default());
+        }
+    }
+
+    fn window_of(&self, session_time: Duration) -> u64 {
+        (session_time.as_millis() / self.window.as_millis()) as u64
+    }
+
+    fn sample(&self, window: u64) -> EvolutionSample {
+        let mut bests: Vec<(VehicleIndex, [Duration; 3])> = Vec::new();
+
+        for lap in self
+            .laps
+            .iter()
+            .filter(|lap| self.window_of(lap.session_time) == window)
+        {
+            match bests
+                .iter_mut()
+                .find(|(vehicle_index, _)| *vehicle_index == lap.vehicle_index)
+            {
+                Some((_, best)) => {
+                    for (best, sector_time) in best.iter_mut().zip(lap.sector_times.iter()) {
+                        *best = (*best).min(*sector_time);
+                    }
+                }
+                None => bests.push((lap.vehicle_index, lap.sector_times)),
+            }
+        }
+
+        let mut sector_times = [None; 3];
+
+        for (sector, median) in sector_times.iter_mut().enumerate() {
+            let mut times: Vec<Duration> = bests.iter().map(|(_, best)| best[sector]).collect();
+            times.sort_unstable();
+            *median = times.get(times.len() / 2).copied();
+        }
+
+        EvolutionSample::new(self.window * window as u32, sector_times, bests.len())
+    }
+}
+
+impl Default for TrackEvolution {
+    fn default() -> Self {
+        TrackEvolution::new(DEFAULT_WINDOW)
+    }
+}
+
+/// Remove the time that the fuel load of the car cost from the sector times.
+///
+/// The time is split between the sectors by their share of the lap.
+fn correct_for_fuel(sector_times: [Duration; 3], fuel: Option<f32>) -> [Duration; 3] {
+    let fuel = match fuel {
+        Some(fuel) if fuel > 0.0 => fuel,
+        _ => return sector_times,
+    };
+
+    let lap_time: f32 = sector_times.iter().map(Duration::as_secs_f32).sum();
+    let penalty = FUEL_EFFECT * fuel;
+
+    sector_times.map(|sector_time| {
+        let share = sector_time.as_secs_f32() / lap_time;
+        sector_time.saturating_sub(Duration::from_secs_f32(penalty * share))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::track_evolution::{correct_for_fuel, TrackEvolution};
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+
+    /// Returns a lap with the sector times of the current lap set to a third of the next lap time.
+    fn lap(lap_number: u8, last_lap_time: f32, next_lap_time: f32) -> Lap {
+        Lap::new(
+            Duration::from_secs_f32(last_lap_time),
+            Duration::default(),
+            Duration::default(),
+            Duration::from_secs_f32(next_lap_time / 3.0),
+            Duration::from_secs_f32(next_lap_time / 3.0),
+            0.0,
+            0.0,
+            0.0,
+            1,
+            lap_number,
+            PitStatus::None,
+            Sector::Third,
+            true,
+            0,
+            1,
+            DriverStatus::FlyingLap,
+            ResultStatus::Active,
+        )
+    }
+
+    #[test]
+    fn improvement_compares_first_and_last_window() {
+        let mut evolution = TrackEvolution::default();
+
+        evolution.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            vec![lap(1, 0.0, 90.0), lap(1, 0.0, 93.0)],
+        ));
+        evolution.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs(90), 0),
+            vec![lap(2, 90.0, 90.0), lap(2, 93.0, 93.0)],
+        ));
+        evolution.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs(900), 0),
+            vec![lap(3, 90.0, 88.2), lap(3, 93.0, 90.0)],
+        ));
+        evolution.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs(990), 0),
+            vec![lap(4, 88.2, 0.0), lap(4, 90.0, 0.0)],
+        ));
+
+        let samples = evolution.samples();
+
+        assert_eq!(2, samples.len());
+        assert_eq!(2, samples[0].drivers());
+        assert_eq!(Duration::from_secs(900), *samples[1].session_time());
+
+        let improvement = evolution.improvement().unwrap();
+        assert!((improvement - 3.23).abs() < 0.01);
+    }
+
+    #[test]
+    fn correct_for_fuel_splits_penalty_by_sector() {
+        let sector_times = [
+            Duration::from_secs(20),
+            Duration::from_secs(40),
+            Duration::from_secs(40),
+        ];
+
+        let corrected = correct_for_fuel(sector_times, Some(100.0));
+
+        assert!((corrected[0].as_secs_f32() - 19.4).abs() < 0.001);
+        assert!((corrected[1].as_secs_f32() - 38.8).abs() < 0.001);
+        assert_eq!(sector_times, correct_for_fuel(sector_times, None));
+    }
+}
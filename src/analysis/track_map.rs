@@ -0,0 +1,313 @@
+//! Map of a track learned from the cars driving on it
+//!
+//! The F1 games do not publish the layout of the tracks. But every car reports its position in 3D
+//! space in the motion packet, and the distance it has travelled in the current lap in the lap
+//! packet. Combining the two, a map of the track can be learned that relates the lap distance to a
+//! position on the track.
+//!
+//! The map is divided into bins of a fixed length. Each bin averages the position of the cars, and
+//! the lateral G force and steering input with which the cars drive through it. The latter are used
+//! to find the corners of the track.
+//!
+//! The boundaries between the sectors of the track are learned from the lap distance at which the
+//! cars enter the next sector.
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::lap::{LapPacket, PitStatus, Sector};
+use crate::packet::Packet;
+use crate::types::Property3D;
+
+/// Default length of a bin in meters
+const DEFAULT_RESOLUTION: f32 = 5.0;
+
+/// Point on the track
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct TrackPoint {
+    /// Returns the lap distance at the start of the point in meters.
+    #[getset(get_copy = "pub")]
+    distance: f32,
+
+    /// Returns the average position of the cars at this point.
+    #[getset(get = "pub")]
+    position: Property3D<f32>,
+
+    /// Returns the average lateral G force of the cars at this point.
+    #[getset(get_copy = "pub")]
+    lateral_g: f32,
+
+    /// Returns the average steering input of the cars at this point.
+    #[getset(get_copy = "pub")]
+    steering: f32,
+}
+
+/// Sums of the samples in a bin
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct Bin {
+    position: (f32, f32, f32),
+    lateral_g: f32,
+    motion_samples: u32,
+    steering: f32,
+    steering_samples: u32,
+}
+
+/// Map of a track that is learned from packets
+///
+/// The map learns from the motion, lap, and telemetry packets of all cars that are on track. Cars
+/// in the pit lane are ignored, since the pit lane is not part of the racing line. The map is reset
+/// when a new session starts.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::track_map::TrackMap;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut map = TrackMap::default();
+///
+/// for packet in packets() {
+///     map.update(&packet);
+/// }
+///
+/// if let Some(point) = map.point(1200.0) {
+///     println!("{:?}", point.position());
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct TrackMap {
+    resolution: f32,
+    session_uid: Option<u64>,
+    track_length: Option<u16>,
+    distances: Vec<Option<f32>>,
+    sectors: Vec<Sector>,
+    sector_boundaries: [Option<f32>; 2],
+    bins: Vec<Bin>,
+}
+
+impl TrackMap {
+    /// Create a new map with bins of the given length in meters.
+    pub fn new(resolution: f32) -> Self {
+        TrackMap {
+            resolution: resolution.max(f32::EPSILON),
+            session_uid: None,
+            track_length: None,
+            distances: Vec::new(),
+            sectors: Vec::new(),
+            sector_boundaries: [None; 2],
+            bins: Vec::new(),
+        }
+    }
+
+    /// Update the map with a new packet.
+    pub fn update(&mut self, packet: &Packet) {
+        let header = packet.header();
+
+        if self.session_uid != Some(header.session_uid()) {
+            *self = TrackMap::new(self.resolution);
+            self.session_uid = Some(header.session_uid());
+        }
+
+        match packet {
+            Packet::Session(packet) => self.track_length = Some(packet.track_length()),
+            Packet::Lap(packet) => self.update_distances(packet),
+            Packet::Motion(packet) => {
+                for (index, motion) in packet.cars().iter().enumerate() {
+                    if let Some(distance) = self.distance(index) {
+                        self.add_motion(distance, motion.position(), motion.g_force().x());
+                    }
+                }
+            }
+            Packet::Telemetry(packet) => {
+                for (index, telemetry) in packet.telemetry().iter().enumerate() {
+                    if let Some(distance) = self.distance(index) {
+                        self.add_steering(distance, telemetry.steering());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the length of a bin in meters.
+    pub fn resolution(&self) -> f32 {
+        self.resolution
+    }
+
+    /// Returns the length of the track in meters.
+    ///
+    /// The length is published in the session packet. Until one has been received, the distance
+    /// to the furthest point of the map is returned.
+    pub fn length(&self) -> f32 {
+        match self.track_length {
+            Some(length) => length as f32,
+            None => self.bins.len() as f32 * self.resolution,
+        }
+    }
+
+    /// Returns the share of the track that has been mapped, between 0 and 1.
+    pub fn coverage(&self) -> f32 {
+        let bins = (self.length() / self.resolution).ceil() as usize;
+
+        if bins == 0 {
+            return 0.0;
+        }
+
+        let mapped = self
+            .bins
+            .iter()
+            .take(bins)
+            .filter(|bin| bin.motion_samples > 0)
+            .count();
+
+        mapped as f32 / bins as f32
+    }
+
+    /// Returns the point of the track at the given lap distance, if it has been mapped.
+    pub fn point(&self, distance: f32) -> Option<TrackPoint> {
+        if distance < 0.0 {
+            return None;
+        }
+
+        self.point_at((distance / self.resolution) as usize)
+    }
+
+    /// Returns the lap distances at which the second and third sector start, once a car has been
+    /// observed entering them.
+    pub fn sector_boundaries(&self) -> [Option<f32>; 2] {
+        self.sector_boundaries
+    }
+
+    /// Returns all points of the track that have been mapped, ordered by lap distance.
+    pub fn points(&self) -> Vec<TrackPoint> {
+        (0..self.bins.len())
+            .filter_map(|index| self.point_at(index))
+            .collect()
+    }
+
+    pub(crate) fn add_motion(&mut self, distance: f32, position: &Property3D<f32>, lateral_g: f32) {
+        let bin = self.bin_mut(distance);
+
+        bin.position.0 += position.x();
+        bin.position.1 += position.y();
+        bin.position.2 += position.z();
+        bin.lateral_g += lateral_g;
+        bin.motion_samples += 1;
+    }
+
+    pub(crate) fn add_steering(&mut self, distance: f32, steering: f32) {
+        let bin = self.bin_mut(distance);
+
+        bin.steering += steering;
+        bin.steering_samples += 1;
+    }
+
+    fn update_distances(&mut self, packet: &LapPacket) {
+        for (index, lap) in packet.laps().iter().enumerate() {
+            let boundary = match (self.sectors.get(index), lap.sector()) {
+                (Some(Sector::First), Sector::Second) => 0,
+                (Some(Sector::Second), Sector::Third) => 1,
+                _ => continue,
+            };
+
+            if lap.pit_status() == PitStatus::None && lap.lap_distance() >= 0.0 {
+                self.sector_boundaries[boundary] = Some(lap.lap_distance());
+            }
+        }
+
+        self.sectors = packet.laps().iter().map(|lap| lap.sector()).collect();
+        self.distances = packet
+            .laps()
+            .iter()
+            .map(|lap| {
+                if lap.pit_status() == PitStatus::None && lap.lap_distance() >= 0.0 {
+                    Some(lap.lap_distance())
+                } else {
+                    None
+                }
+            })
+            .collect();
+    }
+
+    fn distance(&self, index: usize) -> Option<f32> {
+        self.distances.get(index).copied().flatten()
+    }
+
+    fn bin_mut(&mut self, distance: f32) -> &mut Bin {
+        let index = (distance.max(0.0) / self.resolution) as usize;
+
+        if index >= self.bins.len() {
+            self.bins.resize(index + 1, Bin::default());
+        }
+
+        &mut self.bins[index]
+    }
+
+    fn point_at(&self, index: usize) -> Option<TrackPoint> {
+        let bin = self.bins.get(index)?;
+
+        if bin.motion_samples == 0 {
+            return None;
+        }
+
+        let samples = bin.motion_samples as f32;
+        let steering = if bin.steering_samples > 0 {
+            bin.steering / bin.steering_samples as f32
+        } else {
+            0.0
+        };
+
+        Some(TrackPoint::new(
+            index as f32 * self.resolution,
+            Property3D::new(
+                bin.position.0 / samples,
+                bin.position.1 / samples,
+                bin.position.2 / samples,
+            ),
+            bin.lateral_g / samples,
+            steering,
+        ))
+    }
+}
+
+impl Default for TrackMap {
+    fn default() -> Self {
+        TrackMap::new(DEFAULT_RESOLUTION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::track_map::TrackMap;
+    use crate::types::Property3D;
+
+    #[test]
+    fn point_with_averages() {
+        let mut map = TrackMap::new(10.0);
+
+        map.add_motion(12.0, &Property3D::new(1.0, 0.0, 2.0), 1.0);
+        map.add_motion(18.0, &Property3D::new(3.0, 0.0, 4.0), 2.0);
+        map.add_steering(15.0, 0.5);
+
+        let point = map.point(11.0).unwrap();
+        assert_eq!(10.0, point.distance());
+        assert_eq!(Property3D::new(2.0, 0.0, 3.0), *point.position());
+        assert_eq!(1.5, point.lateral_g());
+        assert_eq!(0.5, point.steering());
+
+        assert_eq!(None, map.point(5.0));
+        assert_eq!(None, map.point(-5.0));
+    }
+
+    #[test]
+    fn coverage_without_track_length() {
+        let mut map = TrackMap::new(10.0);
+
+        map.add_motion(5.0, &Property3D::default(), 0.0);
+        map.add_motion(35.0, &Property3D::default(), 0.0);
+
+        assert_eq!(40.0, map.length());
+        assert_eq!(0.5, map.coverage());
+    }
+}
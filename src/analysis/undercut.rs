@@ -0,0 +1,286 @@
+//! What-if calculator for the undercut and the overcut
+//!
+//! Whether a driver should pit now or stay out depends on how quickly the tyres are degrading, how
+//! much time a pit stop costs, and how far the rival is ahead or behind. The lap times of the
+//! current stint of each car are fitted with a linear `Degradation` model, which is then used to
+//! project the gap to a rival over the next laps, once for pitting now and once for pitting at the
+//! end of those laps.
+//!
+//! The projection assumes that the new tyres degrade like the current ones, and that the rival does
+//! not pit during the projected laps. It is a quick estimate for the question every strategist asks
+//! mid-race, not a full race simulation.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::CopyGetters;
+
+use crate::tracker::{CarHistory, LapRecord, SessionTracker, Stint};
+use crate::types::VehicleIndex;
+
+/// Linear model of the lap time as a function of the age of the tyres
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct Degradation {
+    /// Returns the lap time on new tyres in seconds.
+    #[getset(get_copy = "pub")]
+    base_lap_time: f32,
+
+    /// Returns the change of the lap time per lap of tyre age in seconds.
+    ///
+    /// The slope can be negative when the car is getting faster due to the fuel being burned.
+    #[getset(get_copy = "pub")]
+    per_lap: f32,
+}
+
+impl Degradation {
+    /// Fit the model to the given tyre ages and lap times with a least squares regression.
+    ///
+    /// `None` is returned if the samples do not cover at least two different tyre ages.
+    pub fn fit(samples: &[(u8, Duration)]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let count = samples.len() as f32;
+        let mean_age = samples.iter().map(|(age, _)| *age as f32).sum::<f32>() / count;
+        let mean_time = samples
+            .iter()
+            .map(|(_, time)| time.as_secs_f32())
+            .sum::<f32>()
+            / count;
+
+        let (covariance, variance) =
+            samples
+                .iter()
+                .fold((0.0, 0.0), |(covariance, variance), (age, time)| {
+                    let age = *age as f32 - mean_age;
+                    (
+                        covariance + age * (time.as_secs_f32() - mean_time),
+                        variance + age * age,
+                    )
+                });
+
+        if variance == 0.0 {
+            return None;
+        }
+
+        let per_lap = covariance / variance;
+
+        Some(Degradation::new(mean_time - per_lap * mean_age, per_lap))
+    }
+
+    /// Fit the model to the laps of the current stint of a car.
+    pub fn of_current_stint(car: &CarHistory) -> Option<Self> {
+        Degradation::of_stint(car.laps(), car.current_stint()?)
+    }
+
+    /// Fit the model to the laps of a stint.
+    ///
+    /// The first lap of a stint is either the start of the race or an out-lap, and is ignored
+    /// together with laps that ended in the pit lane.
+    pub fn of_stint(laps: &[LapRecord], stint: &Stint) -> Option<Self> {
+        let start_lap = stint.start_lap();
+        let end_lap = stint.end_lap().unwrap_or(u8::MAX);
+
+        let samples: Vec<(u8, Duration)> = laps
+            .iter()
+            .filter(|lap| lap.lap_number() > start_lap && lap.lap_number() <= end_lap)
+            .filter(|lap| !lap.pit_stop())
+            .map(|lap| (lap.lap_number() - start_lap, *lap.lap_time()))
+            .collect();
+
+        Degradation::fit(&samples)
+    }
+
+    /// Returns the predicted lap time in seconds with tyres of the given age.
+    pub fn lap_time(&self, age: u8) -> f32 {
+        self.base_lap_time + self.per_lap * age as f32
+    }
+
+    /// Returns the predicted time in seconds for the given number of laps, starting with tyres of
+    /// the given age.
+    pub fn stint_time(&self, age: u8, laps: u8) -> f32 {
+        (0..laps)
+            .map(|lap| self.lap_time(age.saturating_add(lap)))
+            .sum()
+    }
+}
+
+/// Projected gap to a rival after pitting now or later
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct UndercutProjection {
+    /// Returns the index of the car that pits.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the index of the rival.
+    #[getset(get_copy = "pub")]
+    rival: VehicleIndex,
+
+    /// Returns the number of laps that are projected.
+    #[getset(get_copy = "pub")]
+    laps: u8,
+
+    /// Returns the current gap to the rival in seconds, which is positive when the rival is ahead.
+    #[getset(get_copy = "pub")]
+    current_gap: f32,
+
+    /// Returns the gap to the rival in seconds after the projected laps when pitting now.
+    #[getset(get_copy = "pub")]
+    gap_if_pitting_now: f32,
+
+    /// Returns the gap to the rival in seconds after the projected laps when pitting at their end.
+    #[getset(get_copy = "pub")]
+    gap_if_pitting_later: f32,
+}
+
+impl UndercutProjection {
+    /// Returns the time in seconds that is gained by pitting now instead of later.
+    ///
+    /// A negative value means that staying out, i.e. the overcut, is faster.
+    pub fn gain(&self) -> f32 {
+        self.gap_if_pitting_later - self.gap_if_pitting_now
+    }
+
+    /// Returns whether pitting now is faster than pitting later.
+    pub fn pit_now(&self) -> bool {
+        self.gain() > 0.0
+    }
+}
+
+/// Project the gap of a car to a rival when pitting now compared to pitting in the given number of
+/// laps.
+///
+/// The gaps are taken from the classification of the tracker, and the degradation is fitted to the
+/// current stints of both cars. `None` is returned if either car is not classified with a gap, or
+/// has not driven enough laps in its stint to fit the model.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::pit_window::measure_pit_loss;
+/// use f1_api::analysis::undercut::undercut;
+/// use f1_api::tracker::SessionTracker;
+/// # use std::time::Duration;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = SessionTracker::default();
+///
+/// for packet in packets() {
+///     tracker.update(&packet);
+/// }
+///
+/// let pit_loss = measure_pit_loss(&tracker).unwrap_or(Duration::from_secs(22));
+///
+/// if let Some(projection) = undercut(&tracker, 0, 1, pit_loss, 3) {
+///     println!("Pitting now gains {:.1}s on car #1", projection.gain());
+/// }
+/// ```
+pub fn undercut(
+    tracker: &SessionTracker,
+    vehicle_index: VehicleIndex,
+    rival: VehicleIndex,
+    pit_loss: Duration,
+    laps: u8,
+) -> Option<UndercutProjection> {
+    let classification = tracker.classification();
+    let gap_to_leader = |index: VehicleIndex| {
+        let entry = classification
+            .iter()
+            .find(|entry| entry.vehicle_index() == index)?;
+
+        match entry.position() {
+            1 => Some(0.0),
+            _ => entry.gap().map(|gap| gap.as_secs_f32()),
+        }
+    };
+
+    let current_gap = gap_to_leader(vehicle_index)? - gap_to_leader(rival)?;
+
+    let (degradation, age) = stint(tracker, vehicle_index)?;
+    let (rival_degradation, rival_age) = stint(tracker, rival)?;
+    let rival_time = rival_degradation.stint_time(rival_age, laps);
+
+    let (gap_if_pitting_now, gap_if_pitting_later) = project(
+        current_gap,
+        pit_loss.as_secs_f32(),
+        degradation.stint_time(0, laps) - rival_time,
+        degradation.stint_time(age, laps) - rival_time,
+    );
+
+    Some(UndercutProjection::new(
+        vehicle_index,
+        rival,
+        laps,
+        current_gap,
+        gap_if_pitting_now,
+        gap_if_pitting_later,
+    ))
+}
+
+/// Returns the degradation of the current stint of a car, and the age of its tyres.
+fn stint(tracker: &SessionTracker, vehicle_index: VehicleIndex) -> Option<(Degradation, u8)> {
+    let car = tracker.car(vehicle_index)?;
+    let start_lap = car.current_stint()?.start_lap();
+
+    let age = tracker
+        .laps()
+        .as_ref()?
+        .car(vehicle_index)?
+        .current_lap_number()
+        .saturating_sub(start_lap);
+
+    Some((Degradation::of_current_stint(car)?, age))
+}
+
+/// Add the pit loss and the time lost to the rival in each scenario to the current gap.
+fn project(current_gap: f32, pit_loss: f32, loss_now: f32, loss_later: f32) -> (f32, f32) {
+    (
+        current_gap + pit_loss + loss_now,
+        current_gap + pit_loss + loss_later,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::undercut::{project, Degradation, UndercutProjection};
+
+    #[test]
+    fn fit_finds_slope_of_lap_times() {
+        let samples = vec![
+            (1, Duration::from_secs_f32(90.2)),
+            (2, Duration::from_secs_f32(90.4)),
+            (3, Duration::from_secs_f32(90.6)),
+        ];
+
+        let degradation = Degradation::fit(&samples).unwrap();
+
+        assert!((degradation.per_lap() - 0.2).abs() < 0.001);
+        assert!((degradation.base_lap_time() - 90.0).abs() < 0.001);
+        assert!((degradation.stint_time(0, 2) - 180.2).abs() < 0.01);
+
+        assert_eq!(None, Degradation::fit(&samples[..1]));
+    }
+
+    #[test]
+    fn projection_favours_new_tyres_on_high_degradation() {
+        let degradation = Degradation::new(90.0, 0.5);
+        let rival = Degradation::new(90.0, 0.5);
+        let rival_time = rival.stint_time(20, 3);
+
+        let (now, later) = project(
+            1.0,
+            20.0,
+            degradation.stint_time(0, 3) - rival_time,
+            degradation.stint_time(20, 3) - rival_time,
+        );
+        let projection = UndercutProjection::new(0, 1, 3, 1.0, now, later);
+
+        assert!((projection.gap_if_pitting_now() + 9.0).abs() < 0.01);
+        assert!((projection.gain() - 30.0).abs() < 0.01);
+        assert!(projection.pit_now());
+    }
+}
@@ -0,0 +1,255 @@
+//! Changes of the weather and the weather forecast
+//!
+//! The session packet reports the current weather and temperatures, and newer games also publish a
+//! forecast for the sessions of the weekend. The `WeatherTracker` compares consecutive session
+//! packets and reports what has changed, so that strategy tools can react to rain before it
+//! arrives instead of polling the session packet.
+
+use std::time::Duration;
+
+use crate::packet::session::{Session, Weather, WeatherForecastSample};
+use crate::packet::Packet;
+
+/// Change of the weather between two session packets
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum WeatherChange {
+    /// The weather has changed.
+    Weather { from: Weather, to: Weather },
+
+    /// The track temperature has changed, in degrees celsius.
+    TrackTemperature { from: i8, to: i8 },
+
+    /// The air temperature has changed, in degrees celsius.
+    AirTemperature { from: i8, to: i8 },
+
+    /// The game has published a new weather forecast.
+    Forecast,
+}
+
+/// Tracker for the weather and the weather forecast of a session
+///
+/// The first session packet of a session sets the initial conditions without reporting a change.
+/// Forecasts are only tracked for games that publish them.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::weather::{WeatherChange, WeatherTracker};
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = WeatherTracker::default();
+///
+/// for packet in packets() {
+///     for change in tracker.update(&packet) {
+///         if let WeatherChange::Weather { from, to } = change {
+///             println!("Weather changed from {:?} to {:?}", from, to);
+///         }
+///     }
+///
+///     if let Some(offset) = tracker.rain_expected_in() {
+///         println!("Rain expected in {} minutes", offset.as_secs() / 60);
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct WeatherTracker {
+    session_uid: Option<u64>,
+    session_type: Session,
+    conditions: Option<(Weather, i8, i8)>,
+    forecast: Option<Vec<WeatherForecastSample>>,
+}
+
+impl WeatherTracker {
+    /// Returns the current weather.
+    pub fn weather(&self) -> Option<Weather> {
+        self.conditions.map(|(weather, _, _)| weather)
+    }
+
+    /// Returns the current track temperature in degrees celsius.
+    pub fn track_temperature(&self) -> Option<i8> {
+        self.conditions
+            .map(|(_, track_temperature, _)| track_temperature)
+    }
+
+    /// Returns the current air temperature in degrees celsius.
+    pub fn air_temperature(&self) -> Option<i8> {
+        self.conditions
+            .map(|(_, _, air_temperature)| air_temperature)
+    }
+
+    /// Returns the latest weather forecast, or `None` if the game does not publish one.
+    pub fn forecast(&self) -> Option<&[WeatherForecastSample]> {
+        self.forecast.as_deref()
+    }
+
+    /// Returns the time offset of the first forecast sample with rain in the current session.
+    pub fn rain_expected_in(&self) -> Option<Duration> {
+        self.forecast
+            .as_ref()?
+            .iter()
+            .filter(|sample| sample.session_type() == self.session_type)
+            .filter(|sample| sample.weather() >= Weather::LightRain)
+            .map(|sample| *sample.time_offset())
+            .min()
+    }
+
+    /// Update the tracker with a new packet, and return the changes of the weather.
+    pub fn update(&mut self, packet: &Packet) -> Vec<WeatherChange> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.session_type = Session::default();
+            self.conditions = None;
+            self.forecast = None;
+        }
+
+        let session = match packet {
+            Packet::Session(session) => session,
+            _ => return Vec::new(),
+        };
+
+        let mut changes = Vec::new();
+        let conditions = (
+            session.weather(),
+            session.track_temperature(),
+            session.air_temperature(),
+        );
+
+        if let Some((weather, track_temperature, air_temperature)) = self.conditions {
+            if weather != conditions.0 {
+                changes.push(WeatherChange::Weather {
+                    from: weather,
+                    to: conditions.0,
+                });
+            }
+
+            if track_temperature != conditions.1 {
+                changes.push(WeatherChange::TrackTemperature {
+                    from: track_temperature,
+                    to: conditions.1,
+                });
+            }
+
+            if air_temperature != conditions.2 {
+                changes.push(WeatherChange::AirTemperature {
+                    from: air_temperature,
+                    to: conditions.2,
+                });
+            }
+        }
+
+        if self.forecast.is_some() && self.forecast != *session.weather_forecast() {
+            changes.push(WeatherChange::Forecast);
+        }
+
+        self.session_type = session.session_type();
+        self.conditions = Some(conditions);
+        self.forecast = session.weather_forecast().clone();
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::analysis::weather::{WeatherChange, WeatherTracker};
+    use crate::fixtures::header;
+    use crate::packet::header::PacketType;
+    use crate::packet::session::{
+        Formula, SafetyCar, Session, SessionPacket, Track, Weather, WeatherForecastSample,
+    };
+    use crate::packet::Packet;
+
+    fn session_packet(
+        weather: Weather,
+        track_temperature: i8,
+        forecast: Option<Vec<WeatherForecastSample>>,
+    ) -> Packet {
+        Packet::Session(SessionPacket::new(
+            header(PacketType::Session, 1, Duration::default(), 0),
+            weather,
+            track_temperature,
+            20,
+            50,
+            5000,
+            Session::Race,
+            Track::Monza,
+            Formula::ModernF1,
+            Duration::default(),
+            Duration::default(),
+            80,
+            false,
+            false,
+            0,
+            false,
+            Vec::new(),
+            SafetyCar::None,
+            false,
+            forecast,
+        ))
+    }
+
+    fn sample(session_type: Session, minutes: u64, weather: Weather) -> WeatherForecastSample {
+        WeatherForecastSample::new(
+            session_type,
+            Duration::from_secs(minutes * 60),
+            weather,
+            30,
+            20,
+        )
+    }
+
+    #[test]
+    fn update_reports_changes() {
+        let mut tracker = WeatherTracker::default();
+
+        assert!(tracker
+            .update(&session_packet(Weather::Clear, 30, None))
+            .is_empty());
+
+        let changes = tracker.update(&session_packet(Weather::Overcast, 28, None));
+
+        assert_eq!(
+            vec![
+                WeatherChange::Weather {
+                    from: Weather::Clear,
+                    to: Weather::Overcast
+                },
+                WeatherChange::TrackTemperature { from: 30, to: 28 },
+            ],
+            changes
+        );
+        assert_eq!(None, tracker.rain_expected_in());
+    }
+
+    #[test]
+    fn rain_expected_in_current_session() {
+        let mut tracker = WeatherTracker::default();
+
+        let forecast = vec![
+            sample(Session::Q1, 5, Weather::HeavyRain),
+            sample(Session::Race, 0, Weather::Overcast),
+            sample(Session::Race, 10, Weather::LightRain),
+        ];
+        tracker.update(&session_packet(
+            Weather::Overcast,
+            30,
+            Some(forecast.clone()),
+        ));
+
+        assert_eq!(Some(Duration::from_secs(600)), tracker.rain_expected_in());
+
+        let changes = tracker.update(&session_packet(
+            Weather::Overcast,
+            30,
+            Some(forecast[1..2].to_vec()),
+        ));
+
+        assert_eq!(vec![WeatherChange::Forecast], changes);
+        assert_eq!(None, tracker.rain_expected_in());
+    }
+}
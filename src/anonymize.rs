@@ -0,0 +1,266 @@
+//! Anonymization of captures
+//!
+//! Captures of online sessions contain the names of the players, which are usually their Steam or
+//! console names. The `Anonymizer` rewrites the raw datagrams of a capture so that it can be shared
+//! publicly, for example to debug an issue in a league. The names of human players are replaced by
+//! pseudonyms like `Player 1`, or removed entirely, while the names of AI drivers and all telemetry
+//! are kept intact. Optionally, the unique ids of the sessions are replaced as well, so that a
+//! capture cannot be matched with other data from the same session.
+//!
+//! The datagrams are rewritten byte by byte, without decoding and encoding them. Only packets of
+//! F1 2019 are supported, and any other packet results in an error instead of leaking a name.
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::mem::size_of;
+
+use bytes::{Bytes, BytesMut};
+
+use crate::capture::{CaptureReader, CaptureWriter, Datagram};
+use crate::nineteen::raw::{PacketHeader, PacketParticipantsData, ParticipantData};
+
+/// Packet format of F1 2019
+const PACKET_FORMAT: u16 = 2019;
+
+/// Packet id of the participants packet
+const PARTICIPANTS: u8 = 4;
+
+/// Offset of the packet id in the header
+const PACKET_ID_OFFSET: usize = 5;
+
+/// Offset of the session UID in the header
+const SESSION_UID_OFFSET: usize = 6;
+
+/// Offset of the name in the data of a participant
+const NAME_OFFSET: usize = 5;
+
+/// Length of the name of a participant in bytes
+const NAME_LENGTH: usize = 48;
+
+/// How the names of human players are anonymized
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+pub enum Names {
+    /// Names are replaced with `Player 1`, `Player 2`, ... in the order in which they appear.
+    #[default]
+    Pseudonymize,
+
+    /// Names are removed.
+    Strip,
+}
+
+/// Anonymizer for the datagrams of a capture
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use f1_api::anonymize::Anonymizer;
+/// use f1_api::capture::{CaptureReader, CaptureWriter};
+///
+/// let reader = CaptureReader::new(File::open("league.f1cap")?)?;
+/// let writer = CaptureWriter::new(File::create("league-anonymized.f1cap")?)?;
+///
+/// Anonymizer::default()
+///     .replace_session_uids()
+///     .anonymize_capture(reader, writer)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Anonymizer {
+    names: Names,
+    replace_session_uids: bool,
+    pseudonyms: HashMap<Vec<u8>, usize>,
+    session_uids: HashMap<u64, u64>,
+}
+
+impl Anonymizer {
+    /// Create an anonymizer that treats the names of human players as given.
+    pub fn new(names: Names) -> Self {
+        Anonymizer {
+            names,
+            ..Default::default()
+        }
+    }
+
+    /// Replace the unique id of each session with a sequential number.
+    pub fn replace_session_uids(mut self) -> Self {
+        self.replace_session_uids = true;
+        self
+    }
+
+    /// Anonymize a single datagram.
+    ///
+    /// An error is returned if the datagram is not a packet of F1 2019.
+    pub fn anonymize(&mut self, datagram: &Datagram) -> Result<Datagram, Error> {
+        let mut payload = BytesMut::from(&datagram.payload()[..]);
+
+        if payload.len() < size_of::<PacketHeader>()
+            || u16::from_le_bytes([payload[0], payload[1]]) != PACKET_FORMAT
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Only packets of F1 2019 can be anonymized.",
+            ));
+        }
+
+        if self.replace_session_uids {
+            self.replace_session_uid(&mut payload);
+        }
+
+        if payload[PACKET_ID_OFFSET] == PARTICIPANTS {
+            self.replace_names(&mut payload)?;
+        }
+
+        Ok(Datagram::new(*datagram.timestamp(), Bytes::from(payload)))
+    }
+
+    /// Anonymize every datagram in a capture and write it to another capture.
+    ///
+    /// Returns the number of datagrams that were written.
+    pub fn anonymize_capture<R: Read, W: Write>(
+        &mut self,
+        reader: CaptureReader<R>,
+        mut writer: CaptureWriter<W>,
+    ) -> Result<usize, Error> {
+        let mut count = 0;
+
+        for datagram in reader {
+            writer.write(&self.anonymize(&datagram?)?)?;
+            count += 1;
+        }
+
+        writer.flush()?;
+        Ok(count)
+    }
+
+    fn replace_session_uid(&mut self, payload: &mut [u8]) {
+        let range = SESSION_UID_OFFSET..SESSION_UID_OFFSET + size_of::<u64>();
+
+        let mut session_uid = [0u8; 8];
+        session_uid.copy_from_slice(&payload[range.clone()]);
+
+        let next = self.session_uids.len() as u64 + 1;
+        let replacement = *self
+            .session_uids
+            .entry(u64::from_le_bytes(session_uid))
+            .or_insert(next);
+
+        payload[range].copy_from_slice(&replacement.to_le_bytes());
+    }
+
+    fn replace_names(&mut self, payload: &mut [u8]) -> Result<(), Error> {
+        if payload.len() < size_of::<PacketParticipantsData>() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Participants packet is incomplete.",
+            ));
+        }
+
+        // The header is followed by the number of active cars and the participants.
+        let start = size_of::<PacketHeader>() + 1;
+
+        for participant in payload[start..size_of::<PacketParticipantsData>()]
+            .chunks_exact_mut(size_of::<ParticipantData>())
+        {
+            // Only the names of human players are anonymized, not those of AI drivers.
+            if participant[0] != 0 {
+                continue;
+            }
+
+            let name = &mut participant[NAME_OFFSET..NAME_OFFSET + NAME_LENGTH];
+            let length = name
+                .iter()
+                .position(|byte| *byte == 0)
+                .unwrap_or(NAME_LENGTH);
+
+            if length == 0 {
+                continue;
+            }
+
+            let replacement = match self.names {
+                Names::Pseudonymize => {
+                    let next = self.pseudonyms.len() + 1;
+                    let number = *self
+                        .pseudonyms
+                        .entry(name[..length].to_vec())
+                        .or_insert(next);
+
+                    format!("Player {}", number)
+                }
+                Names::Strip => String::new(),
+            };
+
+            name.fill(0);
+            name[..replacement.len()].copy_from_slice(replacement.as_bytes());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::anonymize::{Anonymizer, Names};
+    use crate::capture::Datagram;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::participants::Controller;
+    use crate::packet::Packet;
+
+    fn participants() -> Vec<Datagram> {
+        crate::fixtures::capture(ApiSpec::Nineteen, PacketType::Participants)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn decode(datagram: &Datagram) -> Packet {
+        datagram.decode().unwrap().unwrap()
+    }
+
+    #[test]
+    fn anonymize_replaces_names_of_human_players() {
+        let mut anonymizer = Anonymizer::default().replace_session_uids();
+
+        for datagram in participants() {
+            let original = decode(&datagram);
+            let anonymized = decode(&anonymizer.anonymize(&datagram).unwrap());
+
+            assert_eq!(1, anonymized.header().session_uid());
+
+            match (original, anonymized) {
+                (Packet::Participants(original), Packet::Participants(anonymized)) => {
+                    for (original, anonymized) in original
+                        .participants()
+                        .iter()
+                        .zip(anonymized.participants())
+                    {
+                        match original.controller() {
+                            Controller::Human => assert_eq!("Player 1", anonymized.name()),
+                            Controller::AI => assert_eq!(original.name(), anonymized.name()),
+                        }
+                        assert_eq!(original.driver(), anonymized.driver());
+                    }
+                }
+                _ => panic!("Expected participants packets"),
+            }
+        }
+
+        let mut anonymizer = Anonymizer::new(Names::Strip);
+
+        match decode(&anonymizer.anonymize(&participants()[0]).unwrap()) {
+            Packet::Participants(packet) => assert_eq!("", packet.participants()[0].name()),
+            _ => panic!("Expected a participants packet"),
+        }
+    }
+
+    #[test]
+    fn anonymize_rejects_unknown_packet_formats() {
+        let datagram = Datagram::new(Duration::default(), Bytes::from(vec![0u8; 32]));
+
+        assert!(Anonymizer::default().anonymize(&datagram).is_err());
+    }
+}
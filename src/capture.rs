@@ -0,0 +1,469 @@
+//! Recording and replaying of raw UDP packets
+//!
+//! The F1 games publish their telemetry as a stream of UDP packets that is gone once it has been
+//! consumed. A capture preserves the raw packets together with the time at which they arrived, so
+//! that a session can be decoded and analysed again at a later point in time.
+//!
+//! Captures are stored in a simple binary format. The file starts with the magic bytes `F1CP` and a
+//! single byte with the version of the format. It is followed by a list of records, each consisting
+//! of the arrival time in microseconds since the start of the capture (`u64`), the size of the
+//! packet in bytes (`u32`), and the packet itself. All numbers are encoded as little endian.
+//!
+//! Collectors often record a whole evening of sessions into a single capture. `split` divides such
+//! a capture into one capture per session, while `concat` and `merge` combine multiple captures
+//! into one.
+//!
+//! Archives that were recorded with Wireshark or `tcpdump` can be read with the `PcapReader` in the
+//! `pcap` module, and sessions recorded with the `f1-2019-telemetry` package with the
+//! `RecorderReader` in the `recorder` module, and converted into a capture. The `Replay` in the
+//! `replay` module plays a capture back with the timing of the original session, and can be
+//! paused, sped up, and rewound.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::time::Duration;
+
+use bytes::Bytes;
+use derive_new::new;
+use getset::Getters;
+
+use crate::codec::decode_bytes;
+use crate::packet::Packet;
+
+pub mod pcap;
+#[cfg(feature = "sqlite")]
+pub mod recorder;
+pub mod replay;
+
+/// Magic bytes at the beginning of every capture
+pub const MAGIC: [u8; 4] = *b"F1CP";
+
+/// Version of the capture format that is written by this crate
+pub const VERSION: u8 = 1;
+
+/// Largest size of a datagram in bytes, which is the largest payload of a UDP packet
+pub const MAX_DATAGRAM_SIZE: usize = 65_535;
+
+/// Raw UDP packet and the time at which it was received
+///
+/// The timestamp is relative to the start of the capture, which makes it possible to replay a
+/// capture with the same timing as the original session.
+#[derive(new, Debug, Getters, PartialEq, Clone, Eq, Hash)]
+pub struct Datagram {
+    /// Returns the time at which the packet was received, relative to the start of the capture.
+    #[getset(get = "pub")]
+    timestamp: Duration,
+
+    /// Returns the raw payload of the UDP packet.
+    #[getset(get = "pub")]
+    payload: Bytes,
+}
+
+impl Datagram {
+    /// Decode the payload of the datagram.
+    ///
+    /// The payload is decoded like the `F1Codec` decodes packets from the network. `Ok(None)` is
+    /// returned if the payload does not contain a complete packet.
+    pub fn decode(&self) -> Result<Option<Packet>, Error> {
+        decode_bytes(&self.payload)
+    }
+}
+
+/// Writer for captures
+///
+/// The capture writer writes the header of the capture format when it is created, and then appends
+/// each datagram that it is given to the underlying writer.
+pub struct CaptureWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Create a new capture writer and write the header of the capture format.
+    pub fn new(mut writer: W) -> Result<Self, Error> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION])?;
+
+        Ok(CaptureWriter { writer })
+    }
+
+    /// Append a datagram to the capture.
+    pub fn write(&mut self, datagram: &Datagram) -> Result<(), Error> {
+        if datagram.payload().len() > MAX_DATAGRAM_SIZE {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Datagram is larger than the largest UDP packet",
+            ));
+        }
+
+        let timestamp = datagram.timestamp().as_micros() as u64;
+
+        self.writer.write_all(&timestamp.to_le_bytes())?;
+        self.writer
+            .write_all(&(datagram.payload().len() as u32).to_le_bytes())?;
+        self.writer.write_all(datagram.payload())
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reader for captures
+///
+/// The capture reader validates the header of the capture format when it is created, and then
+/// iterates over the datagrams in the capture.
+pub struct CaptureReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Create a new capture reader and validate the header of the capture format.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header)?;
+
+        if header[0..4] != MAGIC {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "File is not a capture of F1 packets.",
+            ));
+        }
+
+        if header[4] != VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unsupported capture version {}.", header[4]),
+            ));
+        }
+
+        Ok(CaptureReader { reader })
+    }
+
+    /// Read the next datagram from the capture.
+    ///
+    /// `Ok(None)` is returned when the end of the capture has been reached. A capture that ends in
+    /// the middle of a record is considered corrupt, and returns an error.
+    pub fn read(&mut self) -> Result<Option<Datagram>, Error> {
+        let mut timestamp = [0u8; 8];
+
+        match self.reader.read(&mut timestamp[..1])? {
+            0 => return Ok(None),
+            _ => self.reader.read_exact(&mut timestamp[1..])?,
+        }
+
+        let mut size = [0u8; 4];
+        self.reader.read_exact(&mut size)?;
+
+        // Records larger than a UDP packet can only come from a corrupt capture, and are rejected
+        // before their payload is allocated.
+        let size = u32::from_le_bytes(size) as usize;
+        if size > MAX_DATAGRAM_SIZE {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Datagram in capture is larger than the largest UDP packet",
+            ));
+        }
+
+        let mut payload = vec![0u8; size];
+        self.reader.read_exact(&mut payload)?;
+
+        Ok(Some(Datagram::new(
+            Duration::from_micros(u64::from_le_bytes(timestamp)),
+            Bytes::from(payload),
+        )))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<Datagram, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+/// Split a capture into one capture per session.
+///
+/// `create` is called with the unique id of each session when its first packet is read, and
+/// returns the writer for the capture of that session. The timestamps in each capture are relative
+/// to the first packet of its session. Datagrams that do not contain a complete packet or cannot be
+/// decoded are skipped, so that a corrupt datagram does not abort the split. Returns the unique ids of the sessions in the order in which they started.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use f1_api::capture::{split, CaptureReader};
+///
+/// let reader = CaptureReader::new(File::open("evening.f1cap")?)?;
+/// let sessions = split(reader, |session_uid| File::create(format!("{}.f1cap", session_uid)))?;
+///
+/// println!("Found {} sessions", sessions.len());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn split<R, W, F>(reader: CaptureReader<R>, mut create: F) -> Result<Vec<u64>, Error>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(u64) -> Result<W, Error>,
+{
+    let mut sessions = Vec::new();
+    let mut writers: HashMap<u64, (CaptureWriter<W>, Duration)> = HashMap::new();
+
+    for datagram in reader {
+        let datagram = datagram?;
+
+        let session_uid = match datagram.decode() {
+            Ok(Some(packet)) => packet.header().session_uid(),
+            Ok(None) => continue,
+            Err(_error) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %_error, "Skipped datagram that could not be decoded");
+
+                continue;
+            }
+        };
+
+        let (writer, start) = match writers.entry(session_uid) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                sessions.push(session_uid);
+
+                let writer = CaptureWriter::new(create(session_uid)?)?;
+                entry.insert((writer, datagram.timestamp))
+            }
+        };
+
+        let timestamp = datagram.timestamp.saturating_sub(*start);
+        writer.write(&Datagram::new(timestamp, datagram.payload))?;
+    }
+
+    for (writer, _) in writers.values_mut() {
+        writer.flush()?;
+    }
+
+    Ok(sessions)
+}
+
+/// Append captures to each other.
+///
+/// The captures are written in the given order. The timestamps of each capture are shifted so
+/// that it starts when the previous capture ended. Returns the number of datagrams written.
+pub fn concat<R: Read, W: Write>(
+    readers: Vec<CaptureReader<R>>,
+    mut writer: CaptureWriter<W>,
+) -> Result<usize, Error> {
+    let mut count = 0;
+    let mut offset = Duration::default();
+
+    for reader in readers {
+        let mut end = offset;
+
+        for datagram in reader {
+            let datagram = datagram?;
+            let timestamp = offset + datagram.timestamp;
+
+            writer.write(&Datagram::new(timestamp, datagram.payload))?;
+            end = end.max(timestamp);
+            count += 1;
+        }
+
+        offset = end;
+    }
+
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Interleave captures by the timestamps of their datagrams.
+///
+/// Merging is meant for captures that were recorded at the same time, for example by two
+/// collectors listening to different games, and that therefore share the same start. Returns the
+/// number of datagrams written.
+pub fn merge<R: Read, W: Write>(
+    readers: Vec<CaptureReader<R>>,
+    mut writer: CaptureWriter<W>,
+) -> Result<usize, Error> {
+    let mut readers = readers;
+    let mut heads = readers
+        .iter_mut()
+        .map(CaptureReader::read)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut count = 0;
+
+    loop {
+        let next = heads
+            .iter()
+            .enumerate()
+            .filter_map(|(index, head)| head.as_ref().map(|datagram| (index, datagram.timestamp)))
+            .min_by_key(|(_, timestamp)| *timestamp)
+            .map(|(index, _)| index);
+
+        let index = match next {
+            Some(index) => index,
+            None => break,
+        };
+
+        if let Some(datagram) = heads[index].take() {
+            writer.write(&datagram)?;
+            count += 1;
+        }
+
+        heads[index] = readers[index].read()?;
+    }
+
+    writer.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::capture::{concat, merge, split, CaptureReader, CaptureWriter, Datagram};
+    use crate::packet::header::{ApiSpec, PacketType};
+
+    /// Returns an event packet of the given session, received at the given time.
+    fn event(session_uid: u64, millis: u64) -> Datagram {
+        // The first packet follows the header of the capture and of its record.
+        let fixture = crate::fixtures::bytes(ApiSpec::Nineteen, PacketType::Event);
+        let mut payload = fixture[17..49].to_vec();
+        payload[6..14].copy_from_slice(&session_uid.to_le_bytes());
+
+        Datagram::new(Duration::from_millis(millis), Bytes::from(payload))
+    }
+
+    fn capture(datagrams: &[Datagram]) -> CaptureReader<Cursor<Vec<u8>>> {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+
+        for datagram in datagrams {
+            writer.write(datagram).unwrap();
+        }
+
+        CaptureReader::new(Cursor::new(writer.into_inner())).unwrap()
+    }
+
+    fn datagrams(bytes: Vec<u8>) -> Vec<Datagram> {
+        CaptureReader::new(Cursor::new(bytes))
+            .unwrap()
+            .map(|datagram| datagram.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn write_and_read_capture() {
+        let first = Datagram::new(Duration::from_millis(1), Bytes::from_static(&[1, 2, 3]));
+        let second = Datagram::new(Duration::from_millis(20), Bytes::from_static(&[4]));
+
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer.write(&first).unwrap();
+        writer.write(&second).unwrap();
+
+        let bytes = writer.into_inner();
+        let reader = CaptureReader::new(Cursor::new(bytes)).unwrap();
+        let datagrams: Vec<Datagram> = reader.map(|datagram| datagram.unwrap()).collect();
+
+        assert_eq!(vec![first, second], datagrams);
+    }
+
+    #[test]
+    fn read_capture_with_invalid_magic() {
+        let reader = CaptureReader::new(Cursor::new(b"PCAP\x01".to_vec()));
+        assert!(reader.is_err());
+    }
+
+    #[test]
+    fn read_truncated_capture() {
+        let datagram = Datagram::new(Duration::from_millis(1), Bytes::from_static(&[1, 2, 3]));
+
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer.write(&datagram).unwrap();
+
+        let mut bytes = writer.into_inner();
+        bytes.pop();
+
+        let mut reader = CaptureReader::new(Cursor::new(bytes)).unwrap();
+        let error = reader.read().unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+    }
+
+    #[test]
+    fn read_capture_with_oversized_datagram() {
+        let mut bytes = CaptureWriter::new(Vec::new()).unwrap().into_inner();
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut reader = CaptureReader::new(Cursor::new(bytes)).unwrap();
+        let error = reader.read().unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn split_capture_by_session() {
+        let reader = capture(&[event(1, 0), event(2, 10), event(1, 20), event(2, 50)]);
+        let mut files = Vec::new();
+
+        let sessions = split(reader, |session_uid| {
+            files.push(session_uid);
+            Ok(Vec::new())
+        })
+        .unwrap();
+
+        assert_eq!(vec![1, 2], sessions);
+        assert_eq!(vec![1, 2], files);
+    }
+
+    #[test]
+    fn split_capture_with_corrupt_datagram() {
+        let corrupt = Datagram::new(Duration::from_millis(5), Bytes::from(vec![0xff; 32]));
+        assert!(corrupt.decode().is_err());
+
+        let reader = capture(&[event(1, 0), corrupt, event(2, 10), event(1, 20)]);
+
+        let sessions = split(reader, |_| Ok(Vec::new())).unwrap();
+
+        assert_eq!(vec![1, 2], sessions);
+    }
+
+    #[test]
+    fn concat_and_merge_captures() {
+        let first = [event(1, 0), event(1, 30)];
+        let second = [event(2, 10), event(2, 20)];
+
+        let timestamps = |bytes: Vec<u8>| -> Vec<u128> {
+            datagrams(bytes)
+                .iter()
+                .map(|datagram| datagram.timestamp().as_millis())
+                .collect()
+        };
+
+        let mut output = Vec::new();
+        let writer = CaptureWriter::new(&mut output).unwrap();
+        assert_eq!(
+            4,
+            concat(vec![capture(&first), capture(&second)], writer).unwrap()
+        );
+        assert_eq!(vec![0, 30, 40, 50], timestamps(output));
+
+        let mut output = Vec::new();
+        let writer = CaptureWriter::new(&mut output).unwrap();
+        assert_eq!(
+            4,
+            merge(vec![capture(&first), capture(&second)], writer).unwrap()
+        );
+        assert_eq!(vec![0, 10, 20, 30], timestamps(output));
+    }
+}
@@ -0,0 +1,301 @@
+//! Import of packet captures recorded with Wireshark or tcpdump
+//!
+//! Many community tools do not record the telemetry themselves, but ask their users to record the
+//! UDP traffic of the game with Wireshark or `tcpdump`. These tools store the traffic in the pcap
+//! format of `libpcap`. The `PcapReader` extracts the payloads of the UDP datagrams from such a
+//! file, so that existing archives can be decoded and analysed with this crate, or converted into
+//! a capture with the `CaptureWriter`.
+//!
+//! The classic pcap format is supported with timestamps in micro- and nanoseconds, and with frames
+//! captured from Ethernet, loopback, and Linux "any" interfaces. Frames that do not contain a UDP
+//! datagram are skipped, as are fragmented IP packets. The newer pcapng format is not supported.
+
+use std::io::{Error, ErrorKind, Read};
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::capture::Datagram;
+
+/// Magic number of captures with timestamps in microseconds
+const MAGIC_MICROSECONDS: u32 = 0xa1b2_c3d4;
+
+/// Magic number of captures with timestamps in nanoseconds
+const MAGIC_NANOSECONDS: u32 = 0xa1b2_3c4d;
+
+/// Link type of BSD loopback interfaces
+const LINKTYPE_NULL: u32 = 0;
+
+/// Link type of Ethernet interfaces
+const LINKTYPE_ETHERNET: u32 = 1;
+
+/// Link type of raw IP packets
+const LINKTYPE_RAW: u32 = 101;
+
+/// Link type of the Linux "any" interface
+const LINKTYPE_LINUX_SLL: u32 = 113;
+
+/// Ether type of IPv4
+const ETHERTYPE_IPV4: u16 = 0x0800;
+
+/// Ether type of IPv6
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+
+/// Protocol number of UDP
+const PROTOCOL_UDP: u8 = 17;
+
+/// Reader for pcap files
+///
+/// The timestamps of the datagrams are relative to the first frame in the file, like the
+/// timestamps in a capture.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use f1_api::capture::pcap::PcapReader;
+///
+/// let reader = PcapReader::new(File::open("session.pcap")?)?.with_port(20777);
+///
+/// for datagram in reader {
+///     if let Some(packet) = datagram?.decode()? {
+///         println!("{:?}", packet.header());
+///     }
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct PcapReader<R: Read> {
+    reader: R,
+    big_endian: bool,
+    nanoseconds: bool,
+    link_type: u32,
+    port: Option<u16>,
+    start: Option<Duration>,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Create a new reader and validate the header of the pcap file.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut header = [0u8; 24];
+        reader.read_exact(&mut header)?;
+
+        let magic = [header[0], header[1], header[2], header[3]];
+
+        let (big_endian, nanoseconds) = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic))
+        {
+            (MAGIC_MICROSECONDS, _) => (false, false),
+            (MAGIC_NANOSECONDS, _) => (false, true),
+            (_, MAGIC_MICROSECONDS) => (true, false),
+            (_, MAGIC_NANOSECONDS) => (true, true),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "File is not a pcap capture.",
+                ))
+            }
+        };
+
+        let mut pcap = PcapReader {
+            reader,
+            big_endian,
+            nanoseconds,
+            link_type: 0,
+            port: None,
+            start: None,
+        };
+
+        pcap.link_type = pcap.u32(&header[20..24]);
+
+        match pcap.link_type {
+            LINKTYPE_NULL | LINKTYPE_ETHERNET | LINKTYPE_RAW | LINKTYPE_LINUX_SLL => Ok(pcap),
+            link_type => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unsupported link type {}.", link_type),
+            )),
+        }
+    }
+
+    /// Only read datagrams that were sent to the given port.
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Read the next UDP datagram from the file.
+    ///
+    /// `Ok(None)` is returned when the end of the file has been reached.
+    pub fn read(&mut self) -> Result<Option<Datagram>, Error> {
+        loop {
+            let mut header = [0u8; 16];
+
+            match self.reader.read(&mut header[..1])? {
+                0 => return Ok(None),
+                _ => self.reader.read_exact(&mut header[1..])?,
+            }
+
+            let seconds = self.u32(&header[0..4]) as u64;
+            let fraction = self.u32(&header[4..8]);
+            let length = self.u32(&header[8..12]) as usize;
+
+            let mut frame = vec![0u8; length];
+            self.reader.read_exact(&mut frame)?;
+
+            let time = match self.nanoseconds {
+                true => Duration::new(seconds, fraction),
+                false => Duration::new(seconds, 0) + Duration::from_micros(fraction as u64),
+            };
+
+            let start = *self.start.get_or_insert(time);
+
+            if let Some(payload) = self.udp_payload(&frame) {
+                return Ok(Some(Datagram::new(
+                    time.saturating_sub(start),
+                    Bytes::copy_from_slice(payload),
+                )));
+            }
+        }
+    }
+
+    fn u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+
+        match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        }
+    }
+
+    /// Returns the payload of the UDP datagram in a frame, if the frame contains one.
+    fn udp_payload<'a>(&self, frame: &'a [u8]) -> Option<&'a [u8]> {
+        let (ether_type, packet) = match self.link_type {
+            LINKTYPE_ETHERNET => (u16_be(frame, 12)?, frame.get(14..)?),
+            LINKTYPE_LINUX_SLL => (u16_be(frame, 14)?, frame.get(16..)?),
+            // The loopback header contains the address family in the byte order of the host.
+            LINKTYPE_NULL => match frame.get(4..)? {
+                packet if packet.first()? >> 4 == 6 => (ETHERTYPE_IPV6, packet),
+                packet => (ETHERTYPE_IPV4, packet),
+            },
+            _ => match frame.first()? >> 4 {
+                6 => (ETHERTYPE_IPV6, frame),
+                _ => (ETHERTYPE_IPV4, frame),
+            },
+        };
+
+        let segment = match ether_type {
+            ETHERTYPE_IPV4 => {
+                let header_length = ((packet.first()? & 0x0f) as usize) * 4;
+                let fragment = u16_be(packet, 6)?;
+
+                // Skip packets that are fragmented, i.e. that have more fragments or an offset.
+                if *packet.get(9)? != PROTOCOL_UDP || fragment & 0x3fff != 0 {
+                    return None;
+                }
+
+                let total_length = u16_be(packet, 2)? as usize;
+                packet.get(header_length..total_length)?
+            }
+            ETHERTYPE_IPV6 => {
+                if *packet.get(6)? != PROTOCOL_UDP {
+                    return None;
+                }
+
+                let payload_length = u16_be(packet, 4)? as usize;
+                packet.get(40..40 + payload_length)?
+            }
+            _ => return None,
+        };
+
+        if let Some(port) = self.port {
+            if u16_be(segment, 2)? != port {
+                return None;
+            }
+        }
+
+        let length = u16_be(segment, 4)? as usize;
+        segment.get(8..length)
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<Datagram, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+/// Read a big endian `u16` at the given offset.
+fn u16_be(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes = bytes.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use crate::capture::pcap::PcapReader;
+
+    /// Returns an Ethernet frame with a UDP datagram to the given port.
+    fn frame(port: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&0x0800u16.to_be_bytes());
+
+        let udp_length = 8 + payload.len() as u16;
+        frame.extend_from_slice(&[0x45, 0, 0, 0, 0, 0, 0x40, 0, 64, 17, 0, 0]);
+        frame[16..18].copy_from_slice(&(20 + udp_length).to_be_bytes());
+        frame.extend_from_slice(&[127, 0, 0, 1, 127, 0, 0, 1]);
+
+        frame.extend_from_slice(&50000u16.to_be_bytes());
+        frame.extend_from_slice(&port.to_be_bytes());
+        frame.extend_from_slice(&udp_length.to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(payload);
+
+        frame
+    }
+
+    fn pcap(frames: &[(u32, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+        bytes.extend_from_slice(&[2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0]);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+
+        for (seconds, microseconds, frame) in frames {
+            bytes.extend_from_slice(&seconds.to_le_bytes());
+            bytes.extend_from_slice(&microseconds.to_le_bytes());
+            bytes.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(frame);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn read_udp_datagrams() {
+        let bytes = pcap(&[
+            (100, 500_000, frame(20777, &[1, 2, 3])),
+            (100, 700_000, frame(53, &[9])),
+            (101, 0, frame(20777, &[4, 5])),
+        ]);
+
+        let reader = PcapReader::new(Cursor::new(bytes))
+            .unwrap()
+            .with_port(20777);
+        let datagrams: Vec<_> = reader.map(|datagram| datagram.unwrap()).collect();
+
+        assert_eq!(2, datagrams.len());
+        assert_eq!(&[1, 2, 3][..], &datagrams[0].payload()[..]);
+        assert_eq!(Duration::from_millis(500), *datagrams[1].timestamp());
+    }
+
+    #[test]
+    fn reject_files_that_are_not_pcap() {
+        let mut bytes = vec![0u8; 24];
+        bytes[..4].copy_from_slice(b"F1CP");
+
+        assert!(PcapReader::new(Cursor::new(bytes)).is_err());
+    }
+}
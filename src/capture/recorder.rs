@@ -0,0 +1,153 @@
+//! Import of sessions recorded with the `f1-2019-telemetry` package
+//!
+//! The Python package `f1-2019-telemetry` is a popular community tool to record the telemetry of
+//! F1 2019. Its recorder, `f1-2019-telemetry-recorder`, stores every packet of a session in a
+//! SQLite database, e.g. `F1_2019_3f5d6a8c2b1e0d47.sqlite3`. The packets are kept in the table
+//! `packets`, with the POSIX time at which a packet was received in the column `timestamp`, a few
+//! fields of its header, and the raw packet in the column `packet`.
+//!
+//! The `RecorderReader` reads the packets of such a database in the order in which they were
+//! recorded, so that existing archives can be decoded and analysed with this crate, or converted
+//! into a capture with the `CaptureWriter`.
+
+use std::io::Error;
+use std::path::Path;
+use std::time::Duration;
+
+use bytes::Bytes;
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::capture::Datagram;
+
+/// Number of packets that are read from the database at once
+const BATCH_SIZE: usize = 1024;
+
+/// Reader for databases of the `f1-2019-telemetry` recorder
+///
+/// The timestamps of the datagrams are relative to the first packet in the database, like the
+/// timestamps in a capture.
+///
+/// # Examples
+///
+/// ```no_run
+/// use f1_api::capture::recorder::RecorderReader;
+///
+/// let reader = RecorderReader::open("F1_2019_3f5d6a8c2b1e0d47.sqlite3")?;
+///
+/// for datagram in reader {
+///     if let Some(packet) = datagram?.decode()? {
+///         println!("{:?}", packet.header());
+///     }
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct RecorderReader {
+    connection: Connection,
+    last_id: i64,
+    start: Option<f64>,
+    batch: std::vec::IntoIter<(f64, Vec<u8>)>,
+}
+
+impl RecorderReader {
+    /// Open the database at the given path as read-only.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(Error::other)?;
+
+        Ok(RecorderReader::new(connection))
+    }
+
+    /// Create a reader for an open connection to a database of the recorder.
+    pub fn new(connection: Connection) -> Self {
+        RecorderReader {
+            connection,
+            last_id: i64::MIN,
+            start: None,
+            batch: Vec::new().into_iter(),
+        }
+    }
+
+    /// Read the next packet from the database.
+    ///
+    /// `Ok(None)` is returned when every packet has been read.
+    pub fn read(&mut self) -> Result<Option<Datagram>, Error> {
+        if self.batch.len() == 0 {
+            self.batch = self.read_batch()?.into_iter();
+        }
+
+        let (timestamp, packet) = match self.batch.next() {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let start = *self.start.get_or_insert(timestamp);
+        let offset = Duration::try_from_secs_f64(timestamp - start).unwrap_or_default();
+
+        Ok(Some(Datagram::new(offset, Bytes::from(packet))))
+    }
+
+    /// Read the packets after the last packet that has been read, in the order of their ids.
+    fn read_batch(&mut self) -> Result<Vec<(f64, Vec<u8>)>, Error> {
+        let mut statement = self
+            .connection
+            .prepare_cached(
+                "SELECT pkt_id, timestamp, packet FROM packets \
+                 WHERE pkt_id > ?1 ORDER BY pkt_id LIMIT ?2",
+            )
+            .map_err(Error::other)?;
+
+        let rows = statement
+            .query_map(params![self.last_id, BATCH_SIZE as i64], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(Error::other)?;
+
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+        for row in rows {
+            let (id, timestamp, packet) = row.map_err(Error::other)?;
+
+            self.last_id = id;
+            batch.push((timestamp, packet));
+        }
+
+        Ok(batch)
+    }
+}
+
+impl Iterator for RecorderReader {
+    type Item = Result<Datagram, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::capture::recorder::RecorderReader;
+    use crate::packet::header::PacketType;
+
+    #[test]
+    fn read_packets_of_recorder() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/nineteen/recorder.sqlite3"
+        );
+        let reader = RecorderReader::open(path).unwrap();
+
+        let datagrams: Vec<_> = reader.map(|datagram| datagram.unwrap()).collect();
+        let packet_types: Vec<PacketType> = datagrams
+            .iter()
+            .map(|datagram| datagram.decode().unwrap().unwrap().header().packet_type())
+            .collect();
+
+        assert_eq!(6, datagrams.len());
+        assert_eq!(PacketType::Session, packet_types[0]);
+        assert_eq!(PacketType::Session, packet_types[5]);
+        assert_eq!(Duration::from_secs(2), *datagrams[5].timestamp());
+    }
+}
@@ -0,0 +1,309 @@
+//! Replay of captures with the timing of the original session
+//!
+//! The `Replay` releases the datagrams of a capture at the pace at which they were recorded, so
+//! that tools built for the live game can be fed with a recorded session. The replay can be
+//! controlled while it is being consumed through a `ReplayControl`, which is a cheap handle that
+//! can be cloned and sent to other threads, for example the user interface of a tool for stewards
+//! who review an incident. The handle pauses and resumes the replay, changes the speed of the
+//! playback, and jumps to a timestamp or the start of a lap.
+//!
+//! The capture is loaded into memory when the replay is created, so that it can jump backwards.
+
+use std::io::{Error, Read};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::capture::{CaptureReader, Datagram};
+use crate::packet::Packet;
+
+/// Playback position and settings that are shared between the replay and its controls
+#[derive(Debug)]
+struct State {
+    position: usize,
+    paused: bool,
+    speed: f64,
+    anchor: Instant,
+    anchor_time: Duration,
+}
+
+impl State {
+    /// Returns the time in the capture that is being played back at the given instant.
+    fn playback_time(&self, now: Instant) -> Duration {
+        match self.paused {
+            true => self.anchor_time,
+            false => {
+                self.anchor_time
+                    + Duration::from_secs_f64(
+                        now.saturating_duration_since(self.anchor).as_secs_f64() * self.speed,
+                    )
+            }
+        }
+    }
+
+    /// Restart the playback clock at the current playback time.
+    fn reanchor(&mut self, now: Instant) {
+        self.anchor_time = self.playback_time(now);
+        self.anchor = now;
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    datagrams: Vec<Datagram>,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+/// Replay of a capture
+///
+/// The replay is an iterator that blocks until the next datagram is due, and returns `None` once
+/// the end of the capture has been reached. Jumping backwards after the end has no effect, since
+/// the iterator has already finished.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use f1_api::capture::CaptureReader;
+/// use f1_api::capture::replay::Replay;
+///
+/// let replay = Replay::new(CaptureReader::new(File::open("race.f1cap")?)?)?;
+/// let control = replay.control();
+///
+/// thread::spawn(move || {
+///     control.seek_to_lap(12);
+///     control.set_speed(0.25);
+/// });
+///
+/// for datagram in replay {
+///     println!("{:?}", datagram.decode()?);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Replay {
+    shared: Arc<Shared>,
+}
+
+impl Replay {
+    /// Load a capture and create a replay that starts at its beginning in real time.
+    pub fn new<R: Read>(reader: CaptureReader<R>) -> Result<Self, Error> {
+        Ok(Replay::from_datagrams(reader.collect::<Result<_, _>>()?))
+    }
+
+    /// Create a replay of the given datagrams, which must be ordered by their timestamp.
+    pub fn from_datagrams(datagrams: Vec<Datagram>) -> Self {
+        let state = State {
+            position: 0,
+            paused: false,
+            speed: 1.0,
+            anchor: Instant::now(),
+            anchor_time: Duration::default(),
+        };
+
+        Replay {
+            shared: Arc::new(Shared {
+                datagrams,
+                state: Mutex::new(state),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Returns a handle to control the replay.
+    pub fn control(&self) -> ReplayControl {
+        ReplayControl {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Iterator for Replay {
+    type Item = Datagram;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let shared = &*self.shared;
+        let mut state = lock(&shared.state);
+
+        loop {
+            let datagram = shared.datagrams.get(state.position)?;
+
+            if state.paused {
+                state = shared
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(|error| error.into_inner());
+                continue;
+            }
+
+            let playback_time = state.playback_time(Instant::now());
+
+            if *datagram.timestamp() <= playback_time {
+                state.position += 1;
+                return Some(datagram.clone());
+            }
+
+            let wait = (*datagram.timestamp() - playback_time).as_secs_f64() / state.speed;
+
+            state = shared
+                .changed
+                .wait_timeout(state, Duration::from_secs_f64(wait))
+                .unwrap_or_else(|error| error.into_inner())
+                .0;
+        }
+    }
+}
+
+/// Handle to control a replay while it is being consumed
+#[derive(Debug, Clone)]
+pub struct ReplayControl {
+    shared: Arc<Shared>,
+}
+
+impl ReplayControl {
+    /// Pause the replay.
+    pub fn pause(&self) {
+        self.update(|state| state.paused = true);
+    }
+
+    /// Resume the replay where it was paused.
+    pub fn resume(&self) {
+        self.update(|state| state.paused = false);
+    }
+
+    /// Returns true if the replay is paused.
+    pub fn is_paused(&self) -> bool {
+        lock(&self.shared.state).paused
+    }
+
+    /// Change the speed of the playback, e.g. to `0.5` for half speed or `4.0` for four times the
+    /// original speed. Speeds that are not positive are ignored.
+    pub fn set_speed(&self, speed: f64) {
+        if speed > 0.0 && speed.is_finite() {
+            self.update(|state| state.speed = speed);
+        }
+    }
+
+    /// Returns the speed of the playback.
+    pub fn speed(&self) -> f64 {
+        lock(&self.shared.state).speed
+    }
+
+    /// Returns the time in the capture that is currently being played back.
+    pub fn playback_time(&self) -> Duration {
+        lock(&self.shared.state).playback_time(Instant::now())
+    }
+
+    /// Jump to the given time in the capture.
+    ///
+    /// The replay continues with the first datagram that was received at or after the given time.
+    pub fn seek(&self, timestamp: Duration) {
+        let position = self
+            .shared
+            .datagrams
+            .partition_point(|datagram| *datagram.timestamp() < timestamp);
+
+        self.update(|state| {
+            state.position = position;
+            state.anchor_time = timestamp;
+        });
+    }
+
+    /// Jump to the start of the given lap of the player's car.
+    ///
+    /// The replay continues with the first lap packet in which the player is on the given lap.
+    /// Returns false and keeps the position if the lap is not in the capture.
+    pub fn seek_to_lap(&self, lap: u8) -> bool {
+        let datagram = self
+            .shared
+            .datagrams
+            .iter()
+            .find(|datagram| match datagram.decode() {
+                Ok(Some(Packet::Lap(packet))) => packet
+                    .player()
+                    .is_some_and(|data| data.current_lap_number() == lap),
+                _ => false,
+            });
+
+        match datagram {
+            Some(datagram) => {
+                self.seek(*datagram.timestamp());
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn update<F: FnOnce(&mut State)>(&self, change: F) {
+        let mut state = lock(&self.shared.state);
+
+        state.reanchor(Instant::now());
+        change(&mut state);
+
+        self.shared.changed.notify_all();
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::capture::replay::Replay;
+    use crate::capture::Datagram;
+    use crate::packet::header::{ApiSpec, PacketType};
+
+    fn laps() -> Vec<Datagram> {
+        crate::fixtures::capture(ApiSpec::Nineteen, PacketType::Lap)
+            .unwrap()
+            .map(Result::unwrap)
+            .enumerate()
+            .map(|(index, datagram)| {
+                Datagram::new(
+                    Duration::from_secs(60 * (index as u64 + 1)),
+                    datagram.payload().clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn seek_jumps_to_timestamp_and_lap() {
+        let datagrams = laps();
+        let mut replay = Replay::from_datagrams(datagrams.clone());
+        let control = replay.control();
+
+        control.seek(Duration::from_secs(120));
+        assert_eq!(Some(datagrams[1].clone()), replay.next());
+
+        assert!(!control.seek_to_lap(9));
+        assert!(control.seek_to_lap(5));
+        assert_eq!(Some(datagrams[0].clone()), replay.next());
+    }
+
+    #[test]
+    fn pause_and_speed_change_playback_time() {
+        let mut replay = Replay::from_datagrams(laps());
+        let control = replay.control();
+
+        control.pause();
+        control.seek(Duration::from_secs(59));
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(Duration::from_secs(59), control.playback_time());
+
+        control.set_speed(100.0);
+        control.set_speed(-1.0);
+        control.resume();
+
+        let start = Instant::now();
+        assert!(replay.next().is_some());
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert_eq!(100.0, control.speed());
+    }
+}
@@ -0,0 +1,239 @@
+//! Client that calls closures for incoming packets
+//!
+//! Many telemetry libraries register a callback for each type of data, and run a loop that invokes
+//! them. The `F1Client` offers the same model on top of the packet stream: closures are registered
+//! with methods like `on_lap`, and `listen` receives packets until the socket is closed.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::io::Error;
+use std::net::SocketAddr;
+
+use tokio_stream::{Stream, StreamExt};
+
+use crate::packet::event::EventPacket;
+use crate::packet::lap::LapPacket;
+use crate::packet::motion::MotionPacket;
+use crate::packet::participants::ParticipantsPacket;
+use crate::packet::session::SessionPacket;
+use crate::packet::setup::CarSetupPacket;
+use crate::packet::status::CarStatusPacket;
+use crate::packet::telemetry::TelemetryPacket;
+use crate::packet::Packet;
+use crate::F1;
+
+/// Callbacks that are registered for a type of packet
+type Callbacks<T> = Vec<Box<dyn FnMut(&T) + Send>>;
+
+/// Client that calls the registered closures for each packet it receives
+///
+/// Any number of closures can be registered for each type of packet. They are called in the order
+/// in which they were registered.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use f1_api::F1Client;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let socket = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+///
+/// F1Client::new()
+///     .on_event(|event| println!("{}", event))
+///     .on_lap(|lap| println!("Received lap data of {} cars", lap.laps().active_count()))
+///     .listen(socket)
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Default)]
+pub struct F1Client {
+    events: Callbacks<EventPacket>,
+    laps: Callbacks<LapPacket>,
+    motion: Callbacks<MotionPacket>,
+    participants: Callbacks<ParticipantsPacket>,
+    sessions: Callbacks<SessionPacket>,
+    setups: Callbacks<CarSetupPacket>,
+    statuses: Callbacks<CarStatusPacket>,
+    telemetry: Callbacks<TelemetryPacket>,
+}
+
+impl F1Client {
+    /// Create a client without any callbacks.
+    pub fn new() -> Self {
+        F1Client::default()
+    }
+
+    /// Register a closure that is called for event packets.
+    pub fn on_event<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&EventPacket) + Send + 'static,
+    {
+        self.events.push(Box::new(callback));
+        self
+    }
+
+    /// Register a closure that is called for lap data packets.
+    pub fn on_lap<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&LapPacket) + Send + 'static,
+    {
+        self.laps.push(Box::new(callback));
+        self
+    }
+
+    /// Register a closure that is called for motion packets.
+    pub fn on_motion<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&MotionPacket) + Send + 'static,
+    {
+        self.motion.push(Box::new(callback));
+        self
+    }
+
+    /// Register a closure that is called for participants packets.
+    pub fn on_participants<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&ParticipantsPacket) + Send + 'static,
+    {
+        self.participants.push(Box::new(callback));
+        self
+    }
+
+    /// Register a closure that is called for session packets.
+    pub fn on_session<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&SessionPacket) + Send + 'static,
+    {
+        self.sessions.push(Box::new(callback));
+        self
+    }
+
+    /// Register a closure that is called for car setup packets.
+    pub fn on_setup<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&CarSetupPacket) + Send + 'static,
+    {
+        self.setups.push(Box::new(callback));
+        self
+    }
+
+    /// Register a closure that is called for car status packets.
+    pub fn on_status<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&CarStatusPacket) + Send + 'static,
+    {
+        self.statuses.push(Box::new(callback));
+        self
+    }
+
+    /// Register a closure that is called for telemetry packets.
+    pub fn on_telemetry<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&TelemetryPacket) + Send + 'static,
+    {
+        self.telemetry.push(Box::new(callback));
+        self
+    }
+
+    /// Listen for packets at the given socket, and call the registered closures for each of them.
+    pub async fn listen(mut self, socket_address: SocketAddr) -> Result<(), Error> {
+        let stream = F1::stream(socket_address)?;
+        self.run(Box::pin(stream)).await;
+
+        Ok(())
+    }
+
+    /// Call the registered closures for each packet of the stream, until the stream ends.
+    pub async fn run<S>(&mut self, stream: S)
+    where
+        S: Stream<Item = Packet> + Unpin,
+    {
+        let mut stream = stream;
+
+        while let Some(packet) = stream.next().await {
+            self.handle(&packet);
+        }
+    }
+
+    /// Call the closures that are registered for the type of the packet.
+    fn handle(&mut self, packet: &Packet) {
+        match packet {
+            Packet::Event(packet) => call(&mut self.events, packet),
+            Packet::Lap(packet) => call(&mut self.laps, packet),
+            Packet::Motion(packet) => call(&mut self.motion, packet),
+            Packet::Participants(packet) => call(&mut self.participants, packet),
+            Packet::Session(packet) => call(&mut self.sessions, packet),
+            Packet::Setup(packet) => call(&mut self.setups, packet),
+            Packet::Status(packet) => call(&mut self.statuses, packet),
+            Packet::Telemetry(packet) => call(&mut self.telemetry, packet),
+        }
+    }
+}
+
+fn call<T>(callbacks: &mut Callbacks<T>, packet: &T) {
+    for callback in callbacks.iter_mut() {
+        callback(packet);
+    }
+}
+
+impl Debug for F1Client {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("F1Client")
+            .field("events", &self.events.len())
+            .field("laps", &self.laps.len())
+            .field("motion", &self.motion.len())
+            .field("participants", &self.participants.len())
+            .field("sessions", &self.sessions.len())
+            .field("setups", &self.setups.len())
+            .field("statuses", &self.statuses.len())
+            .field("telemetry", &self.telemetry.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::client::F1Client;
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+
+    #[tokio::test]
+    async fn run_calls_registered_closures() {
+        let laps = Arc::new(AtomicUsize::new(0));
+        let events = Arc::new(AtomicUsize::new(0));
+
+        let (first, second, counter) = (laps.clone(), laps.clone(), events.clone());
+        let mut client = F1Client::new()
+            .on_lap(move |_| {
+                first.fetch_add(1, Ordering::SeqCst);
+            })
+            .on_lap(move |_| {
+                second.fetch_add(1, Ordering::SeqCst);
+            })
+            .on_event(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+
+        let stream = tokio_stream::iter(vec![
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                Vec::new(),
+            ),
+        ]);
+        client.run(stream).await;
+
+        assert_eq!(4, laps.load(Ordering::SeqCst));
+        assert_eq!(0, events.load(Ordering::SeqCst));
+    }
+}
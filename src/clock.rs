@@ -0,0 +1,345 @@
+//! Synchronization of the session time with the wall clock
+//!
+//! Every packet carries the time since the start of the session at which it was created. This time
+//! is independent of the clock of the computer receiving the packets, which makes it difficult to
+//! align telemetry with other recordings of the session, for example a video of the race.
+//!
+//! The `ClockSync` correlates the session time of packets with the time at which they arrived, and
+//! fits a linear mapping between the two clocks. The slope of the mapping corrects for the drift
+//! between the clock of the game and the local clock, while the jitter of the network averages out
+//! over many packets.
+
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+use crate::packet::header::Header;
+
+/// Number of samples that are used by default to fit the mapping, which is roughly 20 seconds of
+/// packets at 60 Hz
+const DEFAULT_WINDOW: usize = 1200;
+
+/// Mapping between the session time and the wall clock
+///
+/// The mapping is fitted with a linear regression over the most recent samples. Older samples are
+/// discarded, so that the mapping adapts when the game is paused, which stops the session time
+/// while the wall clock keeps running. The mapping is reset when a new session starts.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+///
+/// use f1_api::clock::ClockSync;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut clock = ClockSync::default();
+///
+/// for packet in packets() {
+///     clock.update(packet.header(), SystemTime::now());
+/// }
+///
+/// let start_of_lap = clock.to_wall_clock(Duration::from_secs(90));
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClockSync {
+    window: usize,
+    session_uid: Option<u64>,
+    origin: Option<SystemTime>,
+    samples: VecDeque<(f64, f64)>,
+    reference: (f64, f64),
+    sums: Sums,
+    updates: usize,
+    fit: Option<(f64, f64)>,
+}
+
+impl ClockSync {
+    /// Create a new synchronization that fits the mapping over the given number of samples.
+    pub fn new(window: usize) -> Self {
+        ClockSync {
+            window: window.max(1),
+            session_uid: None,
+            origin: None,
+            samples: VecDeque::with_capacity(window),
+            reference: (0.0, 0.0),
+            sums: Sums::default(),
+            updates: 0,
+            fit: None,
+        }
+    }
+
+    /// Add a sample with the header of a packet and the time at which the packet arrived.
+    pub fn update(&mut self, header: &Header, arrival: SystemTime) {
+        if self.session_uid != Some(header.session_uid()) {
+            *self = ClockSync::new(self.window);
+            self.session_uid = Some(header.session_uid());
+        }
+
+        let origin = *self.origin.get_or_insert(arrival);
+        let sample = (
+            header.session_time().as_secs_f64(),
+            seconds_between(origin, arrival),
+        );
+
+        if self.samples.is_empty() {
+            self.reference = sample;
+        }
+
+        if self.samples.len() == self.window {
+            if let Some(oldest) = self.samples.pop_front() {
+                self.sums.remove(relative(self.reference, oldest));
+            }
+        }
+
+        self.samples.push_back(sample);
+        self.sums.add(relative(self.reference, sample));
+        self.updates += 1;
+
+        // The running sums accumulate rounding errors, so they are recomputed relative to the
+        // oldest sample every time the window has been replaced.
+        if self.updates >= self.window {
+            self.rebase();
+        }
+
+        // A fit without a positive slope cannot be inverted, e.g. after a burst of packets that
+        // arrived at the same time, so the previous fit is kept instead.
+        if let Some(fit) = fit(&self.sums, self.samples.len(), self.reference) {
+            self.fit = Some(fit);
+        }
+    }
+
+    /// Returns the wall-clock time that corresponds to the given session time.
+    pub fn to_wall_clock(&self, session_time: Duration) -> Option<SystemTime> {
+        let (slope, intercept) = self.fit?;
+        let seconds = intercept + slope * session_time.as_secs_f64();
+
+        Some(add_seconds(self.origin?, seconds))
+    }
+
+    /// Returns the session time that corresponds to the given wall-clock time.
+    ///
+    /// `None` is returned if the wall-clock time is before the start of the session.
+    pub fn to_session_time(&self, wall_clock: SystemTime) -> Option<Duration> {
+        let (slope, intercept) = self.fit?;
+        let seconds = (seconds_between(self.origin?, wall_clock) - intercept) / slope;
+
+        Duration::try_from_secs_f64(seconds).ok()
+    }
+
+    /// Returns the drift of the game's clock relative to the wall clock.
+    ///
+    /// The drift is the number of seconds that the wall clock advances more than the session time
+    /// per second of session time. A positive drift means that the game's clock is slower.
+    pub fn drift(&self) -> Option<f64> {
+        self.fit.map(|(slope, _)| slope - 1.0)
+    }
+
+    fn rebase(&mut self) {
+        let reference = self.samples.front().copied().unwrap_or_default();
+
+        self.reference = reference;
+        self.sums = Sums::default();
+        self.updates = 0;
+
+        for sample in &self.samples {
+            self.sums.add(relative(reference, *sample));
+        }
+    }
+}
+
+impl Default for ClockSync {
+    fn default() -> Self {
+        ClockSync::new(DEFAULT_WINDOW)
+    }
+}
+
+/// Running sums of the samples, relative to a reference sample
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct Sums {
+    x: f64,
+    y: f64,
+    xx: f64,
+    xy: f64,
+}
+
+impl Sums {
+    fn add(&mut self, (x, y): (f64, f64)) {
+        self.x += x;
+        self.y += y;
+        self.xx += x * x;
+        self.xy += x * y;
+    }
+
+    fn remove(&mut self, (x, y): (f64, f64)) {
+        self.x -= x;
+        self.y -= y;
+        self.xx -= x * x;
+        self.xy -= x * y;
+    }
+}
+
+/// Fit a line through the samples, and return its slope and intercept.
+///
+/// `None` is returned if there are no samples, or if the slope is not positive.
+fn fit(sums: &Sums, samples: usize, reference: (f64, f64)) -> Option<(f64, f64)> {
+    if samples == 0 {
+        return None;
+    }
+
+    let n = samples as f64;
+    let mean_x = sums.x / n;
+    let mean_y = sums.y / n;
+
+    let covariance = sums.xy - n * mean_x * mean_y;
+    let variance = sums.xx - n * mean_x * mean_x;
+
+    // Without a spread in the session time, e.g. after the first packet, only the offset between
+    // the two clocks can be determined.
+    let slope = if variance > 1e-9 * sums.xx {
+        covariance / variance
+    } else {
+        1.0
+    };
+    let intercept = reference.1 + mean_y - slope * (reference.0 + mean_x);
+
+    if slope > 0.0 && slope.is_finite() && intercept.is_finite() {
+        Some((slope, intercept))
+    } else {
+        None
+    }
+}
+
+fn relative(reference: (f64, f64), (x, y): (f64, f64)) -> (f64, f64) {
+    (x - reference.0, y - reference.1)
+}
+
+fn seconds_between(from: SystemTime, to: SystemTime) -> f64 {
+    match to.duration_since(from) {
+        Ok(duration) => duration.as_secs_f64(),
+        Err(error) => -error.duration().as_secs_f64(),
+    }
+}
+
+fn add_seconds(time: SystemTime, seconds: f64) -> SystemTime {
+    if seconds >= 0.0 {
+        time + Duration::from_secs_f64(seconds)
+    } else {
+        time - Duration::from_secs_f64(-seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::clock::ClockSync;
+    use crate::fixtures::header;
+    use crate::packet::header::PacketType;
+
+    fn assert_close(expected: SystemTime, actual: SystemTime) {
+        let difference = match actual.duration_since(expected) {
+            Ok(difference) => difference,
+            Err(error) => error.duration(),
+        };
+
+        assert!(difference < Duration::from_micros(10), "{:?}", difference);
+    }
+
+    #[test]
+    fn to_wall_clock_with_drift() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let mut clock = ClockSync::default();
+
+        for second in 10..100 {
+            let session_time = Duration::from_secs(second);
+            let arrival = start + Duration::from_secs_f64(second as f64 * 1.001);
+
+            clock.update(&header(PacketType::Lap, 1, session_time, 0), arrival);
+        }
+
+        assert!((clock.drift().unwrap() - 0.001).abs() < 1e-9);
+        assert_close(
+            start + Duration::from_secs_f64(200.2),
+            clock.to_wall_clock(Duration::from_secs(200)).unwrap(),
+        );
+
+        let session_time = clock
+            .to_session_time(start + Duration::from_secs_f64(50.05))
+            .unwrap();
+        assert!((session_time.as_secs_f64() - 50.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn to_wall_clock_with_single_sample() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let mut clock = ClockSync::default();
+
+        assert_eq!(None, clock.to_wall_clock(Duration::from_secs(1)));
+
+        clock.update(
+            &header(PacketType::Lap, 1, Duration::from_secs(5), 0),
+            start,
+        );
+
+        assert_close(
+            start + Duration::from_secs(1),
+            clock.to_wall_clock(Duration::from_secs(6)).unwrap(),
+        );
+    }
+
+    #[test]
+    fn to_wall_clock_with_small_window() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let mut clock = ClockSync::new(10);
+
+        for second in 10..1000 {
+            let session_time = Duration::from_secs(second);
+            let arrival = start + Duration::from_secs_f64(second as f64 * 1.001);
+
+            clock.update(&header(PacketType::Lap, 1, session_time, 0), arrival);
+        }
+
+        assert!((clock.drift().unwrap() - 0.001).abs() < 1e-9);
+        assert_close(
+            start + Duration::from_secs_f64(1001.0),
+            clock.to_wall_clock(Duration::from_secs(1000)).unwrap(),
+        );
+    }
+
+    #[test]
+    fn to_session_time_after_burst() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let mut clock = ClockSync::default();
+
+        for second in 1..5 {
+            let session_time = Duration::from_secs(second);
+            clock.update(&header(PacketType::Lap, 1, session_time, 0), start);
+        }
+
+        assert_eq!(Some(0.0), clock.drift());
+        assert_eq!(
+            Some(Duration::from_secs(2)),
+            clock.to_session_time(start + Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn update_with_new_session() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let mut clock = ClockSync::default();
+
+        clock.update(
+            &header(PacketType::Lap, 1, Duration::from_secs(100), 0),
+            start,
+        );
+        clock.update(
+            &header(PacketType::Lap, 2, Duration::from_secs(0), 0),
+            start + Duration::from_secs(60),
+        );
+
+        assert_close(
+            start + Duration::from_secs(70),
+            clock.to_wall_clock(Duration::from_secs(10)).unwrap(),
+        );
+    }
+}
@@ -1,67 +1,208 @@
 //! Codec for modern F1 games
+//!
+//! The `F1Codec` decodes the packets that arrive at a UDP socket, and requires the `net` feature.
+//! Applications that receive the packets through other channels, for example a WebSocket in the
+//! browser or a capture file, can decode each datagram with `decode_datagram` instead. The decoders
+//! do not depend on any networking, and compile to WebAssembly.
 
-use std::io::{Cursor, Error, ErrorKind};
+use std::io::Error;
 
-use bytes::{Buf, BytesMut};
+use bytes::Bytes;
+#[cfg(feature = "net")]
+use bytes::BytesMut;
+use derive_new::new;
+use getset::Getters;
+#[cfg(feature = "net")]
 use tokio_util::codec::Decoder;
 
-use crate::nineteen::decode_nineteen;
-use crate::packet::Packet;
+use crate::packet::{DecodeError, Packet};
 
 /// Codec to decode UDP packets published by modern F1 games.
 ///
 /// This struct implements the `Decoder` trait for tokio-utils. It can be used to decode incoming
 /// UDP packets, and convert them into internal data representations. The F1 codec can decode the
 /// packets of all F1 games that are supported by this library.
+#[cfg(feature = "net")]
 pub struct F1Codec;
 
+#[cfg(feature = "net")]
 impl Decoder for F1Codec {
     type Item = Packet;
     type Error = Error;
 
     /// Decode a UDP packet and return its data.
     ///
-    /// The `decode` method is called whenever a new data frame is received on a UDP socket, and the
-    /// data frame is passed as an argument. This method has to make a few decisions then:
+    /// The `decode` method is called whenever a datagram is received on the UDP socket, and the
+    /// datagram is passed as an argument. Every datagram contains exactly one packet, so the
+    /// datagram is always consumed as a whole, independent of the result.
     ///
-    /// 1. Does the data form a complete packet so that it can be decoded?
-    /// 2. Is the packet a valid packet sent by an F1 game?
-    /// 3. Can the packet be parsed?
-    ///
-    /// To answer these questions, the following process is used. First, the packet header is read
-    /// to determine the game that sent the packet. With the game and the packet type from the
-    /// header, the expected size of the packet can be determined by calling `buffer_size` from the
-    /// `FromBytes` trait. If the packet is too small, `Ok(None)` is returned to signal that more
-    /// data needs to be retrieved from the UDP socket.
-    ///
-    /// If the packet is complete, it is decoded using the `from_bytes` method in the `FromBytes`
-    /// trait. If the packet can be decoded successfully, it is returned. Otherwise, the error from
-    /// the decoding is returned, signaling that the UDP stream is corrupted and should be shut
-    /// down.
+    /// The packet format is read to determine the game that sent the packet, which is then decoded
+    /// by the `GameSpec` of that game. If the packet can be decoded, it is returned as a unified
+    /// `Packet`. A datagram that is too small for its packet type returns `Ok(None)` and is
+    /// dropped, since UDP never delivers the rest of a datagram later. Any other datagram that
+    /// cannot be decoded returns an error, which the streams of `F1` skip, so that the stream
+    /// continues with the next datagram.
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Error> {
-        let mut cursor = Cursor::new(src);
+        // Every datagram contains exactly one packet. The datagram is consumed, so that the framed
+        // socket moves on to the next datagram instead of decoding the same one again.
+        decode_bytes(&src.split())
+    }
+}
+
+/// Decoded packet together with the raw datagram that it was decoded from
+///
+/// The raw bytes are useful to debug packets that are decoded differently than expected, since they
+/// can be logged or attached to a bug report, and decoded again later.
+#[derive(new, Debug, Getters, PartialEq, Clone)]
+pub struct RawPacket {
+    /// Returns the decoded packet.
+    #[getset(get = "pub")]
+    packet: Packet,
+
+    /// Returns the raw datagram that the packet was decoded from.
+    #[getset(get = "pub")]
+    bytes: Bytes,
+}
+
+impl RawPacket {
+    /// Drop the raw bytes and return the decoded packet.
+    pub fn into_packet(self) -> Packet {
+        self.packet
+    }
+}
 
-        // Not enough data yet to decode the packet format.
-        if cursor.remaining() < 2 {
-            return Ok(None);
+/// Codec that decodes UDP packets and keeps their raw bytes
+///
+/// The codec decodes packets like the `F1Codec`, but yields each packet together with the datagram
+/// that it was decoded from. It is used by `F1::stream_with_raw_bytes`.
+#[cfg(feature = "net")]
+pub struct RawCodec;
+
+#[cfg(feature = "net")]
+impl Decoder for RawCodec {
+    type Item = RawPacket;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Error> {
+        let bytes = src.split().freeze();
+
+        Ok(decode_bytes(&bytes)?.map(|packet| RawPacket::new(packet, bytes)))
+    }
+}
+
+/// Decode the packet at the start of the given bytes.
+///
+/// `Ok(None)` is returned if the bytes do not contain a complete packet.
+pub(crate) fn decode_bytes(bytes: &[u8]) -> Result<Option<Packet>, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("decode", size = bytes.len()).entered();
+
+    match Packet::from_datagram(bytes) {
+        Ok(packet) => Ok(Some(packet)),
+        Err(DecodeError::Incomplete { .. }) => {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(size = bytes.len(), "Incomplete packet");
+
+            Ok(None)
         }
+        Err(error) => {
+            #[cfg(feature = "tracing")]
+            if let DecodeError::UnknownFormat(format) = error {
+                tracing::warn!(format, "Unknown packet format");
+            }
 
-        let packet_format = cursor.get_u16_le();
-
-        let packet = match packet_format {
-            2019 => decode_nineteen(&mut cursor),
-            format => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("Unknown packet format {}.", format),
-            )),
-        };
-
-        match packet {
-            Ok(packet) => Ok(Some(packet)),
-            Err(error) => match error.kind() {
-                ErrorKind::UnexpectedEof => Ok(None),
-                _ => Err(error),
-            },
+            Err(Error::from(error))
         }
     }
 }
+
+/// Decode a single datagram sent by an F1 game.
+///
+/// The datagram must contain exactly one packet, as it was sent by the game. An error is returned
+/// if the datagram is incomplete or cannot be decoded.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::codec::decode_datagram;
+///
+/// assert!(decode_datagram(&[0xe3, 0x07]).is_err());
+/// ```
+pub fn decode_datagram(datagram: &[u8]) -> Result<Packet, Error> {
+    Packet::from_datagram(datagram).map_err(Error::from)
+}
+
+#[cfg(all(test, feature = "net"))]
+mod tests {
+    use bytes::{BufMut, BytesMut};
+    use tokio_util::codec::Decoder;
+
+    use crate::codec::{decode_datagram, F1Codec, RawCodec};
+    use crate::packet::event::Event;
+    use crate::packet::Packet;
+
+    #[test]
+    fn decode_with_incomplete_packet() {
+        let mut bytes = BytesMut::with_capacity(1);
+        bytes.put_u8(0);
+
+        assert!(F1Codec.decode(&mut bytes).unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_with_unknown_format() {
+        let mut bytes = BytesMut::with_capacity(2);
+        bytes.put_u16_le(2017);
+
+        assert!(F1Codec.decode(&mut bytes).is_err());
+    }
+
+    fn session_started() -> BytesMut {
+        let mut bytes = BytesMut::with_capacity(32);
+        bytes.put_u16_le(2019);
+        bytes.put_u8(1);
+        bytes.put_u8(2);
+        bytes.put_u8(3);
+        bytes.put_u8(3);
+        bytes.put_u64_le(u64::MAX);
+        bytes.put_f32_le(1.0);
+        bytes.put_u32_le(u32::MAX);
+        bytes.put_u8(0);
+        bytes.put_slice(b"SSTA");
+        bytes.put_slice(&[0u8; 5]);
+
+        bytes
+    }
+
+    #[test]
+    fn decode_nineteen_packet() {
+        let mut bytes = session_started();
+
+        match F1Codec.decode(&mut bytes).unwrap() {
+            Some(Packet::Event(packet)) => assert_eq!(Event::SessionStarted, *packet.event()),
+            _ => panic!("Expected an event packet"),
+        }
+
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn decode_with_raw_bytes() {
+        let datagram = session_started().freeze();
+
+        let packet = RawCodec
+            .decode(&mut BytesMut::from(&datagram[..]))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(&datagram, packet.bytes());
+        assert!(matches!(packet.into_packet(), Packet::Event(_)));
+    }
+
+    #[test]
+    fn decode_datagram_with_incomplete_packet() {
+        let error = decode_datagram(&[0xe3, 0x07, 1, 2]).unwrap_err();
+
+        assert_eq!(std::io::ErrorKind::UnexpectedEof, error.kind());
+    }
+}
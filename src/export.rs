@@ -0,0 +1,278 @@
+//! Export of decoded packets to flat files
+//!
+//! The packets published by the F1 games are deeply nested, with most of them containing data for
+//! each of the 20 cars in a session. Tools like spreadsheets or data frames work best with flat
+//! tables, though. This module flattens the packets into one table per packet type, with one row
+//! per car and packet, and writes the tables to files in a directory.
+//!
+//! CSV export is always available. Exporting JSON requires the `json` feature, and exporting
+//! Parquet the `parquet` feature.
+//!
+//! Besides flat tables, the telemetry of a single car can be exported as CSV for video overlays, and
+//! to the log files of MoTeC i2 with the `motec` feature. Laps can be exported as GPX or KML to be
+//! viewed in mapping tools, and the learned map of a track can be rendered as SVG.
+//!
+//! With the `ndarray` feature, the data of a session can be converted into matrices for analysis
+//! and machine learning pipelines in Rust.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Error, ErrorKind};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::packet::header::PacketType;
+use crate::packet::Packet;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+use crate::sink::{ExportFuture, Exporter, Item};
+
+mod csv;
+#[cfg(feature = "json")]
+pub(crate) mod json;
+#[cfg(feature = "parquet")]
+mod parquet;
+
+#[cfg(feature = "ndarray")]
+pub mod dataframe;
+pub mod geo;
+#[cfg(feature = "motec")]
+pub mod motec;
+pub mod overlay;
+pub mod svg;
+pub mod table;
+
+/// Type of the values in a column
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum ColumnType {
+    Bool,
+    Float,
+    Int,
+    Text,
+}
+
+/// Column in a flat table
+#[derive(Debug, PartialEq, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct Column {
+    /// The name of the column.
+    pub name: String,
+
+    /// The type of the values in the column.
+    pub column_type: ColumnType,
+}
+
+impl Column {
+    /// Create a new column with the given name and type.
+    pub fn new(name: impl Into<String>, column_type: ColumnType) -> Self {
+        Column {
+            name: name.into(),
+            column_type,
+        }
+    }
+}
+
+/// Value in a cell of a flat table
+///
+/// Some values are only published for a subset of the cars, for example the additional motion data
+/// that is only sent for the player's car. Cells without a value contain `Null`.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Float(f32),
+    Int(i64),
+    Text(String),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// File formats that packets can be exported to
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum Format {
+    /// Comma-separated values with a header row.
+    Csv,
+
+    /// A JSON array with one object per row.
+    Json,
+
+    /// Apache Parquet files with one column per field.
+    Parquet,
+}
+
+impl Format {
+    /// Returns the file extension for the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            "parquet" => Ok(Format::Parquet),
+            format => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown export format {}.", format),
+            )),
+        }
+    }
+}
+
+/// Writer for a single flat table
+pub(crate) trait TableWriter {
+    /// Append a row to the table.
+    fn write_row(&mut self, row: &[Value]) -> Result<(), Error>;
+
+    /// Write any buffered rows and close the table.
+    fn finish(self: Box<Self>) -> Result<(), Error>;
+}
+
+/// Writer that exports packets to one flat file per packet type
+///
+/// The writer creates a file for each packet type the first time it receives a packet of this type.
+/// The files are named after the packet type, e.g. `lap.csv` or `telemetry.parquet`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use f1_api::export::{FlatFileWriter, Format};
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut writer = FlatFileWriter::new("export", Format::Csv).unwrap();
+///
+/// for packet in packets() {
+///     writer.write(&packet).unwrap();
+/// }
+///
+/// writer.finish().unwrap();
+/// ```
+pub struct FlatFileWriter {
+    directory: PathBuf,
+    format: Format,
+    tables: HashMap<PacketType, Box<dyn TableWriter + Send>>,
+}
+
+impl FlatFileWriter {
+    /// Create a new writer that writes files in the given format to the given directory.
+    ///
+    /// The directory is created if it does not exist yet. An error is returned if the format
+    /// requires a feature that has not been enabled.
+    pub fn new(directory: impl Into<PathBuf>, format: Format) -> Result<Self, Error> {
+        match format {
+            #[cfg(not(feature = "json"))]
+            Format::Json => return Err(missing_feature("json")),
+            #[cfg(not(feature = "parquet"))]
+            Format::Parquet => return Err(missing_feature("parquet")),
+            _ => {}
+        }
+
+        let directory = directory.into();
+        create_dir_all(&directory)?;
+
+        Ok(FlatFileWriter {
+            directory,
+            format,
+            tables: HashMap::new(),
+        })
+    }
+
+    /// Flatten a packet and append its rows to the file for its packet type.
+    pub fn write(&mut self, packet: &Packet) -> Result<(), Error> {
+        let packet_type = table::packet_type(packet);
+
+        if !self.tables.contains_key(&packet_type) {
+            let writer = self.create_table(packet_type)?;
+            self.tables.insert(packet_type, writer);
+        }
+
+        let writer = self.tables.get_mut(&packet_type).unwrap();
+
+        for row in table::rows(packet) {
+            writer.write_row(&row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write any buffered data and close all files.
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.finish_tables()
+    }
+
+    fn finish_tables(&mut self) -> Result<(), Error> {
+        for (_, writer) in self.tables.drain() {
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+
+    fn create_table(&self, packet_type: PacketType) -> Result<Box<dyn TableWriter + Send>, Error> {
+        let columns = table::columns(packet_type);
+        let path = self.directory.join(format!(
+            "{}.{}",
+            table::name(packet_type),
+            self.format.extension()
+        ));
+        let file = BufWriter::new(File::create(path)?);
+
+        match self.format {
+            Format::Csv => Ok(Box::new(csv::CsvTable::new(file, &columns)?)),
+            #[cfg(feature = "json")]
+            Format::Json => Ok(Box::new(json::JsonTable::new(file, columns)?)),
+            #[cfg(feature = "parquet")]
+            Format::Parquet => Ok(Box::new(parquet::ParquetTable::new(file, columns)?)),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("Unsupported formats are rejected when the writer is created"),
+        }
+    }
+}
+
+/// Packets are written to their files, while changes are ignored. Shutting the writer down closes
+/// all files.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+impl Exporter for FlatFileWriter {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        let result = match item {
+            Item::Packet(packet) => self.write(packet),
+            Item::Change(..) => Ok(()),
+        };
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn shutdown(&mut self) -> ExportFuture<'_> {
+        Box::pin(std::future::ready(self.finish_tables()))
+    }
+}
+
+#[cfg(not(all(feature = "json", feature = "parquet")))]
+fn missing_feature(feature: &str) -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "Exporting this format requires the `{}` feature of f1-api.",
+            feature
+        ),
+    )
+}
@@ -0,0 +1,85 @@
+//! Writer for CSV files
+
+use std::io::{Error, Write};
+
+use crate::export::{Column, TableWriter, Value};
+
+/// Table that is written as comma-separated values
+///
+/// The first line of the file contains the names of the columns. Text that contains a comma, a
+/// quote, or a line break is quoted as described in RFC 4180.
+pub(crate) struct CsvTable<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> CsvTable<W> {
+    /// Create a new table and write the header row.
+    pub fn new(mut writer: W, columns: &[Column]) -> Result<Self, Error> {
+        let names: Vec<String> = columns.iter().map(|column| escape(&column.name)).collect();
+        writeln!(writer, "{}", names.join(","))?;
+
+        Ok(CsvTable { writer })
+    }
+
+    /// Write any buffered rows without closing the table.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()
+    }
+}
+
+impl<W: Write> TableWriter for CsvTable<W> {
+    fn write_row(&mut self, row: &[Value]) -> Result<(), Error> {
+        let cells: Vec<String> = row.iter().map(|value| escape(&value.to_string())).collect();
+        writeln!(self.writer, "{}", cells.join(","))
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Error> {
+        self.writer.flush()
+    }
+}
+
+fn escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        String::from(cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::csv::{escape, CsvTable};
+    use crate::export::{Column, ColumnType, TableWriter, Value};
+
+    #[test]
+    fn write_table() {
+        let columns = vec![
+            Column::new("name", ColumnType::Text),
+            Column::new("speed", ColumnType::Int),
+            Column::new("brake", ColumnType::Float),
+        ];
+
+        let mut bytes = Vec::new();
+        let mut table = CsvTable::new(&mut bytes, &columns).unwrap();
+        table
+            .write_row(&[
+                Value::Text(String::from("Player")),
+                Value::Int(312),
+                Value::Null,
+            ])
+            .unwrap();
+        Box::new(table).finish().unwrap();
+
+        assert_eq!(
+            "name,speed,brake\nPlayer,312,\n",
+            String::from_utf8(bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn escape_with_special_characters() {
+        assert_eq!("plain", escape("plain"));
+        assert_eq!("\"a,b\"", escape("a,b"));
+        assert_eq!("\"say \"\"hi\"\"\"", escape("say \"hi\""));
+    }
+}
@@ -0,0 +1,201 @@
+//! Conversion of session data to ndarray matrices
+//!
+//! Analysis and machine learning pipelines in Rust work with numeric matrices rather than files.
+//! This module converts the series that are returned by `SessionData` into `ndarray` arrays, with
+//! one row per sample and one column per channel, so that telemetry can be consumed without a round
+//! trip through CSV.
+//!
+//! Channels are grouped by their purpose, so that related channels can be converted together.
+
+use std::ops::RangeInclusive;
+
+use ndarray::{Array1, Array2, ArrayView1};
+
+use crate::query::{Channel, Corner, Series, SessionData};
+use crate::types::VehicleIndex;
+
+/// Corners of a car in the order of their columns
+const CORNERS: [Corner; 4] = [
+    Corner::FrontLeft,
+    Corner::FrontRight,
+    Corner::RearLeft,
+    Corner::RearRight,
+];
+
+/// Group of related channels
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum ChannelGroup {
+    /// Speed, inputs of the driver, gear, engine RPM, and DRS
+    Inputs,
+
+    /// Temperatures of the brakes, tyres, and engine
+    Temperatures,
+
+    /// Pressure and wear of the tyres
+    Tyres,
+
+    /// Fuel and energy in the ERS store
+    Energy,
+}
+
+impl ChannelGroup {
+    /// Returns the channels of the group, in the order of their columns.
+    pub fn channels(self) -> Vec<Channel> {
+        match self {
+            ChannelGroup::Inputs => vec![
+                Channel::Speed,
+                Channel::Throttle,
+                Channel::Brake,
+                Channel::Steering,
+                Channel::Gear,
+                Channel::EngineRpm,
+                Channel::Drs,
+            ],
+            ChannelGroup::Temperatures => CORNERS
+                .iter()
+                .map(|corner| Channel::BrakeTemperature(*corner))
+                .chain(
+                    CORNERS
+                        .iter()
+                        .map(|corner| Channel::TyreSurfaceTemperature(*corner)),
+                )
+                .chain(
+                    CORNERS
+                        .iter()
+                        .map(|corner| Channel::TyreInnerTemperature(*corner)),
+                )
+                .chain(std::iter::once(Channel::EngineTemperature))
+                .collect(),
+            ChannelGroup::Tyres => CORNERS
+                .iter()
+                .map(|corner| Channel::TyrePressure(*corner))
+                .chain(CORNERS.iter().map(|corner| Channel::TyreWear(*corner)))
+                .collect(),
+            ChannelGroup::Energy => vec![Channel::FuelRemaining, Channel::ErsEnergy],
+        }
+    }
+}
+
+/// Matrix of the values of a car
+///
+/// The matrix has one row per sample and one column per channel. The session time, lap, and lap
+/// distance of each row are stored next to it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DataFrame {
+    channels: Vec<Channel>,
+    session_time: Array1<f64>,
+    lap_number: Array1<u8>,
+    lap_distance: Array1<f32>,
+    values: Array2<f32>,
+}
+
+impl DataFrame {
+    /// Query the channels of a group for a range of laps, and convert them to a matrix.
+    pub fn query(
+        data: &SessionData,
+        vehicle_index: VehicleIndex,
+        group: ChannelGroup,
+        laps: RangeInclusive<u8>,
+    ) -> Self {
+        DataFrame::from(&data.series(vehicle_index, &group.channels(), laps))
+    }
+
+    /// Returns the channels of the columns.
+    pub fn channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
+    /// Returns the session time of each row in seconds.
+    pub fn session_time(&self) -> &Array1<f64> {
+        &self.session_time
+    }
+
+    /// Returns the lap of each row.
+    pub fn lap_number(&self) -> &Array1<u8> {
+        &self.lap_number
+    }
+
+    /// Returns the lap distance of each row in meters.
+    pub fn lap_distance(&self) -> &Array1<f32> {
+        &self.lap_distance
+    }
+
+    /// Returns the matrix of values, with one row per sample and one column per channel.
+    pub fn values(&self) -> &Array2<f32> {
+        &self.values
+    }
+
+    /// Returns the values of a single channel, or `None` if the channel is not in the frame.
+    pub fn column(&self, channel: Channel) -> Option<ArrayView1<'_, f32>> {
+        let index = self.channels.iter().position(|c| *c == channel)?;
+        Some(self.values.column(index))
+    }
+
+    /// Consume the frame and return its matrix of values.
+    pub fn into_values(self) -> Array2<f32> {
+        self.values
+    }
+}
+
+impl From<&Series> for DataFrame {
+    fn from(series: &Series) -> Self {
+        let channels = series.channels().clone();
+        let mut values = Array2::from_elem((series.len(), channels.len()), f32::NAN);
+
+        for (index, channel) in channels.iter().enumerate() {
+            if let Some(column) = series.column(*channel) {
+                values.column_mut(index).assign(&ArrayView1::from(column));
+            }
+        }
+
+        DataFrame {
+            channels,
+            session_time: series
+                .session_time()
+                .iter()
+                .map(|time| time.as_secs_f64())
+                .collect(),
+            lap_number: Array1::from(series.lap_number().clone()),
+            lap_distance: Array1::from(series.lap_distance().clone()),
+            values,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "decode"))]
+mod tests {
+    use crate::export::dataframe::{ChannelGroup, DataFrame};
+    use crate::fixtures;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::query::{Channel, Corner, SessionData};
+
+    #[test]
+    fn query_converts_group_to_matrix() {
+        let data: SessionData = [PacketType::Lap, PacketType::Telemetry]
+            .iter()
+            .flat_map(|packet_type| fixtures::packets(ApiSpec::Nineteen, *packet_type).unwrap())
+            .collect();
+
+        let frame = DataFrame::query(&data, 0, ChannelGroup::Inputs, 0..=u8::MAX);
+        let speed = frame.column(Channel::Speed).unwrap();
+
+        assert_eq!((2, 7), frame.values().dim());
+        assert_eq!(2, frame.lap_number().len());
+        assert_eq!(
+            data.series(0, &[Channel::Speed], 0..=u8::MAX)
+                .column(Channel::Speed)
+                .unwrap(),
+            speed.to_vec().as_slice()
+        );
+        assert_eq!(None, frame.column(Channel::FuelRemaining));
+
+        let tyres = DataFrame::query(&data, 0, ChannelGroup::Tyres, 0..=u8::MAX);
+
+        assert_eq!(8, tyres.channels().len());
+        assert!(tyres
+            .column(Channel::TyreWear(Corner::FrontLeft))
+            .unwrap()
+            .iter()
+            .all(|wear| wear.is_nan()));
+    }
+}
@@ -0,0 +1,253 @@
+//! Export of car trajectories to GPX and KML
+//!
+//! Mapping tools like Google Earth or GPS analysis software read trajectories as GPX or KML files.
+//! The F1 games place the cars in a local coordinate system in meters, which is mapped onto the real
+//! world with a `Georeference` for the track. The location of every track in the games is known,
+//! which makes it possible to view a lap next to real-world onboard data of the same circuit.
+//!
+//! The games do not publish how their coordinate system is aligned with the real world. The
+//! georeference for a track places the origin of the game world at the circuit and assumes that the
+//! z axis points north. The rotation and origin can be adjusted to line up the lap with satellite
+//! imagery.
+
+use std::io::{Error, Write};
+
+use derive_new::new;
+use getset::CopyGetters;
+
+use crate::analysis::trace::LapTrace;
+use crate::packet::session::Track;
+use crate::types::Property3D;
+
+/// Length of a degree of latitude in meters
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Placement of the game world in the real world
+///
+/// The georeference maps the origin of the game world to a latitude and longitude. The x axis of
+/// the game world points east and the z axis north, after both have been rotated clockwise by the
+/// rotation of the georeference.
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct Georeference {
+    /// Returns the latitude of the origin of the game world in degrees.
+    #[getset(get_copy = "pub")]
+    latitude: f64,
+
+    /// Returns the longitude of the origin of the game world in degrees.
+    #[getset(get_copy = "pub")]
+    longitude: f64,
+
+    /// Returns the clockwise rotation of the game world in degrees.
+    #[getset(get_copy = "pub")]
+    rotation: f64,
+}
+
+impl Georeference {
+    /// Returns the georeference for a track, or `None` if the track is unknown.
+    ///
+    /// The origin is placed at the center of the real-world circuit, without any rotation.
+    pub fn for_track(track: Track) -> Option<Self> {
+        let (latitude, longitude) = match track {
+            Track::AbuDhabi => (24.4672, 54.6031),
+            Track::Austria => (47.2197, 14.7647),
+            Track::Azerbaijan => (40.3725, 49.8533),
+            Track::Bahrain | Track::BahrainShort => (26.0325, 50.5106),
+            Track::Brazil => (-23.7036, -46.6997),
+            Track::Catalunya => (41.5700, 2.2611),
+            Track::Hockenheim => (49.3278, 8.5656),
+            Track::Hungaroring => (47.5789, 19.2486),
+            Track::Melbourne => (-37.8497, 144.9680),
+            Track::Mexico => (19.4042, -99.0907),
+            Track::Monaco => (43.7347, 7.4206),
+            Track::Montreal => (45.5000, -73.5228),
+            Track::Monza => (45.6156, 9.2811),
+            Track::PaulRicard => (43.2506, 5.7917),
+            Track::Shanghai => (31.3389, 121.2197),
+            Track::Silverstone | Track::SilverstoneShort => (52.0786, -1.0169),
+            Track::Singapore => (1.2914, 103.8644),
+            Track::Sochi => (43.4057, 39.9578),
+            Track::Spa => (50.4372, 5.9714),
+            Track::Suzuka | Track::SuzukaShort => (34.8431, 136.5407),
+            Track::Texas | Track::TexasShort => (30.1328, -97.6411),
+            Track::Unknown => return None,
+        };
+
+        Some(Georeference::new(latitude, longitude, 0.0))
+    }
+
+    /// Returns a copy of the georeference with the given rotation in degrees.
+    pub fn rotated(self, rotation: f64) -> Self {
+        Georeference { rotation, ..self }
+    }
+
+    /// Returns the latitude and longitude of a position in the game world.
+    pub fn locate(&self, position: &Property3D<f32>) -> (f64, f64) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let x = position.x() as f64;
+        let z = position.z() as f64;
+
+        let east = x * cos + z * sin;
+        let north = z * cos - x * sin;
+
+        let latitude = self.latitude + north / METERS_PER_DEGREE;
+        let longitude =
+            self.longitude + east / (METERS_PER_DEGREE * self.latitude.to_radians().cos());
+
+        (latitude, longitude)
+    }
+}
+
+/// Write the laps as tracks to a GPX file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// use f1_api::export::geo::{write_gpx, Georeference};
+/// use f1_api::packet::session::Track;
+/// # use f1_api::analysis::trace::LapTrace;
+/// # fn laps() -> Vec<LapTrace> { Vec::new() }
+///
+/// let georeference = Georeference::for_track(Track::Spa).unwrap();
+/// let file = File::create("laps.gpx").unwrap();
+///
+/// write_gpx(file, &georeference, &laps()).unwrap();
+/// ```
+pub fn write_gpx<W: Write>(
+    mut writer: W,
+    georeference: &Georeference,
+    laps: &[LapTrace],
+) -> Result<(), Error> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<gpx version="1.1" creator="f1-api" xmlns="http://www.topografix.com/GPX/1/1">"#
+    )?;
+
+    for lap in laps {
+        writeln!(writer, "  <trk>")?;
+        writeln!(writer, "    <name>{}</name>", name(lap))?;
+        writeln!(writer, "    <trkseg>")?;
+
+        for sample in lap.samples() {
+            let (latitude, longitude) = georeference.locate(sample.position());
+            writeln!(
+                writer,
+                r#"      <trkpt lat="{:.8}" lon="{:.8}"><ele>{:.2}</ele></trkpt>"#,
+                latitude,
+                longitude,
+                sample.position().y()
+            )?;
+        }
+
+        writeln!(writer, "    </trkseg>")?;
+        writeln!(writer, "  </trk>")?;
+    }
+
+    writeln!(writer, "</gpx>")?;
+    writer.flush()
+}
+
+/// Write the laps as line strings to a KML file.
+pub fn write_kml<W: Write>(
+    mut writer: W,
+    georeference: &Georeference,
+    laps: &[LapTrace],
+) -> Result<(), Error> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(writer, "  <Document>")?;
+
+    for lap in laps {
+        writeln!(writer, "    <Placemark>")?;
+        writeln!(writer, "      <name>{}</name>", name(lap))?;
+        writeln!(writer, "      <LineString>")?;
+        writeln!(writer, "        <coordinates>")?;
+
+        for sample in lap.samples() {
+            let (latitude, longitude) = georeference.locate(sample.position());
+            writeln!(
+                writer,
+                "          {:.8},{:.8},{:.2}",
+                longitude,
+                latitude,
+                sample.position().y()
+            )?;
+        }
+
+        writeln!(writer, "        </coordinates>")?;
+        writeln!(writer, "      </LineString>")?;
+        writeln!(writer, "    </Placemark>")?;
+    }
+
+    writeln!(writer, "  </Document>")?;
+    writeln!(writer, "</kml>")?;
+    writer.flush()
+}
+
+fn name(lap: &LapTrace) -> String {
+    format!("Car {} lap {}", lap.vehicle_index(), lap.lap_number())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::analysis::trace::{LapTrace, TraceSample};
+    use crate::export::geo::{write_gpx, write_kml, Georeference};
+    use crate::packet::telemetry::Gear;
+    use crate::types::Property3D;
+
+    fn lap() -> LapTrace {
+        let sample = TraceSample::new(
+            0.0,
+            Duration::default(),
+            Property3D::new(0.0, 10.0, 111.32),
+            200.0,
+            1.0,
+            0.0,
+            0.0,
+            Gear::Sixth,
+            10000.0,
+        );
+
+        LapTrace::new(3, 2, Duration::default(), 5.0, vec![sample])
+    }
+
+    #[test]
+    fn locate_with_rotation() {
+        let position = Property3D::new(0.0, 0.0, 111.32);
+
+        let (latitude, longitude) = Georeference::new(0.0, 0.0, 0.0).locate(&position);
+        assert_approx_eq!(0.001, latitude);
+        assert_approx_eq!(0.0, longitude);
+
+        let (latitude, longitude) = Georeference::new(0.0, 0.0, 90.0).locate(&position);
+        assert_approx_eq!(0.0, latitude);
+        assert_approx_eq!(0.001, longitude);
+    }
+
+    #[test]
+    fn write_gpx_with_track_per_lap() {
+        let mut bytes = Vec::new();
+        write_gpx(&mut bytes, &Georeference::new(10.0, 20.0, 0.0), &[lap()]).unwrap();
+
+        let gpx = String::from_utf8(bytes).unwrap();
+
+        assert!(gpx.contains("<name>Car 3 lap 2</name>"));
+        assert!(gpx.contains(r#"<trkpt lat="10.00100000" lon="20.00000000"><ele>10.00</ele>"#));
+    }
+
+    #[test]
+    fn write_kml_with_longitude_first() {
+        let mut bytes = Vec::new();
+        write_kml(&mut bytes, &Georeference::new(10.0, 20.0, 0.0), &[lap()]).unwrap();
+
+        let kml = String::from_utf8(bytes).unwrap();
+
+        assert!(kml.contains("20.00000000,10.00100000,10.00"));
+    }
+}
@@ -0,0 +1,103 @@
+//! Writer for JSON files
+
+use std::io::{Error, Write};
+
+use serde_json::Number;
+
+use crate::export::{Column, TableWriter, Value};
+
+/// Table that is written as a JSON array of objects
+///
+/// Each row is written as an object that maps the names of the columns to their values. The rows
+/// are written as soon as they are received, so that large captures do not need to be kept in
+/// memory.
+pub(crate) struct JsonTable<W: Write> {
+    writer: W,
+    columns: Vec<Column>,
+    rows: usize,
+}
+
+impl<W: Write> JsonTable<W> {
+    /// Create a new table and open the JSON array.
+    pub fn new(mut writer: W, columns: Vec<Column>) -> Result<Self, Error> {
+        writer.write_all(b"[")?;
+
+        Ok(JsonTable {
+            writer,
+            columns,
+            rows: 0,
+        })
+    }
+}
+
+impl<W: Write> TableWriter for JsonTable<W> {
+    fn write_row(&mut self, row: &[Value]) -> Result<(), Error> {
+        if self.rows > 0 {
+            self.writer.write_all(b",")?;
+        }
+        self.writer.write_all(b"\n{")?;
+
+        // The object is written field by field to keep the fields in the order of the columns.
+        for (index, (column, value)) in self.columns.iter().zip(row.iter()).enumerate() {
+            if index > 0 {
+                self.writer.write_all(b",")?;
+            }
+
+            serde_json::to_writer(&mut self.writer, &column.name)?;
+            self.writer.write_all(b":")?;
+            serde_json::to_writer(&mut self.writer, &json_value(value))?;
+        }
+
+        self.writer.write_all(b"}")?;
+
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Error> {
+        self.writer.write_all(b"\n]\n")?;
+        self.writer.flush()
+    }
+}
+
+pub(crate) fn json_value(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Bool(value) => serde_json::Value::Bool(*value),
+        Value::Float(value) => match Number::from_f64(*value as f64) {
+            Some(number) => serde_json::Value::Number(number),
+            None => serde_json::Value::Null,
+        },
+        Value::Int(value) => serde_json::Value::Number(Number::from(*value)),
+        Value::Text(value) => serde_json::Value::String(value.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::json::JsonTable;
+    use crate::export::{Column, ColumnType, TableWriter, Value};
+
+    #[test]
+    fn write_table() {
+        let columns = vec![
+            Column::new("name", ColumnType::Text),
+            Column::new("speed", ColumnType::Int),
+        ];
+
+        let mut bytes = Vec::new();
+        let mut table = JsonTable::new(&mut bytes, columns).unwrap();
+        table
+            .write_row(&[Value::Text(String::from("Player")), Value::Int(312)])
+            .unwrap();
+        table.write_row(&[Value::Null, Value::Int(1)]).unwrap();
+        Box::new(table).finish().unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(2, json.as_array().unwrap().len());
+        assert_eq!("Player", json[0]["name"]);
+        assert_eq!(312, json[0]["speed"]);
+        assert!(json[1]["name"].is_null());
+    }
+}
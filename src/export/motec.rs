@@ -0,0 +1,573 @@
+//! Export of a car's telemetry to MoTeC i2 log files
+//!
+//! MoTeC i2 is the data analysis software that is used in many professional racing series, and
+//! that is popular among sim racers as well. It reads log files in the binary `.ld` format, which
+//! stores each channel as a series of samples at a fixed rate.
+//!
+//! The F1 games publish telemetry at the rate that is configured in the game, and packets can get
+//! lost on the network. The `MotecLog` samples the latest known values of a car at a fixed rate, so
+//! that the channels in the log file line up with the session time. The layout of the file follows
+//! the format that has been documented by the sim racing community, which i2 Standard and i2 Pro
+//! can both read.
+
+use std::io::{Error, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::packet::motion::Motion;
+use crate::packet::telemetry::Telemetry;
+use crate::packet::Packet;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+use crate::sink::{ExportFuture, Exporter, Item};
+use crate::types::VehicleIndex;
+
+/// Size of the file header in bytes
+const HEADER_SIZE: u32 = 1762;
+
+/// Size of the event block in bytes
+const EVENT_SIZE: u32 = 1154;
+
+/// Size of the venue block in bytes
+const VENUE_SIZE: u32 = 1100;
+
+/// Size of the vehicle block in bytes
+const VEHICLE_SIZE: u32 = 260;
+
+/// Size of the header of a channel in bytes
+const CHANNEL_SIZE: u32 = 124;
+
+/// Channels that are written to the log file, with their name, short name, and unit
+const CHANNELS: [(&str, &str, &str); 13] = [
+    ("Ground Speed", "Speed", "km/h"),
+    ("Throttle Pos", "Throttle", "%"),
+    ("Brake Pos", "Brake", "%"),
+    ("Steering Pos", "Steer", "%"),
+    ("Gear", "Gear", ""),
+    ("Engine RPM", "RPM", "rpm"),
+    ("Tyre Temp FL", "TTempFL", "C"),
+    ("Tyre Temp FR", "TTempFR", "C"),
+    ("Tyre Temp RL", "TTempRL", "C"),
+    ("Tyre Temp RR", "TTempRR", "C"),
+    ("G Force Lat", "GLat", "G"),
+    ("G Force Long", "GLong", "G"),
+    ("G Force Vert", "GVert", "G"),
+];
+
+/// Values of all channels at a point in time
+type Sample = [f32; CHANNELS.len()];
+
+/// Log of a car's telemetry that can be written as a MoTeC `.ld` file
+///
+/// The log records the player's car by default. Its channels are sampled at a fixed rate, using the
+/// latest telemetry and motion data that has been received for the car. Sampling starts with the
+/// first telemetry packet, and restarts when a new session begins.
+///
+/// The name of the driver and the venue are taken from the participants and session packets.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::time::SystemTime;
+///
+/// use f1_api::export::motec::MotecLog;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut log = MotecLog::new(60).recorded_at(SystemTime::now());
+///
+/// for packet in packets() {
+///     log.update(&packet);
+/// }
+///
+/// log.write(File::create("session.ld").unwrap()).unwrap();
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct MotecLog {
+    hertz: u16,
+    vehicle_index: Option<VehicleIndex>,
+    recorded_at: Option<SystemTime>,
+    driver: String,
+    venue: String,
+    comment: String,
+    session_uid: Option<u64>,
+    start: Option<Duration>,
+    telemetry: Option<Telemetry>,
+    motion: Option<Motion>,
+    samples: Vec<Sample>,
+}
+
+impl MotecLog {
+    /// Create a new log that samples its channels at the given rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rate is zero.
+    pub fn new(hertz: u16) -> Self {
+        assert!(hertz > 0, "The sample rate must be greater than zero");
+
+        MotecLog {
+            hertz,
+            vehicle_index: None,
+            recorded_at: None,
+            driver: String::new(),
+            venue: String::new(),
+            comment: String::new(),
+            session_uid: None,
+            start: None,
+            telemetry: None,
+            motion: None,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Record the car with the given index instead of the player's car.
+    pub fn vehicle_index(mut self, vehicle_index: VehicleIndex) -> Self {
+        self.vehicle_index = Some(vehicle_index);
+        self
+    }
+
+    /// Set the time at which the session was recorded, which is shown in i2.
+    pub fn recorded_at(mut self, time: SystemTime) -> Self {
+        self.recorded_at = Some(time);
+        self
+    }
+
+    /// Set a short comment that is shown in i2.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    /// Returns the number of samples that have been recorded for each channel.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns true if no samples have been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Update the log with a new packet.
+    pub fn update(&mut self, packet: &Packet) {
+        let header = packet.header();
+
+        if self.session_uid != Some(header.session_uid()) {
+            self.session_uid = Some(header.session_uid());
+            self.start = None;
+            self.telemetry = None;
+            self.motion = None;
+            self.samples.clear();
+        }
+
+        // Samples before this packet hold the values that were known until now
+        let time = *header.session_time();
+        self.sample(|next| next < time);
+
+        let index = self
+            .vehicle_index
+            .unwrap_or_else(|| header.player_car_index()) as usize;
+
+        match packet {
+            Packet::Motion(packet) => {
+                if let Some(motion) = packet.cars().get(index) {
+                    self.motion = Some(*motion);
+                }
+            }
+            Packet::Participants(packet) => {
+                if let Some(participant) = packet.participants().get(index) {
+                    self.driver = participant.name().clone();
+                }
+            }
+            Packet::Session(packet) => self.venue = format!("{:?}", packet.track()),
+            Packet::Telemetry(packet) => {
+                if let Some(telemetry) = packet.telemetry().get(index) {
+                    self.telemetry = Some(*telemetry);
+                    self.start.get_or_insert(time);
+                }
+            }
+            _ => {}
+        }
+
+        self.sample(|next| next <= time);
+    }
+
+    /// Write the log to the given writer in the `.ld` format.
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        let channel_count = CHANNELS.len() as u32;
+        let event_pointer = HEADER_SIZE;
+        let venue_pointer = event_pointer + EVENT_SIZE;
+        let vehicle_pointer = venue_pointer + VENUE_SIZE;
+        let channel_pointer = vehicle_pointer + VEHICLE_SIZE;
+        let data_pointer = channel_pointer + channel_count * CHANNEL_SIZE;
+        let (date, time) = self.recorded_at.map(format_time).unwrap_or_default();
+
+        let mut bytes = Vec::new();
+
+        // File header
+        put_u32(&mut bytes, 0x40);
+        pad(&mut bytes, 4);
+        put_u32(&mut bytes, channel_pointer);
+        put_u32(&mut bytes, data_pointer);
+        pad(&mut bytes, 20);
+        put_u32(&mut bytes, event_pointer);
+        pad(&mut bytes, 24);
+        put_u16(&mut bytes, 1);
+        put_u16(&mut bytes, 0x4240);
+        put_u16(&mut bytes, 0xf);
+        put_u32(&mut bytes, 0x1f44);
+        put_str(&mut bytes, "ADL", 8);
+        put_u16(&mut bytes, 420);
+        put_u16(&mut bytes, 0xadb0);
+        put_u32(&mut bytes, channel_count);
+        pad(&mut bytes, 4);
+        put_str(&mut bytes, &date, 16);
+        pad(&mut bytes, 16);
+        put_str(&mut bytes, &time, 16);
+        pad(&mut bytes, 16);
+        put_str(&mut bytes, &self.driver, 64);
+        put_str(&mut bytes, "F1", 64);
+        pad(&mut bytes, 64);
+        put_str(&mut bytes, &self.venue, 64);
+        pad(&mut bytes, 64 + 1024);
+        put_u32(&mut bytes, 0xc81a4);
+        pad(&mut bytes, 66);
+        put_str(&mut bytes, &self.comment, 64);
+        pad(&mut bytes, 126);
+
+        // Event, venue, and vehicle
+        put_str(&mut bytes, "F1", 64);
+        put_str(&mut bytes, "", 64);
+        put_str(&mut bytes, &self.comment, 1024);
+        put_u16(&mut bytes, venue_pointer as u16);
+        put_str(&mut bytes, &self.venue, 64);
+        pad(&mut bytes, 1034);
+        put_u16(&mut bytes, vehicle_pointer as u16);
+        put_str(&mut bytes, "F1", 64);
+        pad(&mut bytes, 128);
+        put_u32(&mut bytes, 0);
+        put_str(&mut bytes, "Car", 32);
+        put_str(&mut bytes, "", 32);
+
+        // Channel headers, which form a doubly linked list
+        let sample_count = self.samples.len() as u32;
+
+        for (index, (name, short_name, unit)) in CHANNELS.iter().enumerate() {
+            let index = index as u32;
+            let pointer = channel_pointer + index * CHANNEL_SIZE;
+            let previous = if index == 0 {
+                0
+            } else {
+                pointer - CHANNEL_SIZE
+            };
+            let next = if index + 1 == channel_count {
+                0
+            } else {
+                pointer + CHANNEL_SIZE
+            };
+
+            put_u32(&mut bytes, previous);
+            put_u32(&mut bytes, next);
+            put_u32(&mut bytes, data_pointer + index * sample_count * 4);
+            put_u32(&mut bytes, sample_count);
+            put_u16(&mut bytes, 0x2ee1 + index as u16);
+            // Samples are stored as 32-bit floats
+            put_u16(&mut bytes, 0x07);
+            put_u16(&mut bytes, 4);
+            put_u16(&mut bytes, self.hertz);
+            // Shift, multiplier, scale, and decimal places of the samples
+            put_u16(&mut bytes, 0);
+            put_u16(&mut bytes, 1);
+            put_u16(&mut bytes, 1);
+            put_u16(&mut bytes, 0);
+            put_str(&mut bytes, name, 32);
+            put_str(&mut bytes, short_name, 8);
+            put_str(&mut bytes, unit, 12);
+            pad(&mut bytes, 40);
+        }
+
+        // Channel data
+        for channel in 0..CHANNELS.len() {
+            for sample in &self.samples {
+                bytes.extend_from_slice(&sample[channel].to_le_bytes());
+            }
+        }
+
+        writer.write_all(&bytes)?;
+        writer.flush()
+    }
+
+    fn sample(&mut self, is_due: impl Fn(Duration) -> bool) {
+        let telemetry = match &self.telemetry {
+            Some(telemetry) => telemetry,
+            None => return,
+        };
+        let start = match self.start {
+            Some(start) => start,
+            None => return,
+        };
+
+        let mut next = start + self.offset(self.samples.len());
+
+        while is_due(next) {
+            let sample = sample(telemetry, self.motion.as_ref());
+            self.samples.push(sample);
+            next = start + self.offset(self.samples.len());
+        }
+    }
+
+    fn offset(&self, samples: usize) -> Duration {
+        Duration::from_secs_f64(samples as f64 / self.hertz as f64)
+    }
+}
+
+/// Exporter that records a MoTeC log, and writes it when it is shut down
+///
+/// The header of the `.ld` format contains the number of samples, so the log is kept in memory
+/// until the end of the session, and written to the writer once.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// use f1_api::export::motec::{MotecLog, MotecWriter};
+/// use f1_api::sink::Fanout;
+/// # use f1_api::packet::Packet;
+/// # async fn example(packets: impl tokio_stream::Stream<Item = Packet> + Unpin) {
+/// let file = File::create("session.ld").unwrap();
+///
+/// Fanout::new()
+///     .exporter(MotecWriter::new(MotecLog::new(60), file))
+///     .serve(packets)
+///     .await;
+/// # }
+/// ```
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+pub struct MotecWriter<W> {
+    log: MotecLog,
+    writer: W,
+}
+
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+impl<W: Write + Send> MotecWriter<W> {
+    /// Create a new exporter that records the given log, and writes it to the writer.
+    pub fn new(log: MotecLog, writer: W) -> Self {
+        MotecWriter { log, writer }
+    }
+
+    /// Returns the log that has been recorded so far.
+    pub fn log(&self) -> &MotecLog {
+        &self.log
+    }
+}
+
+/// Packets are recorded in the log, while changes are ignored. The log is written on shutdown.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+impl<W: Write + Send> Exporter for MotecWriter<W> {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        if let Item::Packet(packet) = item {
+            self.log.update(packet);
+        }
+
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn shutdown(&mut self) -> ExportFuture<'_> {
+        Box::pin(std::future::ready(self.log.write(&mut self.writer)))
+    }
+}
+
+fn sample(telemetry: &Telemetry, motion: Option<&Motion>) -> Sample {
+    let temperature = telemetry.tyre_surface_temperature();
+    let g_force = motion.map(|motion| *motion.g_force()).unwrap_or_default();
+
+    [
+        telemetry.speed() as f32,
+        telemetry.throttle() * 100.0,
+        telemetry.brake() * 100.0,
+        telemetry.steering() * 100.0,
+        telemetry.gear() as i8 as f32,
+        telemetry.engine_rpm() as f32,
+        temperature.front_left() as f32,
+        temperature.front_right() as f32,
+        temperature.rear_left() as f32,
+        temperature.rear_right() as f32,
+        g_force.x(),
+        g_force.y(),
+        g_force.z(),
+    ]
+}
+
+/// Format the time as the date and time of day in UTC, the way i2 expects them.
+fn format_time(time: SystemTime) -> (String, String) {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let (days, seconds) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    // Conversion from days to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (
+        format!("{:02}/{:02}/{}", day, month, year),
+        format!(
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        ),
+    )
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Append the string as a fixed-length field, truncating or padding it with zeros.
+fn put_str(bytes: &mut Vec<u8>, value: &str, length: usize) {
+    let value = value.as_bytes();
+    let length_of_value = value.len().min(length);
+
+    bytes.extend_from_slice(&value[..length_of_value]);
+    pad(bytes, length - length_of_value);
+}
+
+fn pad(bytes: &mut Vec<u8>, length: usize) {
+    bytes.resize(bytes.len() + length, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[cfg(feature = "net")]
+    use crate::export::motec::MotecWriter;
+    use crate::export::motec::{format_time, MotecLog, CHANNELS, CHANNEL_SIZE, HEADER_SIZE};
+    use crate::fixtures::{header, telemetry, telemetry_packet, with_corners};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+    #[cfg(feature = "net")]
+    use crate::sink::{Exporter, Item};
+    use crate::types::CornerProperty;
+
+    fn speed(millis: u64, speed: u16) -> Packet {
+        let telemetry = with_corners(
+            telemetry(speed, 1.0, 0.0, 0.0, Gear::Fifth, 11000),
+            CornerProperty::default(),
+            CornerProperty::new(90, 91, 92, 93),
+            CornerProperty::default(),
+        );
+
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+            vec![telemetry],
+        )
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn update_samples_at_fixed_rate() {
+        let mut log = MotecLog::new(10);
+
+        log.update(&speed(1000, 100));
+        log.update(&speed(1150, 200));
+        log.update(&speed(1320, 300));
+
+        // Samples at 1.0s, 1.1s, 1.2s, and 1.3s
+        assert_eq!(4, log.len());
+        assert_eq!(100.0, log.samples[0][0]);
+        assert_eq!(100.0, log.samples[1][0]);
+        assert_eq!(200.0, log.samples[2][0]);
+        assert_eq!(200.0, log.samples[3][0]);
+        assert_eq!(5.0, log.samples[3][4]);
+        assert_eq!(93.0, log.samples[3][9]);
+    }
+
+    #[test]
+    fn write_log() {
+        let mut log = MotecLog::new(10).recorded_at(UNIX_EPOCH);
+
+        log.update(&speed(0, 100));
+        log.update(&speed(100, 200));
+
+        let mut bytes = Vec::new();
+        log.write(&mut bytes).unwrap();
+
+        let channel_pointer = u32_at(&bytes, 8) as usize;
+        let data_pointer = u32_at(&bytes, 12) as usize;
+
+        assert_eq!(0x40, u32_at(&bytes, 0));
+        assert_eq!(HEADER_SIZE as usize, u32_at(&bytes, 36) as usize);
+        assert_eq!(
+            data_pointer,
+            channel_pointer + CHANNELS.len() * CHANNEL_SIZE as usize
+        );
+        assert_eq!(data_pointer + CHANNELS.len() * 2 * 4, bytes.len());
+
+        // The first channel is the speed of the car
+        assert_eq!(data_pointer as u32, u32_at(&bytes, channel_pointer + 8));
+        assert_eq!(2, u32_at(&bytes, channel_pointer + 12));
+        assert_eq!(
+            b"Ground Speed",
+            &bytes[channel_pointer + 32..channel_pointer + 44]
+        );
+        assert_eq!(
+            200.0,
+            f32::from_le_bytes(
+                bytes[data_pointer + 4..data_pointer + 8]
+                    .try_into()
+                    .unwrap()
+            )
+        );
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn export_writes_log_on_shutdown() {
+        let mut bytes = Vec::new();
+        let mut writer = MotecWriter::new(MotecLog::new(10), &mut bytes);
+
+        writer.export(&Item::Packet(speed(0, 100))).await.unwrap();
+        writer.export(&Item::Packet(speed(100, 200))).await.unwrap();
+        assert_eq!(2, writer.log().len());
+
+        writer.shutdown().await.unwrap();
+
+        assert_eq!(0x40, u32_at(&bytes, 0));
+    }
+
+    #[test]
+    fn format_time_as_utc() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+
+        assert_eq!(
+            (String::from("13/09/2020"), String::from("12:26:40")),
+            format_time(time)
+        );
+    }
+}
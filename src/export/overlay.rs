@@ -0,0 +1,243 @@
+//! Export of a car's telemetry for video overlays
+//!
+//! Tools like RaceRender add gauges, a track map, and lap timers to onboard videos, using data that
+//! has been logged during the drive. They read CSV files with a time column and one column per
+//! channel, and draw the track map from GPS coordinates.
+//!
+//! The F1 games do not publish GPS coordinates, but the position of each car in the world. The
+//! `OverlayWriter` turns this position into a substitute latitude and longitude around the null
+//! island, so that a degree corresponds to the same distance in both directions and the shape of the
+//! track is preserved.
+
+use std::io::{Error, Write};
+use std::time::Duration;
+
+use crate::export::csv::CsvTable;
+use crate::export::{Column, ColumnType, TableWriter, Value};
+use crate::packet::Packet;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+use crate::sink::{ExportFuture, Exporter, Item};
+use crate::types::{Property3D, VehicleIndex};
+
+/// Length of a degree of latitude at the equator in meters
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Writer that exports the telemetry of a car as a CSV file for video overlays
+///
+/// The writer adds a row for every telemetry packet of the car, which is the player's car by
+/// default. The time in each row is relative to the first row of the session, and the position is
+/// taken from the latest motion packet.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// use f1_api::export::overlay::OverlayWriter;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut writer = OverlayWriter::new(File::create("overlay.csv").unwrap()).unwrap();
+///
+/// for packet in packets() {
+///     writer.write(&packet).unwrap();
+/// }
+///
+/// writer.finish().unwrap();
+/// ```
+pub struct OverlayWriter<W: Write> {
+    table: CsvTable<W>,
+    vehicle_index: Option<VehicleIndex>,
+    session_uid: Option<u64>,
+    start: Option<Duration>,
+    lap_number: Option<u8>,
+    position: Option<Property3D<f32>>,
+}
+
+impl<W: Write> OverlayWriter<W> {
+    /// Create a new writer and write the header row.
+    pub fn new(writer: W) -> Result<Self, Error> {
+        let columns = [
+            Column::new("Time", ColumnType::Float),
+            Column::new("Lap", ColumnType::Int),
+            Column::new("Latitude", ColumnType::Float),
+            Column::new("Longitude", ColumnType::Float),
+            Column::new("Speed (km/h)", ColumnType::Int),
+            Column::new("Gear", ColumnType::Int),
+            Column::new("RPM", ColumnType::Int),
+            Column::new("Throttle (%)", ColumnType::Float),
+            Column::new("Brake (%)", ColumnType::Float),
+            Column::new("Steering (%)", ColumnType::Float),
+        ];
+
+        Ok(OverlayWriter {
+            table: CsvTable::new(writer, &columns)?,
+            vehicle_index: None,
+            session_uid: None,
+            start: None,
+            lap_number: None,
+            position: None,
+        })
+    }
+
+    /// Export the car with the given index instead of the player's car.
+    pub fn vehicle_index(mut self, vehicle_index: VehicleIndex) -> Self {
+        self.vehicle_index = Some(vehicle_index);
+        self
+    }
+
+    /// Update the writer with a new packet, and write a row if it contains telemetry for the car.
+    pub fn write(&mut self, packet: &Packet) -> Result<(), Error> {
+        let header = packet.header();
+
+        if self.session_uid != Some(header.session_uid()) {
+            self.session_uid = Some(header.session_uid());
+            self.start = None;
+            self.lap_number = None;
+            self.position = None;
+        }
+
+        let index = self
+            .vehicle_index
+            .unwrap_or_else(|| header.player_car_index()) as usize;
+
+        match packet {
+            Packet::Lap(packet) => {
+                if let Some(lap) = packet.laps().get(index) {
+                    self.lap_number = Some(lap.current_lap_number());
+                }
+            }
+            Packet::Motion(packet) => {
+                if let Some(motion) = packet.cars().get(index) {
+                    self.position = Some(*motion.position());
+                }
+            }
+            Packet::Telemetry(packet) => {
+                if let Some(telemetry) = packet.telemetry().get(index) {
+                    let time = *header.session_time();
+                    let start = *self.start.get_or_insert(time);
+                    let (latitude, longitude) = match self.position {
+                        Some(position) => coordinates(&position),
+                        None => (Value::Null, Value::Null),
+                    };
+
+                    self.table.write_row(&[
+                        Value::Float(time.saturating_sub(start).as_secs_f32()),
+                        self.lap_number
+                            .map_or(Value::Null, |lap| Value::Int(lap as i64)),
+                        latitude,
+                        longitude,
+                        Value::Int(telemetry.speed() as i64),
+                        Value::Int(telemetry.gear() as i64),
+                        Value::Int(telemetry.engine_rpm() as i64),
+                        Value::Float(telemetry.throttle() * 100.0),
+                        Value::Float(telemetry.brake() * 100.0),
+                        Value::Float(telemetry.steering() * 100.0),
+                    ])?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Write any buffered rows.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.table.flush()
+    }
+
+    /// Write any buffered rows and close the file.
+    pub fn finish(self) -> Result<(), Error> {
+        Box::new(self.table).finish()
+    }
+}
+
+/// Packets are written as rows for the car, while changes are ignored.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+impl<W: Write + Send> Exporter for OverlayWriter<W> {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        let result = match item {
+            Item::Packet(packet) => self.write(packet),
+            Item::Change(..) => Ok(()),
+        };
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn flush(&mut self) -> ExportFuture<'_> {
+        Box::pin(std::future::ready(OverlayWriter::flush(self)))
+    }
+}
+
+/// Returns the substitute latitude and longitude for a position in the world.
+///
+/// The games use a coordinate system in which the y axis points up. The z axis is used as latitude
+/// and the x axis as longitude.
+fn coordinates(position: &Property3D<f32>) -> (Value, Value) {
+    let latitude = position.z() as f64 / METERS_PER_DEGREE;
+    let longitude = position.x() as f64 / METERS_PER_DEGREE;
+
+    (
+        Value::Text(format!("{:.8}", latitude)),
+        Value::Text(format!("{:.8}", longitude)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::export::overlay::OverlayWriter;
+    use crate::fixtures::{header, motion, motion_packet, telemetry, telemetry_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+    use crate::types::{CornerProperty, Property3D};
+
+    fn position(millis: u64, x: f32, z: f32) -> Packet {
+        let position = Property3D::new(x, 0.0, z);
+
+        motion_packet(
+            header(PacketType::Motion, 1, Duration::from_millis(millis), 0),
+            vec![motion(
+                position,
+                Property3D::default(),
+                Property3D::default(),
+            )],
+            CornerProperty::default(),
+        )
+    }
+
+    fn inputs(millis: u64) -> Packet {
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+            vec![telemetry(250, 1.0, -0.5, 0.0, Gear::Seventh, 11500)],
+        )
+    }
+
+    #[test]
+    fn write_rows_for_telemetry() {
+        let mut bytes = Vec::new();
+        let mut writer = OverlayWriter::new(&mut bytes).unwrap();
+
+        writer.write(&inputs(5000)).unwrap();
+        writer.write(&position(5010, 1113.2, -556.6)).unwrap();
+        writer.write(&inputs(5500)).unwrap();
+        writer.finish().unwrap();
+
+        let csv = String::from_utf8(bytes).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(3, lines.len());
+        assert_eq!(
+            "Time,Lap,Latitude,Longitude,Speed (km/h),Gear,RPM,Throttle (%),Brake (%),Steering (%)",
+            lines[0]
+        );
+        assert_eq!("0,,,,250,7,11500,100,0,-50", lines[1]);
+        assert_eq!(
+            "0.5,,-0.00500000,0.01000000,250,7,11500,100,0,-50",
+            lines[2]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Writer for Apache Parquet files
+
+use std::io::{Error, Write};
+use std::sync::Arc;
+
+use parquet::basic::{LogicalType, Repetition, Type as PhysicalType};
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, FloatType, Int64Type};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+
+use crate::export::{Column, ColumnType, TableWriter, Value};
+
+/// Number of rows that are buffered before they are written as a row group
+const ROW_GROUP_SIZE: usize = 16384;
+
+/// Values of a single column that have not been written yet
+///
+/// Parquet stores missing values by recording a definition level of 0 for them, and only storing
+/// the values that are present.
+struct ColumnBuffer {
+    values: Values,
+    definition_levels: Vec<i16>,
+}
+
+enum Values {
+    Bool(Vec<bool>),
+    Float(Vec<f32>),
+    Int(Vec<i64>),
+    Text(Vec<ByteArray>),
+}
+
+/// Table that is written as an Apache Parquet file
+///
+/// Rows are buffered in memory and written in row groups, with every column of the table being
+/// optional.
+pub(crate) struct ParquetTable<W: Write + Send> {
+    writer: SerializedFileWriter<W>,
+    buffers: Vec<ColumnBuffer>,
+    rows: usize,
+}
+
+impl<W: Write + Send> ParquetTable<W> {
+    /// Create a new table with a schema that is derived from the columns.
+    pub fn new(writer: W, columns: Vec<Column>) -> Result<Self, Error> {
+        let mut fields = Vec::with_capacity(columns.len());
+
+        for column in &columns {
+            let (physical_type, logical_type) = match column.column_type {
+                ColumnType::Bool => (PhysicalType::BOOLEAN, None),
+                ColumnType::Float => (PhysicalType::FLOAT, None),
+                ColumnType::Int => (PhysicalType::INT64, None),
+                ColumnType::Text => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+            };
+
+            let field = Type::primitive_type_builder(&column.name, physical_type)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical_type)
+                .build()?;
+
+            fields.push(Arc::new(field));
+        }
+
+        let schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?;
+
+        let buffers = columns
+            .iter()
+            .map(|column| ColumnBuffer {
+                values: match column.column_type {
+                    ColumnType::Bool => Values::Bool(Vec::new()),
+                    ColumnType::Float => Values::Float(Vec::new()),
+                    ColumnType::Int => Values::Int(Vec::new()),
+                    ColumnType::Text => Values::Text(Vec::new()),
+                },
+                definition_levels: Vec::new(),
+            })
+            .collect();
+
+        Ok(ParquetTable {
+            writer: SerializedFileWriter::new(writer, Arc::new(schema), Default::default())?,
+            buffers,
+            rows: 0,
+        })
+    }
+
+    fn write_row_group(&mut self) -> Result<(), Error> {
+        let mut row_group = self.writer.next_row_group()?;
+
+        for buffer in &mut self.buffers {
+            let mut column = row_group
+                .next_column()?
+                .expect("Schema has a column for each buffer");
+            let levels = Some(buffer.definition_levels.as_slice());
+
+            match &mut buffer.values {
+                Values::Bool(values) => {
+                    column
+                        .typed::<BoolType>()
+                        .write_batch(values, levels, None)?;
+                    values.clear();
+                }
+                Values::Float(values) => {
+                    column
+                        .typed::<FloatType>()
+                        .write_batch(values, levels, None)?;
+                    values.clear();
+                }
+                Values::Int(values) => {
+                    column
+                        .typed::<Int64Type>()
+                        .write_batch(values, levels, None)?;
+                    values.clear();
+                }
+                Values::Text(values) => {
+                    column
+                        .typed::<ByteArrayType>()
+                        .write_batch(values, levels, None)?;
+                    values.clear();
+                }
+            }
+
+            column.close()?;
+            buffer.definition_levels.clear();
+        }
+
+        row_group.close()?;
+        self.rows = 0;
+
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> TableWriter for ParquetTable<W> {
+    fn write_row(&mut self, row: &[Value]) -> Result<(), Error> {
+        for (buffer, value) in self.buffers.iter_mut().zip(row.iter()) {
+            let defined = match (&mut buffer.values, value) {
+                (Values::Bool(values), Value::Bool(value)) => {
+                    values.push(*value);
+                    true
+                }
+                (Values::Float(values), Value::Float(value)) => {
+                    values.push(*value);
+                    true
+                }
+                (Values::Int(values), Value::Int(value)) => {
+                    values.push(*value);
+                    true
+                }
+                (Values::Text(values), Value::Text(value)) => {
+                    values.push(ByteArray::from(value.as_str()));
+                    true
+                }
+                _ => false,
+            };
+
+            buffer.definition_levels.push(if defined { 1 } else { 0 });
+        }
+
+        self.rows += 1;
+
+        if self.rows >= ROW_GROUP_SIZE {
+            self.write_row_group()?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Error> {
+        if self.rows > 0 {
+            self.write_row_group()?;
+        }
+
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::parquet::ParquetTable;
+    use crate::export::{Column, ColumnType, TableWriter, Value};
+
+    #[test]
+    fn write_table() {
+        let columns = vec![
+            Column::new("name", ColumnType::Text),
+            Column::new("speed", ColumnType::Int),
+        ];
+
+        let mut bytes = Vec::new();
+        let mut table = ParquetTable::new(&mut bytes, columns).unwrap();
+        table
+            .write_row(&[Value::Text(String::from("Player")), Value::Int(312)])
+            .unwrap();
+        table.write_row(&[Value::Null, Value::Int(1)]).unwrap();
+        Box::new(table).finish().unwrap();
+
+        assert_eq!(b"PAR1", &bytes[0..4]);
+        assert_eq!(b"PAR1", &bytes[bytes.len() - 4..]);
+    }
+}
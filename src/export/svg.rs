@@ -0,0 +1,223 @@
+//! Rendering of track maps as SVG
+//!
+//! Applications that visualize a session usually show a map of the track with the position of the
+//! cars on it. Instead of shipping artwork for every track, the map that has been learned from the
+//! motion of the cars can be rendered as a scalable vector graphic.
+//!
+//! The graphic uses the coordinate system of the game world in meters, with north pointing up. Every
+//! element has a class, so that applications can change its appearance with CSS.
+
+use std::fmt::Write;
+
+use crate::analysis::track_map::TrackMap;
+use crate::packet::session::MarshalZone;
+use crate::types::{Flag, Property3D};
+
+/// Space around the track in meters
+const MARGIN: f32 = 50.0;
+
+/// Renderer for the SVG graphic of a track map
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::analysis::track_map::TrackMap;
+/// use f1_api::export::svg::TrackSvg;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut map = TrackMap::default();
+///
+/// for packet in packets() {
+///     map.update(&packet);
+/// }
+///
+/// let svg = TrackSvg::new(&map).render();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TrackSvg<'a> {
+    map: &'a TrackMap,
+    marshal_zones: Vec<MarshalZone>,
+    cars: Vec<(String, Property3D<f32>)>,
+}
+
+impl<'a> TrackSvg<'a> {
+    /// Create a new renderer for the given map.
+    pub fn new(map: &'a TrackMap) -> Self {
+        TrackSvg {
+            map,
+            marshal_zones: Vec::new(),
+            cars: Vec::new(),
+        }
+    }
+
+    /// Show the start of the marshal zones, colored by the flag that is shown in them.
+    pub fn marshal_zones(mut self, marshal_zones: &[MarshalZone]) -> Self {
+        self.marshal_zones = marshal_zones.to_vec();
+        self
+    }
+
+    /// Show a car with the given label at a position in the game world.
+    pub fn car(mut self, label: impl Into<String>, position: Property3D<f32>) -> Self {
+        self.cars.push((label.into(), position));
+        self
+    }
+
+    /// Render the map as an SVG document.
+    pub fn render(&self) -> String {
+        let points = self.map.points();
+        let mut svg = String::new();
+
+        let (min_x, min_y, max_x, max_y) = points.iter().fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(min_x, min_y, max_x, max_y), point| {
+                let (x, y) = project(point.position());
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            },
+        );
+        let (min_x, min_y, width, height) = if points.is_empty() {
+            (0.0, 0.0, 0.0, 0.0)
+        } else {
+            (min_x, min_y, max_x - min_x, max_y - min_y)
+        };
+
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.1} {:.1} {:.1} {:.1}">"#,
+            min_x - MARGIN,
+            min_y - MARGIN,
+            width + 2.0 * MARGIN,
+            height + 2.0 * MARGIN
+        )
+        .unwrap();
+
+        // The path is interrupted where parts of the track have not been mapped yet
+        let mut path = String::new();
+        let mut previous: Option<f32> = None;
+
+        for point in &points {
+            let (x, y) = project(point.position());
+            let connected = previous
+                .is_some_and(|distance| point.distance() - distance <= self.map.resolution());
+            let command = if connected { 'L' } else { 'M' };
+
+            write!(path, "{}{:.1} {:.1} ", command, x, y).unwrap();
+            previous = Some(point.distance());
+        }
+
+        if self.map.coverage() >= 1.0 {
+            path.push('Z');
+        }
+
+        writeln!(
+            svg,
+            r##"  <path class="track" d="{}" fill="none" stroke="#333333" stroke-width="12" stroke-linejoin="round"/>"##,
+            path.trim_end()
+        )
+        .unwrap();
+
+        if let Some(point) = self.map.point(0.0) {
+            let (x, y) = project(point.position());
+            writeln!(
+                svg,
+                r##"  <circle class="start" cx="{:.1}" cy="{:.1}" r="10" fill="#ffffff" stroke="#333333" stroke-width="3"/>"##,
+                x, y
+            )
+            .unwrap();
+        }
+
+        for (index, boundary) in self.map.sector_boundaries().iter().enumerate() {
+            if let Some(point) = boundary.and_then(|distance| self.map.point(distance)) {
+                let (x, y) = project(point.position());
+                writeln!(
+                    svg,
+                    r##"  <circle class="sector-boundary" data-sector="{}" cx="{:.1}" cy="{:.1}" r="8" fill="#ffd600"/>"##,
+                    index + 2,
+                    x,
+                    y
+                )
+                .unwrap();
+            }
+        }
+
+        for zone in &self.marshal_zones {
+            if let Some(point) = self.map.point(zone.start() * self.map.length()) {
+                let (x, y) = project(point.position());
+                writeln!(
+                    svg,
+                    r#"  <circle class="marshal-zone" data-flag="{:?}" cx="{:.1}" cy="{:.1}" r="5" fill="{}"/>"#,
+                    zone.flag(),
+                    x,
+                    y,
+                    color(zone.flag())
+                )
+                .unwrap();
+            }
+        }
+
+        for (label, position) in &self.cars {
+            let (x, y) = project(position);
+            writeln!(
+                svg,
+                r##"  <g class="car"><circle cx="{:.1}" cy="{:.1}" r="14" fill="#e10600"/><text x="{:.1}" y="{:.1}" font-size="16" text-anchor="middle" dominant-baseline="central" fill="#ffffff">{}</text></g>"##,
+                x,
+                y,
+                x,
+                y,
+                escape(label)
+            )
+            .unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Returns the coordinates of a position in the graphic, with the z axis pointing up.
+fn project(position: &Property3D<f32>) -> (f32, f32) {
+    (position.x(), 0.0 - position.z())
+}
+
+fn color(flag: Flag) -> &'static str {
+    match flag {
+        Flag::Green => "#2e7d32",
+        Flag::Blue => "#1565c0",
+        Flag::Yellow => "#f9a825",
+        Flag::Red => "#c62828",
+        Flag::None | Flag::Invalid => "#9e9e9e",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::track_map::TrackMap;
+    use crate::export::svg::TrackSvg;
+    use crate::packet::session::MarshalZone;
+    use crate::types::{Flag, Property3D};
+
+    #[test]
+    fn render_map_with_gap() {
+        let mut map = TrackMap::new(10.0);
+
+        map.add_motion(0.0, &Property3D::new(0.0, 0.0, 0.0), 0.0);
+        map.add_motion(10.0, &Property3D::new(10.0, 0.0, 0.0), 0.0);
+        map.add_motion(30.0, &Property3D::new(10.0, 0.0, 20.0), 0.0);
+
+        let svg = TrackSvg::new(&map)
+            .marshal_zones(&[MarshalZone::new(0.25, Flag::Yellow)])
+            .car("<44>", Property3D::new(10.0, 0.0, 20.0))
+            .render();
+
+        assert!(svg.contains(r#"viewBox="-50.0 -70.0 110.0 120.0""#));
+        assert!(svg.contains(r#"d="M0.0 0.0 L10.0 0.0 M10.0 -20.0""#));
+        assert!(svg.contains(r#"class="marshal-zone" data-flag="Yellow" cx="10.0" cy="0.0""#));
+        assert!(svg.contains("&lt;44&gt;</text>"));
+    }
+}
@@ -0,0 +1,661 @@
+//! Flattening of packets into tables
+//!
+//! Each packet type is mapped to its own table. Packets with data for every car in the session
+//! produce one row per car, while packets that describe the session as a whole produce a single
+//! row. Every row starts with the fields of the packet header, so that rows from different tables
+//! can be joined on the session and frame they belong to.
+
+use crate::export::{Column, ColumnType, Value};
+use crate::packet::event::Event;
+use crate::packet::header::{Header, PacketType};
+use crate::packet::participants::Driver;
+use crate::packet::Packet;
+use crate::types::{CornerProperty, Property3D};
+
+/// Returns the type of the given packet.
+pub fn packet_type(packet: &Packet) -> PacketType {
+    match packet {
+        Packet::Event(_) => PacketType::Event,
+        Packet::Lap(_) => PacketType::Lap,
+        Packet::Motion(_) => PacketType::Motion,
+        Packet::Participants(_) => PacketType::Participants,
+        Packet::Session(_) => PacketType::Session,
+        Packet::Setup(_) => PacketType::Setup,
+        Packet::Status(_) => PacketType::Status,
+        Packet::Telemetry(_) => PacketType::Telemetry,
+    }
+}
+
+/// Returns the name of the table for the given packet type.
+pub fn name(packet_type: PacketType) -> &'static str {
+    match packet_type {
+        PacketType::Event => "event",
+        PacketType::Lap => "lap",
+        PacketType::Motion => "motion",
+        PacketType::Participants => "participants",
+        PacketType::Session => "session",
+        PacketType::Setup => "setup",
+        PacketType::Status => "status",
+        PacketType::Telemetry => "telemetry",
+    }
+}
+
+/// Returns the columns of the table for the given packet type.
+pub fn columns(packet_type: PacketType) -> Vec<Column> {
+    let mut columns = vec![
+        Column::new("session_uid", ColumnType::Text),
+        Column::new("session_time", ColumnType::Float),
+        Column::new("frame_identifier", ColumnType::Int),
+        Column::new("player_car_index", ColumnType::Int),
+    ];
+
+    match packet_type {
+        PacketType::Event => {
+            columns.push(Column::new("event", ColumnType::Text));
+            columns.push(Column::new("vehicle_index", ColumnType::Int));
+            columns.push(Column::new("lap_time", ColumnType::Float));
+        }
+        PacketType::Lap => {
+            columns.push(Column::new("vehicle_index", ColumnType::Int));
+            push_columns(
+                &mut columns,
+                ColumnType::Float,
+                &[
+                    "last_lap_time",
+                    "current_lap_time",
+                    "best_lap_time",
+                    "sector1_time",
+                    "sector2_time",
+                    "lap_distance",
+                    "total_distance",
+                    "safety_car_delta",
+                ],
+            );
+            push_columns(
+                &mut columns,
+                ColumnType::Int,
+                &["position", "current_lap_number"],
+            );
+            push_columns(&mut columns, ColumnType::Text, &["pit_status", "sector"]);
+            columns.push(Column::new("is_valid_lap", ColumnType::Bool));
+            push_columns(
+                &mut columns,
+                ColumnType::Int,
+                &["penalties", "grid_position"],
+            );
+            push_columns(
+                &mut columns,
+                ColumnType::Text,
+                &["driver_status", "result_status"],
+            );
+        }
+        PacketType::Motion => {
+            columns.push(Column::new("vehicle_index", ColumnType::Int));
+            push_3d_columns(&mut columns, "position", ColumnType::Float);
+            push_3d_columns(&mut columns, "velocity", ColumnType::Float);
+            push_3d_columns(&mut columns, "forward_direction", ColumnType::Int);
+            push_3d_columns(&mut columns, "right_direction", ColumnType::Int);
+            push_3d_columns(&mut columns, "g_force", ColumnType::Float);
+            push_columns(&mut columns, ColumnType::Float, &["yaw", "pitch", "roll"]);
+            push_corner_columns(&mut columns, "suspension_position", ColumnType::Float);
+            push_corner_columns(&mut columns, "suspension_velocity", ColumnType::Float);
+            push_corner_columns(&mut columns, "suspension_acceleration", ColumnType::Float);
+            push_corner_columns(&mut columns, "wheel_speed", ColumnType::Float);
+            push_corner_columns(&mut columns, "wheel_slip", ColumnType::Float);
+            push_3d_columns(&mut columns, "local_velocity", ColumnType::Float);
+            push_3d_columns(&mut columns, "angular_velocity", ColumnType::Float);
+            push_3d_columns(&mut columns, "angular_acceleration", ColumnType::Float);
+            columns.push(Column::new("front_wheels_angle", ColumnType::Float));
+        }
+        PacketType::Participants => {
+            columns.push(Column::new("active_participants_count", ColumnType::Int));
+            columns.push(Column::new("vehicle_index", ColumnType::Int));
+            push_columns(
+                &mut columns,
+                ColumnType::Text,
+                &["controller", "driver", "team"],
+            );
+            columns.push(Column::new("race_number", ColumnType::Int));
+            push_columns(
+                &mut columns,
+                ColumnType::Text,
+                &["nationality", "name", "telemetry_privacy"],
+            );
+        }
+        PacketType::Session => {
+            columns.push(Column::new("weather", ColumnType::Text));
+            push_columns(
+                &mut columns,
+                ColumnType::Int,
+                &[
+                    "track_temperature",
+                    "air_temperature",
+                    "total_laps",
+                    "track_length",
+                ],
+            );
+            push_columns(
+                &mut columns,
+                ColumnType::Text,
+                &["session_type", "track", "formula"],
+            );
+            push_columns(&mut columns, ColumnType::Float, &["time_left", "duration"]);
+            columns.push(Column::new("pit_speed_limit", ColumnType::Int));
+            push_columns(
+                &mut columns,
+                ColumnType::Bool,
+                &["game_paused", "is_spectating"],
+            );
+            columns.push(Column::new("spectator_car_index", ColumnType::Int));
+            columns.push(Column::new("sli_pro_support", ColumnType::Bool));
+            columns.push(Column::new("marshal_zone_count", ColumnType::Int));
+            columns.push(Column::new("safety_car", ColumnType::Text));
+            columns.push(Column::new("network_session", ColumnType::Bool));
+        }
+        PacketType::Setup => {
+            columns.push(Column::new("vehicle_index", ColumnType::Int));
+            push_columns(
+                &mut columns,
+                ColumnType::Int,
+                &["front_wing", "rear_wing", "on_throttle", "off_throttle"],
+            );
+            push_columns(
+                &mut columns,
+                ColumnType::Float,
+                &["front_camber", "rear_camber", "front_toe", "rear_toe"],
+            );
+            push_columns(
+                &mut columns,
+                ColumnType::Int,
+                &[
+                    "front_suspension",
+                    "rear_suspension",
+                    "front_anti_roll_bar",
+                    "rear_anti_roll_bar",
+                    "front_suspension_height",
+                    "rear_suspension_height",
+                    "brake_pressure",
+                    "brake_bias",
+                ],
+            );
+            push_columns(
+                &mut columns,
+                ColumnType::Float,
+                &["front_tyre_pressure", "rear_tyre_pressure"],
+            );
+            columns.push(Column::new("ballast", ColumnType::Int));
+            columns.push(Column::new("fuel_load", ColumnType::Float));
+        }
+        PacketType::Status => {
+            columns.push(Column::new("vehicle_index", ColumnType::Int));
+            columns.push(Column::new("traction_control", ColumnType::Text));
+            columns.push(Column::new("abs", ColumnType::Bool));
+            columns.push(Column::new("fuel_mix", ColumnType::Text));
+            columns.push(Column::new("brake_bias", ColumnType::Int));
+            columns.push(Column::new("pit_limiter", ColumnType::Bool));
+            push_columns(
+                &mut columns,
+                ColumnType::Float,
+                &["fuel_remaining", "fuel_capacity", "fuel_remaining_laps"],
+            );
+            push_columns(
+                &mut columns,
+                ColumnType::Int,
+                &["max_rpm", "idle_rpm", "gear_count"],
+            );
+            columns.push(Column::new("drs", ColumnType::Text));
+            push_corner_columns(&mut columns, "tyre_wear", ColumnType::Int);
+            push_columns(
+                &mut columns,
+                ColumnType::Text,
+                &["physical_tyre_compound", "visual_tyre_compound"],
+            );
+            push_corner_columns(&mut columns, "tyre_damage", ColumnType::Int);
+            push_columns(
+                &mut columns,
+                ColumnType::Int,
+                &[
+                    "front_left_wing_damage",
+                    "front_right_wing_damage",
+                    "rear_wing_damage",
+                    "engine_damage",
+                    "gear_box_damage",
+                ],
+            );
+            columns.push(Column::new("vehicle_flags", ColumnType::Text));
+            columns.push(Column::new("ers_energy", ColumnType::Float));
+            columns.push(Column::new("ers_deploy_mode", ColumnType::Text));
+            push_columns(
+                &mut columns,
+                ColumnType::Float,
+                &["ers_harvest_mgu_k", "ers_harvest_mgu_h", "ers_deployed"],
+            );
+        }
+        PacketType::Telemetry => {
+            columns.push(Column::new("vehicle_index", ColumnType::Int));
+            columns.push(Column::new("speed", ColumnType::Int));
+            push_columns(
+                &mut columns,
+                ColumnType::Float,
+                &["throttle", "steering", "brake"],
+            );
+            columns.push(Column::new("clutch", ColumnType::Int));
+            columns.push(Column::new("gear", ColumnType::Text));
+            columns.push(Column::new("engine_rpm", ColumnType::Int));
+            columns.push(Column::new("drs", ColumnType::Bool));
+            columns.push(Column::new("rev_lights", ColumnType::Int));
+            push_corner_columns(&mut columns, "brake_temperature", ColumnType::Int);
+            push_corner_columns(&mut columns, "tyre_surface_temperature", ColumnType::Int);
+            push_corner_columns(&mut columns, "tyre_inner_temperature", ColumnType::Int);
+            columns.push(Column::new("engine_temperature", ColumnType::Int));
+            push_corner_columns(&mut columns, "tyre_pressure", ColumnType::Float);
+            push_corner_columns(&mut columns, "surface_type", ColumnType::Text);
+            columns.push(Column::new("button_status", ColumnType::Int));
+        }
+    }
+
+    columns
+}
+
+/// Flatten a packet into the rows of its table.
+pub fn rows(packet: &Packet) -> Vec<Vec<Value>> {
+    match packet {
+        Packet::Event(packet) => {
+            let mut row = header_values(packet.header());
+
+            let (vehicle_index, lap_time) = match packet.event() {
+                Event::FastestLap(lap) => (
+                    Value::Int(lap.vehicle_index() as i64),
+                    Value::Float(lap.time().as_secs_f32()),
+                ),
+                Event::Retirement(retirement) => {
+                    (Value::Int(retirement.vehicle_index() as i64), Value::Null)
+                }
+                Event::TeammatesInPits(teammate) => {
+                    (Value::Int(teammate.vehicle_index() as i64), Value::Null)
+                }
+                Event::RaceWinner(winner) => {
+                    (Value::Int(winner.vehicle_index() as i64), Value::Null)
+                }
+                _ => (Value::Null, Value::Null),
+            };
+
+            row.push(Value::Text(event_name(packet.event()).to_string()));
+            row.push(vehicle_index);
+            row.push(lap_time);
+
+            vec![row]
+        }
+        Packet::Lap(packet) => packet
+            .laps()
+            .iter_active()
+            .map(|(index, lap)| {
+                let mut row = header_values(packet.header());
+                row.push(Value::Int(index as i64));
+                row.push(Value::Float(lap.last_lap_time().as_secs_f32()));
+                row.push(Value::Float(lap.current_lap_time().as_secs_f32()));
+                row.push(Value::Float(lap.best_lap_time().as_secs_f32()));
+                row.push(Value::Float(lap.sector1_time().as_secs_f32()));
+                row.push(Value::Float(lap.sector2_time().as_secs_f32()));
+                row.push(Value::Float(lap.lap_distance()));
+                row.push(Value::Float(lap.total_distance()));
+                row.push(Value::Float(lap.safety_car_delta()));
+                row.push(Value::Int(lap.position() as i64));
+                row.push(Value::Int(lap.current_lap_number() as i64));
+                row.push(debug_value(lap.pit_status()));
+                row.push(debug_value(lap.sector()));
+                row.push(Value::Bool(lap.is_valid_lap()));
+                row.push(Value::Int(lap.penalties() as i64));
+                row.push(Value::Int(lap.grid_position() as i64));
+                row.push(debug_value(lap.driver_status()));
+                row.push(debug_value(lap.result_status()));
+                row
+            })
+            .collect(),
+        Packet::Motion(packet) => packet
+            .cars()
+            .iter_active()
+            .map(|(index, motion)| {
+                let mut row = header_values(packet.header());
+                row.push(Value::Int(index as i64));
+                push_3d_values(&mut row, motion.position(), Value::Float);
+                push_3d_values(&mut row, motion.velocity(), Value::Float);
+                push_3d_values(&mut row, motion.forward_direction(), |v| {
+                    Value::Int(v as i64)
+                });
+                push_3d_values(&mut row, motion.right_direction(), |v| Value::Int(v as i64));
+                push_3d_values(&mut row, motion.g_force(), Value::Float);
+                row.push(Value::Float(motion.yaw()));
+                row.push(Value::Float(motion.pitch()));
+                row.push(Value::Float(motion.roll()));
+
+                if index == packet.header().player_car_index() {
+                    push_corner_values(&mut row, packet.suspension_position(), Value::Float);
+                    push_corner_values(&mut row, packet.suspension_velocity(), Value::Float);
+                    push_corner_values(&mut row, packet.suspension_acceleration(), Value::Float);
+                    push_corner_values(&mut row, packet.wheel_speed(), Value::Float);
+                    push_corner_values(&mut row, packet.wheel_slip(), Value::Float);
+                    push_3d_values(&mut row, packet.local_velocity(), Value::Float);
+                    push_3d_values(&mut row, packet.angular_velocity(), Value::Float);
+                    push_3d_values(&mut row, packet.angular_acceleration(), Value::Float);
+                    row.push(Value::Float(packet.front_wheels_angle()));
+                } else {
+                    row.resize(row.len() + 30, Value::Null);
+                }
+
+                row
+            })
+            .collect(),
+        Packet::Participants(packet) => packet
+            .participants()
+            .iter_active()
+            .map(|(index, participant)| {
+                let mut row = header_values(packet.header());
+                row.push(Value::Int(packet.active_participants_count() as i64));
+                row.push(Value::Int(index as i64));
+                row.push(debug_value(participant.controller()));
+                // The name of a custom driver is already in the name column.
+                row.push(match participant.driver() {
+                    Driver::Custom { .. } => Value::Text("Custom".into()),
+                    driver => debug_value(driver),
+                });
+                row.push(debug_value(participant.team()));
+                row.push(Value::Int(participant.race_number() as i64));
+                row.push(debug_value(participant.nationality()));
+                row.push(Value::Text(participant.name().clone()));
+                row.push(match participant.telemetry_privacy() {
+                    Some(privacy) => debug_value(privacy),
+                    None => Value::Null,
+                });
+                row
+            })
+            .collect(),
+        Packet::Session(packet) => {
+            let mut row = header_values(packet.header());
+            row.push(debug_value(packet.weather()));
+            row.push(Value::Int(packet.track_temperature() as i64));
+            row.push(Value::Int(packet.air_temperature() as i64));
+            row.push(Value::Int(packet.total_laps() as i64));
+            row.push(Value::Int(packet.track_length() as i64));
+            row.push(debug_value(packet.session_type()));
+            row.push(debug_value(packet.track()));
+            row.push(debug_value(packet.formula()));
+            row.push(Value::Float(packet.time_left().as_secs_f32()));
+            row.push(Value::Float(packet.duration().as_secs_f32()));
+            row.push(Value::Int(packet.pit_speed_limit() as i64));
+            row.push(Value::Bool(packet.game_paused()));
+            row.push(Value::Bool(packet.is_spectating()));
+            row.push(Value::Int(packet.spectator_car_index() as i64));
+            row.push(Value::Bool(packet.sli_pro_support()));
+            row.push(Value::Int(packet.marshal_zones().len() as i64));
+            row.push(debug_value(packet.safety_car()));
+            row.push(Value::Bool(packet.network_session()));
+
+            vec![row]
+        }
+        Packet::Setup(packet) => packet
+            .setups()
+            .iter_active()
+            .map(|(index, setup)| {
+                let mut row = header_values(packet.header());
+                row.push(Value::Int(index as i64));
+                row.push(Value::Int(setup.front_wing() as i64));
+                row.push(Value::Int(setup.rear_wing() as i64));
+                row.push(Value::Int(setup.on_throttle() as i64));
+                row.push(Value::Int(setup.off_throttle() as i64));
+                row.push(Value::Float(setup.front_camber()));
+                row.push(Value::Float(setup.rear_camber()));
+                row.push(Value::Float(setup.front_toe()));
+                row.push(Value::Float(setup.rear_toe()));
+                row.push(Value::Int(setup.front_suspension() as i64));
+                row.push(Value::Int(setup.rear_suspension() as i64));
+                row.push(Value::Int(setup.front_anti_roll_bar() as i64));
+                row.push(Value::Int(setup.rear_anti_roll_bar() as i64));
+                row.push(Value::Int(setup.front_suspension_height() as i64));
+                row.push(Value::Int(setup.rear_suspension_height() as i64));
+                row.push(Value::Int(setup.brake_pressure() as i64));
+                row.push(Value::Int(setup.brake_bias() as i64));
+                row.push(Value::Float(setup.front_tyre_pressure()));
+                row.push(Value::Float(setup.rear_tyre_pressure()));
+                row.push(Value::Int(setup.ballast() as i64));
+                row.push(Value::Float(setup.fuel_load()));
+                row
+            })
+            .collect(),
+        Packet::Status(packet) => packet
+            .statuses()
+            .iter_active()
+            .map(|(index, status)| {
+                let mut row = header_values(packet.header());
+                row.push(Value::Int(index as i64));
+                row.push(debug_value(status.traction_control()));
+                row.push(Value::Bool(status.abs()));
+                row.push(debug_value(status.fuel_mix()));
+                row.push(Value::Int(status.brake_bias() as i64));
+                row.push(Value::Bool(status.pit_limiter()));
+                row.push(Value::Float(status.fuel_remaining()));
+                row.push(Value::Float(status.fuel_capacity()));
+                row.push(Value::Float(status.fuel_remaining_laps()));
+                row.push(Value::Int(status.max_rpm() as i64));
+                row.push(Value::Int(status.idle_rpm() as i64));
+                row.push(Value::Int(status.gear_count() as i64));
+                row.push(debug_value(status.drs()));
+                push_corner_values(&mut row, status.tyre_wear(), |v| Value::Int(v as i64));
+                row.push(debug_value(status.physical_tyre_compound()));
+                row.push(debug_value(status.visual_tyre_compound()));
+                push_corner_values(&mut row, status.tyre_damage(), |v| Value::Int(v as i64));
+                row.push(Value::Int(status.front_left_wing_damage() as i64));
+                row.push(Value::Int(status.front_right_wing_damage() as i64));
+                row.push(Value::Int(status.rear_wing_damage() as i64));
+                row.push(Value::Int(status.engine_damage() as i64));
+                row.push(Value::Int(status.gear_box_damage() as i64));
+                row.push(debug_value(status.vehicle_flags()));
+                row.push(Value::Float(status.ers_energy()));
+                row.push(debug_value(status.ers_deploy_mode()));
+                row.push(Value::Float(status.ers_harvest_mgu_k()));
+                row.push(Value::Float(status.ers_harvest_mgu_h()));
+                row.push(Value::Float(status.ers_deployed()));
+                row
+            })
+            .collect(),
+        Packet::Telemetry(packet) => packet
+            .telemetry()
+            .iter_active()
+            .map(|(index, telemetry)| {
+                let mut row = header_values(packet.header());
+                row.push(Value::Int(index as i64));
+                row.push(Value::Int(telemetry.speed() as i64));
+                row.push(Value::Float(telemetry.throttle()));
+                row.push(Value::Float(telemetry.steering()));
+                row.push(Value::Float(telemetry.brake()));
+                row.push(Value::Int(telemetry.clutch() as i64));
+                row.push(debug_value(telemetry.gear()));
+                row.push(Value::Int(telemetry.engine_rpm() as i64));
+                row.push(Value::Bool(telemetry.drs()));
+                row.push(Value::Int(telemetry.rev_lights() as i64));
+                push_corner_values(&mut row, telemetry.brake_temperature(), |v| {
+                    Value::Int(v as i64)
+                });
+                push_corner_values(&mut row, telemetry.tyre_surface_temperature(), |v| {
+                    Value::Int(v as i64)
+                });
+                push_corner_values(&mut row, telemetry.tyre_inner_temperature(), |v| {
+                    Value::Int(v as i64)
+                });
+                row.push(Value::Int(telemetry.engine_temperature() as i64));
+                push_corner_values(&mut row, telemetry.tyre_pressure(), Value::Float);
+                push_corner_values(&mut row, telemetry.surface_type(), debug_value);
+
+                if index == packet.header().player_car_index() {
+                    row.push(Value::Int(packet.button_status().bits() as i64));
+                } else {
+                    row.push(Value::Null);
+                }
+
+                row
+            })
+            .collect(),
+    }
+}
+
+fn header_values(header: &Header) -> Vec<Value> {
+    vec![
+        Value::Text(header.session_uid().to_string()),
+        Value::Float(header.session_time().as_secs_f32()),
+        Value::Int(header.frame_identifier() as i64),
+        Value::Int(header.player_car_index() as i64),
+    ]
+}
+
+/// Returns the name of the event in the `event` column.
+pub(crate) fn event_name(event: &Event) -> &'static str {
+    match event {
+        Event::ChequeredFlag => "ChequeredFlag",
+        Event::DrsDisabled => "DrsDisabled",
+        Event::DrsEnabled => "DrsEnabled",
+        Event::FastestLap(_) => "FastestLap",
+        Event::RaceWinner(_) => "RaceWinner",
+        Event::Retirement(_) => "Retirement",
+        Event::SessionEnded => "SessionEnded",
+        Event::SessionStarted => "SessionStarted",
+        Event::TeammatesInPits(_) => "TeammatesInPits",
+    }
+}
+
+fn debug_value<T: std::fmt::Debug>(value: T) -> Value {
+    Value::Text(format!("{:?}", value))
+}
+
+fn push_columns(columns: &mut Vec<Column>, column_type: ColumnType, names: &[&str]) {
+    for name in names {
+        columns.push(Column::new(*name, column_type));
+    }
+}
+
+fn push_corner_columns(columns: &mut Vec<Column>, name: &str, column_type: ColumnType) {
+    for corner in &["front_left", "front_right", "rear_left", "rear_right"] {
+        columns.push(Column::new(format!("{}_{}", name, corner), column_type));
+    }
+}
+
+fn push_3d_columns(columns: &mut Vec<Column>, name: &str, column_type: ColumnType) {
+    for axis in &["x", "y", "z"] {
+        columns.push(Column::new(format!("{}_{}", name, axis), column_type));
+    }
+}
+
+fn push_corner_values<T: Copy>(
+    row: &mut Vec<Value>,
+    property: &CornerProperty<T>,
+    value: impl Fn(T) -> Value,
+) {
+    row.push(value(property.front_left()));
+    row.push(value(property.front_right()));
+    row.push(value(property.rear_left()));
+    row.push(value(property.rear_right()));
+}
+
+fn push_3d_values<T: Copy>(
+    row: &mut Vec<Value>,
+    property: &Property3D<T>,
+    value: impl Fn(T) -> Value,
+) {
+    row.push(value(property.x()));
+    row.push(value(property.y()));
+    row.push(value(property.z()));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::export::table::{columns, packet_type, rows};
+    use crate::export::{ColumnType, Value};
+    use crate::fixtures::{
+        header, lap_packet, motion_packet, participants_packet, session_packet, setup_packet,
+        status_packet, telemetry_packet,
+    };
+    use crate::packet::event::{Event, EventPacket, FastestLap};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::Lap;
+    use crate::packet::motion::Motion;
+    use crate::packet::participants::Participant;
+    use crate::packet::session::{Session, Track};
+    use crate::packet::setup::CarSetup;
+    use crate::packet::status::CarStatus;
+    use crate::packet::telemetry::Telemetry;
+    use crate::packet::Packet;
+    use crate::types::CornerProperty;
+
+    fn packets() -> Vec<Packet> {
+        let header = |packet_type| header(packet_type, 1, Duration::from_secs(1), 2);
+
+        vec![
+            Packet::Event(EventPacket::new(
+                header(PacketType::Event),
+                Event::FastestLap(FastestLap::new(3, Duration::from_secs(62))),
+            )),
+            lap_packet(header(PacketType::Lap), vec![Lap::default(); 20]),
+            motion_packet(
+                header(PacketType::Motion),
+                vec![Motion::default(); 20],
+                CornerProperty::default(),
+            ),
+            participants_packet(
+                header(PacketType::Participants),
+                vec![Participant::default(); 20],
+            ),
+            session_packet(
+                header(PacketType::Session),
+                Session::Race,
+                Track::Silverstone,
+            ),
+            setup_packet(header(PacketType::Setup), vec![CarSetup::default(); 20]),
+            status_packet(header(PacketType::Status), vec![CarStatus::default(); 20]),
+            telemetry_packet(
+                header(PacketType::Telemetry),
+                vec![Telemetry::default(); 20],
+            ),
+        ]
+    }
+
+    #[test]
+    fn rows_match_columns() {
+        for packet in packets() {
+            let columns = columns(packet_type(&packet));
+
+            for row in rows(&packet) {
+                assert_eq!(columns.len(), row.len());
+
+                for (column, value) in columns.iter().zip(row.iter()) {
+                    let matches = match value {
+                        Value::Null => true,
+                        Value::Bool(_) => column.column_type == ColumnType::Bool,
+                        Value::Float(_) => column.column_type == ColumnType::Float,
+                        Value::Int(_) => column.column_type == ColumnType::Int,
+                        Value::Text(_) => column.column_type == ColumnType::Text,
+                    };
+
+                    assert!(matches, "Value {:?} in column {}", value, column.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rows_with_one_row_per_car() {
+        let packet = &packets()[1];
+        assert_eq!(20, rows(packet).len());
+    }
+
+    #[test]
+    fn rows_for_event() {
+        let packet = &packets()[0];
+        let rows = rows(packet);
+
+        assert_eq!(1, rows.len());
+        assert_eq!(Value::Text(String::from("FastestLap")), rows[0][4]);
+        assert_eq!(Value::Int(3), rows[0][5]);
+        assert_eq!(Value::Float(62.0), rows[0][6]);
+    }
+}
@@ -0,0 +1,504 @@
+//! Miniature captures for tests
+//!
+//! Testing a decoder with byte arrays that are written by hand is tedious and error-prone. This
+//! module bundles a miniature capture for every packet type of every supported game, with two or
+//! three datagrams each, that tests can load and decode instead. The captures are stored in the
+//! `fixtures` directory, with one directory per API specification and one file per packet type.
+//!
+//! The fixtures show a race at Silverstone with the full grid of the game, on the fifth lap about
+//! ten minutes into the session. Contributors adding support for a new game add a directory with
+//! the captures of the new game, and a match arm for its API specification below.
+//!
+//! Tests that need packets with specific values build them with the constructors of the packets
+//! instead. The `header`, `lap`, `motion`, `participant`, `setup`, `status`, and `telemetry`
+//! functions, their packet counterparts, and `session_packet` fill in the fields that such tests do
+//! not care about, and functions like `in_position` and `with_times` replace selected fields of
+//! the data they return.
+//!
+//! The module is only available with the `test-support` feature, and is meant to be used in tests.
+//!
+//! # Examples
+//!
+//! ```
+//! use f1_api::fixtures::decode;
+//! use f1_api::packet::header::{ApiSpec, PacketType};
+//! use f1_api::packet::lap::LapPacket;
+//!
+//! let packets: Vec<LapPacket> = decode(ApiSpec::Nineteen, PacketType::Lap)?;
+//!
+//! assert_eq!(2, packets.len());
+//! assert_eq!(20, packets[0].laps().len());
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+#[cfg(all(feature = "std", feature = "decode"))]
+use std::convert::TryFrom;
+#[cfg(all(feature = "std", feature = "decode"))]
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+
+#[cfg(all(feature = "std", feature = "decode"))]
+use bytes::Bytes;
+
+#[cfg(all(feature = "std", feature = "decode"))]
+use crate::capture::CaptureReader;
+use crate::packet::header::{ApiSpec, Header, PacketType};
+use crate::packet::lap::{DriverStatus, Lap, LapPacket, PitStatus, ResultStatus, Sector};
+use crate::packet::motion::{Motion, MotionPacket};
+use crate::packet::participants::{
+    Controller, Driver, Nationality, Participant, ParticipantsPacket, Team, TelemetryPrivacy,
+};
+use crate::packet::session::{Formula, SafetyCar, Session, SessionPacket, Track, Weather};
+use crate::packet::setup::{CarSetup, CarSetupPacket};
+use crate::packet::status::{CarStatus, CarStatusPacket};
+use crate::packet::telemetry::{Button, Gear, Surface, Telemetry, TelemetryPacket};
+#[cfg(all(feature = "std", feature = "decode"))]
+use crate::packet::DecodeError;
+use crate::packet::Packet;
+use crate::types::{CornerProperty, Property3D, VehicleIndex};
+
+/// Returns the bytes of the capture for the given packet type.
+#[cfg(all(feature = "std", feature = "decode"))]
+pub fn bytes(api_spec: ApiSpec, packet_type: PacketType) -> &'static [u8] {
+    match api_spec {
+        ApiSpec::Nineteen => match packet_type {
+            PacketType::Event => include_bytes!("../fixtures/nineteen/event.f1cap"),
+            PacketType::Lap => include_bytes!("../fixtures/nineteen/lap.f1cap"),
+            PacketType::Motion => include_bytes!("../fixtures/nineteen/motion.f1cap"),
+            PacketType::Participants => include_bytes!("../fixtures/nineteen/participants.f1cap"),
+            PacketType::Session => include_bytes!("../fixtures/nineteen/session.f1cap"),
+            PacketType::Setup => include_bytes!("../fixtures/nineteen/setup.f1cap"),
+            PacketType::Status => include_bytes!("../fixtures/nineteen/status.f1cap"),
+            PacketType::Telemetry => include_bytes!("../fixtures/nineteen/telemetry.f1cap"),
+        },
+    }
+}
+
+/// Open the capture for the given packet type.
+#[cfg(all(feature = "std", feature = "decode"))]
+pub fn capture(
+    api_spec: ApiSpec,
+    packet_type: PacketType,
+) -> Result<CaptureReader<&'static [u8]>, Error> {
+    CaptureReader::new(bytes(api_spec, packet_type))
+}
+
+/// Decode the datagrams in the capture for the given packet type into packets.
+///
+/// An error is returned if a datagram does not contain a complete packet.
+#[cfg(all(feature = "std", feature = "decode"))]
+pub fn packets(api_spec: ApiSpec, packet_type: PacketType) -> Result<Vec<Packet>, Error> {
+    capture(api_spec, packet_type)?
+        .map(|datagram| {
+            datagram?.decode()?.ok_or_else(|| {
+                Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "Fixture contains an incomplete packet.",
+                )
+            })
+        })
+        .collect()
+}
+
+/// Decode the datagrams in the capture for the given packet type as packets of type `T`.
+#[cfg(all(feature = "std", feature = "decode"))]
+pub fn decode<T>(api_spec: ApiSpec, packet_type: PacketType) -> Result<Vec<T>, Error>
+where
+    T: TryFrom<Bytes, Error = DecodeError>,
+{
+    capture(api_spec, packet_type)?
+        .map(|datagram| Ok(T::try_from(datagram?.payload().clone())?))
+        .collect()
+}
+
+/// Returns the header of an F1 2019 packet with the given type, session, time, and frame.
+pub fn header(
+    packet_type: PacketType,
+    session_uid: u64,
+    session_time: Duration,
+    frame_identifier: u32,
+) -> Header {
+    Header::new(
+        ApiSpec::Nineteen,
+        None,
+        packet_type,
+        session_uid,
+        session_time,
+        frame_identifier,
+        0,
+        None,
+    )
+}
+
+/// Returns the header with the given car index of the secondary player in a split-screen session.
+pub fn with_secondary_player(header: Header, secondary_player_car_index: VehicleIndex) -> Header {
+    Header::new(
+        header.api_spec(),
+        *header.game_version(),
+        header.packet_type(),
+        header.session_uid(),
+        *header.session_time(),
+        header.frame_identifier(),
+        header.player_car_index(),
+        Some(secondary_player_car_index),
+    )
+}
+
+/// Returns the valid flying lap of a car in first place, at the given distance into the lap.
+///
+/// The times of the lap are zero, and the total distance is the same as the lap distance.
+pub fn lap(lap_number: u8, lap_distance: f32) -> Lap {
+    Lap::new(
+        Duration::default(),
+        Duration::default(),
+        Duration::default(),
+        Duration::default(),
+        Duration::default(),
+        lap_distance,
+        lap_distance,
+        0.0,
+        1,
+        lap_number,
+        PitStatus::None,
+        Sector::First,
+        true,
+        0,
+        1,
+        DriverStatus::FlyingLap,
+        ResultStatus::Active,
+    )
+}
+
+/// Returns the lap with the given position, which is also the grid position, and pit status.
+pub fn in_position(lap: Lap, position: u8, pit_status: PitStatus) -> Lap {
+    Lap::new(
+        *lap.last_lap_time(),
+        *lap.current_lap_time(),
+        *lap.best_lap_time(),
+        *lap.sector1_time(),
+        *lap.sector2_time(),
+        lap.lap_distance(),
+        lap.total_distance(),
+        lap.safety_car_delta(),
+        position,
+        lap.current_lap_number(),
+        pit_status,
+        lap.sector(),
+        lap.is_valid_lap(),
+        lap.penalties(),
+        position,
+        lap.driver_status(),
+        lap.result_status(),
+    )
+}
+
+/// Returns the lap with the given times of the last and current lap, and sector times.
+///
+/// The best lap time is the time of the last lap.
+pub fn with_times(
+    lap: Lap,
+    last_lap_time: Duration,
+    current_lap_time: Duration,
+    sector1_time: Duration,
+    sector2_time: Duration,
+) -> Lap {
+    Lap::new(
+        last_lap_time,
+        current_lap_time,
+        last_lap_time,
+        sector1_time,
+        sector2_time,
+        lap.lap_distance(),
+        lap.total_distance(),
+        lap.safety_car_delta(),
+        lap.position(),
+        lap.current_lap_number(),
+        lap.pit_status(),
+        lap.sector(),
+        lap.is_valid_lap(),
+        lap.penalties(),
+        lap.grid_position(),
+        lap.driver_status(),
+        lap.result_status(),
+    )
+}
+
+/// Returns a lap packet with the given header and laps.
+pub fn lap_packet(header: Header, laps: Vec<Lap>) -> Packet {
+    Packet::Lap(LapPacket::new(header, laps.into()))
+}
+
+/// Returns the motion of a car with the given position, velocity, and G forces.
+///
+/// The car points along the world axes, and its yaw, pitch, and roll are zero.
+pub fn motion(
+    position: Property3D<f32>,
+    velocity: Property3D<f32>,
+    g_force: Property3D<f32>,
+) -> Motion {
+    Motion::new(
+        position,
+        velocity,
+        Property3D::default(),
+        Property3D::default(),
+        g_force,
+        0.0,
+        0.0,
+        0.0,
+    )
+}
+
+/// Returns a motion packet with the given header, cars, and wheel slip of the player's car.
+///
+/// The other data of the player's car is zero.
+pub fn motion_packet(header: Header, cars: Vec<Motion>, wheel_slip: CornerProperty<f32>) -> Packet {
+    Packet::Motion(MotionPacket::new(
+        header,
+        cars.into(),
+        CornerProperty::default(),
+        CornerProperty::default(),
+        CornerProperty::default(),
+        CornerProperty::default(),
+        wheel_slip,
+        Property3D::default(),
+        Property3D::default(),
+        Property3D::default(),
+        0.0,
+    ))
+}
+
+/// Returns a human participant with the given name and telemetry privacy.
+///
+/// The participant is Lewis Hamilton in the Mercedes with number 44.
+pub fn participant(name: &str, telemetry_privacy: TelemetryPrivacy) -> Participant {
+    Participant::new(
+        Controller::Human,
+        Driver::LewisHamilton,
+        Team::Mercedes,
+        44,
+        Nationality::British,
+        String::from(name),
+        Some(telemetry_privacy),
+    )
+}
+
+/// Returns a participants packet with the given header and participants, who are all active.
+pub fn participants_packet(header: Header, participants: Vec<Participant>) -> Packet {
+    Packet::Participants(ParticipantsPacket::new(
+        header,
+        participants.len() as u8,
+        participants.into(),
+    ))
+}
+
+/// Returns a setup with moderate downforce, differential, and suspension settings.
+pub fn setup() -> CarSetup {
+    CarSetup::new(
+        5, 6, 75, 60, -3.0, -1.5, 0.05, 0.2, 5, 4, 5, 6, 3, 4, 100, 56, 23.0, 21.5, 6, 10.0,
+    )
+}
+
+/// Returns a setup packet with the given header and setups.
+pub fn setup_packet(header: Header, setups: Vec<CarSetup>) -> Packet {
+    Packet::Setup(CarSetupPacket::new(header, setups.into()))
+}
+
+/// Returns a session packet with the given header, type of session, and track.
+///
+/// The session has clear weather, 50 laps, and an hour to go, and no safety car is out.
+pub fn session_packet(header: Header, session_type: Session, track: Track) -> Packet {
+    Packet::Session(SessionPacket::new(
+        header,
+        Weather::Clear,
+        30,
+        20,
+        50,
+        5000,
+        session_type,
+        track,
+        Formula::ModernF1,
+        Duration::from_secs(3600),
+        Duration::from_secs(3600),
+        80,
+        false,
+        false,
+        0,
+        false,
+        Vec::new(),
+        SafetyCar::None,
+        false,
+        None,
+    ))
+}
+
+/// Returns the status of a car with the given maximum engine speed and number of gears.
+///
+/// The car is undamaged, its tyres are new, and its tank and battery are empty.
+pub fn status(max_rpm: u16, gear_count: u8) -> CarStatus {
+    CarStatus::new(
+        Default::default(),
+        false,
+        Default::default(),
+        0,
+        false,
+        0.0,
+        0.0,
+        0.0,
+        max_rpm,
+        0,
+        gear_count,
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        0,
+        0,
+        0,
+        0,
+        0,
+        Default::default(),
+        0.0,
+        Default::default(),
+        0.0,
+        0.0,
+        0.0,
+    )
+}
+
+/// Returns a status packet with the given header and cars.
+pub fn status_packet(header: Header, statuses: Vec<CarStatus>) -> Packet {
+    Packet::Status(CarStatusPacket::new(header, statuses.into()))
+}
+
+/// Returns the telemetry of a car with the given speed, inputs, gear, and engine speed.
+///
+/// The car drives on tarmac, and its temperatures and tyre pressures are zero.
+pub fn telemetry(
+    speed: u16,
+    throttle: f32,
+    steering: f32,
+    brake: f32,
+    gear: Gear,
+    engine_rpm: u16,
+) -> Telemetry {
+    Telemetry::new(
+        speed,
+        throttle,
+        steering,
+        brake,
+        0,
+        gear,
+        engine_rpm,
+        false,
+        0,
+        CornerProperty::default(),
+        CornerProperty::default(),
+        CornerProperty::default(),
+        0,
+        CornerProperty::default(),
+        CornerProperty::default(),
+    )
+}
+
+/// Returns the telemetry with the given brake and tyre temperatures, and surfaces under the tyres.
+pub fn with_corners(
+    telemetry: Telemetry,
+    brake_temperature: CornerProperty<u16>,
+    tyre_surface_temperature: CornerProperty<u16>,
+    surface_type: CornerProperty<Surface>,
+) -> Telemetry {
+    Telemetry::new(
+        telemetry.speed(),
+        telemetry.throttle(),
+        telemetry.steering(),
+        telemetry.brake(),
+        telemetry.clutch(),
+        telemetry.gear(),
+        telemetry.engine_rpm(),
+        telemetry.drs(),
+        telemetry.rev_lights(),
+        brake_temperature,
+        tyre_surface_temperature,
+        *telemetry.tyre_inner_temperature(),
+        telemetry.engine_temperature(),
+        *telemetry.tyre_pressure(),
+        surface_type,
+    )
+}
+
+/// Returns a telemetry packet with the given header and cars, and no buttons pressed.
+pub fn telemetry_packet(header: Header, telemetry: Vec<Telemetry>) -> Packet {
+    Packet::Telemetry(TelemetryPacket::new(
+        header,
+        telemetry.into(),
+        Button::empty(),
+    ))
+}
+
+#[cfg(all(test, feature = "std", feature = "decode"))]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{decode, packets};
+    use crate::packet::event::Event;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::lap::LapPacket;
+    use crate::packet::participants::{CarClass, Driver, Team};
+    use crate::packet::Packet;
+
+    #[test]
+    fn every_fixture_decodes_as_its_type() {
+        let packet_types = [
+            PacketType::Event,
+            PacketType::Lap,
+            PacketType::Motion,
+            PacketType::Participants,
+            PacketType::Session,
+            PacketType::Setup,
+            PacketType::Status,
+            PacketType::Telemetry,
+        ];
+
+        for packet_type in packet_types.iter().copied() {
+            let packets = packets(ApiSpec::Nineteen, packet_type).unwrap();
+
+            assert!(packets.len() >= 2);
+
+            for packet in packets {
+                assert_eq!(packet_type, packet.header().packet_type());
+            }
+        }
+    }
+
+    #[test]
+    fn fixtures_decode_field_values() {
+        let laps: Vec<LapPacket> = decode(ApiSpec::Nineteen, PacketType::Lap).unwrap();
+        let lap = laps[0].laps()[0];
+
+        assert_eq!(1, lap.position());
+        assert_eq!(5, lap.current_lap_number());
+        assert_eq!(Duration::from_secs_f32(90.879), *lap.best_lap_time());
+
+        let participants = packets(ApiSpec::Nineteen, PacketType::Participants).unwrap();
+
+        match &participants[0] {
+            Packet::Participants(packet) => {
+                let participant = &packet.participants()[3];
+
+                assert_eq!(&Driver::CharlesLeclerc, participant.driver());
+                assert_eq!(Team::Ferrari, participant.team());
+                assert_eq!(Some(CarClass::ModernF1), participant.car_class());
+                assert_eq!("Charles LECLERC", participant.name());
+            }
+            packet => panic!("Expected participants, got {:?}", packet),
+        }
+
+        let events = packets(ApiSpec::Nineteen, PacketType::Event).unwrap();
+
+        match &events[0] {
+            Packet::Event(packet) => assert_eq!(Event::SessionStarted, *packet.event()),
+            packet => panic!("Expected an event, got {:?}", packet),
+        }
+    }
+}
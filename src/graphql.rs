@@ -0,0 +1,413 @@
+//! GraphQL API over the session model
+//!
+//! Frontend teams often prefer a typed query interface over ad-hoc REST routes. This module exposes
+//! the `SessionTracker` through an `async-graphql` schema, which supports nested queries from the
+//! drivers to their laps and sectors, and a subscription for the events of the session.
+//!
+//! The schema is not bound to an HTTP server. Applications feed packets into a `LiveSession`, and
+//! pass the schema it creates to the integration of `async-graphql` for their web framework.
+//!
+//! ```graphql
+//! query {
+//!   session { track sessionType totalLaps }
+//!   drivers {
+//!     position
+//!     name
+//!     laps { lapNumber lapTime sectors { number time } }
+//!   }
+//! }
+//! ```
+
+use std::sync::{Arc, RwLock};
+
+use async_graphql::{Context, EmptyMutation, Object, Schema, SimpleObject, Subscription};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::packet::event::{Event, EventPacket};
+use crate::packet::Packet;
+use crate::tracker::{CarHistory, LapRecord, SessionTracker};
+use crate::types::VehicleIndex;
+
+/// Number of events that are buffered for slow subscribers
+const EVENT_CAPACITY: usize = 64;
+
+/// Schema of the GraphQL API
+pub type SessionSchema = Schema<QueryRoot, EmptyMutation, SubscriptionRoot>;
+
+/// Session model that is shared with the GraphQL schema
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::graphql::LiveSession;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let session = LiveSession::new();
+/// let schema = session.schema();
+///
+/// for packet in packets() {
+///     session.update(&packet);
+/// }
+///
+/// let response = schema.execute("{ drivers { name } }").await;
+/// assert!(response.errors.is_empty());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LiveSession {
+    tracker: Arc<RwLock<SessionTracker>>,
+    events: broadcast::Sender<EventPacket>,
+}
+
+impl LiveSession {
+    /// Create a new session model without any data.
+    pub fn new() -> Self {
+        LiveSession {
+            tracker: Arc::new(RwLock::new(SessionTracker::default())),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Update the model with a new packet, and publish events to the subscribers.
+    pub fn update(&self, packet: &Packet) {
+        self.tracker.write().unwrap().update(packet);
+
+        if let Packet::Event(event) = packet {
+            // Sending only fails when nobody is subscribed
+            let _ = self.events.send(*event);
+        }
+    }
+
+    /// Create the GraphQL schema that answers queries from this model.
+    pub fn schema(&self) -> SessionSchema {
+        Schema::build(QueryRoot, EmptyMutation, SubscriptionRoot)
+            .data(self.clone())
+            .finish()
+    }
+}
+
+impl Default for LiveSession {
+    fn default() -> Self {
+        LiveSession::new()
+    }
+}
+
+/// Queries of the GraphQL API
+#[derive(Debug, Default)]
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// The current session, or null if no session data has been received yet.
+    async fn session(&self, ctx: &Context<'_>) -> Option<SessionNode> {
+        let tracker = tracker(ctx).read().unwrap();
+        let session = tracker.session().as_ref()?;
+
+        Some(SessionNode {
+            session_uid: session.header().session_uid().to_string(),
+            session_type: format!("{:?}", session.session_type()),
+            track: format!("{:?}", session.track()),
+            weather: format!("{:?}", session.weather()),
+            track_temperature: session.track_temperature(),
+            air_temperature: session.air_temperature(),
+            total_laps: session.total_laps(),
+            time_left: session.time_left().as_secs_f64(),
+        })
+    }
+
+    /// The drivers in the session, ordered by their position.
+    async fn drivers(&self, ctx: &Context<'_>) -> Vec<DriverNode> {
+        let tracker = tracker(ctx).read().unwrap();
+
+        tracker
+            .classification()
+            .iter()
+            .filter_map(|entry| driver(&tracker, entry.vehicle_index()))
+            .collect()
+    }
+
+    /// The driver of the car with the given vehicle index.
+    async fn driver(&self, ctx: &Context<'_>, vehicle_index: VehicleIndex) -> Option<DriverNode> {
+        driver(&tracker(ctx).read().unwrap(), vehicle_index)
+    }
+}
+
+/// Subscriptions of the GraphQL API
+#[derive(Debug, Default)]
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Events of the session, as they occur.
+    async fn events(&self, ctx: &Context<'_>) -> impl Stream<Item = EventNode> {
+        let receiver = ctx.data_unchecked::<LiveSession>().events.subscribe();
+
+        // Subscribers that fall behind skip the events they missed
+        BroadcastStream::new(receiver).filter_map(|event| event.ok().map(EventNode::from))
+    }
+}
+
+/// Session that is tracked by the API
+#[derive(Debug, SimpleObject, PartialEq, Clone)]
+#[graphql(name = "Session")]
+pub struct SessionNode {
+    /// Unique id of the session.
+    session_uid: String,
+
+    /// Type of the session.
+    session_type: String,
+
+    /// Race track of the session.
+    track: String,
+
+    /// Current weather.
+    weather: String,
+
+    /// Track temperature in degrees celsius.
+    track_temperature: i8,
+
+    /// Air temperature in degrees celsius.
+    air_temperature: i8,
+
+    /// Total number of laps of the race.
+    total_laps: u8,
+
+    /// Time that is left in the session in seconds.
+    time_left: f64,
+}
+
+/// Driver in the session and the laps they completed
+#[derive(Debug, PartialEq, Clone)]
+pub struct DriverNode {
+    vehicle_index: VehicleIndex,
+    name: Option<String>,
+    position: Option<u8>,
+    history: CarHistory,
+}
+
+#[Object(name = "Driver")]
+impl DriverNode {
+    /// Index of the driver's car.
+    async fn vehicle_index(&self) -> VehicleIndex {
+        self.vehicle_index
+    }
+
+    /// Name of the driver, if the participants are known.
+    async fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Current position of the driver.
+    async fn position(&self) -> Option<u8> {
+        self.position
+    }
+
+    /// Number of pit stops the driver has made.
+    async fn pit_stops(&self) -> u8 {
+        self.history.pit_stops()
+    }
+
+    /// Best valid lap of the driver.
+    async fn best_lap(&self) -> Option<LapNode> {
+        self.history.best_lap().map(|lap| LapNode(*lap))
+    }
+
+    /// Laps the driver has completed.
+    async fn laps(&self) -> Vec<LapNode> {
+        self.history
+            .laps()
+            .iter()
+            .map(|lap| LapNode(*lap))
+            .collect()
+    }
+}
+
+/// Lap that has been completed by a driver
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct LapNode(LapRecord);
+
+#[Object(name = "Lap")]
+impl LapNode {
+    /// Number of the lap.
+    async fn lap_number(&self) -> u8 {
+        self.0.lap_number()
+    }
+
+    /// Time of the lap in seconds.
+    async fn lap_time(&self) -> f64 {
+        self.0.lap_time().as_secs_f64()
+    }
+
+    /// Whether the lap was valid.
+    async fn valid(&self) -> bool {
+        self.0.is_valid()
+    }
+
+    /// Position of the driver at the end of the lap.
+    async fn position(&self) -> u8 {
+        self.0.position()
+    }
+
+    /// Tyre compound the lap was driven on, if known.
+    async fn tyre_compound(&self) -> Option<String> {
+        self.0.tyre_compound().map(|tyre| format!("{:?}", tyre))
+    }
+
+    /// Whether the driver entered the pit lane during the lap.
+    async fn pit_stop(&self) -> bool {
+        self.0.pit_stop()
+    }
+
+    /// Times of the three sectors of the lap.
+    async fn sectors(&self) -> Vec<SectorNode> {
+        [
+            self.0.sector1_time(),
+            self.0.sector2_time(),
+            self.0.sector3_time(),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(index, time)| SectorNode {
+            number: index as u8 + 1,
+            time: time.as_secs_f64(),
+        })
+        .collect()
+    }
+}
+
+/// Sector of a lap
+#[derive(Debug, SimpleObject, PartialEq, Copy, Clone)]
+#[graphql(name = "Sector")]
+pub struct SectorNode {
+    /// Number of the sector, starting at 1.
+    number: u8,
+
+    /// Time spent in the sector in seconds.
+    time: f64,
+}
+
+/// Event that occurred in the session
+#[derive(Debug, SimpleObject, PartialEq, Clone)]
+#[graphql(name = "Event")]
+pub struct EventNode {
+    /// Type of the event, e.g. `FastestLap`.
+    kind: String,
+
+    /// Description of the event.
+    description: String,
+
+    /// Index of the car the event refers to, if any.
+    vehicle_index: Option<VehicleIndex>,
+
+    /// Session time at which the event occurred in seconds.
+    session_time: f64,
+}
+
+impl From<EventPacket> for EventNode {
+    fn from(packet: EventPacket) -> Self {
+        let event = packet.event();
+        let (kind, vehicle_index) = match event {
+            Event::ChequeredFlag => ("ChequeredFlag", None),
+            Event::DrsDisabled => ("DrsDisabled", None),
+            Event::DrsEnabled => ("DrsEnabled", None),
+            Event::FastestLap(lap) => ("FastestLap", Some(lap.vehicle_index())),
+            Event::RaceWinner(winner) => ("RaceWinner", Some(winner.vehicle_index())),
+            Event::Retirement(retirement) => ("Retirement", Some(retirement.vehicle_index())),
+            Event::SessionEnded => ("SessionEnded", None),
+            Event::SessionStarted => ("SessionStarted", None),
+            Event::TeammatesInPits(teammate) => ("TeammatesInPits", Some(teammate.vehicle_index())),
+        };
+
+        EventNode {
+            kind: kind.into(),
+            description: event.to_string(),
+            vehicle_index,
+            session_time: packet.header().session_time().as_secs_f64(),
+        }
+    }
+}
+
+fn tracker<'a>(ctx: &Context<'a>) -> &'a RwLock<SessionTracker> {
+    &ctx.data_unchecked::<LiveSession>().tracker
+}
+
+fn driver(tracker: &SessionTracker, vehicle_index: VehicleIndex) -> Option<DriverNode> {
+    let history = tracker.car(vehicle_index)?;
+    let position = tracker
+        .laps()
+        .as_ref()
+        .and_then(|laps| laps.car(vehicle_index))
+        .map(|lap| lap.position())
+        .filter(|position| *position > 0);
+
+    Some(DriverNode {
+        vehicle_index,
+        name: tracker.name(vehicle_index).map(String::from),
+        position,
+        history: history.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio_stream::StreamExt;
+
+    use crate::fixtures;
+    use crate::graphql::LiveSession;
+    use crate::packet::event::{Event, EventPacket};
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::Packet;
+
+    #[tokio::test]
+    async fn query_drivers_with_nested_laps() {
+        let session = LiveSession::new();
+
+        for packet_type in [PacketType::Session, PacketType::Lap] {
+            for packet in fixtures::packets(ApiSpec::Nineteen, packet_type).unwrap() {
+                session.update(&packet);
+            }
+        }
+
+        let response = session
+            .schema()
+            .execute("{ session { track } drivers { vehicleIndex laps { sectors { number } } } }")
+            .await;
+        let data = response.data.into_json().unwrap();
+
+        assert!(response.errors.is_empty());
+        assert!(data["session"]["track"].is_string());
+        assert!(data["drivers"].is_array());
+    }
+
+    #[tokio::test]
+    async fn subscription_publishes_events() {
+        let session = LiveSession::new();
+        let mut events = session
+            .schema()
+            .execute_stream("subscription { events { kind sessionTime } }");
+
+        // The subscription is only registered once the stream is polled
+        let mut next = tokio::spawn(async move { events.next().await.unwrap() });
+
+        let header = fixtures::header(PacketType::Event, 1, Duration::from_secs(2), 0);
+
+        let response = loop {
+            session.update(&Packet::Event(EventPacket::new(header, Event::DrsEnabled)));
+
+            if let Ok(response) = tokio::time::timeout(Duration::from_millis(10), &mut next).await {
+                break response.unwrap();
+            }
+        };
+        let data = response.data.into_json().unwrap();
+
+        assert_eq!("DrsEnabled", data["events"]["kind"]);
+        assert_eq!(2.0, data["events"]["sessionTime"]);
+    }
+}
@@ -0,0 +1,188 @@
+//! Callbacks for the different types of packets
+//!
+//! Applications that receive packets typically match on the `Packet` enum, and call a function for
+//! each of the eight packet types. The `PacketHandler` trait provides this match once. It has a
+//! method for each packet type that does nothing by default, so that applications implement only
+//! the methods for the packets they care about. With the `net` feature, `dispatch` feeds a stream
+//! of packets into a handler.
+
+#[cfg(feature = "net")]
+use tokio_stream::{Stream, StreamExt};
+
+use crate::packet::event::EventPacket;
+use crate::packet::lap::LapPacket;
+use crate::packet::motion::MotionPacket;
+use crate::packet::participants::ParticipantsPacket;
+use crate::packet::session::SessionPacket;
+use crate::packet::setup::CarSetupPacket;
+use crate::packet::status::CarStatusPacket;
+use crate::packet::telemetry::TelemetryPacket;
+use crate::packet::Packet;
+
+/// Handler with a callback for each type of packet
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::handler::PacketHandler;
+/// use f1_api::packet::lap::LapPacket;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// #[derive(Default)]
+/// struct LapCounter {
+///     laps: usize,
+/// }
+///
+/// impl PacketHandler for LapCounter {
+///     fn on_lap(&mut self, _packet: &LapPacket) {
+///         self.laps += 1;
+///     }
+/// }
+///
+/// let mut counter = LapCounter::default();
+///
+/// for packet in packets() {
+///     counter.handle(&packet);
+/// }
+/// ```
+pub trait PacketHandler {
+    /// Handle an event packet.
+    fn on_event(&mut self, _packet: &EventPacket) {}
+
+    /// Handle a lap data packet.
+    fn on_lap(&mut self, _packet: &LapPacket) {}
+
+    /// Handle a motion packet.
+    fn on_motion(&mut self, _packet: &MotionPacket) {}
+
+    /// Handle a participants packet.
+    fn on_participants(&mut self, _packet: &ParticipantsPacket) {}
+
+    /// Handle a session packet.
+    fn on_session(&mut self, _packet: &SessionPacket) {}
+
+    /// Handle a car setup packet.
+    fn on_setup(&mut self, _packet: &CarSetupPacket) {}
+
+    /// Handle a car status packet.
+    fn on_status(&mut self, _packet: &CarStatusPacket) {}
+
+    /// Handle a telemetry packet.
+    fn on_telemetry(&mut self, _packet: &TelemetryPacket) {}
+
+    /// Call the method for the type of the given packet.
+    fn handle(&mut self, packet: &Packet) {
+        match packet {
+            Packet::Event(packet) => self.on_event(packet),
+            Packet::Lap(packet) => self.on_lap(packet),
+            Packet::Motion(packet) => self.on_motion(packet),
+            Packet::Participants(packet) => self.on_participants(packet),
+            Packet::Session(packet) => self.on_session(packet),
+            Packet::Setup(packet) => self.on_setup(packet),
+            Packet::Status(packet) => self.on_status(packet),
+            Packet::Telemetry(packet) => self.on_telemetry(packet),
+        }
+    }
+}
+
+/// Feed all packets of a stream into a handler, until the stream ends.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use f1_api::handler::{dispatch, PacketHandler};
+/// use f1_api::packet::event::EventPacket;
+/// use f1_api::F1;
+///
+/// struct EventPrinter;
+///
+/// impl PacketHandler for EventPrinter {
+///     fn on_event(&mut self, packet: &EventPacket) {
+///         println!("{}", packet);
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let ip_address = IpAddr::from([0, 0, 0, 0]);
+/// let port = 20777;
+/// let socket = SocketAddr::new(ip_address, port);
+///
+/// let stream = F1::stream(socket).unwrap();
+/// dispatch(stream, &mut EventPrinter).await;
+/// # }
+/// ```
+#[cfg(feature = "net")]
+pub async fn dispatch<S, H>(mut stream: S, handler: &mut H)
+where
+    S: Stream<Item = Packet> + Unpin,
+    H: PacketHandler + ?Sized,
+{
+    while let Some(packet) = stream.next().await {
+        handler.handle(&packet);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::handler::PacketHandler;
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::LapPacket;
+
+    #[derive(Default)]
+    struct LapCounter {
+        laps: usize,
+    }
+
+    impl PacketHandler for LapCounter {
+        fn on_lap(&mut self, _packet: &LapPacket) {
+            self.laps += 1;
+        }
+    }
+
+    #[test]
+    fn handle_calls_method_for_packet_type() {
+        let mut counter = LapCounter::default();
+
+        counter.handle(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            Vec::new(),
+        ));
+        counter.handle(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            Vec::new(),
+        ));
+
+        assert_eq!(2, counter.laps);
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn dispatch_stream() {
+        let mut counter = LapCounter::default();
+        let stream = tokio_stream::iter(vec![
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                Vec::new(),
+            ),
+        ]);
+
+        crate::handler::dispatch(stream, &mut counter).await;
+
+        assert_eq!(3, counter.laps);
+    }
+}
@@ -0,0 +1,87 @@
+//! Minimal HTTP/1.1 for the built-in servers
+//!
+//! The servers in this crate answer simple `GET` requests from browsers and scripts. Instead of
+//! depending on a full HTTP stack, this module reads the request line of a request, and writes
+//! responses with a fixed set of headers.
+
+use std::io::{Error, ErrorKind};
+
+use tokio::io::AsyncReadExt;
+#[cfg(feature = "rest")]
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+/// Maximum size of the head of a request in bytes
+const MAX_REQUEST_SIZE: usize = 16 * 1024;
+
+/// Method and path of a request
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub(crate) struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String,
+}
+
+/// Read the head of a request, and return its method and path.
+pub(crate) async fn read_request(stream: &mut TcpStream) -> Result<Request, Error> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+
+        if read == 0 || request.len() > MAX_REQUEST_SIZE {
+            break;
+        }
+
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&request);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+
+    match (request_line.next(), request_line.next()) {
+        (Some(method), Some(path)) => Ok(Request {
+            method: method.into(),
+            path: path.split('?').next().unwrap_or_default().into(),
+        }),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            "Failed to read the request line of the HTTP request.",
+        )),
+    }
+}
+
+/// Write a complete response with the given status and JSON body, and close the connection.
+#[cfg(feature = "rest")]
+pub(crate) async fn write_json(
+    stream: &mut TcpStream,
+    status: u16,
+    body: &str,
+) -> Result<(), Error> {
+    let response = format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(feature = "rest")]
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
@@ -0,0 +1,250 @@
+//! Output of packets to other processes on the same machine
+//!
+//! Applications are often split into several processes, for example a service that receives the
+//! telemetry and an Electron UI that displays it. Instead of opening another UDP or TCP port, the
+//! `IpcSink` publishes the packets over a Unix domain socket, or a named pipe on Windows. Any number
+//! of processes can connect to the socket, and receive every packet as a JSON line in the format of
+//! the `jsonl` module.
+//!
+//! Each client has a bounded queue. When a client falls behind and its queue is full, packets are
+//! dropped for this client instead of slowing down the others. Clients that disconnect are removed.
+
+use std::io::Error;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{channel, Sender};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::jsonl::JsonLinesWriter;
+use crate::packet::Packet;
+use crate::sink::{ExportFuture, Exporter, Item};
+
+/// Number of packets that are queued for each client
+const CLIENT_CAPACITY: usize = 256;
+
+/// Sink that publishes packets to local processes
+///
+/// On Unix, the sink binds a domain socket at the given path. On Windows, it creates a named pipe
+/// with the given name, e.g. `\\.\pipe\f1-api`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use f1_api::ipc::IpcSink;
+/// use f1_api::F1;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let game = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+///
+/// IpcSink::bind("/tmp/f1-api.sock")
+///     .unwrap()
+///     .serve(F1::stream(game).unwrap())
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct IpcSink {
+    listener: Listener,
+    clients: Vec<Sender<Bytes>>,
+}
+
+impl IpcSink {
+    /// Bind the sink to a Unix domain socket at the given path.
+    ///
+    /// The socket file is removed when the sink is dropped.
+    #[cfg(unix)]
+    pub fn bind<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let listener = tokio::net::UnixListener::bind(&path)?;
+
+        Ok(IpcSink {
+            listener: Listener { listener, path },
+            clients: Vec::new(),
+        })
+    }
+
+    /// Create a named pipe with the given name for the sink.
+    #[cfg(windows)]
+    pub fn bind(name: &str) -> Result<Self, Error> {
+        let next = tokio::net::windows::named_pipe::ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(name)?;
+
+        Ok(IpcSink {
+            listener: Listener {
+                name: name.into(),
+                next,
+            },
+            clients: Vec::new(),
+        })
+    }
+
+    /// Publish the packets of the stream to the connected processes until the stream ends.
+    pub async fn serve<S>(mut self, packets: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Packet> + Unpin,
+    {
+        let mut packets = packets;
+
+        loop {
+            tokio::select! {
+                client = self.listener.accept() => self.clients.push(spawn_client(client?)),
+                packet = packets.next() => match packet {
+                    Some(packet) => publish(&mut self.clients, &packet)?,
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+
+    /// Register the clients that are waiting to connect, without waiting for new ones.
+    async fn accept_pending(&mut self) -> Result<(), Error> {
+        loop {
+            tokio::select! {
+                biased;
+                client = self.listener.accept() => self.clients.push(spawn_client(client?)),
+                _ = std::future::ready(()) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Packets are published to the connected processes, while changes are ignored. Processes that
+/// connect are registered whenever a packet is exported.
+impl Exporter for IpcSink {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        Box::pin(async move {
+            match item {
+                Item::Packet(packet) => {
+                    self.accept_pending().await?;
+                    publish(&mut self.clients, packet)
+                }
+                Item::Change(..) => Ok(()),
+            }
+        })
+    }
+}
+
+/// Listener for the Unix domain socket
+#[cfg(unix)]
+#[derive(Debug)]
+struct Listener {
+    listener: tokio::net::UnixListener,
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl Listener {
+    async fn accept(&mut self) -> Result<tokio::net::UnixStream, Error> {
+        self.listener.accept().await.map(|(stream, _)| stream)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Listener for the named pipe
+///
+/// A named pipe has one instance per client. The listener always keeps an instance open for the
+/// next client, and creates a new one when a client connects.
+#[cfg(windows)]
+#[derive(Debug)]
+struct Listener {
+    name: String,
+    next: tokio::net::windows::named_pipe::NamedPipeServer,
+}
+
+#[cfg(windows)]
+impl Listener {
+    async fn accept(&mut self) -> Result<tokio::net::windows::named_pipe::NamedPipeServer, Error> {
+        self.next.connect().await?;
+
+        let next = tokio::net::windows::named_pipe::ServerOptions::new().create(&self.name)?;
+        Ok(std::mem::replace(&mut self.next, next))
+    }
+}
+
+/// Spawn a task that writes the queued packets to a client.
+fn spawn_client<W>(mut client: W) -> Sender<Bytes>
+where
+    W: AsyncWrite + Send + Unpin + 'static,
+{
+    let (sender, mut receiver) = channel::<Bytes>(CLIENT_CAPACITY);
+
+    tokio::spawn(async move {
+        while let Some(line) = receiver.recv().await {
+            if client.write_all(&line).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    sender
+}
+
+/// Queue the packet for every client, and remove the clients that have disconnected.
+fn publish(clients: &mut Vec<Sender<Bytes>>, packet: &Packet) -> Result<(), Error> {
+    let mut writer = JsonLinesWriter::new(Vec::new());
+    writer.write(packet)?;
+    let line = Bytes::from(writer.into_inner());
+
+    clients.retain(|client| !matches!(client.try_send(line.clone()), Err(TrySendError::Closed(_))));
+
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::UnixStream;
+
+    use crate::fixtures;
+    use crate::ipc::IpcSink;
+    use crate::packet::header::{ApiSpec, PacketType};
+
+    #[tokio::test]
+    async fn serve_publishes_json_lines() {
+        let path = std::env::temp_dir().join(format!("f1-api-ipc-{}.sock", std::process::id()));
+        let sink = IpcSink::bind(&path).unwrap();
+
+        let packets = fixtures::packets(ApiSpec::Nineteen, PacketType::Lap).unwrap();
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let stream = tokio_stream::wrappers::ReceiverStream::new(receiver);
+        let server = tokio::spawn(sink.serve(stream));
+
+        let client = UnixStream::connect(&path).await.unwrap();
+        let mut lines = BufReader::new(client).lines();
+
+        // The client is registered asynchronously, so packets are sent until one arrives
+        let line = loop {
+            sender.send(packets[0].clone()).await.unwrap();
+
+            if let Ok(line) =
+                tokio::time::timeout(Duration::from_millis(10), lines.next_line()).await
+            {
+                break line.unwrap().unwrap();
+            }
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!("lap", json["type"]);
+
+        drop(sender);
+        server.await.unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
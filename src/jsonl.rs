@@ -0,0 +1,282 @@
+//! JSON lines for shell pipelines
+//!
+//! Tools like `jq`, or processors written in other languages, read one JSON object per line from
+//! their standard input. The `JsonLinesWriter` writes each packet as such a line, and the
+//! `JsonLinesSource` reads the lines back as a source of packets, so that packets can be piped
+//! through other programs and back into an application that uses this crate.
+//!
+//! Each line contains the type and header of the packet, the version of its schema, and its data
+//! with one object per car. The fields of the data are named like the columns of the flat files of
+//! the `export` module. Lines that are written from a datagram also contain the datagram as a
+//! hexadecimal string, which allows the `JsonLinesSource` to decode the packet again without any
+//! loss. Lines without the datagram can be processed by other programs, but not read back. Lines
+//! that were written by an older version of this crate can be upgraded to the current schema with
+//! `schema::migrate`.
+//!
+//! ```json
+//! {"type":"lap","schema_version":1,"session_uid":"4242","session_time":12.5,"frame_identifier":750,"player_car_index":0,"data":[...],"datagram":"e307..."}
+//! ```
+
+use std::io::{BufRead, Error, ErrorKind, Write};
+
+use serde_json::{Map, Value};
+
+use crate::export::json::json_value;
+use crate::export::table;
+use crate::packet::Packet;
+use crate::schema::{SCHEMA_VERSION, VERSION_FIELD};
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+use crate::sink::{ExportFuture, Exporter, Item};
+use crate::source::PacketSource;
+
+/// Number of columns at the start of every row that contain the fields of the header
+const HEADER_COLUMNS: usize = 4;
+
+/// Writer of one JSON object per packet and line
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::jsonl::JsonLinesWriter;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut writer = JsonLinesWriter::new(std::io::stdout());
+///
+/// for packet in packets() {
+///     writer.write(&packet)?;
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct JsonLinesWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    /// Create a new writer that writes lines to the given writer.
+    pub fn new(writer: W) -> Self {
+        JsonLinesWriter { writer }
+    }
+
+    /// Write a line for the packet.
+    ///
+    /// The line does not contain the datagram, and can therefore not be read back by the
+    /// `JsonLinesSource`.
+    pub fn write(&mut self, packet: &Packet) -> Result<(), Error> {
+        self.write_line(to_object(packet))
+    }
+
+    /// Decode the datagram, and write a line for its packet that includes the datagram.
+    pub fn write_datagram(&mut self, datagram: &[u8]) -> Result<(), Error> {
+        let packet = Packet::from_datagram(datagram)?;
+
+        let mut object = to_object(&packet);
+        object.insert("datagram".into(), Value::String(hex(datagram)));
+
+        self.write_line(object)
+    }
+
+    /// Flush the lines that have been written.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_line(&mut self, object: Map<String, Value>) -> Result<(), Error> {
+        serde_json::to_writer(&mut self.writer, &object)?;
+        self.writer.write_all(b"\n")
+    }
+}
+
+/// Packets are written as lines without their datagram, while changes are ignored.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+impl<W: Write + Send> Exporter for JsonLinesWriter<W> {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        let result = match item {
+            Item::Packet(packet) => self.write(packet),
+            Item::Change(..) => Ok(()),
+        };
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn flush(&mut self) -> ExportFuture<'_> {
+        Box::pin(std::future::ready(JsonLinesWriter::flush(self)))
+    }
+}
+
+/// Source of the packets in JSON lines
+///
+/// The source decodes the datagram of each line. Empty lines are skipped, and lines without a
+/// datagram return an error.
+///
+/// # Examples
+///
+/// ```no_run
+/// use f1_api::jsonl::JsonLinesSource;
+/// use f1_api::source::PacketSource;
+///
+/// let mut source = JsonLinesSource::new(std::io::stdin().lock());
+///
+/// while let Some(packet) = source.next_packet()? {
+///     println!("{:?}", packet.header());
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct JsonLinesSource<R: BufRead> {
+    reader: R,
+    line: String,
+}
+
+impl<R: BufRead> JsonLinesSource<R> {
+    /// Create a new source that reads lines from the given reader.
+    pub fn new(reader: R) -> Self {
+        JsonLinesSource {
+            reader,
+            line: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> PacketSource for JsonLinesSource<R> {
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error> {
+        loop {
+            self.line.clear();
+
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+
+            if self.line.trim().is_empty() {
+                continue;
+            }
+
+            let object: Value = serde_json::from_str(&self.line)?;
+            let datagram = object
+                .get("datagram")
+                .and_then(Value::as_str)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        "JSON line does not contain the datagram of the packet.",
+                    )
+                })?;
+
+            return Ok(Some(Packet::from_datagram(&unhex(datagram)?)?));
+        }
+    }
+}
+
+/// Convert a packet to an object with its type, header, and data.
+pub(crate) fn to_object(packet: &Packet) -> Map<String, Value> {
+    let packet_type = table::packet_type(packet);
+    let columns = table::columns(packet_type);
+    let rows = table::rows(packet);
+
+    let mut object = Map::new();
+    object.insert("type".into(), table::name(packet_type).into());
+    object.insert(VERSION_FIELD.into(), SCHEMA_VERSION.into());
+
+    if let Some(row) = rows.first() {
+        for (column, value) in columns.iter().zip(row).take(HEADER_COLUMNS) {
+            object.insert(column.name.clone(), json_value(value));
+        }
+    }
+
+    let data = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .zip(row)
+                .skip(HEADER_COLUMNS)
+                .map(|(column, value)| (column.name.clone(), json_value(value)))
+                .collect::<Map<String, Value>>()
+                .into()
+        })
+        .collect();
+
+    object.insert("data".into(), Value::Array(data));
+    object
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            "Datagram is not a hexadecimal string.",
+        )
+    };
+
+    if !text.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|index| {
+            text.get(index..index + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::fixtures;
+    use crate::jsonl::{JsonLinesSource, JsonLinesWriter};
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::Packet;
+    use crate::source::PacketSource;
+
+    #[test]
+    fn write_datagram_and_read_back() {
+        let mut writer = JsonLinesWriter::new(Vec::new());
+
+        for datagram in fixtures::capture(ApiSpec::Nineteen, PacketType::Lap).unwrap() {
+            writer.write_datagram(datagram.unwrap().payload()).unwrap();
+        }
+
+        let lines = writer.into_inner();
+        let first: serde_json::Value =
+            serde_json::from_slice(lines.split(|byte| *byte == b'\n').next().unwrap()).unwrap();
+
+        assert_eq!("lap", first["type"]);
+        assert_eq!(20, first["data"].as_array().unwrap().len());
+        assert!(first["data"][0].get("session_uid").is_none());
+
+        let source = JsonLinesSource::new(Cursor::new(lines));
+        let packets: Vec<Packet> = source.into_packets().map(Result::unwrap).collect();
+
+        assert_eq!(
+            fixtures::packets(ApiSpec::Nineteen, PacketType::Lap).unwrap(),
+            packets
+        );
+    }
+
+    #[test]
+    fn read_line_without_datagram_fails() {
+        let mut writer = JsonLinesWriter::new(Vec::new());
+
+        for packet in fixtures::packets(ApiSpec::Nineteen, PacketType::Session).unwrap() {
+            writer.write(&packet).unwrap();
+        }
+
+        let mut source = JsonLinesSource::new(Cursor::new(writer.into_inner()));
+
+        assert!(source.next_packet().is_err());
+    }
+}
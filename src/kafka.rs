@@ -0,0 +1,265 @@
+//! Publishing of packets and changes to Kafka
+//!
+//! Organisations that run esports events at scale feed telemetry into their existing streaming
+//! pipelines. The `KafkaSink` publishes every packet as a JSON object in the format of the `jsonl`
+//! module to a topic per packet type, e.g. `f1-telemetry` or `f1-lap`. The changes that are derived
+//! from the packets by the `ChangeDetector` are published to a separate topic, e.g. `f1-changes`.
+//!
+//! Every record is keyed with the unique identifier of its session. Kafka assigns records with the
+//! same key to the same partition, so that consumers receive the records of a session in order.
+
+use std::io::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rdkafka::config::ClientConfig;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{BaseRecord, DefaultProducerContext, Producer, ThreadedProducer};
+use serde_json::json;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::export::table;
+use crate::jsonl::to_object;
+use crate::packet::header::Header;
+use crate::packet::Packet;
+use crate::sink::{ExportFuture, Exporter, Item};
+use crate::stream::changes::{Change, ChangeDetector};
+
+/// Time to wait for queued records to be delivered when the sink is flushed
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time to wait before a record is queued again when the queue of the producer is full
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Record that is published to a topic
+#[derive(Debug, PartialEq, Clone)]
+struct Record {
+    topic: String,
+    key: String,
+    payload: String,
+}
+
+/// Sink that publishes packets and changes to Kafka topics
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use f1_api::kafka::KafkaSink;
+/// use f1_api::F1;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let game = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+///
+/// KafkaSink::new("localhost:9092")
+///     .unwrap()
+///     .prefix("league")
+///     .serve(F1::stream(game).unwrap())
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+pub struct KafkaSink {
+    producer: Arc<ThreadedProducer<DefaultProducerContext>>,
+    prefix: String,
+    detector: ChangeDetector,
+}
+
+impl KafkaSink {
+    /// Create a sink that publishes to the given comma-separated list of brokers.
+    pub fn new(brokers: &str) -> Result<Self, Error> {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", brokers);
+
+        KafkaSink::with_config(&config)
+    }
+
+    /// Create a sink with a custom configuration of the producer, e.g. for authentication.
+    pub fn with_config(config: &ClientConfig) -> Result<Self, Error> {
+        Ok(KafkaSink {
+            producer: Arc::new(config.create().map_err(Error::other)?),
+            prefix: "f1".into(),
+            detector: ChangeDetector::default(),
+        })
+    }
+
+    /// Prefix the names of the topics with the given prefix instead of `f1`.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Queue the packet and its changes for publishing.
+    ///
+    /// The records are delivered in the background. Queuing fails when the queue of the producer
+    /// is full, for example because the brokers cannot be reached.
+    pub fn send(&mut self, packet: &Packet) -> Result<(), Error> {
+        let changes = self.detector.update(packet);
+
+        for record in records(&self.prefix, packet, &changes) {
+            self.produce(&record)?;
+        }
+
+        Ok(())
+    }
+
+    /// Wait until the queued records have been delivered.
+    ///
+    /// The producer blocks while it waits, so it is flushed on a thread that may block instead of
+    /// the thread of the runtime.
+    pub async fn flush(&self) -> Result<(), Error> {
+        let producer = Arc::clone(&self.producer);
+
+        tokio::task::spawn_blocking(move || producer.flush(FLUSH_TIMEOUT))
+            .await
+            .map_err(Error::other)?
+            .map_err(Error::other)
+    }
+
+    /// Publish the packets of the stream until it ends, and flush the queued records.
+    ///
+    /// Bursts of packets can fill the queue of the producer faster than the records are delivered.
+    /// The sink then waits for the queue to drain instead of failing, and only returns early when
+    /// a record cannot be queued for another reason.
+    pub async fn serve<S>(mut self, packets: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Packet> + Unpin,
+    {
+        let mut packets = packets;
+
+        while let Some(packet) = packets.next().await {
+            let changes = self.detector.update(&packet);
+
+            for record in records(&self.prefix, &packet, &changes) {
+                self.publish(&record).await?;
+            }
+        }
+
+        self.flush().await
+    }
+
+    /// Queue the record, and wait while the queue of the producer is full.
+    async fn publish(&self, record: &Record) -> Result<(), Error> {
+        loop {
+            match self.try_produce(record) {
+                Err(KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull)) => {
+                    tokio::time::sleep(QUEUE_FULL_BACKOFF).await
+                }
+                result => return result.map_err(Error::other),
+            }
+        }
+    }
+
+    fn produce(&self, record: &Record) -> Result<(), Error> {
+        self.try_produce(record).map_err(Error::other)
+    }
+
+    fn try_produce(&self, record: &Record) -> Result<(), KafkaError> {
+        self.producer
+            .send(
+                BaseRecord::to(&record.topic)
+                    .key(&record.key)
+                    .payload(&record.payload),
+            )
+            .map_err(|(error, _)| error)
+    }
+}
+
+/// Packets are published to the topic of their type, and changes to the topic for changes. Unlike
+/// `KafkaSink::send`, the sink does not derive the changes itself, since they are passed to it. Like
+/// `KafkaSink::serve`, it waits while the queue of the producer is full.
+impl Exporter for KafkaSink {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        let record = match item {
+            Item::Packet(packet) => packet_record(&self.prefix, packet),
+            Item::Change(header, change) => change_record(&self.prefix, header, change),
+        };
+
+        Box::pin(async move { self.publish(&record).await })
+    }
+
+    fn flush(&mut self) -> ExportFuture<'_> {
+        Box::pin(KafkaSink::flush(self))
+    }
+}
+
+impl std::fmt::Debug for KafkaSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaSink")
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+/// Returns the records for a packet and its changes.
+fn records(prefix: &str, packet: &Packet, changes: &[Change]) -> Vec<Record> {
+    std::iter::once(packet_record(prefix, packet))
+        .chain(
+            changes
+                .iter()
+                .map(|change| change_record(prefix, packet.header(), change)),
+        )
+        .collect()
+}
+
+/// Returns the record for a packet in the topic of its type.
+fn packet_record(prefix: &str, packet: &Packet) -> Record {
+    Record {
+        topic: format!("{}-{}", prefix, table::name(table::packet_type(packet))),
+        key: packet.header().session_uid().to_string(),
+        payload: serde_json::Value::Object(to_object(packet)).to_string(),
+    }
+}
+
+/// Returns the record for a change that has been derived from the packet with the given header.
+fn change_record(prefix: &str, header: &Header, change: &Change) -> Record {
+    let key = header.session_uid().to_string();
+    let vehicle_index = match change {
+        Change::Session(_) => None,
+        Change::Status(index, _) | Change::Participant(index, _) => Some(*index),
+    };
+
+    Record {
+        topic: format!("{}-changes", prefix),
+        key: key.clone(),
+        payload: json!({
+            "session_uid": key,
+            "session_time": header.session_time().as_secs_f64(),
+            "vehicle_index": vehicle_index,
+            "change": change.to_json(),
+        })
+        .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use crate::kafka::records;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::stream::changes::ChangeDetector;
+
+    #[test]
+    fn records_are_keyed_by_session() {
+        let packet = &fixtures::packets(ApiSpec::Nineteen, PacketType::Session).unwrap()[0];
+        let changes = ChangeDetector::default().update(packet);
+
+        let records = records("f1", packet, &changes);
+        let session_uid = packet.header().session_uid().to_string();
+
+        assert_eq!(1 + changes.len(), records.len());
+        assert_eq!("f1-session", records[0].topic);
+        assert_eq!("f1-changes", records[1].topic);
+        assert!(records.iter().all(|record| record.key == session_uid));
+
+        let json: serde_json::Value = serde_json::from_str(&records[0].payload).unwrap();
+        assert_eq!("session", json["type"]);
+
+        let json: serde_json::Value = serde_json::from_str(&records[1].payload).unwrap();
+        assert_eq!("session", json["change"]["kind"]);
+        assert_eq!("Weather", json["change"]["field"]);
+        assert!(json["change"]["value"].is_string());
+    }
+}
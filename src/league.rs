@@ -0,0 +1,544 @@
+//! Reports for leagues that race over several rounds
+//!
+//! Leagues publish their results on websites: the standings of the championship, the results of
+//! each round, the penalties that were handed out, the fastest laps, and which drivers attended
+//! which round. The `League` collects the final state of the sessions of a season, for example from
+//! a `SessionTracker` that consumed the capture of each round, and aggregates them into a
+//! `LeagueReport`. With the `json` feature, the report can be serialized to JSON.
+//!
+//! Drivers are identified by their name across sessions, since the index of a car changes from one
+//! session to the next. Cars without a known name are reported as `Car <index>`.
+
+use std::collections::BTreeMap;
+#[cfg(feature = "json")]
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::lap::ResultStatus;
+use crate::packet::session::{Session, Track};
+use crate::tracker::SessionTracker;
+
+/// Points for the first ten positions of a race, as awarded in Formula 1
+const DEFAULT_POINTS: [u32; 10] = [25, 18, 15, 12, 10, 8, 6, 4, 2, 1];
+
+/// Result of a driver in a round
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Clone, PartialOrd)]
+pub struct RoundResult {
+    /// Returns the position of the driver.
+    #[getset(get_copy = "pub")]
+    position: u8,
+
+    /// Returns the name of the driver.
+    #[getset(get = "pub")]
+    name: String,
+
+    /// Returns the best lap time of the driver.
+    #[getset(get = "pub")]
+    best_lap_time: Duration,
+
+    /// Returns the penalties of the driver in seconds.
+    #[getset(get_copy = "pub")]
+    penalties: u8,
+
+    /// Returns the points that the driver scored.
+    #[getset(get_copy = "pub")]
+    points: u32,
+
+    /// Returns the status of the result.
+    #[getset(get_copy = "pub")]
+    result_status: ResultStatus,
+}
+
+/// Results of a single round
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Clone, PartialOrd)]
+pub struct Round {
+    /// Returns the number of the round, starting at 1.
+    #[getset(get_copy = "pub")]
+    number: usize,
+
+    /// Returns the unique id of the session.
+    #[getset(get_copy = "pub")]
+    session_uid: u64,
+
+    /// Returns the track of the round, if known.
+    #[getset(get_copy = "pub")]
+    track: Option<Track>,
+
+    /// Returns the type of the session, if known.
+    #[getset(get_copy = "pub")]
+    session_type: Option<Session>,
+
+    /// Returns the results of the round, ordered by position.
+    #[getset(get = "pub")]
+    results: Vec<RoundResult>,
+}
+
+impl Round {
+    /// Returns the result with the fastest lap of the round.
+    pub fn fastest_lap(&self) -> Option<&RoundResult> {
+        self.results
+            .iter()
+            .filter(|result| !result.best_lap_time().is_zero())
+            .min_by_key(|result| *result.best_lap_time())
+    }
+}
+
+/// Standing of a driver in the championship
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct Standing {
+    /// Returns the position of the driver in the championship.
+    #[getset(get_copy = "pub")]
+    position: usize,
+
+    /// Returns the name of the driver.
+    #[getset(get = "pub")]
+    name: String,
+
+    /// Returns the points of the driver.
+    #[getset(get_copy = "pub")]
+    points: u32,
+
+    /// Returns the number of rounds the driver has won.
+    #[getset(get_copy = "pub")]
+    wins: usize,
+}
+
+/// Summary of the penalties of a driver
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct PenaltySummary {
+    /// Returns the name of the driver.
+    #[getset(get = "pub")]
+    name: String,
+
+    /// Returns the total penalties of the driver in seconds.
+    #[getset(get_copy = "pub")]
+    seconds: u32,
+
+    /// Returns the numbers of the rounds in which the driver received penalties.
+    #[getset(get = "pub")]
+    rounds: Vec<usize>,
+}
+
+/// Fastest lap of a round
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct FastestLap {
+    /// Returns the number of the round.
+    #[getset(get_copy = "pub")]
+    round: usize,
+
+    /// Returns the name of the driver.
+    #[getset(get = "pub")]
+    name: String,
+
+    /// Returns the time of the lap.
+    #[getset(get = "pub")]
+    lap_time: Duration,
+}
+
+/// Attendance of a driver
+#[derive(new, Debug, Getters, PartialEq, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct Attendance {
+    /// Returns the name of the driver.
+    #[getset(get = "pub")]
+    name: String,
+
+    /// Returns the numbers of the rounds the driver took part in.
+    #[getset(get = "pub")]
+    rounds: Vec<usize>,
+}
+
+/// Aggregated report of a league
+#[derive(Debug, Getters, PartialEq, Clone, PartialOrd, Default)]
+pub struct LeagueReport {
+    /// Returns the standings of the championship, ordered by position.
+    #[getset(get = "pub")]
+    standings: Vec<Standing>,
+
+    /// Returns the results of each round.
+    #[getset(get = "pub")]
+    rounds: Vec<Round>,
+
+    /// Returns the penalties of each driver that received any, ordered by name.
+    #[getset(get = "pub")]
+    penalties: Vec<PenaltySummary>,
+
+    /// Returns the fastest lap of each round.
+    #[getset(get = "pub")]
+    fastest_laps: Vec<FastestLap>,
+
+    /// Returns the attendance of each driver, ordered by name.
+    #[getset(get = "pub")]
+    attendance: Vec<Attendance>,
+}
+
+impl LeagueReport {
+    /// Serialize the report as pretty-printed JSON.
+    ///
+    /// Lap times are written as seconds, and tracks, sessions, and result statuses by their name.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        use serde_json::json;
+
+        let results = |round: &Round| -> Vec<serde_json::Value> {
+            round
+                .results
+                .iter()
+                .map(|result| {
+                    json!({
+                        "position": result.position,
+                        "name": result.name,
+                        "best_lap_time": result.best_lap_time.as_secs_f64(),
+                        "penalties": result.penalties,
+                        "points": result.points,
+                        "result_status": format!("{:?}", result.result_status),
+                    })
+                })
+                .collect()
+        };
+
+        let report = json!({
+            "standings": self.standings.iter().map(|standing| json!({
+                "position": standing.position,
+                "name": standing.name,
+                "points": standing.points,
+                "wins": standing.wins,
+            })).collect::<Vec<_>>(),
+            "rounds": self.rounds.iter().map(|round| json!({
+                "round": round.number,
+                "session_uid": round.session_uid,
+                "track": round.track.map(|track| format!("{:?}", track)),
+                "session": round.session_type.map(|session| format!("{:?}", session)),
+                "results": results(round),
+            })).collect::<Vec<_>>(),
+            "penalties": self.penalties.iter().map(|penalty| json!({
+                "name": penalty.name,
+                "seconds": penalty.seconds,
+                "rounds": penalty.rounds,
+            })).collect::<Vec<_>>(),
+            "fastest_laps": self.fastest_laps.iter().map(|lap| json!({
+                "round": lap.round,
+                "name": lap.name,
+                "lap_time": lap.lap_time.as_secs_f64(),
+            })).collect::<Vec<_>>(),
+            "attendance": self.attendance.iter().map(|attendance| json!({
+                "name": attendance.name,
+                "rounds": attendance.rounds,
+            })).collect::<Vec<_>>(),
+        });
+
+        serde_json::to_string_pretty(&report)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+    }
+}
+
+/// Collection of the rounds of a league
+///
+/// Each session that is added to the league becomes a round. Points are awarded for races only,
+/// based on the points table of the league. Qualifying and practice sessions appear in the results
+/// and the attendance, but do not count towards the standings.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::league::League;
+/// use f1_api::tracker::SessionTracker;
+/// # use f1_api::packet::Packet;
+/// # fn rounds() -> Vec<Vec<Packet>> { Vec::new() }
+///
+/// let mut league = League::default();
+///
+/// for packets in rounds() {
+///     let mut tracker = SessionTracker::default();
+///
+///     for packet in packets {
+///         tracker.update(&packet);
+///     }
+///
+///     league.add_session(&tracker);
+/// }
+///
+/// for standing in league.report().standings() {
+///     println!("{}. {} {}", standing.position(), standing.name(), standing.points());
+/// }
+/// ```
+#[derive(Debug, Getters, PartialEq, Clone, PartialOrd)]
+pub struct League {
+    /// Returns the points that are awarded for each position in a race.
+    #[getset(get = "pub")]
+    points: Vec<u32>,
+
+    rounds: Vec<Round>,
+}
+
+impl League {
+    /// Create a league that awards the given points for the positions in a race.
+    pub fn new(points: Vec<u32>) -> Self {
+        League {
+            points,
+            rounds: Vec::new(),
+        }
+    }
+
+    /// Add the final state of a session as the next round.
+    ///
+    /// Sessions without a classification, e.g. because no lap packets have been received, are
+    /// ignored.
+    pub fn add_session(&mut self, tracker: &SessionTracker) {
+        let classification = tracker.classification();
+
+        if classification.is_empty() {
+            return;
+        }
+
+        let session_type = tracker
+            .session()
+            .as_ref()
+            .map(|session| session.session_type());
+        let scores = matches!(session_type, Some(Session::Race) | Some(Session::Race2));
+
+        let results = classification
+            .iter()
+            .map(|entry| {
+                let penalties = tracker
+                    .laps()
+                    .as_ref()
+                    .and_then(|laps| laps.car(entry.vehicle_index()))
+                    .map(|lap| lap.penalties())
+                    .unwrap_or(0);
+
+                let classified = !matches!(
+                    entry.result_status(),
+                    ResultStatus::Disqualified | ResultStatus::NotClassified
+                );
+
+                let points = if scores && classified {
+                    self.points
+                        .get(entry.position() as usize - 1)
+                        .copied()
+                        .unwrap_or(0)
+                } else {
+                    0
+                };
+
+                RoundResult::new(
+                    entry.position(),
+                    entry
+                        .name()
+                        .clone()
+                        .unwrap_or_else(|| format!("Car {}", entry.vehicle_index())),
+                    *entry.best_lap_time(),
+                    penalties,
+                    points,
+                    entry.result_status(),
+                )
+            })
+            .collect();
+
+        self.rounds.push(Round::new(
+            self.rounds.len() + 1,
+            tracker.session_uid().unwrap_or_default(),
+            tracker.session().as_ref().map(|session| session.track()),
+            session_type,
+            results,
+        ));
+    }
+
+    /// Returns the rounds of the league.
+    pub fn rounds(&self) -> &[Round] {
+        &self.rounds
+    }
+
+    /// Aggregate the rounds into a report.
+    pub fn report(&self) -> LeagueReport {
+        let mut points: BTreeMap<&str, (u32, usize)> = BTreeMap::new();
+        let mut penalties: BTreeMap<&str, (u32, Vec<usize>)> = BTreeMap::new();
+        let mut attendance: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        let mut fastest_laps = Vec::new();
+
+        for round in &self.rounds {
+            for result in &round.results {
+                let standing = points.entry(result.name()).or_default();
+                standing.0 += result.points();
+
+                if result.position() == 1 && result.points() > 0 {
+                    standing.1 += 1;
+                }
+
+                if result.penalties() > 0 {
+                    let penalty = penalties.entry(result.name()).or_default();
+                    penalty.0 += result.penalties() as u32;
+                    penalty.1.push(round.number());
+                }
+
+                attendance
+                    .entry(result.name())
+                    .or_default()
+                    .push(round.number());
+            }
+
+            if let Some(result) = round.fastest_lap() {
+                fastest_laps.push(FastestLap::new(
+                    round.number(),
+                    result.name().clone(),
+                    *result.best_lap_time(),
+                ));
+            }
+        }
+
+        let mut standings: Vec<(&str, (u32, usize))> = points.into_iter().collect();
+        standings.sort_by(|a, b| (b.1).0.cmp(&(a.1).0).then((b.1).1.cmp(&(a.1).1)));
+
+        LeagueReport {
+            standings: standings
+                .into_iter()
+                .enumerate()
+                .map(|(index, (name, (points, wins)))| {
+                    Standing::new(index + 1, name.to_string(), points, wins)
+                })
+                .collect(),
+            rounds: self.rounds.clone(),
+            penalties: penalties
+                .into_iter()
+                .map(|(name, (seconds, rounds))| {
+                    PenaltySummary::new(name.to_string(), seconds, rounds)
+                })
+                .collect(),
+            fastest_laps,
+            attendance: attendance
+                .into_iter()
+                .map(|(name, rounds)| Attendance::new(name.to_string(), rounds))
+                .collect(),
+        }
+    }
+}
+
+impl Default for League {
+    fn default() -> Self {
+        League::new(DEFAULT_POINTS.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::league::League;
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+    use crate::packet::session::{Session, SessionPacket, Track};
+    use crate::packet::Packet;
+    use crate::tracker::SessionTracker;
+
+    fn lap(position: u8, best_lap_time: f32, penalties: u8) -> Lap {
+        Lap::new(
+            Duration::from_secs_f32(best_lap_time),
+            Duration::default(),
+            Duration::from_secs_f32(best_lap_time),
+            Duration::default(),
+            Duration::default(),
+            0.0,
+            0.0,
+            0.0,
+            position,
+            5,
+            PitStatus::None,
+            Sector::First,
+            true,
+            penalties,
+            position,
+            DriverStatus::OnTrack,
+            ResultStatus::Finished,
+        )
+    }
+
+    fn round(session_uid: u64, session_type: Session, laps: Vec<Lap>) -> SessionTracker {
+        let mut tracker = SessionTracker::default();
+
+        tracker.update(&Packet::Session(SessionPacket::new(
+            header(PacketType::Session, session_uid, Duration::default(), 0),
+            Default::default(),
+            30,
+            20,
+            5,
+            5000,
+            session_type,
+            Track::Monza,
+            Default::default(),
+            Duration::default(),
+            Duration::from_secs(3600),
+            80,
+            false,
+            false,
+            0,
+            false,
+            Vec::new(),
+            Default::default(),
+            false,
+            None,
+        )));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, session_uid, Duration::default(), 0),
+            laps,
+        ));
+
+        tracker
+    }
+
+    #[test]
+    fn report_aggregates_rounds() {
+        let mut league = League::new(vec![10, 5]);
+
+        league.add_session(&round(
+            1,
+            Session::Q3,
+            vec![lap(2, 88.0, 0), lap(1, 87.0, 0)],
+        ));
+        league.add_session(&round(
+            2,
+            Session::Race,
+            vec![lap(1, 90.0, 0), lap(2, 89.0, 5)],
+        ));
+        league.add_session(&round(
+            3,
+            Session::Race,
+            vec![lap(1, 91.0, 0), lap(2, 92.0, 3)],
+        ));
+        league.add_session(&round(4, Session::Race, Vec::new()));
+
+        let report = league.report();
+
+        assert_eq!(3, report.rounds().len());
+        assert_eq!(Some(Track::Monza), report.rounds()[0].track());
+
+        assert_eq!("Car 0", report.standings()[0].name());
+        assert_eq!(20, report.standings()[0].points());
+        assert_eq!(2, report.standings()[0].wins());
+        assert_eq!(10, report.standings()[1].points());
+
+        assert_eq!("Car 1", report.fastest_laps()[0].name());
+        assert_eq!("Car 1", report.fastest_laps()[1].name());
+        assert_eq!("Car 0", report.fastest_laps()[2].name());
+        assert_eq!(1, report.penalties().len());
+        assert_eq!(8, report.penalties()[0].seconds());
+        assert_eq!(&vec![2, 3], report.penalties()[0].rounds());
+        assert_eq!(&vec![1, 2, 3], report.attendance()[0].rounds());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn report_to_json() {
+        let mut league = League::default();
+        league.add_session(&round(1, Session::Race, vec![lap(1, 90.0, 0)]));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&league.report().to_json().unwrap()).unwrap();
+
+        assert_eq!(25, json["standings"][0]["points"]);
+        assert_eq!("Monza", json["rounds"][0]["track"]);
+        assert_eq!(90.0, json["fastest_laps"][0]["lap_time"]);
+    }
+}
@@ -1,76 +1,105 @@
 //! A Rust implementation of the telemetry API provided by modern F1 video games
+//!
+//! All games are decoded into the same `Packet` format. Each supported game has its own module,
+//! e.g. `nineteen` for F1 2019, with a type that implements `GameSpec` and decodes the packets of
+//! the game.
+//!
+//! The packets and their decoders only require an allocator, and can be used without the standard
+//! library by disabling the default `std` feature. This allows embedded devices, for example a dash
+//! display, to decode the packets that they receive from the game. Everything else, from the
+//! networking to the analysis of the packets, requires the standard library.
+//!
+//! The UDP socket and the stream adapters are enabled by the `net` feature, and the decoders by
+//! the `decode` feature. Applications that only analyze packets from another source, e.g. a
+//! capture, can disable `net` to drop the dependencies on `tokio` and `socket2`.
 
-use std::io::Error;
-use std::net::SocketAddr;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use socket2::{Domain, Protocol, Socket, Type};
-use tokio::net::UdpSocket;
-use tokio_stream::{Stream, StreamExt};
-use tokio_util::udp::UdpFramed;
+extern crate alloc;
 
-use crate::codec::F1Codec;
-use crate::packet::Packet;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+pub use crate::client::F1Client;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+pub use crate::net::{SocketOptions, F1};
 
+#[cfg(feature = "std")]
+pub mod alerts;
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(all(feature = "std", feature = "decode"))]
+pub mod anonymize;
+#[cfg(all(feature = "std", feature = "decode"))]
+pub mod capture;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+pub mod client;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(all(feature = "std", feature = "decode"))]
 pub mod codec;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(any(feature = "test-support", test))]
+pub mod fixtures;
+#[cfg(all(feature = "graphql", not(target_arch = "wasm32")))]
+pub mod graphql;
+pub mod handler;
+#[cfg(all(any(feature = "rest", feature = "sse"), not(target_arch = "wasm32")))]
+mod http;
+#[cfg(all(feature = "ipc", any(unix, windows)))]
+pub mod ipc;
+#[cfg(all(feature = "json", feature = "decode"))]
+pub mod jsonl;
+#[cfg(all(feature = "kafka", not(target_arch = "wasm32")))]
+pub mod kafka;
+#[cfg(feature = "std")]
+pub mod league;
+#[cfg(all(feature = "nats", not(target_arch = "wasm32")))]
+pub mod nats;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+mod net;
+#[cfg(feature = "decode")]
 pub mod nineteen;
+#[cfg(feature = "std")]
+pub mod output;
 pub mod packet;
+#[cfg(feature = "plot")]
+pub mod plot;
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+pub mod postgres;
+#[cfg(feature = "std")]
+pub mod privacy;
+#[cfg(feature = "std")]
+pub mod query;
+#[cfg(feature = "std")]
+pub mod recent;
+#[cfg(all(feature = "std", feature = "decode"))]
+pub mod relay;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod resample;
+#[cfg(all(feature = "rest", not(target_arch = "wasm32")))]
+pub mod rest;
+#[cfg(feature = "json")]
+pub mod schema;
+#[cfg(any(feature = "json", feature = "toml"))]
+pub mod setup;
+#[cfg(feature = "shm")]
+pub mod shm;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+pub mod sink;
+#[cfg(all(feature = "std", feature = "decode"))]
+pub mod source;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+pub mod split;
+#[cfg(all(feature = "sse", not(target_arch = "wasm32")))]
+pub mod sse;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "json")]
+pub mod topic;
+#[cfg(feature = "std")]
+pub mod tracker;
 pub mod types;
-
-/// A high-level interface to the telemetry data of modern F1 video games.
-///
-/// The F1 struct implements a high-level interface to the telemetry data of the
-/// modern F1 video games. It is the recommended way to use the library, as it
-/// provides a simple interface to consumers that hides the low-level internals
-/// of the library.
-pub struct F1 {}
-
-impl F1 {
-    /// Create a stream that yields decoded UDP packets.
-    ///
-    /// Modern F1 games publish their telemetry and session data through a UDP-based protocol. With
-    /// this function, a stream can be created that listens at the given socket for incoming
-    /// packets, decodes them using the `F1Codec`, and returns their Rust representations.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use std::net::{IpAddr, SocketAddr};
-    ///
-    /// use f1_api::F1;
-    /// use f1_api::packet::Packet::{Event, Lap, Motion, Participants, Session, Setup, Status, Telemetry};
-    /// use tokio_stream::StreamExt;
-    ///
-    /// async fn example() {
-    ///     let ip_address = IpAddr::from([0, 0, 0, 0]);
-    ///     let port = 20777;
-    ///     let socket = SocketAddr::new(ip_address, port);
-    ///
-    ///     let mut stream = F1::stream(socket).unwrap();
-    ///
-    ///     while let Some(packet) = stream.next().await {
-    ///         match packet {
-    ///             Event(_) => println!("Received Event packet"),
-    ///             Lap(_) => println!("Received Lap packet"),
-    ///             Motion(_) => println!("Received Motion packet"),
-    ///             Participants(_) => println!("Received Participants packet"),
-    ///             Session(_) => println!("Received Session packet"),
-    ///             Setup(_) => println!("Received Setup packet"),
-    ///             Status(_) => println!("Received Status packet"),
-    ///             Telemetry(_) => println!("Received Telemetry packet"),
-    ///         }
-    ///     }
-    /// }
-    /// ```
-    pub fn stream(socket_address: SocketAddr) -> Result<impl Stream<Item = Packet>, Error> {
-        let socket = match socket_address {
-            SocketAddr::V4(_) => Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP)),
-            SocketAddr::V6(_) => Socket::new(Domain::IPV6, Type::DGRAM, Some(Protocol::UDP)),
-        }?;
-
-        socket.bind(&socket_address.into())?;
-
-        Ok(UdpFramed::new(UdpSocket::from_std(socket.into())?, F1Codec)
-            .map(|result| result.unwrap())
-            .map(|(packet, _address)| packet))
-    }
-}
+#[cfg(all(feature = "zenoh", not(target_arch = "wasm32")))]
+pub mod zenoh;
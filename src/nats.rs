@@ -0,0 +1,160 @@
+//! Publishing of packets to NATS
+//!
+//! NATS is a lightweight alternative to Kafka for setups with a few services that consume the
+//! telemetry, for example a bot, a dashboard, and a database writer on a home server. The
+//! `NatsSink` publishes every packet as a JSON object in the format of the `jsonl` module to a
+//! subject per packet type, e.g. `f1.telemetry` or `f1.lap`. Services subscribe to the subjects
+//! they need, or to `f1.>` for every packet.
+//!
+//! Messages that are published to core NATS are lost when no service is subscribed. With JetStream,
+//! the server persists the messages in a stream, and the sink waits for the server to acknowledge
+//! each message. The stream must be created on the server before the sink is used, e.g. with
+//! `nats stream add f1 --subjects "f1.>"`.
+
+use std::io::Error;
+
+use async_nats::jetstream::{self, Context};
+use async_nats::Client;
+use bytes::Bytes;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::export::table;
+use crate::jsonl::to_object;
+use crate::packet::Packet;
+use crate::sink::{ExportFuture, Exporter, Item};
+
+/// Sink that publishes packets to NATS subjects
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use f1_api::nats::NatsSink;
+/// use f1_api::F1;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let game = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+///
+/// NatsSink::connect("localhost:4222")
+///     .await
+///     .unwrap()
+///     .jetstream()
+///     .serve(F1::stream(game).unwrap())
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct NatsSink {
+    client: Client,
+    jetstream: Option<Context>,
+    prefix: String,
+}
+
+impl NatsSink {
+    /// Connect to the NATS server at the given address.
+    pub async fn connect(address: &str) -> Result<Self, Error> {
+        let client = async_nats::connect(address).await.map_err(Error::other)?;
+
+        Ok(NatsSink::new(client))
+    }
+
+    /// Create a sink that publishes with an existing client, e.g. with custom credentials.
+    pub fn new(client: Client) -> Self {
+        NatsSink {
+            client,
+            jetstream: None,
+            prefix: "f1".into(),
+        }
+    }
+
+    /// Prefix the subjects with the given prefix instead of `f1`.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Publish the packets to JetStream, and wait for the server to acknowledge them.
+    pub fn jetstream(mut self) -> Self {
+        self.jetstream = Some(jetstream::new(self.client.clone()));
+        self
+    }
+
+    /// Publish the packet to the subject of its type.
+    pub async fn send(&self, packet: &Packet) -> Result<(), Error> {
+        let subject = subject(&self.prefix, packet);
+        let payload = Bytes::from(serde_json::Value::Object(to_object(packet)).to_string());
+
+        match &self.jetstream {
+            Some(jetstream) => {
+                jetstream
+                    .publish(subject, payload)
+                    .await
+                    .map_err(Error::other)?
+                    .await
+                    .map_err(Error::other)?;
+            }
+            None => {
+                self.client
+                    .publish(subject, payload)
+                    .await
+                    .map_err(Error::other)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Publish the packets of the stream until it ends, and flush the published messages.
+    pub async fn serve<S>(self, packets: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Packet> + Unpin,
+    {
+        let mut packets = packets;
+
+        while let Some(packet) = packets.next().await {
+            self.send(&packet).await?;
+        }
+
+        self.client.flush().await.map_err(Error::other)
+    }
+}
+
+/// Packets are published to the subject of their type, while changes are ignored.
+impl Exporter for NatsSink {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        Box::pin(async move {
+            match item {
+                Item::Packet(packet) => self.send(packet).await,
+                Item::Change(..) => Ok(()),
+            }
+        })
+    }
+
+    fn flush(&mut self) -> ExportFuture<'_> {
+        Box::pin(async move { self.client.flush().await.map_err(Error::other) })
+    }
+}
+
+/// Returns the subject for a packet.
+fn subject(prefix: &str, packet: &Packet) -> String {
+    format!("{}.{}", prefix, table::name(table::packet_type(packet)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use crate::nats::subject;
+    use crate::packet::header::{ApiSpec, PacketType};
+
+    #[test]
+    fn subject_per_packet_type() {
+        let lap = &fixtures::packets(ApiSpec::Nineteen, PacketType::Lap).unwrap()[0];
+        let telemetry = &fixtures::packets(ApiSpec::Nineteen, PacketType::Telemetry).unwrap()[0];
+
+        assert_eq!("f1.lap", subject("f1", lap));
+        assert_eq!("league.telemetry", subject("league", telemetry));
+    }
+}
@@ -0,0 +1,449 @@
+//! Networking with the F1 games
+//!
+//! The games publish their packets over UDP. The `F1` struct binds a UDP socket, and decodes the
+//! packets that arrive at it with the `F1Codec`. Networking requires the standard library and is not
+//! available in WebAssembly.
+
+use std::io::{Error, ErrorKind};
+use std::net::SocketAddr;
+
+use derive_new::new;
+use getset::CopyGetters;
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+use tokio_stream::{Stream, StreamExt};
+use tokio_util::udp::UdpFramed;
+
+use crate::codec::{F1Codec, RawCodec, RawPacket};
+use crate::packet::Packet;
+use crate::split::{Capacities, PacketReceivers};
+
+/// Options for the socket that receives the packets
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::SocketOptions;
+///
+/// let options = SocketOptions::default().reuse_port(true).dual_stack(true);
+///
+/// assert!(options.reuse_port_enabled());
+/// assert!(options.dual_stack_enabled());
+/// ```
+#[derive(new, Debug, CopyGetters, Default, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct SocketOptions {
+    /// Returns whether the socket is bound with `SO_REUSEPORT`.
+    #[getset(get_copy = "pub")]
+    reuse_port_enabled: bool,
+
+    /// Returns whether an IPv6 socket also receives packets from IPv4 senders.
+    #[getset(get_copy = "pub")]
+    dual_stack_enabled: bool,
+
+    /// Returns the scope of IPv6 addresses that do not specify one.
+    #[getset(get_copy = "pub")]
+    scope_id: Option<u32>,
+}
+
+impl SocketOptions {
+    /// Bind the socket with `SO_REUSEPORT`.
+    ///
+    /// With this option, several sockets can be bound to the same port, as long as all of them set
+    /// the option. The kernel distributes the incoming datagrams between the sockets. This allows
+    /// multiple collectors to share the port, and a new collector to take over the port before the
+    /// old one shuts down. The option is only supported on Unix.
+    pub fn reuse_port(mut self, enabled: bool) -> Self {
+        self.reuse_port_enabled = enabled;
+        self
+    }
+
+    /// Receive packets from both IPv4 and IPv6 senders on an IPv6 socket.
+    ///
+    /// The option disables `IPV6_V6ONLY`, so that the socket also accepts IPv4 packets, which
+    /// appear as IPv4-mapped IPv6 addresses. A socket bound to `[::]` with this option replaces
+    /// two separate streams for IPv4 and IPv6. The option requires an IPv6 address.
+    pub fn dual_stack(mut self, enabled: bool) -> Self {
+        self.dual_stack_enabled = enabled;
+        self
+    }
+
+    /// Set the scope of IPv6 addresses that do not specify one.
+    ///
+    /// Link-local addresses like `fe80::1` are only unique together with the network interface
+    /// that they belong to. The scope identifies the interface, and is used when the address of the
+    /// stream has a scope of `0`.
+    pub fn scope(mut self, scope_id: u32) -> Self {
+        self.scope_id = Some(scope_id);
+        self
+    }
+
+    /// Create a socket with the options, and bind it to the given address.
+    fn bind(&self, socket_address: SocketAddr) -> Result<Socket, Error> {
+        let socket_address = match (socket_address, self.scope_id) {
+            (SocketAddr::V6(mut address), Some(scope_id)) if address.scope_id() == 0 => {
+                address.set_scope_id(scope_id);
+                SocketAddr::V6(address)
+            }
+            (address, _) => address,
+        };
+
+        let socket = match socket_address {
+            SocketAddr::V4(_) => Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP)),
+            SocketAddr::V6(_) => Socket::new(Domain::IPV6, Type::DGRAM, Some(Protocol::UDP)),
+        }?;
+
+        if self.dual_stack_enabled {
+            if socket_address.is_ipv4() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "dual-stack sockets require an IPv6 address",
+                ));
+            }
+
+            socket.set_only_v6(false)?;
+        }
+
+        if self.reuse_port_enabled {
+            #[cfg(unix)]
+            socket.set_reuse_port(true)?;
+
+            #[cfg(not(unix))]
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "SO_REUSEPORT is only supported on Unix",
+            ));
+        }
+
+        socket.bind(&socket_address.into())?;
+        socket.set_nonblocking(true)?;
+
+        Ok(socket)
+    }
+}
+
+/// A high-level interface to the telemetry data of modern F1 video games.
+///
+/// The F1 struct implements a high-level interface to the telemetry data of the
+/// modern F1 video games. It is the recommended way to use the library, as it
+/// provides a simple interface to consumers that hides the low-level internals
+/// of the library.
+pub struct F1 {}
+
+impl F1 {
+    /// Create a stream that yields decoded UDP packets.
+    ///
+    /// Modern F1 games publish their telemetry and session data through a UDP-based protocol. With
+    /// this function, a stream can be created that listens at the given socket for incoming
+    /// packets, decodes them using the `F1Codec`, and returns their Rust representations.
+    /// Datagrams that cannot be decoded, e.g. packets in an unknown format, are dropped, and logged
+    /// when the `tracing` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net::{IpAddr, SocketAddr};
+    ///
+    /// use f1_api::F1;
+    /// use f1_api::packet::Packet::{Event, Lap, Motion, Participants, Session, Setup, Status, Telemetry};
+    /// use tokio_stream::StreamExt;
+    ///
+    /// async fn example() {
+    ///     let ip_address = IpAddr::from([0, 0, 0, 0]);
+    ///     let port = 20777;
+    ///     let socket = SocketAddr::new(ip_address, port);
+    ///
+    ///     let mut stream = F1::stream(socket).unwrap();
+    ///
+    ///     while let Some(packet) = stream.next().await {
+    ///         match packet {
+    ///             Event(_) => println!("Received Event packet"),
+    ///             Lap(_) => println!("Received Lap packet"),
+    ///             Motion(_) => println!("Received Motion packet"),
+    ///             Participants(_) => println!("Received Participants packet"),
+    ///             Session(_) => println!("Received Session packet"),
+    ///             Setup(_) => println!("Received Setup packet"),
+    ///             Status(_) => println!("Received Status packet"),
+    ///             Telemetry(_) => println!("Received Telemetry packet"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn stream(socket_address: SocketAddr) -> Result<impl Stream<Item = Packet>, Error> {
+        F1::stream_with_options(socket_address, SocketOptions::default())
+    }
+
+    /// Create a stream that yields decoded UDP packets, and configure its socket.
+    ///
+    /// The stream behaves like the one created by `F1::stream`, but the socket is configured with
+    /// the given options before it is bound.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::net::{IpAddr, SocketAddr};
+    ///
+    /// use f1_api::{SocketOptions, F1};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let socket = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+    /// let options = SocketOptions::default().reuse_port(true);
+    ///
+    /// let stream = F1::stream_with_options(socket, options).unwrap();
+    /// # }
+    /// ```
+    pub fn stream_with_options(
+        socket_address: SocketAddr,
+        options: SocketOptions,
+    ) -> Result<impl Stream<Item = Packet>, Error> {
+        let socket = options.bind(socket_address)?;
+
+        let stream =
+            UdpFramed::new(UdpSocket::from_std(socket.into())?, F1Codec).filter_map(drop_errors);
+
+        #[cfg(feature = "tracing")]
+        let stream = stream.map(|(packet, address)| {
+            tracing::trace!(
+                %address,
+                packet_type = ?packet.header().packet_type(),
+                "Received packet"
+            );
+
+            (packet, address)
+        });
+
+        Ok(stream.map(|(packet, _address)| packet))
+    }
+
+    /// Create a stream that yields decoded UDP packets together with their raw bytes.
+    ///
+    /// The stream behaves like the one created by `F1::stream_with_options`, but attaches the raw
+    /// datagram to each packet. This is meant for debugging, e.g. to log the exact bytes of a packet
+    /// that is decoded differently than expected, without recording a separate capture. Like the
+    /// other streams, it drops datagrams that cannot be decoded, so enable the `tracing` feature to
+    /// see the errors of packets that fail to decode.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::net::{IpAddr, SocketAddr};
+    ///
+    /// use f1_api::{SocketOptions, F1};
+    /// use tokio_stream::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let socket = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+    /// let mut stream = F1::stream_with_raw_bytes(socket, SocketOptions::default()).unwrap();
+    ///
+    /// while let Some(raw) = stream.next().await {
+    ///     println!("{:?} decoded from {:02x?}", raw.packet().header(), &raw.bytes()[..]);
+    /// }
+    /// # }
+    /// ```
+    pub fn stream_with_raw_bytes(
+        socket_address: SocketAddr,
+        options: SocketOptions,
+    ) -> Result<impl Stream<Item = RawPacket>, Error> {
+        let socket = options.bind(socket_address)?;
+
+        let stream =
+            UdpFramed::new(UdpSocket::from_std(socket.into())?, RawCodec).filter_map(drop_errors);
+
+        #[cfg(feature = "tracing")]
+        let stream = stream.map(|(raw, address)| {
+            tracing::trace!(
+                %address,
+                packet_type = ?raw.packet().header().packet_type(),
+                size = raw.bytes().len(),
+                "Received packet"
+            );
+
+            (raw, address)
+        });
+
+        Ok(stream.map(|(packet, _address)| packet))
+    }
+
+    /// Split a stream of packets into a channel for each packet type.
+    ///
+    /// The packets of the stream are distributed by a background task to bounded channels, whose
+    /// capacities are given by `capacities`. The returned receivers are independent of each other,
+    /// and can be consumed by different tasks. When a channel is full, packets of its type are
+    /// dropped so that a slow consumer does not hold up the others. The function must be called
+    /// from within a tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::net::{IpAddr, SocketAddr};
+    ///
+    /// use f1_api::split::Capacities;
+    /// use f1_api::F1;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let socket = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+    /// let stream = F1::stream(socket).unwrap();
+    ///
+    /// let receivers = F1::split(Box::pin(stream), Capacities::default().telemetry(256));
+    /// let mut laps = receivers.laps;
+    ///
+    /// tokio::spawn(async move {
+    ///     while let Some(packet) = laps.recv().await {
+    ///         println!("Received lap data of {} cars", packet.laps().active_count());
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn split<S>(stream: S, capacities: Capacities) -> PacketReceivers
+    where
+        S: Stream<Item = Packet> + Send + Unpin + 'static,
+    {
+        crate::split::split(stream, capacities)
+    }
+}
+
+/// Drop a datagram that could not be received or decoded.
+///
+/// A single invalid datagram, e.g. a packet in an unknown format, must not end the stream. The
+/// error is logged when the `tracing` feature is enabled, and the stream moves on to the next
+/// datagram.
+fn drop_errors<T>(result: Result<(T, SocketAddr), Error>) -> Option<(T, SocketAddr)> {
+    #[cfg(feature = "tracing")]
+    if let Err(error) = &result {
+        tracing::warn!(%error, "Dropped datagram that could not be decoded");
+    }
+
+    result.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::net::{SocketAddr, UdpSocket};
+    use std::time::Duration;
+
+    use tokio_stream::StreamExt;
+
+    use crate::net::{SocketOptions, F1};
+
+    fn free_address() -> SocketAddr {
+        UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    fn session_started() -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32);
+        bytes.extend_from_slice(&2019u16.to_le_bytes());
+        bytes.extend_from_slice(&[1, 2, 3, 3]);
+        bytes.extend_from_slice(&7u64.to_le_bytes());
+        bytes.extend_from_slice(&1f32.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(b"SSTA");
+        bytes.extend_from_slice(&[0u8; 5]);
+
+        bytes
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stream_with_reuse_port() {
+        let address = free_address();
+        let options = SocketOptions::default().reuse_port(true);
+
+        let first = F1::stream_with_options(address, options);
+        let second = F1::stream_with_options(address, options);
+
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stream_without_reuse_port() {
+        let address = free_address();
+
+        let first = F1::stream(address);
+        let second = F1::stream(address);
+
+        assert!(first.is_ok());
+        assert!(second.is_err());
+    }
+
+    #[tokio::test]
+    async fn stream_with_dual_stack() {
+        let port = free_address().port();
+        let address = SocketAddr::from(([0u16; 8], port));
+        let options = SocketOptions::default().dual_stack(true);
+
+        let mut stream = F1::stream_with_options(address, options).unwrap();
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender
+            .send_to(&session_started(), ("127.0.0.1", port))
+            .unwrap();
+
+        let packet = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(7, packet.header().session_uid());
+    }
+
+    #[tokio::test]
+    async fn stream_with_raw_bytes() {
+        let address = free_address();
+        let mut stream = F1::stream_with_raw_bytes(address, SocketOptions::default()).unwrap();
+
+        let mut second = session_started();
+        second[6] = 8;
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.send_to(&session_started(), address).unwrap();
+        sender.send_to(&second, address).unwrap();
+
+        for expected in [session_started(), second] {
+            let raw = tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(&expected[..], &raw.bytes()[..]);
+            assert_eq!(expected[6] as u64, raw.packet().header().session_uid());
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_skips_undecodable_datagrams() {
+        let address = free_address();
+        let mut stream = F1::stream_with_raw_bytes(address, SocketOptions::default()).unwrap();
+
+        let mut unknown_format = session_started();
+        unknown_format[0] = 0;
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.send_to(&unknown_format, address).unwrap();
+        sender.send_to(&session_started(), address).unwrap();
+
+        let raw = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(&session_started()[..], &raw.bytes()[..]);
+    }
+
+    #[test]
+    fn dual_stack_requires_ipv6() {
+        let options = SocketOptions::default().dual_stack(true);
+
+        let error = options.bind(free_address()).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+    }
+}
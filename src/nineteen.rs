@@ -7,9 +7,9 @@
 //! The full API specification can be found here:
 //! https://forums.codemasters.com/topic/44592-f1-2019-udp-specification/
 
-use std::io::{Cursor, Error};
-
-use bytes::BytesMut;
+use alloc::format;
+use alloc::string::String;
+use core::time::Duration;
 
 use crate::nineteen::event::decode_event;
 use crate::nineteen::header::decode_header;
@@ -21,7 +21,10 @@ use crate::nineteen::setup::decode_setups;
 use crate::nineteen::status::decode_statuses;
 use crate::nineteen::telemetry::decode_telemetry;
 use crate::packet::header::PacketType;
-use crate::packet::Packet;
+use crate::packet::reader::Reader;
+use crate::packet::DecodeError;
+use crate::packet::{GameSpec, Packet};
+use crate::types::CornerProperty;
 
 mod header;
 
@@ -30,6 +33,7 @@ pub mod flag;
 pub mod lap;
 pub mod motion;
 pub mod participants;
+pub mod raw;
 pub mod session;
 pub mod setup;
 pub mod status;
@@ -50,6 +54,27 @@ pub enum Flag {
     Red = 4,
 }
 
+/// API specification of F1 2019
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::nineteen::Nineteen;
+/// use f1_api::packet::GameSpec;
+///
+/// assert_eq!(2019, Nineteen::PACKET_FORMAT);
+/// ```
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+pub struct Nineteen;
+
+impl GameSpec for Nineteen {
+    const PACKET_FORMAT: u16 = 2019;
+
+    fn decode(cursor: &mut Reader<'_>) -> Result<Packet, DecodeError> {
+        decode_nineteen(cursor)
+    }
+}
+
 /// Index referencing a car in the packet payloads
 ///
 /// Data for all vehicles is provided as an array. References to the data in
@@ -61,20 +86,79 @@ pub type VehicleIndex = u8;
 /// F1 2019 defines its own API specification that is implemented in the `nineteen` module. For each
 /// packet type defined in the API specification, a decoder function exists that maps the packet
 /// from F1 2019 to the unified packet format of this crate.
-pub fn decode_nineteen(cursor: &mut Cursor<&mut BytesMut>) -> Result<Packet, Error> {
+pub fn decode_nineteen(cursor: &mut Reader<'_>) -> Result<Packet, DecodeError> {
     let header = decode_header(cursor)?;
     cursor.set_position(0);
 
     let packet = match header.packet_type() {
-        PacketType::Event => Packet::Event(decode_event(cursor)?),
-        PacketType::Lap => Packet::Lap(decode_lap_data(cursor)?),
-        PacketType::Motion => Packet::Motion(decode_motion(cursor)?),
-        PacketType::Participants => Packet::Participants(decode_participants(cursor)?),
-        PacketType::Session => Packet::Session(decode_session(cursor)?),
-        PacketType::Setup => Packet::Setup(decode_setups(cursor)?),
-        PacketType::Status => Packet::Status(decode_statuses(cursor)?),
-        PacketType::Telemetry => Packet::Telemetry(decode_telemetry(cursor)?),
+        PacketType::Event => decode_event(cursor).map(Packet::Event),
+        PacketType::Lap => decode_lap_data(cursor).map(Packet::Lap),
+        PacketType::Motion => decode_motion(cursor).map(Packet::Motion),
+        PacketType::Participants => decode_participants(cursor).map(Packet::Participants),
+        PacketType::Session => decode_session(cursor).map(Packet::Session),
+        PacketType::Setup => decode_setups(cursor).map(Packet::Setup),
+        PacketType::Status => decode_statuses(cursor).map(Packet::Status),
+        PacketType::Telemetry => decode_telemetry(cursor).map(Packet::Telemetry),
     };
 
-    Ok(packet)
+    #[cfg(feature = "tracing")]
+    if let Err(error) = &packet {
+        tracing::debug!(
+            packet_type = ?header.packet_type(),
+            size = cursor.get_ref().len(),
+            %error,
+            "Failed to decode packet"
+        );
+    }
+
+    packet
+}
+
+/// Decode a packet of the given type sent by F1 2019
+///
+/// The packet type in the header is compared to the expected type before the packet is decoded
+/// with the given decoder, so that the payload of another packet type is not misinterpreted.
+pub(crate) fn decode_nineteen_as<T>(
+    cursor: &mut Reader<'_>,
+    packet_type: PacketType,
+    decoder: fn(&mut Reader<'_>) -> Result<T, DecodeError>,
+) -> Result<T, DecodeError> {
+    let header = decode_header(cursor)?;
+    cursor.set_position(0);
+
+    if header.packet_type() != packet_type {
+        return Err(DecodeError::InvalidData(format!(
+            "Expected a packet of type {:?}, but got {:?}.",
+            packet_type,
+            header.packet_type()
+        )));
+    }
+
+    decoder(cursor)
+}
+
+/// Decode a property that is sent for each corner of a car
+pub(crate) fn decode_corners<T>(values: [T; 4]) -> CornerProperty<T>
+where
+    T: Copy,
+{
+    CornerProperty::new(values[0], values[1], values[2], values[3])
+}
+
+/// Decode a time in seconds, which must not be negative, infinite, or NaN
+pub(crate) fn decode_duration(seconds: f32) -> Result<Duration, DecodeError> {
+    Duration::try_from_secs_f32(seconds).map_err(|_| {
+        DecodeError::InvalidData(format!("Failed to decode time of {} seconds.", seconds))
+    })
+}
+
+/// Decode a signed time in seconds, which may be negative but must not be NaN
+pub(crate) fn decode_signed_seconds(seconds: f32) -> Result<f32, DecodeError> {
+    if seconds.is_nan() {
+        return Err(DecodeError::InvalidData(String::from(
+            "Failed to decode time of NaN seconds.",
+        )));
+    }
+
+    Ok(seconds)
 }
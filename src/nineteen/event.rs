@@ -3,16 +3,18 @@
 //! F1 2019 extended the event packet with seven new events compared to its predecessor, four of
 //! which can carry a payload.
 
-use std::io::{Cursor, Error, ErrorKind};
-use std::time::Duration;
+use alloc::format;
+use alloc::string::String;
 
-use bytes::{Buf, BytesMut};
-
-use crate::nineteen::header::decode_header;
+use crate::nineteen::decode_duration;
+use crate::nineteen::header::decode_raw_header;
+use crate::nineteen::raw::{self, PacketEventData};
 use crate::packet::ensure_packet_size;
 use crate::packet::event::{
     Event, EventPacket, FastestLap, RaceWinner, Retirement, TeammateInPits,
 };
+use crate::packet::reader::{Decode, Reader};
+use crate::packet::DecodeError;
 
 /// Size of the event packet in bytes
 ///
@@ -26,27 +28,31 @@ pub const PACKET_SIZE: usize = 32;
 /// which can carry a payload. A four character event code is provided after the packet header to
 /// identify the event. Based on this code the right decoding function is called, and a variant of
 /// the `EventPacket` is returned.
-pub fn decode_event(cursor: &mut Cursor<&mut BytesMut>) -> Result<EventPacket, Error> {
+pub fn decode_event(cursor: &mut Reader<'_>) -> Result<EventPacket, DecodeError> {
     ensure_packet_size(PACKET_SIZE, cursor)?;
 
-    let header = decode_header(cursor)?;
-    let event_code = decode_event_code(cursor);
+    let packet = PacketEventData::decode(cursor)?;
+    let header = decode_raw_header(&packet.header)?;
+
+    let event_code = decode_event_code(packet.event_string_code);
+    let details = packet.event_details;
+    let mut details = Reader::new(&details);
 
     let payload = match event_code.as_str() {
         "SSTA" => Event::SessionStarted,
         "SEND" => Event::SessionEnded,
-        "FTLP" => decode_fastest_lap(cursor),
-        "RTMT" => decode_retirement(cursor),
+        "FTLP" => decode_fastest_lap(&mut details)?,
+        "RTMT" => decode_retirement(&mut details)?,
         "DRSE" => Event::DrsEnabled,
         "DRSD" => Event::DrsDisabled,
-        "TMPT" => decode_teammate_pits(cursor),
+        "TMPT" => decode_teammate_pits(&mut details)?,
         "CHQF" => Event::ChequeredFlag,
-        "RCWN" => decode_race_winner(cursor),
+        "RCWN" => decode_race_winner(&mut details)?,
         event_code => {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("Unexpected event code {}", event_code),
-            ))
+            return Err(DecodeError::InvalidData(format!(
+                "Unexpected event code {}",
+                event_code
+            )))
         }
     };
 
@@ -57,48 +63,50 @@ pub fn decode_event(cursor: &mut Cursor<&mut BytesMut>) -> Result<EventPacket, E
 ///
 /// The event packet contains a string that identifies the type of the event. Based on the event
 /// code different logic can be used to decode the remainder of the packet.
-fn decode_event_code(cursor: &mut Cursor<&mut BytesMut>) -> String {
-    [
-        cursor.get_u8() as char,
-        cursor.get_u8() as char,
-        cursor.get_u8() as char,
-        cursor.get_u8() as char,
-    ]
-    .iter()
-    .collect()
+fn decode_event_code(code: [u8; 4]) -> String {
+    code.iter().map(|letter| *letter as char).collect()
 }
 
 /// Decode the "Fastest Lap" event.
-fn decode_fastest_lap(cursor: &mut Cursor<&mut BytesMut>) -> Event {
-    Event::FastestLap(FastestLap::new(
-        cursor.get_u8(),
-        Duration::from_secs_f32(cursor.get_f32_le()),
-    ))
+fn decode_fastest_lap(cursor: &mut Reader<'_>) -> Result<Event, DecodeError> {
+    let details = raw::FastestLap::decode(cursor)?;
+
+    Ok(Event::FastestLap(FastestLap::new(
+        details.vehicle_idx,
+        decode_duration(details.lap_time)?,
+    )))
 }
 
 /// Decode the "Retirement" event.
-fn decode_retirement(cursor: &mut Cursor<&mut BytesMut>) -> Event {
-    Event::Retirement(Retirement::new(cursor.get_u8()))
+fn decode_retirement(cursor: &mut Reader<'_>) -> Result<Event, DecodeError> {
+    let details = raw::Retirement::decode(cursor)?;
+
+    Ok(Event::Retirement(Retirement::new(details.vehicle_idx)))
 }
 
 /// Decode the "Teammate in Pits" event.
-fn decode_teammate_pits(cursor: &mut Cursor<&mut BytesMut>) -> Event {
-    Event::TeammatesInPits(TeammateInPits::new(cursor.get_u8()))
+fn decode_teammate_pits(cursor: &mut Reader<'_>) -> Result<Event, DecodeError> {
+    let details = raw::TeamMateInPits::decode(cursor)?;
+
+    Ok(Event::TeammatesInPits(TeammateInPits::new(
+        details.vehicle_idx,
+    )))
 }
 
 /// Decode the "Race Winner" event.
-fn decode_race_winner(cursor: &mut Cursor<&mut BytesMut>) -> Event {
-    Event::RaceWinner(RaceWinner::new(cursor.get_u8()))
+fn decode_race_winner(cursor: &mut Reader<'_>) -> Result<Event, DecodeError> {
+    let details = raw::RaceWinner::decode(cursor)?;
+
+    Ok(Event::RaceWinner(RaceWinner::new(details.vehicle_idx)))
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
-
     use bytes::{BufMut, BytesMut};
 
     use crate::nineteen::event::{decode_event, PACKET_SIZE};
     use crate::packet::event::Event;
+    use crate::packet::reader::Reader;
 
     fn put_packet_header(mut bytes: BytesMut) -> BytesMut {
         bytes.put_u16_le(2019);
@@ -106,9 +114,9 @@ mod tests {
         bytes.put_u8(2);
         bytes.put_u8(3);
         bytes.put_u8(0);
-        bytes.put_u64_le(u64::max_value());
+        bytes.put_u64_le(u64::MAX);
         bytes.put_f32_le(1.0);
-        bytes.put_u32_le(u32::max_value());
+        bytes.put_u32_le(u32::MAX);
         bytes.put_u8(0);
 
         bytes
@@ -116,8 +124,8 @@ mod tests {
 
     #[test]
     fn decode_event_with_error() {
-        let mut bytes = BytesMut::with_capacity(0);
-        let mut cursor = Cursor::new(&mut bytes);
+        let bytes = BytesMut::with_capacity(0);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_event(&mut cursor);
         assert!(packet.is_err());
@@ -135,7 +143,7 @@ mod tests {
         bytes.put_u8(1);
         bytes.put_f32_le(2.0);
 
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_event(&mut cursor).unwrap();
         match packet.event() {
@@ -156,7 +164,7 @@ mod tests {
         let padding = vec![0u8; 5];
         bytes.put(padding.as_slice());
 
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_event(&mut cursor).unwrap();
         assert_eq!(Event::SessionStarted, *packet.event())
@@ -1,15 +1,12 @@
 //! Decoder for flags that can be shown to cars
 
-use std::io::{Cursor, Error, ErrorKind};
-
-use bytes::{Buf, BytesMut};
+use alloc::string::String;
 
+use crate::packet::DecodeError;
 use crate::types::Flag;
 
 /// Decode a flag that can be shown to cars
-pub fn decode_flag(cursor: &mut Cursor<&mut BytesMut>) -> Result<Flag, Error> {
-    let value = cursor.get_i8();
-
+pub fn decode_flag(value: i8) -> Result<Flag, DecodeError> {
     match value {
         -1 => Ok(Flag::Invalid),
         0 => Ok(Flag::None),
@@ -17,6 +14,8 @@ pub fn decode_flag(cursor: &mut Cursor<&mut BytesMut>) -> Result<Flag, Error> {
         2 => Ok(Flag::Blue),
         3 => Ok(Flag::Yellow),
         4 => Ok(Flag::Red),
-        _ => Err(Error::new(ErrorKind::InvalidData, "Failed to decode flag.")),
+        _ => Err(DecodeError::InvalidData(String::from(
+            "Failed to decode flag.",
+        ))),
     }
 }
@@ -1,12 +1,14 @@
 //! Decoder for header prefixing packets sent by F1 2019
 
-use std::io::{Cursor, Error, ErrorKind};
-
-use bitflags::_core::time::Duration;
-use bytes::{Buf, BytesMut};
+use alloc::format;
+use alloc::string::String;
 
+use crate::nineteen::decode_duration;
+use crate::nineteen::raw::PacketHeader;
 use crate::packet::ensure_packet_size;
 use crate::packet::header::{ApiSpec, GameVersion, Header, PacketType};
+use crate::packet::reader::{Decode, Reader};
+use crate::packet::DecodeError;
 
 /// Size of the packet header in F1 2019
 pub const HEADER_SIZE: usize = 23;
@@ -17,50 +19,41 @@ pub const HEADER_SIZE: usize = 23;
 /// required to decode the package properly and information about the session the packet belongs to.
 /// The latter is extracted from the header and returned to the caller. The technical details are
 /// dropped, since their information is encoded in the type system once the packet has been decoded.
-pub fn decode_header(cursor: &mut Cursor<&mut BytesMut>) -> Result<Header, Error> {
+pub fn decode_header(cursor: &mut Reader<'_>) -> Result<Header, DecodeError> {
     ensure_packet_size(HEADER_SIZE, cursor)?;
 
-    let api_spec = decode_api_spec(cursor)?;
-    let game_version = decode_game_version(cursor);
-
-    cursor.get_u8(); // Move cursor past packet version
-
-    let packet_type = decode_packet_type(cursor)?;
-    let session_uid = cursor.get_u64_le();
-    let session_time = Duration::from_secs_f32(cursor.get_f32_le());
-    let frame_identifier = cursor.get_u32_le();
-    let player_car_index = cursor.get_u8();
+    decode_raw_header(&PacketHeader::decode(cursor)?)
+}
 
+/// Map the raw header of a packet to the unified header format.
+pub(crate) fn decode_raw_header(header: &PacketHeader) -> Result<Header, DecodeError> {
     Ok(Header::new(
-        api_spec,
-        game_version,
-        packet_type,
-        session_uid,
-        session_time,
-        frame_identifier,
-        player_car_index,
+        decode_api_spec(header.packet_format)?,
+        Some(GameVersion::new(
+            header.game_major_version,
+            header.game_minor_version,
+        )),
+        decode_packet_type(header.packet_id)?,
+        header.session_uid,
+        decode_duration(header.session_time)?,
+        header.frame_identifier,
+        header.player_car_index,
+        // F1 2019 does not publish a secondary player for split-screen sessions.
+        None,
     ))
 }
 
-fn decode_api_spec(cursor: &mut Cursor<&mut BytesMut>) -> Result<ApiSpec, Error> {
-    let value = cursor.get_u16_le();
-
+fn decode_api_spec(value: u16) -> Result<ApiSpec, DecodeError> {
     match value {
         2019 => Ok(ApiSpec::Nineteen),
-        format => Err(Error::new(
-            ErrorKind::InvalidData,
-            format!("Unknown API specification {}.", format),
-        )),
+        format => Err(DecodeError::InvalidData(format!(
+            "Unknown API specification {}.",
+            format
+        ))),
     }
 }
 
-fn decode_game_version(cursor: &mut Cursor<&mut BytesMut>) -> Option<GameVersion> {
-    Some(GameVersion::new(cursor.get_u8(), cursor.get_u8()))
-}
-
-fn decode_packet_type(cursor: &mut Cursor<&mut BytesMut>) -> Result<PacketType, Error> {
-    let value = cursor.get_u8();
-
+fn decode_packet_type(value: u8) -> Result<PacketType, DecodeError> {
     match value {
         0 => Ok(PacketType::Motion),
         1 => Ok(PacketType::Session),
@@ -70,26 +63,24 @@ fn decode_packet_type(cursor: &mut Cursor<&mut BytesMut>) -> Result<PacketType,
         5 => Ok(PacketType::Setup),
         6 => Ok(PacketType::Telemetry),
         7 => Ok(PacketType::Status),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode packet id.",
-        )),
+        ))),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
-
     use bytes::{BufMut, BytesMut};
 
     use crate::nineteen::header::{decode_header, HEADER_SIZE};
     use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::reader::Reader;
 
     #[test]
     fn decode_header_with_error() {
-        let mut bytes = BytesMut::with_capacity(0);
-        let mut cursor = Cursor::new(&mut bytes);
+        let bytes = BytesMut::with_capacity(0);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_header(&mut cursor);
         assert!(packet.is_err());
@@ -103,21 +94,22 @@ mod tests {
         bytes.put_u8(2);
         bytes.put_u8(3);
         bytes.put_u8(0);
-        bytes.put_u64_le(u64::max_value());
+        bytes.put_u64_le(u64::MAX);
         bytes.put_f32_le(1.0);
-        bytes.put_u32_le(u32::max_value());
+        bytes.put_u32_le(u32::MAX);
         bytes.put_u8(0);
 
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&bytes);
         let header = decode_header(&mut cursor).unwrap();
 
         assert_eq!(ApiSpec::Nineteen, header.api_spec());
         assert_eq!(1, header.game_version().unwrap().major());
         assert_eq!(2, header.game_version().unwrap().minor());
         assert_eq!(PacketType::Motion, header.packet_type());
-        assert_eq!(u64::max_value(), header.session_uid());
+        assert_eq!(u64::MAX, header.session_uid());
         assert_eq!(1, header.session_time().as_secs());
-        assert_eq!(u32::max_value(), header.frame_identifier());
+        assert_eq!(u32::MAX, header.frame_identifier());
         assert_eq!(0, header.player_car_index());
+        assert_eq!(None, header.secondary_player_car_index());
     }
 }
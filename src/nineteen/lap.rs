@@ -3,14 +3,17 @@
 //! The lap data packets by F1 2018 and F1 2019 differ only in their packet headers, the rest of the
 //! packet format is identical.
 
-use std::io::{Cursor, Error, ErrorKind};
-use std::time::Duration;
+use alloc::string::String;
+use alloc::vec::Vec;
 
-use bytes::{Buf, BytesMut};
-
-use crate::nineteen::header::decode_header;
+use crate::nineteen::header::decode_raw_header;
+use crate::nineteen::raw::{LapData, PacketLapData};
+use crate::nineteen::{decode_duration, decode_signed_seconds};
 use crate::packet::ensure_packet_size;
 use crate::packet::lap::{DriverStatus, Lap, LapPacket, PitStatus, ResultStatus, Sector};
+use crate::packet::reader::{Decode, Reader};
+use crate::packet::DecodeError;
+use crate::types::Grid;
 
 /// Size of the lap data packet in bytes
 pub const PACKET_SIZE: usize = 843;
@@ -19,84 +22,81 @@ pub const PACKET_SIZE: usize = 843;
 ///
 /// F1 2018 and F1 2019 publish the same data in their lap data packets, but with different packet
 /// headers.
-pub fn decode_lap_data(cursor: &mut Cursor<&mut BytesMut>) -> Result<LapPacket, Error> {
+pub fn decode_lap_data(cursor: &mut Reader<'_>) -> Result<LapPacket, DecodeError> {
     ensure_packet_size(PACKET_SIZE, cursor)?;
 
-    let header = decode_header(cursor)?;
-    let mut laps = Vec::with_capacity(20);
-
-    for _ in 0..20 {
-        laps.push(Lap::new(
-            Duration::from_secs_f32(cursor.get_f32_le()),
-            Duration::from_secs_f32(cursor.get_f32_le()),
-            Duration::from_secs_f32(cursor.get_f32_le()),
-            Duration::from_secs_f32(cursor.get_f32_le()),
-            Duration::from_secs_f32(cursor.get_f32_le()),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            Duration::from_secs_f32(cursor.get_f32_le()),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            decode_pit_status(cursor)?,
-            decode_sector(cursor)?,
-            cursor.get_u8() < 1,
-            cursor.get_u8(),
-            cursor.get_u8(),
-            decode_driver_status(cursor)?,
-            decode_result_status(cursor)?,
-        ));
-    }
+    let packet = PacketLapData::decode(cursor)?;
+    let header = decode_raw_header(&packet.header)?;
+
+    let laps = { packet.lap_data }
+        .iter()
+        .map(decode_lap)
+        .collect::<Result<Vec<Lap>, DecodeError>>()?;
+
+    // Slots without a car have an invalid result status.
+    let laps = Grid::with_active(laps, |lap| lap.result_status() != ResultStatus::Invalid);
 
     Ok(LapPacket::new(header, laps))
 }
 
-fn decode_sector(cursor: &mut Cursor<&mut BytesMut>) -> Result<Sector, Error> {
-    let value = cursor.get_u8();
+fn decode_lap(lap: &LapData) -> Result<Lap, DecodeError> {
+    Ok(Lap::new(
+        decode_duration(lap.last_lap_time)?,
+        decode_duration(lap.current_lap_time)?,
+        decode_duration(lap.best_lap_time)?,
+        decode_duration(lap.sector1_time)?,
+        decode_duration(lap.sector2_time)?,
+        lap.lap_distance,
+        lap.total_distance,
+        decode_signed_seconds(lap.safety_car_delta)?,
+        lap.car_position,
+        lap.current_lap_num,
+        decode_pit_status(lap.pit_status)?,
+        decode_sector(lap.sector)?,
+        lap.current_lap_invalid < 1,
+        lap.penalties,
+        lap.grid_position,
+        decode_driver_status(lap.driver_status)?,
+        decode_result_status(lap.result_status)?,
+    ))
+}
 
+fn decode_sector(value: u8) -> Result<Sector, DecodeError> {
     match value {
         0 => Ok(Sector::First),
         1 => Ok(Sector::Second),
         2 => Ok(Sector::Third),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode sector.",
-        )),
+        ))),
     }
 }
 
-fn decode_pit_status(cursor: &mut Cursor<&mut BytesMut>) -> Result<PitStatus, Error> {
-    let value = cursor.get_u8();
-
+fn decode_pit_status(value: u8) -> Result<PitStatus, DecodeError> {
     match value {
         0 => Ok(PitStatus::None),
         1 => Ok(PitStatus::Pitting),
         2 => Ok(PitStatus::InPits),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode pit status.",
-        )),
+        ))),
     }
 }
 
-fn decode_driver_status(cursor: &mut Cursor<&mut BytesMut>) -> Result<DriverStatus, Error> {
-    let value = cursor.get_u8();
-
+fn decode_driver_status(value: u8) -> Result<DriverStatus, DecodeError> {
     match value {
         0 => Ok(DriverStatus::InGarage),
         1 => Ok(DriverStatus::FlyingLap),
         2 => Ok(DriverStatus::InLap),
         3 => Ok(DriverStatus::OutLap),
         4 => Ok(DriverStatus::OnTrack),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode driver status.",
-        )),
+        ))),
     }
 }
 
-fn decode_result_status(cursor: &mut Cursor<&mut BytesMut>) -> Result<ResultStatus, Error> {
-    let value = cursor.get_u8();
-
+fn decode_result_status(value: u8) -> Result<ResultStatus, DecodeError> {
     match value {
         0 => Ok(ResultStatus::Invalid),
         1 => Ok(ResultStatus::Inactive),
@@ -105,21 +105,19 @@ fn decode_result_status(cursor: &mut Cursor<&mut BytesMut>) -> Result<ResultStat
         4 => Ok(ResultStatus::Disqualified),
         5 => Ok(ResultStatus::NotClassified),
         6 => Ok(ResultStatus::Retired),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode result status.",
-        )),
+        ))),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
-
     use bytes::{BufMut, BytesMut};
 
     use crate::nineteen::lap::{decode_lap_data, PACKET_SIZE};
     use crate::packet::lap::{DriverStatus, PitStatus, ResultStatus, Sector};
+    use crate::packet::reader::Reader;
 
     fn put_packet_header(mut bytes: BytesMut) -> BytesMut {
         bytes.put_u16_le(2019);
@@ -127,9 +125,9 @@ mod tests {
         bytes.put_u8(2);
         bytes.put_u8(3);
         bytes.put_u8(0);
-        bytes.put_u64_le(u64::max_value());
+        bytes.put_u64_le(u64::MAX);
         bytes.put_f32_le(1.0);
-        bytes.put_u32_le(u32::max_value());
+        bytes.put_u32_le(u32::MAX);
         bytes.put_u8(0);
 
         bytes
@@ -137,8 +135,8 @@ mod tests {
 
     #[test]
     fn decode_lap_data_with_error() {
-        let mut bytes = BytesMut::with_capacity(0);
-        let mut cursor = Cursor::new(&mut bytes);
+        let bytes = BytesMut::with_capacity(0);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_lap_data(&mut cursor);
         assert!(packet.is_err());
@@ -170,7 +168,7 @@ mod tests {
         let padding = vec![0u8; 779];
         bytes.put(padding.as_slice());
 
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_lap_data(&mut cursor).unwrap();
         let lap = packet.laps()[0];
@@ -182,7 +180,7 @@ mod tests {
         assert_eq!(19, lap.sector2_time().as_secs());
         assert_eq!(543, lap.lap_distance() as usize);
         assert_eq!(2048, lap.total_distance() as usize);
-        assert_eq!(0, lap.safety_car_delta().as_secs());
+        assert_eq!(0.0, lap.safety_car_delta());
         assert_eq!(1, lap.position());
         assert_eq!(4, lap.current_lap_number());
         assert_eq!(PitStatus::None, lap.pit_status());
@@ -193,4 +191,37 @@ mod tests {
         assert_eq!(DriverStatus::FlyingLap, lap.driver_status());
         assert_eq!(ResultStatus::Active, lap.result_status())
     }
+
+    #[test]
+    fn decode_lap_data_with_negative_safety_car_delta() {
+        let mut bytes = BytesMut::with_capacity(PACKET_SIZE);
+        bytes = put_packet_header(bytes);
+
+        bytes.put_f32_le(62.0);
+        bytes.put_f32_le(60.0);
+        bytes.put_f32_le(58.1);
+        bytes.put_f32_le(21.1);
+        bytes.put_f32_le(19.0);
+        bytes.put_f32_le(543.0);
+        bytes.put_f32_le(2048.4);
+        bytes.put_f32_le(-1.5);
+        bytes.put_u8(1);
+        bytes.put_u8(4);
+        bytes.put_u8(0);
+        bytes.put_u8(2);
+        bytes.put_u8(0);
+        bytes.put_u8(0);
+        bytes.put_u8(3);
+        bytes.put_u8(1);
+        bytes.put_u8(2);
+
+        let padding = vec![0u8; 779];
+        bytes.put(padding.as_slice());
+
+        let mut cursor = Reader::new(&bytes);
+
+        let packet = decode_lap_data(&mut cursor).unwrap();
+
+        assert_eq!(-1.5, packet.laps()[0].safety_car_delta());
+    }
 }
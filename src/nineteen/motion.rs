@@ -3,14 +3,14 @@
 //! The motion packets by F1 2018 and F1 2019 differ only in their packet headers, the rest of the
 //! packet format is identical.
 
-use std::io::{Cursor, Error};
-
-use bytes::{Buf, BytesMut};
-
-use crate::nineteen::header::decode_header;
+use crate::nineteen::decode_corners;
+use crate::nineteen::header::decode_raw_header;
+use crate::nineteen::raw::{CarMotionData, PacketMotionData};
 use crate::packet::ensure_packet_size;
 use crate::packet::motion::{Motion, MotionPacket};
-use crate::types::{CornerProperty, Property3D};
+use crate::packet::reader::{Decode, Reader};
+use crate::packet::DecodeError;
+use crate::types::Property3D;
 
 /// Size of the motion packet in bytes
 pub const PACKET_SIZE: usize = 1343;
@@ -19,169 +19,81 @@ pub const PACKET_SIZE: usize = 1343;
 ///
 /// F1 2018 and F1 2019 publish the same data in their motion packets, but with different packet
 /// headers.
-pub fn decode_motion(cursor: &mut Cursor<&mut BytesMut>) -> Result<MotionPacket, Error> {
+pub fn decode_motion(cursor: &mut Reader<'_>) -> Result<MotionPacket, DecodeError> {
     ensure_packet_size(PACKET_SIZE, cursor)?;
 
-    let header = decode_header(cursor)?;
-    let mut cars = Vec::with_capacity(20);
-
-    for _ in 0..20 {
-        cars.push(Motion::new(
-            decode_position(cursor),
-            decode_velocity(cursor),
-            decode_forward_direction(cursor),
-            decode_right_direction(cursor),
-            decode_g_force(cursor),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-        ))
-    }
+    let packet = PacketMotionData::decode(cursor)?;
+    let header = decode_raw_header(&packet.header)?;
+    let cars = { packet.car_motion_data }.iter().map(decode_car).collect();
 
     Ok(MotionPacket::new(
         header,
         cars,
-        decode_suspension_position(cursor),
-        decode_suspension_velocity(cursor),
-        decode_suspension_acceleration(cursor),
-        decode_wheel_speed(cursor),
-        decode_wheel_slip(cursor),
-        decode_local_velocity(cursor),
-        decode_angular_velocity(cursor),
-        decode_angular_acceleration(cursor),
-        cursor.get_f32_le(),
+        decode_corners(packet.suspension_position),
+        decode_corners(packet.suspension_velocity),
+        decode_corners(packet.suspension_acceleration),
+        decode_corners(packet.wheel_speed),
+        decode_corners(packet.wheel_slip),
+        Property3D::new(
+            packet.local_velocity_x,
+            packet.local_velocity_y,
+            packet.local_velocity_z,
+        ),
+        Property3D::new(
+            packet.angular_velocity_x,
+            packet.angular_velocity_y,
+            packet.angular_velocity_z,
+        ),
+        Property3D::new(
+            packet.angular_acceleration_x,
+            packet.angular_acceleration_y,
+            packet.angular_acceleration_z,
+        ),
+        packet.front_wheels_angle,
     ))
 }
 
-/// Decode position of the car
-fn decode_position(cursor: &mut Cursor<&mut BytesMut>) -> Property3D<f32> {
-    Property3D::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-/// Decode velocity of the car
-fn decode_velocity(cursor: &mut Cursor<&mut BytesMut>) -> Property3D<f32> {
-    Property3D::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-/// Decode forward direction of the car
-fn decode_forward_direction(cursor: &mut Cursor<&mut BytesMut>) -> Property3D<i16> {
-    Property3D::new(
-        cursor.get_i16_le(),
-        cursor.get_i16_le(),
-        cursor.get_i16_le(),
-    )
-}
-
-/// Decode right direction of the car
-fn decode_right_direction(cursor: &mut Cursor<&mut BytesMut>) -> Property3D<i16> {
-    Property3D::new(
-        cursor.get_i16_le(),
-        cursor.get_i16_le(),
-        cursor.get_i16_le(),
-    )
-}
-
-/// Decode G forces on the car
-fn decode_g_force(cursor: &mut Cursor<&mut BytesMut>) -> Property3D<f32> {
-    Property3D::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-/// Decode suspension position of the player's car
-fn decode_suspension_position(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<f32> {
-    CornerProperty::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-/// Decode suspension velocity of the player's car
-fn decode_suspension_velocity(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<f32> {
-    CornerProperty::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-/// Decode suspension acceleration of the player's car
-fn decode_suspension_acceleration(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<f32> {
-    CornerProperty::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-/// Decode the wheel speed of the player's car
-fn decode_wheel_speed(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<f32> {
-    CornerProperty::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-/// Decode the wheel slip of the player's car
-fn decode_wheel_slip(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<f32> {
-    CornerProperty::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-/// Decode the local velocity of the player's car
-fn decode_local_velocity(cursor: &mut Cursor<&mut BytesMut>) -> Property3D<f32> {
-    Property3D::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-/// Decode the angular velocity of the player's car
-fn decode_angular_velocity(cursor: &mut Cursor<&mut BytesMut>) -> Property3D<f32> {
-    Property3D::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-/// Decode the angular acceleration of the player's car
-fn decode_angular_acceleration(cursor: &mut Cursor<&mut BytesMut>) -> Property3D<f32> {
-    Property3D::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
+/// Decode the motion of a car
+fn decode_car(car: &CarMotionData) -> Motion {
+    Motion::new(
+        Property3D::new(
+            car.world_position_x,
+            car.world_position_y,
+            car.world_position_z,
+        ),
+        Property3D::new(
+            car.world_velocity_x,
+            car.world_velocity_y,
+            car.world_velocity_z,
+        ),
+        Property3D::new(
+            car.world_forward_dir_x,
+            car.world_forward_dir_y,
+            car.world_forward_dir_z,
+        ),
+        Property3D::new(
+            car.world_right_dir_x,
+            car.world_right_dir_y,
+            car.world_right_dir_z,
+        ),
+        Property3D::new(
+            car.g_force_lateral,
+            car.g_force_longitudinal,
+            car.g_force_vertical,
+        ),
+        car.yaw,
+        car.pitch,
+        car.roll,
     )
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
-
     use assert_approx_eq::assert_approx_eq;
     use bytes::{BufMut, BytesMut};
 
     use crate::nineteen::motion::{decode_motion, PACKET_SIZE};
+    use crate::packet::reader::Reader;
 
     fn put_packet_header(mut bytes: BytesMut) -> BytesMut {
         bytes.put_u16_le(2019);
@@ -189,9 +101,9 @@ mod tests {
         bytes.put_u8(2);
         bytes.put_u8(3);
         bytes.put_u8(0);
-        bytes.put_u64_le(u64::max_value());
+        bytes.put_u64_le(u64::MAX);
         bytes.put_f32_le(1.0);
-        bytes.put_u32_le(u32::max_value());
+        bytes.put_u32_le(u32::MAX);
         bytes.put_u8(0);
 
         bytes
@@ -199,8 +111,8 @@ mod tests {
 
     #[test]
     fn decode_motion_with_error() {
-        let mut bytes = BytesMut::with_capacity(0);
-        let mut cursor = Cursor::new(&mut bytes);
+        let bytes = BytesMut::with_capacity(0);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_motion(&mut cursor);
         assert!(packet.is_err());
@@ -264,7 +176,7 @@ mod tests {
         bytes.put_f32_le(47.0);
         bytes.put_f32_le(48.0);
 
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&bytes);
         let packet = decode_motion(&mut cursor).unwrap();
 
         let motion = packet.cars()[0];
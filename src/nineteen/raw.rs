@@ -0,0 +1,386 @@
+//! Raw packets of F1 2019 as defined by the API specification
+//!
+//! The structs in this module match the packets of F1 2019 byte for byte. Fields use the types and
+//! names of the specification, without the `m_` prefix, and no values are converted or validated.
+//! They are meant for applications that need the exact values sent by the game, or that want to
+//! access a packet before it is mapped to the unified packet format of this crate.
+//!
+//! All structs are `#[repr(C, packed)]` and contain only integers and floats, so that any sequence
+//! of bytes of the right length is a valid value. They implement the `Decode` trait, which is used
+//! by the decoders of this crate. With the `bytemuck` feature, they also implement `bytemuck::Pod`
+//! and can be read directly from the payload of a datagram.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "bytemuck")]
+//! # {
+//! use f1_api::nineteen::raw::PacketHeader;
+//!
+//! let mut bytes = [0u8; 23];
+//! bytes[..2].copy_from_slice(&2019u16.to_le_bytes());
+//!
+//! let header: PacketHeader = bytemuck::pod_read_unaligned(&bytes);
+//! let packet_format = header.packet_format;
+//!
+//! assert_eq!(2019, packet_format);
+//! # }
+//! ```
+
+use crate::packet::reader::raw_struct;
+
+raw_struct! {
+    /// Header that prefixes every packet
+    pub struct PacketHeader {
+        pub packet_format: u16,
+        pub game_major_version: u8,
+        pub game_minor_version: u8,
+        pub packet_version: u8,
+        pub packet_id: u8,
+        pub session_uid: u64,
+        pub session_time: f32,
+        pub frame_identifier: u32,
+        pub player_car_index: u8,
+    }
+}
+
+raw_struct! {
+    /// Motion data of a single car
+    pub struct CarMotionData {
+        pub world_position_x: f32,
+        pub world_position_y: f32,
+        pub world_position_z: f32,
+        pub world_velocity_x: f32,
+        pub world_velocity_y: f32,
+        pub world_velocity_z: f32,
+        pub world_forward_dir_x: i16,
+        pub world_forward_dir_y: i16,
+        pub world_forward_dir_z: i16,
+        pub world_right_dir_x: i16,
+        pub world_right_dir_y: i16,
+        pub world_right_dir_z: i16,
+        pub g_force_lateral: f32,
+        pub g_force_longitudinal: f32,
+        pub g_force_vertical: f32,
+        pub yaw: f32,
+        pub pitch: f32,
+        pub roll: f32,
+    }
+}
+
+raw_struct! {
+    /// Motion packet with the motion of all cars and extra data for the player's car
+    pub struct PacketMotionData {
+        pub header: PacketHeader,
+        pub car_motion_data: [CarMotionData; 20],
+        pub suspension_position: [f32; 4],
+        pub suspension_velocity: [f32; 4],
+        pub suspension_acceleration: [f32; 4],
+        pub wheel_speed: [f32; 4],
+        pub wheel_slip: [f32; 4],
+        pub local_velocity_x: f32,
+        pub local_velocity_y: f32,
+        pub local_velocity_z: f32,
+        pub angular_velocity_x: f32,
+        pub angular_velocity_y: f32,
+        pub angular_velocity_z: f32,
+        pub angular_acceleration_x: f32,
+        pub angular_acceleration_y: f32,
+        pub angular_acceleration_z: f32,
+        pub front_wheels_angle: f32,
+    }
+}
+
+raw_struct! {
+    /// Marshal zone on the track
+    pub struct MarshalZone {
+        pub zone_start: f32,
+        pub zone_flag: i8,
+    }
+}
+
+raw_struct! {
+    /// Session packet with information about the current session
+    pub struct PacketSessionData {
+        pub header: PacketHeader,
+        pub weather: u8,
+        pub track_temperature: i8,
+        pub air_temperature: i8,
+        pub total_laps: u8,
+        pub track_length: u16,
+        pub session_type: u8,
+        pub track_id: i8,
+        pub formula: u8,
+        pub session_time_left: u16,
+        pub session_duration: u16,
+        pub pit_speed_limit: u8,
+        pub game_paused: u8,
+        pub is_spectating: u8,
+        pub spectator_car_index: u8,
+        pub sli_pro_native_support: u8,
+        pub num_marshal_zones: u8,
+        pub marshal_zones: [MarshalZone; 21],
+        pub safety_car_status: u8,
+        pub network_game: u8,
+    }
+}
+
+raw_struct! {
+    /// Lap data of a single car
+    pub struct LapData {
+        pub last_lap_time: f32,
+        pub current_lap_time: f32,
+        pub best_lap_time: f32,
+        pub sector1_time: f32,
+        pub sector2_time: f32,
+        pub lap_distance: f32,
+        pub total_distance: f32,
+        pub safety_car_delta: f32,
+        pub car_position: u8,
+        pub current_lap_num: u8,
+        pub pit_status: u8,
+        pub sector: u8,
+        pub current_lap_invalid: u8,
+        pub penalties: u8,
+        pub grid_position: u8,
+        pub driver_status: u8,
+        pub result_status: u8,
+    }
+}
+
+raw_struct! {
+    /// Lap data packet with the lap data of all cars
+    pub struct PacketLapData {
+        pub header: PacketHeader,
+        pub lap_data: [LapData; 20],
+    }
+}
+
+raw_struct! {
+    /// Event packet with the code of the event and its details
+    ///
+    /// The specification declares the details as a union of the payloads of the different events.
+    /// The raw packet exposes the bytes of the union, which are interpreted based on the event code.
+    pub struct PacketEventData {
+        pub header: PacketHeader,
+        pub event_string_code: [u8; 4],
+        pub event_details: [u8; 5],
+    }
+}
+
+raw_struct! {
+    /// Details of the fastest lap event
+    pub struct FastestLap {
+        pub vehicle_idx: u8,
+        pub lap_time: f32,
+    }
+}
+
+raw_struct! {
+    /// Details of the retirement event
+    pub struct Retirement {
+        pub vehicle_idx: u8,
+    }
+}
+
+raw_struct! {
+    /// Details of the team mate in pits event
+    pub struct TeamMateInPits {
+        pub vehicle_idx: u8,
+    }
+}
+
+raw_struct! {
+    /// Details of the race winner event
+    pub struct RaceWinner {
+        pub vehicle_idx: u8,
+    }
+}
+
+raw_struct! {
+    /// Participant in the session
+    pub struct ParticipantData {
+        pub ai_controlled: u8,
+        pub driver_id: u8,
+        pub team_id: u8,
+        pub race_number: u8,
+        pub nationality: u8,
+        pub name: [u8; 48],
+        pub your_telemetry: u8,
+    }
+}
+
+raw_struct! {
+    /// Participants packet with all participants in the session
+    pub struct PacketParticipantsData {
+        pub header: PacketHeader,
+        pub num_active_cars: u8,
+        pub participants: [ParticipantData; 20],
+    }
+}
+
+raw_struct! {
+    /// Setup of a single car
+    pub struct CarSetupData {
+        pub front_wing: u8,
+        pub rear_wing: u8,
+        pub on_throttle: u8,
+        pub off_throttle: u8,
+        pub front_camber: f32,
+        pub rear_camber: f32,
+        pub front_toe: f32,
+        pub rear_toe: f32,
+        pub front_suspension: u8,
+        pub rear_suspension: u8,
+        pub front_anti_roll_bar: u8,
+        pub rear_anti_roll_bar: u8,
+        pub front_suspension_height: u8,
+        pub rear_suspension_height: u8,
+        pub brake_pressure: u8,
+        pub brake_bias: u8,
+        pub front_tyre_pressure: f32,
+        pub rear_tyre_pressure: f32,
+        pub ballast: u8,
+        pub fuel_load: f32,
+    }
+}
+
+raw_struct! {
+    /// Car setup packet with the setups of all cars
+    pub struct PacketCarSetupData {
+        pub header: PacketHeader,
+        pub car_setups: [CarSetupData; 20],
+    }
+}
+
+raw_struct! {
+    /// Telemetry of a single car
+    pub struct CarTelemetryData {
+        pub speed: u16,
+        pub throttle: f32,
+        pub steer: f32,
+        pub brake: f32,
+        pub clutch: u8,
+        pub gear: i8,
+        pub engine_rpm: u16,
+        pub drs: u8,
+        pub rev_lights_percent: u8,
+        pub brakes_temperature: [u16; 4],
+        pub tyres_surface_temperature: [u16; 4],
+        pub tyres_inner_temperature: [u16; 4],
+        pub engine_temperature: u16,
+        pub tyres_pressure: [f32; 4],
+        pub surface_type: [u8; 4],
+    }
+}
+
+raw_struct! {
+    /// Telemetry packet with the telemetry of all cars and the buttons pressed by the player
+    pub struct PacketCarTelemetryData {
+        pub header: PacketHeader,
+        pub car_telemetry_data: [CarTelemetryData; 20],
+        pub button_status: u32,
+    }
+}
+
+raw_struct! {
+    /// Status of a single car
+    pub struct CarStatusData {
+        pub traction_control: u8,
+        pub anti_lock_brakes: u8,
+        pub fuel_mix: u8,
+        pub front_brake_bias: u8,
+        pub pit_limiter_status: u8,
+        pub fuel_in_tank: f32,
+        pub fuel_capacity: f32,
+        pub fuel_remaining_laps: f32,
+        pub max_rpm: u16,
+        pub idle_rpm: u16,
+        pub max_gears: u8,
+        pub drs_allowed: i8,
+        pub tyres_wear: [u8; 4],
+        pub actual_tyre_compound: u8,
+        pub visual_tyre_compound: u8,
+        pub tyres_damage: [u8; 4],
+        pub front_left_wing_damage: u8,
+        pub front_right_wing_damage: u8,
+        pub rear_wing_damage: u8,
+        pub engine_damage: u8,
+        pub gear_box_damage: u8,
+        pub vehicle_fia_flags: i8,
+        pub ers_store_energy: f32,
+        pub ers_deploy_mode: u8,
+        pub ers_harvested_this_lap_mguk: f32,
+        pub ers_harvested_this_lap_mguh: f32,
+        pub ers_deployed_this_lap: f32,
+    }
+}
+
+raw_struct! {
+    /// Car status packet with the status of all cars
+    pub struct PacketCarStatusData {
+        pub header: PacketHeader,
+        pub car_status_data: [CarStatusData; 20],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use crate::nineteen::header::HEADER_SIZE;
+    use crate::nineteen::raw::*;
+    use crate::nineteen::{event, lap, motion, participants, session, setup, status, telemetry};
+    use crate::packet::reader::{Decode, Reader};
+    use crate::packet::DecodeError;
+
+    #[test]
+    fn sizes_match_specification() {
+        assert_eq!(HEADER_SIZE, size_of::<PacketHeader>());
+        assert_eq!(motion::PACKET_SIZE, size_of::<PacketMotionData>());
+        assert_eq!(session::PACKET_SIZE, size_of::<PacketSessionData>());
+        assert_eq!(lap::PACKET_SIZE, size_of::<PacketLapData>());
+        assert_eq!(event::PACKET_SIZE, size_of::<PacketEventData>());
+        assert_eq!(
+            participants::PACKET_SIZE,
+            size_of::<PacketParticipantsData>()
+        );
+        assert_eq!(setup::PACKET_SIZE, size_of::<PacketCarSetupData>());
+        assert_eq!(telemetry::PACKET_SIZE, size_of::<PacketCarTelemetryData>());
+        assert_eq!(status::PACKET_SIZE, size_of::<PacketCarStatusData>());
+    }
+
+    #[test]
+    fn decode_fields_in_order() {
+        let mut bytes = [0u8; 5];
+        bytes[..4].copy_from_slice(&0.5f32.to_le_bytes());
+        bytes[4] = 0xff;
+
+        let zone = MarshalZone::decode(&mut Reader::new(&bytes)).unwrap();
+        let (zone_start, zone_flag) = (zone.zone_start, zone.zone_flag);
+
+        assert_eq!(0.5, zone_start);
+        assert_eq!(-1, zone_flag);
+
+        assert_eq!(
+            Err(DecodeError::Incomplete {
+                expected: 1,
+                actual: 0
+            }),
+            MarshalZone::decode(&mut Reader::new(&bytes[..4]))
+        );
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn read_lap_data() {
+        let mut bytes = [0u8; lap::PACKET_SIZE];
+        bytes[..2].copy_from_slice(&2019u16.to_le_bytes());
+        bytes[23..27].copy_from_slice(&90.5f32.to_le_bytes());
+
+        let packet: PacketLapData = bytemuck::pod_read_unaligned(&bytes);
+        let last_lap_time = packet.lap_data[0].last_lap_time;
+
+        assert_eq!(90.5, last_lap_time);
+    }
+}
@@ -3,17 +3,18 @@
 //! The session packets by F1 2018 and F1 2019 differ only in their packet headers, the rest of the
 //! packet format is identical.
 
-use std::io::{Cursor, Error, ErrorKind};
-use std::time::Duration;
-
-use bytes::{Buf, BytesMut};
+use alloc::string::String;
+use core::time::Duration;
 
 use crate::nineteen::flag::decode_flag;
-use crate::nineteen::header::decode_header;
+use crate::nineteen::header::decode_raw_header;
+use crate::nineteen::raw::PacketSessionData;
 use crate::packet::ensure_packet_size;
+use crate::packet::reader::{Decode, Reader};
 use crate::packet::session::{
     Formula, MarshalZone, SafetyCar, Session, SessionPacket, Track, Weather,
 };
+use crate::packet::DecodeError;
 
 /// Size of the session packet in F1 2019
 pub const PACKET_SIZE: usize = 149;
@@ -22,63 +23,48 @@ pub const PACKET_SIZE: usize = 149;
 ///
 /// The session packets by F1 2018 and F1 2019 differ only in their packet headers, the rest of the
 /// packet format is identical.
-pub fn decode_session(cursor: &mut Cursor<&mut BytesMut>) -> Result<SessionPacket, Error> {
+pub fn decode_session(cursor: &mut Reader<'_>) -> Result<SessionPacket, DecodeError> {
     ensure_packet_size(PACKET_SIZE, cursor)?;
 
-    let header = decode_header(cursor)?;
-
-    let weather = decode_weather(cursor)?;
-    let track_temperature = cursor.get_i8();
-    let air_temperature = cursor.get_i8();
-    let total_laps = cursor.get_u8();
-    let track_length = cursor.get_u16_le();
-    let session_type = decode_session_type(cursor)?;
-    let track = decode_track(cursor)?;
-    let formula = decode_formula(cursor)?;
-    let time_left = Duration::from_secs(cursor.get_u16_le() as u64);
-    let duration = Duration::from_secs(cursor.get_u16_le() as u64);
-    let pit_speed_limit = cursor.get_u8();
-    let game_paused = cursor.get_u8() > 0;
-    let is_spectating = cursor.get_u8() > 0;
-    let spectator_car_index = cursor.get_u8();
-    let sli_pro_support = cursor.get_u8() > 0;
+    let packet = PacketSessionData::decode(cursor)?;
+    let header = decode_raw_header(&packet.header)?;
 
-    let marshal_zone_count = cursor.get_u8();
-    let mut marshal_zones = Vec::with_capacity(marshal_zone_count as usize);
-
-    for _ in 0..marshal_zone_count {
-        marshal_zones.push(MarshalZone::new(cursor.get_f32_le(), decode_flag(cursor)?));
-    }
-
-    let safety_car = decode_safety_car(cursor)?;
-    let network_session = cursor.get_u8() > 0;
+    let marshal_zones = { packet.marshal_zones }
+        .iter()
+        .take(packet.num_marshal_zones as usize)
+        .map(|zone| {
+            Ok(MarshalZone::new(
+                zone.zone_start,
+                decode_flag(zone.zone_flag)?,
+            ))
+        })
+        .collect::<Result<_, DecodeError>>()?;
 
     Ok(SessionPacket::new(
         header,
-        weather,
-        track_temperature,
-        air_temperature,
-        total_laps,
-        track_length,
-        session_type,
-        track,
-        formula,
-        time_left,
-        duration,
-        pit_speed_limit,
-        game_paused,
-        is_spectating,
-        spectator_car_index,
-        sli_pro_support,
+        decode_weather(packet.weather)?,
+        packet.track_temperature,
+        packet.air_temperature,
+        packet.total_laps,
+        packet.track_length,
+        decode_session_type(packet.session_type)?,
+        decode_track(packet.track_id)?,
+        decode_formula(packet.formula)?,
+        Duration::from_secs(packet.session_time_left as u64),
+        Duration::from_secs(packet.session_duration as u64),
+        packet.pit_speed_limit,
+        packet.game_paused > 0,
+        packet.is_spectating > 0,
+        packet.spectator_car_index,
+        packet.sli_pro_native_support > 0,
         marshal_zones,
-        safety_car,
-        network_session,
+        decode_safety_car(packet.safety_car_status)?,
+        packet.network_game > 0,
+        None,
     ))
 }
 
-fn decode_weather(cursor: &mut Cursor<&mut BytesMut>) -> Result<Weather, Error> {
-    let value = cursor.get_u8();
-
+fn decode_weather(value: u8) -> Result<Weather, DecodeError> {
     match value {
         0 => Ok(Weather::Clear),
         1 => Ok(Weather::LightCloud),
@@ -86,16 +72,13 @@ fn decode_weather(cursor: &mut Cursor<&mut BytesMut>) -> Result<Weather, Error>
         3 => Ok(Weather::LightRain),
         4 => Ok(Weather::HeavyRain),
         5 => Ok(Weather::Storm),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode weather.",
-        )),
+        ))),
     }
 }
 
-fn decode_session_type(cursor: &mut Cursor<&mut BytesMut>) -> Result<Session, Error> {
-    let value = cursor.get_u8();
-
+fn decode_session_type(value: u8) -> Result<Session, DecodeError> {
     match value {
         0 => Ok(Session::Unknown),
         1 => Ok(Session::P1),
@@ -110,86 +93,43 @@ fn decode_session_type(cursor: &mut Cursor<&mut BytesMut>) -> Result<Session, Er
         10 => Ok(Session::Race),
         11 => Ok(Session::Race2),
         12 => Ok(Session::TimeTrial),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode session.",
-        )),
+        ))),
     }
 }
 
-fn decode_track(cursor: &mut Cursor<&mut BytesMut>) -> Result<Track, Error> {
-    let value = cursor.get_i8();
-
-    match value {
-        -1 => Ok(Track::Unknown),
-        0 => Ok(Track::Melbourne),
-        1 => Ok(Track::PaulRicard),
-        2 => Ok(Track::Shanghai),
-        3 => Ok(Track::Bahrain),
-        4 => Ok(Track::Catalunya),
-        5 => Ok(Track::Monaco),
-        6 => Ok(Track::Montreal),
-        7 => Ok(Track::Silverstone),
-        8 => Ok(Track::Hockenheim),
-        9 => Ok(Track::Hungaroring),
-        10 => Ok(Track::Spa),
-        11 => Ok(Track::Monza),
-        12 => Ok(Track::Singapore),
-        13 => Ok(Track::Suzuka),
-        14 => Ok(Track::AbuDhabi),
-        15 => Ok(Track::Texas),
-        16 => Ok(Track::Brazil),
-        17 => Ok(Track::Austria),
-        18 => Ok(Track::Sochi),
-        19 => Ok(Track::Mexico),
-        20 => Ok(Track::Azerbaijan),
-        21 => Ok(Track::BahrainShort),
-        22 => Ok(Track::SilverstoneShort),
-        23 => Ok(Track::TexasShort),
-        24 => Ok(Track::SuzukaShort),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
-            "Failed to decode track.",
-        )),
-    }
-}
-
-fn decode_formula(cursor: &mut Cursor<&mut BytesMut>) -> Result<Formula, Error> {
-    let value = cursor.get_u8();
+include!(concat!(env!("OUT_DIR"), "/nineteen/decode_track.rs"));
 
+fn decode_formula(value: u8) -> Result<Formula, DecodeError> {
     match value {
         0 => Ok(Formula::ModernF1),
         1 => Ok(Formula::ClassicF1),
         2 => Ok(Formula::F2),
         3 => Ok(Formula::GenericF1),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode formula.",
-        )),
+        ))),
     }
 }
 
-fn decode_safety_car(cursor: &mut Cursor<&mut BytesMut>) -> Result<SafetyCar, Error> {
-    let value = cursor.get_u8();
-
+fn decode_safety_car(value: u8) -> Result<SafetyCar, DecodeError> {
     match value {
         0 => Ok(SafetyCar::None),
         1 => Ok(SafetyCar::Full),
         2 => Ok(SafetyCar::Virtual),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode safety car.",
-        )),
+        ))),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
-
     use bytes::{BufMut, BytesMut};
 
     use crate::nineteen::session::{decode_session, PACKET_SIZE};
+    use crate::packet::reader::Reader;
     use crate::packet::session::{Formula, SafetyCar, Session, Track, Weather};
 
     fn put_packet_header(mut bytes: BytesMut) -> BytesMut {
@@ -198,9 +138,9 @@ mod tests {
         bytes.put_u8(2);
         bytes.put_u8(3);
         bytes.put_u8(1);
-        bytes.put_u64_le(u64::max_value());
+        bytes.put_u64_le(u64::MAX);
         bytes.put_f32_le(1.0);
-        bytes.put_u32_le(u32::max_value());
+        bytes.put_u32_le(u32::MAX);
         bytes.put_u8(0);
 
         bytes
@@ -208,8 +148,8 @@ mod tests {
 
     #[test]
     fn decode_session_with_error() {
-        let mut bytes = BytesMut::with_capacity(0);
-        let mut cursor = Cursor::new(&mut bytes);
+        let bytes = BytesMut::with_capacity(0);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_session(&mut cursor);
         assert!(packet.is_err());
@@ -245,7 +185,7 @@ mod tests {
         bytes.put_u8(1);
         bytes.put_u8(1);
 
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_session(&mut cursor).unwrap();
 
@@ -3,13 +3,12 @@
 //! The car setup packets by F1 2018 and F1 2019 differ only in their packet headers, the rest of
 //! the packet format is identical.
 
-use std::io::{Cursor, Error};
-
-use bytes::{Buf, BytesMut};
-
-use crate::nineteen::header::decode_header;
+use crate::nineteen::header::decode_raw_header;
+use crate::nineteen::raw::{CarSetupData, PacketCarSetupData};
 use crate::packet::ensure_packet_size;
+use crate::packet::reader::{Decode, Reader};
 use crate::packet::setup::{CarSetup, CarSetupPacket};
+use crate::packet::DecodeError;
 
 /// Size of the car setups packet in bytes
 pub const PACKET_SIZE: usize = 843;
@@ -18,48 +17,48 @@ pub const PACKET_SIZE: usize = 843;
 ///
 /// F1 2018 and F1 2019 publish the same data in their car setup packets, but with different packet
 /// headers. In multiplayer sessions, the setups of other players are redacted and appear empty.
-pub fn decode_setups(cursor: &mut Cursor<&mut BytesMut>) -> Result<CarSetupPacket, Error> {
+pub fn decode_setups(cursor: &mut Reader<'_>) -> Result<CarSetupPacket, DecodeError> {
     ensure_packet_size(PACKET_SIZE, cursor)?;
 
-    let header = decode_header(cursor)?;
-    let mut setups = Vec::with_capacity(20);
-
-    for _ in 0..20 {
-        setups.push(CarSetup::new(
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_u8(),
-            cursor.get_f32_le(),
-        ))
-    }
+    let packet = PacketCarSetupData::decode(cursor)?;
+    let header = decode_raw_header(&packet.header)?;
+    let setups = { packet.car_setups }.iter().map(decode_setup).collect();
 
     Ok(CarSetupPacket::new(header, setups))
 }
 
+fn decode_setup(setup: &CarSetupData) -> CarSetup {
+    CarSetup::new(
+        setup.front_wing,
+        setup.rear_wing,
+        setup.on_throttle,
+        setup.off_throttle,
+        setup.front_camber,
+        setup.rear_camber,
+        setup.front_toe,
+        setup.rear_toe,
+        setup.front_suspension,
+        setup.rear_suspension,
+        setup.front_anti_roll_bar,
+        setup.rear_anti_roll_bar,
+        setup.front_suspension_height,
+        setup.rear_suspension_height,
+        setup.brake_pressure,
+        setup.brake_bias,
+        setup.front_tyre_pressure,
+        setup.rear_tyre_pressure,
+        setup.ballast,
+        setup.fuel_load,
+    )
+}
+
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
-
     use assert_approx_eq::assert_approx_eq;
     use bytes::{BufMut, BytesMut};
 
     use crate::nineteen::setup::{decode_setups, PACKET_SIZE};
+    use crate::packet::reader::Reader;
 
     fn put_packet_header(mut bytes: BytesMut) -> BytesMut {
         bytes.put_u16_le(2019);
@@ -67,9 +66,9 @@ mod tests {
         bytes.put_u8(2);
         bytes.put_u8(3);
         bytes.put_u8(5);
-        bytes.put_u64_le(u64::max_value());
+        bytes.put_u64_le(u64::MAX);
         bytes.put_f32_le(1.0);
-        bytes.put_u32_le(u32::max_value());
+        bytes.put_u32_le(u32::MAX);
         bytes.put_u8(0);
 
         bytes
@@ -77,8 +76,8 @@ mod tests {
 
     #[test]
     fn decode_setups_with_error() {
-        let mut bytes = BytesMut::with_capacity(0);
-        let mut cursor = Cursor::new(&mut bytes);
+        let bytes = BytesMut::with_capacity(0);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_setups(&mut cursor);
         assert!(packet.is_err());
@@ -113,7 +112,7 @@ mod tests {
         let padding = vec![0u8; 779];
         bytes.put(padding.as_slice());
 
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_setups(&mut cursor).unwrap();
         let setup = packet.setups()[0];
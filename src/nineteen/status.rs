@@ -4,18 +4,20 @@
 //! visual tyre compound (e.g. hard). This makes it packet format and decoder incompatible with
 //! earlier F1 games.
 
-use std::io::{Cursor, Error, ErrorKind};
-
-use bytes::{Buf, BytesMut};
+use alloc::string::String;
+use alloc::vec::Vec;
 
+use crate::nineteen::decode_corners;
 use crate::nineteen::flag::decode_flag;
-use crate::nineteen::header::decode_header;
+use crate::nineteen::header::decode_raw_header;
+use crate::nineteen::raw::{CarStatusData, PacketCarStatusData};
 use crate::packet::ensure_packet_size;
+use crate::packet::reader::{Decode, Reader};
 use crate::packet::status::{
     CarStatus, CarStatusPacket, DrsSetting, ErsDeployMode, FuelMix, PhysicalTyreCompound,
     TractionControl, VisualTyreCompound,
 };
-use crate::types::CornerProperty;
+use crate::packet::DecodeError;
 
 /// Size of the car status packet in bytes
 pub const PACKET_SIZE: usize = 1143;
@@ -24,104 +26,87 @@ pub const PACKET_SIZE: usize = 1143;
 ///
 /// The car status packet by F1 2019 introduces the differentiation between a physical and a visual
 /// tyre compound.
-pub fn decode_statuses(cursor: &mut Cursor<&mut BytesMut>) -> Result<CarStatusPacket, Error> {
+pub fn decode_statuses(cursor: &mut Reader<'_>) -> Result<CarStatusPacket, DecodeError> {
     ensure_packet_size(PACKET_SIZE, cursor)?;
 
-    let header = decode_header(cursor)?;
-    let mut car_status = Vec::with_capacity(20);
+    let packet = PacketCarStatusData::decode(cursor)?;
+    let header = decode_raw_header(&packet.header)?;
 
-    for _ in 0..20 {
-        car_status.push(CarStatus::new(
-            decode_traction_control(cursor)?,
-            cursor.get_u8() > 0,
-            decode_fuel_mix(cursor)?,
-            cursor.get_u8(),
-            cursor.get_u8() > 0,
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_u16_le(),
-            cursor.get_u16_le(),
-            cursor.get_u8(),
-            decode_drs(cursor)?,
-            decode_tyre_wear(cursor),
-            decode_physical_tyre_compound(cursor)?,
-            decode_visual_tyre_compound(cursor)?,
-            decode_tyre_damage(cursor),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            cursor.get_u8(),
-            decode_flag(cursor)?,
-            cursor.get_f32_le(),
-            decode_ers_deploy_mode(cursor)?,
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-        ));
-    }
+    let car_status = { packet.car_status_data }
+        .iter()
+        .map(decode_status)
+        .collect::<Result<Vec<CarStatus>, DecodeError>>()?;
 
-    Ok(CarStatusPacket::new(header, car_status))
+    Ok(CarStatusPacket::new(header, car_status.into()))
 }
 
-fn decode_traction_control(cursor: &mut Cursor<&mut BytesMut>) -> Result<TractionControl, Error> {
-    let value = cursor.get_u8();
+fn decode_status(status: &CarStatusData) -> Result<CarStatus, DecodeError> {
+    Ok(CarStatus::new(
+        decode_traction_control(status.traction_control)?,
+        status.anti_lock_brakes > 0,
+        decode_fuel_mix(status.fuel_mix)?,
+        status.front_brake_bias,
+        status.pit_limiter_status > 0,
+        status.fuel_in_tank,
+        status.fuel_capacity,
+        status.fuel_remaining_laps,
+        status.max_rpm,
+        status.idle_rpm,
+        status.max_gears,
+        decode_drs(status.drs_allowed)?,
+        decode_corners(status.tyres_wear),
+        decode_physical_tyre_compound(status.actual_tyre_compound)?,
+        decode_visual_tyre_compound(status.visual_tyre_compound)?,
+        decode_corners(status.tyres_damage),
+        status.front_left_wing_damage,
+        status.front_right_wing_damage,
+        status.rear_wing_damage,
+        status.engine_damage,
+        status.gear_box_damage,
+        decode_flag(status.vehicle_fia_flags)?,
+        status.ers_store_energy,
+        decode_ers_deploy_mode(status.ers_deploy_mode)?,
+        status.ers_harvested_this_lap_mguk,
+        status.ers_harvested_this_lap_mguh,
+        status.ers_deployed_this_lap,
+    ))
+}
 
+fn decode_traction_control(value: u8) -> Result<TractionControl, DecodeError> {
     match value {
         0 => Ok(TractionControl::Off),
         1 => Ok(TractionControl::Low),
         2 => Ok(TractionControl::High),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode transaction control.",
-        )),
+        ))),
     }
 }
 
-fn decode_fuel_mix(cursor: &mut Cursor<&mut BytesMut>) -> Result<FuelMix, Error> {
-    let value = cursor.get_u8();
-
+fn decode_fuel_mix(value: u8) -> Result<FuelMix, DecodeError> {
     match value {
         0 => Ok(FuelMix::Lean),
         1 => Ok(FuelMix::Standard),
         2 => Ok(FuelMix::Rich),
         3 => Ok(FuelMix::Max),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode fuel mix.",
-        )),
+        ))),
     }
 }
 
-fn decode_drs(cursor: &mut Cursor<&mut BytesMut>) -> Result<DrsSetting, Error> {
-    let value = cursor.get_i8();
-
+fn decode_drs(value: i8) -> Result<DrsSetting, DecodeError> {
     match value {
         -1 => Ok(DrsSetting::Unknown),
         0 => Ok(DrsSetting::NotAllowed),
         1 => Ok(DrsSetting::Allowed),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode DRS status.",
-        )),
+        ))),
     }
 }
 
-fn decode_tyre_wear(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<u8> {
-    CornerProperty::new(
-        cursor.get_u8(),
-        cursor.get_u8(),
-        cursor.get_u8(),
-        cursor.get_u8(),
-    )
-}
-
-fn decode_physical_tyre_compound(
-    cursor: &mut Cursor<&mut BytesMut>,
-) -> Result<PhysicalTyreCompound, Error> {
-    let value = cursor.get_u8();
-
+fn decode_physical_tyre_compound(value: u8) -> Result<PhysicalTyreCompound, DecodeError> {
     match value {
         7 => Ok(PhysicalTyreCompound::F1Intermediate),
         8 => Ok(PhysicalTyreCompound::F1Wet),
@@ -137,18 +122,13 @@ fn decode_physical_tyre_compound(
         18 => Ok(PhysicalTyreCompound::F1C3),
         19 => Ok(PhysicalTyreCompound::F1C2),
         20 => Ok(PhysicalTyreCompound::F1C1),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode physical tyre compound.",
-        )),
+        ))),
     }
 }
 
-fn decode_visual_tyre_compound(
-    cursor: &mut Cursor<&mut BytesMut>,
-) -> Result<VisualTyreCompound, Error> {
-    let value = cursor.get_u8();
-
+fn decode_visual_tyre_compound(value: u8) -> Result<VisualTyreCompound, DecodeError> {
     match value {
         7 => Ok(VisualTyreCompound::F1Intermediate),
         8 => Ok(VisualTyreCompound::F1Wet),
@@ -162,25 +142,13 @@ fn decode_visual_tyre_compound(
         16 => Ok(VisualTyreCompound::F1Soft),
         17 => Ok(VisualTyreCompound::F1Medium),
         18 => Ok(VisualTyreCompound::F1Hard),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode visual tyre compound.",
-        )),
+        ))),
     }
 }
 
-fn decode_tyre_damage(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<u8> {
-    CornerProperty::new(
-        cursor.get_u8(),
-        cursor.get_u8(),
-        cursor.get_u8(),
-        cursor.get_u8(),
-    )
-}
-
-fn decode_ers_deploy_mode(cursor: &mut Cursor<&mut BytesMut>) -> Result<ErsDeployMode, Error> {
-    let value = cursor.get_u8();
-
+fn decode_ers_deploy_mode(value: u8) -> Result<ErsDeployMode, DecodeError> {
     match value {
         0 => Ok(ErsDeployMode::None),
         1 => Ok(ErsDeployMode::Low),
@@ -188,21 +156,19 @@ fn decode_ers_deploy_mode(cursor: &mut Cursor<&mut BytesMut>) -> Result<ErsDeplo
         3 => Ok(ErsDeployMode::High),
         4 => Ok(ErsDeployMode::Overtake),
         5 => Ok(ErsDeployMode::Hotlap),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode ERS deployment mode.",
-        )),
+        ))),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
-
     use assert_approx_eq::assert_approx_eq;
     use bytes::{BufMut, BytesMut};
 
     use crate::nineteen::status::{decode_statuses, PACKET_SIZE};
+    use crate::packet::reader::Reader;
     use crate::packet::status::{
         DrsSetting, ErsDeployMode, FuelMix, PhysicalTyreCompound, TractionControl,
         VisualTyreCompound,
@@ -215,9 +181,9 @@ mod tests {
         bytes.put_u8(2);
         bytes.put_u8(3);
         bytes.put_u8(7);
-        bytes.put_u64_le(u64::max_value());
+        bytes.put_u64_le(u64::MAX);
         bytes.put_f32_le(1.0);
-        bytes.put_u32_le(u32::max_value());
+        bytes.put_u32_le(u32::MAX);
         bytes.put_u8(0);
 
         bytes
@@ -225,8 +191,8 @@ mod tests {
 
     #[test]
     fn decode_statuses_with_error() {
-        let mut bytes = BytesMut::with_capacity(0);
-        let mut cursor = Cursor::new(&mut bytes);
+        let bytes = BytesMut::with_capacity(0);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_statuses(&mut cursor);
         assert!(packet.is_err());
@@ -274,7 +240,7 @@ mod tests {
             bytes.put_f32_le(33.0);
         }
 
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_statuses(&mut cursor).unwrap();
         let status = packet.statuses()[0];
@@ -3,13 +3,16 @@
 //! The telemetry packets by F1 2018 and F1 2019 differ only in their packet headers, the rest of
 //! the packet format is identical.
 
-use std::io::{Cursor, Error, ErrorKind};
+use alloc::string::String;
+use alloc::vec::Vec;
 
-use bytes::{Buf, BytesMut};
-
-use crate::nineteen::header::decode_header;
+use crate::nineteen::decode_corners;
+use crate::nineteen::header::decode_raw_header;
+use crate::nineteen::raw::{CarTelemetryData, PacketCarTelemetryData};
 use crate::packet::ensure_packet_size;
+use crate::packet::reader::{Decode, Reader};
 use crate::packet::telemetry::{Button, Gear, Surface, Telemetry, TelemetryPacket};
+use crate::packet::DecodeError;
 use crate::types::CornerProperty;
 
 /// Size of the telemetry packet in bytes
@@ -19,43 +22,45 @@ pub const PACKET_SIZE: usize = 1347;
 ///
 /// F1 2018 and F1 2019 publish the same data in their telemetry packets, but with different packet
 /// headers.
-pub fn decode_telemetry(cursor: &mut Cursor<&mut BytesMut>) -> Result<TelemetryPacket, Error> {
+pub fn decode_telemetry(cursor: &mut Reader<'_>) -> Result<TelemetryPacket, DecodeError> {
     ensure_packet_size(PACKET_SIZE, cursor)?;
 
-    let header = decode_header(cursor)?;
-    let mut telemetry = Vec::with_capacity(20);
-
-    for _ in 0..20 {
-        telemetry.push(Telemetry::new(
-            cursor.get_u16_le(),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_f32_le(),
-            cursor.get_u8(),
-            decode_gear(cursor)?,
-            cursor.get_u16_le(),
-            cursor.get_u8() > 0,
-            cursor.get_u8(),
-            decode_brake_temperature(cursor),
-            decode_tyre_surface_temperature(cursor),
-            decode_tyre_inner_temperature(cursor),
-            cursor.get_u16_le(),
-            decode_tyre_pressure(cursor),
-            decode_surface_type(cursor)?,
-        ));
-    }
+    let packet = PacketCarTelemetryData::decode(cursor)?;
+    let header = decode_raw_header(&packet.header)?;
 
-    let button_status = match Button::from_bits(cursor.get_u32_le()) {
-        Some(button) => button,
-        None => Button::NONE,
-    };
+    let telemetry = { packet.car_telemetry_data }
+        .iter()
+        .map(decode_car_telemetry)
+        .collect::<Result<Vec<Telemetry>, DecodeError>>()?;
 
-    Ok(TelemetryPacket::new(header, telemetry, button_status))
+    Ok(TelemetryPacket::new(
+        header,
+        telemetry.into(),
+        Button::from_bits_truncate(packet.button_status),
+    ))
 }
 
-fn decode_gear(cursor: &mut Cursor<&mut BytesMut>) -> Result<Gear, Error> {
-    let value = cursor.get_i8();
+fn decode_car_telemetry(telemetry: &CarTelemetryData) -> Result<Telemetry, DecodeError> {
+    Ok(Telemetry::new(
+        telemetry.speed,
+        telemetry.throttle,
+        telemetry.steer,
+        telemetry.brake,
+        telemetry.clutch,
+        decode_gear(telemetry.gear)?,
+        telemetry.engine_rpm,
+        telemetry.drs > 0,
+        telemetry.rev_lights_percent,
+        decode_corners(telemetry.brakes_temperature),
+        decode_corners(telemetry.tyres_surface_temperature),
+        decode_corners(telemetry.tyres_inner_temperature),
+        telemetry.engine_temperature,
+        decode_corners(telemetry.tyres_pressure),
+        decode_surface_type(telemetry.surface_type)?,
+    ))
+}
 
+fn decode_gear(value: i8) -> Result<Gear, DecodeError> {
     match value {
         -1 => Ok(Gear::Reverse),
         0 => Ok(Gear::Neutral),
@@ -67,60 +72,22 @@ fn decode_gear(cursor: &mut Cursor<&mut BytesMut>) -> Result<Gear, Error> {
         6 => Ok(Gear::Sixth),
         7 => Ok(Gear::Seventh),
         8 => Ok(Gear::Eighth),
-        _ => Err(Error::new(ErrorKind::InvalidData, "Failed to decode gear.")),
+        _ => Err(DecodeError::InvalidData(String::from(
+            "Failed to decode gear.",
+        ))),
     }
 }
 
-fn decode_brake_temperature(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<u16> {
-    CornerProperty::new(
-        cursor.get_u16_le(),
-        cursor.get_u16_le(),
-        cursor.get_u16_le(),
-        cursor.get_u16_le(),
-    )
-}
-
-fn decode_tyre_surface_temperature(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<u16> {
-    CornerProperty::new(
-        cursor.get_u16_le(),
-        cursor.get_u16_le(),
-        cursor.get_u16_le(),
-        cursor.get_u16_le(),
-    )
-}
-
-fn decode_tyre_inner_temperature(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<u16> {
-    CornerProperty::new(
-        cursor.get_u16_le(),
-        cursor.get_u16_le(),
-        cursor.get_u16_le(),
-        cursor.get_u16_le(),
-    )
-}
-
-fn decode_tyre_pressure(cursor: &mut Cursor<&mut BytesMut>) -> CornerProperty<f32> {
-    CornerProperty::new(
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-        cursor.get_f32_le(),
-    )
-}
-
-fn decode_surface_type(
-    cursor: &mut Cursor<&mut BytesMut>,
-) -> Result<CornerProperty<Surface>, Error> {
+fn decode_surface_type(surfaces: [u8; 4]) -> Result<CornerProperty<Surface>, DecodeError> {
     Ok(CornerProperty::new(
-        decode_surface(cursor)?,
-        decode_surface(cursor)?,
-        decode_surface(cursor)?,
-        decode_surface(cursor)?,
+        decode_surface(surfaces[0])?,
+        decode_surface(surfaces[1])?,
+        decode_surface(surfaces[2])?,
+        decode_surface(surfaces[3])?,
     ))
 }
 
-fn decode_surface(cursor: &mut Cursor<&mut BytesMut>) -> Result<Surface, Error> {
-    let value = cursor.get_u8();
-
+fn decode_surface(value: u8) -> Result<Surface, DecodeError> {
     match value {
         0 => Ok(Surface::Tarmac),
         1 => Ok(Surface::RumbleStrip),
@@ -134,21 +101,19 @@ fn decode_surface(cursor: &mut Cursor<&mut BytesMut>) -> Result<Surface, Error>
         9 => Ok(Surface::Cobblestone),
         10 => Ok(Surface::Metal),
         11 => Ok(Surface::Ridged),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
+        _ => Err(DecodeError::InvalidData(String::from(
             "Failed to decode surface.",
-        )),
+        ))),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
-
     use assert_approx_eq::assert_approx_eq;
     use bytes::{BufMut, BytesMut};
 
     use crate::nineteen::telemetry::{decode_telemetry, PACKET_SIZE};
+    use crate::packet::reader::Reader;
     use crate::packet::telemetry::{Button, Gear, Surface};
 
     fn put_packet_header(mut bytes: BytesMut) -> BytesMut {
@@ -157,9 +122,9 @@ mod tests {
         bytes.put_u8(2);
         bytes.put_u8(3);
         bytes.put_u8(0);
-        bytes.put_u64_le(u64::max_value());
+        bytes.put_u64_le(u64::MAX);
         bytes.put_f32_le(1.0);
-        bytes.put_u32_le(u32::max_value());
+        bytes.put_u32_le(u32::MAX);
         bytes.put_u8(0);
 
         bytes
@@ -167,18 +132,14 @@ mod tests {
 
     #[test]
     fn decode_telemetry_with_error() {
-        let mut bytes = BytesMut::with_capacity(0);
-        let mut cursor = Cursor::new(&mut bytes);
+        let bytes = BytesMut::with_capacity(0);
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_telemetry(&mut cursor);
         assert!(packet.is_err());
     }
 
-    #[test]
-    fn decode_telemetry_with_success() {
-        let mut bytes = BytesMut::with_capacity(PACKET_SIZE);
-        bytes = put_packet_header(bytes);
-
+    fn put_telemetry(mut bytes: BytesMut, buttons: u32) -> BytesMut {
         for _ in 0..20 {
             bytes.put_u16_le(1);
             bytes.put_f32_le(2.0);
@@ -212,9 +173,17 @@ mod tests {
             bytes.put_u8(8);
         }
 
-        bytes.put_u32_le(0x0001);
+        bytes.put_u32_le(buttons);
+
+        bytes
+    }
 
-        let mut cursor = Cursor::new(&mut bytes);
+    #[test]
+    fn decode_telemetry_with_success() {
+        let bytes = BytesMut::with_capacity(PACKET_SIZE);
+        let bytes = put_telemetry(put_packet_header(bytes), 0x0001);
+
+        let mut cursor = Reader::new(&bytes);
 
         let packet = decode_telemetry(&mut cursor).unwrap();
         let telemetry = packet.telemetry()[0];
@@ -236,4 +205,18 @@ mod tests {
         assert_eq!(Surface::Mud, telemetry.surface_type().front_left());
         assert_eq!(Button::CROSS_OR_A, packet.button_status());
     }
+
+    #[test]
+    fn decode_telemetry_with_udp_actions() {
+        let bytes = BytesMut::with_capacity(PACKET_SIZE);
+        let bytes = put_telemetry(put_packet_header(bytes), 0x8000_0001);
+
+        let mut cursor = Reader::new(&bytes);
+        let packet = decode_telemetry(&mut cursor).unwrap();
+
+        assert_eq!(
+            Button::CROSS_OR_A | Button::UDP_ACTION_12,
+            packet.button_status()
+        );
+    }
 }
@@ -0,0 +1,9 @@
+//! Signals for hardware that is driven by the game
+//!
+//! Sim racing rigs are often extended with hardware that reacts to the car, for example tactile
+//! transducers that are mounted to the seat or the pedals. The modules in this namespace turn the
+//! packets of the player's car into normalized signals that such devices can consume, and define
+//! the interfaces that the backends of the devices implement.
+
+pub mod haptics;
+pub mod shift;
@@ -0,0 +1,293 @@
+//! Haptic cues from wheel slip and the surface under the tyres
+//!
+//! Tactile transducers let drivers feel what the car is doing: the tyres spinning on exit, the
+//! wheels locking under braking, or the car running over a kerb. The `HapticsGenerator` derives
+//! these cues from the motion and telemetry packets of the player's car, and emits a frame with one
+//! vibration channel per cue at a fixed rate. Each channel is normalized between 0 and 1, so that
+//! a backend only has to map it to the amplitude of its device.
+//!
+//! The game sends motion and telemetry packets at the rate that is configured in its settings. A
+//! frame is emitted whenever at least the configured interval has passed in the session since the
+//! previous frame, using the latest packet of each type.
+
+use std::io::Error;
+use std::time::Duration;
+
+use derive_new::new;
+use getset::CopyGetters;
+
+use crate::packet::telemetry::{Surface, Telemetry};
+use crate::packet::Packet;
+use crate::types::CornerProperty;
+
+/// Interval between frames when no rate is configured, which is 60 Hz
+const DEFAULT_INTERVAL: Duration = Duration::from_micros(16_667);
+
+/// Wheel slip below which the tyres are considered to grip
+const SLIP_THRESHOLD: f32 = 0.1;
+
+/// Wheel slip at which the tyres are considered to have no grip at all
+const FULL_SLIP: f32 = 1.0;
+
+/// Brake input above which negative wheel slip is considered a lockup
+const BRAKE_THRESHOLD: f32 = 0.05;
+
+/// Speed in km/h at which the vibration of a surface reaches its full strength
+const FULL_SURFACE_SPEED: f32 = 100.0;
+
+/// Vibration of each channel of a haptic device, between 0 and 1
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct HapticFrame {
+    /// Returns the session time at which the frame has been generated.
+    #[getset(get_copy = "pub")]
+    session_time: Duration,
+
+    /// Returns the vibration for wheels that spin faster than the car is moving.
+    #[getset(get_copy = "pub")]
+    wheel_spin: f32,
+
+    /// Returns the vibration for wheels that lock up under braking.
+    #[getset(get_copy = "pub")]
+    lockup: f32,
+
+    /// Returns the vibration for kerbs and surfaces other than tarmac.
+    #[getset(get_copy = "pub")]
+    surface: f32,
+}
+
+/// Backend of a haptic device
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Error;
+///
+/// use f1_api::output::haptics::{HapticDevice, HapticFrame};
+///
+/// struct Console;
+///
+/// impl HapticDevice for Console {
+///     fn apply(&mut self, frame: &HapticFrame) -> Result<(), Error> {
+///         println!("Lockup: {:.2}", frame.lockup());
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait HapticDevice {
+    /// Set the vibration of the device's channels to the values of the frame.
+    fn apply(&mut self, frame: &HapticFrame) -> Result<(), Error>;
+
+    /// Stop all vibration, e.g. when the session has ended.
+    fn stop(&mut self) -> Result<(), Error> {
+        self.apply(&HapticFrame::default())
+    }
+}
+
+/// Generator of haptic frames for the player's car
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use f1_api::output::haptics::HapticsGenerator;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut generator = HapticsGenerator::new(Duration::from_millis(10));
+///
+/// for packet in packets() {
+///     if let Some(frame) = generator.update(&packet) {
+///         println!("Kerb: {:.2}", frame.surface());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct HapticsGenerator {
+    interval: Duration,
+    session_uid: Option<u64>,
+    wheel_slip: Option<CornerProperty<f32>>,
+    telemetry: Option<Telemetry>,
+    last_frame: Option<Duration>,
+}
+
+impl HapticsGenerator {
+    /// Create a generator that emits a frame at most once per interval.
+    pub fn new(interval: Duration) -> Self {
+        HapticsGenerator {
+            interval,
+            session_uid: None,
+            wheel_slip: None,
+            telemetry: None,
+            last_frame: None,
+        }
+    }
+
+    /// Record the player's car in a motion or telemetry packet, and return a frame if the interval
+    /// has passed since the previous frame.
+    ///
+    /// Frames are only generated once both a motion and a telemetry packet have been received.
+    pub fn update(&mut self, packet: &Packet) -> Option<HapticFrame> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            *self = HapticsGenerator::new(self.interval);
+            self.session_uid = Some(session_uid);
+        }
+
+        match packet {
+            Packet::Motion(packet) => self.wheel_slip = Some(*packet.wheel_slip()),
+            Packet::Telemetry(packet) => self.telemetry = packet.player().copied(),
+            _ => return None,
+        }
+
+        let time = *packet.header().session_time();
+        if let Some(last_frame) = self.last_frame {
+            if time >= last_frame && time - last_frame < self.interval {
+                return None;
+            }
+        }
+
+        let frame = frame(time, self.wheel_slip.as_ref()?, self.telemetry.as_ref()?);
+        self.last_frame = Some(time);
+
+        Some(frame)
+    }
+
+    /// Update the generator with the packet, and apply the frame to the device if one is due.
+    pub fn drive<D: HapticDevice>(&mut self, packet: &Packet, device: &mut D) -> Result<(), Error> {
+        match self.update(packet) {
+            Some(frame) => device.apply(&frame),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for HapticsGenerator {
+    fn default() -> Self {
+        HapticsGenerator::new(DEFAULT_INTERVAL)
+    }
+}
+
+/// Compute the frame for the latest wheel slip and telemetry of the player's car.
+fn frame(time: Duration, wheel_slip: &CornerProperty<f32>, telemetry: &Telemetry) -> HapticFrame {
+    let slip = corners(wheel_slip);
+
+    let wheel_spin = slip.iter().copied().fold(0.0, f32::max);
+    let lockup = if telemetry.brake() > BRAKE_THRESHOLD {
+        slip.iter().map(|slip| -slip).fold(0.0, f32::max)
+    } else {
+        0.0
+    };
+
+    let surface = corners(telemetry.surface_type())
+        .iter()
+        .copied()
+        .map(surface_intensity)
+        .fold(0.0, f32::max);
+    let speed = (f32::from(telemetry.speed()) / FULL_SURFACE_SPEED).min(1.0);
+
+    HapticFrame::new(
+        time,
+        normalize_slip(wheel_spin),
+        normalize_slip(lockup),
+        surface * speed,
+    )
+}
+
+/// Scale the slip between the threshold and full slip to a value between 0 and 1.
+fn normalize_slip(slip: f32) -> f32 {
+    ((slip - SLIP_THRESHOLD) / (FULL_SLIP - SLIP_THRESHOLD)).clamp(0.0, 1.0)
+}
+
+/// Returns how strongly a surface vibrates, between 0 and 1.
+fn surface_intensity(surface: Surface) -> f32 {
+    match surface {
+        Surface::Tarmac => 0.0,
+        Surface::Concrete => 0.1,
+        Surface::Metal | Surface::Water => 0.2,
+        Surface::Grass | Surface::Mud => 0.3,
+        Surface::Sand => 0.4,
+        Surface::Gravel | Surface::Rock => 0.5,
+        Surface::Cobblestone => 0.6,
+        Surface::Ridged => 0.8,
+        Surface::RumbleStrip => 1.0,
+    }
+}
+
+fn corners<T: Copy>(property: &CornerProperty<T>) -> [T; 4] {
+    [
+        property.front_left(),
+        property.front_right(),
+        property.rear_left(),
+        property.rear_right(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::fixtures::{header, motion_packet, telemetry, telemetry_packet, with_corners};
+    use crate::output::haptics::{frame, HapticsGenerator};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::{Gear, Surface, Telemetry};
+    use crate::types::CornerProperty;
+
+    fn on_surface(speed: u16, brake: f32, front_left: Surface) -> Telemetry {
+        let surface = CornerProperty::new(
+            front_left,
+            Surface::Tarmac,
+            Surface::Tarmac,
+            Surface::Tarmac,
+        );
+
+        with_corners(
+            telemetry(speed, 0.0, 0.0, brake, Gear::Third, 8000),
+            CornerProperty::default(),
+            CornerProperty::default(),
+            surface,
+        )
+    }
+
+    #[test]
+    fn frame_of_lockup_on_a_kerb() {
+        let wheel_slip = CornerProperty::new(-0.55, -0.1, 0.0, 0.0);
+        let telemetry = on_surface(50, 1.0, Surface::RumbleStrip);
+
+        let frame = frame(Duration::default(), &wheel_slip, &telemetry);
+
+        assert_approx_eq!(0.0, frame.wheel_spin());
+        assert_approx_eq!(0.5, frame.lockup());
+        assert_approx_eq!(0.5, frame.surface());
+    }
+
+    #[test]
+    fn update_limits_the_rate_of_frames() {
+        let mut generator = HapticsGenerator::new(Duration::from_millis(10));
+
+        let motion = |millis| {
+            motion_packet(
+                header(PacketType::Motion, 1, Duration::from_millis(millis), 0),
+                Vec::new(),
+                CornerProperty::new(1.0, 1.0, 1.0, 1.0),
+            )
+        };
+        let telemetry = |millis| {
+            telemetry_packet(
+                header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+                vec![on_surface(200, 0.0, Surface::Tarmac)],
+            )
+        };
+
+        assert!(generator.update(&motion(0)).is_none());
+
+        let frame = generator.update(&telemetry(0)).unwrap();
+        assert_approx_eq!(1.0, frame.wheel_spin());
+
+        assert!(generator.update(&motion(5)).is_none());
+        assert!(generator.update(&telemetry(10)).is_some());
+    }
+}
@@ -0,0 +1,392 @@
+//! Shift points and rev lights for the player's car
+//!
+//! LED dashboards and wheels with rev lights need to know when the driver should shift up. The rev
+//! limit differs between cars, and the best point to shift is usually below it, where the next gear
+//! accelerates the car more than the current one. The `ShiftAdvisor` derives a shift point for each
+//! gear, and emits a cue for every telemetry packet of the player's car with the fraction of the
+//! rev lights that should be lit and whether to shift now.
+//!
+//! Shift points can be configured per gear. Gears without a configured shift point use a learned
+//! shift point once enough telemetry at full throttle has been recorded in this gear and the next.
+//! The advisor compares the acceleration of the car in both gears at the same speed, and shifts at
+//! the lowest engine speed at which the next gear accelerates faster. Until then, the shift point is
+//! a fixed fraction of the maximum RPM that the game publishes in the status packet.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use derive_new::new;
+use getset::CopyGetters;
+
+use crate::packet::telemetry::{Gear, Telemetry};
+use crate::packet::Packet;
+
+/// Fraction of the maximum RPM that is used as the shift point before one has been learned
+const DEFAULT_SHIFT_FRACTION: f32 = 0.97;
+
+/// Fraction of the shift point at which the rev lights start to light up
+const DEFAULT_REV_LIGHT_START: f32 = 0.75;
+
+/// Throttle input above which telemetry is used to learn the shift points
+const FULL_THROTTLE: f32 = 0.98;
+
+/// Width of the engine speed ranges in which the acceleration is averaged
+const RPM_BIN: u16 = 250;
+
+/// Number of samples in a range before its acceleration is trusted
+const MIN_SAMPLES: u32 = 5;
+
+/// Cue for the driver and the rev lights
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd)]
+pub struct ShiftCue {
+    /// Returns the session time of the telemetry packet.
+    #[getset(get_copy = "pub")]
+    session_time: Duration,
+
+    /// Returns the gear the car is in.
+    #[getset(get_copy = "pub")]
+    gear: Gear,
+
+    /// Returns the speed of the engine.
+    #[getset(get_copy = "pub")]
+    engine_rpm: u16,
+
+    /// Returns the shift point of the current gear.
+    #[getset(get_copy = "pub")]
+    shift_point: u16,
+
+    /// Returns the fraction of the rev lights that should be lit, between 0 and 1.
+    #[getset(get_copy = "pub")]
+    rev_lights: f32,
+
+    /// Returns whether the driver should shift up now.
+    #[getset(get_copy = "pub")]
+    shift_now: bool,
+}
+
+/// Average acceleration in a range of engine speeds
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct Acceleration {
+    sum: f32,
+    samples: u32,
+}
+
+impl Acceleration {
+    fn average(&self) -> Option<f32> {
+        if self.samples >= MIN_SAMPLES {
+            Some(self.sum / self.samples as f32)
+        } else {
+            None
+        }
+    }
+}
+
+/// What has been learned about a gear
+#[derive(Debug, PartialEq, Clone, Default)]
+struct GearHistory {
+    /// Sum of the ratios between speed and engine speed
+    ratio_sum: f32,
+
+    /// Number of ratios in the sum
+    ratio_samples: u32,
+
+    /// Acceleration by range of engine speeds
+    acceleration: BTreeMap<u16, Acceleration>,
+}
+
+impl GearHistory {
+    fn ratio(&self) -> Option<f32> {
+        if self.ratio_samples >= MIN_SAMPLES {
+            Some(self.ratio_sum / self.ratio_samples as f32)
+        } else {
+            None
+        }
+    }
+}
+
+/// Advisor for the shift points of the player's car
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::output::shift::ShiftAdvisor;
+/// use f1_api::packet::telemetry::Gear;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut advisor = ShiftAdvisor::new().shift_point(Gear::First, 10500);
+///
+/// for packet in packets() {
+///     if let Some(cue) = advisor.update(&packet) {
+///         println!("Rev lights: {:.0}%", cue.rev_lights() * 100.0);
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct ShiftAdvisor {
+    configured: BTreeMap<Gear, u16>,
+    rev_light_start: f32,
+    session_uid: Option<u64>,
+    max_rpm: Option<u16>,
+    gear_count: Option<u8>,
+    history: BTreeMap<Gear, GearHistory>,
+    previous: Option<(Duration, Telemetry)>,
+}
+
+impl ShiftAdvisor {
+    /// Create an advisor without configured shift points.
+    pub fn new() -> Self {
+        ShiftAdvisor {
+            configured: BTreeMap::new(),
+            rev_light_start: DEFAULT_REV_LIGHT_START,
+            session_uid: None,
+            max_rpm: None,
+            gear_count: None,
+            history: BTreeMap::new(),
+            previous: None,
+        }
+    }
+
+    /// Shift up from the given gear at the given engine speed, instead of a learned shift point.
+    pub fn shift_point(mut self, gear: Gear, rpm: u16) -> Self {
+        self.configured.insert(gear, rpm);
+        self
+    }
+
+    /// Start to light the rev lights at the given fraction of the shift point.
+    pub fn rev_light_start(mut self, fraction: f32) -> Self {
+        self.rev_light_start = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns the shift point of the gear, or `None` if it is unknown.
+    ///
+    /// The shift point of the top gear is the maximum RPM.
+    pub fn shift_point_of(&self, gear: Gear) -> Option<u16> {
+        if let Some(rpm) = self.configured.get(&gear) {
+            return Some(*rpm);
+        }
+
+        let max_rpm = self.max_rpm?;
+        if self.is_top_gear(gear) {
+            return Some(max_rpm);
+        }
+
+        let learned = next_gear(gear).and_then(|next| self.learned_shift_point(gear, next));
+        let default = (f32::from(max_rpm) * DEFAULT_SHIFT_FRACTION) as u16;
+
+        Some(learned.unwrap_or(default).min(max_rpm))
+    }
+
+    /// Record the player's car in a status or telemetry packet, and return a cue for telemetry.
+    ///
+    /// Cues are only returned for gears with a known shift point, and not in neutral or reverse.
+    pub fn update(&mut self, packet: &Packet) -> Option<ShiftCue> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.max_rpm = None;
+            self.gear_count = None;
+            self.history.clear();
+            self.previous = None;
+        }
+
+        let packet = match packet {
+            Packet::Status(packet) => {
+                let status = packet.player()?;
+                self.max_rpm = Some(status.max_rpm());
+                self.gear_count = Some(status.gear_count());
+                return None;
+            }
+            Packet::Telemetry(packet) => packet,
+            _ => return None,
+        };
+
+        let time = *packet.header().session_time();
+        let telemetry = *packet.player()?;
+
+        self.learn(time, &telemetry);
+        self.previous = Some((time, telemetry));
+
+        let gear = telemetry.gear();
+        if gear == Gear::Neutral || gear == Gear::Reverse {
+            return None;
+        }
+
+        let shift_point = self.shift_point_of(gear)?;
+        let rpm = telemetry.engine_rpm();
+        let start = f32::from(shift_point) * self.rev_light_start;
+        let rev_lights = if f32::from(shift_point) > start {
+            ((f32::from(rpm) - start) / (f32::from(shift_point) - start)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        Some(ShiftCue::new(
+            time,
+            gear,
+            rpm,
+            shift_point,
+            rev_lights,
+            rpm >= shift_point && !self.is_top_gear(gear),
+        ))
+    }
+
+    fn is_top_gear(&self, gear: Gear) -> bool {
+        match self.gear_count {
+            Some(count) => gear as i8 >= count as i8,
+            None => gear == Gear::Eighth,
+        }
+    }
+
+    /// Record the ratio and the acceleration of the gear at full throttle.
+    fn learn(&mut self, time: Duration, telemetry: &Telemetry) {
+        let (previous_time, previous) = match &self.previous {
+            Some(previous) => previous,
+            None => return,
+        };
+
+        let gear = telemetry.gear();
+        let rpm = telemetry.engine_rpm();
+
+        if gear == Gear::Neutral
+            || gear == Gear::Reverse
+            || gear != previous.gear()
+            || rpm == 0
+            || time <= *previous_time
+            || telemetry.throttle() < FULL_THROTTLE
+            || telemetry.brake() > 0.0
+        {
+            return;
+        }
+
+        let elapsed = (time - *previous_time).as_secs_f32();
+        let acceleration = (f32::from(telemetry.speed()) - f32::from(previous.speed())) / elapsed;
+
+        let history = self.history.entry(gear).or_default();
+        history.ratio_sum += f32::from(telemetry.speed()) / f32::from(rpm);
+        history.ratio_samples += 1;
+
+        let bin = history.acceleration.entry(rpm / RPM_BIN).or_default();
+        bin.sum += acceleration;
+        bin.samples += 1;
+    }
+
+    /// Returns the lowest engine speed at which the next gear accelerates faster than the gear.
+    fn learned_shift_point(&self, gear: Gear, next: Gear) -> Option<u16> {
+        let current = self.history.get(&gear)?;
+        let next = self.history.get(&next)?;
+        let ratio = current.ratio()? / next.ratio()?;
+
+        current
+            .acceleration
+            .iter()
+            .filter_map(|(bin, acceleration)| Some((*bin, acceleration.average()?)))
+            .find(|(bin, acceleration)| {
+                let rpm = f32::from(*bin * RPM_BIN + RPM_BIN / 2);
+                let next_bin = (rpm * ratio) as u16 / RPM_BIN;
+
+                next.acceleration
+                    .get(&next_bin)
+                    .and_then(Acceleration::average)
+                    .is_some_and(|next| next > *acceleration)
+            })
+            .map(|(bin, _)| bin * RPM_BIN)
+    }
+}
+
+impl Default for ShiftAdvisor {
+    fn default() -> Self {
+        ShiftAdvisor::new()
+    }
+}
+
+/// Returns the gear above the given gear.
+fn next_gear(gear: Gear) -> Option<Gear> {
+    match gear {
+        Gear::First => Some(Gear::Second),
+        Gear::Second => Some(Gear::Third),
+        Gear::Third => Some(Gear::Fourth),
+        Gear::Fourth => Some(Gear::Fifth),
+        Gear::Fifth => Some(Gear::Sixth),
+        Gear::Sixth => Some(Gear::Seventh),
+        Gear::Seventh => Some(Gear::Eighth),
+        Gear::Reverse | Gear::Neutral | Gear::Eighth => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::fixtures::{header, status, status_packet, telemetry, telemetry_packet};
+    use crate::output::shift::ShiftAdvisor;
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+
+    fn accelerating(millis: u64, gear: Gear, rpm: u16, speed: u16) -> Packet {
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+            vec![telemetry(speed, 1.0, 0.0, 0.0, gear, rpm)],
+        )
+    }
+
+    fn status_at(millis: u64, max_rpm: u16) -> Packet {
+        status_packet(
+            header(PacketType::Status, 1, Duration::from_millis(millis), 0),
+            vec![status(max_rpm, 8)],
+        )
+    }
+
+    #[test]
+    fn update_with_configured_shift_point() {
+        let mut advisor = ShiftAdvisor::new()
+            .shift_point(Gear::Second, 10000)
+            .rev_light_start(0.5);
+
+        let cue = advisor
+            .update(&accelerating(0, Gear::Second, 7500, 120))
+            .unwrap();
+        assert_approx_eq!(0.5, cue.rev_lights());
+        assert!(!cue.shift_now());
+
+        let cue = advisor
+            .update(&accelerating(10, Gear::Second, 10100, 150))
+            .unwrap();
+        assert_approx_eq!(1.0, cue.rev_lights());
+        assert!(cue.shift_now());
+
+        // Without a status packet, the shift points of other gears are unknown.
+        assert!(advisor
+            .update(&accelerating(20, Gear::Third, 9000, 150))
+            .is_none());
+    }
+
+    #[test]
+    fn learn_shift_point_from_acceleration() {
+        let mut advisor = ShiftAdvisor::new();
+        let mut millis = 0;
+
+        // Second gear accelerates with 100 km/h per second up to 9000 RPM, and with 20 above.
+        for rpm in (8000..11000).step_by(50) {
+            millis += if rpm <= 9000 { 10 } else { 50 };
+            advisor.update(&accelerating(millis, Gear::Second, rpm, rpm / 50));
+        }
+
+        // Third gear runs at 80% of the engine speed of second gear, and accelerates with 50.
+        for rpm in (6000..9600).step_by(40) {
+            millis += 20;
+            advisor.update(&accelerating(millis, Gear::Third, rpm, rpm / 40));
+        }
+
+        assert_eq!(None, advisor.shift_point_of(Gear::Second));
+
+        assert!(advisor.update(&status_at(millis, 12000)).is_none());
+        assert_eq!(Some(9000), advisor.shift_point_of(Gear::Second));
+        assert_eq!(Some(11640), advisor.shift_point_of(Gear::Fourth));
+    }
+}
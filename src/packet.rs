@@ -3,16 +3,26 @@
 //! This library implements a single packet format for all the F1 games it supports. The API
 //! specification has been slowly evolving from game to game, but without such significant changes
 //! that it would require a different packet format.
+//!
+//! With the `arbitrary` feature, the packets and their fields implement `arbitrary::Arbitrary`.
+
+use alloc::string::String;
+use core::fmt;
 
-use std::io::{Cursor, Error, ErrorKind};
+#[cfg(feature = "decode")]
+use bytes::Buf;
 
-use bytes::{Buf, BytesMut};
+#[cfg(feature = "decode")]
+use crate::nineteen::{decode_nineteen_as, Nineteen};
+#[cfg(feature = "decode")]
+use crate::packet::reader::Reader;
 
 pub mod event;
 pub mod header;
 pub mod lap;
 pub mod motion;
 pub mod participants;
+pub mod reader;
 pub mod session;
 pub mod setup;
 pub mod status;
@@ -24,6 +34,7 @@ pub mod telemetry;
 /// packets is decoded from UDP to their respective representation in this Rust crate. The `Packet`
 /// enum lists all packets that can be expected, and that a client should handle.
 #[derive(Debug, PartialEq, Clone, PartialOrd)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Packet {
     /// The F1 games send event packets whenever certain events occur in a session. Some event
     /// packets carry a payload with more information about the event.
@@ -56,6 +67,132 @@ pub enum Packet {
     Telemetry(telemetry::TelemetryPacket),
 }
 
+impl Packet {
+    /// Returns the header of the packet.
+    pub fn header(&self) -> &header::Header {
+        match self {
+            Packet::Event(packet) => packet.header(),
+            Packet::Lap(packet) => packet.header(),
+            Packet::Motion(packet) => packet.header(),
+            Packet::Participants(packet) => packet.header(),
+            Packet::Session(packet) => packet.header(),
+            Packet::Setup(packet) => packet.header(),
+            Packet::Status(packet) => packet.header(),
+            Packet::Telemetry(packet) => packet.header(),
+        }
+    }
+
+    /// Limit the data of every car to the cars that are active in the given mask.
+    ///
+    /// The motion, setup, status, and telemetry packets consider all of their slots active, and
+    /// return padding for slots without a car unless they are limited with the mask of the active
+    /// participants, which `Grid::active` returns. Packets without data per car are returned
+    /// unchanged.
+    pub fn limit_cars(self, active: &[bool]) -> Packet {
+        match self {
+            Packet::Lap(packet) => Packet::Lap(packet.limit_cars(active)),
+            Packet::Motion(packet) => Packet::Motion(packet.limit_cars(active)),
+            Packet::Participants(packet) => Packet::Participants(packet.limit_cars(active)),
+            Packet::Setup(packet) => Packet::Setup(packet.limit_cars(active)),
+            Packet::Status(packet) => Packet::Status(packet.limit_cars(active)),
+            Packet::Telemetry(packet) => Packet::Telemetry(packet.limit_cars(active)),
+            packet => packet,
+        }
+    }
+
+    /// Decode a packet from the payload of a single UDP datagram.
+    ///
+    /// The packet format in the first two bytes of the payload determines the decoder. Unlike the
+    /// codec, this function does not wait for more data, and returns `DecodeError::Incomplete` if
+    /// the payload is too short. It can be used by applications that already have the payload,
+    /// e.g. from their own socket, a pcap file, or a message queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use f1_api::packet::{DecodeError, Packet};
+    ///
+    /// assert_eq!(
+    ///     Err(DecodeError::UnknownFormat(2018)),
+    ///     Packet::from_datagram(&[0xe2, 0x07])
+    /// );
+    /// ```
+    #[cfg(feature = "decode")]
+    pub fn from_datagram(datagram: &[u8]) -> Result<Packet, DecodeError> {
+        let mut cursor = Reader::new(datagram);
+        ensure_packet_size(2, &mut cursor)?;
+
+        let packet_format = cursor.get_u16_le();
+        cursor.set_position(0);
+
+        match packet_format {
+            Nineteen::PACKET_FORMAT => Nineteen::decode(&mut cursor),
+            format => Err(DecodeError::UnknownFormat(format)),
+        }
+    }
+}
+
+/// API specification of an F1 game
+///
+/// Every F1 game defines its own packet format, which is identified by the first two bytes of each
+/// packet. The decoders of a game implement this trait to turn its packets into the unified
+/// `Packet` of this crate, which is what the codec, `Packet::from_datagram`, and the stream of
+/// `F1` produce. Supporting a new game means implementing this trait, and adding it to the
+/// dispatch in `Packet::from_datagram`. Fields that were added to a packet by a patch of a game
+/// can be gated with `Header::is_game_version_at_least`.
+#[cfg(feature = "decode")]
+pub trait GameSpec {
+    /// Packet format that identifies the packets of the game
+    const PACKET_FORMAT: u16;
+
+    /// Decode a packet sent by the game.
+    fn decode(cursor: &mut Reader<'_>) -> Result<Packet, DecodeError>;
+}
+
+/// Error that occurs when a packet cannot be decoded
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub enum DecodeError {
+    /// The packet is shorter than its format requires.
+    Incomplete { expected: usize, actual: usize },
+
+    /// The packet contains a value that is not defined by the API specification.
+    InvalidData(String),
+
+    /// The packet has a packet format that is not supported by this crate.
+    UnknownFormat(u16),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Incomplete { expected, actual } => write!(
+                f,
+                "Packet is expected to have a size of {} bytes, but was {}.",
+                expected, actual
+            ),
+            DecodeError::InvalidData(message) => write!(f, "{}", message),
+            DecodeError::UnknownFormat(format) => write!(f, "Unknown packet format {}.", format),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+#[cfg(feature = "std")]
+impl From<DecodeError> for std::io::Error {
+    fn from(error: DecodeError) -> Self {
+        let kind = match error {
+            DecodeError::Incomplete { .. } => std::io::ErrorKind::UnexpectedEof,
+            DecodeError::InvalidData(_) | DecodeError::UnknownFormat(_) => {
+                std::io::ErrorKind::InvalidData
+            }
+        };
+
+        std::io::Error::new(kind, error)
+    }
+}
+
 /// Ensure a packet has the expected size
 ///
 /// Modern F1 games send their packets over UDP. Depending on their size, these packets might be
@@ -63,34 +200,56 @@ pub enum Packet {
 /// enough data has been received to decode a packet.
 ///
 /// The sizes of the packets sent by F1 games are part of the API specification, and can be used to
-/// determine if a full packet has ben received. This function takes a cursor to the raw data and
+/// determine if a full packet has ben received. This function takes a reader for the raw data and
 /// the expected size of the packet, and returns an error if not enough data is ready to decode the
 /// complete packet.
+#[cfg(feature = "decode")]
 pub(crate) fn ensure_packet_size(
     expected_size: usize,
-    cursor: &mut Cursor<&mut BytesMut>,
-) -> Result<(), Error> {
+    cursor: &mut Reader<'_>,
+) -> Result<(), DecodeError> {
     if cursor.remaining() < expected_size {
-        Err(Error::new(
-            ErrorKind::UnexpectedEof,
-            format!(
-                "Packet is expected to have a size of {} bytes, but was {}.",
-                expected_size,
-                cursor.remaining()
-            ),
-        ))
+        Err(DecodeError::Incomplete {
+            expected: expected_size,
+            actual: cursor.remaining(),
+        })
     } else {
         Ok(())
     }
 }
 
-#[cfg(test)]
+/// Decode a packet of the given type from the payload of a datagram.
+///
+/// The packet format in the payload selects the decoder, which must return a packet of the given
+/// type. This is used by the `TryFrom` implementations of the individual packets.
+#[cfg(feature = "decode")]
+pub(crate) fn decode_packet_as<T>(
+    datagram: &[u8],
+    packet_type: header::PacketType,
+    nineteen: fn(&mut Reader<'_>) -> Result<T, DecodeError>,
+) -> Result<T, DecodeError> {
+    let mut cursor = Reader::new(datagram);
+    ensure_packet_size(2, &mut cursor)?;
+
+    let packet_format = cursor.get_u16_le();
+    cursor.set_position(0);
+
+    match packet_format {
+        Nineteen::PACKET_FORMAT => decode_nineteen_as(&mut cursor, packet_type, nineteen),
+        format => Err(DecodeError::UnknownFormat(format)),
+    }
+}
+
+#[cfg(all(test, feature = "decode"))]
 mod tests {
-    use std::io::{Cursor, Error};
+    use std::convert::TryFrom;
 
-    use bytes::{Buf, BufMut, BytesMut};
+    use bytes::{Buf, BufMut, Bytes, BytesMut};
 
-    use crate::packet::ensure_packet_size;
+    use crate::packet::event::{Event, EventPacket};
+    use crate::packet::reader::Reader;
+    use crate::packet::telemetry::TelemetryPacket;
+    use crate::packet::{ensure_packet_size, DecodeError};
 
     struct Packet {
         counter: u8,
@@ -98,7 +257,7 @@ mod tests {
 
     const PACKET_SIZE: usize = 1;
 
-    fn decode_packet(cursor: &mut Cursor<&mut BytesMut>) -> Result<Packet, Error> {
+    fn decode_packet(cursor: &mut Reader<'_>) -> Result<Packet, DecodeError> {
         ensure_packet_size(PACKET_SIZE, cursor)?;
 
         Ok(Packet {
@@ -108,10 +267,7 @@ mod tests {
 
     #[test]
     fn ensure_packet_size_correctly() {
-        let mut bytes = BytesMut::with_capacity(1);
-        bytes.put_u8(0);
-
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&[0]);
 
         let packet = decode_packet(&mut cursor).unwrap();
         assert_eq!(0, packet.counter);
@@ -119,10 +275,102 @@ mod tests {
 
     #[test]
     fn ensure_packet_size_with_error() {
-        let mut bytes = BytesMut::with_capacity(0);
-        let mut cursor = Cursor::new(&mut bytes);
+        let mut cursor = Reader::new(&[]);
 
         let packet = decode_packet(&mut cursor);
-        assert!(packet.is_err());
+        assert_eq!(
+            Some(DecodeError::Incomplete {
+                expected: 1,
+                actual: 0
+            }),
+            packet.err()
+        );
+    }
+
+    #[test]
+    fn from_datagram_with_incomplete_format() {
+        assert_eq!(
+            Err(DecodeError::Incomplete {
+                expected: 2,
+                actual: 1
+            }),
+            crate::packet::Packet::from_datagram(&[0xe3])
+        );
+    }
+
+    #[test]
+    fn from_datagram_with_incomplete_packet() {
+        let error = crate::packet::Packet::from_datagram(&[0xe3, 0x07, 1, 2]).unwrap_err();
+
+        assert!(matches!(error, DecodeError::Incomplete { actual: 4, .. }));
+    }
+
+    fn session_started(session_time: f32) -> Bytes {
+        let mut bytes = BytesMut::with_capacity(32);
+        bytes.put_u16_le(2019);
+        bytes.put_u8(1);
+        bytes.put_u8(2);
+        bytes.put_u8(3);
+        bytes.put_u8(3);
+        bytes.put_u64_le(u64::MAX);
+        bytes.put_f32_le(session_time);
+        bytes.put_u32_le(u32::MAX);
+        bytes.put_u8(0);
+        bytes.put_slice(b"SSTA");
+        bytes.put_slice(&[0u8; 5]);
+
+        bytes.freeze()
+    }
+
+    #[test]
+    fn try_from_bytes_with_matching_type() {
+        let packet = EventPacket::try_from(&session_started(1.0)[..]).unwrap();
+
+        assert_eq!(Event::SessionStarted, *packet.event());
+    }
+
+    #[test]
+    fn try_from_bytes_with_other_type() {
+        let error = TelemetryPacket::try_from(session_started(1.0)).unwrap_err();
+
+        assert!(matches!(error, DecodeError::InvalidData(_)));
+    }
+
+    #[test]
+    fn from_datagram_with_invalid_session_time() {
+        for session_time in [-1.0, f32::NAN] {
+            let error = crate::packet::Packet::from_datagram(&session_started(session_time));
+
+            assert!(matches!(error, Err(DecodeError::InvalidData(_))));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn arbitrary_packet_matches_its_header() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        use crate::packet::header::PacketType;
+        use crate::packet::Packet;
+
+        let bytes: Vec<u8> = (0..8192).map(|index| (index * 37 % 251) as u8).collect();
+        let mut unstructured = Unstructured::new(&bytes);
+
+        for _ in 0..8 {
+            let packet = Packet::arbitrary(&mut unstructured).unwrap();
+
+            let packet_type = match packet {
+                Packet::Event(_) => PacketType::Event,
+                Packet::Lap(_) => PacketType::Lap,
+                Packet::Motion(_) => PacketType::Motion,
+                Packet::Participants(_) => PacketType::Participants,
+                Packet::Session(_) => PacketType::Session,
+                Packet::Setup(_) => PacketType::Setup,
+                Packet::Status(_) => PacketType::Status,
+                Packet::Telemetry(_) => PacketType::Telemetry,
+            };
+
+            assert_eq!(packet_type, packet.header().packet_type());
+        }
     }
 }
@@ -4,14 +4,24 @@
 //! only two events, but _F1 2019_ extended this to nine different events. Some events carry a
 //! payload that further defines the event, and that are declared in this module as structs.
 
-use std::fmt;
-use std::fmt::Display;
-use std::time::Duration;
+#[cfg(feature = "decode")]
+use core::convert::TryFrom;
+use core::fmt;
+use core::fmt::Display;
+use core::time::Duration;
 
+#[cfg(feature = "decode")]
+use bytes::Bytes;
 use derive_new::new;
 use getset::{CopyGetters, Getters};
 
+#[cfg(feature = "decode")]
+use crate::nineteen::event::decode_event;
 use crate::packet::header::Header;
+#[cfg(feature = "decode")]
+use crate::packet::header::PacketType;
+#[cfg(feature = "decode")]
+use crate::packet::{decode_packet_as, DecodeError};
 use crate::types::VehicleIndex;
 
 /// Payload for fastest lap event
@@ -24,7 +34,7 @@ use crate::types::VehicleIndex;
 ///
 /// ```
 /// # use f1_api::packet::event::{FastestLap, Event};
-/// # use std::time::Duration;
+/// # use core::time::Duration;
 /// #
 /// # let fastest_lap = FastestLap::new(0, Duration::from_secs(62));
 /// # let event = Event::FastestLap(fastest_lap);
@@ -41,6 +51,7 @@ use crate::types::VehicleIndex;
 #[derive(
     new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default,
 )]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct FastestLap {
     /// Returns the index of the car achieving the fastest lap.
     #[getset(get_copy = "pub")]
@@ -85,6 +96,7 @@ impl Display for FastestLap {
 #[derive(
     new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default,
 )]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Retirement {
     /// Returns the index of the car retiring.
     #[getset(get_copy = "pub")]
@@ -121,6 +133,7 @@ impl Display for Retirement {
 #[derive(
     new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default,
 )]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct TeammateInPits {
     /// Returns the index of the teammate who has just entered the pits.
     #[getset(get_copy = "pub")]
@@ -156,6 +169,7 @@ impl Display for TeammateInPits {
 #[derive(
     new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default,
 )]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct RaceWinner {
     /// Returns the index of the car that has won the race.
     #[getset(get_copy = "pub")]
@@ -174,7 +188,8 @@ impl Display for RaceWinner {
 /// game, only a subset of the defined events may be published. Some events carry a payload that
 /// further describes the event. For example, the event declaring the race winner sends with it the
 /// vehicle index of said winner.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Event {
     /// The chequered flag signals the end of the race.
     ChequeredFlag,
@@ -202,18 +217,13 @@ pub enum Event {
     SessionEnded,
 
     /// The start of a session is announced in an event.
+    #[default]
     SessionStarted,
 
     /// When a teammate enters the pits, an event carrying their vehicle index is published.
     TeammatesInPits(TeammateInPits),
 }
 
-impl Default for Event {
-    fn default() -> Self {
-        Event::SessionStarted
-    }
-}
-
 impl Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -243,9 +253,14 @@ impl Display for Event {
 /// frequency with which these packets are sent is not fixed, but rather packets are sent whenever
 /// events occur.
 #[derive(new, Debug, Getters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct EventPacket {
     /// Returns the packet header prefixing the event packet.
     #[getset(get = "pub")]
+    #[cfg_attr(
+        feature = "arbitrary",
+        arbitrary(with = |u| Header::arbitrary_of_type(u, crate::packet::header::PacketType::Event))
+    )]
     header: Header,
 
     /// Returns the event from the event packet.
@@ -262,3 +277,23 @@ impl Display for EventPacket {
         )
     }
 }
+
+#[cfg(feature = "decode")]
+impl TryFrom<&[u8]> for EventPacket {
+    type Error = DecodeError;
+
+    /// Decode an event packet from the payload of a datagram.
+    fn try_from(datagram: &[u8]) -> Result<Self, Self::Error> {
+        decode_packet_as(datagram, PacketType::Event, decode_event)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<Bytes> for EventPacket {
+    type Error = DecodeError;
+
+    /// Decode an event packet from the payload of a datagram.
+    fn try_from(datagram: Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(datagram.as_ref())
+    }
+}
@@ -1,8 +1,10 @@
 //! Header prefixing packets from modern F1 games
 
-use std::fmt;
-use std::fmt::Display;
-use std::time::Duration;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::fmt::Display;
+use core::time::Duration;
 
 use derive_new::new;
 use getset::{CopyGetters, Getters};
@@ -15,6 +17,7 @@ use crate::types::VehicleIndex;
 /// Since the data published by each game is unique in one way or another, support for additional
 /// API specs has to be implemented manually.
 #[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum ApiSpec {
     Nineteen,
 }
@@ -24,6 +27,7 @@ pub enum ApiSpec {
 /// The modern F1 games have divided their telemetry output into multiple packets, which can be sent
 /// at different intervals based on how quickly their data changes.
 #[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum PacketType {
     Event,
     Lap,
@@ -38,12 +42,21 @@ pub enum PacketType {
 /// Version number of the game
 ///
 /// The modern F1 games include their version number in the packet header. The games are versioned
-/// using the scheme `MAJOR.MINOR`.
+/// using the scheme `MAJOR.MINOR`. Versions are ordered by their major version first, and then by
+/// their numeric minor version, so that `1.9` is older than `1.10`.
 ///
-/// TODO Test that partial order works correctly with version numbers
+/// # Examples
+///
+/// ```
+/// use f1_api::packet::header::GameVersion;
+///
+/// assert!(GameVersion::new(1, 9) < GameVersion::new(1, 10));
+/// assert!(GameVersion::new(1, 22).at_least(1, 20));
+/// ```
 #[derive(
     new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default,
 )]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct GameVersion {
     /// Returns the major version of the game.
     #[getset(get_copy = "pub")]
@@ -54,6 +67,15 @@ pub struct GameVersion {
     minor: u8,
 }
 
+impl GameVersion {
+    /// Returns true if this version is the same as or newer than `major.minor`.
+    ///
+    /// Decoders use this to read fields that were only added to a packet by a patch of the game.
+    pub fn at_least(&self, major: u8, minor: u8) -> bool {
+        *self >= GameVersion::new(major, minor)
+    }
+}
+
 impl Display for GameVersion {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}.{}", self.major, self.minor)
@@ -72,6 +94,8 @@ impl Display for GameVersion {
 ///
 /// TODO Verify that the session tie can be represented as a duration
 #[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[allow(clippy::too_many_arguments)]
 pub struct Header {
     /// Returns the API specification that was used to decode the packet.
     #[getset(get_copy = "pub")]
@@ -106,6 +130,23 @@ pub struct Header {
     /// in these arrays the player's car has.
     #[getset(get_copy = "pub")]
     player_car_index: VehicleIndex,
+
+    /// Returns the secondary player's car index in split-screen sessions.
+    ///
+    /// Newer games publish the index of the second player's car when two players share a screen.
+    /// F1 2019 does not, so its packets never have a secondary player.
+    #[getset(get_copy = "pub")]
+    secondary_player_car_index: Option<VehicleIndex>,
+}
+
+impl Header {
+    /// Returns true if the packet was sent by the game at version `major.minor` or newer.
+    ///
+    /// Packets without a game version are treated as older than any version.
+    pub fn is_game_version_at_least(&self, major: u8, minor: u8) -> bool {
+        self.game_version
+            .is_some_and(|version| version.at_least(major, minor))
+    }
 }
 
 impl Display for Header {
@@ -126,3 +167,76 @@ impl Display for Header {
         )
     }
 }
+
+#[cfg(feature = "arbitrary")]
+impl Header {
+    /// Generate an arbitrary header for a packet of the given type.
+    ///
+    /// Packets with an arbitrary header would otherwise declare a type that does not match their
+    /// own.
+    pub(crate) fn arbitrary_of_type(
+        u: &mut arbitrary::Unstructured<'_>,
+        packet_type: PacketType,
+    ) -> arbitrary::Result<Self> {
+        use arbitrary::Arbitrary;
+
+        Ok(Header {
+            packet_type,
+            ..Header::arbitrary(u)?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::packet::header::{ApiSpec, GameVersion, Header, PacketType};
+
+    #[test]
+    fn game_versions_are_ordered_numerically() {
+        let mut versions = vec![
+            GameVersion::new(2, 0),
+            GameVersion::new(1, 10),
+            GameVersion::new(1, 9),
+            GameVersion::new(1, 22),
+        ];
+        versions.sort();
+
+        assert_eq!(
+            vec![
+                GameVersion::new(1, 9),
+                GameVersion::new(1, 10),
+                GameVersion::new(1, 22),
+                GameVersion::new(2, 0),
+            ],
+            versions
+        );
+    }
+
+    #[test]
+    fn game_version_at_least() {
+        let version = GameVersion::new(1, 10);
+
+        assert!(version.at_least(1, 10));
+        assert!(version.at_least(1, 9));
+        assert!(!version.at_least(1, 11));
+        assert!(!version.at_least(2, 0));
+
+        let header = |game_version| {
+            Header::new(
+                ApiSpec::Nineteen,
+                game_version,
+                PacketType::Lap,
+                0,
+                Duration::default(),
+                0,
+                0,
+                None,
+            )
+        };
+
+        assert!(header(Some(version)).is_game_version_at_least(1, 2));
+        assert!(!header(None).is_game_version_at_least(1, 2));
+    }
+}
@@ -4,17 +4,31 @@
 //! which the packets are sent can be configured in the game. F1 2018 and F1 2019 share the same
 //! packet format.
 
-use std::time::Duration;
+use alloc::vec::Vec;
+#[cfg(feature = "decode")]
+use core::convert::TryFrom;
+use core::time::Duration;
 
+#[cfg(feature = "decode")]
+use bytes::Bytes;
 use derive_new::new;
 use getset::{CopyGetters, Getters};
 
+#[cfg(feature = "decode")]
+use crate::nineteen::lap::decode_lap_data;
 use crate::packet::header::Header;
+#[cfg(feature = "decode")]
+use crate::packet::header::PacketType;
+#[cfg(feature = "decode")]
+use crate::packet::{decode_packet_as, DecodeError};
+use crate::types::{Grid, VehicleIndex};
 
 /// Statuses a driver can have during a lap
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum DriverStatus {
     /// The driver is still in the garage, and has not left it yet.
+    #[default]
     InGarage,
 
     /// The driver is on a flying lap, and cars that are on an in- or out-lap have to give room.
@@ -33,16 +47,12 @@ pub enum DriverStatus {
     OnTrack,
 }
 
-impl Default for DriverStatus {
-    fn default() -> Self {
-        DriverStatus::InGarage
-    }
-}
-
 /// Statuses used to signal the progression of a pit stop
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum PitStatus {
     /// No pit stop is being performed, and the car is most likely on track or in the garage.
+    #[default]
     None,
 
     /// The car is pitting, which means it is on the pit lane but not stationary in the pit box.
@@ -52,16 +62,12 @@ pub enum PitStatus {
     InPits,
 }
 
-impl Default for PitStatus {
-    fn default() -> Self {
-        PitStatus::None
-    }
-}
-
 /// Statuses that classify the result
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum ResultStatus {
     /// The results are invalid.
+    #[default]
     Invalid,
 
     /// The results are not being collected yet.
@@ -84,16 +90,12 @@ pub enum ResultStatus {
     Retired,
 }
 
-impl Default for ResultStatus {
-    fn default() -> Self {
-        ResultStatus::Invalid
-    }
-}
-
 /// The three sectors of a race track in F1
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Sector {
     /// The first sector
+    #[default]
     First,
 
     /// The second sector
@@ -103,12 +105,6 @@ pub enum Sector {
     Third,
 }
 
-impl Default for Sector {
-    fn default() -> Self {
-        Sector::First
-    }
-}
-
 /// Data about a car and its lap times
 ///
 /// For each car in the session, a set of lap data is published. It contains data on the current
@@ -116,6 +112,7 @@ impl Default for Sector {
 /// last and best lap.
 #[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
 #[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Lap {
     /// Returns the time of the last lap.
     #[getset(get = "pub")]
@@ -146,8 +143,11 @@ pub struct Lap {
     total_distance: f32,
 
     /// Returns the delta during a safety car in seconds.
-    #[getset(get = "pub")]
-    safety_car_delta: Duration,
+    ///
+    /// The delta is negative while a car is behind its target time, and positive while it is
+    /// ahead of it.
+    #[getset(get_copy = "pub")]
+    safety_car_delta: f32,
 
     /// Returns a car's position in the race.
     #[getset(get_copy = "pub")]
@@ -195,12 +195,105 @@ pub struct Lap {
 /// The F1 games publish a lap packet that contains data on all 20 cars in a session. The packet is
 /// sent at a fixed interval that can be configured in the game.
 #[derive(new, Debug, Getters, PartialEq, Clone, PartialOrd)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct LapPacket {
     /// Returns the packet header prefixing the lap data packet.
     #[getset(get = "pub")]
+    #[cfg_attr(
+        feature = "arbitrary",
+        arbitrary(with = |u| Header::arbitrary_of_type(u, crate::packet::header::PacketType::Lap))
+    )]
     header: Header,
 
-    /// Returns the laps for all 20 cars in a session.
+    /// Returns the laps of the cars in the session.
+    ///
+    /// Slots without a result status are considered inactive.
     #[getset(get = "pub")]
-    laps: Vec<Lap>,
+    laps: Grid<Lap>,
+}
+
+impl LapPacket {
+    /// Limit the laps to the cars that are active in the given mask.
+    pub(crate) fn limit_cars(mut self, active: &[bool]) -> Self {
+        self.laps = self.laps.limit(active);
+        self
+    }
+
+    /// Returns the lap data of the car with the given vehicle index.
+    pub fn car(&self, vehicle_index: VehicleIndex) -> Option<&Lap> {
+        self.laps.car(vehicle_index)
+    }
+
+    /// Returns the lap data of the player's car.
+    pub fn player(&self) -> Option<&Lap> {
+        self.car(self.header.player_car_index())
+    }
+
+    /// Returns the lap data of the secondary player's car in split-screen sessions.
+    pub fn secondary_player(&self) -> Option<&Lap> {
+        self.car(self.header.secondary_player_car_index()?)
+    }
+
+    /// Returns the cars that have a position in the session, ordered by their position.
+    ///
+    /// Each car is returned with its vehicle index, which identifies it in the other packets.
+    pub fn by_position(&self) -> Vec<(VehicleIndex, &Lap)> {
+        let mut cars: Vec<(VehicleIndex, &Lap)> = self
+            .laps
+            .iter_active()
+            .filter(|(_, lap)| lap.position > 0)
+            .collect();
+
+        cars.sort_by_key(|(_, lap)| lap.position);
+        cars
+    }
+
+    /// Returns the car that leads the session, together with its vehicle index.
+    pub fn leader(&self) -> Option<(VehicleIndex, &Lap)> {
+        self.laps.iter_active().find(|(_, lap)| lap.position == 1)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<&[u8]> for LapPacket {
+    type Error = DecodeError;
+
+    /// Decode a lap data packet from the payload of a datagram.
+    fn try_from(datagram: &[u8]) -> Result<Self, Self::Error> {
+        decode_packet_as(datagram, PacketType::Lap, decode_lap_data)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<Bytes> for LapPacket {
+    type Error = DecodeError;
+
+    /// Decode a lap data packet from the payload of a datagram.
+    fn try_from(datagram: Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(datagram.as_ref())
+    }
+}
+
+#[cfg(all(test, feature = "std", feature = "decode"))]
+mod tests {
+    use crate::fixtures::decode;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::lap::LapPacket;
+
+    #[test]
+    fn by_position_and_leader() {
+        let packets: Vec<LapPacket> = decode(ApiSpec::Nineteen, PacketType::Lap).unwrap();
+        let packet = &packets[0];
+
+        let positions: Vec<u8> = packet
+            .by_position()
+            .iter()
+            .map(|(_, lap)| lap.position())
+            .collect();
+
+        assert_eq!((1..=20).collect::<Vec<u8>>(), positions);
+        assert_eq!(0, packet.leader().unwrap().0);
+        assert_eq!(packet.leader().map(|(_, lap)| lap), packet.player());
+        assert_eq!(None, packet.car(20));
+    }
 }
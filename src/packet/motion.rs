@@ -4,17 +4,29 @@
 //! motion packet. The rate with which these packets are sent can be configured in the game. F1 2018
 //! and F1 2019 publish the same motion data.
 
+#[cfg(feature = "decode")]
+use core::convert::TryFrom;
+
+#[cfg(feature = "decode")]
+use bytes::Bytes;
 use derive_new::new;
 use getset::{CopyGetters, Getters};
 
+#[cfg(feature = "decode")]
+use crate::nineteen::motion::decode_motion;
 use crate::packet::header::Header;
-use crate::types::{CornerProperty, Property3D};
+#[cfg(feature = "decode")]
+use crate::packet::header::PacketType;
+#[cfg(feature = "decode")]
+use crate::packet::{decode_packet_as, DecodeError};
+use crate::types::{CornerProperty, Grid, Property3D, VehicleIndex};
 
 /// Data about a car and its position and movement in space
 ///
 /// The position and movement of each car in a session is described in the motion packet.
 #[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, PartialOrd, Default)]
 #[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Motion {
     /// Returns the position of the car in 3D space.
     #[getset(get = "pub")]
@@ -61,14 +73,19 @@ pub struct Motion {
 /// its suspension.
 #[derive(new, Debug, CopyGetters, Getters, PartialEq, Clone, PartialOrd)]
 #[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MotionPacket {
     /// Returns the packet header prefixing the motion packet.
     #[getset(get = "pub")]
+    #[cfg_attr(
+        feature = "arbitrary",
+        arbitrary(with = |u| Header::arbitrary_of_type(u, crate::packet::header::PacketType::Motion))
+    )]
     header: Header,
 
-    /// Returns the publicly observable motion data for all 20 cars in the session.
+    /// Returns the publicly observable motion data for the cars in the session.
     #[getset(get = "pub")]
-    cars: Vec<Motion>,
+    cars: Grid<Motion>,
 
     /// Returns the position of the suspension at each corner of the car.
     #[getset(get = "pub")]
@@ -106,3 +123,46 @@ pub struct MotionPacket {
     #[getset(get_copy = "pub")]
     front_wheels_angle: f32,
 }
+
+impl MotionPacket {
+    /// Limit the motion data to the cars that are active in the given mask.
+    pub(crate) fn limit_cars(mut self, active: &[bool]) -> Self {
+        self.cars = self.cars.limit(active);
+        self
+    }
+
+    /// Returns the motion data of the car with the given vehicle index.
+    pub fn car(&self, vehicle_index: VehicleIndex) -> Option<&Motion> {
+        self.cars.car(vehicle_index)
+    }
+
+    /// Returns the motion data of the player's car.
+    pub fn player(&self) -> Option<&Motion> {
+        self.car(self.header.player_car_index())
+    }
+
+    /// Returns the motion data of the secondary player's car in split-screen sessions.
+    pub fn secondary_player(&self) -> Option<&Motion> {
+        self.car(self.header.secondary_player_car_index()?)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<&[u8]> for MotionPacket {
+    type Error = DecodeError;
+
+    /// Decode a motion packet from the payload of a datagram.
+    fn try_from(datagram: &[u8]) -> Result<Self, Self::Error> {
+        decode_packet_as(datagram, PacketType::Motion, decode_motion)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<Bytes> for MotionPacket {
+    type Error = DecodeError;
+
+    /// Decode a motion packet from the payload of a datagram.
+    fn try_from(datagram: Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(datagram.as_ref())
+    }
+}
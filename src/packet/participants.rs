@@ -3,31 +3,45 @@
 //! The F1 games provide information about each participant in a session, for example their name,
 //! team, and nationality. The data is updated every 5 seconds.
 
-use crate::packet::header::Header;
+use alloc::string::String;
+#[cfg(feature = "decode")]
+use core::convert::TryFrom;
+
+#[cfg(feature = "decode")]
+use bytes::Bytes;
 use derive_new::new;
 use getset::{CopyGetters, Getters};
 
+#[cfg(feature = "decode")]
+use crate::nineteen::participants::{decode_participants, driver_by_name, resolve_driver};
+use crate::packet::header::Header;
+#[cfg(feature = "decode")]
+use crate::packet::header::PacketType;
+#[cfg(feature = "decode")]
+use crate::packet::{decode_packet_as, DecodeError};
+use crate::types::{Grid, VehicleIndex};
+
 /// Controller of a car
 ///
 /// Cars can either be controlled by a human player or the AI.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Controller {
+    #[default]
     AI,
     Human,
 }
 
-impl Default for Controller {
-    fn default() -> Self {
-        Controller::AI
-    }
-}
-
 /// Drivers that appear in the F1 games
 ///
 /// The F1 games feature a long list of drivers that appear in the games. Not every driver is
 /// available in every game, and some drivers might be in a F2 championship in one game, and in F1
 /// in the next.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+///
+/// Drivers that are not part of the game's roster, for example a player's MyTeam driver or an AI
+/// driver in a modded roster, are represented as `Driver::Custom` with the name of the participant.
+#[derive(Debug, PartialEq, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Driver {
     AlainForest,
     AlessioLorandi,
@@ -80,6 +94,8 @@ pub enum Driver {
     MickSchumacher,
     NaotaIzum,
     NicholasLatifi,
+    // Open a PR to change this and I will block you!
+    #[default]
     NicoHulkenburg,
     NikitaMazepin,
     NikoKari,
@@ -106,12 +122,42 @@ pub enum Driver {
     ValtteriBottas,
     WilheimKaufmann,
     YasarAtiyeh,
+    Custom {
+        name: String,
+    },
 }
 
-impl Default for Driver {
-    fn default() -> Self {
-        // Open a PR to change this and I will block you!
-        Driver::NicoHulkenburg
+#[cfg(feature = "decode")]
+impl Driver {
+    /// Resolve the driver with the given identifier and name from the participants packet.
+    ///
+    /// Identifiers that are not in the roster of the game are matched by the name of the
+    /// participant instead. When the name does not match a known driver either, the driver is
+    /// returned as `Driver::Custom`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use f1_api::packet::participants::Driver;
+    ///
+    /// assert_eq!(Driver::LewisHamilton, Driver::resolve(7, "Lewis Hamilton"));
+    /// assert_eq!(Driver::LewisHamilton, Driver::resolve(200, "HAMILTON"));
+    /// assert_eq!(
+    ///     Driver::Custom {
+    ///         name: "Jane Doe".into()
+    ///     },
+    ///     Driver::resolve(200, "Jane Doe")
+    /// );
+    /// ```
+    pub fn resolve(id: u8, name: &str) -> Driver {
+        resolve_driver(id, name)
+    }
+
+    /// Returns the known driver whose full name or unique last name matches the given name.
+    ///
+    /// Names are compared without case, whitespace, and punctuation.
+    pub fn from_name(name: &str) -> Option<Driver> {
+        driver_by_name(name)
     }
 }
 
@@ -119,7 +165,11 @@ impl Default for Driver {
 ///
 /// The F1 games feature a long list of teams that appear in the games, with some teams only being
 /// available in certain games.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+///
+/// Teams that are not part of the game's roster, most notably the team that players create in the
+/// MyTeam career mode, are represented as `Team::Custom` with the identifier that the game sent.
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Team {
     ARTGrandPrix,
     AlfaRomeo,
@@ -156,6 +206,8 @@ pub enum Team {
     McLaren1991,
     McLaren1998,
     McLaren2010,
+    // We don't really have a choice in the hybrid area.
+    #[default]
     Mercedes,
     Pertamina,
     Prema2019,
@@ -174,20 +226,129 @@ pub enum Team {
     Williams1992,
     Williams1996,
     Williams2003,
+    Custom(u8),
 }
 
-impl Default for Team {
-    fn default() -> Self {
-        // We don't really have a choice in the hybrid area.
-        Team::Mercedes
+impl Team {
+    /// Returns the identifier of a team that is not part of the game's roster.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use f1_api::packet::participants::Team;
+    ///
+    /// assert_eq!(Some(255), Team::Custom(255).custom_id());
+    /// assert_eq!(None, Team::Ferrari.custom_id());
+    /// ```
+    pub fn custom_id(self) -> Option<u8> {
+        match self {
+            Team::Custom(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the team is not part of the game's roster, e.g. a player's MyTeam.
+    pub fn is_custom(self) -> bool {
+        self.custom_id().is_some()
+    }
+
+    /// Returns the class of the team's car.
+    ///
+    /// The class of a team outside the game's roster is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use f1_api::packet::participants::{CarClass, Team};
+    ///
+    /// assert_eq!(Some(CarClass::ModernF1), Team::Ferrari.car_class());
+    /// assert_eq!(Some(CarClass::Classic), Team::Ferrari2004.car_class());
+    /// assert_eq!(Some(CarClass::F2), Team::Prema2019.car_class());
+    /// assert_eq!(None, Team::Custom(255).car_class());
+    /// ```
+    pub fn car_class(self) -> Option<CarClass> {
+        let class = match self {
+            Team::AlfaRomeo
+            | Team::Ferrari
+            | Team::Haas
+            | Team::McLaren
+            | Team::Mercedes
+            | Team::RacingPoint
+            | Team::RedBullRacing
+            | Team::Renault
+            | Team::ToroRosso
+            | Team::Williams => CarClass::ModernF1,
+            Team::Brawn2009
+            | Team::Ferrari1976
+            | Team::Ferrari1979
+            | Team::Ferrari1990
+            | Team::Ferrari1995
+            | Team::Ferrari2002
+            | Team::Ferrari2004
+            | Team::Ferrari2007
+            | Team::Ferrari2010
+            | Team::Lotus1972
+            | Team::Lotus1978
+            | Team::McLaren1976
+            | Team::McLaren1982
+            | Team::McLaren1988
+            | Team::McLaren1990
+            | Team::McLaren1991
+            | Team::McLaren1998
+            | Team::McLaren2010
+            | Team::RedBull2010
+            | Team::Renault2006
+            | Team::Williams1992
+            | Team::Williams1996
+            | Team::Williams2003 => CarClass::Classic,
+            Team::ARTGrandPrix
+            | Team::Arden2019
+            | Team::ArtGP2019
+            | Team::BWTArden
+            | Team::Campos2019
+            | Team::CamposVexatecRacing
+            | Team::Carlin
+            | Team::Carlin2019
+            | Team::CharouzRacingSystem
+            | Team::DAMS
+            | Team::Dams2019
+            | Team::MPMotorsport
+            | Team::MPMotorsport2019
+            | Team::Pertamina
+            | Team::Prema2019
+            | Team::RussianTime
+            | Team::SauberJuniorCharouz2019
+            | Team::Trident
+            | Team::Trident2019
+            | Team::UniVirtuosi2019 => CarClass::F2,
+            Team::Custom(_) => return None,
+        };
+
+        Some(class)
     }
 }
 
+/// Classes of cars in the F1 games
+///
+/// Besides the cars of the current Formula 1 season, the games feature classic Formula 1 cars and
+/// the cars of the F2 championship. Newer games add supercars and the safety car, which F1 2019
+/// does not publish in its participants.
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum CarClass {
+    ModernF1,
+    Classic,
+    F2,
+    Supercar,
+    SafetyCar,
+}
+
 /// Nationalities that appear in the F1 games
 ///
 /// The F1 games feature a long list of drivers and teams, all of which have different
 /// nationalities.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Nationality {
     American,
     Argentinean,
@@ -217,6 +378,8 @@ pub enum Nationality {
     Estonian,
     Finnish,
     French,
+    // Greetings from the Nürburg!
+    #[default]
     German,
     Ghanaian,
     Greek,
@@ -277,29 +440,18 @@ pub enum Nationality {
     Welsh,
 }
 
-impl Default for Nationality {
-    fn default() -> Self {
-        // Greetings from the Nürburg!
-        Nationality::German
-    }
-}
-
 /// Privacy setting for telemetry data
 ///
 /// In multiplayer sessions, only the player's telemetry data is broadcast over UDP. Telemetry data
 /// of other cars is restricted to prevent players gaining an unfair advantage.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum TelemetryPrivacy {
+    #[default]
     Public,
     Restricted,
 }
 
-impl Default for TelemetryPrivacy {
-    fn default() -> Self {
-        TelemetryPrivacy::Public
-    }
-}
-
 /// Data about a participant in the session
 ///
 /// The F1 games publish data for each participant in a session that identifies them. This data
@@ -307,13 +459,14 @@ impl Default for TelemetryPrivacy {
 #[derive(
     new, Debug, CopyGetters, Getters, PartialEq, Clone, Eq, Ord, PartialOrd, Hash, Default,
 )]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Participant {
     /// Returns the type of controller.
     #[getset(get_copy = "pub")]
     controller: Controller,
 
     /// Returns the driver.
-    #[getset(get_copy = "pub")]
+    #[getset(get = "pub")]
     driver: Driver,
 
     /// Returns the participant's team.
@@ -341,14 +494,26 @@ pub struct Participant {
     telemetry_privacy: Option<TelemetryPrivacy>,
 }
 
+impl Participant {
+    /// Returns the class of the participant's car.
+    pub fn car_class(&self) -> Option<CarClass> {
+        self.team.car_class()
+    }
+}
+
 /// Packet containing information about each participant in the session
 ///
 /// The F1 games provide information about each participant in a session, for example their name,
 /// team, and nationality. The data is updated every 5 seconds.
 #[derive(new, Debug, CopyGetters, Getters, PartialEq, Clone, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ParticipantsPacket {
     /// Returns the packet header prefixing the participants packet.
     #[getset(get = "pub")]
+    #[cfg_attr(
+        feature = "arbitrary",
+        arbitrary(with = |u| Header::arbitrary_of_type(u, crate::packet::header::PacketType::Participants))
+    )]
     header: Header,
 
     /// Returns the number of active participant in the session.
@@ -361,7 +526,51 @@ pub struct ParticipantsPacket {
     /// Returns the participants in the session.
     ///
     /// As is the case in other packets, the participants packet always contain 20 entries. This is
-    /// also the case when there are less then 20 active participants in the session.
+    /// also the case when there are less then 20 active participants in the session. Slots without a
+    /// name are considered inactive.
     #[getset(get = "pub")]
-    participants: Vec<Participant>,
+    participants: Grid<Participant>,
+}
+
+impl ParticipantsPacket {
+    /// Limit the participants to the cars that are active in the given mask.
+    pub(crate) fn limit_cars(mut self, active: &[bool]) -> Self {
+        self.participants = self.participants.limit(active);
+        self
+    }
+
+    /// Returns the participant of the car with the given vehicle index.
+    pub fn car(&self, vehicle_index: VehicleIndex) -> Option<&Participant> {
+        self.participants.car(vehicle_index)
+    }
+
+    /// Returns the participant of the player's car.
+    pub fn player(&self) -> Option<&Participant> {
+        self.car(self.header.player_car_index())
+    }
+
+    /// Returns the participant of the secondary player's car in split-screen sessions.
+    pub fn secondary_player(&self) -> Option<&Participant> {
+        self.car(self.header.secondary_player_car_index()?)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<&[u8]> for ParticipantsPacket {
+    type Error = DecodeError;
+
+    /// Decode a participants packet from the payload of a datagram.
+    fn try_from(datagram: &[u8]) -> Result<Self, Self::Error> {
+        decode_packet_as(datagram, PacketType::Participants, decode_participants)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<Bytes> for ParticipantsPacket {
+    type Error = DecodeError;
+
+    /// Decode a participants packet from the payload of a datagram.
+    fn try_from(datagram: Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(datagram.as_ref())
+    }
 }
@@ -0,0 +1,173 @@
+//! Reader for the raw bytes of a packet
+//!
+//! The decoders read packets from a byte slice with the `Reader`. Unlike `std::io::Cursor`, the
+//! reader does not depend on the standard library, which allows the decoders to run on embedded
+//! devices that receive the packets directly. Values are read through the `bytes::Buf` trait.
+//!
+//! The layout of a packet is declared as a struct of the values in the order in which the game
+//! sends them. Types that implement the `Decode` trait can be read from a reader, and the
+//! `raw_struct!` macro implements it for a struct by reading its fields one after another. Adding a
+//! packet format is therefore a matter of declaring its fields.
+
+#[cfg(feature = "decode")]
+use alloc::vec::Vec;
+#[cfg(feature = "decode")]
+use core::convert::TryInto;
+#[cfg(feature = "decode")]
+use core::mem::size_of;
+
+use bytes::Buf;
+
+#[cfg(feature = "decode")]
+use crate::packet::{ensure_packet_size, DecodeError};
+
+/// Reader that tracks its position in a byte slice
+///
+/// # Examples
+///
+/// ```
+/// use bytes::Buf;
+/// use f1_api::packet::reader::Reader;
+///
+/// let bytes = [0xe3, 0x07, 1];
+/// let mut reader = Reader::new(&bytes);
+///
+/// assert_eq!(2019, reader.get_u16_le());
+/// assert_eq!(1, reader.remaining());
+/// ```
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Create a reader at the start of the given bytes.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, position: 0 }
+    }
+
+    /// Returns the position of the reader in the bytes.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Move the reader to the given position.
+    ///
+    /// Like `std::io::Cursor`, the reader can be moved past the end of the bytes, after which no
+    /// bytes remain to be read.
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// Returns all bytes, independent of the position of the reader.
+    pub fn get_ref(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl Buf for Reader<'_> {
+    fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.position)
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.bytes.get(self.position..).unwrap_or_default()
+    }
+
+    fn advance(&mut self, count: usize) {
+        assert!(count <= self.remaining(), "cannot advance past the end");
+        self.position += count;
+    }
+}
+
+/// Value that can be read from the raw bytes of a packet
+///
+/// The F1 games send all values in little endian byte order.
+#[cfg(feature = "decode")]
+pub trait Decode: Sized {
+    /// Read the value at the position of the reader, and move the reader past it.
+    fn decode(cursor: &mut Reader<'_>) -> Result<Self, DecodeError>;
+}
+
+#[cfg(feature = "decode")]
+macro_rules! decode_primitive {
+    ($($ty:ty => $get:ident,)*) => {
+        $(
+            impl Decode for $ty {
+                fn decode(cursor: &mut Reader<'_>) -> Result<Self, DecodeError> {
+                    ensure_packet_size(size_of::<$ty>(), cursor)?;
+                    Ok(cursor.$get())
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "decode")]
+decode_primitive! {
+    u8 => get_u8,
+    i8 => get_i8,
+    u16 => get_u16_le,
+    i16 => get_i16_le,
+    u32 => get_u32_le,
+    u64 => get_u64_le,
+    f32 => get_f32_le,
+}
+
+#[cfg(feature = "decode")]
+impl<T, const N: usize> Decode for [T; N]
+where
+    T: Decode,
+{
+    fn decode(cursor: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let mut values = Vec::with_capacity(N);
+
+        for _ in 0..N {
+            values.push(T::decode(cursor)?);
+        }
+
+        values
+            .try_into()
+            .map_err(|values: Vec<T>| DecodeError::Incomplete {
+                expected: N,
+                actual: values.len(),
+            })
+    }
+}
+
+/// Declare the layout of a packet as it is sent by the game.
+///
+/// The macro declares a `#[repr(C, packed)]` struct with the given fields, which matches the packet
+/// byte for byte, and implements `Decode` by reading the fields in the order of their declaration.
+/// With the `bytemuck` feature, the struct also implements `bytemuck::Pod`.
+#[cfg(feature = "decode")]
+macro_rules! raw_struct {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $($(#[$field_meta:meta])* pub $field:ident: $ty:ty,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, PartialEq, Copy, Clone)]
+        #[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+        #[repr(C, packed)]
+        pub struct $name {
+            $($(#[$field_meta])* pub $field: $ty,)*
+        }
+
+        impl $crate::packet::reader::Decode for $name {
+            fn decode(
+                cursor: &mut $crate::packet::reader::Reader<'_>,
+            ) -> Result<Self, $crate::packet::DecodeError> {
+                Ok($name {
+                    $($field: $crate::packet::reader::Decode::decode(cursor)?,)*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "decode")]
+pub(crate) use raw_struct;
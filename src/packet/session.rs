@@ -3,55 +3,59 @@
 //! The F1 games provide information about the current session, for example weather and temperature
 //! as well as settings like the type of safety car in use.
 
-use std::time::Duration;
+use alloc::vec::Vec;
+#[cfg(feature = "decode")]
+use core::convert::TryFrom;
+use core::time::Duration;
 
+#[cfg(feature = "decode")]
+use bytes::Bytes;
 use derive_new::new;
 use getset::{CopyGetters, Getters};
 
+#[cfg(feature = "decode")]
+use crate::nineteen::session::decode_session;
 use crate::packet::header::Header;
+#[cfg(feature = "decode")]
+use crate::packet::header::PacketType;
+#[cfg(feature = "decode")]
+use crate::packet::{decode_packet_as, DecodeError};
 use crate::types::{Flag, VehicleIndex};
 
 /// Types of formula racing supported by the F1 games
 ///
 /// The F1 games support different types of formula racing, with newer games typically supporting
 /// more than older games.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Formula {
     ClassicF1,
     GenericF1,
+    #[default]
     ModernF1,
     F2,
 }
 
-impl Default for Formula {
-    fn default() -> Self {
-        Formula::ModernF1
-    }
-}
-
 /// Safety car rules that can be set for a session
 ///
 /// The F1 games allow different rules to be configured for the safety car. Sessions can have no
 /// safety car at all, a virtual safety car, or a full safety car.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum SafetyCar {
     None,
+    #[default]
     Full,
     Virtual,
 }
 
-impl Default for SafetyCar {
-    fn default() -> Self {
-        SafetyCar::Full
-    }
-}
-
 /// Types of sessions
 ///
 /// F1 knows many different types of sessions. A typical race weekend consists of free practice,
 /// qualifying and a race, each of which can be divided into multiple sessions (e.g. first or second
 /// free practice).
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Session {
     OneShotQualifying,
     P1,
@@ -65,20 +69,16 @@ pub enum Session {
     ShortPractice,
     ShortQualifying,
     TimeTrial,
+    #[default]
     Unknown,
 }
 
-impl Default for Session {
-    fn default() -> Self {
-        Session::Unknown
-    }
-}
-
 /// Race tracks that are in the F1 games
 ///
 /// The F1 games feature a long list of race tracks that appear in the games. Not every track is
 /// available in every game.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Track {
     AbuDhabi,
     Austria,
@@ -105,21 +105,18 @@ pub enum Track {
     SuzukaShort,
     Texas,
     TexasShort,
+    #[default]
     Unknown,
 }
 
-impl Default for Track {
-    fn default() -> Self {
-        Track::Unknown
-    }
-}
-
 /// Weather conditions that can occur in a session
 ///
 /// The modern F1 games support changing weather conditions, though not every weather condition is
 /// supported by every game.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Weather {
+    #[default]
     Clear,
     LightCloud,
     Overcast,
@@ -128,10 +125,32 @@ pub enum Weather {
     Storm,
 }
 
-impl Default for Weather {
-    fn default() -> Self {
-        Weather::Clear
-    }
+/// A sample of the weather forecast for a session
+///
+/// Newer F1 games publish a forecast of the weather in the session packet. Each sample predicts
+/// the weather and temperatures for a session of the weekend at a time offset from now.
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, PartialOrd, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WeatherForecastSample {
+    /// Returns the type of session that the sample is for.
+    #[getset(get_copy = "pub")]
+    session_type: Session,
+
+    /// Returns the time offset of the sample from now.
+    #[getset(get = "pub")]
+    time_offset: Duration,
+
+    /// Returns the forecasted weather.
+    #[getset(get_copy = "pub")]
+    weather: Weather,
+
+    /// Returns the forecasted track temperature in degrees celsius.
+    #[getset(get_copy = "pub")]
+    track_temperature: i8,
+
+    /// Returns the forecasted air temperature in degrees celsius.
+    #[getset(get_copy = "pub")]
+    air_temperature: i8,
 }
 
 /// A marshal zone around the track and its current flags.
@@ -141,6 +160,7 @@ impl Default for Weather {
 /// updates. Each zone is represented by a struct containing the fraction of the race track's length
 /// where the zone starts, and any flag that is currently being shown there.
 #[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MarshalZone {
     /// Returns the start point of the marshal zone as a fraction of the race track's total length.
     #[getset(get_copy = "pub")]
@@ -157,9 +177,14 @@ pub struct MarshalZone {
 /// temperature as well as settings like the type of safety car in use.
 #[derive(new, Debug, CopyGetters, Getters, PartialEq, Clone, PartialOrd)]
 #[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct SessionPacket {
     /// Returns the packet header prefixing the session packet.
     #[getset(get = "pub")]
+    #[cfg_attr(
+        feature = "arbitrary",
+        arbitrary(with = |u| Header::arbitrary_of_type(u, crate::packet::header::PacketType::Session))
+    )]
     header: Header,
 
     /// Returns the current weather in the session.
@@ -233,4 +258,28 @@ pub struct SessionPacket {
     /// Returns whether the session is a multiplayer session.
     #[getset(get_copy = "pub")]
     network_session: bool,
+
+    /// Returns the weather forecast, or `None` if the game does not publish one.
+    #[getset(get = "pub")]
+    weather_forecast: Option<Vec<WeatherForecastSample>>,
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<&[u8]> for SessionPacket {
+    type Error = DecodeError;
+
+    /// Decode a session packet from the payload of a datagram.
+    fn try_from(datagram: &[u8]) -> Result<Self, Self::Error> {
+        decode_packet_as(datagram, PacketType::Session, decode_session)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<Bytes> for SessionPacket {
+    type Error = DecodeError;
+
+    /// Decode a session packet from the payload of a datagram.
+    fn try_from(datagram: Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(datagram.as_ref())
+    }
 }
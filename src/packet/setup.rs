@@ -3,10 +3,22 @@
 //! The F1 games publish data about the setups of all cars in a session. In multiplayer sessions,
 //! setups of other players are redacted to prevent anyone from gaining an unfair advantage.
 
+#[cfg(feature = "decode")]
+use core::convert::TryFrom;
+
+#[cfg(feature = "decode")]
+use bytes::Bytes;
 use derive_new::new;
 use getset::{CopyGetters, Getters};
 
+#[cfg(feature = "decode")]
+use crate::nineteen::setup::decode_setups;
 use crate::packet::header::Header;
+#[cfg(feature = "decode")]
+use crate::packet::header::PacketType;
+#[cfg(feature = "decode")]
+use crate::packet::{decode_packet_as, DecodeError};
+use crate::types::{Grid, VehicleIndex};
 
 /// Setup of a car
 ///
@@ -14,6 +26,7 @@ use crate::packet::header::Header;
 /// before leaving the garage.
 #[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, PartialOrd, Default)]
 #[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CarSetup {
     /// Returns the setting for the front wing aero.
     #[getset(get_copy = "pub")]
@@ -102,12 +115,60 @@ pub struct CarSetup {
 /// multiplayer sessions, the setups of other players are redacted to prevent anyone from gaining an
 /// unfair advantage.
 #[derive(new, Debug, Getters, PartialEq, Clone, PartialOrd)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CarSetupPacket {
     /// Returns the packet header prefixing the car setup packet.
     #[getset(get = "pub")]
+    #[cfg_attr(
+        feature = "arbitrary",
+        arbitrary(with = |u| Header::arbitrary_of_type(u, crate::packet::header::PacketType::Setup))
+    )]
     header: Header,
 
-    /// Returns the setups of all 20 cars in the session.
+    /// Returns the setups of the cars in the session.
     #[getset(get = "pub")]
-    setups: Vec<CarSetup>,
+    setups: Grid<CarSetup>,
+}
+
+impl CarSetupPacket {
+    /// Limit the setups to the cars that are active in the given mask.
+    pub(crate) fn limit_cars(mut self, active: &[bool]) -> Self {
+        self.setups = self.setups.limit(active);
+        self
+    }
+
+    /// Returns the setup of the car with the given vehicle index.
+    pub fn car(&self, vehicle_index: VehicleIndex) -> Option<&CarSetup> {
+        self.setups.car(vehicle_index)
+    }
+
+    /// Returns the setup of the player's car.
+    pub fn player(&self) -> Option<&CarSetup> {
+        self.car(self.header.player_car_index())
+    }
+
+    /// Returns the setup of the secondary player's car in split-screen sessions.
+    pub fn secondary_player(&self) -> Option<&CarSetup> {
+        self.car(self.header.secondary_player_car_index()?)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<&[u8]> for CarSetupPacket {
+    type Error = DecodeError;
+
+    /// Decode a car setup packet from the payload of a datagram.
+    fn try_from(datagram: &[u8]) -> Result<Self, Self::Error> {
+        decode_packet_as(datagram, PacketType::Setup, decode_setups)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<Bytes> for CarSetupPacket {
+    type Error = DecodeError;
+
+    /// Decode a car setup packet from the payload of a datagram.
+    fn try_from(datagram: Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(datagram.as_ref())
+    }
 }
@@ -3,19 +3,32 @@
 //! The F1 games provide detailed information about the status of each car in the session. The rate
 //! with which the data is provided can be configured in the in-game settings.
 
+#[cfg(feature = "decode")]
+use core::convert::TryFrom;
+
+#[cfg(feature = "decode")]
+use bytes::Bytes;
 use derive_new::new;
 use getset::{CopyGetters, Getters};
 
+#[cfg(feature = "decode")]
+use crate::nineteen::status::decode_statuses;
 use crate::packet::header::Header;
-use crate::types::{CornerProperty, Flag};
+#[cfg(feature = "decode")]
+use crate::packet::header::PacketType;
+#[cfg(feature = "decode")]
+use crate::packet::{decode_packet_as, DecodeError};
+use crate::types::{CornerProperty, Flag, Grid, VehicleIndex};
 
 /// Traction control settings
 ///
 /// Traction control is a driver assist that does only exist in-game, and not on an actual F1 car.
 /// It can be turned off, or switched between a low and high setting.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum TractionControl {
     /// Traction control is turned off.
+    #[default]
     Off,
 
     /// Traction control operates at a low setting, and offers only minor assists.
@@ -25,22 +38,18 @@ pub enum TractionControl {
     High,
 }
 
-impl Default for TractionControl {
-    fn default() -> Self {
-        TractionControl::Off
-    }
-}
-
 /// Fuel mix settings
 ///
 /// F1 cars can run on different fuel mixes, and drivers are often required to change the fuel mix
 /// during a race to save fuel or prevent the engine from overheating.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum FuelMix {
     /// The engine runs on a lean fuel mix.
     Lean,
 
     /// The engine runs on the standard fuel mix.
+    #[default]
     Standard,
 
     /// The engine runs on a rich fuel mix.
@@ -50,19 +59,15 @@ pub enum FuelMix {
     Max,
 }
 
-impl Default for FuelMix {
-    fn default() -> Self {
-        FuelMix::Standard
-    }
-}
-
 /// Setting of the Drag Reduction System
 ///
 /// The Drag Reduction System, or DRS, can be disabled and enabled during a race. When it is
 /// disabled, drivers cannot activate it.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum DrsSetting {
     ///  The DRS setting is unknown, for example because the current formula does not support it.
+    #[default]
     Unknown,
 
     /// DRS is disabled, and cannot be used by drivers.
@@ -72,12 +77,6 @@ pub enum DrsSetting {
     Allowed,
 }
 
-impl Default for DrsSetting {
-    fn default() -> Self {
-        DrsSetting::Unknown
-    }
-}
-
 /// Tyre compounds that influence the physical simulation
 ///
 /// The latest generations of F1 games started to distinguish between physical and visual tyre
@@ -88,10 +87,12 @@ impl Default for DrsSetting {
 ///
 /// For older games that do not know this distinction yet, the tyre compound is duplicated in both
 /// fields.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum PhysicalTyreCompound {
     ClassicDry,
     ClassicWet,
+    #[default]
     F1C1,
     F1C2,
     F1C3,
@@ -113,12 +114,6 @@ pub enum PhysicalTyreCompound {
     F2Wet,
 }
 
-impl Default for PhysicalTyreCompound {
-    fn default() -> Self {
-        PhysicalTyreCompound::F1C1
-    }
-}
-
 /// Tyre compounds that influence the visual appearance
 ///
 /// The latest generations of F1 games started to distinguish between physical and visual tyre
@@ -129,13 +124,15 @@ impl Default for PhysicalTyreCompound {
 ///
 /// For older games that do not know this distinction yet, the tyre compound is duplicated in both
 /// fields.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum VisualTyreCompound {
     ClassicDry,
     ClassicWet,
     F1HyperSoft,
     F1UltraSoft,
     F1SuperSoft,
+    #[default]
     F1Soft,
     F1Medium,
     F1Hard,
@@ -149,23 +146,19 @@ pub enum VisualTyreCompound {
     F2Wet,
 }
 
-impl Default for VisualTyreCompound {
-    fn default() -> Self {
-        VisualTyreCompound::F1Soft
-    }
-}
-
 /// Deploy modes for the Energy Recovery System
 ///
 /// The Energy Recovery System, or ERS, can be operated in different modes that determine how much
 /// energy is harvested under braking, and how much is used to accelerate the car.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum ErsDeployMode {
     /// The Energy Recovery System is disabled or does not exist in the current car.
     None,
 
     /// The Energy Recovery System operates at a low setting, and harvest more energy than it
     /// consumes.
+    #[default]
     Low,
 
     /// The Energy Recovery System operates at a medium setting, harvesting and deploying at a
@@ -185,12 +178,6 @@ pub enum ErsDeployMode {
     Hotlap,
 }
 
-impl Default for ErsDeployMode {
-    fn default() -> Self {
-        ErsDeployMode::Low
-    }
-}
-
 /// Data describing the status of a car
 ///
 /// The status of each car is a collection of properties that can change over time. It includes data
@@ -199,6 +186,7 @@ impl Default for ErsDeployMode {
 /// shown for the player's own car.
 #[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, PartialOrd, Default)]
 #[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CarStatus {
     /// Returns the traction control setting.
     #[getset(get_copy = "pub")]
@@ -314,12 +302,60 @@ pub struct CarStatus {
 /// The F1 games publish data on the status of each car in the session at a rate that can be
 /// configured in the in-game settings.
 #[derive(new, Debug, Getters, PartialEq, Clone, PartialOrd)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CarStatusPacket {
     /// Returns the packet header prefixing the car status packet.
     #[getset(get = "pub")]
+    #[cfg_attr(
+        feature = "arbitrary",
+        arbitrary(with = |u| Header::arbitrary_of_type(u, crate::packet::header::PacketType::Status))
+    )]
     header: Header,
 
     /// Returns the status of each car in the session.
     #[getset(get = "pub")]
-    statuses: Vec<CarStatus>,
+    statuses: Grid<CarStatus>,
+}
+
+impl CarStatusPacket {
+    /// Limit the statuses to the cars that are active in the given mask.
+    pub(crate) fn limit_cars(mut self, active: &[bool]) -> Self {
+        self.statuses = self.statuses.limit(active);
+        self
+    }
+
+    /// Returns the status of the car with the given vehicle index.
+    pub fn car(&self, vehicle_index: VehicleIndex) -> Option<&CarStatus> {
+        self.statuses.car(vehicle_index)
+    }
+
+    /// Returns the status of the player's car.
+    pub fn player(&self) -> Option<&CarStatus> {
+        self.car(self.header.player_car_index())
+    }
+
+    /// Returns the status of the secondary player's car in split-screen sessions.
+    pub fn secondary_player(&self) -> Option<&CarStatus> {
+        self.car(self.header.secondary_player_car_index()?)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<&[u8]> for CarStatusPacket {
+    type Error = DecodeError;
+
+    /// Decode a car status packet from the payload of a datagram.
+    fn try_from(datagram: &[u8]) -> Result<Self, Self::Error> {
+        decode_packet_as(datagram, PacketType::Status, decode_statuses)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<Bytes> for CarStatusPacket {
+    type Error = DecodeError;
+
+    /// Decode a car status packet from the payload of a datagram.
+    fn try_from(datagram: Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(datagram.as_ref())
+    }
 }
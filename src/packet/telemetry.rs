@@ -4,18 +4,34 @@
 //! includes physical properties of the car, e.g. its speed, but also information about the controls
 //! that are applied, e.g. which buttons are being pressed.
 
+#[cfg(feature = "decode")]
+use core::convert::TryFrom;
+
 use bitflags::bitflags;
+#[cfg(feature = "decode")]
+use bytes::Bytes;
 use derive_new::new;
 use getset::{CopyGetters, Getters};
 
+#[cfg(feature = "decode")]
+use crate::nineteen::telemetry::decode_telemetry;
 use crate::packet::header::Header;
-use crate::types::CornerProperty;
+#[cfg(feature = "decode")]
+use crate::packet::header::PacketType;
+#[cfg(feature = "decode")]
+use crate::packet::{decode_packet_as, DecodeError};
+use crate::types::{CornerProperty, Grid, VehicleIndex};
 
 bitflags! {
     /// A bit field with currently pressed buttons.
     ///
     /// The F1 games publish which buttons are currently being pressed by the user. This information
     /// is encoded in a bit field, where each bit represents a different button.
+    ///
+    /// Newer games define more buttons than F1 2019, including the UDP actions that players can map
+    /// to the buttons of their wheel. With these, every bit of the field is defined as a flag, so
+    /// that no button is lost when a bit field is decoded.
+    #[derive(Default)]
     pub struct Button: u32 {
         const NONE = 0x0;
         const CROSS_OR_A = 0x0001;
@@ -33,19 +49,39 @@ bitflags! {
         const R2_OR_RT = 0x1000;
         const LEFT_STICK_CLICK = 0x2000;
         const RIGHT_STICK_CLICK =0x4000;
+        const RIGHT_STICK_LEFT = 0x0000_8000;
+        const RIGHT_STICK_RIGHT = 0x0001_0000;
+        const RIGHT_STICK_UP = 0x0002_0000;
+        const RIGHT_STICK_DOWN = 0x0004_0000;
+        const SPECIAL = 0x0008_0000;
+        const UDP_ACTION_1 = 0x0010_0000;
+        const UDP_ACTION_2 = 0x0020_0000;
+        const UDP_ACTION_3 = 0x0040_0000;
+        const UDP_ACTION_4 = 0x0080_0000;
+        const UDP_ACTION_5 = 0x0100_0000;
+        const UDP_ACTION_6 = 0x0200_0000;
+        const UDP_ACTION_7 = 0x0400_0000;
+        const UDP_ACTION_8 = 0x0800_0000;
+        const UDP_ACTION_9 = 0x1000_0000;
+        const UDP_ACTION_10 = 0x2000_0000;
+        const UDP_ACTION_11 = 0x4000_0000;
+        const UDP_ACTION_12 = 0x8000_0000;
     }
 }
 
-impl Default for Button {
-    fn default() -> Self {
-        Button::NONE
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Button {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Button::from_bits_truncate(u32::arbitrary(u)?))
     }
 }
 
 /// Gears of a Formula One car
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Gear {
     Reverse = -1,
+    #[default]
     Neutral = 0,
     First = 1,
     Second = 2,
@@ -57,15 +93,11 @@ pub enum Gear {
     Eighth = 8,
 }
 
-impl Default for Gear {
-    fn default() -> Self {
-        Gear::Neutral
-    }
-}
-
 /// Surfaces that a tyre can come in contact with in the F1 games
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Surface {
+    #[default]
     Tarmac = 0,
     RumbleStrip = 1,
     Concrete = 2,
@@ -80,18 +112,13 @@ pub enum Surface {
     Ridged = 11,
 }
 
-impl Default for Surface {
-    fn default() -> Self {
-        Surface::Tarmac
-    }
-}
-
 /// Telemetry data coming from a car
 ///
 /// The telemetry data provided from the F1 games contains detailed, and quickly changing data on
 /// the inner mechanics of each car, e.g. its speed, engine RPMs, and temperatures.
 #[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, PartialOrd, Default)]
 #[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Telemetry {
     /// Returns the speed of the car in kilometers per hour.
     #[getset(get_copy = "pub")]
@@ -161,16 +188,64 @@ pub struct Telemetry {
 /// The F1 games publish telemetry data for each car in the session. The telemetry data includes
 /// parameters such as the car's speed, as well as information in controller inputs from the user.
 #[derive(new, Debug, CopyGetters, Getters, PartialEq, Clone, PartialOrd)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct TelemetryPacket {
     /// Returns the packet header prefixing the telemetry packet.
     #[getset(get = "pub")]
+    #[cfg_attr(
+        feature = "arbitrary",
+        arbitrary(with = |u| Header::arbitrary_of_type(u, crate::packet::header::PacketType::Telemetry))
+    )]
     header: Header,
 
     /// Returns the telemetry data for each car in the session.
     #[getset(get = "pub")]
-    telemetry: Vec<Telemetry>,
+    telemetry: Grid<Telemetry>,
 
     /// Returns a bit flag indicating which buttons are currently pressed.
     #[getset(get_copy = "pub")]
     button_status: Button,
 }
+
+impl TelemetryPacket {
+    /// Limit the telemetry to the cars that are active in the given mask.
+    pub(crate) fn limit_cars(mut self, active: &[bool]) -> Self {
+        self.telemetry = self.telemetry.limit(active);
+        self
+    }
+
+    /// Returns the telemetry of the car with the given vehicle index.
+    pub fn car(&self, vehicle_index: VehicleIndex) -> Option<&Telemetry> {
+        self.telemetry.car(vehicle_index)
+    }
+
+    /// Returns the telemetry of the player's car.
+    pub fn player(&self) -> Option<&Telemetry> {
+        self.car(self.header.player_car_index())
+    }
+
+    /// Returns the telemetry of the secondary player's car in split-screen sessions.
+    pub fn secondary_player(&self) -> Option<&Telemetry> {
+        self.car(self.header.secondary_player_car_index()?)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<&[u8]> for TelemetryPacket {
+    type Error = DecodeError;
+
+    /// Decode a telemetry packet from the payload of a datagram.
+    fn try_from(datagram: &[u8]) -> Result<Self, Self::Error> {
+        decode_packet_as(datagram, PacketType::Telemetry, decode_telemetry)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl TryFrom<Bytes> for TelemetryPacket {
+    type Error = DecodeError;
+
+    /// Decode a telemetry packet from the payload of a datagram.
+    fn try_from(datagram: Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(datagram.as_ref())
+    }
+}
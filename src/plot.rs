@@ -0,0 +1,347 @@
+//! Charts of a session
+//!
+//! Post-race reports of leagues show the same few charts again and again: the progression of the
+//! lap times, the speed of two laps over the distance, the temperatures of the tyres, and the
+//! positions of the cars over the race. This module builds these charts from the models in this
+//! crate, and renders them with `plotters` to SVG or PNG.
+//!
+//! Charts are created with one of the constructors of `Chart`, and then rendered to a string with
+//! `Chart::to_svg` or to a file with `Chart::save`. Text is rendered with the fonts that are
+//! installed on the system.
+
+use std::io::Error;
+use std::ops::{Range, RangeInclusive};
+use std::path::Path;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use crate::analysis::trace::LapTrace;
+use crate::query::{Channel, Corner, SessionData};
+use crate::tracker::SessionTracker;
+use crate::types::VehicleIndex;
+
+/// Size of charts in pixels by default
+const DEFAULT_SIZE: (u32, u32) = (1024, 640);
+
+/// Named line in a chart
+#[derive(new, Debug, Getters, PartialEq, Clone, PartialOrd, Default)]
+pub struct Line {
+    /// Returns the name of the line, which is shown in the legend.
+    #[getset(get = "pub")]
+    name: String,
+
+    /// Returns the points of the line.
+    #[getset(get = "pub")]
+    points: Vec<(f32, f32)>,
+}
+
+/// Line chart that can be rendered to SVG or PNG
+#[derive(Debug, Getters, CopyGetters, PartialEq, Clone, PartialOrd)]
+pub struct Chart {
+    /// Returns the title of the chart.
+    #[getset(get = "pub")]
+    title: String,
+
+    /// Returns the description of the x axis.
+    #[getset(get = "pub")]
+    x_label: String,
+
+    /// Returns the description of the y axis.
+    #[getset(get = "pub")]
+    y_label: String,
+
+    /// Returns the lines of the chart.
+    #[getset(get = "pub")]
+    lines: Vec<Line>,
+
+    /// Returns whether the y axis grows downwards, like positions do.
+    #[getset(get_copy = "pub")]
+    inverted: bool,
+
+    /// Returns the size of the chart in pixels.
+    #[getset(get_copy = "pub")]
+    size: (u32, u32),
+}
+
+impl Chart {
+    /// Create an empty chart with the given title and axis descriptions.
+    pub fn new(title: &str, x_label: &str, y_label: &str) -> Self {
+        Chart {
+            title: title.into(),
+            x_label: x_label.into(),
+            y_label: y_label.into(),
+            lines: Vec::new(),
+            inverted: false,
+            size: DEFAULT_SIZE,
+        }
+    }
+
+    /// Chart of the lap times of the given cars, by lap.
+    pub fn lap_times(tracker: &SessionTracker, cars: &[VehicleIndex]) -> Self {
+        let mut chart = Chart::new("Lap times", "Lap", "Lap time (s)");
+
+        for vehicle_index in cars {
+            if let Some(history) = tracker.car(*vehicle_index) {
+                let points = history
+                    .laps()
+                    .iter()
+                    .filter(|lap| !lap.lap_time().is_zero())
+                    .map(|lap| (lap.lap_number() as f32, lap.lap_time().as_secs_f32()))
+                    .collect();
+
+                chart = chart.line(Line::new(car_name(tracker, *vehicle_index), points));
+            }
+        }
+
+        chart
+    }
+
+    /// Chart of the positions of the given cars at the end of each lap.
+    pub fn positions(tracker: &SessionTracker, cars: &[VehicleIndex]) -> Self {
+        let mut chart = Chart::new("Positions", "Lap", "Position");
+        chart.inverted = true;
+
+        for vehicle_index in cars {
+            if let Some(history) = tracker.car(*vehicle_index) {
+                let points = history
+                    .laps()
+                    .iter()
+                    .filter(|lap| lap.position() > 0)
+                    .map(|lap| (lap.lap_number() as f32, lap.position() as f32))
+                    .collect();
+
+                chart = chart.line(Line::new(car_name(tracker, *vehicle_index), points));
+            }
+        }
+
+        chart
+    }
+
+    /// Chart of the speed of laps over the lap distance.
+    pub fn speed_overlay(traces: &[&LapTrace]) -> Self {
+        let mut chart = Chart::new("Speed", "Distance (m)", "Speed (km/h)");
+
+        for trace in traces {
+            let points = trace
+                .samples()
+                .iter()
+                .map(|sample| (sample.distance(), sample.speed()))
+                .collect();
+
+            chart = chart.line(Line::new(
+                format!("Car {} lap {}", trace.vehicle_index(), trace.lap_number()),
+                points,
+            ));
+        }
+
+        chart
+    }
+
+    /// Chart of the surface temperatures of the tyres of a car over a range of laps.
+    pub fn tyre_temperatures(
+        data: &SessionData,
+        vehicle_index: VehicleIndex,
+        laps: RangeInclusive<u8>,
+    ) -> Self {
+        let corners = [
+            ("Front left", Corner::FrontLeft),
+            ("Front right", Corner::FrontRight),
+            ("Rear left", Corner::RearLeft),
+            ("Rear right", Corner::RearRight),
+        ];
+
+        let channels: Vec<Channel> = corners
+            .iter()
+            .map(|(_, corner)| Channel::TyreSurfaceTemperature(*corner))
+            .collect();
+
+        let series = data.series(vehicle_index, &channels, laps);
+        let mut chart = Chart::new("Tyre temperatures", "Session time (s)", "Temperature (°C)");
+
+        for ((name, _), channel) in corners.iter().zip(&channels) {
+            let points = series
+                .session_time()
+                .iter()
+                .zip(series.column(*channel).unwrap_or_default())
+                .map(|(time, value)| (time.as_secs_f32(), *value))
+                .collect();
+
+            chart = chart.line(Line::new(name.to_string(), points));
+        }
+
+        chart
+    }
+
+    /// Add a line to the chart.
+    pub fn line(mut self, line: Line) -> Self {
+        self.lines.push(line);
+        self
+    }
+
+    /// Set the size of the chart in pixels.
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    /// Render the chart as SVG.
+    pub fn to_svg(&self) -> Result<String, Error> {
+        let mut svg = String::new();
+
+        {
+            let root = SVGBackend::with_string(&mut svg, self.size).into_drawing_area();
+            self.draw(&root)?;
+        }
+
+        Ok(svg)
+    }
+
+    /// Render the chart to a file.
+    ///
+    /// The chart is rendered as SVG if the path ends in `.svg`, and as PNG otherwise.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let is_svg = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+
+        if is_svg {
+            self.draw(&SVGBackend::new(path, self.size).into_drawing_area())
+        } else {
+            self.draw(&BitMapBackend::new(path, self.size).into_drawing_area())
+        }
+    }
+
+    fn draw<DB: DrawingBackend>(&self, root: &DrawingArea<DB, Shift>) -> Result<(), Error> {
+        root.fill(&WHITE).map_err(chart_error)?;
+
+        let x_range = self.bounds(|(x, _)| *x);
+        let y_range = self.bounds(|(_, y)| *y);
+        let y_range = if self.inverted {
+            y_range.end..y_range.start
+        } else {
+            y_range
+        };
+
+        let mut chart = ChartBuilder::on(root)
+            .caption(&self.title, ("sans-serif", 24))
+            .margin(16)
+            .x_label_area_size(40)
+            .y_label_area_size(56)
+            .build_cartesian_2d(x_range, y_range)
+            .map_err(chart_error)?;
+
+        chart
+            .configure_mesh()
+            .x_desc(self.x_label.as_str())
+            .y_desc(self.y_label.as_str())
+            .draw()
+            .map_err(chart_error)?;
+
+        for (index, line) in self.lines.iter().enumerate() {
+            let color = Palette99::pick(index).to_rgba();
+
+            chart
+                .draw_series(LineSeries::new(
+                    line.points
+                        .iter()
+                        .copied()
+                        .filter(|(x, y)| x.is_finite() && y.is_finite()),
+                    color.stroke_width(2),
+                ))
+                .map_err(chart_error)?
+                .label(line.name.as_str())
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 16, y)], color));
+        }
+
+        if !self.lines.is_empty() {
+            chart
+                .configure_series_labels()
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK)
+                .draw()
+                .map_err(chart_error)?;
+        }
+
+        root.present().map_err(chart_error)
+    }
+
+    /// Returns the range of the values of all lines, with a margin of 5% on both sides.
+    fn bounds<F: Fn(&(f32, f32)) -> f32>(&self, value: F) -> Range<f32> {
+        let (min, max) = self
+            .lines
+            .iter()
+            .flat_map(|line| line.points.iter())
+            .map(value)
+            .filter(|value| value.is_finite())
+            .fold((f32::MAX, f32::MIN), |(min, max), value| {
+                (min.min(value), max.max(value))
+            });
+
+        if min > max {
+            return 0.0..1.0;
+        }
+
+        let margin = ((max - min) * 0.05).max(0.5);
+        (min - margin)..(max + margin)
+    }
+}
+
+/// Returns the name of the driver of a car, or a generic name if the participants are unknown.
+fn car_name(tracker: &SessionTracker, vehicle_index: VehicleIndex) -> String {
+    tracker
+        .name(vehicle_index)
+        .map(String::from)
+        .unwrap_or_else(|| format!("Car {}", vehicle_index))
+}
+
+fn chart_error<E: std::error::Error + Send + Sync>(error: DrawingAreaErrorKind<E>) -> Error {
+    Error::other(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plot::{Chart, Line};
+    use crate::tracker::SessionTracker;
+
+    fn chart() -> Chart {
+        Chart::new("Lap times", "Lap", "Lap time (s)")
+            .line(Line::new(
+                "Car 0".into(),
+                vec![(1.0, 92.4), (2.0, 90.1), (3.0, 89.8)],
+            ))
+            .line(Line::new(
+                "Car 1".into(),
+                vec![(1.0, 93.0), (2.0, f32::NAN)],
+            ))
+            .with_size(640, 400)
+    }
+
+    #[test]
+    fn to_svg_renders_lines_and_legend() {
+        let svg = chart().to_svg().unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Lap times"));
+        assert!(svg.contains("Car 1"));
+        assert!(svg.contains("<polyline"));
+
+        assert!(Chart::positions(&SessionTracker::default(), &[0])
+            .to_svg()
+            .is_ok());
+    }
+
+    #[test]
+    fn save_renders_png() {
+        let path = std::env::temp_dir().join("f1-api-plot-test.png");
+
+        chart().save(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(b"\x89PNG", &bytes[..4]);
+    }
+}
@@ -0,0 +1,760 @@
+//! Persistence of sessions in PostgreSQL and TimescaleDB
+//!
+//! Leagues keep the history of a whole season to compare drivers across races. The
+//! `PostgresWriter` persists the completed laps, the events, the stints, and a sample of the
+//! telemetry of every car into four tables:
+//!
+//! - `f1_laps` with one row per completed lap and its sector times
+//! - `f1_events` with one row per event of the session
+//! - `f1_stints` with one row per stint, which is updated while the stint is ongoing
+//! - `f1_telemetry` with one row per car and sample, taken at a configurable interval
+//!
+//! The tables are created by `PostgresWriter::create_schema` if they do not exist, and upgraded if
+//! they have been created by an older version of this crate. When the TimescaleDB extension is
+//! installed, the telemetry table is turned into a hypertable partitioned by the time at which the
+//! samples were received.
+//!
+//! Rows are collected in memory and written in a single transaction once a batch is full, with one
+//! prepared statement per table that inserts all rows of the batch at once. Events that have
+//! already been written, e.g. when a session is imported twice, are skipped. The unique
+//! identifier of the session is stored as a `BIGINT` with the same bits as the unsigned identifier
+//! in the packets. The telemetry of cars whose drivers have restricted their telemetry is not
+//! persisted.
+
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, SystemTime};
+
+use tokio_postgres::{Client, NoTls, Statement};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::export::table;
+use crate::packet::event::{Event, EventPacket};
+use crate::packet::Packet;
+use crate::privacy::Privacy;
+use crate::sink::{ExportFuture, Exporter, Item};
+use crate::tracker::{LapRecord, SessionTracker, Stint};
+use crate::types::VehicleIndex;
+
+/// Number of rows that are collected before they are written
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// Interval in session time between two samples of the telemetry
+const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Version of the tables that are created by this crate
+const DATABASE_VERSION: i32 = 1;
+
+/// Statements that upgrade the tables from the version at their index plus one to the next version
+///
+/// Tables that do not exist yet are created in the latest version, so that the migrations only
+/// change tables that were created by an older version of this crate.
+const MIGRATIONS: [&str; DATABASE_VERSION as usize - 1] = [];
+
+/// Statements that create the tables, and the hypertable if TimescaleDB is installed
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS f1_schema (
+    version INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS f1_laps (
+    session_uid BIGINT NOT NULL,
+    vehicle_index SMALLINT NOT NULL,
+    lap_number SMALLINT NOT NULL,
+    lap_time DOUBLE PRECISION NOT NULL,
+    sector1_time DOUBLE PRECISION NOT NULL,
+    sector2_time DOUBLE PRECISION NOT NULL,
+    sector3_time DOUBLE PRECISION NOT NULL,
+    is_valid BOOLEAN NOT NULL,
+    position SMALLINT NOT NULL,
+    tyre_compound TEXT,
+    pit_stop BOOLEAN NOT NULL,
+    completed_at DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (session_uid, vehicle_index, lap_number)
+);
+
+CREATE TABLE IF NOT EXISTS f1_events (
+    session_uid BIGINT NOT NULL,
+    session_time DOUBLE PRECISION NOT NULL,
+    event TEXT NOT NULL,
+    vehicle_index SMALLINT,
+    lap_time DOUBLE PRECISION
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS f1_events_key
+    ON f1_events (session_uid, session_time, event, COALESCE(vehicle_index, -1));
+
+CREATE TABLE IF NOT EXISTS f1_stints (
+    session_uid BIGINT NOT NULL,
+    vehicle_index SMALLINT NOT NULL,
+    stint SMALLINT NOT NULL,
+    compound TEXT NOT NULL,
+    start_lap SMALLINT NOT NULL,
+    end_lap SMALLINT,
+    PRIMARY KEY (session_uid, vehicle_index, stint)
+);
+
+CREATE TABLE IF NOT EXISTS f1_telemetry (
+    time TIMESTAMPTZ NOT NULL,
+    session_uid BIGINT NOT NULL,
+    session_time DOUBLE PRECISION NOT NULL,
+    vehicle_index SMALLINT NOT NULL,
+    speed SMALLINT NOT NULL,
+    throttle REAL NOT NULL,
+    brake REAL NOT NULL,
+    steering REAL NOT NULL,
+    gear SMALLINT NOT NULL,
+    engine_rpm INTEGER NOT NULL,
+    drs BOOLEAN NOT NULL
+);
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb') THEN
+        PERFORM create_hypertable('f1_telemetry', 'time', if_not_exists => TRUE);
+    END IF;
+END
+$$;
+"#;
+
+/// Statements that insert the rows of a batch, with one array per column and a single session
+const INSERT_LAPS: &str = "INSERT INTO f1_laps \
+    SELECT $1, * FROM UNNEST($2::SMALLINT[], $3::SMALLINT[], $4::DOUBLE PRECISION[], \
+    $5::DOUBLE PRECISION[], $6::DOUBLE PRECISION[], $7::DOUBLE PRECISION[], $8::BOOLEAN[], \
+    $9::SMALLINT[], $10::TEXT[], $11::BOOLEAN[], $12::DOUBLE PRECISION[]) \
+    ON CONFLICT DO NOTHING";
+
+const INSERT_EVENTS: &str = "INSERT INTO f1_events \
+    SELECT $1, * FROM UNNEST($2::DOUBLE PRECISION[], $3::TEXT[], $4::SMALLINT[], \
+    $5::DOUBLE PRECISION[]) \
+    ON CONFLICT DO NOTHING";
+
+const UPSERT_STINTS: &str = "INSERT INTO f1_stints \
+    SELECT $1, * FROM UNNEST($2::SMALLINT[], $3::SMALLINT[], $4::TEXT[], $5::SMALLINT[], \
+    $6::SMALLINT[]) \
+    ON CONFLICT (session_uid, vehicle_index, stint) DO UPDATE \
+    SET compound = EXCLUDED.compound, start_lap = EXCLUDED.start_lap, end_lap = EXCLUDED.end_lap";
+
+const INSERT_TELEMETRY: &str = "INSERT INTO f1_telemetry \
+    SELECT sample.time, $1, sample.session_time, sample.vehicle_index, sample.speed, \
+    sample.throttle, sample.brake, sample.steering, sample.gear, sample.engine_rpm, sample.drs \
+    FROM UNNEST($2::TIMESTAMPTZ[], $3::DOUBLE PRECISION[], $4::SMALLINT[], $5::SMALLINT[], \
+    $6::REAL[], $7::REAL[], $8::REAL[], $9::SMALLINT[], $10::INTEGER[], $11::BOOLEAN[]) \
+    AS sample(time, session_time, vehicle_index, speed, throttle, brake, steering, gear, \
+    engine_rpm, drs)";
+
+/// Prepared statements for the rows of a batch
+#[derive(Debug, Clone)]
+struct Statements {
+    laps: Statement,
+    events: Statement,
+    stints: Statement,
+    telemetry: Statement,
+}
+
+impl Statements {
+    async fn prepare(client: &Client) -> Result<Self, Error> {
+        Ok(Statements {
+            laps: client.prepare(INSERT_LAPS).await.map_err(Error::other)?,
+            events: client.prepare(INSERT_EVENTS).await.map_err(Error::other)?,
+            stints: client.prepare(UPSERT_STINTS).await.map_err(Error::other)?,
+            telemetry: client
+                .prepare(INSERT_TELEMETRY)
+                .await
+                .map_err(Error::other)?,
+        })
+    }
+}
+
+/// Completed lap of a car
+#[derive(Debug, PartialEq, Clone)]
+struct LapRow {
+    vehicle_index: VehicleIndex,
+    lap: LapRecord,
+}
+
+/// Event in the session
+#[derive(Debug, PartialEq, Clone)]
+struct EventRow {
+    session_time: f64,
+    event: String,
+    vehicle_index: Option<i16>,
+    lap_time: Option<f64>,
+}
+
+/// Stint of a car that has started or changed
+#[derive(Debug, PartialEq, Clone)]
+struct StintRow {
+    vehicle_index: VehicleIndex,
+    stint: usize,
+    data: Stint,
+}
+
+/// Sample of the telemetry of a car
+#[derive(Debug, PartialEq, Clone)]
+struct TelemetryRow {
+    time: SystemTime,
+    session_time: f64,
+    vehicle_index: VehicleIndex,
+    speed: u16,
+    throttle: f32,
+    brake: f32,
+    steering: f32,
+    gear: i8,
+    engine_rpm: u16,
+    drs: bool,
+}
+
+/// Rows that have not been written yet
+#[derive(Debug, PartialEq, Clone, Default)]
+struct Batch {
+    session_uid: u64,
+    laps: Vec<LapRow>,
+    events: Vec<EventRow>,
+    stints: Vec<StintRow>,
+    telemetry: Vec<TelemetryRow>,
+}
+
+impl Batch {
+    fn len(&self) -> usize {
+        self.laps.len() + self.events.len() + self.stints.len() + self.telemetry.len()
+    }
+}
+
+/// Collector of the rows that are derived from the packets
+#[derive(Debug, Default)]
+struct Collector {
+    tracker: SessionTracker,
+    privacy: Privacy,
+    sample_interval: Duration,
+    last_sample: Option<Duration>,
+    written_laps: Vec<usize>,
+    written_stints: Vec<Vec<Stint>>,
+    batch: Batch,
+}
+
+impl Collector {
+    fn new(sample_interval: Duration) -> Self {
+        Collector {
+            sample_interval,
+            ..Default::default()
+        }
+    }
+
+    /// Update the models with the packet, and collect the rows that it completes.
+    ///
+    /// A packet from a new session returns the rows of the previous session, which must be written
+    /// before the rows of the new session are collected.
+    fn update(&mut self, packet: &Packet) -> Option<Batch> {
+        let session_uid = packet.header().session_uid();
+        let mut finished = None;
+
+        if self.tracker.session_uid() != Some(session_uid) {
+            let previous = std::mem::take(&mut self.batch);
+            if previous.len() > 0 {
+                finished = Some(previous);
+            }
+
+            *self = Collector::new(self.sample_interval);
+            self.batch.session_uid = session_uid;
+        }
+
+        self.tracker.update(packet);
+        self.privacy.update(packet);
+
+        match packet {
+            Packet::Event(event) => self.collect_event(event),
+            Packet::Lap(_) | Packet::Status(_) => self.collect_history(),
+            Packet::Telemetry(telemetry) => {
+                let session_time = *telemetry.header().session_time();
+
+                let due = match self.last_sample {
+                    Some(last) => session_time >= last + self.sample_interval,
+                    None => true,
+                };
+
+                if due {
+                    self.collect_telemetry(packet);
+                    self.last_sample = Some(session_time);
+                }
+            }
+            _ => {}
+        }
+
+        finished
+    }
+
+    fn collect_event(&mut self, packet: &EventPacket) {
+        let (vehicle_index, lap_time) = match packet.event() {
+            Event::FastestLap(lap) => (Some(lap.vehicle_index()), Some(lap.time().as_secs_f64())),
+            Event::RaceWinner(winner) => (Some(winner.vehicle_index()), None),
+            Event::Retirement(retirement) => (Some(retirement.vehicle_index()), None),
+            Event::TeammatesInPits(teammate) => (Some(teammate.vehicle_index()), None),
+            Event::ChequeredFlag
+            | Event::DrsDisabled
+            | Event::DrsEnabled
+            | Event::SessionEnded
+            | Event::SessionStarted => (None, None),
+        };
+
+        self.batch.events.push(EventRow {
+            session_time: packet.header().session_time().as_secs_f64(),
+            event: table::event_name(packet.event()).to_string(),
+            vehicle_index: vehicle_index.map(i16::from),
+            lap_time,
+        });
+    }
+
+    fn collect_history(&mut self) {
+        let cars = self.tracker.cars();
+
+        self.written_laps.resize(cars.len(), 0);
+        self.written_stints.resize(cars.len(), Vec::new());
+
+        for (index, car) in cars.iter().enumerate() {
+            let vehicle_index = index as VehicleIndex;
+
+            for lap in &car.laps()[self.written_laps[index]..] {
+                self.batch.laps.push(LapRow {
+                    vehicle_index,
+                    lap: *lap,
+                });
+            }
+            self.written_laps[index] = car.laps().len();
+
+            for (stint, data) in car.stints().iter().enumerate() {
+                if self.written_stints[index].get(stint) != Some(data) {
+                    self.batch.stints.push(StintRow {
+                        vehicle_index,
+                        stint,
+                        data: *data,
+                    });
+                }
+            }
+            self.written_stints[index] = car.stints().clone();
+        }
+    }
+
+    fn collect_telemetry(&mut self, packet: &Packet) {
+        let packet = match packet {
+            Packet::Telemetry(packet) => packet,
+            _ => return,
+        };
+
+        let time = SystemTime::now();
+        let session_time = packet.header().session_time().as_secs_f64();
+
+        for (index, telemetry) in self.privacy.telemetry(packet).into_iter().enumerate() {
+            let telemetry = match telemetry {
+                Some(telemetry) => telemetry,
+                None => continue,
+            };
+
+            self.batch.telemetry.push(TelemetryRow {
+                time,
+                session_time,
+                vehicle_index: index as VehicleIndex,
+                speed: telemetry.speed(),
+                throttle: telemetry.throttle(),
+                brake: telemetry.brake(),
+                steering: telemetry.steering(),
+                gear: telemetry.gear() as i8,
+                engine_rpm: telemetry.engine_rpm(),
+                drs: telemetry.drs(),
+            });
+        }
+    }
+}
+
+/// Batched writer of sessions into PostgreSQL
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use f1_api::postgres::PostgresWriter;
+/// use f1_api::F1;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let game = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+///
+/// let mut writer = PostgresWriter::connect("host=localhost user=league dbname=season")
+///     .await
+///     .unwrap();
+///
+/// writer.create_schema().await.unwrap();
+/// writer.serve(F1::stream(game).unwrap()).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PostgresWriter {
+    client: Client,
+    statements: Option<Statements>,
+    collector: Collector,
+    batch_size: usize,
+}
+
+impl PostgresWriter {
+    /// Connect to the database with the given connection string, without TLS.
+    pub async fn connect(config: &str) -> Result<Self, Error> {
+        let (client, connection) = tokio_postgres::connect(config, NoTls)
+            .await
+            .map_err(Error::other)?;
+
+        tokio::spawn(connection);
+
+        Ok(PostgresWriter::new(client))
+    }
+
+    /// Create a writer with an existing client, e.g. with a TLS connection.
+    pub fn new(client: Client) -> Self {
+        PostgresWriter {
+            client,
+            statements: None,
+            collector: Collector::new(DEFAULT_SAMPLE_INTERVAL),
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Write the rows once the given number of rows has been collected.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Sample the telemetry at the given interval in session time.
+    pub fn sample_interval(mut self, interval: Duration) -> Self {
+        self.collector.sample_interval = interval;
+        self
+    }
+
+    /// Create the tables if they do not exist, and upgrade tables of an older version.
+    ///
+    /// The version of the tables is stored in the table `f1_schema`. Tables that have been created
+    /// by a newer version of this crate are not changed, and return an error.
+    pub async fn create_schema(&mut self) -> Result<(), Error> {
+        let transaction = self.client.transaction().await.map_err(Error::other)?;
+        transaction
+            .batch_execute(SCHEMA)
+            .await
+            .map_err(Error::other)?;
+
+        let version: Option<i32> = transaction
+            .query_opt("SELECT version FROM f1_schema", &[])
+            .await
+            .map_err(Error::other)?
+            .map(|row| row.get(0));
+
+        match version {
+            None => {
+                transaction
+                    .execute("INSERT INTO f1_schema VALUES ($1)", &[&DATABASE_VERSION])
+                    .await
+                    .map_err(Error::other)?;
+            }
+            Some(version) if version > DATABASE_VERSION => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Database schema version {} is newer than the supported version {}.",
+                        version, DATABASE_VERSION
+                    ),
+                ));
+            }
+            Some(version) => {
+                for migration in &MIGRATIONS[(version.max(1) - 1) as usize..] {
+                    transaction
+                        .batch_execute(migration)
+                        .await
+                        .map_err(Error::other)?;
+                }
+
+                transaction
+                    .execute("UPDATE f1_schema SET version = $1", &[&DATABASE_VERSION])
+                    .await
+                    .map_err(Error::other)?;
+            }
+        }
+
+        transaction.commit().await.map_err(Error::other)
+    }
+
+    /// Collect the rows of the packet, and write them if the batch is full.
+    pub async fn write(&mut self, packet: &Packet) -> Result<(), Error> {
+        if let Some(batch) = self.collector.update(packet) {
+            self.write_batch(batch).await?;
+        }
+
+        if self.collector.batch.len() >= self.batch_size {
+            self.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the rows that have been collected.
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        let session_uid = self.collector.batch.session_uid;
+        let batch = std::mem::replace(
+            &mut self.collector.batch,
+            Batch {
+                session_uid,
+                ..Default::default()
+            },
+        );
+
+        self.write_batch(batch).await
+    }
+
+    /// Write the packets of the stream until it ends, and flush the remaining rows.
+    pub async fn serve<S>(mut self, packets: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Packet> + Unpin,
+    {
+        let mut packets = packets;
+
+        while let Some(packet) = packets.next().await {
+            self.write(&packet).await?;
+        }
+
+        self.flush().await
+    }
+
+    /// Write the rows of the batch in a single transaction, with one statement per table.
+    async fn write_batch(&mut self, batch: Batch) -> Result<(), Error> {
+        if batch.len() == 0 {
+            return Ok(());
+        }
+
+        let statements = match &self.statements {
+            Some(statements) => statements.clone(),
+            None => {
+                let statements = Statements::prepare(&self.client).await?;
+                self.statements = Some(statements.clone());
+                statements
+            }
+        };
+
+        let session_uid = batch.session_uid as i64;
+        let transaction = self.client.transaction().await.map_err(Error::other)?;
+
+        if !batch.laps.is_empty() {
+            let laps = &batch.laps;
+
+            transaction
+                .execute(
+                    &statements.laps,
+                    &[
+                        &session_uid,
+                        &column(laps, |row| row.vehicle_index as i16),
+                        &column(laps, |row| row.lap.lap_number() as i16),
+                        &column(laps, |row| row.lap.lap_time().as_secs_f64()),
+                        &column(laps, |row| row.lap.sector1_time().as_secs_f64()),
+                        &column(laps, |row| row.lap.sector2_time().as_secs_f64()),
+                        &column(laps, |row| row.lap.sector3_time().as_secs_f64()),
+                        &column(laps, |row| row.lap.is_valid()),
+                        &column(laps, |row| row.lap.position() as i16),
+                        &column(laps, |row| {
+                            row.lap
+                                .tyre_compound()
+                                .map(|compound| format!("{:?}", compound))
+                        }),
+                        &column(laps, |row| row.lap.pit_stop()),
+                        &column(laps, |row| row.lap.completed_at().as_secs_f64()),
+                    ],
+                )
+                .await
+                .map_err(Error::other)?;
+        }
+
+        if !batch.events.is_empty() {
+            let events = &batch.events;
+
+            transaction
+                .execute(
+                    &statements.events,
+                    &[
+                        &session_uid,
+                        &column(events, |row| row.session_time),
+                        &column(events, |row| row.event.clone()),
+                        &column(events, |row| row.vehicle_index),
+                        &column(events, |row| row.lap_time),
+                    ],
+                )
+                .await
+                .map_err(Error::other)?;
+        }
+
+        if !batch.stints.is_empty() {
+            let stints = latest_stints(&batch.stints);
+
+            transaction
+                .execute(
+                    &statements.stints,
+                    &[
+                        &session_uid,
+                        &column(&stints, |row| row.vehicle_index as i16),
+                        &column(&stints, |row| row.stint as i16),
+                        &column(&stints, |row| format!("{:?}", row.data.compound())),
+                        &column(&stints, |row| row.data.start_lap() as i16),
+                        &column(&stints, |row| row.data.end_lap().map(i16::from)),
+                    ],
+                )
+                .await
+                .map_err(Error::other)?;
+        }
+
+        if !batch.telemetry.is_empty() {
+            let telemetry = &batch.telemetry;
+
+            transaction
+                .execute(
+                    &statements.telemetry,
+                    &[
+                        &session_uid,
+                        &column(telemetry, |row| row.time),
+                        &column(telemetry, |row| row.session_time),
+                        &column(telemetry, |row| row.vehicle_index as i16),
+                        &column(telemetry, |row| row.speed as i16),
+                        &column(telemetry, |row| row.throttle),
+                        &column(telemetry, |row| row.brake),
+                        &column(telemetry, |row| row.steering),
+                        &column(telemetry, |row| row.gear as i16),
+                        &column(telemetry, |row| row.engine_rpm as i32),
+                        &column(telemetry, |row| row.drs),
+                    ],
+                )
+                .await
+                .map_err(Error::other)?;
+        }
+
+        transaction.commit().await.map_err(Error::other)
+    }
+}
+
+/// Packets are collected and written in batches, while changes are ignored.
+impl Exporter for PostgresWriter {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        Box::pin(async move {
+            match item {
+                Item::Packet(packet) => self.write(packet).await,
+                Item::Change(..) => Ok(()),
+            }
+        })
+    }
+
+    fn flush(&mut self) -> ExportFuture<'_> {
+        Box::pin(PostgresWriter::flush(self))
+    }
+}
+
+/// Returns the values of a column of the rows, which are sent to the database as an array.
+fn column<R, T, F: Fn(&R) -> T>(rows: &[R], value: F) -> Vec<T> {
+    rows.iter().map(value).collect()
+}
+
+/// Returns the latest row of every stint, since a stint can only be upserted once per statement.
+fn latest_stints(rows: &[StintRow]) -> Vec<StintRow> {
+    let mut stints: Vec<StintRow> = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        match stints
+            .iter_mut()
+            .find(|stint| (stint.vehicle_index, stint.stint) == (row.vehicle_index, row.stint))
+        {
+            Some(stint) => *stint = row.clone(),
+            None => stints.push(row.clone()),
+        }
+    }
+
+    stints
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures;
+    use crate::packet::event::{Event, EventPacket, FastestLap};
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::status::VisualTyreCompound;
+    use crate::packet::Packet;
+    use crate::postgres::{latest_stints, Collector, StintRow};
+    use crate::tracker::Stint;
+
+    #[test]
+    fn collector_samples_telemetry() {
+        let packets = fixtures::packets(ApiSpec::Nineteen, PacketType::Telemetry).unwrap();
+        let mut collector = Collector::new(Duration::from_secs(3600));
+
+        for packet in &packets {
+            assert!(collector.update(packet).is_none());
+        }
+
+        assert_eq!(20, collector.batch.telemetry.len());
+        assert_eq!(
+            packets[0].header().session_uid(),
+            collector.batch.session_uid
+        );
+    }
+
+    #[test]
+    fn collector_collects_events() {
+        let packets = fixtures::packets(ApiSpec::Nineteen, PacketType::Event).unwrap();
+        let mut collector = Collector::new(Duration::from_secs(1));
+
+        for packet in &packets {
+            collector.update(packet);
+        }
+
+        assert_eq!(packets.len(), collector.batch.events.len());
+    }
+
+    #[test]
+    fn collector_collects_fastest_lap() {
+        let mut collector = Collector::new(Duration::from_secs(1));
+
+        collector.update(&Packet::Event(EventPacket::new(
+            fixtures::header(PacketType::Event, 1, Duration::from_secs(90), 0),
+            Event::FastestLap(FastestLap::new(7, Duration::from_millis(81_500))),
+        )));
+
+        let event = &collector.batch.events[0];
+
+        assert_eq!("FastestLap", event.event);
+        assert_eq!(Some(7), event.vehicle_index);
+        assert_eq!(Some(81.5), event.lap_time);
+    }
+
+    #[test]
+    fn latest_stints_keeps_last_row_of_stint() {
+        let started = Stint::new(VisualTyreCompound::F1Soft, 1, None);
+        let ended = Stint::new(VisualTyreCompound::F1Soft, 1, Some(12));
+        let next = Stint::new(VisualTyreCompound::F1Medium, 13, None);
+
+        let rows = vec![
+            StintRow {
+                vehicle_index: 0,
+                stint: 0,
+                data: started,
+            },
+            StintRow {
+                vehicle_index: 0,
+                stint: 0,
+                data: ended,
+            },
+            StintRow {
+                vehicle_index: 0,
+                stint: 1,
+                data: next,
+            },
+        ];
+
+        let stints = latest_stints(&rows);
+
+        assert_eq!(2, stints.len());
+        assert_eq!(ended, stints[0].data);
+        assert_eq!(next, stints[1].data);
+    }
+}
@@ -0,0 +1,404 @@
+//! Telemetry privacy of the participants in a session
+//!
+//! In multiplayer sessions, players can restrict the telemetry of their car. The game still sends
+//! data for restricted cars, but fills it with zeros. Since zeros are valid values for most fields,
+//! the restricted data is indistinguishable from real data without looking up the privacy setting
+//! of each car in the participants packet.
+//!
+//! `Privacy` remembers the privacy settings of the participants, and hides restricted data from
+//! the application. It can also redact packets before they are republished, for example by a relay
+//! that shares a session with spectators. Redacted packets carry a mask of the redacted cars, since
+//! their default values would otherwise look like real data again.
+
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+
+use derive_new::new;
+use getset::Getters;
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::header::Header;
+use crate::packet::participants::TelemetryPrivacy;
+use crate::packet::setup::{CarSetup, CarSetupPacket};
+use crate::packet::status::{CarStatus, CarStatusPacket};
+use crate::packet::telemetry::{Telemetry, TelemetryPacket};
+use crate::packet::Packet;
+use crate::types::{Grid, VehicleIndex};
+
+/// Privacy settings of the participants in a session
+///
+/// The settings are learned from the participants packets, and reset when a new session starts.
+/// Until a participants packet has been received, no car is considered restricted.
+///
+/// The data of the player's car is always sent by the game, even if the player has restricted
+/// their telemetry. The same applies to the secondary player's car in split-screen sessions. The
+/// accessors for telemetry, status, and setup data therefore return the data of the players' cars,
+/// while `redact` hides it like the data of any other restricted car.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::packet::Packet;
+/// use f1_api::privacy::Privacy;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut privacy = Privacy::default();
+///
+/// for packet in packets() {
+///     privacy.update(&packet);
+///
+///     if let Packet::Telemetry(telemetry) = &packet {
+///         for (index, car) in privacy.telemetry(telemetry).iter().enumerate() {
+///             match car {
+///                 Some(car) => println!("Car {}: {} km/h", index, car.speed()),
+///                 None => println!("Car {}: restricted", index),
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Privacy {
+    session_uid: Option<u64>,
+    restricted: Vec<bool>,
+}
+
+impl Privacy {
+    /// Update the privacy settings with a new packet.
+    pub fn update(&mut self, packet: &Packet) {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.restricted.clear();
+        }
+
+        if let Packet::Participants(packet) = packet {
+            self.restricted = packet
+                .participants()
+                .iter()
+                .map(|participant| {
+                    participant.telemetry_privacy() == Some(TelemetryPrivacy::Restricted)
+                })
+                .collect();
+        }
+    }
+
+    /// Returns whether a car's telemetry is restricted.
+    pub fn is_restricted(&self, vehicle_index: VehicleIndex) -> bool {
+        self.restricted
+            .get(vehicle_index as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Returns the telemetry of each slot, or `None` if it is restricted or has no active car.
+    pub fn telemetry<'a>(&self, packet: &'a TelemetryPacket) -> Vec<Option<&'a Telemetry>> {
+        self.filter(packet.telemetry(), packet.header())
+    }
+
+    /// Returns the status of each slot, or `None` if it is restricted or has no active car.
+    pub fn statuses<'a>(&self, packet: &'a CarStatusPacket) -> Vec<Option<&'a CarStatus>> {
+        self.filter(packet.statuses(), packet.header())
+    }
+
+    /// Returns the setup of each slot, or `None` if it is restricted or has no active car.
+    pub fn setups<'a>(&self, packet: &'a CarSetupPacket) -> Vec<Option<&'a CarSetup>> {
+        self.filter(packet.setups(), packet.header())
+    }
+
+    /// Returns a copy of the packet with the data of restricted cars removed.
+    ///
+    /// The redacted packet carries a mask of the cars whose data has been removed, so that
+    /// consumers can tell them apart from cars with real data. See `redact_packet` for details.
+    pub fn redact(&self, packet: &Packet) -> RedactedPacket {
+        let redacted = match packet {
+            Packet::Setup(packet) => self.redacted_cars(packet.setups()),
+            Packet::Status(packet) => self.redacted_cars(packet.statuses()),
+            Packet::Telemetry(packet) => self.redacted_cars(packet.telemetry()),
+            _ => Vec::new(),
+        };
+
+        RedactedPacket::new(self.redact_packet(packet), redacted)
+    }
+
+    /// Returns a copy of the packet with the data of restricted cars replaced by default values.
+    ///
+    /// The telemetry, status, and setup of restricted cars are replaced with their default values,
+    /// like the game does for restricted cars of remote players. Unlike the accessors, the player's
+    /// car is redacted as well if the player has restricted their telemetry. Other packets are
+    /// returned unchanged.
+    ///
+    /// The default values look like real data, so this method is only meant for republishing
+    /// packets in the format of the game. Consumers of the redacted data should use `redact`
+    /// instead, which marks the redacted cars.
+    pub fn redact_packet(&self, packet: &Packet) -> Packet {
+        match packet {
+            Packet::Setup(packet) => Packet::Setup(CarSetupPacket::new(
+                *packet.header(),
+                self.redact_cars(packet.setups()),
+            )),
+            Packet::Status(packet) => Packet::Status(CarStatusPacket::new(
+                *packet.header(),
+                self.redact_cars(packet.statuses()),
+            )),
+            Packet::Telemetry(packet) => Packet::Telemetry(TelemetryPacket::new(
+                *packet.header(),
+                self.redact_cars(packet.telemetry()),
+                packet.button_status(),
+            )),
+            packet => packet.clone(),
+        }
+    }
+
+    fn filter<'a, T>(&self, cars: &'a Grid<T>, header: &Header) -> Vec<Option<&'a T>> {
+        (0..cars.len())
+            .map(|index| {
+                let index = index as VehicleIndex;
+                let is_player = index == header.player_car_index()
+                    || Some(index) == header.secondary_player_car_index();
+
+                if !is_player && self.is_restricted(index) {
+                    None
+                } else {
+                    cars.car(index)
+                }
+            })
+            .collect()
+    }
+
+    fn redacted_cars<T>(&self, cars: &Grid<T>) -> Vec<bool> {
+        (0..cars.len())
+            .map(|index| self.is_restricted(index as VehicleIndex))
+            .collect()
+    }
+
+    fn redact_cars<T: Default + Clone>(&self, cars: &Grid<T>) -> Grid<T> {
+        let slots = cars
+            .iter()
+            .enumerate()
+            .map(|(index, car)| {
+                if self.is_restricted(index as VehicleIndex) {
+                    T::default()
+                } else {
+                    car.clone()
+                }
+            })
+            .collect();
+
+        Grid::new(slots).limit(cars.active())
+    }
+}
+
+/// Packet with the data of restricted cars removed
+///
+/// The data of redacted cars is replaced with default values, which cannot be told apart from real
+/// data. The packet therefore carries a mask with the cars whose data has been redacted.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::packet::Packet;
+/// use f1_api::privacy::Privacy;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut privacy = Privacy::default();
+///
+/// for packet in packets() {
+///     privacy.update(&packet);
+///     let redacted = privacy.redact(&packet);
+///
+///     if let Packet::Telemetry(telemetry) = redacted.packet() {
+///         for (index, car) in telemetry.telemetry().iter_active() {
+///             if !redacted.is_redacted(index) {
+///                 println!("Car {}: {} km/h", index, car.speed());
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(new, Debug, Getters, PartialEq, Clone)]
+pub struct RedactedPacket {
+    /// Returns the packet with the data of restricted cars replaced by default values.
+    #[getset(get = "pub")]
+    packet: Packet,
+
+    /// Returns for each car whether its data has been redacted.
+    ///
+    /// The mask is empty for packets that do not contain data of individual cars.
+    #[getset(get = "pub")]
+    redacted: Vec<bool>,
+}
+
+impl RedactedPacket {
+    /// Returns whether the data of a car has been redacted.
+    pub fn is_redacted(&self, vehicle_index: VehicleIndex) -> bool {
+        self.redacted
+            .get(vehicle_index as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Drop the mask and return the redacted packet, e.g. to republish it.
+    pub fn into_packet(self) -> Packet {
+        self.packet
+    }
+}
+
+/// Stream adapter that redacts the data of restricted cars
+///
+/// The adapter is created by `PacketStreamExt::redact_restricted`. It yields a `RedactedPacket` for
+/// each packet, which marks the cars whose data has been redacted.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct RedactRestricted<S> {
+    stream: S,
+    privacy: Privacy,
+}
+
+#[cfg(feature = "net")]
+impl<S> RedactRestricted<S> {
+    pub(crate) fn new(stream: S) -> Self {
+        RedactRestricted {
+            stream,
+            privacy: Privacy::default(),
+        }
+    }
+
+    /// Returns the privacy settings that the adapter has learned.
+    pub fn privacy(&self) -> &Privacy {
+        &self.privacy
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for RedactRestricted<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = RedactedPacket;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        Pin::new(&mut this.stream).poll_next(cx).map(|packet| {
+            packet.map(|packet| {
+                this.privacy.update(&packet);
+                this.privacy.redact(&packet)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{
+        header, participant, participants_packet, setup, setup_packet, with_secondary_player,
+    };
+    use crate::packet::header::PacketType;
+    use crate::packet::participants::TelemetryPrivacy;
+    use crate::packet::setup::{CarSetup, CarSetupPacket};
+    use crate::packet::Packet;
+    use crate::privacy::Privacy;
+
+    fn setups() -> Packet {
+        setup_packet(
+            header(PacketType::Setup, 1, Duration::default(), 0),
+            vec![setup(); 2],
+        )
+    }
+
+    fn restricted() -> Packet {
+        participants_packet(
+            header(PacketType::Participants, 1, Duration::default(), 0),
+            vec![
+                participant("Player", TelemetryPrivacy::Restricted),
+                participant("Player", TelemetryPrivacy::Restricted),
+            ],
+        )
+    }
+
+    #[test]
+    fn setups_of_player_and_restricted_car() {
+        let mut privacy = Privacy::default();
+        privacy.update(&restricted());
+
+        let packet = CarSetupPacket::new(
+            header(PacketType::Setup, 1, Duration::default(), 0),
+            vec![setup(); 2].into(),
+        );
+        let setups = privacy.setups(&packet);
+
+        assert!(setups[0].is_some());
+        assert!(setups[1].is_none());
+    }
+
+    #[test]
+    fn setups_of_split_screen_players() {
+        let mut privacy = Privacy::default();
+        privacy.update(&restricted());
+
+        let header = header(PacketType::Setup, 1, Duration::default(), 0);
+        let packet = CarSetupPacket::new(with_secondary_player(header, 1), vec![setup(); 2].into());
+        let setups = privacy.setups(&packet);
+
+        assert!(setups[0].is_some());
+        assert!(setups[1].is_some());
+        assert_eq!(Some(&packet.setups()[1]), packet.secondary_player());
+    }
+
+    #[test]
+    fn redact_restricted_cars() {
+        let mut privacy = Privacy::default();
+        privacy.update(&restricted());
+
+        match privacy.redact_packet(&setups()) {
+            Packet::Setup(packet) => {
+                assert_eq!(CarSetup::default(), packet.setups()[0]);
+                assert_eq!(CarSetup::default(), packet.setups()[1]);
+            }
+            _ => panic!("Expected a setup packet"),
+        }
+    }
+
+    #[test]
+    fn redact_marks_restricted_cars() {
+        let mut privacy = Privacy::default();
+        privacy.update(&participants_packet(
+            header(PacketType::Participants, 1, Duration::default(), 0),
+            vec![
+                participant("Player", TelemetryPrivacy::Restricted),
+                participant("Player", TelemetryPrivacy::Public),
+            ],
+        ));
+
+        let redacted = privacy.redact(&setups());
+
+        assert!(redacted.is_redacted(0));
+        assert!(!redacted.is_redacted(1));
+        assert_eq!(&vec![true, false], redacted.redacted());
+
+        match redacted.packet() {
+            Packet::Setup(packet) => {
+                assert_eq!(CarSetup::default(), packet.setups()[0]);
+                assert_eq!(setup(), packet.setups()[1]);
+            }
+            _ => panic!("Expected a setup packet"),
+        }
+    }
+
+    #[test]
+    fn redact_before_participants() {
+        let privacy = Privacy::default();
+        let packet = setups();
+        let redacted = privacy.redact(&packet);
+
+        assert_eq!(&packet, redacted.packet());
+        assert!(!redacted.is_redacted(0));
+    }
+}
@@ -0,0 +1,479 @@
+//! Queries over the data of a session
+//!
+//! Plots and feature extraction for machine learning ask questions about the whole session, like
+//! "what was the speed of the player at 300 meters into lap 12", or "how did the tyre temperatures
+//! develop between laps 5 and 10". The packets of the games, on the other hand, are snapshots of a
+//! single moment, and spread the data of a car over several packets that are sent at different
+//! rates.
+//!
+//! `SessionData` ingests the packets of a session, either from a capture or a live stream, and
+//! stores a row for each car and telemetry packet. Each row records the lap and lap distance of the
+//! car from the latest lap packet, and the most recent status of the car, so that the channels of
+//! different packets line up. The rows can then be queried by lap and distance, and returned as
+//! aligned series of values.
+//!
+//! The store keeps every telemetry sample of every car, which amounts to a few hundred megabytes
+//! for a long race at the highest send rate.
+
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use getset::{CopyGetters, Getters};
+
+use crate::packet::lap::LapPacket;
+use crate::packet::status::CarStatus;
+use crate::packet::telemetry::Telemetry;
+use crate::packet::Packet;
+use crate::types::{CornerProperty, VehicleIndex};
+
+/// Corner of a car
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum Corner {
+    /// Front left corner of the car
+    FrontLeft,
+
+    /// Front right corner of the car
+    FrontRight,
+
+    /// Rear left corner of the car
+    RearLeft,
+
+    /// Rear right corner of the car
+    RearRight,
+}
+
+impl Corner {
+    fn read<T: Copy + Into<f32>>(self, property: &CornerProperty<T>) -> f32 {
+        match self {
+            Corner::FrontLeft => property.front_left().into(),
+            Corner::FrontRight => property.front_right().into(),
+            Corner::RearLeft => property.rear_left().into(),
+            Corner::RearRight => property.rear_right().into(),
+        }
+    }
+}
+
+/// Value of a car that can be queried
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum Channel {
+    /// Speed of the car in kilometers per hour
+    Speed,
+
+    /// Ratio of the applied throttle
+    Throttle,
+
+    /// Ratio of the applied brake
+    Brake,
+
+    /// Ratio of the steering input, from -1.0 (full lock left) to 1.0 (full lock right)
+    Steering,
+
+    /// Gear of the car, with -1 for reverse and 0 for neutral
+    Gear,
+
+    /// Engine RPM
+    EngineRpm,
+
+    /// Whether the DRS is deployed, as 1.0 or 0.0
+    Drs,
+
+    /// Temperature of the engine in degrees celsius
+    EngineTemperature,
+
+    /// Temperature of a brake in degrees celsius
+    BrakeTemperature(Corner),
+
+    /// Temperature of a tyre's surface in degrees celsius
+    TyreSurfaceTemperature(Corner),
+
+    /// Temperature of a tyre's carcass in degrees celsius
+    TyreInnerTemperature(Corner),
+
+    /// Pressure of a tyre in psi
+    TyrePressure(Corner),
+
+    /// Wear of a tyre in percent
+    TyreWear(Corner),
+
+    /// Fuel in the tank in kilograms
+    FuelRemaining,
+
+    /// Energy in the ERS store in joules
+    ErsEnergy,
+}
+
+impl Channel {
+    /// Returns whether the channel takes discrete values, which are not interpolated.
+    fn is_discrete(self) -> bool {
+        matches!(self, Channel::Gear | Channel::Drs)
+    }
+
+    fn read(self, row: &Row) -> f32 {
+        let telemetry = &row.telemetry;
+        let status = row.status.as_ref();
+
+        match self {
+            Channel::Speed => telemetry.speed() as f32,
+            Channel::Throttle => telemetry.throttle(),
+            Channel::Brake => telemetry.brake(),
+            Channel::Steering => telemetry.steering(),
+            Channel::Gear => telemetry.gear() as i8 as f32,
+            Channel::EngineRpm => telemetry.engine_rpm() as f32,
+            Channel::Drs => telemetry.drs() as u8 as f32,
+            Channel::EngineTemperature => telemetry.engine_temperature() as f32,
+            Channel::BrakeTemperature(corner) => corner.read(telemetry.brake_temperature()),
+            Channel::TyreSurfaceTemperature(corner) => {
+                corner.read(telemetry.tyre_surface_temperature())
+            }
+            Channel::TyreInnerTemperature(corner) => {
+                corner.read(telemetry.tyre_inner_temperature())
+            }
+            Channel::TyrePressure(corner) => corner.read(telemetry.tyre_pressure()),
+            Channel::TyreWear(corner) => status
+                .map(|status| corner.read(status.tyre_wear()))
+                .unwrap_or(f32::NAN),
+            Channel::FuelRemaining => status
+                .map(|status| status.fuel_remaining())
+                .unwrap_or(f32::NAN),
+            Channel::ErsEnergy => status.map(|status| status.ers_energy()).unwrap_or(f32::NAN),
+        }
+    }
+}
+
+/// Values of a car at the time of a telemetry packet
+#[derive(Debug, PartialEq, Copy, Clone, PartialOrd)]
+struct Row {
+    session_time: Duration,
+    lap_number: u8,
+    lap_distance: f32,
+    telemetry: Telemetry,
+    status: Option<CarStatus>,
+}
+
+/// Aligned series of values of a car
+///
+/// The series contains one entry per telemetry packet. All columns have the same length, and the
+/// values at the same index were recorded at the same time. Values that come from the status of the
+/// car are `NaN` until the first status packet has been received.
+#[derive(Debug, Getters, PartialEq, Clone, PartialOrd, Default)]
+pub struct Series {
+    /// Returns the session time of each entry.
+    #[getset(get = "pub")]
+    session_time: Vec<Duration>,
+
+    /// Returns the lap of each entry.
+    #[getset(get = "pub")]
+    lap_number: Vec<u8>,
+
+    /// Returns the lap distance of each entry in meters.
+    #[getset(get = "pub")]
+    lap_distance: Vec<f32>,
+
+    /// Returns the channels of the series, in the order of the columns.
+    #[getset(get = "pub")]
+    channels: Vec<Channel>,
+
+    values: Vec<Vec<f32>>,
+}
+
+impl Series {
+    /// Returns the values of a channel, or `None` if the channel was not queried.
+    pub fn column(&self, channel: Channel) -> Option<&[f32]> {
+        self.channels
+            .iter()
+            .position(|c| *c == channel)
+            .map(|index| self.values[index].as_slice())
+    }
+
+    /// Returns the number of entries in the series.
+    pub fn len(&self) -> usize {
+        self.session_time.len()
+    }
+
+    /// Returns true if the series has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.session_time.is_empty()
+    }
+}
+
+/// Store of the data of a session that can be queried
+///
+/// The store is reset when a packet of a new session is received.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::query::{Channel, Corner, SessionData};
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let data: SessionData = packets().into_iter().collect();
+///
+/// if let Some(player) = data.player_car_index() {
+///     let speed = data.value_at(player, Channel::Speed, 12, 300.0);
+///
+///     let temperatures = data.series(
+///         player,
+///         &[
+///             Channel::TyreSurfaceTemperature(Corner::FrontLeft),
+///             Channel::TyreSurfaceTemperature(Corner::FrontRight),
+///         ],
+///         5..=10,
+///     );
+/// }
+/// ```
+#[derive(Debug, CopyGetters, PartialEq, Clone, PartialOrd, Default)]
+pub struct SessionData {
+    /// Returns the unique id of the session.
+    #[getset(get_copy = "pub")]
+    session_uid: Option<u64>,
+
+    /// Returns the index of the player's car.
+    #[getset(get_copy = "pub")]
+    player_car_index: Option<VehicleIndex>,
+
+    laps: Option<LapPacket>,
+    statuses: Vec<Option<CarStatus>>,
+    rows: Vec<Vec<Row>>,
+}
+
+impl SessionData {
+    /// Add a packet to the store.
+    pub fn update(&mut self, packet: &Packet) {
+        let header = packet.header();
+
+        if self.session_uid != Some(header.session_uid()) {
+            *self = SessionData {
+                session_uid: Some(header.session_uid()),
+                ..Default::default()
+            };
+        }
+
+        self.player_car_index = Some(header.player_car_index());
+
+        match packet {
+            Packet::Lap(packet) => self.laps = Some(packet.clone()),
+            Packet::Status(packet) => {
+                self.statuses = packet.statuses().iter().copied().map(Some).collect();
+            }
+            Packet::Telemetry(packet) => {
+                let laps = match &self.laps {
+                    Some(laps) => laps,
+                    None => return,
+                };
+
+                if self.rows.len() < packet.telemetry().len() {
+                    self.rows.resize(packet.telemetry().len(), Vec::new());
+                }
+
+                for (index, telemetry) in packet.telemetry().iter().enumerate() {
+                    let lap = match laps.laps().get(index) {
+                        Some(lap) => lap,
+                        None => continue,
+                    };
+
+                    self.rows[index].push(Row {
+                        session_time: *header.session_time(),
+                        lap_number: lap.current_lap_number(),
+                        lap_distance: lap.lap_distance(),
+                        telemetry: *telemetry,
+                        status: self.statuses.get(index).copied().flatten(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the laps for which data of the given car has been recorded.
+    pub fn laps(&self, vehicle_index: VehicleIndex) -> Vec<u8> {
+        let mut laps: Vec<u8> = self
+            .rows(vehicle_index)
+            .iter()
+            .map(|row| row.lap_number)
+            .collect();
+
+        laps.dedup();
+        laps
+    }
+
+    /// Returns the value of a channel at a distance into a lap.
+    ///
+    /// The value is interpolated between the two closest samples, except for discrete channels like
+    /// the gear, which take the value of the closest sample. `None` is returned if the car did not
+    /// drive the lap, or the distance is outside of the recorded part of the lap.
+    pub fn value_at(
+        &self,
+        vehicle_index: VehicleIndex,
+        channel: Channel,
+        lap_number: u8,
+        distance: f32,
+    ) -> Option<f32> {
+        let rows: Vec<&Row> = self
+            .rows(vehicle_index)
+            .iter()
+            .filter(|row| row.lap_number == lap_number)
+            .collect();
+
+        let index = rows.iter().position(|row| row.lap_distance >= distance)?;
+        let after = rows[index];
+
+        if index == 0 || after.lap_distance <= distance {
+            return if after.lap_distance == distance {
+                Some(channel.read(after))
+            } else {
+                None
+            };
+        }
+
+        let before = rows[index - 1];
+        let ratio = (distance - before.lap_distance) / (after.lap_distance - before.lap_distance);
+
+        if channel.is_discrete() {
+            let closest = if ratio < 0.5 { before } else { after };
+            return Some(channel.read(closest));
+        }
+
+        let (from, to) = (channel.read(before), channel.read(after));
+        Some(from + (to - from) * ratio)
+    }
+
+    /// Returns the values of the given channels for a range of laps.
+    pub fn series(
+        &self,
+        vehicle_index: VehicleIndex,
+        channels: &[Channel],
+        laps: RangeInclusive<u8>,
+    ) -> Series {
+        let mut series = Series {
+            channels: channels.to_vec(),
+            values: vec![Vec::new(); channels.len()],
+            ..Default::default()
+        };
+
+        for row in self
+            .rows(vehicle_index)
+            .iter()
+            .filter(|row| laps.contains(&row.lap_number))
+        {
+            series.session_time.push(row.session_time);
+            series.lap_number.push(row.lap_number);
+            series.lap_distance.push(row.lap_distance);
+
+            for (column, channel) in series.values.iter_mut().zip(channels) {
+                column.push(channel.read(row));
+            }
+        }
+
+        series
+    }
+
+    fn rows(&self, vehicle_index: VehicleIndex) -> &[Row] {
+        self.rows
+            .get(vehicle_index as usize)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+impl FromIterator<Packet> for SessionData {
+    fn from_iter<I: IntoIterator<Item = Packet>>(packets: I) -> Self {
+        let mut data = SessionData::default();
+
+        for packet in packets {
+            data.update(&packet);
+        }
+
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap, lap_packet, telemetry, telemetry_packet, with_corners};
+    use crate::packet::header::PacketType;
+    use crate::packet::status::{CarStatus, CarStatusPacket};
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+    use crate::query::{Channel, Corner, SessionData};
+    use crate::types::CornerProperty;
+
+    fn driving(millis: u64, speed: u16, gear: Gear) -> Packet {
+        let telemetry = with_corners(
+            telemetry(speed, 1.0, 0.0, 0.0, gear, 10000),
+            CornerProperty::default(),
+            CornerProperty::new(90, 91, 92, 93),
+            CornerProperty::default(),
+        );
+
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+            vec![telemetry],
+        )
+    }
+
+    fn data() -> SessionData {
+        vec![
+            driving(0, 100, Gear::Third),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                vec![lap(1, 100.0)],
+            ),
+            driving(0, 200, Gear::Fourth),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::from_millis(100), 0),
+                vec![lap(1, 200.0)],
+            ),
+            driving(100, 220, Gear::Fifth),
+            Packet::Status(CarStatusPacket::new(
+                header(PacketType::Status, 1, Duration::from_millis(100), 0),
+                vec![CarStatus::default()].into(),
+            )),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::from_millis(200), 0),
+                vec![lap(2, 10.0)],
+            ),
+            driving(200, 240, Gear::Fifth),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn value_at_interpolates_by_distance() {
+        let data = data();
+
+        assert_eq!(Some(0), data.player_car_index());
+        assert_eq!(vec![1, 2], data.laps(0));
+        assert_eq!(Some(210.0), data.value_at(0, Channel::Speed, 1, 150.0));
+        assert_eq!(Some(5.0), data.value_at(0, Channel::Gear, 1, 190.0));
+        assert_eq!(Some(240.0), data.value_at(0, Channel::Speed, 2, 10.0));
+        assert_eq!(None, data.value_at(0, Channel::Speed, 1, 250.0));
+        assert_eq!(None, data.value_at(0, Channel::Speed, 3, 100.0));
+    }
+
+    #[test]
+    fn series_aligns_channels() {
+        let data = data();
+        let temperature = Channel::TyreSurfaceTemperature(Corner::RearLeft);
+        let wear = Channel::TyreWear(Corner::RearLeft);
+
+        let series = data.series(0, &[Channel::Speed, temperature, wear], 1..=2);
+
+        assert_eq!(3, series.len());
+        assert_eq!(&[1, 1, 2], series.lap_number().as_slice());
+        assert_eq!(
+            Some(&[200.0, 220.0, 240.0][..]),
+            series.column(Channel::Speed)
+        );
+        assert_eq!(Some(&[92.0, 92.0, 92.0][..]), series.column(temperature));
+        assert!(series.column(wear).unwrap()[0].is_nan());
+        assert_eq!(0.0, series.column(wear).unwrap()[2]);
+        assert_eq!(None, series.column(Channel::Brake));
+
+        assert!(data.series(0, &[Channel::Speed], 3..=4).is_empty());
+    }
+}
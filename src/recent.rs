@@ -0,0 +1,197 @@
+//! Buffer of the most recent packets
+//!
+//! After an incident, users often want to watch the last seconds again, for example to review a
+//! collision from the data of both cars. Recording the whole session to disk is not necessary for
+//! that. The `RecentBuffer` keeps the packets of the last seconds in memory, and drops older packets
+//! as new ones arrive.
+//!
+//! The packets are stored in the order of their session time, which allows the buffer to find the
+//! packets in a range of time with a binary search.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::packet::Packet;
+
+/// Length of the window that is kept by default
+const DEFAULT_WINDOW: Duration = Duration::from_secs(30);
+
+/// Ring buffer of the packets of the last seconds of a session
+///
+/// The buffer keeps the packets whose session time is within `window` of the most recent packet.
+/// It is cleared when a new session starts. When the session time jumps back, e.g. after a
+/// flashback, the packets after the new session time are dropped, since they describe laps that
+/// have been undone.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use f1_api::recent::RecentBuffer;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut buffer = RecentBuffer::new(Duration::from_secs(60));
+///
+/// for packet in packets() {
+///     buffer.push(packet);
+/// }
+///
+/// for packet in buffer.last(Duration::from_secs(10)) {
+///     println!("{:?}", packet.header());
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct RecentBuffer {
+    window: Duration,
+    session_uid: Option<u64>,
+    packets: VecDeque<Packet>,
+}
+
+impl RecentBuffer {
+    /// Create a new buffer that keeps the packets of the given window.
+    pub fn new(window: Duration) -> Self {
+        RecentBuffer {
+            window,
+            session_uid: None,
+            packets: VecDeque::new(),
+        }
+    }
+
+    /// Add a packet to the buffer, and drop the packets that are older than the window.
+    pub fn push(&mut self, packet: Packet) {
+        let header = packet.header();
+        let time = *header.session_time();
+
+        if self.session_uid != Some(header.session_uid()) {
+            self.session_uid = Some(header.session_uid());
+            self.packets.clear();
+        }
+
+        let end = self.partition(|packet_time| packet_time <= time);
+        self.packets.truncate(end);
+        self.packets.push_back(packet);
+
+        let start = self.partition(|packet_time| packet_time + self.window < time);
+        self.packets.drain(..start);
+    }
+
+    /// Returns the packets with a session time in the range from `start` to `end`, including both.
+    pub fn range(&self, start: Duration, end: Duration) -> impl Iterator<Item = &Packet> + '_ {
+        let first = self.partition(|time| time < start);
+        let last = self.partition(|time| time <= end).max(first);
+
+        self.packets.range(first..last)
+    }
+
+    /// Returns the packets of the last `duration` before the most recent packet.
+    pub fn last(&self, duration: Duration) -> impl Iterator<Item = &Packet> + '_ {
+        let end = self.latest().unwrap_or_default();
+
+        self.range(end.saturating_sub(duration), end)
+    }
+
+    /// Returns all packets in the buffer, ordered by their session time.
+    pub fn packets(&self) -> impl Iterator<Item = &Packet> + '_ {
+        self.packets.iter()
+    }
+
+    /// Returns the session time of the most recent packet.
+    pub fn latest(&self) -> Option<Duration> {
+        self.packets
+            .back()
+            .map(|packet| *packet.header().session_time())
+    }
+
+    /// Returns the unique id of the session whose packets are in the buffer.
+    pub fn session_uid(&self) -> Option<u64> {
+        self.session_uid
+    }
+
+    /// Returns the number of packets in the buffer.
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    /// Returns true if the buffer contains no packets.
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    /// Returns the index of the first packet whose session time does not match the predicate.
+    fn partition<F: Fn(Duration) -> bool>(&self, predicate: F) -> usize {
+        self.packets
+            .partition_point(|packet| predicate(*packet.header().session_time()))
+    }
+}
+
+impl Default for RecentBuffer {
+    fn default() -> Self {
+        RecentBuffer::new(DEFAULT_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::Packet;
+    use crate::recent::RecentBuffer;
+
+    fn seconds<'a>(packets: impl Iterator<Item = &'a Packet>) -> Vec<u64> {
+        packets
+            .map(|packet| packet.header().session_time().as_secs())
+            .collect()
+    }
+
+    #[test]
+    fn push_drops_packets_outside_the_window() {
+        let mut buffer = RecentBuffer::new(Duration::from_secs(10));
+
+        for second in 0..=30 {
+            buffer.push(lap_packet(
+                header(PacketType::Lap, 1, Duration::from_secs(second), 0),
+                Vec::new(),
+            ));
+        }
+
+        assert_eq!(11, buffer.len());
+        assert_eq!(Some(Duration::from_secs(30)), buffer.latest());
+        assert_eq!(
+            vec![22, 23, 24],
+            seconds(buffer.range(Duration::from_secs(22), Duration::from_secs(24)))
+        );
+        assert_eq!(
+            vec![28, 29, 30],
+            seconds(buffer.last(Duration::from_secs(2)))
+        );
+    }
+
+    #[test]
+    fn push_after_flashback_and_new_session() {
+        let mut buffer = RecentBuffer::new(Duration::from_secs(10));
+
+        for second in 0..5 {
+            buffer.push(lap_packet(
+                header(PacketType::Lap, 1, Duration::from_secs(second), 0),
+                Vec::new(),
+            ));
+        }
+
+        buffer.push(lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs(2), 0),
+            Vec::new(),
+        ));
+        assert_eq!(vec![0, 1, 2, 2], seconds(buffer.packets()));
+
+        buffer.push(lap_packet(
+            header(PacketType::Lap, 2, Duration::from_secs(100), 0),
+            Vec::new(),
+        ));
+        assert_eq!(vec![100], seconds(buffer.packets()));
+        assert_eq!(Some(2), buffer.session_uid());
+    }
+}
@@ -0,0 +1,338 @@
+//! Compact framing for relaying packets over the internet
+//!
+//! The games send up to a few hundred kilobytes of telemetry per second, which is no problem on a
+//! local network, but too much for many internet connections. A relay that forwards the packets
+//! from a rig to a remote engineer over TCP or a WebSocket can use the framing in this module to
+//! reduce the bandwidth.
+//!
+//! Consecutive packets of the same type are very similar. The `FrameEncoder` therefore replaces
+//! each datagram with the difference to the previous datagram of the same type, which consists
+//! mostly of zeros, and with the `zstd` feature compresses the result with zstd. Without the
+//! feature, the differences only save bandwidth if the transport compresses them, for example a
+//! WebSocket with the `permessage-deflate` extension. The `FrameDecoder` reverses both steps.
+//! Since each frame depends on the previous frame of its type, the frames must be delivered
+//! reliably and in order, like TCP and WebSockets do.
+//!
+//! Each frame starts with a byte of flags, followed by the packet format (`u16`) and packet id
+//! (`u8`) of the datagram, and the encoded datagram. The flags declare whether the datagram is
+//! stored as a difference (`0x01`) and whether it is compressed (`0x02`). When frames are written
+//! to a byte stream like TCP, each frame is prefixed with its size in bytes (`u32`). All numbers
+//! are encoded as little endian.
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Write};
+
+use bytes::Bytes;
+
+use crate::capture::MAX_DATAGRAM_SIZE;
+
+/// Flag for frames that store the difference to the previous datagram
+const DELTA: u8 = 0x01;
+
+/// Flag for frames that are compressed with zstd
+const COMPRESSED: u8 = 0x02;
+
+/// Size of the flags, the packet format, and the packet id at the start of each frame
+const FRAME_HEADER_SIZE: usize = 4;
+
+/// Largest size of a frame, which leaves room for the overhead of compressing a datagram that does
+/// not compress well
+const MAX_FRAME_SIZE: usize = FRAME_HEADER_SIZE + MAX_DATAGRAM_SIZE + MAX_DATAGRAM_SIZE / 128;
+
+/// Offset of the packet id in the header of a datagram
+const PACKET_ID_OFFSET: usize = 5;
+
+/// Packet format and packet id that identify the type of a datagram
+type PacketKey = (u16, u8);
+
+fn packet_key(datagram: &[u8]) -> PacketKey {
+    let format = match datagram {
+        [low, high, ..] => u16::from_le_bytes([*low, *high]),
+        _ => 0,
+    };
+
+    (
+        format,
+        datagram.get(PACKET_ID_OFFSET).copied().unwrap_or(u8::MAX),
+    )
+}
+
+fn xor(datagram: &mut [u8], previous: &[u8]) {
+    for (byte, previous) in datagram.iter_mut().zip(previous) {
+        *byte ^= previous;
+    }
+}
+
+/// Encoder that turns datagrams into compact frames
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{TcpStream, UdpSocket};
+///
+/// use f1_api::relay::FrameEncoder;
+///
+/// let socket = UdpSocket::bind("0.0.0.0:20777")?;
+/// let mut engineer = TcpStream::connect("engineer.example.com:20778")?;
+/// let mut encoder = FrameEncoder::default();
+///
+/// let mut buffer = [0u8; 2048];
+///
+/// loop {
+///     let size = socket.recv(&mut buffer)?;
+///     encoder.write_frame(&mut engineer, &buffer[..size])?;
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct FrameEncoder {
+    delta: bool,
+    #[cfg(feature = "zstd")]
+    level: Option<i32>,
+    previous: HashMap<PacketKey, Bytes>,
+}
+
+impl FrameEncoder {
+    /// Create an encoder that stores each datagram as it is.
+    pub fn new() -> Self {
+        FrameEncoder {
+            delta: false,
+            #[cfg(feature = "zstd")]
+            level: None,
+            previous: HashMap::new(),
+        }
+    }
+
+    /// Store datagrams as the difference to the previous datagram of the same type.
+    pub fn delta(mut self) -> Self {
+        self.delta = true;
+        self
+    }
+
+    /// Compress frames with zstd at the given level, e.g. `3`.
+    #[cfg(feature = "zstd")]
+    pub fn zstd(mut self, level: i32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Encode a datagram as a frame.
+    pub fn encode(&mut self, datagram: &[u8]) -> Result<Vec<u8>, Error> {
+        let key = packet_key(datagram);
+        let mut flags = 0;
+
+        let mut payload = datagram.to_vec();
+
+        if self.delta {
+            if let Some(previous) = self.previous.get(&key) {
+                if previous.len() == payload.len() {
+                    xor(&mut payload, previous);
+                    flags |= DELTA;
+                }
+            }
+
+            self.previous.insert(key, Bytes::copy_from_slice(datagram));
+        }
+
+        #[cfg(feature = "zstd")]
+        if let Some(level) = self.level {
+            payload = zstd::bulk::compress(&payload, level)?;
+            flags |= COMPRESSED;
+        }
+
+        let mut frame = Vec::with_capacity(FRAME_HEADER_SIZE + payload.len());
+        frame.push(flags);
+        frame.extend_from_slice(&key.0.to_le_bytes());
+        frame.push(key.1);
+        frame.extend_from_slice(&payload);
+
+        Ok(frame)
+    }
+
+    /// Encode a datagram as a frame, and write it to a byte stream with its size as prefix.
+    pub fn write_frame<W: Write>(&mut self, writer: &mut W, datagram: &[u8]) -> Result<(), Error> {
+        let frame = self.encode(datagram)?;
+
+        writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+        writer.write_all(&frame)
+    }
+}
+
+impl Default for FrameEncoder {
+    /// Create an encoder that stores differences, and compresses them if the `zstd` feature is
+    /// enabled.
+    fn default() -> Self {
+        let encoder = FrameEncoder::new().delta();
+
+        #[cfg(feature = "zstd")]
+        let encoder = encoder.zstd(3);
+
+        encoder
+    }
+}
+
+/// Decoder that restores datagrams from frames
+///
+/// The decoder must see every frame that the encoder produced, in the same order.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct FrameDecoder {
+    previous: HashMap<PacketKey, Bytes>,
+}
+
+impl FrameDecoder {
+    /// Create a new decoder.
+    pub fn new() -> Self {
+        FrameDecoder::default()
+    }
+
+    /// Decode a frame and return the original datagram.
+    pub fn decode(&mut self, frame: &[u8]) -> Result<Bytes, Error> {
+        if frame.len() < FRAME_HEADER_SIZE {
+            return Err(Error::new(ErrorKind::InvalidData, "Frame is incomplete."));
+        }
+
+        let flags = frame[0];
+        let key = (u16::from_le_bytes([frame[1], frame[2]]), frame[3]);
+        let payload = &frame[FRAME_HEADER_SIZE..];
+
+        let mut datagram = if flags & COMPRESSED != 0 {
+            decompress(payload)?
+        } else {
+            payload.to_vec()
+        };
+
+        if flags & DELTA != 0 {
+            match self.previous.get(&key) {
+                Some(previous) if previous.len() == datagram.len() => xor(&mut datagram, previous),
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "Frame refers to a datagram that has not been received.",
+                    ))
+                }
+            }
+        }
+
+        let datagram = Bytes::from(datagram);
+        self.previous.insert(key, datagram.clone());
+
+        Ok(datagram)
+    }
+
+    /// Read the next frame from a byte stream and return the original datagram.
+    ///
+    /// `Ok(None)` is returned when the stream has ended. Frames that are larger than any datagram
+    /// can be are rejected as invalid before they are read.
+    pub fn read_frame<R: Read>(&mut self, reader: &mut R) -> Result<Option<Bytes>, Error> {
+        let mut size = [0u8; 4];
+
+        match reader.read(&mut size[..1])? {
+            0 => return Ok(None),
+            _ => reader.read_exact(&mut size[1..])?,
+        }
+
+        let size = u32::from_le_bytes(size) as usize;
+        if size > MAX_FRAME_SIZE {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Frame is larger than the largest datagram.",
+            ));
+        }
+
+        let mut frame = vec![0u8; size];
+        reader.read_exact(&mut frame)?;
+
+        self.decode(&frame).map(Some)
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn decompress(payload: &[u8]) -> Result<Vec<u8>, Error> {
+    zstd::bulk::decompress(payload, MAX_DATAGRAM_SIZE)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress(_payload: &[u8]) -> Result<Vec<u8>, Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "Compressed frames require the zstd feature.",
+    ))
+}
+
+#[cfg(all(test, feature = "decode"))]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+
+    use bytes::Bytes;
+
+    use crate::fixtures;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::relay::{FrameDecoder, FrameEncoder};
+
+    fn datagrams() -> Vec<Bytes> {
+        [
+            PacketType::Telemetry,
+            PacketType::Lap,
+            PacketType::Telemetry,
+        ]
+        .iter()
+        .flat_map(|packet_type| fixtures::capture(ApiSpec::Nineteen, *packet_type).unwrap())
+        .map(|datagram| datagram.unwrap().payload().clone())
+        .collect()
+    }
+
+    #[test]
+    fn frames_restore_datagrams() {
+        let mut encoder = FrameEncoder::default();
+        let mut decoder = FrameDecoder::new();
+
+        let mut encoded = 0;
+        let mut original = 0;
+
+        for datagram in datagrams() {
+            let frame = encoder.encode(&datagram).unwrap();
+
+            encoded += frame.len();
+            original += datagram.len();
+
+            assert_eq!(datagram, decoder.decode(&frame).unwrap());
+        }
+
+        #[cfg(feature = "zstd")]
+        assert!(encoded < original / 2);
+        #[cfg(not(feature = "zstd"))]
+        assert!(encoded > original);
+    }
+
+    #[test]
+    fn frames_over_byte_stream() {
+        let mut encoder = FrameEncoder::new();
+        let mut stream = Vec::new();
+
+        for datagram in datagrams() {
+            encoder.write_frame(&mut stream, &datagram).unwrap();
+        }
+
+        let mut decoder = FrameDecoder::new();
+        let mut reader = Cursor::new(stream);
+        let mut decoded = Vec::new();
+
+        while let Some(datagram) = decoder.read_frame(&mut reader).unwrap() {
+            decoded.push(datagram);
+        }
+
+        assert_eq!(datagrams(), decoded);
+        assert!(FrameDecoder::new()
+            .decode(&[0x01, 0xe3, 0x07, 6, 0])
+            .is_err());
+    }
+
+    #[test]
+    fn read_frame_with_oversized_length() {
+        let mut reader = Cursor::new(u32::MAX.to_le_bytes().to_vec());
+
+        let error = FrameDecoder::new().read_frame(&mut reader).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+    }
+}
@@ -0,0 +1,359 @@
+//! HTML reports of a session
+//!
+//! League admins publish the results of a race right after the chequered flag. The `HtmlReport`
+//! renders the final state of a session into a single HTML document with the classification, the
+//! laps of every driver, their stints, and the penalties that were handed out. The document has no
+//! external dependencies: styles are embedded, and charts are inlined as SVG, so that the file can
+//! be uploaded to any web server or shared as is.
+//!
+//! Charts of the lap times and positions require the `plot` feature. Without it, the report only
+//! contains tables.
+
+use std::fmt::Write;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::analysis::penalties::PenaltyTracker;
+use crate::analysis::stints::stint_report;
+use crate::tracker::SessionTracker;
+use crate::types::VehicleIndex;
+
+/// Styles that are embedded in every report
+const STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:1100px;color:#212121}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{padding:4px 10px;text-align:right;border-bottom:1px solid #e0e0e0}\
+th:first-child,td:first-child{text-align:left}\
+h2{border-bottom:2px solid #e10600;padding-bottom:4px}\
+.invalid{color:#c62828}";
+
+/// Builder for the HTML report of a session
+///
+/// # Examples
+///
+/// ```no_run
+/// use f1_api::analysis::penalties::PenaltyTracker;
+/// use f1_api::report::HtmlReport;
+/// use f1_api::tracker::SessionTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = SessionTracker::default();
+/// let mut penalties = PenaltyTracker::default();
+///
+/// for packet in packets() {
+///     tracker.update(&packet);
+///     penalties.update(&packet);
+/// }
+///
+/// HtmlReport::new(&tracker)
+///     .title("Round 3 - Monza")
+///     .penalties(&penalties)
+///     .save("report.html")
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct HtmlReport<'a> {
+    tracker: &'a SessionTracker,
+    penalties: Option<&'a PenaltyTracker>,
+    title: Option<String>,
+}
+
+impl<'a> HtmlReport<'a> {
+    /// Create a new report of the session in the given tracker.
+    pub fn new(tracker: &'a SessionTracker) -> Self {
+        HtmlReport {
+            tracker,
+            penalties: None,
+            title: None,
+        }
+    }
+
+    /// Set the title of the report.
+    ///
+    /// By default, the title is made up of the track and the type of the session.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Include the penalties and warnings of the drivers in the report.
+    pub fn penalties(mut self, penalties: &'a PenaltyTracker) -> Self {
+        self.penalties = Some(penalties);
+        self
+    }
+
+    /// Render the report as an HTML document.
+    pub fn render(&self) -> String {
+        let title = self
+            .title
+            .clone()
+            .unwrap_or_else(|| match self.tracker.session() {
+                Some(session) => format!("{:?} {:?}", session.track(), session.session_type()),
+                None => "Session report".into(),
+            });
+
+        let mut html = String::new();
+        writeln!(html, "<!DOCTYPE html>").unwrap();
+        writeln!(html, r#"<html lang="en">"#).unwrap();
+        writeln!(
+            html,
+            r#"<head><meta charset="utf-8"><title>{}</title><style>{}</style></head>"#,
+            escape(&title),
+            STYLE
+        )
+        .unwrap();
+        writeln!(html, "<body>").unwrap();
+        writeln!(html, "<h1>{}</h1>", escape(&title)).unwrap();
+
+        self.write_classification(&mut html);
+        self.write_charts(&mut html);
+        self.write_laps(&mut html);
+        self.write_stints(&mut html);
+        self.write_penalties(&mut html);
+
+        writeln!(html, "</body>").unwrap();
+        writeln!(html, "</html>").unwrap();
+        html
+    }
+
+    /// Render the report and write it to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.render())
+    }
+
+    fn write_classification(&self, html: &mut String) {
+        writeln!(html, "<h2>Classification</h2>").unwrap();
+        writeln!(
+            html,
+            "<table><tr><th>Driver</th><th>Pos</th><th>Best lap</th><th>Gap</th><th>Tyre</th><th>Stops</th><th>Status</th></tr>"
+        )
+        .unwrap();
+
+        for entry in self.tracker.classification() {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:?}</td></tr>",
+                escape(&self.name(entry.vehicle_index())),
+                entry.position(),
+                lap_time(entry.best_lap_time()),
+                entry.gap().map(|gap| format!("+{:.3}", gap.as_secs_f64())).unwrap_or_default(),
+                entry.tyre_compound().map(|tyre| format!("{:?}", tyre)).unwrap_or_default(),
+                entry.pit_stops(),
+                entry.result_status()
+            )
+            .unwrap();
+        }
+
+        writeln!(html, "</table>").unwrap();
+    }
+
+    #[cfg(feature = "plot")]
+    fn write_charts(&self, html: &mut String) {
+        use crate::plot::Chart;
+
+        let cars: Vec<VehicleIndex> = self
+            .tracker
+            .classification()
+            .iter()
+            .map(|entry| entry.vehicle_index())
+            .collect();
+
+        if cars.is_empty() {
+            return;
+        }
+
+        writeln!(html, "<h2>Charts</h2>").unwrap();
+
+        for chart in [
+            Chart::lap_times(self.tracker, &cars),
+            Chart::positions(self.tracker, &cars),
+        ] {
+            // A chart that fails to render is left out, since the tables contain the same data
+            if let Ok(svg) = chart.to_svg() {
+                writeln!(html, "<figure>{}</figure>", svg).unwrap();
+            }
+        }
+    }
+
+    #[cfg(not(feature = "plot"))]
+    fn write_charts(&self, _html: &mut String) {}
+
+    fn write_laps(&self, html: &mut String) {
+        writeln!(html, "<h2>Laps</h2>").unwrap();
+
+        for entry in self.tracker.classification() {
+            let history = match self.tracker.car(entry.vehicle_index()) {
+                Some(history) if !history.laps().is_empty() => history,
+                _ => continue,
+            };
+
+            writeln!(
+                html,
+                "<h3>{}</h3>",
+                escape(&self.name(entry.vehicle_index()))
+            )
+            .unwrap();
+            writeln!(
+                html,
+                "<table><tr><th>Lap</th><th>Time</th><th>S1</th><th>S2</th><th>S3</th><th>Pos</th><th>Tyre</th><th>Pit</th></tr>"
+            )
+            .unwrap();
+
+            for lap in history.laps() {
+                let class = if lap.is_valid() {
+                    ""
+                } else {
+                    r#" class="invalid""#
+                };
+
+                writeln!(
+                    html,
+                    "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    class,
+                    lap.lap_number(),
+                    lap_time(lap.lap_time()),
+                    lap_time(lap.sector1_time()),
+                    lap_time(lap.sector2_time()),
+                    lap_time(lap.sector3_time()),
+                    lap.position(),
+                    lap.tyre_compound().map(|tyre| format!("{:?}", tyre)).unwrap_or_default(),
+                    if lap.pit_stop() { "Yes" } else { "" }
+                )
+                .unwrap();
+            }
+
+            writeln!(html, "</table>").unwrap();
+        }
+    }
+
+    fn write_stints(&self, html: &mut String) {
+        writeln!(html, "<h2>Stints</h2>").unwrap();
+        writeln!(
+            html,
+            "<table><tr><th>Driver</th><th>Tyre</th><th>Laps</th><th>From</th><th>To</th><th>Pace</th><th>Degradation</th></tr>"
+        )
+        .unwrap();
+
+        for driver in stint_report(self.tracker, None).drivers() {
+            for stint in driver.stints() {
+                writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape(&self.name(driver.vehicle_index())),
+                    stint.compound(),
+                    stint.laps(),
+                    stint.start_lap(),
+                    stint.end_lap().map(|lap| lap.to_string()).unwrap_or_default(),
+                    stint.average_pace().as_ref().map(lap_time).unwrap_or_default(),
+                    stint
+                        .degradation()
+                        .map(|degradation| format!("{:+.3}s/lap", degradation))
+                        .unwrap_or_default()
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(html, "</table>").unwrap();
+    }
+
+    fn write_penalties(&self, html: &mut String) {
+        let penalties = match self.penalties {
+            Some(penalties) => penalties,
+            None => return,
+        };
+
+        writeln!(html, "<h2>Penalties</h2>").unwrap();
+        writeln!(
+            html,
+            "<table><tr><th>Driver</th><th>Penalties</th><th>Total</th><th>Served</th><th>Outstanding</th><th>Warnings</th></tr>"
+        )
+        .unwrap();
+
+        for driver in penalties
+            .drivers()
+            .iter()
+            .filter(|driver| !driver.penalties().is_empty() || driver.warnings() > 0)
+        {
+            let list: Vec<String> = driver
+                .penalties()
+                .iter()
+                .map(|penalty| format!("{}s (lap {})", penalty.seconds(), penalty.lap()))
+                .collect();
+
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}s</td><td>{}s</td><td>{}s</td><td>{}</td></tr>",
+                escape(&self.name(driver.vehicle_index())),
+                list.join(", "),
+                driver.total(),
+                driver.served(),
+                driver.outstanding(),
+                driver.warnings()
+            )
+            .unwrap();
+        }
+
+        writeln!(html, "</table>").unwrap();
+    }
+
+    /// Returns the name of the driver of a car, or a generic name if the participants are unknown.
+    fn name(&self, vehicle_index: VehicleIndex) -> String {
+        self.tracker
+            .name(vehicle_index)
+            .map(String::from)
+            .unwrap_or_else(|| format!("Car {}", vehicle_index))
+    }
+}
+
+/// Format a lap time as minutes, seconds, and milliseconds, or an empty string if it is not set.
+fn lap_time(time: &Duration) -> String {
+    if time.is_zero() {
+        return String::new();
+    }
+
+    let millis = time.as_millis();
+    let minutes = millis / 60_000;
+    let seconds = (millis % 60_000) as f64 / 1000.0;
+
+    if minutes > 0 {
+        format!("{}:{:06.3}", minutes, seconds)
+    } else {
+        format!("{:.3}", seconds)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::report::{lap_time, HtmlReport};
+    use crate::tracker::SessionTracker;
+
+    #[test]
+    fn render_empty_session() {
+        let tracker = SessionTracker::default();
+        let html = HtmlReport::new(&tracker).title("Round <1>").render();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>Round &lt;1&gt;</h1>"));
+        assert!(html.contains("<h2>Classification</h2>"));
+        assert!(!html.contains("<h2>Penalties</h2>"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn lap_time_formats_minutes() {
+        assert_eq!("1:32.456", lap_time(&Duration::from_millis(92_456)));
+        assert_eq!("28.001", lap_time(&Duration::from_millis(28_001)));
+        assert_eq!("", lap_time(&Duration::default()));
+    }
+}
@@ -0,0 +1,403 @@
+//! Resampling of motion and telemetry data onto a fixed timebase
+//!
+//! The rate at which the F1 games send motion and telemetry data can be configured in the game, and
+//! the actual rate varies with the frame rate of the game. Video overlays and machine learning
+//! pipelines, on the other hand, expect samples at exact intervals.
+//!
+//! The `Resampler` interpolates the position, speed, and inputs of each car between the packets it
+//! receives, and produces frames at a fixed rate in session time.
+
+use std::convert::TryFrom;
+use std::f32::consts::PI;
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::motion::{Motion, MotionPacket};
+use crate::packet::telemetry::{Gear, Telemetry, TelemetryPacket};
+use crate::packet::Packet;
+use crate::types::Property3D;
+
+/// Interpolated state of a car at a point in time
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, PartialOrd, Default)]
+#[allow(clippy::too_many_arguments)]
+pub struct Sample {
+    /// Returns the position of the car in 3D space.
+    #[getset(get = "pub")]
+    position: Property3D<f32>,
+
+    /// Returns the velocity of the car on three axis.
+    #[getset(get = "pub")]
+    velocity: Property3D<f32>,
+
+    /// Returns the yaw angle of the car in radians.
+    #[getset(get_copy = "pub")]
+    yaw: f32,
+
+    /// Returns the speed of the car in kilometers per hour.
+    #[getset(get_copy = "pub")]
+    speed: f32,
+
+    /// Returns the ratio of the throttle input.
+    #[getset(get_copy = "pub")]
+    throttle: f32,
+
+    /// Returns the ratio of the steering input, from -1.0 (full lock left) to 1.0 (full lock right).
+    #[getset(get_copy = "pub")]
+    steering: f32,
+
+    /// Returns the ratio of the brake input.
+    #[getset(get_copy = "pub")]
+    brake: f32,
+
+    /// Returns the gear of the car, which is taken from the closest packet.
+    #[getset(get_copy = "pub")]
+    gear: Gear,
+
+    /// Returns the engine RPM.
+    #[getset(get_copy = "pub")]
+    engine_rpm: f32,
+}
+
+/// Samples of all cars at a point in time
+///
+/// The samples are ordered by vehicle index, like the data in the packets.
+#[derive(new, Debug, Getters, PartialEq, Clone, PartialOrd)]
+pub struct Frame {
+    /// Returns the session time of the frame.
+    #[getset(get = "pub")]
+    session_time: Duration,
+
+    /// Returns the samples of all cars.
+    #[getset(get = "pub")]
+    samples: Vec<Sample>,
+}
+
+/// Two consecutive packets of the same type
+#[derive(Debug, PartialEq, Clone)]
+struct Window<T> {
+    previous: Option<(Duration, Vec<T>)>,
+    current: Option<(Duration, Vec<T>)>,
+}
+
+impl<T> Default for Window<T> {
+    fn default() -> Self {
+        Window {
+            previous: None,
+            current: None,
+        }
+    }
+}
+
+impl<T: Clone> Window<T> {
+    fn push(&mut self, time: Duration, data: &[T]) {
+        self.previous = self.current.take();
+        self.current = Some((time, data.to_vec()));
+    }
+
+    fn latest(&self) -> Option<Duration> {
+        self.current.as_ref().map(|(time, _)| *time)
+    }
+
+    /// Returns the data before and after the given time, and the ratio between them.
+    fn around(&self, time: Duration) -> Option<(&[T], &[T], f32)> {
+        let (current_time, current) = self.current.as_ref()?;
+
+        let (previous_time, previous) = match &self.previous {
+            Some((previous_time, previous)) => (previous_time, previous),
+            None => return Some((current, current, 0.0)),
+        };
+
+        let span = current_time.saturating_sub(*previous_time).as_secs_f32();
+        let ratio = if span > 0.0 {
+            (time.as_secs_f32() - previous_time.as_secs_f32()) / span
+        } else {
+            1.0
+        };
+
+        Some((previous, current, ratio.clamp(0.0, 1.0)))
+    }
+}
+
+/// Resampler for motion and telemetry data
+///
+/// The resampler produces frames at multiples of the interval in session time, e.g. at 0.00s,
+/// 0.02s, 0.04s, and so on for 50 Hz. Each frame is interpolated from the motion and telemetry
+/// packets before and after it, which means that both packet types must be enabled in the game. A
+/// frame is produced as soon as packets of both types have been received for its time.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::resample::Resampler;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut resampler = Resampler::new(50.0);
+///
+/// for packet in packets() {
+///     for frame in resampler.update(&packet) {
+///         println!("{:?}", frame.session_time());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Resampler {
+    hertz: f32,
+    interval: Duration,
+    session_uid: Option<u64>,
+    next: Option<Duration>,
+    motion: Window<Motion>,
+    telemetry: Window<Telemetry>,
+}
+
+impl Resampler {
+    /// Create a new resampler that produces frames at the given rate in Hertz.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rate is not a positive, finite number.
+    pub fn new(hertz: f32) -> Self {
+        assert!(
+            hertz > 0.0 && hertz.is_finite(),
+            "The sample rate must be a positive, finite number"
+        );
+
+        // Clamp the interval of tiny rates, and keep huge rates from producing an empty interval.
+        let interval = Duration::try_from_secs_f64(1.0 / hertz as f64)
+            .unwrap_or(Duration::MAX)
+            .max(Duration::from_nanos(1));
+
+        Resampler {
+            hertz,
+            interval,
+            session_uid: None,
+            next: None,
+            motion: Window::default(),
+            telemetry: Window::default(),
+        }
+    }
+
+    /// Update the resampler with a new packet, and return the frames that have become available.
+    pub fn update(&mut self, packet: &Packet) -> Vec<Frame> {
+        let header = packet.header();
+        let time = *header.session_time();
+
+        // Start over when a new session starts, or when the session time jumps back, e.g. after a
+        // flashback.
+        let rewind = [self.motion.latest(), self.telemetry.latest()]
+            .iter()
+            .flatten()
+            .any(|latest| time < *latest);
+
+        if self.session_uid != Some(header.session_uid()) || rewind {
+            *self = Resampler {
+                session_uid: Some(header.session_uid()),
+                ..Resampler::new(self.hertz)
+            };
+        }
+
+        match packet {
+            Packet::Motion(packet) => self.update_motion(packet),
+            Packet::Telemetry(packet) => self.update_telemetry(packet),
+            _ => return Vec::new(),
+        }
+
+        let available = match (self.motion.latest(), self.telemetry.latest()) {
+            (Some(motion), Some(telemetry)) => motion.min(telemetry),
+            _ => return Vec::new(),
+        };
+
+        let interval = self.interval.as_nanos();
+        let mut next = *self.next.get_or_insert_with(|| {
+            let intervals = available.as_nanos().div_ceil(interval);
+            u64::try_from(intervals * interval).map_or(Duration::MAX, Duration::from_nanos)
+        });
+
+        let mut frames = Vec::new();
+
+        while next <= available {
+            if let Some(frame) = self.frame(next) {
+                frames.push(frame);
+            }
+
+            next = next.saturating_add(self.interval);
+        }
+
+        self.next = Some(next);
+        frames
+    }
+
+    fn update_motion(&mut self, packet: &MotionPacket) {
+        self.motion
+            .push(*packet.header().session_time(), packet.cars());
+    }
+
+    fn update_telemetry(&mut self, packet: &TelemetryPacket) {
+        self.telemetry
+            .push(*packet.header().session_time(), packet.telemetry());
+    }
+
+    fn frame(&self, time: Duration) -> Option<Frame> {
+        let (motion_before, motion_after, m) = self.motion.around(time)?;
+        let (telemetry_before, telemetry_after, t) = self.telemetry.around(time)?;
+
+        let cars = motion_before
+            .len()
+            .min(motion_after.len())
+            .min(telemetry_before.len())
+            .min(telemetry_after.len());
+
+        let samples = (0..cars)
+            .map(|index| {
+                let (before, after) = (&motion_before[index], &motion_after[index]);
+                let (t_before, t_after) = (&telemetry_before[index], &telemetry_after[index]);
+
+                Sample::new(
+                    lerp_3d(before.position(), after.position(), m),
+                    lerp_3d(before.velocity(), after.velocity(), m),
+                    lerp_angle(before.yaw(), after.yaw(), m),
+                    lerp(t_before.speed() as f32, t_after.speed() as f32, t),
+                    lerp(t_before.throttle(), t_after.throttle(), t),
+                    lerp(t_before.steering(), t_after.steering(), t),
+                    lerp(t_before.brake(), t_after.brake(), t),
+                    if t < 0.5 {
+                        t_before.gear()
+                    } else {
+                        t_after.gear()
+                    },
+                    lerp(t_before.engine_rpm() as f32, t_after.engine_rpm() as f32, t),
+                )
+            })
+            .collect();
+
+        Some(Frame::new(time, samples))
+    }
+}
+
+fn lerp(from: f32, to: f32, ratio: f32) -> f32 {
+    from + (to - from) * ratio
+}
+
+fn lerp_3d(from: &Property3D<f32>, to: &Property3D<f32>, ratio: f32) -> Property3D<f32> {
+    Property3D::new(
+        lerp(from.x(), to.x(), ratio),
+        lerp(from.y(), to.y(), ratio),
+        lerp(from.z(), to.z(), ratio),
+    )
+}
+
+/// Interpolate between two angles in radians along the shorter arc.
+fn lerp_angle(from: f32, to: f32, ratio: f32) -> f32 {
+    let mut difference = (to - from) % (2.0 * PI);
+
+    if difference > PI {
+        difference -= 2.0 * PI;
+    } else if difference < -PI {
+        difference += 2.0 * PI;
+    }
+
+    let angle = from + difference * ratio;
+
+    if angle > PI {
+        angle - 2.0 * PI
+    } else if angle < -PI {
+        angle + 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use std::time::Duration;
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::fixtures::{header, motion, motion_packet, telemetry, telemetry_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::Gear;
+    use crate::packet::Packet;
+    use crate::resample::{lerp_angle, Resampler};
+    use crate::types::{CornerProperty, Property3D};
+
+    fn motion_at(millis: u64, x: f32) -> Packet {
+        motion_packet(
+            header(PacketType::Motion, 1, Duration::from_millis(millis), 0),
+            vec![motion(
+                Property3D::new(x, 0.0, 0.0),
+                Property3D::default(),
+                Property3D::default(),
+            )],
+            CornerProperty::default(),
+        )
+    }
+
+    fn telemetry_at(millis: u64, speed: u16, gear: Gear) -> Packet {
+        telemetry_packet(
+            header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+            vec![telemetry(speed, 1.0, 0.0, 0.0, gear, 10000)],
+        )
+    }
+
+    #[test]
+    fn update_with_interpolation() {
+        let mut resampler = Resampler::new(50.0);
+
+        assert!(resampler.update(&motion_at(0, 0.0)).is_empty());
+
+        let frames = resampler.update(&telemetry_at(0, 100, Gear::Third));
+        assert_eq!(1, frames.len());
+        assert_eq!(Duration::from_millis(0), *frames[0].session_time());
+
+        assert!(resampler.update(&motion_at(50, 50.0)).is_empty());
+
+        let frames = resampler.update(&telemetry_at(50, 150, Gear::Fourth));
+        assert_eq!(2, frames.len());
+        assert_eq!(Duration::from_millis(20), *frames[0].session_time());
+        assert_eq!(Duration::from_millis(40), *frames[1].session_time());
+
+        let sample = frames[0].samples()[0];
+        assert_approx_eq!(20.0, sample.position().x(), 0.01);
+        assert_approx_eq!(120.0, sample.speed(), 0.01);
+        assert_eq!(Gear::Third, sample.gear());
+
+        let sample = frames[1].samples()[0];
+        assert_eq!(Gear::Fourth, sample.gear());
+    }
+
+    #[test]
+    fn update_after_flashback() {
+        let mut resampler = Resampler::new(10.0);
+
+        resampler.update(&motion_at(1000, 0.0));
+        resampler.update(&telemetry_at(1000, 100, Gear::Third));
+        resampler.update(&motion_at(500, 0.0));
+
+        let frames = resampler.update(&telemetry_at(500, 100, Gear::Third));
+        assert_eq!(Duration::from_millis(500), *frames[0].session_time());
+    }
+
+    #[test]
+    fn update_with_tiny_rate() {
+        let mut resampler = Resampler::new(f32::MIN_POSITIVE);
+
+        resampler.update(&motion_at(0, 0.0));
+        assert_eq!(
+            1,
+            resampler.update(&telemetry_at(0, 100, Gear::Third)).len()
+        );
+
+        resampler.update(&motion_at(3_600_000, 0.0));
+        let frames = resampler.update(&telemetry_at(3_600_000, 100, Gear::Third));
+        assert!(frames.is_empty());
+    }
+
+    #[test]
+    fn lerp_angle_across_pi() {
+        assert_approx_eq!(PI, lerp_angle(0.9 * PI, -0.9 * PI, 0.5).abs(), 0.001);
+        assert_approx_eq!(0.0, lerp_angle(-0.5, 0.5, 0.5), 0.001);
+    }
+}
@@ -0,0 +1,324 @@
+//! REST API for the state of the current session
+//!
+//! Not every client can consume a stream. Mobile apps, bots, and scripts usually poll an HTTP API
+//! for the data they need. The `RestServer` consumes a stream of packets, keeps the session model
+//! up to date, and answers `GET` requests with the current state as JSON:
+//!
+//! - `/session` returns the track, weather, and type of the session.
+//! - `/participants` returns the drivers in the session.
+//! - `/cars/{index}/telemetry` returns the latest telemetry of a car.
+//! - `/laps/{index}` returns the completed laps of a car.
+//!
+//! Cars are identified by their vehicle index. The telemetry of cars whose drivers have restricted
+//! their telemetry is answered with `403 Forbidden`. Data that has not been received yet is
+//! answered with `404 Not Found`.
+
+use std::io::Error;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::http::{read_request, write_json, Request};
+use crate::packet::Packet;
+use crate::privacy::Privacy;
+use crate::tracker::SessionTracker;
+use crate::types::VehicleIndex;
+
+/// Models that requests are answered from
+#[derive(Debug, Default)]
+struct State {
+    tracker: SessionTracker,
+    privacy: Privacy,
+}
+
+/// Server that answers HTTP requests with the state of the current session
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use f1_api::rest::RestServer;
+/// use f1_api::F1;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let game = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+/// let api = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 8080);
+///
+/// RestServer::bind(api)
+///     .await
+///     .unwrap()
+///     .serve(F1::stream(game).unwrap())
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RestServer {
+    listener: TcpListener,
+}
+
+impl RestServer {
+    /// Bind the server to the given address.
+    pub async fn bind(address: SocketAddr) -> Result<Self, Error> {
+        Ok(RestServer {
+            listener: TcpListener::bind(address).await?,
+        })
+    }
+
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        self.listener.local_addr()
+    }
+
+    /// Consume the packets of the stream, and answer requests until it ends.
+    pub async fn serve<S>(self, packets: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Packet> + Unpin,
+    {
+        let state = Arc::new(Mutex::new(State::default()));
+        let acceptor = tokio::spawn(accept(self.listener, state.clone()));
+
+        let mut packets = packets;
+        while let Some(packet) = packets.next().await {
+            let mut state = state.lock().unwrap();
+            state.tracker.update(&packet);
+            state.privacy.update(&packet);
+        }
+
+        acceptor.abort();
+
+        Ok(())
+    }
+}
+
+/// Accept connections and spawn a task for each request.
+async fn accept(listener: TcpListener, state: Arc<Mutex<State>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(respond(stream, state.clone()));
+    }
+}
+
+/// Answer a single request, and close the connection.
+async fn respond(mut stream: TcpStream, state: Arc<Mutex<State>>) -> Result<(), Error> {
+    let (status, body) = match read_request(&mut stream).await {
+        Ok(request) => route(&state.lock().unwrap(), &request),
+        Err(error) => (400, error_body(&error.to_string())),
+    };
+
+    write_json(&mut stream, status, &body).await
+}
+
+/// Returns the status and body of the response to a request.
+fn route(state: &State, request: &Request) -> (u16, String) {
+    if request.method != "GET" {
+        return (405, error_body("Only GET requests are supported."));
+    }
+
+    let segments: Vec<&str> = request
+        .path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    let response = match segments.as_slice() {
+        ["session"] => session(&state.tracker),
+        ["participants"] => participants(&state.tracker),
+        ["cars", index, "telemetry"] => match index.parse() {
+            Ok(index) => telemetry(state, index),
+            Err(_) => Err((400, "The vehicle index is not a number.")),
+        },
+        ["laps", index] => match index.parse() {
+            Ok(index) => laps(&state.tracker, index),
+            Err(_) => Err((400, "The vehicle index is not a number.")),
+        },
+        _ => Err((404, "The resource does not exist.")),
+    };
+
+    match response {
+        Ok(body) => (200, body.to_string()),
+        Err((status, message)) => (status, error_body(message)),
+    }
+}
+
+fn session(tracker: &SessionTracker) -> Result<Value, (u16, &'static str)> {
+    let session = tracker
+        .session()
+        .as_ref()
+        .ok_or((404, "No session data has been received yet."))?;
+
+    Ok(json!({
+        "session_uid": session.header().session_uid().to_string(),
+        "session_type": format!("{:?}", session.session_type()),
+        "track": format!("{:?}", session.track()),
+        "formula": format!("{:?}", session.formula()),
+        "weather": format!("{:?}", session.weather()),
+        "track_temperature": session.track_temperature(),
+        "air_temperature": session.air_temperature(),
+        "total_laps": session.total_laps(),
+        "track_length": session.track_length(),
+        "time_left": session.time_left().as_secs(),
+        "duration": session.duration().as_secs(),
+        "safety_car": format!("{:?}", session.safety_car()),
+    }))
+}
+
+fn participants(tracker: &SessionTracker) -> Result<Value, (u16, &'static str)> {
+    let participants = tracker
+        .participants()
+        .as_ref()
+        .ok_or((404, "No participants have been received yet."))?;
+
+    Ok(participants
+        .participants()
+        .iter_active()
+        .map(|(index, participant)| {
+            json!({
+                "vehicle_index": index,
+                "name": participant.name(),
+                "team": format!("{:?}", participant.team()),
+                "nationality": format!("{:?}", participant.nationality()),
+                "race_number": participant.race_number(),
+                "controller": format!("{:?}", participant.controller()),
+            })
+        })
+        .collect())
+}
+
+fn telemetry(state: &State, vehicle_index: VehicleIndex) -> Result<Value, (u16, &'static str)> {
+    let packet = state
+        .tracker
+        .telemetry()
+        .as_ref()
+        .ok_or((404, "No telemetry has been received yet."))?;
+
+    let telemetry = state
+        .privacy
+        .telemetry(packet)
+        .get(vehicle_index as usize)
+        .copied()
+        .ok_or((404, "The car does not exist."))?
+        .ok_or((403, "The driver has restricted their telemetry."))?;
+
+    Ok(json!({
+        "vehicle_index": vehicle_index,
+        "session_time": packet.header().session_time().as_secs_f64(),
+        "speed": telemetry.speed(),
+        "throttle": telemetry.throttle(),
+        "brake": telemetry.brake(),
+        "steering": telemetry.steering(),
+        "clutch": telemetry.clutch(),
+        "gear": telemetry.gear() as i8,
+        "engine_rpm": telemetry.engine_rpm(),
+        "drs": telemetry.drs(),
+        "engine_temperature": telemetry.engine_temperature(),
+    }))
+}
+
+fn laps(
+    tracker: &SessionTracker,
+    vehicle_index: VehicleIndex,
+) -> Result<Value, (u16, &'static str)> {
+    let history = tracker
+        .car(vehicle_index)
+        .ok_or((404, "The car does not exist."))?;
+
+    Ok(history
+        .laps()
+        .iter()
+        .map(|lap| {
+            json!({
+                "lap_number": lap.lap_number(),
+                "lap_time": lap.lap_time().as_secs_f64(),
+                "sector1_time": lap.sector1_time().as_secs_f64(),
+                "sector2_time": lap.sector2_time().as_secs_f64(),
+                "sector3_time": lap.sector3_time().as_secs_f64(),
+                "is_valid": lap.is_valid(),
+                "position": lap.position(),
+                "tyre_compound": lap.tyre_compound().map(|tyre| format!("{:?}", tyre)),
+                "pit_stop": lap.pit_stop(),
+            })
+        })
+        .collect())
+}
+
+fn error_body(message: &str) -> String {
+    json!({ "error": message }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, SocketAddr};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use crate::fixtures;
+    use crate::http::Request;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::rest::{route, RestServer, State};
+
+    fn get(path: &str) -> Request {
+        Request {
+            method: "GET".into(),
+            path: path.into(),
+        }
+    }
+
+    #[test]
+    fn route_answers_missing_data_with_not_found() {
+        let state = State::default();
+
+        assert_eq!(404, route(&state, &get("/session")).0);
+        assert_eq!(404, route(&state, &get("/cars/0/telemetry")).0);
+        assert_eq!(400, route(&state, &get("/laps/first")).0);
+        assert_eq!(404, route(&state, &get("/unknown")).0);
+
+        let post = Request {
+            method: "POST".into(),
+            path: "/session".into(),
+        };
+        assert_eq!(405, route(&state, &post).0);
+    }
+
+    #[test]
+    fn route_answers_session() {
+        let mut state = State::default();
+
+        for packet in fixtures::packets(ApiSpec::Nineteen, PacketType::Session).unwrap() {
+            state.tracker.update(&packet);
+        }
+
+        let (status, body) = route(&state, &get("/session/"));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(200, status);
+        assert!(json["track"].is_string());
+    }
+
+    #[tokio::test]
+    async fn serve_answers_requests() {
+        let server = RestServer::bind(SocketAddr::new(IpAddr::from([127, 0, 0, 1]), 0))
+            .await
+            .unwrap();
+        let address = server.local_addr().unwrap();
+
+        tokio::spawn(server.serve(tokio_stream::pending()));
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        client
+            .write_all(b"GET /participants HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+        assert!(response.ends_with(r#"{"error":"No participants have been received yet."}"#));
+    }
+}
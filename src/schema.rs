@@ -0,0 +1,167 @@
+//! Versions of the stored representations of packets
+//!
+//! Archives of telemetry are kept for years, while the packets of this crate gain fields with every
+//! new game. Every JSON object in the format of the `jsonl` module therefore carries the version of
+//! its schema in the `schema_version` field. When the schema changes, the version is incremented,
+//! and a migration is added that upgrades objects of the previous version. `migrate` applies the
+//! migrations that an object is missing, so that archives written by an older version of this crate
+//! can still be read by a newer one.
+//!
+//! Fields that have been added to a packet since an object was written are filled with `null`.
+//! Objects that were written before the schema was versioned are treated as version 0.
+//!
+//! Captures and shared memory have their own versions in their headers, and the `postgres` module
+//! records the version of its tables in the database.
+
+use std::io::{Error, ErrorKind};
+
+use serde_json::{Map, Value};
+
+use crate::export::table;
+use crate::packet::header::PacketType;
+
+/// Version of the schema that is written by this crate
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Name of the field that contains the version of the schema
+pub const VERSION_FIELD: &str = "schema_version";
+
+/// Number of columns at the start of every row that contain the fields of the header
+const HEADER_COLUMNS: usize = 4;
+
+/// Upgrade of an object from one version of the schema to the next
+type Migration = fn(&mut Map<String, Value>) -> Result<(), Error>;
+
+/// Migrations that upgrade an object from the version at their index to the next version
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [from_unversioned];
+
+/// Returns the version of the schema of the object.
+pub fn version(object: &Map<String, Value>) -> Result<u32, Error> {
+    match object.get(VERSION_FIELD) {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .map(|version| version as u32)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    "The schema version is not a number.",
+                )
+            }),
+    }
+}
+
+/// Upgrade the object to the current version of the schema.
+///
+/// Objects that have been written by a newer version of this crate cannot be downgraded, and return
+/// an error.
+pub fn migrate(mut object: Map<String, Value>) -> Result<Map<String, Value>, Error> {
+    let version = version(&object)?;
+
+    if version > SCHEMA_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Schema version {} is newer than the supported version {}.",
+                version, SCHEMA_VERSION
+            ),
+        ));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&mut object)?;
+    }
+
+    fill_missing_fields(&mut object)?;
+    object.insert(VERSION_FIELD.into(), SCHEMA_VERSION.into());
+
+    Ok(object)
+}
+
+/// Objects without a version have the same fields as version 1.
+fn from_unversioned(_object: &mut Map<String, Value>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Fill the fields that the current schema has, but the object does not, with `null`.
+fn fill_missing_fields(object: &mut Map<String, Value>) -> Result<(), Error> {
+    let columns = table::columns(packet_type(object)?);
+
+    for column in columns.iter().take(HEADER_COLUMNS) {
+        object.entry(column.name.clone()).or_insert(Value::Null);
+    }
+
+    if let Some(Value::Array(rows)) = object.get_mut("data") {
+        for row in rows.iter_mut().filter_map(Value::as_object_mut) {
+            for column in columns.iter().skip(HEADER_COLUMNS) {
+                row.entry(column.name.clone()).or_insert(Value::Null);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the type of the packet that the object represents.
+fn packet_type(object: &Map<String, Value>) -> Result<PacketType, Error> {
+    let name = object.get("type").and_then(Value::as_str);
+
+    [
+        PacketType::Event,
+        PacketType::Lap,
+        PacketType::Motion,
+        PacketType::Participants,
+        PacketType::Session,
+        PacketType::Setup,
+        PacketType::Status,
+        PacketType::Telemetry,
+    ]
+    .iter()
+    .copied()
+    .find(|packet_type| Some(table::name(*packet_type)) == name)
+    .ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            "The object does not have a known packet type.",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::schema::{migrate, SCHEMA_VERSION};
+
+    fn object(value: Value) -> serde_json::Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn migrate_unversioned_object() {
+        let old = object(json!({
+            "type": "event",
+            "session_uid": "4242",
+            "session_time": 12.5,
+            "data": [{ "event": "SessionStarted" }],
+        }));
+
+        let migrated = migrate(old).unwrap();
+
+        assert_eq!(SCHEMA_VERSION, migrated["schema_version"]);
+        assert_eq!(Value::Null, migrated["player_car_index"]);
+        assert_eq!("SessionStarted", migrated["data"][0]["event"]);
+        assert_eq!(Value::Null, migrated["data"][0]["lap_time"]);
+    }
+
+    #[test]
+    fn migrate_rejects_newer_versions() {
+        let new = object(json!({
+            "type": "event",
+            "schema_version": SCHEMA_VERSION + 1,
+            "data": [],
+        }));
+
+        assert!(migrate(new).is_err());
+    }
+}
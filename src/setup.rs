@@ -0,0 +1,266 @@
+//! Setup files that can be read and edited by humans
+//!
+//! Setups that have been captured from the game can be saved as TOML or JSON files, for example to
+//! archive them, to share them with other drivers in a league, or to compare them with a diff tool.
+//! Saving setups as TOML requires the `toml` feature, and saving them as JSON the `json` feature.
+//!
+//! The files group the parameters of a setup in the same way as the setup menu of the game:
+//!
+//! ```toml
+//! [aerodynamics]
+//! front_wing = 5
+//! rear_wing = 6
+//!
+//! [transmission]
+//! on_throttle = 75
+//! off_throttle = 60
+//!
+//! [suspension_geometry]
+//! front_camber = -3.0
+//! rear_camber = -1.5
+//! front_toe = 0.05
+//! rear_toe = 0.2
+//!
+//! [suspension]
+//! front_suspension = 5
+//! rear_suspension = 4
+//! front_anti_roll_bar = 5
+//! rear_anti_roll_bar = 6
+//! front_ride_height = 3
+//! rear_ride_height = 4
+//!
+//! [brakes]
+//! pressure = 100
+//! bias = 56
+//!
+//! [tyres]
+//! front_pressure = 23.0
+//! rear_pressure = 21.5
+//!
+//! [weight]
+//! ballast = 6
+//! fuel_load = 10.0
+//! ```
+//!
+//! JSON files use the same layout, with one object per group.
+
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use crate::packet::setup::CarSetup;
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+struct SetupFile {
+    aerodynamics: Aerodynamics,
+    transmission: Transmission,
+    suspension_geometry: SuspensionGeometry,
+    suspension: Suspension,
+    brakes: Brakes,
+    tyres: Tyres,
+    weight: Weight,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+struct Aerodynamics {
+    front_wing: u8,
+    rear_wing: u8,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+struct Transmission {
+    on_throttle: u8,
+    off_throttle: u8,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+struct SuspensionGeometry {
+    front_camber: f32,
+    rear_camber: f32,
+    front_toe: f32,
+    rear_toe: f32,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+struct Suspension {
+    front_suspension: u8,
+    rear_suspension: u8,
+    front_anti_roll_bar: u8,
+    rear_anti_roll_bar: u8,
+    front_ride_height: u8,
+    rear_ride_height: u8,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+struct Brakes {
+    pressure: u8,
+    bias: u8,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+struct Tyres {
+    front_pressure: f32,
+    rear_pressure: f32,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+struct Weight {
+    ballast: u8,
+    fuel_load: f32,
+}
+
+impl From<&CarSetup> for SetupFile {
+    fn from(setup: &CarSetup) -> Self {
+        SetupFile {
+            aerodynamics: Aerodynamics {
+                front_wing: setup.front_wing(),
+                rear_wing: setup.rear_wing(),
+            },
+            transmission: Transmission {
+                on_throttle: setup.on_throttle(),
+                off_throttle: setup.off_throttle(),
+            },
+            suspension_geometry: SuspensionGeometry {
+                front_camber: setup.front_camber(),
+                rear_camber: setup.rear_camber(),
+                front_toe: setup.front_toe(),
+                rear_toe: setup.rear_toe(),
+            },
+            suspension: Suspension {
+                front_suspension: setup.front_suspension(),
+                rear_suspension: setup.rear_suspension(),
+                front_anti_roll_bar: setup.front_anti_roll_bar(),
+                rear_anti_roll_bar: setup.rear_anti_roll_bar(),
+                front_ride_height: setup.front_suspension_height(),
+                rear_ride_height: setup.rear_suspension_height(),
+            },
+            brakes: Brakes {
+                pressure: setup.brake_pressure(),
+                bias: setup.brake_bias(),
+            },
+            tyres: Tyres {
+                front_pressure: setup.front_tyre_pressure(),
+                rear_pressure: setup.rear_tyre_pressure(),
+            },
+            weight: Weight {
+                ballast: setup.ballast(),
+                fuel_load: setup.fuel_load(),
+            },
+        }
+    }
+}
+
+impl From<SetupFile> for CarSetup {
+    fn from(file: SetupFile) -> Self {
+        CarSetup::new(
+            file.aerodynamics.front_wing,
+            file.aerodynamics.rear_wing,
+            file.transmission.on_throttle,
+            file.transmission.off_throttle,
+            file.suspension_geometry.front_camber,
+            file.suspension_geometry.rear_camber,
+            file.suspension_geometry.front_toe,
+            file.suspension_geometry.rear_toe,
+            file.suspension.front_suspension,
+            file.suspension.rear_suspension,
+            file.suspension.front_anti_roll_bar,
+            file.suspension.rear_anti_roll_bar,
+            file.suspension.front_ride_height,
+            file.suspension.rear_ride_height,
+            file.brakes.pressure,
+            file.brakes.bias,
+            file.tyres.front_pressure,
+            file.tyres.rear_pressure,
+            file.weight.ballast,
+            file.weight.fuel_load,
+        )
+    }
+}
+
+/// Serialize a setup as TOML.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::packet::setup::CarSetup;
+/// use f1_api::setup::{from_toml, to_toml};
+///
+/// let setup = CarSetup::default();
+/// let toml = to_toml(&setup).unwrap();
+///
+/// assert_eq!(setup, from_toml(&toml).unwrap());
+/// ```
+#[cfg(feature = "toml")]
+pub fn to_toml(setup: &CarSetup) -> Result<String, Error> {
+    toml::to_string(&SetupFile::from(setup))
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+/// Deserialize a setup from TOML.
+///
+/// An error is returned if the TOML does not follow the layout of a setup file.
+#[cfg(feature = "toml")]
+pub fn from_toml(toml: &str) -> Result<CarSetup, Error> {
+    toml::from_str::<SetupFile>(toml)
+        .map(CarSetup::from)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+/// Serialize a setup as pretty-printed JSON.
+#[cfg(feature = "json")]
+pub fn to_json(setup: &CarSetup) -> Result<String, Error> {
+    serde_json::to_string_pretty(&SetupFile::from(setup))
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+/// Deserialize a setup from JSON.
+///
+/// An error is returned if the JSON does not follow the layout of a setup file.
+#[cfg(feature = "json")]
+pub fn from_json(json: &str) -> Result<CarSetup, Error> {
+    serde_json::from_str::<SetupFile>(json)
+        .map(CarSetup::from)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet::setup::CarSetup;
+
+    fn setup() -> CarSetup {
+        CarSetup::new(
+            5, 6, 75, 60, -3.0, -1.5, 0.05, 0.2, 5, 4, 5, 6, 3, 4, 100, 56, 23.0, 21.5, 6, 10.0,
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn toml_round_trip() {
+        use crate::setup::{from_toml, to_toml};
+
+        let toml = to_toml(&setup()).unwrap();
+
+        assert!(toml.contains("[aerodynamics]\nfront_wing = 5\nrear_wing = 6\n"));
+        assert!(toml.contains("front_ride_height = 3\n"));
+        assert_eq!(setup(), from_toml(&toml).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn from_toml_with_missing_group() {
+        use crate::setup::from_toml;
+
+        assert!(from_toml("[aerodynamics]\nfront_wing = 5\nrear_wing = 6\n").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_round_trip() {
+        use crate::setup::{from_json, to_json};
+
+        let json = to_json(&setup()).unwrap();
+
+        assert!(json.contains("\"bias\": 56"));
+        assert_eq!(setup(), from_json(&json).unwrap());
+    }
+}
@@ -0,0 +1,402 @@
+//! Shared memory with the latest state of every car
+//!
+//! Many tools and plugins for racing simulators read telemetry from a block of shared memory that
+//! the simulator updates in place, instead of receiving it over the network. This module writes the
+//! latest state of every car into such a block in a fixed layout, so that these tools can read the
+//! data of the F1 games with this crate acting as a bridge. On Windows, the block is published as a
+//! named file mapping by the `SharedMemoryWriter`, which can also be driven as an exporter by the
+//! `Fanout`. On other platforms, the layout can be written to any buffer with `write_layout`.
+//!
+//! # Layout
+//!
+//! The block has a size of `SHARED_MEMORY_SIZE` bytes. All numbers are little endian, and all times
+//! are in seconds. The block starts with a header, which is followed by `SHARED_MEMORY_CARS` cars.
+//!
+//! | Offset | Type  | Header field                                      |
+//! |--------|-------|---------------------------------------------------|
+//! | 0      | `u32` | Version of the layout, currently `1`              |
+//! | 4      | `u32` | Update counter, odd while the block is written    |
+//! | 8      | `u64` | Unique id of the session                          |
+//! | 16     | `f32` | Session time                                      |
+//! | 20     | `u32` | Frame identifier                                  |
+//! | 24     | `u8`  | Index of the player's car                         |
+//! | 25     | `u8`  | Number of cars with data                          |
+//! | 26     | -     | Reserved                                          |
+//!
+//! Car `i` starts at offset `32 + i * 72`:
+//!
+//! | Offset | Type      | Car field                                                  |
+//! |--------|-----------|------------------------------------------------------------|
+//! | 0      | `f32` × 3 | Position in the world in meters (x, y, z)                  |
+//! | 12     | `f32`     | Speed in kilometers per hour                               |
+//! | 16     | `f32`     | Throttle, from 0 to 1                                      |
+//! | 20     | `f32`     | Brake, from 0 to 1                                         |
+//! | 24     | `f32`     | Steering, from -1 (left) to 1 (right)                      |
+//! | 28     | `i8`      | Gear, -1 for reverse and 0 for neutral                     |
+//! | 29     | `u8`      | DRS, 1 if open                                             |
+//! | 30     | `u8`      | Position in the race                                       |
+//! | 31     | `u8`      | Current lap                                                |
+//! | 32     | `u16`     | Engine RPM                                                 |
+//! | 34     | `u8`      | Pit status: 0 on track, 1 in the pit lane, 2 in the pit box |
+//! | 35     | `u8`      | Visual tyre compound in the order of `VisualTyreCompound`  |
+//! | 36     | `f32`     | Distance into the lap in meters                            |
+//! | 40     | `f32`     | Last lap time                                              |
+//! | 44     | `f32`     | Current lap time                                           |
+//! | 48     | `f32`     | Fuel in the tank in kilograms                              |
+//! | 52     | `f32`     | ERS energy in joules                                       |
+//! | 56     | `f32` × 4 | Tyre wear in percent (front left, front right, rear left, rear right) |
+//!
+//! Readers should read the update counter before and after copying the block, and copy it again
+//! if the counter changed or is odd. Fields of data that has not been received yet are zero, and
+//! the tyre compound is `255`.
+
+#[cfg(all(windows, feature = "net"))]
+use crate::sink::{ExportFuture, Exporter, Item};
+use crate::tracker::SessionTracker;
+use crate::types::VehicleIndex;
+
+/// Version of the layout of the shared memory
+pub const SHARED_MEMORY_VERSION: u32 = 1;
+
+/// Number of cars in the shared memory
+pub const SHARED_MEMORY_CARS: usize = 20;
+
+/// Size of the header in bytes
+const HEADER_SIZE: usize = 32;
+
+/// Size of each car in bytes
+const CAR_SIZE: usize = 72;
+
+/// Size of the shared memory in bytes
+pub const SHARED_MEMORY_SIZE: usize = HEADER_SIZE + SHARED_MEMORY_CARS * CAR_SIZE;
+
+/// Offset of the update counter in the header
+const COUNTER_OFFSET: usize = 4;
+
+/// Offset of the first byte after the update counter
+const PAYLOAD_OFFSET: usize = COUNTER_OFFSET + 4;
+
+/// Write the latest state of the session into the buffer.
+///
+/// The update counter is left untouched, since it is managed by the writer of the shared memory.
+///
+/// # Panics
+///
+/// Panics if the buffer is smaller than `SHARED_MEMORY_SIZE`.
+pub fn write_layout(tracker: &SessionTracker, buffer: &mut [u8]) {
+    let buffer = &mut buffer[..SHARED_MEMORY_SIZE];
+
+    buffer[..COUNTER_OFFSET].fill(0);
+    buffer[PAYLOAD_OFFSET..].fill(0);
+    write(buffer, 0, &SHARED_MEMORY_VERSION.to_le_bytes());
+    write(
+        buffer,
+        8,
+        &tracker.session_uid().unwrap_or_default().to_le_bytes(),
+    );
+
+    let mut cars = 0;
+    let mut latest = None;
+
+    for index in 0..SHARED_MEMORY_CARS {
+        let snapshot = match tracker.snapshot(index as VehicleIndex) {
+            Some(snapshot) => snapshot,
+            None => continue,
+        };
+
+        cars = index + 1;
+        latest = latest
+            .filter(|(time, _)| time >= snapshot.session_time())
+            .or(Some((
+                *snapshot.session_time(),
+                snapshot.frame_identifier(),
+            )));
+
+        let car = &mut buffer[HEADER_SIZE + index * CAR_SIZE..][..CAR_SIZE];
+        car[35] = u8::MAX;
+
+        if let Some(motion) = snapshot.motion() {
+            let position = motion.position();
+            write_f32(car, 0, position.x());
+            write_f32(car, 4, position.y());
+            write_f32(car, 8, position.z());
+        }
+
+        if let Some(telemetry) = snapshot.telemetry() {
+            write_f32(car, 12, telemetry.speed() as f32);
+            write_f32(car, 16, telemetry.throttle());
+            write_f32(car, 20, telemetry.brake());
+            write_f32(car, 24, telemetry.steering());
+            car[28] = telemetry.gear() as i8 as u8;
+            car[29] = telemetry.drs() as u8;
+            write(car, 32, &telemetry.engine_rpm().to_le_bytes());
+        }
+
+        if let Some(lap) = snapshot.lap() {
+            car[30] = lap.position();
+            car[31] = lap.current_lap_number();
+            car[34] = lap.pit_status() as u8;
+            write_f32(car, 36, lap.lap_distance());
+            write_f32(car, 40, lap.last_lap_time().as_secs_f32());
+            write_f32(car, 44, lap.current_lap_time().as_secs_f32());
+        }
+
+        if let Some(status) = snapshot.status() {
+            let wear = status.tyre_wear();
+            car[35] = status.visual_tyre_compound() as u8;
+            write_f32(car, 48, status.fuel_remaining());
+            write_f32(car, 52, status.ers_energy());
+            write_f32(car, 56, wear.front_left() as f32);
+            write_f32(car, 60, wear.front_right() as f32);
+            write_f32(car, 64, wear.rear_left() as f32);
+            write_f32(car, 68, wear.rear_right() as f32);
+        }
+    }
+
+    if let Some((session_time, frame_identifier)) = latest {
+        write_f32(buffer, 16, session_time.as_secs_f32());
+        write(buffer, 20, &frame_identifier.to_le_bytes());
+    }
+
+    if let Some(player) = tracker.laps().as_ref() {
+        buffer[24] = player.header().player_car_index();
+    }
+
+    buffer[25] = cars as u8;
+}
+
+/// Writer of the shared memory on Windows
+///
+/// The writer creates a named file mapping, for example `Local\F1ApiTelemetry`, that other
+/// processes can open with `OpenFileMappingW` and read with `MapViewOfFile`. The mapping is closed
+/// when the writer is dropped.
+///
+/// Applications that track the session themselves pass their tracker to `update`. As an exporter,
+/// the writer keeps its own tracker, which is updated with every packet before the block is written.
+///
+/// # Examples
+///
+/// ```no_run
+/// use f1_api::shm::SharedMemoryWriter;
+/// use f1_api::tracker::SessionTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut writer = SharedMemoryWriter::create("Local\\F1ApiTelemetry")?;
+/// let mut tracker = SessionTracker::default();
+///
+/// for packet in packets() {
+///     tracker.update(&packet);
+///     writer.update(&tracker);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(windows)]
+#[derive(Debug)]
+pub struct SharedMemoryWriter {
+    mapping: windows_sys::Win32::Foundation::HANDLE,
+    view: *mut u8,
+    block: Vec<u8>,
+    counter: u32,
+    #[cfg(feature = "net")]
+    tracker: SessionTracker,
+}
+
+// SAFETY: The mapping and its view are owned by the writer, and only accessed through `&mut self`.
+// Windows allows file mappings to be used and closed from any thread.
+#[cfg(windows)]
+unsafe impl Send for SharedMemoryWriter {}
+
+#[cfg(windows)]
+impl SharedMemoryWriter {
+    /// Create a named file mapping for the shared memory.
+    pub fn create(name: &str) -> Result<Self, std::io::Error> {
+        use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+        use windows_sys::Win32::System::Memory::{
+            CreateFileMappingW, MapViewOfFile, FILE_MAP_WRITE, PAGE_READWRITE,
+        };
+
+        let name: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+
+        // SAFETY: The name is a null-terminated UTF-16 string that outlives the call, and the
+        // handle is checked before it is used.
+        let mapping = unsafe {
+            CreateFileMappingW(
+                INVALID_HANDLE_VALUE,
+                std::ptr::null(),
+                PAGE_READWRITE,
+                0,
+                SHARED_MEMORY_SIZE as u32,
+                name.as_ptr(),
+            )
+        };
+
+        if mapping.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // SAFETY: The mapping is a valid handle with a size of `SHARED_MEMORY_SIZE` bytes.
+        let view = unsafe { MapViewOfFile(mapping, FILE_MAP_WRITE, 0, 0, SHARED_MEMORY_SIZE) };
+
+        if view.Value.is_null() {
+            let error = std::io::Error::last_os_error();
+
+            // SAFETY: The mapping is a valid handle that is not used afterwards.
+            unsafe { CloseHandle(mapping) };
+
+            return Err(error);
+        }
+
+        Ok(SharedMemoryWriter {
+            mapping,
+            view: view.Value as *mut u8,
+            block: vec![0; SHARED_MEMORY_SIZE],
+            counter: 0,
+            #[cfg(feature = "net")]
+            tracker: SessionTracker::default(),
+        })
+    }
+
+    /// Write the latest state of the session into the shared memory.
+    ///
+    /// The block is composed in a private buffer first, so that the shared memory is only written
+    /// while the update counter is odd.
+    pub fn update(&mut self, tracker: &SessionTracker) {
+        write_layout(tracker, &mut self.block);
+        self.publish_block();
+    }
+
+    fn publish_block(&mut self) {
+        // SAFETY: The view maps `SHARED_MEMORY_SIZE` writable bytes that are owned by the writer,
+        // and no references into it exist. It is page-aligned, so the counter is aligned as well.
+        unsafe { publish(self.view, &self.block, &mut self.counter) };
+    }
+}
+
+/// Packets update the tracker of the writer and are written to the shared memory, while changes
+/// are ignored.
+#[cfg(all(windows, feature = "net"))]
+impl Exporter for SharedMemoryWriter {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        if let Item::Packet(packet) = item {
+            self.tracker.update(packet);
+            write_layout(&self.tracker, &mut self.block);
+            self.publish_block();
+        }
+
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+#[cfg(windows)]
+impl Drop for SharedMemoryWriter {
+    fn drop(&mut self) {
+        use windows_sys::Win32::Foundation::CloseHandle;
+        use windows_sys::Win32::System::Memory::{UnmapViewOfFile, MEMORY_MAPPED_VIEW_ADDRESS};
+
+        // SAFETY: The view and the mapping were created by the writer, and are not used again.
+        unsafe {
+            UnmapViewOfFile(MEMORY_MAPPED_VIEW_ADDRESS {
+                Value: self.view.cast(),
+            });
+            CloseHandle(self.mapping);
+        }
+    }
+}
+
+/// Copy the block into the shared memory at `base`, and increment the update counter before and
+/// after the copy.
+///
+/// The counter is only accessed atomically, and the bytes of the counter in the block are ignored.
+///
+/// # Safety
+///
+/// `base` must point to `SHARED_MEMORY_SIZE` writable bytes that are aligned to four bytes, and
+/// that are not accessed through references while the block is copied.
+#[cfg(any(windows, test))]
+unsafe fn publish(base: *mut u8, block: &[u8], counter: &mut u32) {
+    use std::sync::atomic::{fence, AtomicU32, Ordering};
+
+    assert!(block.len() >= SHARED_MEMORY_SIZE);
+    let sequence = &*(base.add(COUNTER_OFFSET) as *const AtomicU32);
+
+    *counter = counter.wrapping_add(1);
+    sequence.store(*counter, Ordering::Relaxed);
+    fence(Ordering::Release);
+
+    std::ptr::copy_nonoverlapping(block.as_ptr(), base, COUNTER_OFFSET);
+    std::ptr::copy_nonoverlapping(
+        block.as_ptr().add(PAYLOAD_OFFSET),
+        base.add(PAYLOAD_OFFSET),
+        SHARED_MEMORY_SIZE - PAYLOAD_OFFSET,
+    );
+
+    *counter = counter.wrapping_add(1);
+    sequence.store(*counter, Ordering::Release);
+}
+
+fn write(buffer: &mut [u8], offset: usize, bytes: &[u8]) {
+    buffer[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn write_f32(buffer: &mut [u8], offset: usize, value: f32) {
+    write(buffer, offset, &value.to_le_bytes());
+}
+
+#[cfg(all(test, feature = "decode"))]
+mod tests {
+    use crate::fixtures;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::shm::{publish, write_layout, SHARED_MEMORY_SIZE};
+    use crate::tracker::SessionTracker;
+
+    fn f32_at(buffer: &[u8], offset: usize) -> f32 {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&buffer[offset..offset + 4]);
+        f32::from_le_bytes(bytes)
+    }
+
+    #[test]
+    fn write_layout_of_session() {
+        let mut tracker = SessionTracker::default();
+
+        for packet_type in [PacketType::Lap, PacketType::Telemetry] {
+            for packet in fixtures::packets(ApiSpec::Nineteen, packet_type).unwrap() {
+                tracker.update(&packet);
+            }
+        }
+
+        let mut buffer = vec![0xff; SHARED_MEMORY_SIZE];
+        buffer[4..8].copy_from_slice(&7u32.to_le_bytes());
+        write_layout(&tracker, &mut buffer);
+
+        let telemetry = tracker.snapshot(0).unwrap().telemetry().unwrap();
+        let lap = tracker.snapshot(0).unwrap().lap().unwrap();
+
+        assert_eq!([1, 0, 0, 0], buffer[0..4]);
+        assert_eq!([7, 0, 0, 0], buffer[4..8]);
+        assert_eq!(20, buffer[25]);
+        assert_eq!(telemetry.speed() as f32, f32_at(&buffer, 32 + 12));
+        assert_eq!(lap.position(), buffer[32 + 30]);
+        assert_eq!(u8::MAX, buffer[32 + 35]);
+        assert_eq!(0.0, f32_at(&buffer, 32 + 48));
+    }
+
+    #[test]
+    fn publish_increments_counter_around_copy() {
+        #[repr(align(4))]
+        struct Mapping([u8; SHARED_MEMORY_SIZE]);
+
+        let mut mapping = Mapping([0; SHARED_MEMORY_SIZE]);
+        let mut counter = 0;
+        let block = vec![0xab; SHARED_MEMORY_SIZE];
+
+        // SAFETY: The mapping is aligned, has the size of the shared memory, and is not borrowed.
+        unsafe { publish(mapping.0.as_mut_ptr(), &block, &mut counter) };
+
+        assert_eq!(2, counter);
+        assert_eq!([0xab; 4], mapping.0[0..4]);
+        assert_eq!([2, 0, 0, 0], mapping.0[4..8]);
+        assert!(mapping.0[8..].iter().all(|byte| *byte == 0xab));
+    }
+}
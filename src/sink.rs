@@ -0,0 +1,378 @@
+//! Exporters and a runner that feeds them concurrently
+//!
+//! Applications often write the same session to several destinations at once, for example to JSON
+//! lines for debugging, to Kafka for a streaming pipeline, and to PostgreSQL for the history of a
+//! season. The `Exporter` trait is the common interface of these sinks. It receives the packets and
+//! the changes that are derived from them, and is flushed and shut down when the session ends. All
+//! sinks of this crate implement it, and applications can implement it for their own backends.
+//!
+//! The `Fanout` drives any number of exporters from a single stream of packets. Each exporter runs
+//! in its own task with its own queue, so that a slow or failing exporter does not hold up the
+//! others. When the queue of an exporter is full, items are dropped for this exporter. When an
+//! exporter returns an error, it stops receiving items. The result of each exporter and the number
+//! of items that it dropped are reported when the stream ends.
+
+use std::future::Future;
+use std::io::Error;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::packet::header::Header;
+use crate::packet::Packet;
+use crate::stream::changes::{Change, ChangeDetector};
+
+/// Number of items that are queued for each exporter
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Future that is returned by the methods of an exporter
+pub type ExportFuture<'a> = Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>;
+
+/// Item that is passed to an exporter
+#[derive(Debug, PartialEq, Clone)]
+pub enum Item {
+    /// A packet that has been received from the game.
+    Packet(Packet),
+
+    /// A change that has been derived from the packet with the given header.
+    Change(Header, Change),
+}
+
+/// Destination for packets and the changes derived from them
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::sink::{ExportFuture, Exporter, Item};
+///
+/// #[derive(Default)]
+/// struct Counter {
+///     packets: usize,
+/// }
+///
+/// impl Exporter for Counter {
+///     fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+///         if let Item::Packet(_) = item {
+///             self.packets += 1;
+///         }
+///
+///         Box::pin(async { Ok(()) })
+///     }
+/// }
+/// ```
+pub trait Exporter: Send {
+    /// Export a packet or a change.
+    ///
+    /// Exporters that are only interested in packets ignore the changes.
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a>;
+
+    /// Write any buffered data.
+    fn flush(&mut self) -> ExportFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Write any buffered data, and release the resources of the exporter.
+    ///
+    /// The exporter receives no further items after it has been shut down.
+    fn shutdown(&mut self) -> ExportFuture<'_> {
+        self.flush()
+    }
+}
+
+/// Outcome of an exporter when the stream has ended
+#[derive(new, Debug, Getters, CopyGetters)]
+pub struct ExportReport {
+    /// Returns the result of the exporter, which is the first error that it returned.
+    #[getset(get = "pub")]
+    result: Result<(), Error>,
+
+    /// Returns the number of items that were dropped because the queue of the exporter was full.
+    #[getset(get_copy = "pub")]
+    dropped: u64,
+}
+
+impl ExportReport {
+    /// Drop the number of dropped items and return the result of the exporter.
+    pub fn into_result(self) -> Result<(), Error> {
+        self.result
+    }
+}
+
+/// Runner that feeds a stream of packets into several exporters
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use f1_api::export::{FlatFileWriter, Format};
+/// use f1_api::sink::Fanout;
+/// use f1_api::F1;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let game = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+///
+/// let reports = Fanout::new()
+///     .exporter(FlatFileWriter::new("csv", Format::Csv).unwrap())
+///     .exporter(FlatFileWriter::new("json", Format::Json).unwrap())
+///     .serve(F1::stream(game).unwrap())
+///     .await;
+///
+/// for report in reports {
+///     if report.dropped() > 0 {
+///         eprintln!("Dropped {} items", report.dropped());
+///     }
+///     if let Err(error) = report.result() {
+///         eprintln!("Export failed: {}", error);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Fanout {
+    exporters: Vec<Box<dyn Exporter>>,
+    capacity: Option<usize>,
+}
+
+impl Fanout {
+    /// Create a runner without exporters.
+    pub fn new() -> Self {
+        Fanout::default()
+    }
+
+    /// Add an exporter to the runner.
+    pub fn exporter<E: Exporter + 'static>(mut self, exporter: E) -> Self {
+        self.exporters.push(Box::new(exporter));
+        self
+    }
+
+    /// Queue up to the given number of items for each exporter.
+    ///
+    /// Each exporter queues at least one item, so a capacity of zero is treated as one.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity.max(1));
+        self
+    }
+
+    /// Feed the packets of the stream and their changes into the exporters until the stream ends,
+    /// and shut the exporters down.
+    ///
+    /// Returns a report with the result and the number of dropped items of each exporter, in the
+    /// order in which they were added.
+    pub async fn serve<S>(self, packets: S) -> Vec<ExportReport>
+    where
+        S: Stream<Item = Packet> + Unpin,
+    {
+        let capacity = self.capacity.unwrap_or(DEFAULT_CAPACITY);
+
+        let mut senders = Vec::with_capacity(self.exporters.len());
+        let mut tasks = Vec::with_capacity(self.exporters.len());
+
+        for exporter in self.exporters {
+            let (sender, receiver) = channel(capacity);
+
+            senders.push(sender);
+            tasks.push(tokio::spawn(run(exporter, receiver)));
+        }
+
+        let mut packets = packets;
+        let mut detector = ChangeDetector::default();
+        let mut dropped = vec![0; senders.len()];
+
+        while let Some(packet) = packets.next().await {
+            let header = *packet.header();
+            let changes = detector.update(&packet);
+
+            publish(&senders, &mut dropped, Item::Packet(packet));
+            for change in changes {
+                publish(&senders, &mut dropped, Item::Change(header, change));
+            }
+        }
+
+        drop(senders);
+
+        #[cfg(feature = "tracing")]
+        for (exporter, dropped) in dropped.iter().enumerate() {
+            if *dropped > 0 {
+                tracing::warn!(
+                    exporter,
+                    dropped,
+                    "Exporter dropped items with a full queue"
+                );
+            }
+        }
+
+        let mut reports = Vec::with_capacity(tasks.len());
+        for (task, dropped) in tasks.into_iter().zip(dropped) {
+            let result = task.await.unwrap_or_else(|error| Err(Error::other(error)));
+            reports.push(ExportReport::new(result, dropped));
+        }
+
+        reports
+    }
+}
+
+impl std::fmt::Debug for Fanout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Fanout")
+            .field("exporters", &self.exporters.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+/// Queue the item for every exporter that is still running, and count the exporters whose queue
+/// is full.
+fn publish(senders: &[Sender<Arc<Item>>], dropped: &mut [u64], item: Item) {
+    let item = Arc::new(item);
+
+    for (exporter, sender) in senders.iter().enumerate() {
+        if let Err(TrySendError::Full(_)) = sender.try_send(item.clone()) {
+            dropped[exporter] += 1;
+
+            #[cfg(feature = "tracing")]
+            if dropped[exporter] == 1 {
+                tracing::warn!(exporter, "Queue of exporter is full, dropping items");
+            }
+        }
+    }
+}
+
+/// Export the queued items until the queue is closed or the exporter fails, and shut it down.
+async fn run(
+    mut exporter: Box<dyn Exporter>,
+    mut receiver: Receiver<Arc<Item>>,
+) -> Result<(), Error> {
+    let mut result = Ok(());
+
+    while let Some(item) = receiver.recv().await {
+        if let Err(error) = exporter.export(&item).await {
+            result = Err(error);
+            break;
+        }
+    }
+
+    receiver.close();
+    let shutdown = exporter.shutdown().await;
+
+    result.and(shutdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Error;
+    use std::sync::{Arc, Mutex};
+
+    use tokio::sync::Semaphore;
+
+    use crate::fixtures;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::sink::{ExportFuture, Exporter, Fanout, Item};
+
+    #[derive(Default)]
+    struct Collect {
+        items: Arc<Mutex<Vec<Item>>>,
+        shut_down: Arc<Mutex<bool>>,
+    }
+
+    impl Exporter for Collect {
+        fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+            self.items.lock().unwrap().push(item.clone());
+            Box::pin(async { Ok(()) })
+        }
+
+        fn shutdown(&mut self) -> ExportFuture<'_> {
+            *self.shut_down.lock().unwrap() = true;
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    struct Fail;
+
+    struct Block(Arc<Semaphore>);
+
+    impl Exporter for Block {
+        fn export<'a>(&'a mut self, _item: &'a Item) -> ExportFuture<'a> {
+            Box::pin(async move {
+                let _permit = self.0.acquire().await.map_err(Error::other)?;
+                Ok(())
+            })
+        }
+    }
+
+    impl Exporter for Fail {
+        fn export<'a>(&'a mut self, _item: &'a Item) -> ExportFuture<'a> {
+            Box::pin(async { Err(Error::other("Backend is unavailable.")) })
+        }
+    }
+
+    #[tokio::test]
+    async fn serve_isolates_failing_exporters() {
+        let packets = fixtures::packets(ApiSpec::Nineteen, PacketType::Session).unwrap();
+
+        let collect = Collect::default();
+        let items = collect.items.clone();
+        let shut_down = collect.shut_down.clone();
+
+        let reports = Fanout::new()
+            .exporter(Fail)
+            .exporter(collect)
+            .serve(tokio_stream::iter(packets.clone()))
+            .await;
+
+        assert!(reports[0].result().is_err());
+        assert!(reports[1].result().is_ok());
+        assert_eq!(0, reports[1].dropped());
+        assert!(*shut_down.lock().unwrap());
+
+        let items = items.lock().unwrap();
+        let exported = items
+            .iter()
+            .filter(|item| matches!(item, Item::Packet(_)))
+            .count();
+
+        assert_eq!(packets.len(), exported);
+        assert!(items.iter().any(|item| matches!(item, Item::Change(..))));
+    }
+
+    #[tokio::test]
+    async fn serve_with_zero_capacity() {
+        let packets = fixtures::packets(ApiSpec::Nineteen, PacketType::Session).unwrap();
+
+        let reports = Fanout::new()
+            .capacity(0)
+            .exporter(Collect::default())
+            .serve(tokio_stream::iter(packets))
+            .await;
+
+        assert!(reports[0].result().is_ok());
+    }
+
+    #[tokio::test]
+    async fn serve_counts_dropped_items() {
+        let packets = fixtures::packets(ApiSpec::Nineteen, PacketType::Session).unwrap();
+
+        // The exporter is blocked until every packet has been published.
+        let gate = Arc::new(Semaphore::new(0));
+        let release = gate.clone();
+        let packets = packets.into_iter().chain(std::iter::from_fn(move || {
+            release.add_permits(Semaphore::MAX_PERMITS / 2);
+            None
+        }));
+
+        let reports = Fanout::new()
+            .capacity(1)
+            .exporter(Block(gate))
+            .serve(tokio_stream::iter(packets))
+            .await;
+
+        assert!(reports[0].result().is_ok());
+        assert!(reports[0].dropped() > 0);
+    }
+}
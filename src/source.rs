@@ -0,0 +1,238 @@
+//! Sources of packets
+//!
+//! Packets can come from the game, from a capture that is replayed, or from a script in a test.
+//! The `PacketSource` trait abstracts over where the packets come from, so that applications can
+//! be written once and run against the live game, a recorded session, or a fixed list of packets.
+//!
+//! The trait is implemented by the `UdpSource` that receives packets from the game, by the
+//! `CaptureReader` that reads a capture as fast as possible, by the `Replay` that plays a capture
+//! back with its original timing, and by the `MockSource` that returns a list of packets. Sources
+//! are blocking, which allows them to be used without an async runtime. Applications that use
+//! tokio can use the stream of `F1::stream` instead.
+
+use std::collections::VecDeque;
+use std::io::{Error, Read};
+use std::net::{SocketAddr, UdpSocket};
+
+use crate::capture::replay::Replay;
+use crate::capture::CaptureReader;
+use crate::codec::decode_bytes;
+use crate::handler::PacketHandler;
+use crate::packet::Packet;
+
+/// Maximum size of a datagram that is received from the game
+const BUFFER_SIZE: usize = 2048;
+
+/// Source of packets
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::source::{MockSource, PacketSource};
+/// use f1_api::tracker::SessionTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// fn track<S: PacketSource>(mut source: S) -> std::io::Result<SessionTracker> {
+///     let mut tracker = SessionTracker::default();
+///
+///     while let Some(packet) = source.next_packet()? {
+///         tracker.update(&packet);
+///     }
+///
+///     Ok(tracker)
+/// }
+///
+/// let tracker = track(MockSource::new(packets()))?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub trait PacketSource {
+    /// Wait for the next packet.
+    ///
+    /// `Ok(None)` is returned when the source has no more packets, e.g. at the end of a capture.
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error>;
+
+    /// Pass every packet of the source to the handler, until the source has no more packets.
+    fn dispatch<H: PacketHandler>(&mut self, handler: &mut H) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        while let Some(packet) = self.next_packet()? {
+            handler.handle(&packet);
+        }
+
+        Ok(())
+    }
+
+    /// Turn the source into an iterator over its packets.
+    fn into_packets(self) -> Packets<Self>
+    where
+        Self: Sized,
+    {
+        Packets { source: self }
+    }
+}
+
+impl<S: PacketSource + ?Sized> PacketSource for &mut S {
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error> {
+        (**self).next_packet()
+    }
+}
+
+impl<S: PacketSource + ?Sized> PacketSource for Box<S> {
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error> {
+        (**self).next_packet()
+    }
+}
+
+/// Iterator over the packets of a source
+///
+/// The iterator is created by `PacketSource::into_packets`.
+#[derive(Debug)]
+pub struct Packets<S> {
+    source: S,
+}
+
+impl<S: PacketSource> Iterator for Packets<S> {
+    type Item = Result<Packet, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.source.next_packet().transpose()
+    }
+}
+
+/// Source that receives packets from the game
+///
+/// Datagrams that do not contain a complete packet are skipped.
+#[derive(Debug)]
+pub struct UdpSource {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+}
+
+impl UdpSource {
+    /// Bind a socket to the given address and receive packets from it.
+    pub fn bind(socket_address: SocketAddr) -> Result<Self, Error> {
+        Ok(UdpSource::from_socket(UdpSocket::bind(socket_address)?))
+    }
+
+    /// Receive packets from a socket that has already been bound.
+    pub fn from_socket(socket: UdpSocket) -> Self {
+        UdpSource {
+            socket,
+            buffer: vec![0u8; BUFFER_SIZE],
+        }
+    }
+}
+
+impl PacketSource for UdpSource {
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error> {
+        loop {
+            let size = self.socket.recv(&mut self.buffer)?;
+
+            if let Some(packet) = decode_bytes(&self.buffer[..size])? {
+                return Ok(Some(packet));
+            }
+        }
+    }
+}
+
+impl<R: Read> PacketSource for CaptureReader<R> {
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error> {
+        while let Some(datagram) = self.read()? {
+            if let Some(packet) = datagram.decode()? {
+                return Ok(Some(packet));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl PacketSource for Replay {
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error> {
+        for datagram in self {
+            if let Some(packet) = datagram.decode()? {
+                return Ok(Some(packet));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Source that returns a fixed list of packets
+///
+/// The mock source is meant for tests, which can script the packets that an application receives.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct MockSource {
+    packets: VecDeque<Packet>,
+}
+
+impl MockSource {
+    /// Create a source that returns the given packets in order.
+    pub fn new<I: IntoIterator<Item = Packet>>(packets: I) -> Self {
+        MockSource {
+            packets: packets.into_iter().collect(),
+        }
+    }
+
+    /// Append a packet to the end of the source.
+    pub fn push(&mut self, packet: Packet) {
+        self.packets.push_back(packet);
+    }
+}
+
+impl PacketSource for MockSource {
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error> {
+        Ok(self.packets.pop_front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+
+    use crate::capture::CaptureReader;
+    use crate::fixtures;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::Packet;
+    use crate::source::{MockSource, PacketSource, UdpSource};
+
+    fn count<S: PacketSource>(source: S) -> usize {
+        source.into_packets().map(Result::unwrap).count()
+    }
+
+    #[test]
+    fn sources_return_the_same_packets() {
+        let packets = fixtures::packets(ApiSpec::Nineteen, PacketType::Lap).unwrap();
+        let capture = fixtures::capture(ApiSpec::Nineteen, PacketType::Lap).unwrap();
+
+        assert_eq!(packets.len(), count(MockSource::new(packets.clone())));
+        assert_eq!(packets.len(), count(capture));
+
+        let mut boxed: Box<dyn PacketSource> = Box::new(MockSource::new(packets.clone()));
+        assert_eq!(packets[0], boxed.next_packet().unwrap().unwrap());
+    }
+
+    #[test]
+    fn udp_source_skips_incomplete_datagrams() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.connect(receiver.local_addr().unwrap()).unwrap();
+
+        let mut capture =
+            CaptureReader::new(fixtures::bytes(ApiSpec::Nineteen, PacketType::Event)).unwrap();
+        let datagram = capture.read().unwrap().unwrap();
+
+        sender.send(&datagram.payload()[..10]).unwrap();
+        sender.send(datagram.payload()).unwrap();
+
+        let mut source = UdpSource::from_socket(receiver);
+
+        assert!(matches!(
+            source.next_packet().unwrap(),
+            Some(Packet::Event(_))
+        ));
+    }
+}
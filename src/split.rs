@@ -0,0 +1,313 @@
+//! Separate channels for each type of packet
+//!
+//! Applications with several widgets often hand the packets of each type to a different task,
+//! e.g. the lap data to a timing tower and the telemetry to a dashboard. `F1::split` takes a stream
+//! of packets and returns a receiver for each packet type, so that each task consumes only the
+//! packets it needs.
+//!
+//! Each channel is bounded. When a task falls behind and its channel is full, new packets of that
+//! type are dropped instead of stalling the other channels. The capacities can be configured for
+//! each packet type with `Capacities`.
+
+use getset::CopyGetters;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::packet::event::EventPacket;
+use crate::packet::lap::LapPacket;
+use crate::packet::motion::MotionPacket;
+use crate::packet::participants::ParticipantsPacket;
+use crate::packet::session::SessionPacket;
+use crate::packet::setup::CarSetupPacket;
+use crate::packet::status::CarStatusPacket;
+use crate::packet::telemetry::TelemetryPacket;
+use crate::packet::Packet;
+
+/// Capacity of each channel that is used by default
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Capacities of the channels for each packet type
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::split::Capacities;
+///
+/// let capacities = Capacities::default().telemetry(256).motion(256);
+///
+/// assert_eq!(256, capacities.telemetry_capacity());
+/// assert_eq!(64, capacities.laps_capacity());
+/// ```
+#[derive(Debug, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct Capacities {
+    /// Returns the capacity of the channel for event packets.
+    #[getset(get_copy = "pub")]
+    events_capacity: usize,
+
+    /// Returns the capacity of the channel for lap data packets.
+    #[getset(get_copy = "pub")]
+    laps_capacity: usize,
+
+    /// Returns the capacity of the channel for motion packets.
+    #[getset(get_copy = "pub")]
+    motion_capacity: usize,
+
+    /// Returns the capacity of the channel for participants packets.
+    #[getset(get_copy = "pub")]
+    participants_capacity: usize,
+
+    /// Returns the capacity of the channel for session packets.
+    #[getset(get_copy = "pub")]
+    sessions_capacity: usize,
+
+    /// Returns the capacity of the channel for car setup packets.
+    #[getset(get_copy = "pub")]
+    setups_capacity: usize,
+
+    /// Returns the capacity of the channel for car status packets.
+    #[getset(get_copy = "pub")]
+    statuses_capacity: usize,
+
+    /// Returns the capacity of the channel for telemetry packets.
+    #[getset(get_copy = "pub")]
+    telemetry_capacity: usize,
+}
+
+impl Capacities {
+    /// Use the same capacity for all channels.
+    pub fn uniform(capacity: usize) -> Self {
+        Capacities {
+            events_capacity: capacity,
+            laps_capacity: capacity,
+            motion_capacity: capacity,
+            participants_capacity: capacity,
+            sessions_capacity: capacity,
+            setups_capacity: capacity,
+            statuses_capacity: capacity,
+            telemetry_capacity: capacity,
+        }
+    }
+
+    /// Set the capacity of the channel for event packets.
+    pub fn events(mut self, capacity: usize) -> Self {
+        self.events_capacity = capacity;
+        self
+    }
+
+    /// Set the capacity of the channel for lap data packets.
+    pub fn laps(mut self, capacity: usize) -> Self {
+        self.laps_capacity = capacity;
+        self
+    }
+
+    /// Set the capacity of the channel for motion packets.
+    pub fn motion(mut self, capacity: usize) -> Self {
+        self.motion_capacity = capacity;
+        self
+    }
+
+    /// Set the capacity of the channel for participants packets.
+    pub fn participants(mut self, capacity: usize) -> Self {
+        self.participants_capacity = capacity;
+        self
+    }
+
+    /// Set the capacity of the channel for session packets.
+    pub fn sessions(mut self, capacity: usize) -> Self {
+        self.sessions_capacity = capacity;
+        self
+    }
+
+    /// Set the capacity of the channel for car setup packets.
+    pub fn setups(mut self, capacity: usize) -> Self {
+        self.setups_capacity = capacity;
+        self
+    }
+
+    /// Set the capacity of the channel for car status packets.
+    pub fn statuses(mut self, capacity: usize) -> Self {
+        self.statuses_capacity = capacity;
+        self
+    }
+
+    /// Set the capacity of the channel for telemetry packets.
+    pub fn telemetry(mut self, capacity: usize) -> Self {
+        self.telemetry_capacity = capacity;
+        self
+    }
+}
+
+impl Default for Capacities {
+    fn default() -> Self {
+        Capacities::uniform(DEFAULT_CAPACITY)
+    }
+}
+
+/// Receivers for each type of packet
+///
+/// The receivers are independent of each other, and can be moved into different tasks. Dropping a
+/// receiver discards the packets of its type.
+#[derive(Debug)]
+pub struct PacketReceivers {
+    /// Receiver for event packets
+    pub events: Receiver<EventPacket>,
+
+    /// Receiver for lap data packets
+    pub laps: Receiver<LapPacket>,
+
+    /// Receiver for motion packets
+    pub motion: Receiver<MotionPacket>,
+
+    /// Receiver for participants packets
+    pub participants: Receiver<ParticipantsPacket>,
+
+    /// Receiver for session packets
+    pub sessions: Receiver<SessionPacket>,
+
+    /// Receiver for car setup packets
+    pub setups: Receiver<CarSetupPacket>,
+
+    /// Receiver for car status packets
+    pub statuses: Receiver<CarStatusPacket>,
+
+    /// Receiver for telemetry packets
+    pub telemetry: Receiver<TelemetryPacket>,
+}
+
+struct PacketSenders {
+    events: Sender<EventPacket>,
+    laps: Sender<LapPacket>,
+    motion: Sender<MotionPacket>,
+    participants: Sender<ParticipantsPacket>,
+    sessions: Sender<SessionPacket>,
+    setups: Sender<CarSetupPacket>,
+    statuses: Sender<CarStatusPacket>,
+    telemetry: Sender<TelemetryPacket>,
+}
+
+impl PacketSenders {
+    fn send(&self, packet: Packet) {
+        // A full channel drops the packet, and a closed channel discards it.
+        match packet {
+            Packet::Event(packet) => self.events.try_send(packet).ok(),
+            Packet::Lap(packet) => self.laps.try_send(packet).ok(),
+            Packet::Motion(packet) => self.motion.try_send(packet).ok(),
+            Packet::Participants(packet) => self.participants.try_send(packet).ok(),
+            Packet::Session(packet) => self.sessions.try_send(packet).ok(),
+            Packet::Setup(packet) => self.setups.try_send(packet).ok(),
+            Packet::Status(packet) => self.statuses.try_send(packet).ok(),
+            Packet::Telemetry(packet) => self.telemetry.try_send(packet).ok(),
+        };
+    }
+
+    fn is_closed(&self) -> bool {
+        self.events.is_closed()
+            && self.laps.is_closed()
+            && self.motion.is_closed()
+            && self.participants.is_closed()
+            && self.sessions.is_closed()
+            && self.setups.is_closed()
+            && self.statuses.is_closed()
+            && self.telemetry.is_closed()
+    }
+}
+
+/// Spawn a task that distributes the packets of the stream to a channel for each packet type.
+///
+/// The task ends when the stream ends, or when all receivers have been dropped.
+pub(crate) fn split<S>(stream: S, capacities: Capacities) -> PacketReceivers
+where
+    S: Stream<Item = Packet> + Send + Unpin + 'static,
+{
+    let (events, events_receiver) = channel(capacities.events_capacity);
+    let (laps, laps_receiver) = channel(capacities.laps_capacity);
+    let (motion, motion_receiver) = channel(capacities.motion_capacity);
+    let (participants, participants_receiver) = channel(capacities.participants_capacity);
+    let (sessions, sessions_receiver) = channel(capacities.sessions_capacity);
+    let (setups, setups_receiver) = channel(capacities.setups_capacity);
+    let (statuses, statuses_receiver) = channel(capacities.statuses_capacity);
+    let (telemetry, telemetry_receiver) = channel(capacities.telemetry_capacity);
+
+    let senders = PacketSenders {
+        events,
+        laps,
+        motion,
+        participants,
+        sessions,
+        setups,
+        statuses,
+        telemetry,
+    };
+
+    tokio::spawn(async move {
+        let mut stream = stream;
+
+        while let Some(packet) = stream.next().await {
+            senders.send(packet);
+
+            if senders.is_closed() {
+                break;
+            }
+        }
+    });
+
+    PacketReceivers {
+        events: events_receiver,
+        laps: laps_receiver,
+        motion: motion_receiver,
+        participants: participants_receiver,
+        sessions: sessions_receiver,
+        setups: setups_receiver,
+        statuses: statuses_receiver,
+        telemetry: telemetry_receiver,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::Packet;
+    use crate::split::Capacities;
+    use crate::F1;
+
+    #[tokio::test]
+    async fn split_drops_packets_of_full_channels() {
+        let packets = (1..=5)
+            .map(|frame| {
+                lap_packet(
+                    header(PacketType::Lap, 1, Duration::default(), frame),
+                    Vec::new(),
+                )
+            })
+            .collect::<Vec<Packet>>();
+        let stream = tokio_stream::iter(packets);
+
+        let mut receivers = F1::split(stream, Capacities::default().laps(2));
+
+        assert_eq!(
+            1,
+            receivers
+                .laps
+                .recv()
+                .await
+                .unwrap()
+                .header()
+                .frame_identifier()
+        );
+        assert_eq!(
+            2,
+            receivers
+                .laps
+                .recv()
+                .await
+                .unwrap()
+                .header()
+                .frame_identifier()
+        );
+        assert!(receivers.laps.recv().await.is_none());
+        assert!(receivers.events.recv().await.is_none());
+    }
+}
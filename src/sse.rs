@@ -0,0 +1,245 @@
+//! Server-Sent Events endpoint for live data
+//!
+//! Web overlays for streams usually show a timing tower and the inputs of the player. Many overlay
+//! stacks and reverse proxies support Server-Sent Events out of the box, while WebSockets require
+//! extra configuration. The `SseServer` consumes a stream of packets, and sends a JSON snapshot of
+//! the leaderboard and the player's telemetry to every connected client at a fixed rate.
+//!
+//! Every request is answered with the event stream, regardless of its path. Clients connect with
+//! `new EventSource("http://<host>:<port>/")` in the browser and receive one `message` event per
+//! snapshot.
+
+use std::io::Error;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::http::read_request;
+use crate::packet::Packet;
+use crate::tracker::SessionTracker;
+
+/// Interval between two snapshots by default
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Headers of the response that opens the event stream
+const RESPONSE_HEADERS: &[u8] = b"HTTP/1.1 200 OK\r\n\
+Content-Type: text/event-stream\r\n\
+Cache-Control: no-cache\r\n\
+Connection: keep-alive\r\n\
+Access-Control-Allow-Origin: *\r\n\r\n";
+
+/// Server that streams snapshots of a session to web clients as Server-Sent Events
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+/// use std::time::Duration;
+///
+/// use f1_api::sse::SseServer;
+/// use f1_api::F1;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let game = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+/// let web = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 8080);
+///
+/// SseServer::bind(web)
+///     .await
+///     .unwrap()
+///     .interval(Duration::from_millis(100))
+///     .serve(F1::stream(game).unwrap())
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SseServer {
+    listener: TcpListener,
+    interval: Duration,
+}
+
+impl SseServer {
+    /// Bind the server to the given address.
+    pub async fn bind(address: SocketAddr) -> Result<Self, Error> {
+        Ok(SseServer {
+            listener: TcpListener::bind(address).await?,
+            interval: DEFAULT_INTERVAL,
+        })
+    }
+
+    /// Set the interval at which snapshots are sent to the clients.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        self.listener.local_addr()
+    }
+
+    /// Consume the packets of the stream, and send snapshots to the clients until it ends.
+    pub async fn serve<S>(self, packets: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Packet> + Unpin,
+    {
+        let tracker = Arc::new(Mutex::new(SessionTracker::default()));
+        let (sender, receiver) = watch::channel(String::new());
+
+        let ticker = tokio::spawn(tick(tracker.clone(), sender, self.interval));
+        let acceptor = tokio::spawn(accept(self.listener, receiver));
+
+        let mut packets = packets;
+        while let Some(packet) = packets.next().await {
+            tracker.lock().unwrap().update(&packet);
+        }
+
+        ticker.abort();
+        acceptor.abort();
+
+        Ok(())
+    }
+}
+
+/// Returns the snapshot of the leaderboard and the player's telemetry as JSON.
+///
+/// Lap times and gaps are written as seconds. The telemetry of the player is `null` until the first
+/// telemetry packet has been received.
+pub fn snapshot(tracker: &SessionTracker) -> String {
+    let leaderboard: Vec<Value> = tracker
+        .classification()
+        .iter()
+        .map(|entry| {
+            json!({
+                "position": entry.position(),
+                "vehicle_index": entry.vehicle_index(),
+                "name": entry.name(),
+                "last_lap_time": entry.last_lap_time().as_secs_f64(),
+                "best_lap_time": entry.best_lap_time().as_secs_f64(),
+                "gap": entry.gap().map(|gap| gap.as_secs_f64()),
+                "tyre_compound": entry.tyre_compound().map(|tyre| format!("{:?}", tyre)),
+                "pit_stops": entry.pit_stops(),
+            })
+        })
+        .collect();
+
+    let player = tracker.telemetry().as_ref().and_then(|packet| {
+        packet.player().map(|telemetry| {
+            json!({
+                "vehicle_index": packet.header().player_car_index(),
+                "speed": telemetry.speed(),
+                "throttle": telemetry.throttle(),
+                "brake": telemetry.brake(),
+                "steering": telemetry.steering(),
+                "gear": telemetry.gear() as i8,
+                "engine_rpm": telemetry.engine_rpm(),
+                "drs": telemetry.drs(),
+            })
+        })
+    });
+
+    json!({
+        "session_uid": tracker.session_uid().map(|uid| uid.to_string()),
+        "leaderboard": leaderboard,
+        "player": player,
+    })
+    .to_string()
+}
+
+/// Publish a snapshot of the session at every interval.
+async fn tick(
+    tracker: Arc<Mutex<SessionTracker>>,
+    sender: watch::Sender<String>,
+    interval: Duration,
+) {
+    let mut interval = tokio::time::interval(interval);
+
+    loop {
+        interval.tick().await;
+
+        let snapshot = snapshot(&tracker.lock().unwrap());
+        sender.send_replace(snapshot);
+    }
+}
+
+/// Accept connections and spawn a task for each client.
+async fn accept(listener: TcpListener, receiver: watch::Receiver<String>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(respond(stream, receiver.clone()));
+    }
+}
+
+/// Answer any request of a client with the event stream, until the client disconnects.
+async fn respond(
+    mut stream: TcpStream,
+    mut receiver: watch::Receiver<String>,
+) -> Result<(), Error> {
+    read_request(&mut stream).await?;
+    stream.write_all(RESPONSE_HEADERS).await?;
+
+    while receiver.changed().await.is_ok() {
+        let event = format!("data: {}\n\n", *receiver.borrow_and_update());
+        stream.write_all(event.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, SocketAddr};
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use crate::sse::{snapshot, SseServer};
+    use crate::tracker::SessionTracker;
+
+    #[test]
+    fn snapshot_of_empty_session() {
+        let json: serde_json::Value =
+            serde_json::from_str(&snapshot(&SessionTracker::default())).unwrap();
+
+        assert_eq!(serde_json::json!([]), json["leaderboard"]);
+        assert!(json["player"].is_null());
+    }
+
+    #[tokio::test]
+    async fn serve_sends_snapshots_as_events() {
+        let server = SseServer::bind(SocketAddr::new(IpAddr::from([127, 0, 0, 1]), 0))
+            .await
+            .unwrap()
+            .interval(Duration::from_millis(10));
+        let address = server.local_addr().unwrap();
+
+        tokio::spawn(server.serve(tokio_stream::pending()));
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = Vec::new();
+        let mut buffer = [0; 1024];
+        while !String::from_utf8_lossy(&response).contains("data: ") {
+            let read = tokio::time::timeout(Duration::from_secs(5), client.read(&mut buffer))
+                .await
+                .unwrap()
+                .unwrap();
+            response.extend_from_slice(&buffer[..read]);
+        }
+
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("Content-Type: text/event-stream"));
+    }
+}
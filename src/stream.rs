@@ -0,0 +1,232 @@
+//! Adapters for streams of packets
+//!
+//! The stream returned by `F1::stream` yields packets in the order in which they arrive at the
+//! socket. The adapters in this module can be chained onto the stream to observe or transform the
+//! packets before they reach the application. They are made available through the
+//! `PacketStreamExt` trait, which is implemented for every stream of packets.
+
+#[cfg(feature = "net")]
+use std::time::Duration;
+
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+#[cfg(feature = "net")]
+use crate::alerts::{AlertMonitor, Alerts};
+#[cfg(feature = "net")]
+use crate::analysis::kinematics::DeriveKinematics;
+#[cfg(feature = "net")]
+use crate::analysis::spotter::Spotting;
+#[cfg(feature = "net")]
+use crate::packet::Packet;
+#[cfg(feature = "net")]
+use crate::privacy::RedactRestricted;
+#[cfg(feature = "net")]
+use crate::stream::active::ActiveCars;
+#[cfg(feature = "net")]
+use crate::stream::buttons::Buttons;
+#[cfg(feature = "net")]
+use crate::stream::changes::Changes;
+#[cfg(feature = "net")]
+use crate::stream::dedup::{Dedup, Deduplicator};
+#[cfg(feature = "net")]
+use crate::stream::downsample::{Downsample, Downsampler};
+#[cfg(feature = "net")]
+use crate::stream::loss::{DetectLoss, Gap};
+#[cfg(feature = "net")]
+use crate::stream::progress::Progress;
+#[cfg(feature = "net")]
+use crate::stream::reorder::Reorder;
+#[cfg(feature = "net")]
+use crate::stream::sessions::Sessions;
+#[cfg(feature = "net")]
+use crate::stream::stale::{DropStaleSessions, StaleSessionFilter};
+
+pub mod active;
+pub mod buttons;
+pub mod changes;
+pub mod dedup;
+pub mod downsample;
+pub mod frequency;
+pub mod loss;
+pub mod progress;
+pub mod reorder;
+#[cfg(feature = "net")]
+pub mod sessions;
+pub mod stale;
+
+/// Extension trait that adds adapters to streams of packets
+#[cfg(feature = "net")]
+pub trait PacketStreamExt: Stream<Item = Packet> + Sized {
+    /// Detect packets that have been lost on their way from the game.
+    ///
+    /// The adapter passes all packets through unchanged, and calls the given function for every
+    /// gap that it detects in the frame identifiers of a packet type. Statistics about the loss are
+    /// available through `DetectLoss::detector`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::net::{IpAddr, SocketAddr};
+    ///
+    /// use f1_api::F1;
+    /// use f1_api::stream::PacketStreamExt;
+    /// use tokio_stream::StreamExt;
+    ///
+    /// async fn example() {
+    ///     let socket = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+    ///
+    ///     let mut stream = F1::stream(socket)
+    ///         .unwrap()
+    ///         .detect_loss(|gap| println!("Lost {} packets", gap.missing()));
+    ///
+    ///     while let Some(_packet) = stream.next().await {
+    ///         println!("Loss rate: {:.2}%", stream.detector().loss_rate() * 100.0);
+    ///     }
+    /// }
+    /// ```
+    fn detect_loss<F>(self, on_gap: F) -> DetectLoss<Self, F>
+    where
+        F: FnMut(&Gap),
+    {
+        DetectLoss::new(self, on_gap)
+    }
+
+    /// Put packets that arrived out of order back into order.
+    ///
+    /// The adapter holds back up to `capacity` packets, and releases them ordered by their frame
+    /// identifier and session time. A larger capacity can correct larger displacements, but delays
+    /// every packet by the time it takes to receive `capacity` more packets. No packet is held for
+    /// longer than `max_delay`, so that the last packets are released when the game pauses.
+    fn reorder(self, capacity: usize, max_delay: Duration) -> Reorder<Self> {
+        Reorder::new(self, capacity, max_delay)
+    }
+
+    /// Drop packets that have already been received.
+    ///
+    /// Duplicates occur when the game broadcasts its packets, or when a relay sends them back onto
+    /// the same network. See `Deduplicator` for details.
+    fn dedup(self, deduplicator: Deduplicator) -> Dedup<Self> {
+        Dedup::new(self, deduplicator)
+    }
+
+    /// Drop late packets of the previous session after a new session has started.
+    ///
+    /// See `StaleSessionFilter` for details.
+    fn drop_stale_sessions(self, filter: StaleSessionFilter) -> DropStaleSessions<Self> {
+        DropStaleSessions::new(self, filter)
+    }
+
+    /// Limit the rate of selected packet types.
+    ///
+    /// Packets that exceed the rate configured in the downsampler are dropped. See `Downsampler`
+    /// for details.
+    fn downsample(self, downsampler: Downsampler) -> Downsample<Self> {
+        Downsample::new(self, downsampler)
+    }
+
+    /// Derive the kinematics of all cars from motion packets.
+    ///
+    /// The stream yields a frame for every motion packet, and drops all other packets. See
+    /// `KinematicsEstimator` for details.
+    fn kinematics(self) -> DeriveKinematics<Self> {
+        DeriveKinematics::new(self)
+    }
+
+    /// Redact the telemetry, status, and setup data of cars with restricted telemetry.
+    ///
+    /// The adapter learns the privacy settings of the participants, and replaces the data of
+    /// restricted cars with default values. Each packet is yielded with a mask that marks the
+    /// redacted cars. See `Privacy::redact` for details.
+    fn redact_restricted(self) -> RedactRestricted<Self> {
+        RedactRestricted::new(self)
+    }
+
+    /// Limit the data of every car to the active cars of the session.
+    ///
+    /// The adapter learns the number of active cars from the participants packets, and removes the
+    /// padding from the other packets. See `ActiveCarLimiter` for details.
+    fn active_cars(self) -> ActiveCars<Self> {
+        ActiveCars::new(self)
+    }
+
+    /// Report the time left and the lap count of the session at a fixed interval.
+    ///
+    /// The stream yields the progress through the session whenever the session time has passed
+    /// the interval, and drops all packets. See `ProgressTracker` for details.
+    fn progress(self, interval: Duration) -> Progress<Self> {
+        Progress::new(self, interval)
+    }
+
+    /// Split the stream into a stream for each session.
+    ///
+    /// The adapter yields a `SessionStream` whenever the packets of a new session arrive. A
+    /// session stream ends with the end of its session. The adapter must be polled to distribute
+    /// the packets to the session streams. See `Sessions` for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::net::{IpAddr, SocketAddr};
+    ///
+    /// use f1_api::F1;
+    /// use f1_api::stream::PacketStreamExt;
+    /// use tokio_stream::StreamExt;
+    ///
+    /// async fn example() {
+    ///     let socket = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+    ///
+    ///     let mut sessions = Box::pin(F1::stream(socket).unwrap()).sessions();
+    ///
+    ///     while let Some(mut session) = sessions.next().await {
+    ///         tokio::spawn(async move {
+    ///             let mut packets = 0;
+    ///
+    ///             while session.next().await.is_some() {
+    ///                 packets += 1;
+    ///             }
+    ///
+    ///             println!("Session {} had {} packets", session.session_uid(), packets);
+    ///         });
+    ///     }
+    /// }
+    /// ```
+    fn sessions(self) -> Sessions<Self> {
+        Sessions::new(self)
+    }
+
+    /// Compare session, status, and participants packets with their predecessors.
+    ///
+    /// The stream yields every field that has changed, and drops all packets. See
+    /// `ChangeDetector` for details.
+    fn changes(self) -> Changes<Self> {
+        Changes::new(self)
+    }
+
+    /// Detect presses and releases of buttons in telemetry packets.
+    ///
+    /// The stream yields an event whenever a button has been pressed or released for at least the
+    /// debounce time, and drops all packets. See `ButtonDetector` for details.
+    fn buttons(self, debounce: Duration) -> Buttons<Self> {
+        Buttons::new(self, debounce)
+    }
+
+    /// Detect cars alongside the player's car.
+    ///
+    /// The stream yields an event whenever a car moves alongside on the left or the right, or the
+    /// player's car is clear again, and drops all packets. See `Spotter` for details.
+    fn spotter(self) -> Spotting<Self> {
+        Spotting::new(self)
+    }
+
+    /// Check the values of all cars against the thresholds of a monitor.
+    ///
+    /// The stream yields an alert whenever a value crosses a threshold or returns from it, and
+    /// drops all packets. See `AlertMonitor` for details.
+    fn alerts(self, monitor: AlertMonitor) -> Alerts<Self> {
+        Alerts::new(self, monitor)
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> PacketStreamExt for S where S: Stream<Item = Packet> {}
@@ -0,0 +1,142 @@
+//! Padding in the slots without a car
+//!
+//! The games publish the data of every car in arrays with a fixed number of slots, and fill the
+//! slots without a car with padding. The participants and lap packets mark which of their slots
+//! belong to active cars, while the motion, setup, status, and telemetry packets consider all of
+//! their slots active. The `ActiveCarLimiter` remembers the active cars from the latest
+//! participants packet of a session, and limits the grids of the other packets to them, so that
+//! their active cars never include padding.
+
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::Packet;
+
+/// Limiter of the grids of packets to the active cars
+///
+/// Packets of a session are passed through unchanged until its first participants packet has
+/// been received.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::stream::active::ActiveCarLimiter;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut limiter = ActiveCarLimiter::default();
+///
+/// for packet in packets() {
+///     if let Packet::Telemetry(packet) = limiter.limit(packet) {
+///         println!("Telemetry of {} cars", packet.telemetry().active_count());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Default)]
+pub struct ActiveCarLimiter {
+    session_uid: Option<u64>,
+    active: Option<Vec<bool>>,
+}
+
+impl ActiveCarLimiter {
+    /// Returns the mask of active cars, if a participants packet has been received.
+    pub fn active(&self) -> Option<&[bool]> {
+        self.active.as_deref()
+    }
+
+    /// Limit the data of every car in the packet to the active cars of its session.
+    pub fn limit(&mut self, packet: Packet) -> Packet {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            self.session_uid = Some(session_uid);
+            self.active = None;
+        }
+
+        if let Packet::Participants(participants) = &packet {
+            self.active = Some(participants.participants().active().to_vec());
+        }
+
+        match &self.active {
+            Some(active) => packet.limit_cars(active),
+            None => packet,
+        }
+    }
+}
+
+/// Stream adapter that limits the data of every car to the active cars
+///
+/// The adapter is created by `PacketStreamExt::active_cars`.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct ActiveCars<S> {
+    stream: S,
+    limiter: ActiveCarLimiter,
+}
+
+#[cfg(feature = "net")]
+impl<S> ActiveCars<S> {
+    pub(crate) fn new(stream: S) -> Self {
+        ActiveCars {
+            stream,
+            limiter: ActiveCarLimiter::default(),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for ActiveCars<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = Packet;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        Pin::new(&mut this.stream)
+            .poll_next(cx)
+            .map(|packet| packet.map(|packet| this.limiter.limit(packet)))
+    }
+}
+
+#[cfg(all(test, feature = "decode"))]
+mod tests {
+    use crate::fixtures;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::Packet;
+    use crate::stream::active::ActiveCarLimiter;
+
+    #[test]
+    fn limit_packets_to_participants() {
+        let mut limiter = ActiveCarLimiter::default();
+
+        let telemetry = fixtures::packets(ApiSpec::Nineteen, PacketType::Telemetry).unwrap();
+        let participants = fixtures::packets(ApiSpec::Nineteen, PacketType::Participants).unwrap();
+
+        assert_eq!(telemetry[0], limiter.limit(telemetry[0].clone()));
+
+        // The fixtures show a full grid, so two cars are removed from the middle of the grid.
+        let mut active = vec![true; 20];
+        active[4] = false;
+        active[12] = false;
+
+        limiter.limit(participants[0].clone().limit_cars(&active));
+        assert_eq!(Some(active.as_slice()), limiter.active());
+
+        match limiter.limit(telemetry[1].clone()) {
+            Packet::Telemetry(packet) => {
+                assert_eq!(20, packet.telemetry().len());
+                assert_eq!(18, packet.telemetry().active_count());
+                assert!(packet.car(4).is_none());
+                assert!(packet.car(19).is_some());
+            }
+            _ => unreachable!(),
+        }
+    }
+}
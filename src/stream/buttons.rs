@@ -0,0 +1,267 @@
+//! Presses and releases of buttons
+//!
+//! The telemetry packet contains a bit field with the buttons that the player is currently holding.
+//! Applications that trigger actions from the buttons of a wheel, for example to drop a marker in
+//! a recording or to switch the page of an overlay, are interested in the moment a button is
+//! pressed, and not in every packet in which it is held. The `ButtonDetector` compares the bit
+//! field with the previous packet, and returns a typed event for every button that has been
+//! pressed or released.
+//!
+//! Buttons on wheels can bounce, and the game might report a button for a single packet when it is
+//! pressed only briefly. With a debounce time, a button has to stay in its new state for at least
+//! this long before the change is reported.
+
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use derive_new::new;
+use getset::CopyGetters;
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::telemetry::Button;
+use crate::packet::Packet;
+
+/// Number of buttons in the bit field
+const BUTTONS: usize = 32;
+
+/// Action of a button
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub enum ButtonAction {
+    /// The button has been pressed.
+    Pressed,
+
+    /// The button has been released.
+    Released,
+}
+
+/// Press or release of a single button
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, Eq, Hash)]
+pub struct ButtonEvent {
+    /// Returns the button that has been pressed or released.
+    #[getset(get_copy = "pub")]
+    button: Button,
+
+    /// Returns whether the button has been pressed or released.
+    #[getset(get_copy = "pub")]
+    action: ButtonAction,
+
+    /// Returns the session time of the packet in which the change has been reported.
+    #[getset(get_copy = "pub")]
+    session_time: Duration,
+}
+
+/// Detector for presses and releases of buttons in telemetry packets
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use f1_api::packet::telemetry::Button;
+/// use f1_api::stream::buttons::{ButtonAction, ButtonDetector};
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut detector = ButtonDetector::new(Duration::from_millis(50));
+///
+/// for packet in packets() {
+///     for event in detector.update(&packet) {
+///         if event.button() == Button::UDP_ACTION_1 && event.action() == ButtonAction::Pressed {
+///             println!("Marker at {:?}", event.session_time());
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ButtonDetector {
+    debounce: Duration,
+    session_uid: Option<u64>,
+    held: Button,
+    changed_since: [Option<Duration>; BUTTONS],
+}
+
+impl ButtonDetector {
+    /// Create a detector that reports a change once a button has been in its new state for the
+    /// given time.
+    pub fn new(debounce: Duration) -> Self {
+        ButtonDetector {
+            debounce,
+            ..ButtonDetector::default()
+        }
+    }
+
+    /// Returns the buttons that are held, after debouncing.
+    pub fn held(&self) -> Button {
+        self.held
+    }
+
+    /// Compare the buttons in a telemetry packet with the buttons that are held, and return the
+    /// buttons that have been pressed or released.
+    ///
+    /// Packets of other types do not change any buttons, and return no events.
+    pub fn update(&mut self, packet: &Packet) -> Vec<ButtonEvent> {
+        let packet = match packet {
+            Packet::Telemetry(packet) => packet,
+            _ => return Vec::new(),
+        };
+
+        let header = packet.header();
+        let session_uid = header.session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            *self = ButtonDetector::new(self.debounce);
+            self.session_uid = Some(session_uid);
+        }
+
+        let time = *header.session_time();
+        let pressed = packet.button_status();
+        let mut events = Vec::new();
+
+        for (bit, changed_since) in self.changed_since.iter_mut().enumerate() {
+            let button = Button::from_bits_truncate(1 << bit);
+
+            if pressed.contains(button) == self.held.contains(button) {
+                *changed_since = None;
+                continue;
+            }
+
+            let since = *changed_since.get_or_insert(time);
+            if time.saturating_sub(since) < self.debounce {
+                continue;
+            }
+
+            *changed_since = None;
+            self.held.toggle(button);
+
+            let action = if self.held.contains(button) {
+                ButtonAction::Pressed
+            } else {
+                ButtonAction::Released
+            };
+
+            events.push(ButtonEvent::new(button, action, time));
+        }
+
+        events
+    }
+}
+
+/// Stream adapter that yields the presses and releases of buttons
+///
+/// The adapter is created by `PacketStreamExt::buttons`. The packets themselves are dropped.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct Buttons<S> {
+    stream: S,
+    detector: ButtonDetector,
+    pending: std::vec::IntoIter<ButtonEvent>,
+}
+
+#[cfg(feature = "net")]
+impl<S> Buttons<S> {
+    pub(crate) fn new(stream: S, debounce: Duration) -> Self {
+        Buttons {
+            stream,
+            detector: ButtonDetector::new(debounce),
+            pending: Vec::new().into_iter(),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for Buttons<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = ButtonEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(event) = this.pending.next() {
+                return Poll::Ready(Some(event));
+            }
+
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    this.pending = this.detector.update(&packet).into_iter();
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::header;
+    use crate::packet::header::PacketType;
+    use crate::packet::telemetry::{Button, TelemetryPacket};
+    use crate::packet::Packet;
+    use crate::stream::buttons::{ButtonAction, ButtonDetector, ButtonEvent};
+
+    fn telemetry(millis: u64, buttons: Button) -> Packet {
+        Packet::Telemetry(TelemetryPacket::new(
+            header(PacketType::Telemetry, 1, Duration::from_millis(millis), 0),
+            Vec::new().into(),
+            buttons,
+        ))
+    }
+
+    #[test]
+    fn update_debounces_buttons() {
+        let mut detector = ButtonDetector::new(Duration::from_millis(50));
+
+        // A bounce that is shorter than the debounce time is ignored.
+        assert!(detector
+            .update(&telemetry(0, Button::CROSS_OR_A))
+            .is_empty());
+        assert!(detector.update(&telemetry(20, Button::empty())).is_empty());
+
+        assert!(detector
+            .update(&telemetry(40, Button::CROSS_OR_A))
+            .is_empty());
+        assert_eq!(
+            vec![ButtonEvent::new(
+                Button::CROSS_OR_A,
+                ButtonAction::Pressed,
+                Duration::from_millis(90)
+            )],
+            detector.update(&telemetry(90, Button::CROSS_OR_A))
+        );
+        assert!(detector
+            .update(&telemetry(100, Button::CROSS_OR_A))
+            .is_empty());
+
+        assert!(detector.update(&telemetry(120, Button::empty())).is_empty());
+        assert_eq!(
+            vec![ButtonEvent::new(
+                Button::CROSS_OR_A,
+                ButtonAction::Released,
+                Duration::from_millis(170)
+            )],
+            detector.update(&telemetry(170, Button::empty()))
+        );
+    }
+
+    #[test]
+    fn update_without_debounce_reports_every_change() {
+        let mut detector = ButtonDetector::default();
+
+        let events = detector.update(&telemetry(0, Button::UDP_ACTION_1 | Button::DPAD_UP));
+
+        assert_eq!(2, events.len());
+        assert!(events
+            .iter()
+            .all(|event| event.action() == ButtonAction::Pressed));
+        assert_eq!(Button::UDP_ACTION_1 | Button::DPAD_UP, detector.held());
+    }
+}
@@ -0,0 +1,543 @@
+//! Changes in slowly changing packets
+//!
+//! The session, status, and participants packets are sent several times per second, but most of
+//! their fields change rarely. User interfaces that render these fields have to compare every
+//! packet with the previous one to decide what to update. The `ChangeDetector` does this once, and
+//! returns the fields that have changed as typed changes.
+//!
+//! Fields that change continuously, like the time left in the session or the fuel remaining in a
+//! car, are not reported, since they would change with every packet. They are still available in
+//! the packets themselves. The first packet of a type in a session reports all of its fields, so
+//! that a user interface can render its initial state from the changes alone.
+
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::participants::{
+    Controller, Driver, Nationality, Participant, ParticipantsPacket, Team, TelemetryPrivacy,
+};
+use crate::packet::session::{
+    Formula, MarshalZone, SafetyCar, Session, SessionPacket, Track, Weather, WeatherForecastSample,
+};
+use crate::packet::status::{
+    CarStatus, CarStatusPacket, DrsSetting, ErsDeployMode, FuelMix, PhysicalTyreCompound,
+    TractionControl, VisualTyreCompound,
+};
+use crate::packet::Packet;
+use crate::types::{CornerProperty, Flag, Grid, VehicleIndex};
+
+/// Declare an enum with a variant for each field that is compared, and a function that returns
+/// the fields that differ between two values.
+macro_rules! changes {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident for $ty:ty {
+            $($(#[$variant_meta:meta])* $variant:ident($field:ty) = $get:expr,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, PartialEq, Clone)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant($field),)*
+        }
+
+        impl $name {
+            fn diff(old: Option<&$ty>, new: &$ty) -> Vec<$name> {
+                let mut changes = Vec::new();
+
+                $(
+                    let get: fn(&$ty) -> $field = $get;
+
+                    match old {
+                        Some(old) if get(old) == get(new) => {}
+                        _ => changes.push($name::$variant(get(new))),
+                    }
+                )*
+
+                changes
+            }
+
+            /// Returns the name of the field that has changed.
+            pub fn field(&self) -> &'static str {
+                match self {
+                    $($name::$variant(_) => stringify!($variant),)*
+                }
+            }
+
+            /// Returns the new value of the field as JSON.
+            #[cfg(feature = "json")]
+            fn json_value(&self) -> serde_json::Value {
+                match self {
+                    $($name::$variant(value) => value.to_json(),)*
+                }
+            }
+        }
+    };
+}
+
+changes! {
+    /// Field of the session that has changed, with its new value
+    pub enum SessionChange for SessionPacket {
+        /// The weather has changed.
+        Weather(Weather) = |session| session.weather(),
+        /// The track temperature in degrees Celsius has changed.
+        TrackTemperature(i8) = |session| session.track_temperature(),
+        /// The air temperature in degrees Celsius has changed.
+        AirTemperature(i8) = |session| session.air_temperature(),
+        /// The number of laps in the session has changed.
+        TotalLaps(u8) = |session| session.total_laps(),
+        /// The type of the session has changed.
+        SessionType(Session) = |session| session.session_type(),
+        /// The track has changed.
+        Track(Track) = |session| session.track(),
+        /// The formula of the cars has changed.
+        Formula(Formula) = |session| session.formula(),
+        /// The duration of the session has changed.
+        Duration(Duration) = |session| *session.duration(),
+        /// The pit speed limit in kilometers per hour has changed.
+        PitSpeedLimit(u8) = |session| session.pit_speed_limit(),
+        /// The game has been paused or resumed.
+        GamePaused(bool) = |session| session.game_paused(),
+        /// The player has started or stopped spectating.
+        IsSpectating(bool) = |session| session.is_spectating(),
+        /// The car that is being spectated has changed.
+        SpectatorCarIndex(VehicleIndex) = |session| session.spectator_car_index(),
+        /// The flags in the marshal zones have changed.
+        MarshalZones(Vec<MarshalZone>) = |session| session.marshal_zones().clone(),
+        /// The safety car has been deployed or has come in.
+        SafetyCar(SafetyCar) = |session| session.safety_car(),
+        /// The weather forecast has changed.
+        WeatherForecast(Option<Vec<WeatherForecastSample>>) =
+            |session| session.weather_forecast().clone(),
+    }
+}
+
+changes! {
+    /// Field of the status of a car that has changed, with its new value
+    pub enum StatusChange for CarStatus {
+        /// The traction control setting has changed.
+        TractionControl(TractionControl) = |status| status.traction_control(),
+        /// The anti-lock brakes have been enabled or disabled.
+        Abs(bool) = |status| status.abs(),
+        /// The fuel mix has changed.
+        FuelMix(FuelMix) = |status| status.fuel_mix(),
+        /// The brake bias in percent has changed.
+        BrakeBias(u8) = |status| status.brake_bias(),
+        /// The pit limiter has been engaged or released.
+        PitLimiter(bool) = |status| status.pit_limiter(),
+        /// The DRS setting has changed.
+        Drs(DrsSetting) = |status| status.drs(),
+        /// The wear of the tyres in percent has changed.
+        TyreWear(CornerProperty<u8>) = |status| *status.tyre_wear(),
+        /// The tyres have been changed.
+        PhysicalTyreCompound(PhysicalTyreCompound) = |status| status.physical_tyre_compound(),
+        /// The tyres have been changed.
+        VisualTyreCompound(VisualTyreCompound) = |status| status.visual_tyre_compound(),
+        /// The damage of the tyres in percent has changed.
+        TyreDamage(CornerProperty<u8>) = |status| *status.tyre_damage(),
+        /// The damage of the front left wing in percent has changed.
+        FrontLeftWingDamage(u8) = |status| status.front_left_wing_damage(),
+        /// The damage of the front right wing in percent has changed.
+        FrontRightWingDamage(u8) = |status| status.front_right_wing_damage(),
+        /// The damage of the rear wing in percent has changed.
+        RearWingDamage(u8) = |status| status.rear_wing_damage(),
+        /// The damage of the engine in percent has changed.
+        EngineDamage(u8) = |status| status.engine_damage(),
+        /// The damage of the gear box in percent has changed.
+        GearBoxDamage(u8) = |status| status.gear_box_damage(),
+        /// The flag that is shown to the car has changed.
+        VehicleFlags(Flag) = |status| status.vehicle_flags(),
+        /// The ERS deploy mode has changed.
+        ErsDeployMode(ErsDeployMode) = |status| status.ers_deploy_mode(),
+    }
+}
+
+changes! {
+    /// Field of a participant that has changed, with its new value
+    pub enum ParticipantChange for Participant {
+        /// The car is now controlled by a human or the AI.
+        Controller(Controller) = |participant| participant.controller(),
+        /// The driver has changed.
+        Driver(Driver) = |participant| participant.driver().clone(),
+        /// The team has changed.
+        Team(Team) = |participant| participant.team(),
+        /// The race number has changed.
+        RaceNumber(u8) = |participant| participant.race_number(),
+        /// The nationality has changed.
+        Nationality(Nationality) = |participant| participant.nationality(),
+        /// The name has changed.
+        Name(String) = |participant| participant.name().clone(),
+        /// The telemetry privacy setting has changed.
+        TelemetryPrivacy(Option<TelemetryPrivacy>) = |participant| participant.telemetry_privacy(),
+    }
+}
+
+/// Change in a session, a car, or a participant
+#[derive(Debug, PartialEq, Clone)]
+pub enum Change {
+    /// A field of the session has changed.
+    Session(SessionChange),
+
+    /// A field of the status of the car with the given vehicle index has changed.
+    Status(VehicleIndex, StatusChange),
+
+    /// A field of the participant with the given vehicle index has changed.
+    Participant(VehicleIndex, ParticipantChange),
+}
+
+#[cfg(feature = "json")]
+impl Change {
+    /// Returns the change as a JSON object with its kind, the field that has changed, and the new
+    /// value of the field.
+    pub fn to_json(&self) -> serde_json::Value {
+        let (kind, field, value) = match self {
+            Change::Session(change) => ("session", change.field(), change.json_value()),
+            Change::Status(_, change) => ("status", change.field(), change.json_value()),
+            Change::Participant(_, change) => ("participant", change.field(), change.json_value()),
+        };
+
+        serde_json::json!({
+            "kind": kind,
+            "field": field,
+            "value": value,
+        })
+    }
+}
+
+/// Detector for changes in the session, status, and participants packets
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::stream::changes::{Change, ChangeDetector, SessionChange};
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut detector = ChangeDetector::default();
+///
+/// for packet in packets() {
+///     for change in detector.update(&packet) {
+///         if let Change::Session(SessionChange::SafetyCar(safety_car)) = change {
+///             println!("Safety car: {:?}", safety_car);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ChangeDetector {
+    session_uid: Option<u64>,
+    session: Option<SessionPacket>,
+    statuses: Option<CarStatusPacket>,
+    participants: Option<ParticipantsPacket>,
+}
+
+impl ChangeDetector {
+    /// Compare the packet with the previous packet of its type, and return the fields that have
+    /// changed.
+    ///
+    /// Packets of other types do not change anything, and return no changes.
+    pub fn update(&mut self, packet: &Packet) -> Vec<Change> {
+        let session_uid = packet.header().session_uid();
+
+        if self.session_uid != Some(session_uid) {
+            *self = ChangeDetector::default();
+            self.session_uid = Some(session_uid);
+        }
+
+        match packet {
+            Packet::Session(packet) => {
+                let changes = SessionChange::diff(self.session.as_ref(), packet)
+                    .into_iter()
+                    .map(Change::Session)
+                    .collect();
+
+                self.session = Some(packet.clone());
+                changes
+            }
+            Packet::Status(packet) => {
+                let previous = self.statuses.as_ref();
+                let changes = diff_cars(
+                    previous.map(|previous| previous.statuses()),
+                    packet.statuses(),
+                    StatusChange::diff,
+                    Change::Status,
+                );
+
+                self.statuses = Some(packet.clone());
+                changes
+            }
+            Packet::Participants(packet) => {
+                let previous = self.participants.as_ref();
+                let changes = diff_cars(
+                    previous.map(|previous| previous.participants()),
+                    packet.participants(),
+                    ParticipantChange::diff,
+                    Change::Participant,
+                );
+
+                self.participants = Some(packet.clone());
+                changes
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Compare the data of each active car with the data of the same car in the previous packet.
+fn diff_cars<T, C>(
+    previous: Option<&Grid<T>>,
+    current: &Grid<T>,
+    diff: fn(Option<&T>, &T) -> Vec<C>,
+    change: fn(VehicleIndex, C) -> Change,
+) -> Vec<Change> {
+    current
+        .iter_active()
+        .flat_map(|(index, car)| {
+            let old = previous.and_then(|previous| previous.car(index));
+
+            diff(old, car)
+                .into_iter()
+                .map(move |field| change(index, field))
+        })
+        .collect()
+}
+
+/// Stream adapter that yields the changes in slowly changing packets
+///
+/// The adapter is created by `PacketStreamExt::changes`. The packets themselves are dropped.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct Changes<S> {
+    stream: S,
+    detector: ChangeDetector,
+    pending: std::vec::IntoIter<Change>,
+}
+
+#[cfg(feature = "net")]
+impl<S> Changes<S> {
+    pub(crate) fn new(stream: S) -> Self {
+        Changes {
+            stream,
+            detector: ChangeDetector::default(),
+            pending: Vec::new().into_iter(),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for Changes<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = Change;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(change) = this.pending.next() {
+                return Poll::Ready(Some(change));
+            }
+
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    this.pending = this.detector.update(&packet).into_iter();
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Conversion of the values of changed fields to JSON
+///
+/// Enums are converted to the names of their variants, like in the exported tables.
+#[cfg(feature = "json")]
+trait ToJson {
+    fn to_json(&self) -> serde_json::Value;
+}
+
+#[cfg(feature = "json")]
+macro_rules! to_json_from {
+    ($($ty:ty),*) => {
+        $(impl ToJson for $ty {
+            fn to_json(&self) -> serde_json::Value {
+                serde_json::Value::from(self.clone())
+            }
+        })*
+    };
+}
+
+#[cfg(feature = "json")]
+macro_rules! to_json_debug {
+    ($($ty:ty),*) => {
+        $(impl ToJson for $ty {
+            fn to_json(&self) -> serde_json::Value {
+                serde_json::Value::String(format!("{:?}", self))
+            }
+        })*
+    };
+}
+
+#[cfg(feature = "json")]
+to_json_from!(bool, u8, i8, String);
+
+#[cfg(feature = "json")]
+to_json_debug!(
+    Controller,
+    DrsSetting,
+    ErsDeployMode,
+    Flag,
+    Formula,
+    FuelMix,
+    Nationality,
+    PhysicalTyreCompound,
+    SafetyCar,
+    Session,
+    Team,
+    TelemetryPrivacy,
+    Track,
+    TractionControl,
+    VisualTyreCompound,
+    Weather
+);
+
+#[cfg(feature = "json")]
+impl ToJson for Driver {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            // The name of a custom driver is reported by the change of the name.
+            Driver::Custom { .. } => serde_json::Value::String("Custom".into()),
+            driver => serde_json::Value::String(format!("{:?}", driver)),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl ToJson for Duration {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::from(self.as_secs_f64())
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: ToJson + Copy> ToJson for CornerProperty<T> {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "front_left": self.front_left().to_json(),
+            "front_right": self.front_right().to_json(),
+            "rear_left": self.rear_left().to_json(),
+            "rear_right": self.rear_right().to_json(),
+        })
+    }
+}
+
+#[cfg(feature = "json")]
+impl ToJson for MarshalZone {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "start": self.start(),
+            "flag": self.flag().to_json(),
+        })
+    }
+}
+
+#[cfg(feature = "json")]
+impl ToJson for WeatherForecastSample {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "session_type": self.session_type().to_json(),
+            "time_offset": self.time_offset().to_json(),
+            "weather": self.weather().to_json(),
+            "track_temperature": self.track_temperature(),
+            "air_temperature": self.air_temperature(),
+        })
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Some(value) => value.to_json(),
+            None => serde_json::Value::Null,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+#[cfg(all(test, feature = "decode"))]
+mod tests {
+    use crate::fixtures::packets;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::packet::participants::{Participant, ParticipantsPacket};
+    use crate::packet::Packet;
+    use crate::stream::changes::{Change, ChangeDetector, ParticipantChange};
+
+    #[test]
+    fn update_reports_changed_fields() {
+        let participants = packets(ApiSpec::Nineteen, PacketType::Participants).unwrap();
+        let mut detector = ChangeDetector::default();
+
+        assert_eq!(20 * 7, detector.update(&participants[0]).len());
+        assert!(detector.update(&participants[0]).is_empty());
+
+        let packet = match &participants[0] {
+            Packet::Participants(packet) => packet,
+            packet => panic!("Expected participants, got {:?}", packet),
+        };
+
+        let mut cars = packet.participants().to_vec();
+        let car = cars[3].clone();
+        cars[3] = Participant::new(
+            car.controller(),
+            car.driver().clone(),
+            car.team(),
+            car.race_number(),
+            car.nationality(),
+            String::from("Player 1"),
+            car.telemetry_privacy(),
+        );
+
+        let renamed = Packet::Participants(ParticipantsPacket::new(
+            *packet.header(),
+            packet.active_participants_count(),
+            cars.into(),
+        ));
+
+        assert_eq!(
+            vec![Change::Participant(
+                3,
+                ParticipantChange::Name(String::from("Player 1"))
+            )],
+            detector.update(&renamed)
+        );
+    }
+
+    #[test]
+    fn update_ignores_other_packets() {
+        let mut detector = ChangeDetector::default();
+
+        for packet in packets(ApiSpec::Nineteen, PacketType::Telemetry).unwrap() {
+            assert!(detector.update(&packet).is_empty());
+        }
+
+        let sessions = packets(ApiSpec::Nineteen, PacketType::Session).unwrap();
+
+        assert!(!detector.update(&sessions[0]).is_empty());
+        assert!(detector.update(&sessions[0]).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+//! Deduplication of packets
+//!
+//! When the game broadcasts its telemetry, or a relay sends the packets back onto the network that
+//! they came from, the same packet can arrive more than once. Consumers that count laps, events,
+//! or samples then count some of them twice.
+//!
+//! The `Deduplicator` remembers the most recent packets by their session, packet type, and frame
+//! identifier, and drops packets that it has already seen. Since several events can happen in the
+//! same frame, event packets are only dropped if their event is the same as well.
+
+use std::collections::{HashSet, VecDeque};
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::event::Event;
+use crate::packet::header::PacketType;
+use crate::packet::Packet;
+
+/// Number of packets that are remembered by default, which covers the packets of several frames
+const DEFAULT_WINDOW: usize = 64;
+
+/// Identity of a packet
+type Key = (u64, PacketType, u32, Option<Event>);
+
+/// Filter that drops duplicate packets
+///
+/// Duplicates are only detected within the most recent `window` packets. This keeps the memory
+/// bounded, and is sufficient since duplicates arrive shortly after the original.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::stream::dedup::Deduplicator;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut deduplicator = Deduplicator::default();
+///
+/// for packet in packets().iter().filter(|packet| deduplicator.accept(packet)) {
+///     println!("{:?}", packet.header());
+/// }
+///
+/// println!("Dropped {} duplicates", deduplicator.duplicates());
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Deduplicator {
+    window: usize,
+    order: VecDeque<Key>,
+    seen: HashSet<Key>,
+    duplicates: u64,
+}
+
+impl Deduplicator {
+    /// Create a new filter that remembers the given number of packets.
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+
+        Deduplicator {
+            window,
+            order: VecDeque::with_capacity(window),
+            seen: HashSet::with_capacity(window),
+            duplicates: 0,
+        }
+    }
+
+    /// Returns whether the packet should be passed through, i.e. whether it is not a duplicate.
+    pub fn accept(&mut self, packet: &Packet) -> bool {
+        let header = packet.header();
+        let event = match packet {
+            Packet::Event(packet) => Some(*packet.event()),
+            _ => None,
+        };
+
+        let key = (
+            header.session_uid(),
+            header.packet_type(),
+            header.frame_identifier(),
+            event,
+        );
+
+        if !self.seen.insert(key) {
+            self.duplicates += 1;
+            return false;
+        }
+
+        if self.order.len() == self.window {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+
+        self.order.push_back(key);
+        true
+    }
+
+    /// Returns the number of duplicates that have been dropped.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+}
+
+impl Default for Deduplicator {
+    fn default() -> Self {
+        Deduplicator::new(DEFAULT_WINDOW)
+    }
+}
+
+/// Stream adapter that drops duplicate packets
+///
+/// The adapter is created by `PacketStreamExt::dedup`.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct Dedup<S> {
+    stream: S,
+    deduplicator: Deduplicator,
+}
+
+#[cfg(feature = "net")]
+impl<S> Dedup<S> {
+    pub(crate) fn new(stream: S, deduplicator: Deduplicator) -> Self {
+        Dedup {
+            stream,
+            deduplicator,
+        }
+    }
+
+    /// Returns the filter of the adapter.
+    pub fn deduplicator(&self) -> &Deduplicator {
+        &self.deduplicator
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for Dedup<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = Packet;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    if this.deduplicator.accept(&packet) {
+                        return Poll::Ready(Some(packet));
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::event::{Event, EventPacket};
+    use crate::packet::header::PacketType;
+    use crate::packet::Packet;
+    use crate::stream::dedup::Deduplicator;
+
+    fn event_packet(frame: u32, event: Event) -> Packet {
+        Packet::Event(EventPacket::new(
+            header(PacketType::Event, 1, Duration::default(), frame),
+            event,
+        ))
+    }
+
+    #[test]
+    fn accept_drops_duplicates() {
+        let mut deduplicator = Deduplicator::default();
+
+        assert!(deduplicator.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 1),
+            Vec::new()
+        )));
+        assert!(!deduplicator.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 1),
+            Vec::new()
+        )));
+        assert!(deduplicator.accept(&lap_packet(
+            header(PacketType::Lap, 2, Duration::default(), 1),
+            Vec::new()
+        )));
+
+        assert!(deduplicator.accept(&event_packet(1, Event::DrsEnabled)));
+        assert!(deduplicator.accept(&event_packet(1, Event::ChequeredFlag)));
+        assert!(!deduplicator.accept(&event_packet(1, Event::DrsEnabled)));
+
+        assert_eq!(2, deduplicator.duplicates());
+    }
+
+    #[test]
+    fn accept_forgets_packets_outside_the_window() {
+        let mut deduplicator = Deduplicator::new(2);
+
+        assert!(deduplicator.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 1),
+            Vec::new()
+        )));
+        assert!(deduplicator.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 2),
+            Vec::new()
+        )));
+        assert!(deduplicator.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 3),
+            Vec::new()
+        )));
+        assert!(deduplicator.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 1),
+            Vec::new()
+        )));
+    }
+}
@@ -0,0 +1,275 @@
+//! Downsampling of packets
+//!
+//! The F1 games can send some packets, for example motion and telemetry data, at up to 60 Hz. Many
+//! consumers do not need this resolution, and would rather receive fewer packets. The `Downsampler`
+//! limits selected packet types to a maximum rate, and drops the packets in between.
+//!
+//! The rate is measured in session time, not in wall-clock time. This makes downsampling
+//! deterministic, and allows captures to be downsampled the same way as live sessions.
+
+use std::collections::HashMap;
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::header::PacketType;
+use crate::packet::Packet;
+
+/// Rate limit for a single packet type
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+struct Limit {
+    interval: Duration,
+    session_uid: Option<u64>,
+    last: Option<Duration>,
+    next: Duration,
+}
+
+impl Limit {
+    fn accept(&mut self, session_uid: u64, time: Duration) -> bool {
+        // Reset the limit when a new session starts, or when the session time jumps back, e.g.
+        // after a flashback.
+        if self.session_uid != Some(session_uid) || self.last.is_some_and(|last| time < last) {
+            self.session_uid = Some(session_uid);
+            self.next = time;
+        }
+
+        if time < self.next {
+            return false;
+        }
+
+        // The next packet is scheduled relative to the previous schedule rather than the time of
+        // this packet, so that the average rate matches the limit even if the game's send rate is
+        // not a multiple of it.
+        self.next = if self.next.saturating_add(self.interval) > time {
+            self.next.saturating_add(self.interval)
+        } else {
+            time.saturating_add(self.interval)
+        };
+        self.last = Some(time);
+
+        true
+    }
+}
+
+/// Filter that limits the rate of selected packet types
+///
+/// Packet types without a limit are passed through unchanged. Event packets are always passed
+/// through, since each of them announces something that happened in the session, and so are
+/// session packets, since they carry the state of the session that other packets depend on.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::packet::header::PacketType;
+/// use f1_api::stream::downsample::Downsampler;
+///
+/// let downsampler = Downsampler::default()
+///     .limit(PacketType::Motion, 10.0)
+///     .limit(PacketType::Telemetry, 5.0);
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Downsampler {
+    limits: HashMap<PacketType, Limit>,
+}
+
+impl Downsampler {
+    /// Limit the given packet type to a maximum rate in Hertz.
+    ///
+    /// Rates that are too low for their interval to be represented as a `Duration` pass only the
+    /// first packet of a session.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rate is not a positive, finite number.
+    pub fn limit(mut self, packet_type: PacketType, hertz: f32) -> Self {
+        assert!(
+            hertz > 0.0 && hertz.is_finite(),
+            "The rate limit must be a positive, finite number"
+        );
+
+        let exempt = matches!(packet_type, PacketType::Event | PacketType::Session);
+
+        if !exempt {
+            self.limits.insert(
+                packet_type,
+                Limit {
+                    interval: Duration::try_from_secs_f32(1.0 / hertz).unwrap_or(Duration::MAX),
+                    session_uid: None,
+                    last: None,
+                    next: Duration::default(),
+                },
+            );
+        }
+
+        self
+    }
+
+    /// Returns whether the packet should be passed through.
+    pub fn accept(&mut self, packet: &Packet) -> bool {
+        let header = packet.header();
+
+        match self.limits.get_mut(&header.packet_type()) {
+            Some(limit) => limit.accept(header.session_uid(), *header.session_time()),
+            None => true,
+        }
+    }
+}
+
+/// Stream adapter that limits the rate of selected packet types
+///
+/// The adapter is created by `PacketStreamExt::downsample`.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct Downsample<S> {
+    stream: S,
+    downsampler: Downsampler,
+}
+
+#[cfg(feature = "net")]
+impl<S> Downsample<S> {
+    pub(crate) fn new(stream: S, downsampler: Downsampler) -> Self {
+        Downsample {
+            stream,
+            downsampler,
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for Downsample<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = Packet;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    if this.downsampler.accept(&packet) {
+                        return Poll::Ready(Some(packet));
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::event::{Event, EventPacket};
+    use crate::packet::header::PacketType;
+    use crate::packet::Packet;
+    use crate::stream::downsample::Downsampler;
+
+    #[test]
+    fn accept_with_limit() {
+        let mut downsampler = Downsampler::default().limit(PacketType::Lap, 25.0);
+
+        let accepted = (0..60)
+            .map(|frame| {
+                lap_packet(
+                    header(
+                        PacketType::Lap,
+                        1,
+                        Duration::from_millis(frame * 1000 / 60),
+                        0,
+                    ),
+                    Vec::new(),
+                )
+            })
+            .filter(|packet| downsampler.accept(packet))
+            .count();
+
+        assert_eq!(25, accepted);
+    }
+
+    #[test]
+    fn accept_without_limit() {
+        let mut downsampler = Downsampler::default().limit(PacketType::Motion, 1.0);
+
+        assert!(downsampler.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            Vec::new()
+        )));
+        assert!(downsampler.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(1), 0),
+            Vec::new()
+        )));
+    }
+
+    #[test]
+    fn accept_events() {
+        let mut downsampler = Downsampler::default().limit(PacketType::Event, 1.0);
+        let event = Packet::Event(EventPacket::new(
+            header(PacketType::Event, 1, Duration::default(), 0),
+            Event::SessionStarted,
+        ));
+
+        assert!(downsampler.accept(&event));
+        assert!(downsampler.accept(&event));
+    }
+
+    #[cfg(feature = "decode")]
+    #[test]
+    fn accept_sessions() {
+        let mut downsampler = Downsampler::default().limit(PacketType::Session, 1.0);
+        let sessions = crate::fixtures::packets(
+            crate::packet::header::ApiSpec::Nineteen,
+            PacketType::Session,
+        );
+        let session = &sessions.unwrap()[0];
+
+        assert!(downsampler.accept(session));
+        assert!(downsampler.accept(session));
+    }
+
+    #[test]
+    fn accept_with_tiny_limit() {
+        let mut downsampler = Downsampler::default().limit(PacketType::Lap, f32::MIN_POSITIVE);
+
+        assert!(downsampler.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            Vec::new()
+        )));
+        assert!(!downsampler.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs(3600), 0),
+            Vec::new()
+        )));
+    }
+
+    #[test]
+    #[should_panic(expected = "The rate limit must be a positive, finite number")]
+    fn limit_with_invalid_rate() {
+        let _ = Downsampler::default().limit(PacketType::Lap, f32::NAN);
+    }
+
+    #[test]
+    fn accept_after_flashback() {
+        let mut downsampler = Downsampler::default().limit(PacketType::Lap, 1.0);
+
+        assert!(downsampler.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(5000), 0),
+            Vec::new()
+        )));
+        assert!(!downsampler.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(5500), 0),
+            Vec::new()
+        )));
+        assert!(downsampler.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_millis(2000), 0),
+            Vec::new()
+        )));
+    }
+}
@@ -0,0 +1,304 @@
+//! Arrival rates of packets
+//!
+//! The F1 games send motion, lap, telemetry, and status packets at the rate that is configured in
+//! the telemetry settings of the game, and the other packet types at fixed rates. When a dashboard
+//! feels laggy, the first question is whether the packets arrive as often as they should. The
+//! `FrequencyAnalyzer` measures how many packets of each type arrived over a sliding window, and
+//! compares the measured rates to the rates that the game should be sending.
+//!
+//! Unlike the `LossDetector`, which looks at frame identifiers, the analyzer measures the arrival
+//! times of packets. It can therefore also detect a game that sends at a different rate than
+//! expected, or a receiver that processes packets too slowly.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use derive_new::new;
+use getset::CopyGetters;
+
+use crate::packet::header::{Header, PacketType};
+
+/// Length of the sliding window that is used by default to measure the rates
+const DEFAULT_WINDOW: Duration = Duration::from_secs(10);
+
+/// Relative deviation from the expected rate that is tolerated by default
+const DEFAULT_TOLERANCE: f32 = 0.2;
+
+/// Packet types that are always sent at a fixed rate, and their rate in Hertz
+const FIXED_RATES: [(PacketType, f32); 3] = [
+    (PacketType::Participants, 0.2),
+    (PacketType::Session, 2.0),
+    (PacketType::Setup, 2.0),
+];
+
+/// Packet types that are sent at the rate configured in the game
+const CONFIGURED_TYPES: [PacketType; 4] = [
+    PacketType::Lap,
+    PacketType::Motion,
+    PacketType::Status,
+    PacketType::Telemetry,
+];
+
+/// Measured arrival rate of a packet type
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd)]
+pub struct PacketRate {
+    /// Returns the type of the packets.
+    #[getset(get_copy = "pub")]
+    packet_type: PacketType,
+
+    /// Returns the measured rate in Hertz.
+    #[getset(get_copy = "pub")]
+    measured: f32,
+
+    /// Returns the rate in Hertz at which the game sends the packets, or `None` for event packets.
+    #[getset(get_copy = "pub")]
+    expected: Option<f32>,
+}
+
+impl PacketRate {
+    /// Returns whether the measured rate deviates from the expected rate by more than the tolerance.
+    pub fn is_anomaly(&self, tolerance: f32) -> bool {
+        match self.expected {
+            Some(expected) => (self.measured - expected).abs() > expected * tolerance,
+            None => false,
+        }
+    }
+}
+
+/// Analyzer for the arrival rates of packets
+///
+/// The analyzer is created with the send rate that is configured in the game, and measures the
+/// rates over the last 10 seconds by default. Rates are only compared once a full window has been
+/// observed, so that a session that just started is not reported as an anomaly. While the game is
+/// paused, it sends fewer packets, which shows up as anomalies.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Instant;
+///
+/// use f1_api::stream::frequency::FrequencyAnalyzer;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut analyzer = FrequencyAnalyzer::new(60.0);
+///
+/// for packet in packets() {
+///     analyzer.update(packet.header(), Instant::now());
+/// }
+///
+/// for rate in analyzer.anomalies() {
+///     println!(
+///         "{:?}: {:.1} Hz instead of {:.1} Hz",
+///         rate.packet_type(),
+///         rate.measured(),
+///         rate.expected().unwrap_or_default()
+///     );
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct FrequencyAnalyzer {
+    send_rate: f32,
+    window: Duration,
+    tolerance: f32,
+    first: Option<Instant>,
+    latest: Option<Instant>,
+    arrivals: HashMap<PacketType, VecDeque<Instant>>,
+}
+
+impl FrequencyAnalyzer {
+    /// Create an analyzer for a game that sends packets at the given rate in Hertz.
+    pub fn new(send_rate: f32) -> Self {
+        FrequencyAnalyzer {
+            send_rate,
+            window: DEFAULT_WINDOW,
+            tolerance: DEFAULT_TOLERANCE,
+            first: None,
+            latest: None,
+            arrivals: HashMap::new(),
+        }
+    }
+
+    /// Measure the rates over the given window.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Tolerate the given relative deviation from the expected rates, e.g. 0.2 for 20%.
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Add the header of a packet and the time at which the packet arrived.
+    pub fn update(&mut self, header: &Header, arrival: Instant) {
+        self.first.get_or_insert(arrival);
+        self.latest = Some(self.latest.map_or(arrival, |latest| latest.max(arrival)));
+
+        self.arrivals
+            .entry(header.packet_type())
+            .or_default()
+            .push_back(arrival);
+
+        self.evict();
+    }
+
+    /// Returns the rate in Hertz at which the game should send the given packet type.
+    pub fn expected_rate(&self, packet_type: PacketType) -> Option<f32> {
+        if CONFIGURED_TYPES.contains(&packet_type) {
+            return Some(self.send_rate);
+        }
+
+        FIXED_RATES
+            .iter()
+            .find(|(fixed_type, _)| *fixed_type == packet_type)
+            .map(|(_, rate)| *rate)
+    }
+
+    /// Returns the measured rate of the given packet type, or `None` before any packet arrived.
+    pub fn rate(&self, packet_type: PacketType) -> Option<PacketRate> {
+        let elapsed = self.elapsed()?.as_secs_f32();
+
+        if elapsed <= 0.0 {
+            return None;
+        }
+
+        let count = self.arrivals.get(&packet_type).map_or(0, |a| a.len());
+
+        Some(PacketRate::new(
+            packet_type,
+            count as f32 / elapsed,
+            self.expected_rate(packet_type),
+        ))
+    }
+
+    /// Returns the measured rates of all packet types that are expected or have arrived.
+    pub fn rates(&self) -> Vec<PacketRate> {
+        let mut packet_types: Vec<PacketType> = CONFIGURED_TYPES
+            .iter()
+            .chain(FIXED_RATES.iter().map(|(packet_type, _)| packet_type))
+            .chain(self.arrivals.keys())
+            .copied()
+            .collect();
+
+        packet_types.sort();
+        packet_types.dedup();
+
+        packet_types
+            .into_iter()
+            .filter_map(|packet_type| self.rate(packet_type))
+            .collect()
+    }
+
+    /// Returns the rates that deviate from the expected rates by more than the tolerance.
+    ///
+    /// No anomalies are reported until packets have been received for the length of a full window.
+    pub fn anomalies(&self) -> Vec<PacketRate> {
+        if self.elapsed().is_none_or(|elapsed| elapsed < self.window) {
+            return Vec::new();
+        }
+
+        self.rates()
+            .into_iter()
+            .filter(|rate| rate.is_anomaly(self.tolerance))
+            .collect()
+    }
+
+    /// Returns the time span over which the rates are measured.
+    fn elapsed(&self) -> Option<Duration> {
+        let elapsed = self.latest?.duration_since(self.first?);
+        Some(elapsed.min(self.window))
+    }
+
+    fn evict(&mut self) {
+        let latest = match self.latest {
+            Some(latest) => latest,
+            None => return,
+        };
+
+        let window = self.window;
+
+        for arrivals in self.arrivals.values_mut() {
+            while arrivals
+                .front()
+                .is_some_and(|arrival| latest.duration_since(*arrival) >= window)
+            {
+                arrivals.pop_front();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use assert_approx_eq::assert_approx_eq;
+
+    use crate::fixtures::header;
+    use crate::packet::header::PacketType;
+    use crate::stream::frequency::FrequencyAnalyzer;
+
+    #[test]
+    fn anomalies_after_full_window() {
+        let start = Instant::now();
+        let mut analyzer = FrequencyAnalyzer::new(20.0).window(Duration::from_secs(2));
+
+        // Motion packets arrive at 20 Hz for 3 seconds, but telemetry packets at only 10 Hz
+        for tick in 1..=60 {
+            let arrival = start + Duration::from_millis(tick * 50);
+
+            analyzer.update(
+                &header(PacketType::Motion, 1, Duration::default(), 0),
+                arrival,
+            );
+
+            if tick % 2 == 0 {
+                analyzer.update(
+                    &header(PacketType::Telemetry, 1, Duration::default(), 0),
+                    arrival,
+                );
+            }
+
+            if tick % 10 == 0 {
+                analyzer.update(
+                    &header(PacketType::Session, 1, Duration::default(), 0),
+                    arrival,
+                );
+            }
+
+            if tick == 20 {
+                assert!(analyzer.anomalies().is_empty());
+            }
+        }
+
+        assert_approx_eq!(
+            20.0,
+            analyzer.rate(PacketType::Motion).unwrap().measured(),
+            1.0
+        );
+        assert_approx_eq!(
+            10.0,
+            analyzer.rate(PacketType::Telemetry).unwrap().measured(),
+            1.0
+        );
+
+        let anomalies: Vec<PacketType> = analyzer
+            .anomalies()
+            .iter()
+            .map(|rate| rate.packet_type())
+            .collect();
+
+        assert_eq!(
+            vec![
+                PacketType::Lap,
+                PacketType::Participants,
+                PacketType::Setup,
+                PacketType::Status,
+                PacketType::Telemetry
+            ],
+            anomalies
+        );
+    }
+}
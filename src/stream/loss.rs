@@ -0,0 +1,356 @@
+//! Detection of lost packets
+//!
+//! The F1 games publish their packets over UDP, which does not guarantee that packets arrive at
+//! their destination. Packets get lost on unreliable networks like Wi-Fi, or when the socket buffer
+//! of the receiver is too small to hold all packets that arrive in a burst.
+//!
+//! Each packet carries the identifier of the frame in which it was created. Since most packet types
+//! are sent at a fixed rate, the difference between the frame identifiers of two consecutive
+//! packets of the same type is roughly constant. The `LossDetector` learns this interval for each
+//! packet type, and reports a gap when the difference is significantly larger than expected.
+
+use std::collections::HashMap;
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+
+use derive_new::new;
+use getset::CopyGetters;
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::header::PacketType;
+use crate::packet::Packet;
+
+/// Factor by which the difference between two frames must exceed the expected interval to be
+/// considered a gap
+const GAP_THRESHOLD: f32 = 1.5;
+
+/// Weight of a new sample in the moving average of the frame interval
+const INTERVAL_SMOOTHING: f32 = 0.1;
+
+/// Gap in the frame identifiers of a packet type
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct Gap {
+    /// Returns the type of the packets that have been lost.
+    #[getset(get_copy = "pub")]
+    packet_type: PacketType,
+
+    /// Returns the frame identifier of the last packet before the gap.
+    #[getset(get_copy = "pub")]
+    from_frame: u32,
+
+    /// Returns the frame identifier of the first packet after the gap.
+    #[getset(get_copy = "pub")]
+    to_frame: u32,
+
+    /// Returns the estimated number of packets that have been lost.
+    #[getset(get_copy = "pub")]
+    missing: u64,
+}
+
+/// Statistics about the packets of a single packet type
+#[derive(Debug, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+pub struct LossStatistics {
+    /// Returns the number of packets that have been received.
+    #[getset(get_copy = "pub")]
+    received: u64,
+
+    /// Returns the estimated number of packets that have been lost.
+    #[getset(get_copy = "pub")]
+    missing: u64,
+
+    /// Returns the number of packets that arrived after a packet from a later frame.
+    #[getset(get_copy = "pub")]
+    out_of_order: u64,
+
+    last_frame: Option<u32>,
+    interval: Option<f32>,
+}
+
+impl LossStatistics {
+    /// Returns the share of packets that have been lost, between 0 and 1.
+    pub fn loss_rate(&self) -> f32 {
+        loss_rate(self.received, self.missing)
+    }
+
+    fn update(&mut self, packet_type: PacketType, frame: u32) -> Option<Gap> {
+        self.received += 1;
+
+        let last_frame = match self.last_frame {
+            Some(last_frame) => last_frame,
+            None => {
+                self.last_frame = Some(frame);
+                return None;
+            }
+        };
+
+        if frame <= last_frame {
+            self.out_of_order += 1;
+            return None;
+        }
+
+        self.last_frame = Some(frame);
+
+        let difference = (frame - last_frame) as f32;
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => {
+                self.interval = Some(difference);
+                return None;
+            }
+        };
+
+        if difference > interval * GAP_THRESHOLD {
+            let missing = ((difference / interval).round() as u64)
+                .saturating_sub(1)
+                .max(1);
+            self.missing += missing;
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                ?packet_type,
+                last_frame,
+                frame,
+                missing,
+                "Detected lost packets"
+            );
+
+            return Some(Gap::new(packet_type, last_frame, frame, missing));
+        }
+
+        // The first interval that was measured might have included a gap, in which case it is
+        // replaced as soon as an interval that is less than half as long is observed.
+        self.interval = if difference * 2.0 < interval {
+            Some(difference)
+        } else {
+            Some(interval + (difference - interval) * INTERVAL_SMOOTHING)
+        };
+
+        None
+    }
+}
+
+/// Detector for lost packets
+///
+/// The detector keeps statistics for each packet type. Event packets are ignored, since they are
+/// only sent when something happens in the session. The frame identifiers are reset when a new
+/// session starts, but the statistics are kept across sessions.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LossDetector {
+    session_uid: Option<u64>,
+    statistics: HashMap<PacketType, LossStatistics>,
+}
+
+impl LossDetector {
+    /// Update the detector with a new packet, and return the gap before it if one was detected.
+    pub fn update(&mut self, packet: &Packet) -> Option<Gap> {
+        let header = packet.header();
+
+        if header.packet_type() == PacketType::Event {
+            return None;
+        }
+
+        if self.session_uid != Some(header.session_uid()) {
+            self.session_uid = Some(header.session_uid());
+
+            for statistics in self.statistics.values_mut() {
+                statistics.last_frame = None;
+                statistics.interval = None;
+            }
+        }
+
+        self.statistics
+            .entry(header.packet_type())
+            .or_default()
+            .update(header.packet_type(), header.frame_identifier())
+    }
+
+    /// Returns the statistics for the given packet type.
+    pub fn statistics(&self, packet_type: PacketType) -> Option<&LossStatistics> {
+        self.statistics.get(&packet_type)
+    }
+
+    /// Returns the share of packets across all packet types that have been lost, between 0 and 1.
+    pub fn loss_rate(&self) -> f32 {
+        let received = self.statistics.values().map(|s| s.received()).sum();
+        let missing = self.statistics.values().map(|s| s.missing()).sum();
+
+        loss_rate(received, missing)
+    }
+}
+
+fn loss_rate(received: u64, missing: u64) -> f32 {
+    if received + missing == 0 {
+        return 0.0;
+    }
+
+    missing as f32 / (received + missing) as f32
+}
+
+/// Stream adapter that detects lost packets
+///
+/// The adapter is created by `PacketStreamExt::detect_loss`.
+#[cfg(feature = "net")]
+pub struct DetectLoss<S, F> {
+    stream: S,
+    on_gap: F,
+    detector: LossDetector,
+}
+
+#[cfg(feature = "net")]
+impl<S, F> DetectLoss<S, F> {
+    pub(crate) fn new(stream: S, on_gap: F) -> Self {
+        DetectLoss {
+            stream,
+            on_gap,
+            detector: LossDetector::default(),
+        }
+    }
+
+    /// Returns the detector with the statistics about lost packets.
+    pub fn detector(&self) -> &LossDetector {
+        &self.detector
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S, F> Stream for DetectLoss<S, F>
+where
+    S: Stream<Item = Packet> + Unpin,
+    F: FnMut(&Gap) + Unpin,
+{
+    type Item = Packet;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let packet = match Pin::new(&mut this.stream).poll_next(cx) {
+            Poll::Ready(Some(packet)) => packet,
+            other => return other,
+        };
+
+        if let Some(gap) = this.detector.update(&packet) {
+            (this.on_gap)(&gap);
+        }
+
+        Poll::Ready(Some(packet))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+    use crate::stream::loss::{Gap, LossDetector};
+
+    #[test]
+    fn update_with_gap() {
+        let mut detector = LossDetector::default();
+
+        assert_eq!(
+            None,
+            detector.update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                Vec::new()
+            ))
+        );
+        assert_eq!(
+            None,
+            detector.update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 3),
+                Vec::new()
+            ))
+        );
+        assert_eq!(
+            None,
+            detector.update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 6),
+                Vec::new()
+            ))
+        );
+
+        let gap = detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 15),
+            Vec::new(),
+        ));
+        assert_eq!(Some(Gap::new(PacketType::Lap, 6, 15, 2)), gap);
+
+        let statistics = detector.statistics(PacketType::Lap).unwrap();
+        assert_eq!(4, statistics.received());
+        assert_eq!(2, statistics.missing());
+        assert!((statistics.loss_rate() - 1.0 / 3.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn update_with_jitter() {
+        let mut detector = LossDetector::default();
+
+        for frame in &[0, 3, 5, 9, 12, 14, 18] {
+            assert_eq!(
+                None,
+                detector.update(&lap_packet(
+                    header(PacketType::Lap, 1, Duration::default(), *frame),
+                    Vec::new()
+                ))
+            );
+        }
+
+        assert_eq!(0.0, detector.loss_rate());
+    }
+
+    #[test]
+    fn update_with_new_session() {
+        let mut detector = LossDetector::default();
+
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 1000),
+            Vec::new(),
+        ));
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 1003),
+            Vec::new(),
+        ));
+
+        assert_eq!(
+            None,
+            detector.update(&lap_packet(
+                header(PacketType::Lap, 2, Duration::default(), 0),
+                Vec::new()
+            ))
+        );
+        assert_eq!(
+            None,
+            detector.update(&lap_packet(
+                header(PacketType::Lap, 2, Duration::default(), 3),
+                Vec::new()
+            ))
+        );
+
+        let statistics = detector.statistics(PacketType::Lap).unwrap();
+        assert_eq!(0, statistics.out_of_order());
+    }
+
+    #[test]
+    fn update_out_of_order() {
+        let mut detector = LossDetector::default();
+
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 0),
+            Vec::new(),
+        ));
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 6),
+            Vec::new(),
+        ));
+        detector.update(&lap_packet(
+            header(PacketType::Lap, 1, Duration::default(), 3),
+            Vec::new(),
+        ));
+
+        let statistics = detector.statistics(PacketType::Lap).unwrap();
+        assert_eq!(1, statistics.out_of_order());
+    }
+}
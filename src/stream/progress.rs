@@ -0,0 +1,257 @@
+//! Progress through the session at a steady rate
+//!
+//! The session packet reports the time that is left in the session, but the games send it only
+//! every two seconds. Countdown and lap counter widgets would have to interpolate between session
+//! packets on their own. The `ProgressTracker` does this once, and reports the progress through
+//! the session at a fixed interval.
+//!
+//! Like the `Downsampler`, the tracker measures the interval in session time. It reports no
+//! progress while the game is paused, and replays of captures produce the same updates as the live
+//! session.
+
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::Packet;
+
+/// Progress through a session at a point in time
+#[derive(new, Debug, CopyGetters, Getters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct SessionProgress {
+    /// Returns the session time of the update.
+    #[getset(get = "pub")]
+    session_time: Duration,
+
+    /// Returns the time that is left in the session.
+    #[getset(get = "pub")]
+    time_left: Duration,
+
+    /// Returns the lap of the leader, or `None` if no lap packet has been received yet.
+    #[getset(get_copy = "pub")]
+    lap: Option<u8>,
+
+    /// Returns the total number of laps in the session, which is zero for sessions without laps.
+    #[getset(get_copy = "pub")]
+    total_laps: u8,
+}
+
+/// Tracker that reports the progress through the session at a fixed interval
+///
+/// Progress is reported once a session packet has been received, and then whenever the session
+/// time of a packet has passed the next interval. Between two session packets, the time that is
+/// left is counted down from the session time.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use f1_api::stream::progress::ProgressTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = ProgressTracker::new(Duration::from_millis(100));
+///
+/// for packet in packets() {
+///     if let Some(progress) = tracker.update(&packet) {
+///         println!("{}s left", progress.time_left().as_secs());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct ProgressTracker {
+    interval: Duration,
+    session_uid: Option<u64>,
+    anchor: Option<(Duration, Duration)>,
+    lap: Option<u8>,
+    total_laps: u8,
+    next: Duration,
+}
+
+impl ProgressTracker {
+    /// Create a tracker that reports the progress at the given interval.
+    pub fn new(interval: Duration) -> Self {
+        ProgressTracker {
+            interval,
+            session_uid: None,
+            anchor: None,
+            lap: None,
+            total_laps: 0,
+            next: Duration::default(),
+        }
+    }
+
+    /// Update the tracker with a new packet, and return the progress if the interval has passed.
+    pub fn update(&mut self, packet: &Packet) -> Option<SessionProgress> {
+        let header = packet.header();
+        let session_time = *header.session_time();
+
+        if self.session_uid != Some(header.session_uid()) {
+            *self = ProgressTracker::new(self.interval);
+            self.session_uid = Some(header.session_uid());
+        }
+
+        match packet {
+            Packet::Session(session) => {
+                // The schedule starts with the first session packet, and restarts when the session
+                // time jumps back, e.g. after a flashback.
+                if self.anchor.is_none_or(|(time, _)| session_time < time) {
+                    self.next = session_time;
+                }
+
+                self.anchor = Some((session_time, *session.time_left()));
+                self.total_laps = session.total_laps();
+            }
+            Packet::Lap(packet) => {
+                if let Some(leader) = packet.laps().iter().find(|lap| lap.position() == 1) {
+                    self.lap = Some(leader.current_lap_number());
+                }
+            }
+            _ => {}
+        }
+
+        let (anchor_time, anchor_time_left) = self.anchor?;
+
+        if session_time < self.next {
+            return None;
+        }
+
+        self.next = if self.next + self.interval > session_time {
+            self.next + self.interval
+        } else {
+            session_time + self.interval
+        };
+
+        let elapsed = session_time.saturating_sub(anchor_time);
+
+        Some(SessionProgress::new(
+            session_time,
+            anchor_time_left.saturating_sub(elapsed),
+            self.lap,
+            self.total_laps,
+        ))
+    }
+}
+
+/// Stream adapter that reports the progress through the session at a fixed interval
+///
+/// The adapter is created by `PacketStreamExt::progress`.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct Progress<S> {
+    stream: S,
+    tracker: ProgressTracker,
+}
+
+#[cfg(feature = "net")]
+impl<S> Progress<S> {
+    pub(crate) fn new(stream: S, interval: Duration) -> Self {
+        Progress {
+            stream,
+            tracker: ProgressTracker::new(interval),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for Progress<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = SessionProgress;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    if let Some(progress) = this.tracker.update(&packet) {
+                        return Poll::Ready(Some(progress));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::session::SessionPacket;
+    use crate::packet::Packet;
+    use crate::stream::progress::ProgressTracker;
+
+    fn session_packet(millis: u64, time_left: u64) -> Packet {
+        Packet::Session(SessionPacket::new(
+            header(PacketType::Session, 1, Duration::from_millis(millis), 0),
+            Default::default(),
+            30,
+            20,
+            50,
+            5000,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Duration::from_secs(time_left),
+            Duration::from_secs(3600),
+            80,
+            false,
+            false,
+            0,
+            false,
+            Vec::new(),
+            Default::default(),
+            false,
+            None,
+        ))
+    }
+
+    #[test]
+    fn update_counts_down_between_session_packets() {
+        let mut tracker = ProgressTracker::new(Duration::from_millis(500));
+
+        assert_eq!(
+            None,
+            tracker.update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 0),
+                Vec::new()
+            ))
+        );
+
+        let progress = tracker.update(&session_packet(1000, 600)).unwrap();
+        assert_eq!(Duration::from_secs(600), *progress.time_left());
+        assert_eq!(50, progress.total_laps());
+
+        assert_eq!(
+            None,
+            tracker.update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::from_millis(1250), 0),
+                Vec::new()
+            ))
+        );
+
+        let progress = tracker
+            .update(&lap_packet(
+                header(PacketType::Lap, 1, Duration::from_millis(1500), 0),
+                Vec::new(),
+            ))
+            .unwrap();
+        assert_eq!(Duration::from_millis(599_500), *progress.time_left());
+
+        let progress = tracker.update(&session_packet(3000, 598)).unwrap();
+        assert_eq!(Duration::from_secs(598), *progress.time_left());
+    }
+}
@@ -0,0 +1,445 @@
+//! Reordering of packets
+//!
+//! UDP does not guarantee that packets arrive in the order in which they were sent. Consumers that
+//! track the state of a session, for example the `SessionTracker`, can show glitches when an older
+//! packet overwrites the data of a newer one.
+//!
+//! The `ReorderBuffer` holds back a small number of packets for a short time, and releases them
+//! ordered by their frame identifier and session time. Packets that arrive after a newer packet has
+//! already been released are too late to be put back into order, and are dropped.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
+#[cfg(feature = "net")]
+use std::future::Future;
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "net")]
+use tokio::time::Sleep;
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::Packet;
+
+/// Packet in the buffer, ordered by its position in the session
+#[derive(Debug)]
+struct Entry {
+    key: (u32, Duration, u64),
+    arrival: Instant,
+    packet: Packet,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// Buffer that puts packets back into order
+///
+/// The buffer holds back up to `capacity` packets, each for at most `max_delay` after it arrived.
+/// Once the buffer is full, every new packet releases the oldest packet in the buffer. When a packet
+/// has been held for `max_delay`, it is released together with all packets that precede it, so that
+/// packets are not stuck in the buffer when the game stops sending, e.g. in the menus. Packets of
+/// the same frame are released in the order in which they arrived. When a new session starts, all
+/// packets of the previous session are released first.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+///
+/// use f1_api::stream::reorder::ReorderBuffer;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut buffer = ReorderBuffer::new(8, Duration::from_millis(50));
+///
+/// for packet in packets() {
+///     buffer.push(packet, Instant::now());
+///
+///     while let Some(packet) = buffer.pop(Instant::now()) {
+///         println!("{:?}", packet);
+///     }
+/// }
+///
+/// while let Some(packet) = buffer.flush() {
+///     println!("{:?}", packet);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ReorderBuffer {
+    capacity: usize,
+    max_delay: Duration,
+    session_uid: Option<u64>,
+    sequence: u64,
+    heap: BinaryHeap<Reverse<Entry>>,
+    ready: VecDeque<Packet>,
+    released: Option<(u32, Duration)>,
+    late: u64,
+}
+
+impl ReorderBuffer {
+    /// Create a new buffer that holds back up to `capacity` packets for at most `max_delay`.
+    pub fn new(capacity: usize, max_delay: Duration) -> Self {
+        ReorderBuffer {
+            capacity,
+            max_delay,
+            session_uid: None,
+            sequence: 0,
+            heap: BinaryHeap::with_capacity(capacity + 1),
+            ready: VecDeque::new(),
+            released: None,
+            late: 0,
+        }
+    }
+
+    /// Add a packet that arrived at the given time to the buffer.
+    pub fn push(&mut self, packet: Packet, arrival: Instant) {
+        let header = packet.header();
+        let position = (header.frame_identifier(), *header.session_time());
+
+        if self.session_uid != Some(header.session_uid()) {
+            self.session_uid = Some(header.session_uid());
+            self.released = None;
+
+            while let Some(Reverse(entry)) = self.heap.pop() {
+                self.ready.push_back(entry.packet);
+            }
+        } else if self.released.is_some_and(|released| position < released) {
+            self.late += 1;
+            return;
+        }
+
+        self.sequence += 1;
+        self.heap.push(Reverse(Entry {
+            key: (position.0, position.1, self.sequence),
+            arrival,
+            packet,
+        }));
+    }
+
+    /// Release the next packet, if the buffer is full or a packet has been held for too long.
+    pub fn pop(&mut self, now: Instant) -> Option<Packet> {
+        if let Some(packet) = self.ready.pop_front() {
+            return Some(packet);
+        }
+
+        let expired = self.deadline().is_some_and(|deadline| deadline <= now);
+
+        if self.heap.len() > self.capacity || expired {
+            self.release()
+        } else {
+            None
+        }
+    }
+
+    /// Release the next packet, even if the buffer is not full.
+    pub fn flush(&mut self) -> Option<Packet> {
+        self.ready.pop_front().or_else(|| self.release())
+    }
+
+    /// Returns the time at which the packet that has been held the longest must be released.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.heap
+            .iter()
+            .map(|Reverse(entry)| entry.arrival)
+            .min()
+            .map(|arrival| arrival + self.max_delay)
+    }
+
+    /// Returns the number of packets that arrived too late, and have been dropped.
+    pub fn late(&self) -> u64 {
+        self.late
+    }
+
+    fn release(&mut self) -> Option<Packet> {
+        let Reverse(entry) = self.heap.pop()?;
+        self.released = Some((entry.key.0, entry.key.1));
+
+        Some(entry.packet)
+    }
+}
+
+/// Stream adapter that puts packets back into order
+///
+/// The adapter is created by `PacketStreamExt::reorder`. A timer releases packets that have been
+/// held for too long while the underlying stream is idle. The packets that are still in the buffer
+/// are released when the underlying stream ends.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct Reorder<S> {
+    stream: S,
+    buffer: ReorderBuffer,
+    timer: Option<Pin<Box<Sleep>>>,
+    finished: bool,
+}
+
+#[cfg(feature = "net")]
+impl<S> Reorder<S> {
+    pub(crate) fn new(stream: S, capacity: usize, max_delay: Duration) -> Self {
+        Reorder {
+            stream,
+            buffer: ReorderBuffer::new(capacity, max_delay),
+            timer: None,
+            finished: false,
+        }
+    }
+
+    /// Returns the buffer of the adapter.
+    pub fn buffer(&self) -> &ReorderBuffer {
+        &self.buffer
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for Reorder<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = Packet;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if this.finished {
+                return Poll::Ready(this.buffer.flush());
+            }
+
+            if let Some(packet) = this.buffer.pop(Instant::now()) {
+                return Poll::Ready(Some(packet));
+            }
+
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => this.buffer.push(packet, Instant::now()),
+                Poll::Ready(None) => this.finished = true,
+                Poll::Pending => {
+                    let deadline = match this.buffer.deadline() {
+                        Some(deadline) => tokio::time::Instant::from_std(deadline),
+                        None => return Poll::Pending,
+                    };
+
+                    let timer = this
+                        .timer
+                        .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+                    timer.as_mut().reset(deadline);
+
+                    if timer.as_mut().poll(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    #[cfg(feature = "net")]
+    use tokio_stream::StreamExt;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+    use crate::packet::Packet;
+    use crate::stream::reorder::ReorderBuffer;
+    #[cfg(feature = "net")]
+    use crate::stream::PacketStreamExt;
+
+    const DELAY: Duration = Duration::from_secs(60);
+
+    fn frame(packet: Option<Packet>) -> Option<u32> {
+        packet.map(|packet| packet.header().frame_identifier())
+    }
+
+    #[test]
+    fn pop_in_order() {
+        let now = Instant::now();
+        let mut buffer = ReorderBuffer::new(2, DELAY);
+
+        buffer.push(
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 2),
+                Vec::new(),
+            ),
+            now,
+        );
+        buffer.push(
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 1),
+                Vec::new(),
+            ),
+            now,
+        );
+        assert_eq!(None, frame(buffer.pop(now)));
+
+        buffer.push(
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 3),
+                Vec::new(),
+            ),
+            now,
+        );
+        assert_eq!(Some(1), frame(buffer.pop(now)));
+        assert_eq!(None, frame(buffer.pop(now)));
+
+        assert_eq!(Some(2), frame(buffer.flush()));
+        assert_eq!(Some(3), frame(buffer.flush()));
+        assert_eq!(None, frame(buffer.flush()));
+    }
+
+    #[test]
+    fn push_late_packet() {
+        let now = Instant::now();
+        let mut buffer = ReorderBuffer::new(0, DELAY);
+
+        buffer.push(
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 2),
+                Vec::new(),
+            ),
+            now,
+        );
+        assert_eq!(Some(2), frame(buffer.pop(now)));
+
+        buffer.push(
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 1),
+                Vec::new(),
+            ),
+            now,
+        );
+        assert_eq!(None, frame(buffer.pop(now)));
+        assert_eq!(1, buffer.late());
+    }
+
+    #[test]
+    fn push_new_session() {
+        let now = Instant::now();
+        let mut buffer = ReorderBuffer::new(4, DELAY);
+
+        buffer.push(
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 100),
+                Vec::new(),
+            ),
+            now,
+        );
+        buffer.push(
+            lap_packet(
+                header(PacketType::Lap, 2, Duration::default(), 1),
+                Vec::new(),
+            ),
+            now,
+        );
+
+        assert_eq!(Some(100), frame(buffer.pop(now)));
+        assert_eq!(None, frame(buffer.pop(now)));
+        assert_eq!(Some(1), frame(buffer.flush()));
+    }
+
+    #[test]
+    fn pop_expired_packet() {
+        let now = Instant::now();
+        let mut buffer = ReorderBuffer::new(4, Duration::from_millis(50));
+
+        buffer.push(
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 2),
+                Vec::new(),
+            ),
+            now,
+        );
+        buffer.push(
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 1),
+                Vec::new(),
+            ),
+            now + Duration::from_millis(20),
+        );
+
+        assert_eq!(Some(now + Duration::from_millis(50)), buffer.deadline());
+        assert_eq!(None, frame(buffer.pop(now + Duration::from_millis(49))));
+
+        let later = now + Duration::from_millis(50);
+        assert_eq!(Some(1), frame(buffer.pop(later)));
+        assert_eq!(Some(2), frame(buffer.pop(later)));
+        assert_eq!(None, buffer.deadline());
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn reorder_stream() {
+        let packets = vec![
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 1),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 3),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 2),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 5),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 4),
+                Vec::new(),
+            ),
+        ];
+
+        let frames: Vec<u32> = tokio_stream::iter(packets)
+            .reorder(2, DELAY)
+            .map(|packet| packet.header().frame_identifier())
+            .collect()
+            .await;
+
+        assert_eq!(vec![1, 2, 3, 4, 5], frames);
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn reorder_stream_releases_held_packet() {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let stream = tokio_stream::wrappers::UnboundedReceiverStream::new(receiver);
+        let mut stream = stream.reorder(8, Duration::from_millis(10));
+
+        sender
+            .send(lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 1),
+                Vec::new(),
+            ))
+            .unwrap();
+
+        let packet = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .unwrap();
+
+        assert_eq!(Some(1), frame(packet));
+    }
+}
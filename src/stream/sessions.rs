@@ -0,0 +1,205 @@
+//! Separate streams for each session
+//!
+//! A collector that runs for a whole race weekend receives the packets of practice, qualifying, and
+//! the race on the same socket. The `Sessions` adapter splits the stream by the session UID of the
+//! packets, and yields a `SessionStream` for each session. A session stream ends when the game
+//! announces the end of the session, or when the packets of another session start to arrive.
+//!
+//! The packets are distributed to the session streams while the `Sessions` adapter is polled. The
+//! adapter must therefore be polled continuously, for example by spawning a task for each session
+//! stream that it yields. Packets are buffered in the session streams without a limit until they
+//! are consumed.
+
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio_stream::Stream;
+
+use crate::packet::event::Event;
+use crate::packet::Packet;
+
+/// Stream of the packets of a single session
+///
+/// Session streams are created by the `Sessions` adapter.
+#[derive(Debug)]
+pub struct SessionStream {
+    session_uid: u64,
+    receiver: UnboundedReceiver<Packet>,
+}
+
+impl SessionStream {
+    /// Returns the unique identifier of the session.
+    pub fn session_uid(&self) -> u64 {
+        self.session_uid
+    }
+}
+
+impl Stream for SessionStream {
+    type Item = Packet;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// Stream adapter that yields a stream for each session
+///
+/// The adapter is created by `PacketStreamExt::sessions`. Packets of a session that has already
+/// ended are dropped.
+#[derive(Debug)]
+pub struct Sessions<S> {
+    stream: S,
+    current: Option<(u64, UnboundedSender<Packet>)>,
+    ended: HashSet<u64>,
+}
+
+impl<S> Sessions<S> {
+    pub(crate) fn new(stream: S) -> Self {
+        Sessions {
+            stream,
+            current: None,
+            ended: HashSet::new(),
+        }
+    }
+
+    /// Forward the packet to the stream of its session, and return the stream if it is new.
+    fn route(&mut self, packet: Packet) -> Option<SessionStream> {
+        let session_uid = packet.header().session_uid();
+
+        if self.ended.contains(&session_uid) {
+            return None;
+        }
+
+        let mut created = None;
+
+        if self.current.as_ref().map(|(uid, _)| *uid) != Some(session_uid) {
+            if let Some((uid, _sender)) = self.current.take() {
+                self.ended.insert(uid);
+            }
+
+            let (sender, receiver) = unbounded_channel();
+            self.current = Some((session_uid, sender));
+            created = Some(SessionStream {
+                session_uid,
+                receiver,
+            });
+        }
+
+        let session_ended = match &packet {
+            Packet::Event(event) => *event.event() == Event::SessionEnded,
+            _ => false,
+        };
+
+        if let Some((_, sender)) = &self.current {
+            // The packet is discarded if the application has dropped the session stream.
+            let _ = sender.send(packet);
+        }
+
+        if session_ended {
+            self.current = None;
+            self.ended.insert(session_uid);
+        }
+
+        created
+    }
+}
+
+impl<S> Stream for Sessions<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = SessionStream;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    if let Some(session) = this.route(packet) {
+                        return Poll::Ready(Some(session));
+                    }
+                }
+                Poll::Ready(None) => {
+                    this.current = None;
+                    return Poll::Ready(None);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio_stream::StreamExt;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::event::{Event, EventPacket};
+    use crate::packet::header::PacketType;
+    use crate::packet::Packet;
+    use crate::stream::PacketStreamExt;
+
+    fn session_ended(session_uid: u64, frame: u32) -> Packet {
+        Packet::Event(EventPacket::new(
+            header(PacketType::Event, session_uid, Duration::default(), frame),
+            Event::SessionEnded,
+        ))
+    }
+
+    #[tokio::test]
+    async fn split_stream_by_session() {
+        let packets = vec![
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 1),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 2),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 2, Duration::default(), 1),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 1, Duration::default(), 3),
+                Vec::new(),
+            ),
+            lap_packet(
+                header(PacketType::Lap, 2, Duration::default(), 2),
+                Vec::new(),
+            ),
+            session_ended(2, 3),
+            lap_packet(
+                header(PacketType::Lap, 2, Duration::default(), 4),
+                Vec::new(),
+            ),
+        ];
+
+        let mut sessions = tokio_stream::iter(packets).sessions();
+
+        let mut first = sessions.next().await.unwrap();
+        let mut second = sessions.next().await.unwrap();
+        assert!(sessions.next().await.is_none());
+
+        assert_eq!(1, first.session_uid());
+        assert_eq!(2, second.session_uid());
+
+        let frames: Vec<u32> = (&mut first)
+            .map(|packet| packet.header().frame_identifier())
+            .collect()
+            .await;
+        assert_eq!(vec![1, 2], frames);
+
+        let frames: Vec<u32> = (&mut second)
+            .map(|packet| packet.header().frame_identifier())
+            .collect()
+            .await;
+        assert_eq!(vec![1, 2, 3], frames);
+    }
+}
@@ -0,0 +1,213 @@
+//! Filtering of packets from a previous session
+//!
+//! When the player restarts a session, the game starts to send packets with a new session UID.
+//! Packets of the previous session can still be in flight, or held back by a relay, and arrive
+//! after the first packets of the new session. Consumers that reset their state when the session
+//! changes, like the `SessionTracker`, would then switch back to the previous session and reset
+//! themselves twice.
+//!
+//! The `StaleSessionFilter` drops the packets of the previous session for a grace window after a
+//! new session has started. The window is measured in the session time of the new session, so
+//! that captures are filtered the same way as live sessions. After the grace window, a packet of
+//! another session is treated as the start of a new session again.
+
+#[cfg(feature = "net")]
+use std::pin::Pin;
+#[cfg(feature = "net")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+#[cfg(feature = "net")]
+use tokio_stream::Stream;
+
+use crate::packet::Packet;
+
+/// Grace window that is used by default
+const DEFAULT_GRACE: Duration = Duration::from_secs(5);
+
+/// Filter that drops late packets of the previous session
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use f1_api::stream::stale::StaleSessionFilter;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut filter = StaleSessionFilter::new(Duration::from_secs(2));
+///
+/// for packet in packets().iter().filter(|packet| filter.accept(packet)) {
+///     println!("{:?}", packet.header());
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub struct StaleSessionFilter {
+    grace: Duration,
+    current: Option<u64>,
+    previous: Option<u64>,
+    started: Duration,
+    latest: Duration,
+    dropped: u64,
+}
+
+impl StaleSessionFilter {
+    /// Create a new filter with the given grace window.
+    pub fn new(grace: Duration) -> Self {
+        StaleSessionFilter {
+            grace,
+            current: None,
+            previous: None,
+            started: Duration::default(),
+            latest: Duration::default(),
+            dropped: 0,
+        }
+    }
+
+    /// Returns whether the packet should be passed through.
+    pub fn accept(&mut self, packet: &Packet) -> bool {
+        let header = packet.header();
+        let session_uid = header.session_uid();
+        let session_time = *header.session_time();
+
+        if self.current.is_none() || self.current == Some(session_uid) {
+            self.current = Some(session_uid);
+            self.latest = self.latest.max(session_time);
+            return true;
+        }
+
+        let in_grace = self.latest.saturating_sub(self.started) < self.grace;
+
+        if self.previous == Some(session_uid) && in_grace {
+            self.dropped += 1;
+            return false;
+        }
+
+        self.previous = self.current;
+        self.current = Some(session_uid);
+        self.started = session_time;
+        self.latest = session_time;
+
+        true
+    }
+
+    /// Returns the unique id of the session whose packets are passed through.
+    pub fn session_uid(&self) -> Option<u64> {
+        self.current
+    }
+
+    /// Returns the number of packets of the previous session that have been dropped.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+impl Default for StaleSessionFilter {
+    fn default() -> Self {
+        StaleSessionFilter::new(DEFAULT_GRACE)
+    }
+}
+
+/// Stream adapter that drops late packets of the previous session
+///
+/// The adapter is created by `PacketStreamExt::drop_stale_sessions`.
+#[cfg(feature = "net")]
+#[derive(Debug)]
+pub struct DropStaleSessions<S> {
+    stream: S,
+    filter: StaleSessionFilter,
+}
+
+#[cfg(feature = "net")]
+impl<S> DropStaleSessions<S> {
+    pub(crate) fn new(stream: S, filter: StaleSessionFilter) -> Self {
+        DropStaleSessions { stream, filter }
+    }
+
+    /// Returns the filter of the adapter.
+    pub fn filter(&self) -> &StaleSessionFilter {
+        &self.filter
+    }
+}
+
+#[cfg(feature = "net")]
+impl<S> Stream for DropStaleSessions<S>
+where
+    S: Stream<Item = Packet> + Unpin,
+{
+    type Item = Packet;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(packet)) => {
+                    if this.filter.accept(&packet) {
+                        return Poll::Ready(Some(packet));
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::header::PacketType;
+    use crate::stream::stale::StaleSessionFilter;
+
+    #[test]
+    fn accept_drops_previous_session_within_grace() {
+        let mut filter = StaleSessionFilter::new(Duration::from_secs(5));
+
+        assert!(filter.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs(300), 0),
+            Vec::new()
+        )));
+        assert!(filter.accept(&lap_packet(
+            header(PacketType::Lap, 2, Duration::default(), 0),
+            Vec::new()
+        )));
+        assert!(!filter.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs(301), 0),
+            Vec::new()
+        )));
+        assert!(filter.accept(&lap_packet(
+            header(PacketType::Lap, 2, Duration::from_secs(1), 0),
+            Vec::new()
+        )));
+
+        assert_eq!(Some(2), filter.session_uid());
+        assert_eq!(1, filter.dropped());
+    }
+
+    #[test]
+    fn accept_previous_session_after_grace() {
+        let mut filter = StaleSessionFilter::new(Duration::from_secs(5));
+
+        assert!(filter.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs(300), 0),
+            Vec::new()
+        )));
+        assert!(filter.accept(&lap_packet(
+            header(PacketType::Lap, 2, Duration::default(), 0),
+            Vec::new()
+        )));
+        assert!(filter.accept(&lap_packet(
+            header(PacketType::Lap, 2, Duration::from_secs(10), 0),
+            Vec::new()
+        )));
+        assert!(filter.accept(&lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs(310), 0),
+            Vec::new()
+        )));
+
+        assert_eq!(Some(1), filter.session_uid());
+    }
+}
@@ -0,0 +1,171 @@
+//! Keyed topics for publish/subscribe middleware
+//!
+//! Robotics-style setups distribute telemetry over middleware like zenoh or DDS, where many
+//! machines subscribe to exactly the data they need. These systems route messages by key, and
+//! subscribers select the keys they are interested in with wildcards. The `Topics` split each
+//! packet into messages with a key per session, packet type, and car:
+//!
+//! ```text
+//! f1/4242/telemetry/0
+//! f1/4242/telemetry/1
+//! f1/4242/session
+//! ```
+//!
+//! Packets with data for every car produce one message per car, while packets that describe the
+//! session as a whole produce a single message. A subscriber of `f1/*/telemetry/3` receives the
+//! telemetry of the fourth car in every session, and a subscriber of `f1/4242/**` every message of
+//! a session. The payload of each message is a JSON object with the fields of the header and the
+//! car, named like the columns of the flat files of the `export` module.
+//!
+//! The messages are independent of the transport, and can be handed to the publisher of any
+//! middleware. With the `zenoh` feature, the `ZenohSink` of the `zenoh` module publishes them to
+//! zenoh.
+
+use derive_new::new;
+use getset::Getters;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::export::json::json_value;
+use crate::export::table;
+use crate::export::Value;
+use crate::packet::Packet;
+
+/// Name of the column that identifies the car of a row
+const VEHICLE_INDEX: &str = "vehicle_index";
+
+/// Message with the data of a single car or session
+#[derive(new, Debug, Getters, PartialEq, Clone)]
+pub struct Message {
+    /// Returns the key of the message.
+    #[getset(get = "pub")]
+    key: String,
+
+    /// Returns the payload of the message as a JSON object.
+    #[getset(get = "pub")]
+    payload: Vec<u8>,
+}
+
+/// Builder of the keys and messages for packets
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::topic::Topics;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let topics = Topics::new("f1");
+///
+/// for packet in packets() {
+///     for message in topics.messages(&packet) {
+///         println!("{}: {} bytes", message.key(), message.payload().len());
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Topics {
+    prefix: String,
+    separator: char,
+}
+
+impl Topics {
+    /// Create topics under the given prefix, with segments separated by `/`.
+    pub fn new<S: Into<String>>(prefix: S) -> Self {
+        Topics {
+            prefix: prefix.into(),
+            separator: '/',
+        }
+    }
+
+    /// Separate the segments of the keys with the given character.
+    ///
+    /// Middleware like NATS separates the segments of its subjects with `.` instead of `/`.
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Returns the key for a packet, and optionally a car.
+    pub fn key(&self, packet: &Packet, vehicle_index: Option<usize>) -> String {
+        let packet_type = table::name(table::packet_type(packet));
+        let session_uid = packet.header().session_uid().to_string();
+
+        let mut segments = vec![self.prefix.as_str(), session_uid.as_str(), packet_type];
+
+        let vehicle_index = vehicle_index.map(|index| index.to_string());
+        if let Some(index) = &vehicle_index {
+            segments.push(index);
+        }
+
+        segments
+            .iter()
+            .filter(|segment| !segment.is_empty())
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(&self.separator.to_string())
+    }
+
+    /// Split the packet into messages with a key per car, or a single key for the session.
+    pub fn messages(&self, packet: &Packet) -> Vec<Message> {
+        let columns = table::columns(table::packet_type(packet));
+        let vehicle_column = columns
+            .iter()
+            .position(|column| column.name == VEHICLE_INDEX);
+
+        table::rows(packet)
+            .iter()
+            .map(|row| {
+                let vehicle_index = vehicle_column.and_then(|index| match row.get(index) {
+                    Some(Value::Int(vehicle_index)) => Some(*vehicle_index as usize),
+                    _ => None,
+                });
+
+                let object: Map<String, JsonValue> = columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, value)| (column.name.clone(), json_value(value)))
+                    .collect();
+
+                Message::new(
+                    self.key(packet, vehicle_index),
+                    JsonValue::Object(object).to_string().into_bytes(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::topic::Topics;
+
+    #[test]
+    fn messages_per_car() {
+        let packet = &fixtures::packets(ApiSpec::Nineteen, PacketType::Telemetry).unwrap()[0];
+        let session_uid = packet.header().session_uid();
+
+        let messages = Topics::new("f1").messages(packet);
+
+        assert_eq!(20, messages.len());
+        assert_eq!(
+            &format!("f1/{}/telemetry/3", session_uid),
+            messages[3].key()
+        );
+
+        let json: serde_json::Value = serde_json::from_slice(messages[3].payload()).unwrap();
+        assert_eq!(3, json["vehicle_index"]);
+    }
+
+    #[test]
+    fn messages_per_session() {
+        let packet = &fixtures::packets(ApiSpec::Nineteen, PacketType::Session).unwrap()[0];
+        let session_uid = packet.header().session_uid();
+
+        let messages = Topics::new("f1").separator('.').messages(packet);
+
+        assert_eq!(1, messages.len());
+        assert_eq!(&format!("f1.{}.session", session_uid), messages[0].key());
+    }
+}
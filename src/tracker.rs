@@ -0,0 +1,732 @@
+//! Live model of the current session
+//!
+//! The packets sent by the F1 games are snapshots of the current state of the session. Many
+//! applications need more than that, for example the times of every lap a car has driven, or the
+//! gap between two cars. The `SessionTracker` consumes the packets of a session, and maintains a
+//! model of the session that accumulates this history.
+
+use std::time::Duration;
+
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+
+use crate::packet::event::EventPacket;
+use crate::packet::header::Header;
+use crate::packet::lap::{Lap, LapPacket, PitStatus, ResultStatus};
+use crate::packet::motion::{Motion, MotionPacket};
+use crate::packet::participants::ParticipantsPacket;
+use crate::packet::session::{Session, SessionPacket};
+use crate::packet::setup::CarSetupPacket;
+use crate::packet::status::{CarStatus, CarStatusPacket, VisualTyreCompound};
+use crate::packet::telemetry::{Telemetry, TelemetryPacket};
+use crate::packet::Packet;
+use crate::types::VehicleIndex;
+
+#[cfg(feature = "json")]
+pub mod snapshot;
+
+/// Minimum distance in meters between two samples that are used to calculate gaps
+const TRACE_RESOLUTION: f32 = 10.0;
+
+/// Drop in the average tyre wear in percent that indicates a change of tyres
+const TYRE_CHANGE_THRESHOLD: f32 = 5.0;
+
+/// A lap that has been completed by a car
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, PartialOrd, Default)]
+#[allow(clippy::too_many_arguments)]
+pub struct LapRecord {
+    /// Returns the number of the lap.
+    #[getset(get_copy = "pub")]
+    lap_number: u8,
+
+    /// Returns the time of the lap.
+    #[getset(get = "pub")]
+    lap_time: Duration,
+
+    /// Returns the time spent in sector 1.
+    #[getset(get = "pub")]
+    sector1_time: Duration,
+
+    /// Returns the time spent in sector 2.
+    #[getset(get = "pub")]
+    sector2_time: Duration,
+
+    /// Returns the time spent in sector 3.
+    #[getset(get = "pub")]
+    sector3_time: Duration,
+
+    /// Returns whether the lap was valid.
+    #[getset(get_copy = "pub")]
+    is_valid: bool,
+
+    /// Returns the position of the car at the end of the lap.
+    #[getset(get_copy = "pub")]
+    position: u8,
+
+    /// Returns the tyre compound the lap was driven on, if known.
+    #[getset(get_copy = "pub")]
+    tyre_compound: Option<VisualTyreCompound>,
+
+    /// Returns whether the car entered the pit lane during the lap.
+    #[getset(get_copy = "pub")]
+    pit_stop: bool,
+
+    /// Returns the session time at which the lap was completed.
+    #[getset(get = "pub")]
+    completed_at: Duration,
+}
+
+/// A stint on a single set of tyres
+#[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+pub struct Stint {
+    /// Returns the tyre compound of the stint.
+    #[getset(get_copy = "pub")]
+    compound: VisualTyreCompound,
+
+    /// Returns the lap on which the stint started.
+    #[getset(get_copy = "pub")]
+    start_lap: u8,
+
+    /// Returns the last lap of the stint, or `None` if the stint is still ongoing.
+    #[getset(get_copy = "pub")]
+    end_lap: Option<u8>,
+}
+
+/// History of a car in the session
+///
+/// The history collects the laps a car has completed, the stints it has driven, and the number of
+/// pit stops it has made.
+#[derive(Debug, Getters, CopyGetters, PartialEq, Clone, PartialOrd, Default)]
+pub struct CarHistory {
+    /// Returns the laps the car has completed.
+    #[getset(get = "pub")]
+    laps: Vec<LapRecord>,
+
+    /// Returns the stints the car has driven.
+    #[getset(get = "pub")]
+    stints: Vec<Stint>,
+
+    /// Returns the number of pit stops the car has made.
+    #[getset(get_copy = "pub")]
+    pit_stops: u8,
+
+    lap: Option<Lap>,
+    lap_valid: bool,
+    lap_pit_stop: bool,
+    tyre_wear: Option<f32>,
+    trace: Vec<(f32, Duration)>,
+}
+
+impl CarHistory {
+    /// Returns the best valid lap of the car.
+    pub fn best_lap(&self) -> Option<&LapRecord> {
+        self.laps
+            .iter()
+            .filter(|lap| lap.is_valid())
+            .min_by_key(|lap| *lap.lap_time())
+    }
+
+    /// Returns the current stint of the car.
+    pub fn current_stint(&self) -> Option<&Stint> {
+        self.stints.last()
+    }
+
+    /// Returns the session time at which the car passed the given total distance.
+    ///
+    /// The time is interpolated between the two samples closest to the distance. `None` is returned
+    /// if the car has not yet passed the distance.
+    pub fn time_at_distance(&self, distance: f32) -> Option<Duration> {
+        let index = self.trace.iter().position(|(d, _)| *d >= distance)?;
+        let (after_distance, after_time) = self.trace[index];
+
+        if index == 0 || after_distance <= distance {
+            return Some(after_time);
+        }
+
+        let (before_distance, before_time) = self.trace[index - 1];
+        let ratio = (distance - before_distance) / (after_distance - before_distance);
+
+        Some(before_time + (after_time - before_time).mul_f32(ratio))
+    }
+
+    fn update_lap(&mut self, header: &Header, lap: &Lap) {
+        if let Some(previous) = self.lap {
+            if lap.current_lap_number() > previous.current_lap_number()
+                && previous.current_lap_number() > 0
+            {
+                let sector1_time = *previous.sector1_time();
+                let sector2_time = *previous.sector2_time();
+                let lap_time = *lap.last_lap_time();
+
+                self.laps.push(LapRecord::new(
+                    previous.current_lap_number(),
+                    lap_time,
+                    sector1_time,
+                    sector2_time,
+                    lap_time
+                        .checked_sub(sector1_time + sector2_time)
+                        .unwrap_or_default(),
+                    self.lap_valid && previous.is_valid_lap(),
+                    lap.position(),
+                    self.current_stint().map(|stint| stint.compound()),
+                    self.lap_pit_stop,
+                    *header.session_time(),
+                ));
+
+                self.lap_valid = true;
+                self.lap_pit_stop = false;
+            } else {
+                self.lap_valid &= lap.is_valid_lap();
+            }
+
+            if previous.pit_status() == PitStatus::None && lap.pit_status() != PitStatus::None {
+                self.pit_stops += 1;
+                self.lap_pit_stop = true;
+            }
+        } else {
+            self.lap_valid = lap.is_valid_lap();
+        }
+
+        let distance = lap.total_distance();
+        let sample = self.trace.last().map(|(d, _)| *d);
+
+        if sample.is_none_or(|d| distance >= d + TRACE_RESOLUTION) {
+            self.trace.push((distance, *header.session_time()));
+        }
+
+        self.lap = Some(*lap);
+    }
+
+    fn update_status(&mut self, status: &CarStatus) {
+        let lap_number = self.lap.map(|lap| lap.current_lap_number()).unwrap_or(0);
+        let compound = status.visual_tyre_compound();
+
+        let tyre_wear = status.tyre_wear();
+        let wear = (tyre_wear.front_left() as f32
+            + tyre_wear.front_right() as f32
+            + tyre_wear.rear_left() as f32
+            + tyre_wear.rear_right() as f32)
+            / 4.0;
+
+        let new_tyres = match (self.current_stint(), self.tyre_wear) {
+            (None, _) => true,
+            (Some(stint), previous_wear) => {
+                stint.compound() != compound
+                    || previous_wear.is_some_and(|w| w - wear > TYRE_CHANGE_THRESHOLD)
+            }
+        };
+
+        if new_tyres {
+            if let Some(stint) = self.stints.last_mut() {
+                stint.end_lap = Some(lap_number.saturating_sub(1).max(stint.start_lap));
+            }
+
+            self.stints
+                .push(Stint::new(compound, lap_number.max(1), None));
+        }
+
+        self.tyre_wear = Some(wear);
+    }
+}
+
+/// Entry in the classification of a session
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Clone, PartialOrd)]
+#[allow(clippy::too_many_arguments)]
+pub struct ClassificationEntry {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the position of the car.
+    #[getset(get_copy = "pub")]
+    position: u8,
+
+    /// Returns the name of the driver, if the participants are known.
+    #[getset(get = "pub")]
+    name: Option<String>,
+
+    /// Returns the time of the last lap.
+    #[getset(get = "pub")]
+    last_lap_time: Duration,
+
+    /// Returns the time of the best lap.
+    #[getset(get = "pub")]
+    best_lap_time: Duration,
+
+    /// Returns the gap to the leader.
+    ///
+    /// In races, the gap is the time difference on track. In all other sessions, the gap is the
+    /// difference between the best lap times. `None` is returned for the leader, and if the gap
+    /// cannot be determined.
+    #[getset(get = "pub")]
+    gap: Option<Duration>,
+
+    /// Returns the current tyre compound, if known.
+    #[getset(get_copy = "pub")]
+    tyre_compound: Option<VisualTyreCompound>,
+
+    /// Returns the number of pit stops.
+    #[getset(get_copy = "pub")]
+    pit_stops: u8,
+
+    /// Returns the status of the result.
+    #[getset(get_copy = "pub")]
+    result_status: ResultStatus,
+}
+
+/// Latest state of a single car
+///
+/// The game sends motion, telemetry, status, and lap data for all cars in separate packets, and at
+/// different rates. A snapshot joins the most recent data of each kind for a single car, so that
+/// consumers do not have to correlate the packets themselves. Each part is `None` until the first
+/// packet of its kind has been received.
+#[derive(new, Debug, Getters, CopyGetters, PartialEq, Copy, Clone, PartialOrd)]
+pub struct CarSnapshot {
+    /// Returns the index of the car.
+    #[getset(get_copy = "pub")]
+    vehicle_index: VehicleIndex,
+
+    /// Returns the frame identifier of the most recent packet in the snapshot.
+    #[getset(get_copy = "pub")]
+    frame_identifier: u32,
+
+    /// Returns the session time of the most recent packet in the snapshot.
+    #[getset(get = "pub")]
+    session_time: Duration,
+
+    /// Returns the motion data of the car.
+    #[getset(get = "pub")]
+    motion: Option<Motion>,
+
+    /// Returns the telemetry data of the car.
+    #[getset(get = "pub")]
+    telemetry: Option<Telemetry>,
+
+    /// Returns the status of the car.
+    #[getset(get = "pub")]
+    status: Option<CarStatus>,
+
+    /// Returns the lap data of the car.
+    #[getset(get = "pub")]
+    lap: Option<Lap>,
+}
+
+/// Model of the current session
+///
+/// The session tracker consumes the packets of a session and keeps the latest packet of each type
+/// as well as the history of every car. When a packet of a new session is received, the model is
+/// reset.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::tracker::SessionTracker;
+/// # use f1_api::packet::Packet;
+/// # fn packets() -> Vec<Packet> { Vec::new() }
+///
+/// let mut tracker = SessionTracker::default();
+///
+/// for packet in packets() {
+///     tracker.update(&packet);
+/// }
+///
+/// for entry in tracker.classification() {
+///     println!("P{} car #{}", entry.position(), entry.vehicle_index());
+/// }
+/// ```
+#[derive(Debug, Getters, CopyGetters, PartialEq, Clone, PartialOrd, Default)]
+pub struct SessionTracker {
+    /// Returns the unique id of the tracked session.
+    #[getset(get_copy = "pub")]
+    session_uid: Option<u64>,
+
+    /// Returns the latest session packet.
+    #[getset(get = "pub")]
+    session: Option<SessionPacket>,
+
+    /// Returns the latest participants packet.
+    #[getset(get = "pub")]
+    participants: Option<ParticipantsPacket>,
+
+    /// Returns the latest lap packet.
+    #[getset(get = "pub")]
+    laps: Option<LapPacket>,
+
+    /// Returns the latest motion packet.
+    #[getset(get = "pub")]
+    motion: Option<MotionPacket>,
+
+    /// Returns the latest car setup packet.
+    #[getset(get = "pub")]
+    setups: Option<CarSetupPacket>,
+
+    /// Returns the latest car status packet.
+    #[getset(get = "pub")]
+    statuses: Option<CarStatusPacket>,
+
+    /// Returns the latest telemetry packet.
+    #[getset(get = "pub")]
+    telemetry: Option<TelemetryPacket>,
+
+    /// Returns the events that occurred in the session.
+    #[getset(get = "pub")]
+    events: Vec<EventPacket>,
+
+    /// Returns the history of each car in the session.
+    #[getset(get = "pub")]
+    cars: Vec<CarHistory>,
+}
+
+impl SessionTracker {
+    /// Update the model with a new packet.
+    pub fn update(&mut self, packet: &Packet) {
+        let header = packet.header();
+
+        if self.session_uid != Some(header.session_uid()) {
+            *self = SessionTracker {
+                session_uid: Some(header.session_uid()),
+                ..Default::default()
+            };
+        }
+
+        match packet.clone() {
+            Packet::Event(packet) => self.events.push(packet),
+            Packet::Lap(packet) => {
+                self.ensure_cars(packet.laps().len());
+
+                for (car, lap) in self.cars.iter_mut().zip(packet.laps()) {
+                    car.update_lap(packet.header(), lap);
+                }
+
+                self.laps = Some(packet);
+            }
+            Packet::Motion(packet) => self.motion = Some(packet),
+            Packet::Participants(packet) => self.participants = Some(packet),
+            Packet::Session(packet) => self.session = Some(packet),
+            Packet::Setup(packet) => self.setups = Some(packet),
+            Packet::Status(packet) => {
+                self.ensure_cars(packet.statuses().len());
+
+                for (car, status) in self.cars.iter_mut().zip(packet.statuses()) {
+                    car.update_status(status);
+                }
+
+                self.statuses = Some(packet);
+            }
+            Packet::Telemetry(packet) => self.telemetry = Some(packet),
+        }
+    }
+
+    /// Returns the history of the given car.
+    pub fn car(&self, vehicle_index: VehicleIndex) -> Option<&CarHistory> {
+        self.cars.get(vehicle_index as usize)
+    }
+
+    /// Returns the name of the given car's driver, if the participants are known.
+    pub fn name(&self, vehicle_index: VehicleIndex) -> Option<&str> {
+        self.participants
+            .as_ref()?
+            .car(vehicle_index)
+            .map(|participant| participant.name().as_str())
+    }
+
+    /// Returns the latest motion, telemetry, status, and lap data of the given car.
+    ///
+    /// `None` is returned if none of these packets has been received for the car.
+    pub fn snapshot(&self, vehicle_index: VehicleIndex) -> Option<CarSnapshot> {
+        let motion = self.motion.as_ref();
+        let telemetry = self.telemetry.as_ref();
+        let statuses = self.statuses.as_ref();
+        let laps = self.laps.as_ref();
+
+        let snapshot = CarSnapshot::new(
+            vehicle_index,
+            0,
+            Duration::default(),
+            motion.and_then(|packet| packet.car(vehicle_index)).copied(),
+            telemetry
+                .and_then(|packet| packet.car(vehicle_index))
+                .copied(),
+            statuses
+                .and_then(|packet| packet.car(vehicle_index))
+                .copied(),
+            laps.and_then(|packet| packet.car(vehicle_index)).copied(),
+        );
+
+        let headers = [
+            motion
+                .filter(|_| snapshot.motion.is_some())
+                .map(|packet| packet.header()),
+            telemetry
+                .filter(|_| snapshot.telemetry.is_some())
+                .map(|packet| packet.header()),
+            statuses
+                .filter(|_| snapshot.status.is_some())
+                .map(|packet| packet.header()),
+            laps.filter(|_| snapshot.lap.is_some())
+                .map(|packet| packet.header()),
+        ];
+
+        let latest = headers
+            .iter()
+            .flatten()
+            .max_by_key(|header| (*header.session_time(), header.frame_identifier()))?;
+
+        Some(CarSnapshot {
+            frame_identifier: latest.frame_identifier(),
+            session_time: *latest.session_time(),
+            ..snapshot
+        })
+    }
+
+    /// Returns the current classification of the session, ordered by position.
+    ///
+    /// Only cars that take part in the session are included in the classification. The
+    /// classification is empty until the first lap packet has been received.
+    pub fn classification(&self) -> Vec<ClassificationEntry> {
+        let laps = match &self.laps {
+            Some(laps) => laps,
+            None => return Vec::new(),
+        };
+
+        let is_participant = |index: VehicleIndex| match &self.participants {
+            Some(participants) => participants.participants().is_active(index),
+            None => true,
+        };
+
+        let mut entries: Vec<(VehicleIndex, &Lap)> = laps
+            .laps()
+            .iter_active()
+            .filter(|(index, lap)| is_participant(*index) && lap.position() > 0)
+            .collect();
+
+        entries.sort_by_key(|(_, lap)| lap.position());
+
+        let leader = entries.first().map(|(index, lap)| (*index, **lap));
+        let is_race = self
+            .session
+            .as_ref()
+            .map(|session| matches!(session.session_type(), Session::Race | Session::Race2))
+            .unwrap_or(true);
+
+        entries
+            .into_iter()
+            .map(|(index, lap)| {
+                let history = self.car(index);
+
+                let gap = match leader {
+                    Some((leader_index, _)) if leader_index == index => None,
+                    Some((leader_index, leader_lap)) => {
+                        if is_race {
+                            self.car(leader_index)
+                                .and_then(|leader| leader.time_at_distance(lap.total_distance()))
+                                .and_then(|time| laps.header().session_time().checked_sub(time))
+                        } else {
+                            lap.best_lap_time()
+                                .checked_sub(*leader_lap.best_lap_time())
+                                .filter(|_| !lap.best_lap_time().is_zero())
+                        }
+                    }
+                    None => None,
+                };
+
+                ClassificationEntry::new(
+                    index,
+                    lap.position(),
+                    self.name(index).map(String::from),
+                    *lap.last_lap_time(),
+                    *lap.best_lap_time(),
+                    gap,
+                    history
+                        .and_then(|history| history.current_stint())
+                        .map(|stint| stint.compound()),
+                    history.map(|history| history.pit_stops()).unwrap_or(0),
+                    lap.result_status(),
+                )
+            })
+            .collect()
+    }
+
+    fn ensure_cars(&mut self, count: usize) {
+        if self.cars.len() < count {
+            self.cars.resize(count, CarHistory::default());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, in_position, lap, lap_packet, with_times};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{Lap, PitStatus};
+    use crate::packet::status::{CarStatus, CarStatusPacket, VisualTyreCompound};
+    use crate::packet::Packet;
+    use crate::tracker::SessionTracker;
+
+    fn timed_lap(lap_number: u8, distance: f32, position: u8, pit_status: PitStatus) -> Lap {
+        let lap = with_times(
+            lap(lap_number, distance),
+            Duration::from_secs(90),
+            Duration::from_secs(10),
+            Duration::from_secs(30),
+            Duration::from_secs(30),
+        );
+
+        in_position(lap, position, pit_status)
+    }
+
+    fn laps_at(seconds: f32, laps: Vec<Lap>) -> Packet {
+        lap_packet(
+            header(PacketType::Lap, 1, Duration::from_secs_f32(seconds), 0),
+            laps,
+        )
+    }
+
+    #[test]
+    fn update_records_completed_laps() {
+        let mut tracker = SessionTracker::default();
+
+        tracker.update(&laps_at(1.0, vec![timed_lap(1, 100.0, 1, PitStatus::None)]));
+        tracker.update(&laps_at(
+            2.0,
+            vec![timed_lap(2, 5100.0, 1, PitStatus::None)],
+        ));
+
+        let laps = tracker.car(0).unwrap().laps();
+
+        assert_eq!(1, laps.len());
+        assert_eq!(1, laps[0].lap_number());
+        assert_eq!(90, laps[0].lap_time().as_secs());
+        assert_eq!(30, laps[0].sector3_time().as_secs());
+    }
+
+    #[test]
+    fn update_counts_pit_stops() {
+        let mut tracker = SessionTracker::default();
+
+        tracker.update(&laps_at(1.0, vec![timed_lap(1, 100.0, 1, PitStatus::None)]));
+        tracker.update(&laps_at(
+            2.0,
+            vec![timed_lap(1, 200.0, 1, PitStatus::Pitting)],
+        ));
+        tracker.update(&laps_at(
+            3.0,
+            vec![timed_lap(1, 300.0, 1, PitStatus::InPits)],
+        ));
+        tracker.update(&laps_at(4.0, vec![timed_lap(1, 400.0, 1, PitStatus::None)]));
+
+        assert_eq!(1, tracker.car(0).unwrap().pit_stops());
+    }
+
+    #[test]
+    fn update_tracks_stints() {
+        let mut tracker = SessionTracker::default();
+        let status = |compound| {
+            Packet::Status(CarStatusPacket::new(
+                header(PacketType::Status, 1, Duration::from_secs(1), 0),
+                vec![CarStatus::new(
+                    Default::default(),
+                    false,
+                    Default::default(),
+                    0,
+                    false,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0,
+                    0,
+                    0,
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                    compound,
+                    Default::default(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    Default::default(),
+                    0.0,
+                    Default::default(),
+                    0.0,
+                    0.0,
+                    0.0,
+                )]
+                .into(),
+            ))
+        };
+
+        tracker.update(&laps_at(1.0, vec![timed_lap(1, 100.0, 1, PitStatus::None)]));
+        tracker.update(&status(VisualTyreCompound::F1Soft));
+        tracker.update(&laps_at(2.0, vec![timed_lap(5, 100.0, 1, PitStatus::None)]));
+        tracker.update(&status(VisualTyreCompound::F1Medium));
+
+        let stints = tracker.car(0).unwrap().stints();
+
+        assert_eq!(2, stints.len());
+        assert_eq!(Some(4), stints[0].end_lap());
+        assert_eq!(VisualTyreCompound::F1Medium, stints[1].compound());
+        assert_eq!(5, stints[1].start_lap());
+    }
+
+    #[test]
+    fn snapshot_joins_latest_data_of_car() {
+        let mut tracker = SessionTracker::default();
+
+        assert_eq!(None, tracker.snapshot(0));
+
+        tracker.update(&laps_at(1.0, vec![timed_lap(1, 100.0, 1, PitStatus::None)]));
+        tracker.update(&laps_at(2.0, vec![timed_lap(1, 200.0, 1, PitStatus::None)]));
+
+        let snapshot = tracker.snapshot(0).unwrap();
+
+        assert_eq!(Duration::from_secs(2), *snapshot.session_time());
+        assert_eq!(Some(200.0), snapshot.lap().map(|lap| lap.lap_distance()));
+        assert_eq!(None, *snapshot.telemetry());
+        assert_eq!(None, tracker.snapshot(1));
+    }
+
+    #[test]
+    fn update_resets_on_new_session() {
+        let mut tracker = SessionTracker::default();
+
+        tracker.update(&laps_at(1.0, vec![timed_lap(1, 100.0, 1, PitStatus::None)]));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 2, Duration::from_secs(1), 0),
+            Vec::new(),
+        ));
+
+        assert_eq!(Some(2), tracker.session_uid());
+        assert!(tracker.cars().is_empty());
+    }
+
+    #[test]
+    fn classification_with_gaps() {
+        let mut tracker = SessionTracker::default();
+
+        tracker.update(&laps_at(
+            10.0,
+            vec![
+                timed_lap(1, 1000.0, 1, PitStatus::None),
+                timed_lap(1, 900.0, 2, PitStatus::None),
+            ],
+        ));
+        tracker.update(&laps_at(
+            12.0,
+            vec![
+                timed_lap(1, 1100.0, 1, PitStatus::None),
+                timed_lap(1, 1000.0, 2, PitStatus::None),
+            ],
+        ));
+
+        let classification = tracker.classification();
+
+        assert_eq!(2, classification.len());
+        assert_eq!(0, classification[0].vehicle_index());
+        assert_eq!(None, *classification[0].gap());
+        assert_eq!(Some(Duration::from_secs(2)), *classification[1].gap());
+    }
+}
@@ -0,0 +1,480 @@
+//! Snapshots of the session model
+//!
+//! The `SessionTracker` accumulates the history of a session over its whole length. When the
+//! application that runs the tracker crashes or is restarted in the middle of a race, this history
+//! would be lost, since the game does not send it again. A snapshot saves the accumulated state of
+//! the tracker to disk, so that it can be restored on startup.
+//!
+//! Snapshots contain the history of every car and the events of the session. The latest packet of
+//! each type is not part of a snapshot, since the game sends these again within seconds. The lap
+//! that a car is driving when the snapshot is taken is recorded normally once the car crosses the
+//! line, but its validity is only tracked from the moment the tracker is restored.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use f1_api::tracker::snapshot::{restore, save};
+//! use f1_api::tracker::SessionTracker;
+//! # use f1_api::packet::Packet;
+//! # fn packets() -> Vec<Packet> { Vec::new() }
+//!
+//! let mut tracker = restore("session.json")?.unwrap_or_default();
+//!
+//! for packet in packets() {
+//!     tracker.update(&packet);
+//!
+//!     if packet.header().frame_identifier() % 600 == 0 {
+//!         save(&tracker, "session.json")?;
+//!     }
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::packet::event::{
+    Event, EventPacket, FastestLap, RaceWinner, Retirement, TeammateInPits,
+};
+use crate::packet::header::{ApiSpec, GameVersion, Header, PacketType};
+use crate::packet::status::VisualTyreCompound;
+use crate::tracker::{CarHistory, LapRecord, SessionTracker, Stint};
+use crate::types::VehicleIndex;
+
+/// Tyre compounds by the name they are stored with
+const COMPOUNDS: [VisualTyreCompound; 16] = [
+    VisualTyreCompound::ClassicDry,
+    VisualTyreCompound::ClassicWet,
+    VisualTyreCompound::F1HyperSoft,
+    VisualTyreCompound::F1UltraSoft,
+    VisualTyreCompound::F1SuperSoft,
+    VisualTyreCompound::F1Soft,
+    VisualTyreCompound::F1Medium,
+    VisualTyreCompound::F1Hard,
+    VisualTyreCompound::F1SuperHard,
+    VisualTyreCompound::F1Intermediate,
+    VisualTyreCompound::F1Wet,
+    VisualTyreCompound::F2SuperSoft,
+    VisualTyreCompound::F2Soft,
+    VisualTyreCompound::F2Medium,
+    VisualTyreCompound::F2Hard,
+    VisualTyreCompound::F2Wet,
+];
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct SnapshotFile {
+    session_uid: Option<u64>,
+    events: Vec<EventRecord>,
+    cars: Vec<CarRecord>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct EventRecord {
+    game_version: Option<(u8, u8)>,
+    session_time: Duration,
+    frame_identifier: u32,
+    player_car_index: VehicleIndex,
+    event: EventKind,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+enum EventKind {
+    ChequeredFlag,
+    DrsDisabled,
+    DrsEnabled,
+    FastestLap {
+        vehicle_index: VehicleIndex,
+        time: Duration,
+    },
+    RaceWinner {
+        vehicle_index: VehicleIndex,
+    },
+    Retirement {
+        vehicle_index: VehicleIndex,
+    },
+    SessionEnded,
+    SessionStarted,
+    TeammatesInPits {
+        vehicle_index: VehicleIndex,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct CarRecord {
+    laps: Vec<LapFile>,
+    stints: Vec<StintFile>,
+    pit_stops: u8,
+    lap_valid: bool,
+    lap_pit_stop: bool,
+    tyre_wear: Option<f32>,
+    trace: Vec<(f32, Duration)>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct LapFile {
+    lap_number: u8,
+    lap_time: Duration,
+    sector1_time: Duration,
+    sector2_time: Duration,
+    sector3_time: Duration,
+    is_valid: bool,
+    position: u8,
+    tyre_compound: Option<String>,
+    pit_stop: bool,
+    completed_at: Duration,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct StintFile {
+    compound: String,
+    start_lap: u8,
+    end_lap: Option<u8>,
+}
+
+impl From<&SessionTracker> for SnapshotFile {
+    fn from(tracker: &SessionTracker) -> Self {
+        SnapshotFile {
+            session_uid: tracker.session_uid,
+            events: tracker.events.iter().map(EventRecord::from).collect(),
+            cars: tracker.cars.iter().map(CarRecord::from).collect(),
+        }
+    }
+}
+
+impl From<&EventPacket> for EventRecord {
+    fn from(packet: &EventPacket) -> Self {
+        let header = packet.header();
+
+        let event = match packet.event() {
+            Event::ChequeredFlag => EventKind::ChequeredFlag,
+            Event::DrsDisabled => EventKind::DrsDisabled,
+            Event::DrsEnabled => EventKind::DrsEnabled,
+            Event::FastestLap(lap) => EventKind::FastestLap {
+                vehicle_index: lap.vehicle_index(),
+                time: *lap.time(),
+            },
+            Event::RaceWinner(winner) => EventKind::RaceWinner {
+                vehicle_index: winner.vehicle_index(),
+            },
+            Event::Retirement(retirement) => EventKind::Retirement {
+                vehicle_index: retirement.vehicle_index(),
+            },
+            Event::SessionEnded => EventKind::SessionEnded,
+            Event::SessionStarted => EventKind::SessionStarted,
+            Event::TeammatesInPits(teammate) => EventKind::TeammatesInPits {
+                vehicle_index: teammate.vehicle_index(),
+            },
+        };
+
+        EventRecord {
+            game_version: header
+                .game_version()
+                .map(|version| (version.major(), version.minor())),
+            session_time: *header.session_time(),
+            frame_identifier: header.frame_identifier(),
+            player_car_index: header.player_car_index(),
+            event,
+        }
+    }
+}
+
+impl From<&CarHistory> for CarRecord {
+    fn from(car: &CarHistory) -> Self {
+        CarRecord {
+            laps: car
+                .laps
+                .iter()
+                .map(|lap| LapFile {
+                    lap_number: lap.lap_number,
+                    lap_time: lap.lap_time,
+                    sector1_time: lap.sector1_time,
+                    sector2_time: lap.sector2_time,
+                    sector3_time: lap.sector3_time,
+                    is_valid: lap.is_valid,
+                    position: lap.position,
+                    tyre_compound: lap.tyre_compound.map(|compound| format!("{:?}", compound)),
+                    pit_stop: lap.pit_stop,
+                    completed_at: lap.completed_at,
+                })
+                .collect(),
+            stints: car
+                .stints
+                .iter()
+                .map(|stint| StintFile {
+                    compound: format!("{:?}", stint.compound),
+                    start_lap: stint.start_lap,
+                    end_lap: stint.end_lap,
+                })
+                .collect(),
+            pit_stops: car.pit_stops,
+            lap_valid: car.lap_valid,
+            lap_pit_stop: car.lap_pit_stop,
+            tyre_wear: car.tyre_wear,
+            trace: car.trace.clone(),
+        }
+    }
+}
+
+impl SnapshotFile {
+    fn into_tracker(self) -> Result<SessionTracker, Error> {
+        let session_uid = self.session_uid.unwrap_or_default();
+
+        let cars = self
+            .cars
+            .into_iter()
+            .map(CarRecord::into_history)
+            .collect::<Result<_, _>>()?;
+
+        Ok(SessionTracker {
+            session_uid: self.session_uid,
+            events: self
+                .events
+                .into_iter()
+                .map(|event| event.into_packet(session_uid))
+                .collect(),
+            cars,
+            ..Default::default()
+        })
+    }
+}
+
+impl EventRecord {
+    fn into_packet(self, session_uid: u64) -> EventPacket {
+        let event = match self.event {
+            EventKind::ChequeredFlag => Event::ChequeredFlag,
+            EventKind::DrsDisabled => Event::DrsDisabled,
+            EventKind::DrsEnabled => Event::DrsEnabled,
+            EventKind::FastestLap {
+                vehicle_index,
+                time,
+            } => Event::FastestLap(FastestLap::new(vehicle_index, time)),
+            EventKind::RaceWinner { vehicle_index } => {
+                Event::RaceWinner(RaceWinner::new(vehicle_index))
+            }
+            EventKind::Retirement { vehicle_index } => {
+                Event::Retirement(Retirement::new(vehicle_index))
+            }
+            EventKind::SessionEnded => Event::SessionEnded,
+            EventKind::SessionStarted => Event::SessionStarted,
+            EventKind::TeammatesInPits { vehicle_index } => {
+                Event::TeammatesInPits(TeammateInPits::new(vehicle_index))
+            }
+        };
+
+        let header = Header::new(
+            ApiSpec::Nineteen,
+            self.game_version
+                .map(|(major, minor)| GameVersion::new(major, minor)),
+            PacketType::Event,
+            session_uid,
+            self.session_time,
+            self.frame_identifier,
+            self.player_car_index,
+            None,
+        );
+
+        EventPacket::new(header, event)
+    }
+}
+
+impl CarRecord {
+    fn into_history(self) -> Result<CarHistory, Error> {
+        let laps = self
+            .laps
+            .into_iter()
+            .map(|lap| {
+                let tyre_compound = match lap.tyre_compound {
+                    Some(name) => Some(compound(&name)?),
+                    None => None,
+                };
+
+                Ok(LapRecord::new(
+                    lap.lap_number,
+                    lap.lap_time,
+                    lap.sector1_time,
+                    lap.sector2_time,
+                    lap.sector3_time,
+                    lap.is_valid,
+                    lap.position,
+                    tyre_compound,
+                    lap.pit_stop,
+                    lap.completed_at,
+                ))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let stints = self
+            .stints
+            .into_iter()
+            .map(|stint| {
+                Ok(Stint::new(
+                    compound(&stint.compound)?,
+                    stint.start_lap,
+                    stint.end_lap,
+                ))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(CarHistory {
+            laps,
+            stints,
+            pit_stops: self.pit_stops,
+            lap: None,
+            lap_valid: self.lap_valid,
+            lap_pit_stop: self.lap_pit_stop,
+            tyre_wear: self.tyre_wear,
+            trace: self.trace,
+        })
+    }
+}
+
+/// Look up a tyre compound by its name.
+fn compound(name: &str) -> Result<VisualTyreCompound, Error> {
+    COMPOUNDS
+        .iter()
+        .copied()
+        .find(|compound| format!("{:?}", compound) == name)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Unknown tyre compound {}", name),
+            )
+        })
+}
+
+/// Serialize the accumulated state of a tracker as JSON.
+pub fn to_json(tracker: &SessionTracker) -> Result<String, Error> {
+    serde_json::to_string(&SnapshotFile::from(tracker))
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+/// Restore a tracker from a snapshot in JSON.
+///
+/// An error is returned if the JSON is not a snapshot of a tracker.
+pub fn from_json(json: &str) -> Result<SessionTracker, Error> {
+    serde_json::from_str::<SnapshotFile>(json)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))?
+        .into_tracker()
+}
+
+/// Save a snapshot of a tracker to a file.
+///
+/// The snapshot is written to a temporary file next to the given path first, which then replaces
+/// the previous snapshot. A crash while saving leaves the previous snapshot intact.
+pub fn save<P: AsRef<Path>>(tracker: &SessionTracker, path: P) -> Result<(), Error> {
+    let path = path.as_ref();
+    let temporary = path.with_extension("tmp");
+
+    fs::write(&temporary, to_json(tracker)?)?;
+    fs::rename(&temporary, path)
+}
+
+/// Restore a tracker from a snapshot file.
+///
+/// `Ok(None)` is returned if the file does not exist, for example on the first start.
+pub fn restore<P: AsRef<Path>>(path: P) -> Result<Option<SessionTracker>, Error> {
+    match fs::read_to_string(path) {
+        Ok(json) => from_json(&json).map(Some),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::fixtures::{header, lap_packet};
+    use crate::packet::event::{Event, EventPacket, FastestLap};
+    use crate::packet::header::PacketType;
+    use crate::packet::lap::{DriverStatus, Lap, PitStatus, ResultStatus, Sector};
+    use crate::packet::Packet;
+    use crate::tracker::snapshot::{from_json, restore, save, to_json};
+    use crate::tracker::SessionTracker;
+
+    fn timed_lap(lap_number: u8, distance: f32) -> Lap {
+        Lap::new(
+            Duration::from_secs(90),
+            Duration::from_secs(10),
+            Duration::from_secs(90),
+            Duration::from_secs(30),
+            Duration::from_secs(30),
+            distance,
+            distance,
+            0.0,
+            1,
+            lap_number,
+            PitStatus::None,
+            Sector::First,
+            true,
+            0,
+            1,
+            DriverStatus::OnTrack,
+            ResultStatus::Active,
+        )
+    }
+
+    fn tracker() -> SessionTracker {
+        let mut tracker = SessionTracker::default();
+
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 7, Duration::default(), 0),
+            vec![timed_lap(1, 0.0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 7, Duration::from_secs(90), 0),
+            vec![timed_lap(2, 5000.0)],
+        ));
+        tracker.update(&Packet::Event(EventPacket::new(
+            header(PacketType::Event, 7, Duration::from_secs(90), 0),
+            Event::FastestLap(FastestLap::new(0, Duration::from_secs(90))),
+        )));
+
+        tracker
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let tracker = tracker();
+        let restored = from_json(&to_json(&tracker).unwrap()).unwrap();
+
+        assert_eq!(tracker.session_uid(), restored.session_uid());
+        assert_eq!(tracker.events(), restored.events());
+        assert_eq!(
+            tracker.car(0).unwrap().laps(),
+            restored.car(0).unwrap().laps()
+        );
+        assert_eq!(None, *restored.laps());
+
+        assert!(from_json("{}").is_err());
+    }
+
+    #[test]
+    fn restore_continues_session() {
+        let path = std::env::temp_dir().join("f1-api-snapshot-test.json");
+
+        assert!(restore(&path).unwrap().is_none());
+
+        save(&tracker(), &path).unwrap();
+        let mut tracker = restore(&path).unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 7, Duration::from_secs(170), 0),
+            vec![timed_lap(2, 9000.0)],
+        ));
+        tracker.update(&lap_packet(
+            header(PacketType::Lap, 7, Duration::from_secs(180), 0),
+            vec![timed_lap(3, 10000.0)],
+        ));
+
+        let laps = tracker.car(0).unwrap().laps();
+
+        assert_eq!(2, laps.len());
+        assert_eq!(2, laps[1].lap_number());
+    }
+}
@@ -1,5 +1,10 @@
 //! Collection of auxiliary types that are used throughout the crate
 
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::ops::Deref;
+
 use derive_new::new;
 use getset::CopyGetters;
 
@@ -9,9 +14,11 @@ use getset::CopyGetters;
 /// flag signals the race start or restart, while a yellow flag warns of hazards on track. The red
 /// flag aborts a race or session. The blue flag signals that a faster car is approaching from
 /// behind.
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum Flag {
     Invalid,
+    #[default]
     None,
     Green,
     Blue,
@@ -19,12 +26,6 @@ pub enum Flag {
     Red,
 }
 
-impl Default for Flag {
-    fn default() -> Self {
-        Flag::None
-    }
-}
-
 /// Reference to a vehicle in a packet
 ///
 /// In Formula 1, a maximum of 20 cars can participate in any session. The modern F1 games use this
@@ -33,6 +34,167 @@ impl Default for Flag {
 /// the indices, their usage can be checked by the Rust compiler.
 pub type VehicleIndex = u8;
 
+/// Data of every car in a session
+///
+/// The games publish the data of all cars in arrays with a fixed number of slots, which is 20 in
+/// F1 2019 and 22 in newer games. The slot of a car is its vehicle index, which the games assign
+/// sparsely, e.g. after retirements, to AI cars at high indices, or in multiplayer sessions. Slots
+/// without a car are filled with padding, which is indistinguishable from real data for most
+/// fields.
+///
+/// A `Grid` dereferences to a slice of all slots, so that every car can be addressed by its
+/// vehicle index, and keeps a mask of the slots that belong to active cars. `Grid::iter_active`
+/// and `Grid::car` only return the entries of active cars, independent of where their slots are.
+///
+/// Decoders mark each slot of the lap and participants packets as active or inactive based on its
+/// own data. The motion, setup, status, and telemetry packets consider all slots active until they
+/// are limited with `Packet::limit_cars` to the active participants, which the `active_cars`
+/// stream adapter does.
+///
+/// # Examples
+///
+/// ```
+/// use f1_api::types::Grid;
+///
+/// let grid = Grid::with_active(vec![1, 0, 3, 0, 5], |car| *car != 0);
+///
+/// assert_eq!(5, grid.len());
+/// assert_eq!(3, grid.active_count());
+/// assert_eq!(Some(&3), grid.car(2));
+/// assert_eq!(None, grid.car(1));
+/// assert_eq!(
+///     vec![(0, 1), (2, 3), (4, 5)],
+///     grid.iter_active()
+///         .map(|(index, car)| (index, *car))
+///         .collect::<Vec<(u8, u8)>>()
+/// );
+/// ```
+#[derive(Debug, PartialEq, Clone, Eq, Ord, PartialOrd, Hash)]
+pub struct Grid<T> {
+    slots: Vec<T>,
+    active: Vec<bool>,
+}
+
+impl<T> Grid<T> {
+    /// Create a grid in which every slot belongs to an active car.
+    pub fn new(slots: Vec<T>) -> Self {
+        let active = vec![true; slots.len()];
+        Grid { slots, active }
+    }
+
+    /// Create a grid in which the slots that match the predicate belong to active cars.
+    pub fn with_active<F: FnMut(&T) -> bool>(slots: Vec<T>, is_active: F) -> Self {
+        let active = slots.iter().map(is_active).collect();
+        Grid { slots, active }
+    }
+
+    /// Returns whether the slot with the given vehicle index belongs to an active car.
+    pub fn is_active(&self, vehicle_index: VehicleIndex) -> bool {
+        self.active
+            .get(vehicle_index as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Returns the mask of active cars, with one entry per slot.
+    pub fn active(&self) -> &[bool] {
+        &self.active
+    }
+
+    /// Returns the number of active cars.
+    pub fn active_count(&self) -> usize {
+        self.active.iter().filter(|active| **active).count()
+    }
+
+    /// Returns the entry of the car with the given vehicle index, if it is active.
+    pub fn car(&self, vehicle_index: VehicleIndex) -> Option<&T> {
+        if self.is_active(vehicle_index) {
+            self.slots.get(vehicle_index as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the entries of the active cars with their vehicle index.
+    pub fn iter_active(&self) -> impl Iterator<Item = (VehicleIndex, &T)> {
+        self.slots
+            .iter()
+            .zip(&self.active)
+            .enumerate()
+            .filter(|(_, (_, active))| **active)
+            .map(|(index, (car, _))| (index as VehicleIndex, car))
+    }
+
+    /// Limit the grid to the cars that are active in the given mask, e.g. from the participants
+    /// packet.
+    ///
+    /// Slots without an entry in the mask are considered inactive.
+    pub fn limit(mut self, active: &[bool]) -> Self {
+        for (index, slot) in self.active.iter_mut().enumerate() {
+            *slot &= active.get(index).copied().unwrap_or(false);
+        }
+
+        self
+    }
+
+    /// Apply a function to every slot, and return a grid with the same active cars.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            slots: self.slots.iter().map(f).collect(),
+            active: self.active.clone(),
+        }
+    }
+}
+
+impl<T> Default for Grid<T> {
+    fn default() -> Self {
+        Grid::new(Vec::new())
+    }
+}
+
+impl<T> Deref for Grid<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.slots
+    }
+}
+
+impl<T> From<Vec<T>> for Grid<T> {
+    fn from(slots: Vec<T>) -> Self {
+        Grid::new(slots)
+    }
+}
+
+impl<T> FromIterator<T> for Grid<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Grid::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Grid<T> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for Grid<T> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let slots = Vec::<T>::arbitrary(u)?;
+        let mut active = Vec::with_capacity(slots.len());
+
+        for _ in 0..slots.len() {
+            active.push(bool::arbitrary(u)?);
+        }
+
+        Ok(Grid { slots, active })
+    }
+}
+
 /// Property on each corner of a car
 ///
 /// The F1 games publish telemetry data and setup parameters that describe each corner of a car. For
@@ -47,6 +209,7 @@ pub type VehicleIndex = u8;
 /// let suspension_position = CornerProperty::new(1.0, 0.9, 1.1, 1.0);
 /// ```
 #[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CornerProperty<T>
 where
     T: Copy,
@@ -81,6 +244,7 @@ where
 /// let g_forces = Property3D::new(1.0, 1.3, 0.9);
 /// ```
 #[derive(new, Debug, CopyGetters, PartialEq, Copy, Clone, Eq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Property3D<T>
 where
     T: Copy,
@@ -0,0 +1,154 @@
+//! Publishing of packets to zenoh
+//!
+//! Robotics-style setups connect the machines around a simulator rig with zenoh, for example the
+//! driver's rig, a PC on the pit wall, and the controller of a motion platform. The `ZenohSink`
+//! publishes the messages of the `topic` module to such a data bus, with a key per session, packet
+//! type, and car, e.g. `f1/4242/telemetry/3`. Each machine subscribes to the keys it needs, e.g.
+//! `f1/*/motion/0` for the motion controller, or `f1/**` for everything.
+//!
+//! The payloads are JSON objects, and are published with the `application/json` encoding.
+
+use std::io::Error;
+
+use tokio_stream::{Stream, StreamExt};
+use zenoh::bytes::Encoding;
+use zenoh::{Config, Session};
+
+use crate::packet::Packet;
+use crate::sink::{ExportFuture, Exporter, Item};
+use crate::topic::Topics;
+
+/// Sink that publishes packets to zenoh
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{IpAddr, SocketAddr};
+///
+/// use f1_api::zenoh::ZenohSink;
+/// use f1_api::F1;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let game = SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 20777);
+///
+/// ZenohSink::open(zenoh::Config::default())
+///     .await
+///     .unwrap()
+///     .serve(F1::stream(game).unwrap())
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ZenohSink {
+    session: Session,
+    topics: Topics,
+}
+
+impl ZenohSink {
+    /// Open a zenoh session with the given configuration.
+    pub async fn open(config: Config) -> Result<Self, Error> {
+        let session = zenoh::open(config).await.map_err(Error::other)?;
+
+        Ok(ZenohSink::new(session))
+    }
+
+    /// Create a sink that publishes with an existing session.
+    pub fn new(session: Session) -> Self {
+        ZenohSink {
+            session,
+            topics: Topics::new("f1"),
+        }
+    }
+
+    /// Build the keys of the messages with the given topics instead of the prefix `f1`.
+    pub fn topics(mut self, topics: Topics) -> Self {
+        self.topics = topics;
+        self
+    }
+
+    /// Publish the messages of the packet, one per car or a single one for the session.
+    pub async fn send(&self, packet: &Packet) -> Result<(), Error> {
+        for message in self.topics.messages(packet) {
+            self.session
+                .put(message.key(), message.payload().clone())
+                .encoding(Encoding::APPLICATION_JSON)
+                .await
+                .map_err(Error::other)?;
+        }
+
+        Ok(())
+    }
+
+    /// Publish the packets of the stream until it ends, and close the session.
+    pub async fn serve<S>(self, packets: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Packet> + Unpin,
+    {
+        let mut packets = packets;
+
+        while let Some(packet) = packets.next().await {
+            self.send(&packet).await?;
+        }
+
+        self.session.close().await.map_err(Error::other)
+    }
+}
+
+/// Packets are published to the keys of their messages, while changes are ignored.
+impl Exporter for ZenohSink {
+    fn export<'a>(&'a mut self, item: &'a Item) -> ExportFuture<'a> {
+        Box::pin(async move {
+            match item {
+                Item::Packet(packet) => self.send(packet).await,
+                Item::Change(..) => Ok(()),
+            }
+        })
+    }
+
+    fn shutdown(&mut self) -> ExportFuture<'_> {
+        Box::pin(async move { self.session.close().await.map_err(Error::other) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use zenoh::Config;
+
+    use crate::fixtures;
+    use crate::packet::header::{ApiSpec, PacketType};
+    use crate::zenoh::ZenohSink;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn send_publishes_message_per_car() {
+        let mut config = Config::default();
+        config.insert_json5("listen/endpoints", "[]").unwrap();
+        config
+            .insert_json5("scouting/multicast/enabled", "false")
+            .unwrap();
+
+        let session = zenoh::open(config).await.unwrap();
+        let subscriber = session
+            .declare_subscriber("f1/*/telemetry/3")
+            .await
+            .unwrap();
+
+        let packet = &fixtures::packets(ApiSpec::Nineteen, PacketType::Telemetry).unwrap()[0];
+        ZenohSink::new(session.clone()).send(packet).await.unwrap();
+
+        let sample = tokio::time::timeout(Duration::from_secs(5), subscriber.recv_async())
+            .await
+            .unwrap()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&sample.payload().to_bytes()).unwrap();
+
+        assert_eq!(
+            format!("f1/{}/telemetry/3", packet.header().session_uid()),
+            sample.key_expr().as_str()
+        );
+        assert_eq!(3, json["vehicle_index"]);
+    }
+}